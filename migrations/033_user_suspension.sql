@@ -0,0 +1,4 @@
+-- Admin soft-delete: suspended users keep their data but are hidden and locked out
+ALTER TABLE users ADD COLUMN suspended_at TIMESTAMPTZ;
+
+CREATE INDEX idx_users_suspended ON users(suspended_at) WHERE suspended_at IS NOT NULL;
@@ -0,0 +1,11 @@
+-- User reports against spots, feeding the admin moderation queue
+CREATE TABLE spot_reports (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    spot_id             UUID NOT NULL REFERENCES spots(id) ON DELETE CASCADE,
+    reporter_callsign   TEXT NOT NULL,
+    reason              TEXT NOT NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (spot_id, reporter_callsign)
+);
+
+CREATE INDEX idx_spot_reports_spot_id ON spot_reports(spot_id);
@@ -0,0 +1,5 @@
+-- Resolved spot position (spotter grid center or reference centroid) for map viewport queries
+ALTER TABLE spots ADD COLUMN latitude DOUBLE PRECISION;
+ALTER TABLE spots ADD COLUMN longitude DOUBLE PRECISION;
+
+CREATE INDEX idx_spots_location ON spots(latitude, longitude) WHERE latitude IS NOT NULL;
@@ -0,0 +1,13 @@
+-- Permanent record of self-spots; survives TTL cleanup of the spots table
+CREATE TABLE self_spot_history (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id         UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    spot_id         UUID NOT NULL,
+    program_slug    TEXT NOT NULL,
+    reference       TEXT,
+    frequency_khz   DOUBLE PRECISION NOT NULL,
+    mode            TEXT NOT NULL,
+    spotted_at      TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_self_spot_history_user_program ON self_spot_history(user_id, program_slug);
@@ -0,0 +1,6 @@
+-- Runtime feature toggles; missing rows are seeded from config defaults at startup
+CREATE TABLE feature_flags (
+    name        TEXT PRIMARY KEY,
+    enabled     BOOLEAN NOT NULL,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,21 @@
+-- Nightly maintenance runs; run_hour is unique so a restart (or a second
+-- instance) can't run maintenance twice in the same hour
+CREATE TABLE maintenance_runs (
+    id           UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    run_hour     TIMESTAMPTZ NOT NULL UNIQUE,
+    started_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at  TIMESTAMPTZ,
+    pruned       JSONB NOT NULL DEFAULT '{}',
+    error        TEXT
+);
+
+-- Per-table row counts recorded by each maintenance run
+CREATE TABLE table_stats (
+    run_id       UUID NOT NULL REFERENCES maintenance_runs(id) ON DELETE CASCADE,
+    table_name   TEXT NOT NULL,
+    row_count    BIGINT NOT NULL,
+    recorded_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (run_id, table_name)
+);
+
+CREATE INDEX idx_table_stats_table ON table_stats(table_name, recorded_at DESC);
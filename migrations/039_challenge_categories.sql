@@ -0,0 +1,26 @@
+-- Admin-managed challenge categories; challenges.category must name one
+CREATE TABLE challenge_categories (
+    name        TEXT PRIMARY KEY,
+    description TEXT,
+    sort_order  INT NOT NULL DEFAULT 0,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Names match case-insensitively, so "CW" and "cw" can't both exist
+CREATE UNIQUE INDEX idx_challenge_categories_name_lower ON challenge_categories (lower(name));
+
+-- The categories previously allowed by challenges_category_check
+INSERT INTO challenge_categories (name, sort_order) VALUES
+    ('award', 1),
+    ('event', 2),
+    ('club', 3),
+    ('personal', 4),
+    ('other', 5);
+
+ALTER TABLE challenges DROP CONSTRAINT challenges_category_check;
+
+-- ON UPDATE CASCADE lets a category rename carry over to its challenges
+ALTER TABLE challenges
+    ADD CONSTRAINT challenges_category_fkey
+    FOREIGN KEY (category) REFERENCES challenge_categories(name) ON UPDATE CASCADE;
@@ -0,0 +1,10 @@
+-- IDs of deleted spots, so GET /v1/spots/delta can tell clients what to
+-- remove. Pruned after a day; older delta cursors must refetch the full list.
+CREATE TABLE spot_tombstones (
+    spot_id     UUID PRIMARY KEY,
+    deleted_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_spot_tombstones_deleted_at ON spot_tombstones(deleted_at);
+
+CREATE INDEX idx_spots_updated_at ON spots(updated_at);
@@ -0,0 +1,2 @@
+-- Spots reported by the WSPR aggregator (wsprnet.org).
+ALTER TYPE spot_source ADD VALUE IF NOT EXISTS 'wspr';
@@ -0,0 +1,22 @@
+-- Daily leaderboard rank samples, recorded by nightly maintenance. Rank
+-- movement for digests (and leaderboards) compares two samples.
+CREATE TABLE rank_history (
+    challenge_id    UUID NOT NULL REFERENCES challenges(id) ON DELETE CASCADE,
+    callsign        TEXT NOT NULL,
+    sampled_on      DATE NOT NULL,
+    rank            INT NOT NULL,
+    score           INT NOT NULL,
+    PRIMARY KEY (challenge_id, callsign, sampled_on)
+);
+
+CREATE INDEX idx_rank_history_callsign ON rank_history(callsign, sampled_on DESC);
+
+-- Digests materialized by POST /v1/admin/digests/precompute, one per user
+-- and ISO week (e.g. '2024-W32')
+CREATE TABLE weekly_digests (
+    user_id         UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    week            TEXT NOT NULL,
+    digest          JSONB NOT NULL,
+    computed_at     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, week)
+);
@@ -0,0 +1,23 @@
+-- Amateur band of each spot (e.g. '20m'), set on insert from the band table
+-- in models/spot.rs; NULL when the frequency is outside every band
+ALTER TABLE spots ADD COLUMN band TEXT;
+
+-- Backfill with the same ranges as models/spot.rs BANDS
+UPDATE spots SET band = CASE
+    WHEN frequency_khz BETWEEN 1800 AND 2000 THEN '160m'
+    WHEN frequency_khz BETWEEN 3500 AND 4000 THEN '80m'
+    WHEN frequency_khz BETWEEN 5250 AND 5450 THEN '60m'
+    WHEN frequency_khz BETWEEN 7000 AND 7300 THEN '40m'
+    WHEN frequency_khz BETWEEN 10100 AND 10150 THEN '30m'
+    WHEN frequency_khz BETWEEN 14000 AND 14350 THEN '20m'
+    WHEN frequency_khz BETWEEN 18068 AND 18168 THEN '17m'
+    WHEN frequency_khz BETWEEN 21000 AND 21450 THEN '15m'
+    WHEN frequency_khz BETWEEN 24890 AND 24990 THEN '12m'
+    WHEN frequency_khz BETWEEN 28000 AND 29700 THEN '10m'
+    WHEN frequency_khz BETWEEN 50000 AND 54000 THEN '6m'
+    WHEN frequency_khz BETWEEN 144000 AND 148000 THEN '2m'
+    WHEN frequency_khz BETWEEN 420000 AND 450000 THEN '70cm'
+END;
+
+-- Heatmap: spots per program in a spotted_at window
+CREATE INDEX idx_spots_program_spotted_at ON spots(program_slug, spotted_at);
@@ -0,0 +1,25 @@
+-- Reference catalog per program (parks, summits, ...) for reference search and
+-- spot location/name enrichment. Named program_references because REFERENCES
+-- is an SQL keyword.
+CREATE TABLE program_references (
+    program_slug    TEXT NOT NULL REFERENCES programs(slug) ON UPDATE CASCADE ON DELETE CASCADE,
+    reference       TEXT NOT NULL,                  -- "US-0189", "W6/SS-001"
+    name            TEXT NOT NULL,
+    grid            TEXT,
+    latitude        DOUBLE PRECISION,
+    longitude       DOUBLE PRECISION,
+    active          BOOLEAN NOT NULL DEFAULT true,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (program_slug, reference)
+);
+
+-- Name search (pg_trgm is created in 027)
+CREATE INDEX idx_program_references_name_trgm
+    ON program_references USING gin (name gin_trgm_ops);
+
+-- Seed from the POTA park catalog; the POTA stats aggregator keeps it in sync
+INSERT INTO program_references (program_slug, reference, name, grid, latitude, longitude, active)
+SELECT 'pota', reference, name, grid, latitude, longitude, active
+FROM pota_parks
+WHERE EXISTS (SELECT 1 FROM programs WHERE slug = 'pota');
@@ -0,0 +1,8 @@
+-- Optional note shown on a friend invite's landing page and to the accepter
+ALTER TABLE friend_invites
+    ADD COLUMN note TEXT CHECK (char_length(note) <= 140);
+
+-- Tokens are now URL-safe base64, which adds '-' and '_'
+ALTER TABLE friend_invites DROP CONSTRAINT friend_invite_token_format;
+ALTER TABLE friend_invites
+    ADD CONSTRAINT friend_invite_token_format CHECK (token ~ '^inv_[A-Za-z0-9_-]{20,}$');
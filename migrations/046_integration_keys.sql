@@ -0,0 +1,13 @@
+-- API keys for trusted partners pushing spots to POST /v1/integrations/spots
+CREATE TABLE integration_keys (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    partner         TEXT NOT NULL,
+    key             TEXT NOT NULL UNIQUE CHECK (key ~ '^ik_[A-Za-z0-9]{32}$'),
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at    TIMESTAMPTZ,
+    revoked_at      TIMESTAMPTZ
+);
+
+-- The partner key a spot was pushed with
+ALTER TABLE spots
+    ADD COLUMN integration_key_id UUID REFERENCES integration_keys(id) ON DELETE SET NULL;
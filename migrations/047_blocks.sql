@@ -0,0 +1,11 @@
+-- Users who blocked each other: no friendship, friend requests, or visibility
+CREATE TABLE blocks (
+    blocker_id  UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    blocked_id  UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (blocker_id, blocked_id),
+    CHECK (blocker_id <> blocked_id)
+);
+
+-- Lookups of who blocked a given user
+CREATE INDEX idx_blocks_blocked ON blocks(blocked_id);
@@ -0,0 +1,14 @@
+-- Individual submissions that moved a participant's progress, e.g. values
+-- typed into a program's data-entry field
+CREATE TABLE progress_entries (
+    id            UUID PRIMARY KEY,
+    challenge_id  UUID NOT NULL REFERENCES challenges(id) ON DELETE CASCADE,
+    callsign      TEXT NOT NULL,
+    source        TEXT NOT NULL,
+    goal_id       TEXT,
+    details       JSONB NOT NULL DEFAULT '{}',
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_progress_entries_participant
+    ON progress_entries(challenge_id, callsign, created_at DESC);
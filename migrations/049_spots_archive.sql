@@ -0,0 +1,18 @@
+-- Spots copied here by TTL cleanup just before they are deleted (when
+-- ARCHIVE_SPOTS is on), for historical counts; pruned by nightly maintenance
+-- after SPOTS_ARCHIVE_RETENTION_MONTHS
+CREATE TABLE spots_archive (
+    id              UUID PRIMARY KEY,
+    source          spot_source NOT NULL,
+    program_slug    TEXT,
+    callsign        TEXT NOT NULL,
+    reference       TEXT,
+    frequency_khz   DOUBLE PRECISION NOT NULL,
+    band            TEXT,
+    mode            TEXT NOT NULL,
+    spotter         TEXT,
+    spotted_at      TIMESTAMPTZ NOT NULL,
+    archived_at     TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_spots_archive_spotted_at ON spots_archive(spotted_at);
@@ -0,0 +1,7 @@
+-- How a submitted spot reached the server, finer-grained than source: the
+-- app, a bulk upload, or a partner integration. NULL for aggregated spots.
+ALTER TABLE spots ADD COLUMN ingest_channel TEXT
+    CHECK (ingest_channel IN ('app', 'bulk', 'integration'));
+
+UPDATE spots SET ingest_channel = 'app' WHERE source = 'self';
+UPDATE spots SET ingest_channel = 'integration' WHERE integration_key_id IS NOT NULL;
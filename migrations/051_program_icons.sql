@@ -0,0 +1,9 @@
+-- Program icons uploaded by admins and served from GET /v1/programs/:slug/icon
+CREATE TABLE program_icons (
+    program_slug  TEXT PRIMARY KEY REFERENCES programs(slug) ON DELETE CASCADE,
+    content_type  TEXT NOT NULL,
+    image_data    BYTEA NOT NULL,
+    -- md5 of image_data, served as a strong ETag
+    etag          TEXT NOT NULL,
+    updated_at    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
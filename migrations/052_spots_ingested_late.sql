@@ -0,0 +1,4 @@
+-- Spots stored by an admin backfill after the fact rather than by a live poll,
+-- so freshness and lag figures can leave them out. Carried into the archive.
+ALTER TABLE spots ADD COLUMN ingested_late BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE spots_archive ADD COLUMN ingested_late BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,3 @@
+-- Spot comments carried into the archive; nightly maintenance shortens those
+-- older than SPOTS_ARCHIVE_COMMENT_RETENTION_DAYS when it is set.
+ALTER TABLE spots_archive ADD COLUMN comments TEXT;
@@ -0,0 +1,3 @@
+-- Division each participant entered, for challenges whose configuration
+-- lists `divisions`; NULL for challenges without them.
+ALTER TABLE challenge_participants ADD COLUMN division TEXT;
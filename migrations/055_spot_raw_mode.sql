@@ -0,0 +1,63 @@
+-- Aggregated spot modes are normalized to a canonical set by
+-- aggregators/mode.rs; raw_mode keeps the mode as the upstream sent it
+ALTER TABLE spots ADD COLUMN raw_mode TEXT;
+ALTER TABLE spots_archive ADD COLUMN raw_mode TEXT;
+
+-- Same mapping as aggregators/mode.rs normalize_mode()
+CREATE FUNCTION pg_temp.normalize_spot_mode(raw TEXT) RETURNS TEXT AS $$
+    SELECT CASE upper(regexp_replace(coalesce(raw, ''), '[-_ \t]', '', 'g'))
+        WHEN '' THEN 'UNKNOWN'
+        WHEN 'UNKNOWN' THEN 'UNKNOWN'
+        WHEN 'CW' THEN 'CW'
+        WHEN 'A1A' THEN 'CW'
+        WHEN 'SSB' THEN 'SSB'
+        WHEN 'USB' THEN 'SSB'
+        WHEN 'LSB' THEN 'SSB'
+        WHEN 'PHONE' THEN 'SSB'
+        WHEN 'PH' THEN 'SSB'
+        WHEN 'VOICE' THEN 'SSB'
+        WHEN 'AM' THEN 'AM'
+        WHEN 'FM' THEN 'FM'
+        WHEN 'NFM' THEN 'FM'
+        WHEN 'WFM' THEN 'FM'
+        WHEN 'DV' THEN 'DV'
+        WHEN 'DSTAR' THEN 'DV'
+        WHEN 'DMR' THEN 'DV'
+        WHEN 'C4FM' THEN 'DV'
+        WHEN 'FUSION' THEN 'DV'
+        WHEN 'YSF' THEN 'DV'
+        WHEN 'FREEDV' THEN 'DV'
+        WHEN 'M17' THEN 'DV'
+        WHEN 'FT8' THEN 'FT8'
+        WHEN 'FT4' THEN 'FT4'
+        WHEN 'JS8' THEN 'JS8'
+        WHEN 'JS8CALL' THEN 'JS8'
+        WHEN 'JT65' THEN 'JT65'
+        WHEN 'JT9' THEN 'JT9'
+        WHEN 'Q65' THEN 'Q65'
+        WHEN 'MSK144' THEN 'MSK144'
+        WHEN 'PSK' THEN 'PSK31'
+        WHEN 'PSK31' THEN 'PSK31'
+        WHEN 'BPSK' THEN 'PSK31'
+        WHEN 'BPSK31' THEN 'PSK31'
+        WHEN 'RTTY' THEN 'RTTY'
+        WHEN 'RY' THEN 'RTTY'
+        WHEN 'OLIVIA' THEN 'OLIVIA'
+        WHEN 'SSTV' THEN 'SSTV'
+        WHEN 'WSPR' THEN 'WSPR'
+        WHEN 'DATA' THEN 'DATA'
+        WHEN 'DIGI' THEN 'DATA'
+        WHEN 'DIGITAL' THEN 'DATA'
+        WHEN 'DIG' THEN 'DATA'
+        ELSE 'OTHER'
+    END
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- Backfill the POTA and SOTA spots stored before normalization
+UPDATE spots
+SET raw_mode = mode, mode = pg_temp.normalize_spot_mode(mode)
+WHERE source IN ('pota', 'sota');
+
+UPDATE spots_archive
+SET raw_mode = mode, mode = pg_temp.normalize_spot_mode(mode)
+WHERE source IN ('pota', 'sota');
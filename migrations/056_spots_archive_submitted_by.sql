@@ -0,0 +1,3 @@
+-- Who submitted an archived self-spot, so archive reads can apply the same
+-- suspended-user and excludeSelf filters as the live table
+ALTER TABLE spots_archive ADD COLUMN submitted_by UUID;
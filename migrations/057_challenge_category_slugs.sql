@@ -0,0 +1,17 @@
+-- Category names are slugs (lowercase letters and digits joined by single
+-- hyphens). Convert existing names where the slug isn't shared with another
+-- category; ON UPDATE CASCADE carries the rename to challenges.
+WITH converted AS (
+    SELECT name, trim(BOTH '-' FROM regexp_replace(lower(name), '[^a-z0-9]+', '-', 'g')) AS slug
+    FROM challenge_categories
+)
+UPDATE challenge_categories c
+SET name = converted.slug, updated_at = now()
+FROM converted
+WHERE c.name = converted.name
+  AND converted.slug <> ''
+  AND converted.slug <> converted.name
+  AND NOT EXISTS (
+      SELECT 1 FROM converted other
+      WHERE other.slug = converted.slug AND other.name <> converted.name
+  );
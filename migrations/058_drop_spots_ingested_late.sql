@@ -0,0 +1,4 @@
+-- Nothing reads the backfill flag: backfilled spots are stored already
+-- expired, so they never reach the live list or freshness figures.
+ALTER TABLE spots DROP COLUMN ingested_late;
+ALTER TABLE spots_archive DROP COLUMN ingested_late;
@@ -0,0 +1,13 @@
+-- Final standings of an ended challenge, written once on the first
+-- certificates request and read from then on, so ranks and certificate
+-- serials stay the same however late progress is reported.
+CREATE TABLE challenge_results (
+    challenge_id  UUID NOT NULL REFERENCES challenges(id) ON DELETE CASCADE,
+    callsign      TEXT NOT NULL,
+    score         INT NOT NULL,
+    rank          BIGINT NOT NULL,
+    completed     BOOLEAN NOT NULL,
+    last_reported_at TIMESTAMPTZ NOT NULL,
+    finalized_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (challenge_id, callsign)
+);
@@ -0,0 +1,115 @@
+//! Callsign allowlist/blocklist applied to aggregated spots before they are
+//! upserted, e.g. to aggregate only a club's members.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+/// Which aggregated spots are kept, by activator callsign. Blocked calls are
+/// always dropped; when an allowlist is set, only listed calls are kept.
+///
+/// Matching is case-insensitive, and a listed call also matches its portable
+/// and prefixed forms (`W1AW` matches `W1AW/P` and `VE/W1AW`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CallsignFilter {
+    allowlist: Option<BTreeSet<String>>,
+    blocklist: BTreeSet<String>,
+}
+
+impl CallsignFilter {
+    pub fn new(allowlist: Option<Vec<String>>, blocklist: Vec<String>) -> Self {
+        let normalize = |calls: Vec<String>| -> BTreeSet<String> {
+            calls
+                .into_iter()
+                .map(|call| call.trim().to_ascii_uppercase())
+                .filter(|call| !call.is_empty())
+                .collect()
+        };
+        Self {
+            allowlist: allowlist.map(normalize),
+            blocklist: normalize(blocklist),
+        }
+    }
+
+    /// Split a list of callsigns separated by commas, whitespace, or
+    /// newlines. Text after `#` on a line is a comment.
+    pub fn parse_list(raw: &str) -> Vec<String> {
+        raw.lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+            .filter(|call| !call.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Whether a spot for `callsign` should be stored.
+    pub fn allows(&self, callsign: &str) -> bool {
+        let callsign = callsign.trim().to_ascii_uppercase();
+        let listed = |list: &BTreeSet<String>| {
+            list.contains(&callsign) || callsign.split('/').any(|part| list.contains(part))
+        };
+        if listed(&self.blocklist) {
+            return false;
+        }
+        self.allowlist.as_ref().is_none_or(listed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn empty_filter_allows_everything() {
+        let filter = CallsignFilter::default();
+        assert!(filter.allows("W1AW"));
+        assert!(filter.allows("k1abc/p"));
+    }
+
+    #[test]
+    fn allowlist_only_keeps_listed_calls() {
+        let filter = CallsignFilter::new(Some(calls(&["w1aw", " K1ABC "])), vec![]);
+        assert!(filter.allows("W1AW"));
+        assert!(filter.allows("k1abc"));
+        assert!(filter.allows("W1AW/P"));
+        assert!(filter.allows("VE/W1AW"));
+        assert!(!filter.allows("N0CALL"));
+        assert!(!filter.allows("W1AWX"));
+    }
+
+    #[test]
+    fn empty_allowlist_keeps_nothing() {
+        let filter = CallsignFilter::new(Some(vec![]), vec![]);
+        assert!(!filter.allows("W1AW"));
+    }
+
+    #[test]
+    fn blocklist_only_drops_listed_calls() {
+        let filter = CallsignFilter::new(None, calls(&["N0CALL"]));
+        assert!(!filter.allows("n0call"));
+        assert!(!filter.allows("N0CALL/M"));
+        assert!(filter.allows("W1AW"));
+    }
+
+    #[test]
+    fn blocklist_wins_over_allowlist() {
+        let filter = CallsignFilter::new(Some(calls(&["W1AW", "K1ABC"])), calls(&["K1ABC"]));
+        assert!(filter.allows("W1AW"));
+        assert!(!filter.allows("K1ABC"));
+        assert!(!filter.allows("N0CALL"));
+    }
+
+    #[test]
+    fn parse_list_accepts_commas_lines_and_comments() {
+        let raw = "W1AW, K1ABC\n# club officers\nN0CALL  # treasurer\n\nVE3XYZ";
+        assert_eq!(
+            CallsignFilter::parse_list(raw),
+            ["W1AW", "K1ABC", "N0CALL", "VE3XYZ"]
+        );
+        assert!(CallsignFilter::parse_list(" , \n# nothing").is_empty());
+    }
+}
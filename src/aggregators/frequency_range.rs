@@ -0,0 +1,120 @@
+//! Sanity range for aggregated spot frequencies, so a spot at 0 kHz or an
+//! absurd frequency from an upstream glitch never reaches the spot list.
+
+use serde::Serialize;
+
+use crate::models::spot::AggregatedSpot;
+
+/// Bottom of the 2200 m amateur band.
+pub const DEFAULT_MIN_KHZ: f64 = 135.7;
+/// Top of the 70 cm amateur band.
+pub const DEFAULT_MAX_KHZ: f64 = 450_000.0;
+
+/// The frequencies, in kHz, aggregated spots must fall within (inclusive).
+/// Defaults to the amateur allocations from 2200 m to 70 cm.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrequencyRange {
+    min_khz: f64,
+    max_khz: f64,
+}
+
+impl Default for FrequencyRange {
+    fn default() -> Self {
+        Self {
+            min_khz: DEFAULT_MIN_KHZ,
+            max_khz: DEFAULT_MAX_KHZ,
+        }
+    }
+}
+
+impl FrequencyRange {
+    pub fn new(min_khz: f64, max_khz: f64) -> Result<Self, String> {
+        if !(min_khz >= 0.0 && min_khz < max_khz) {
+            return Err(format!(
+                "minimum {min_khz} kHz must be at least 0 and below the maximum {max_khz} kHz"
+            ));
+        }
+        Ok(Self { min_khz, max_khz })
+    }
+
+    pub fn contains(&self, frequency_khz: f64) -> bool {
+        (self.min_khz..=self.max_khz).contains(&frequency_khz)
+    }
+
+    /// Drop spots outside the range, logging each, and return how many were
+    /// dropped. `source` names the aggregator in the log.
+    pub fn retain(&self, spots: &mut Vec<AggregatedSpot>, source: &str) -> usize {
+        let before = spots.len();
+        spots.retain(|spot| {
+            let keep = self.contains(spot.frequency_khz);
+            if !keep {
+                tracing::debug!(
+                    "{}: dropped spot {} of {} at {} kHz, outside {}-{} kHz",
+                    source,
+                    spot.external_id,
+                    spot.callsign,
+                    spot.frequency_khz,
+                    self.min_khz,
+                    self.max_khz
+                );
+            }
+            keep
+        });
+        before - spots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::spot::SpotSource;
+
+    fn spot_at(external_id: &str, frequency_khz: f64) -> AggregatedSpot {
+        let now = chrono::Utc::now();
+        AggregatedSpot {
+            callsign: "W1AW".to_string(),
+            program_slug: None,
+            source: SpotSource::Pota,
+            external_id: external_id.to_string(),
+            frequency_khz,
+            mode: "CW".to_string(),
+            raw_mode: None,
+            reference: None,
+            reference_name: None,
+            spotter: None,
+            spotter_grid: None,
+            location_desc: None,
+            country_code: None,
+            state_abbr: None,
+            comments: None,
+            snr: None,
+            wpm: None,
+            spotted_at: now,
+            expires_at: now,
+        }
+    }
+
+    #[test]
+    fn out_of_range_spots_are_dropped() {
+        let mut spots = vec![
+            spot_at("zero", 0.0),
+            spot_at("valid", 14062.0),
+            spot_at("absurd", 999_999.0),
+        ];
+        assert_eq!(FrequencyRange::default().retain(&mut spots, "POTA"), 2);
+        let kept: Vec<_> = spots.iter().map(|spot| spot.external_id.as_str()).collect();
+        assert_eq!(kept, ["valid"]);
+    }
+
+    #[test]
+    fn range_bounds_are_inclusive_and_validated() {
+        let range = FrequencyRange::new(1800.0, 54_000.0).unwrap();
+        assert!(range.contains(1800.0) && range.contains(54_000.0));
+        assert!(!range.contains(146_520.0));
+
+        assert!(FrequencyRange::new(14_000.0, 7_000.0).is_err());
+        assert!(FrequencyRange::new(-1.0, 7_000.0).is_err());
+        assert!(FrequencyRange::new(f64::NAN, 7_000.0).is_err());
+    }
+}
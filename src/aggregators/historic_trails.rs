@@ -6,6 +6,7 @@ use tokio::sync::Semaphore;
 use crate::db::historic_trails::{self, UnfetchedTrail};
 use crate::metrics as app_metrics;
 use crate::models::historic_trail::{NpsTrailFeature, NpsTrailResponse};
+use crate::server::ServerState;
 
 use super::park_boundaries::merge_geojson_geometries;
 
@@ -45,7 +46,12 @@ enum FetchResult {
 }
 
 /// Main poll loop — fetches geometries for unmatched trails, then re-checks stale ones.
-pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: HistoricTrailsConfig) {
+pub async fn poll_loop(
+    pool: PgPool,
+    client: reqwest::Client,
+    config: HistoricTrailsConfig,
+    state: ServerState,
+) {
     // Wait for migrations and initial setup
     tracing::info!("Historic trails: waiting 60s before first cycle");
     tokio::time::sleep(std::time::Duration::from_secs(60)).await;
@@ -53,6 +59,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: HistoricTr
     let semaphore = Arc::new(Semaphore::new(config.concurrency));
 
     loop {
+        let cycle = state.begin_cycle().await;
         let batch_start = std::time::Instant::now();
         let total_cached = historic_trails::count_trails(&pool).await.unwrap_or(0);
 
@@ -141,6 +148,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: HistoricTr
             "Historic trails: sleeping {}h until next cycle",
             config.cycle_hours
         );
+        drop(cycle);
         tokio::time::sleep(std::time::Duration::from_secs(config.cycle_hours * 3600)).await;
     }
 }
@@ -1,55 +1,341 @@
+pub mod callsign_filter;
+pub mod frequency_range;
 pub mod historic_trails;
+pub mod mode;
 pub mod park_boundaries;
 pub mod polish_park_boundaries;
 pub mod pota;
 pub mod pota_stats;
 pub mod sota;
 pub mod state_park_sources;
+pub mod wspr;
 
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use sqlx::PgPool;
 
 use crate::config::Config;
+use crate::feature_flags::FeatureFlags;
+use crate::http_client::{HttpClient, HttpError};
 use crate::metrics as app_metrics;
+use crate::models::spot::AggregatedSpot;
+use crate::server::ServerState;
+
+/// Spawn the spot aggregators and the spot TTL cleanup task, returning how
+/// starting each enabled aggregator went. An aggregator that fails to start
+/// doesn't stop the others. Nothing is spawned when the spots system is
+/// disabled.
+pub fn spawn_aggregators(
+    pool: PgPool,
+    config: &Config,
+    flags: FeatureFlags,
+    state: ServerState,
+) -> Vec<AggregatorStartReport> {
+    if !config.spots_enabled {
+        tracing::info!("Spots system disabled; skipping spot aggregators and TTL cleanup");
+        return Vec::new();
+    }
+    tracing::info!("Spots system enabled");
 
-/// Spawn all aggregator background tasks and the TTL cleanup task.
-pub fn spawn_aggregators(pool: PgPool, config: &Config) {
-    // TTL cleanup always runs
     let cleanup_pool = pool.clone();
+    let cleanup_state = state.clone();
+    let archive_spots = config.archive_spots;
     tokio::spawn(async move {
-        ttl_cleanup_loop(cleanup_pool).await;
+        ttl_cleanup_loop(cleanup_pool, archive_spots, cleanup_state).await;
     });
 
-    // Shared HTTP client for all aggregators
-    let client = reqwest::Client::builder()
-        .user_agent(format!(
-            "{}/{}",
-            env!("CARGO_PKG_NAME"),
-            env!("CARGO_PKG_VERSION")
-        ))
-        .build()
-        .expect("failed to build HTTP client");
+    SpotAggregator::ALL
+        .into_iter()
+        .filter(|aggregator| aggregator.is_enabled(config))
+        .map(|aggregator| {
+            start_reported(aggregator.key(), || {
+                aggregator.start(&pool, config, &flags, &state)
+            })
+        })
+        .collect()
+}
 
-    if config.pota_aggregator_enabled {
-        let pota_pool = pool.clone();
-        let pota_client = client.clone();
-        tokio::spawn(async move {
-            pota::poll_loop(pota_pool, pota_client).await;
-        });
-        tracing::info!("POTA aggregator started");
+/// Whether a spot aggregator's poll loop is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartStatus {
+    Started,
+    Failed,
+}
+
+/// How starting one spot aggregator went, logged at startup and listed in
+/// `GET /v1/status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregatorStartReport {
+    pub name: &'static str,
+    pub status: StartStatus,
+    /// Why the start failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl AggregatorStartReport {
+    pub fn is_failed(&self) -> bool {
+        self.status == StartStatus::Failed
     }
 
-    if config.sota_aggregator_enabled {
-        let sota_pool = pool.clone();
-        let sota_client = client.clone();
-        tokio::spawn(async move {
-            sota::poll_loop(sota_pool, sota_client).await;
-        });
-        tracing::info!("SOTA aggregator started");
+    pub fn log(&self) {
+        match &self.reason {
+            None => tracing::info!(aggregator = self.name, "Spot aggregator started"),
+            Some(reason) => tracing::error!(
+                aggregator = self.name,
+                "Spot aggregator failed to start: {}",
+                reason
+            ),
+        }
+    }
+}
+
+/// Run `start` for the aggregator `name`, turning an error or a panic into a
+/// failed report instead of taking down the caller.
+pub fn start_reported(
+    name: &'static str,
+    start: impl FnOnce() -> Result<(), String>,
+) -> AggregatorStartReport {
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(start))
+        .unwrap_or_else(|panic| Err(panic_message(panic.as_ref())));
+    match result {
+        Ok(()) => AggregatorStartReport {
+            name,
+            status: StartStatus::Started,
+            reason: None,
+        },
+        Err(reason) => AggregatorStartReport {
+            name,
+            status: StartStatus::Failed,
+            reason: Some(reason),
+        },
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("panicked: {message}")
+}
+
+/// A spot aggregator that admins can poll on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpotAggregator {
+    Pota,
+    Sota,
+    Wspr,
+}
+
+impl SpotAggregator {
+    pub const ALL: [Self; 3] = [Self::Pota, Self::Sota, Self::Wspr];
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "pota" => Some(Self::Pota),
+            "sota" => Some(Self::Sota),
+            "wspr" => Some(Self::Wspr),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Pota => "pota",
+            Self::Sota => "sota",
+            Self::Wspr => "wspr",
+        }
+    }
+
+    pub fn is_enabled(self, config: &Config) -> bool {
+        match self {
+            Self::Pota => config.pota_aggregator_enabled,
+            Self::Sota => config.sota_aggregator_enabled,
+            Self::Wspr => config.wspr_aggregator_enabled,
+        }
+    }
+
+    /// Spawn this aggregator's poll loop. Fails without spawning anything when
+    /// its prerequisites aren't met.
+    pub fn start(
+        self,
+        pool: &PgPool,
+        config: &Config,
+        flags: &FeatureFlags,
+        state: &ServerState,
+    ) -> Result<(), String> {
+        let client =
+            HttpClient::try_new().map_err(|e| format!("failed to build HTTP client: {e}"))?;
+        let (pool, flags, state) = (pool.clone(), flags.clone(), state.clone());
+        let comment_max_len = config.spot_comment_max_len;
+        let respot_ttl = config.spot_respot_ttl.clone();
+        let callsign_filter = config.aggregator_callsign_filter.clone();
+        let frequency_range = config.spot_frequency_range;
+        match self {
+            Self::Pota => {
+                if config.pota_spot_urls.is_empty() {
+                    return Err("no POTA spot URLs configured".to_string());
+                }
+                tokio::spawn(pota::poll_loop(
+                    pool,
+                    client,
+                    config.pota_spot_urls.clone(),
+                    config.pota_program_slug.clone(),
+                    comment_max_len,
+                    config.pota_max_spots_per_cycle,
+                    respot_ttl,
+                    callsign_filter,
+                    frequency_range,
+                    flags,
+                    state,
+                ));
+            }
+            Self::Sota => {
+                tokio::spawn(sota::poll_loop(
+                    pool,
+                    client,
+                    config.sota_program_slug.clone(),
+                    comment_max_len,
+                    config.sota_max_spots_per_cycle,
+                    respot_ttl,
+                    callsign_filter,
+                    frequency_range,
+                    flags,
+                    state,
+                ));
+            }
+            Self::Wspr => {
+                tokio::spawn(wspr::poll_loop(
+                    pool,
+                    client,
+                    comment_max_len,
+                    config.wspr_max_spots_per_cycle,
+                    respot_ttl,
+                    callsign_filter,
+                    frequency_range,
+                    flags,
+                    state,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one poll cycle now, outside the regular schedule. Returns how many
+    /// spots were upserted.
+    pub async fn poll_once(
+        self,
+        pool: &PgPool,
+        client: &HttpClient,
+        config: &Config,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        let respot_ttl = &config.spot_respot_ttl;
+        match self {
+            Self::Pota => {
+                pota::fetch_and_upsert(
+                    pool,
+                    client,
+                    &config.pota_spot_urls,
+                    &config.pota_program_slug,
+                    config.spot_comment_max_len,
+                    config.pota_max_spots_per_cycle,
+                    respot_ttl,
+                    &config.aggregator_callsign_filter,
+                    config.spot_frequency_range,
+                )
+                .await
+            }
+            Self::Sota => {
+                sota::fetch_and_upsert(
+                    pool,
+                    client,
+                    &config.sota_program_slug,
+                    config.spot_comment_max_len,
+                    config.sota_max_spots_per_cycle,
+                    respot_ttl,
+                    &config.aggregator_callsign_filter,
+                    config.spot_frequency_range,
+                )
+                .await
+            }
+            Self::Wspr => {
+                wspr::fetch_and_upsert(
+                    pool,
+                    client,
+                    config.spot_comment_max_len,
+                    config.wspr_max_spots_per_cycle,
+                    respot_ttl,
+                    &config.aggregator_callsign_filter,
+                    config.spot_frequency_range,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Fetch the upstream response once without storing anything, counting
+    /// how many entries would map to spots. POTA fetches only its primary
+    /// endpoint.
+    pub async fn fetch_raw(
+        self,
+        client: &HttpClient,
+        config: &Config,
+    ) -> Result<RawFetch, HttpError> {
+        let comment_max_len = config.spot_comment_max_len;
+        match self {
+            Self::Pota => {
+                let url = &config.pota_spot_urls[0];
+                pota::fetch_raw(client, url, &config.pota_program_slug, comment_max_len).await
+            }
+            Self::Sota => sota::fetch_raw(client, &config.sota_program_slug, comment_max_len).await,
+            Self::Wspr => wspr::fetch_raw(client, comment_max_len).await,
+        }
+    }
+}
+
+/// An upstream spot response as received, for debugging a source's mapper.
+#[derive(Debug)]
+pub struct RawFetch {
+    pub url: String,
+    pub body: serde_json::Value,
+    /// Entries in the response array; 0 when the body isn't an array.
+    pub total: usize,
+    /// Entries that parse and map to a spot, before the callsign filter.
+    pub mapped: usize,
+}
+
+impl RawFetch {
+    /// Count the entries of `body` that deserialize as `T` and pass `maps`.
+    fn new<T: DeserializeOwned>(
+        url: &str,
+        body: serde_json::Value,
+        maps: impl Fn(&T) -> bool,
+    ) -> Self {
+        let entries = body.as_array().map(Vec::as_slice).unwrap_or_default();
+        let mapped = entries
+            .iter()
+            .filter(|entry| T::deserialize(*entry).is_ok_and(|parsed| maps(&parsed)))
+            .count();
+        Self {
+            url: url.to_string(),
+            total: entries.len(),
+            mapped,
+            body,
+        }
     }
 }
 
 /// Spawn the historic trails aggregator.
-pub fn spawn_historic_trails_aggregator(pool: PgPool, config: &Config) {
+pub fn spawn_historic_trails_aggregator(pool: PgPool, config: &Config, state: ServerState) {
     let client = reqwest::Client::builder()
         .user_agent(format!(
             "{}/{}",
@@ -65,13 +351,13 @@ pub fn spawn_historic_trails_aggregator(pool: PgPool, config: &Config) {
         concurrency: config.historic_trails_concurrency,
     };
     tokio::spawn(async move {
-        historic_trails::poll_loop(pool, client, trails_config).await;
+        historic_trails::poll_loop(pool, client, trails_config, state).await;
     });
     tracing::info!("Historic trails aggregator started");
 }
 
 /// Spawn the park boundaries aggregator (requires POTA stats for park catalog).
-pub fn spawn_park_boundaries_aggregator(pool: PgPool, config: &Config) {
+pub fn spawn_park_boundaries_aggregator(pool: PgPool, config: &Config, state: ServerState) {
     let client = reqwest::Client::builder()
         .user_agent(format!(
             "{}/{}",
@@ -87,13 +373,13 @@ pub fn spawn_park_boundaries_aggregator(pool: PgPool, config: &Config) {
         concurrency: config.park_boundaries_concurrency,
     };
     tokio::spawn(async move {
-        park_boundaries::poll_loop(pool, client, boundaries_config).await;
+        park_boundaries::poll_loop(pool, client, boundaries_config, state).await;
     });
     tracing::info!("Park boundaries aggregator started");
 }
 
 /// Spawn the Polish park boundaries aggregator (requires POTA stats for park catalog).
-pub fn spawn_polish_park_boundaries_aggregator(pool: PgPool, config: &Config) {
+pub fn spawn_polish_park_boundaries_aggregator(pool: PgPool, config: &Config, state: ServerState) {
     let client = reqwest::Client::builder()
         .user_agent(format!(
             "{}/{}",
@@ -109,49 +395,241 @@ pub fn spawn_polish_park_boundaries_aggregator(pool: PgPool, config: &Config) {
         concurrency: config.polish_park_boundaries_concurrency,
     };
     tokio::spawn(async move {
-        polish_park_boundaries::poll_loop(pool, client, boundaries_config).await;
+        polish_park_boundaries::poll_loop(pool, client, boundaries_config, state).await;
     });
     tracing::info!("Polish park boundaries aggregator started");
 }
 
 /// Spawn the POTA stats aggregator (independent of the spots system).
-pub fn spawn_pota_stats_aggregator(pool: PgPool, config: &Config) {
-    let client = reqwest::Client::builder()
-        .user_agent(format!(
-            "{}/{}",
-            env!("CARGO_PKG_NAME"),
-            env!("CARGO_PKG_VERSION")
-        ))
-        .build()
-        .expect("failed to build HTTP client");
+pub fn spawn_pota_stats_aggregator(pool: PgPool, config: &Config, state: ServerState) {
+    let client = HttpClient::new();
     let stats_config = pota_stats::PotaStatsConfig {
         concurrency: config.pota_stats_concurrency,
         batch_size: config.pota_stats_batch_size,
         cycle_hours: config.pota_stats_cycle_hours,
+        program_slug: config.pota_program_slug.clone(),
     };
     tokio::spawn(async move {
-        pota_stats::poll_loop(pool, client, stats_config).await;
+        pota_stats::poll_loop(pool, client, stats_config, state).await;
     });
     tracing::info!("POTA stats aggregator started");
 }
 
-/// Delete expired spots every 2 minutes.
-async fn ttl_cleanup_loop(pool: PgPool) {
+/// Keep only the `max` newest spots from one upstream poll, newest first, and
+/// return how many were dropped. Spots whose timestamp doesn't parse
+/// (`spotted_at` returns `None`) count as oldest.
+fn keep_newest_spots<T, K: Ord>(
+    spots: &mut Vec<T>,
+    max: usize,
+    spotted_at: impl Fn(&T) -> Option<K>,
+) -> usize {
+    if spots.len() <= max {
+        return 0;
+    }
+    spots.sort_by_cached_key(|spot| Reverse(spotted_at(spot)));
+    let dropped = spots.len() - max;
+    spots.truncate(max);
+    dropped
+}
+
+/// Parse an upstream spot timestamp. POTA and SOTA send UTC without a
+/// suffix, but an explicit `Z` or offset (`+01:00`, `+0100`) is honored and
+/// converted to UTC rather than read as UTC.
+fn parse_upstream_time(time: &str) -> chrono::ParseResult<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time)
+        .or_else(|_| DateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%.f%#z"))
+    {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S").map(|naive| naive.and_utc())
+}
+
+/// Drop spots whose `(source, external_id)` repeats later in the same poll,
+/// keeping the last occurrence, and return how many were dropped. Each spot is
+/// then upserted once per cycle.
+fn dedupe_by_external_id(spots: &mut Vec<AggregatedSpot>) -> usize {
+    let before = spots.len();
+    let mut seen = HashSet::new();
+    spots.reverse();
+    spots.retain(|spot| seen.insert((spot.source.as_str(), spot.external_id.clone())));
+    spots.reverse();
+    before - spots.len()
+}
+
+/// Run [`run_ttl_cleanup`] every 2 minutes.
+async fn ttl_cleanup_loop(pool: PgPool, archive: bool, state: ServerState) {
     let mut interval = tokio::time::interval(std::time::Duration::from_secs(120));
 
     loop {
         interval.tick().await;
-        match crate::db::delete_expired_spots(&pool).await {
-            Ok(count) => {
-                if count > 0 {
-                    tracing::debug!("TTL cleanup: deleted {} expired spots", count);
-                }
+        let _cycle = state.begin_cycle().await;
+        run_ttl_cleanup(&pool, archive, &state).await;
+    }
+}
+
+/// Delete expired spots (archiving them first when `archive` is set) and
+/// stale spot tombstones. A successful spot deletion is recorded in `state`
+/// for `/v1/status`.
+async fn run_ttl_cleanup(pool: &PgPool, archive: bool, state: &ServerState) {
+    match crate::db::delete_expired_spots(pool, archive).await {
+        Ok(count) => {
+            state.record_cleanup(count);
+            if count > 0 {
+                tracing::debug!("TTL cleanup: deleted {} expired spots", count);
             }
-            Err(e) => {
-                tracing::error!("TTL cleanup error: {}", e);
-                metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "ttl_cleanup")
-                    .increment(1);
+        }
+        Err(e) => {
+            tracing::error!("TTL cleanup error: {}", e);
+            metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "ttl_cleanup")
+                .increment(1);
+        }
+    }
+    if let Err(e) = crate::db::prune_spot_tombstones(pool).await {
+        tracing::error!("Tombstone cleanup error: {}", e);
+        metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "ttl_cleanup")
+            .increment(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use crate::models::spot::SpotSource;
+
+    #[test]
+    fn failing_aggregators_are_reported_without_stopping_others() {
+        let started = std::cell::Cell::new(0);
+        let fake_start = |name: &str| match name {
+            "broken" => Err("bad credentials".to_string()),
+            "panicky" => panic!("no TLS backend"),
+            _ => {
+                started.set(started.get() + 1);
+                Ok(())
             }
+        };
+        let reports: Vec<_> = ["ok", "broken", "panicky", "also-ok"]
+            .into_iter()
+            .map(|name| start_reported(name, || fake_start(name)))
+            .collect();
+
+        assert_eq!(started.get(), 2);
+        let statuses: Vec<_> = reports.iter().map(|r| (r.name, r.status)).collect();
+        assert_eq!(
+            statuses,
+            [
+                ("ok", StartStatus::Started),
+                ("broken", StartStatus::Failed),
+                ("panicky", StartStatus::Failed),
+                ("also-ok", StartStatus::Started),
+            ]
+        );
+        assert_eq!(reports[0].reason, None);
+        assert_eq!(reports[1].reason.as_deref(), Some("bad credentials"));
+        assert_eq!(
+            reports[2].reason.as_deref(),
+            Some("panicked: no TLS backend")
+        );
+
+        let json = serde_json::to_value(&reports[1]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"name": "broken", "status": "failed", "reason": "bad credentials"})
+        );
+        assert!(serde_json::to_value(&reports[0]).unwrap()["reason"].is_null());
+    }
+
+    #[test]
+    fn keep_newest_spots_under_limit_is_untouched() {
+        let mut spots = vec![3, 1, 2];
+        assert_eq!(keep_newest_spots(&mut spots, 3, |s| Some(*s)), 0);
+        assert_eq!(spots, [3, 1, 2]);
+    }
+
+    #[test]
+    fn keep_newest_spots_drops_oldest_and_unparseable() {
+        let mut spots = vec![Some(5), None, Some(9), Some(1), Some(7)];
+        assert_eq!(keep_newest_spots(&mut spots, 3, |s| *s), 2);
+        assert_eq!(spots, [Some(9), Some(7), Some(5)]);
+    }
+
+    #[test]
+    fn upstream_time_converts_offsets_to_utc() {
+        let utc = "2025-06-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        for time in [
+            "2025-06-01T12:00:00",
+            "2025-06-01T12:00:00Z",
+            "2025-06-01T13:00:00+01:00",
+            "2025-06-01T13:00:00+0100",
+            "2025-06-01T07:30:00-04:30",
+        ] {
+            assert_eq!(parse_upstream_time(time).unwrap(), utc, "{time}");
         }
+        assert!(parse_upstream_time("2025-06-01 12:00").is_err());
+    }
+
+    fn aggregated(source: SpotSource, external_id: &str, callsign: &str) -> AggregatedSpot {
+        let now = chrono::Utc::now();
+        AggregatedSpot {
+            callsign: callsign.to_string(),
+            program_slug: None,
+            source,
+            external_id: external_id.to_string(),
+            frequency_khz: 14062.0,
+            mode: "CW".to_string(),
+            raw_mode: None,
+            reference: None,
+            reference_name: None,
+            spotter: None,
+            spotter_grid: None,
+            location_desc: None,
+            country_code: None,
+            state_abbr: None,
+            comments: None,
+            snr: None,
+            wpm: None,
+            spotted_at: now,
+            expires_at: now,
+        }
+    }
+
+    #[test]
+    fn dedupe_by_external_id_keeps_last_occurrence() {
+        let mut batch = vec![
+            aggregated(SpotSource::Pota, "1", "W1AW"),
+            aggregated(SpotSource::Pota, "2", "K1ABC"),
+            aggregated(SpotSource::Pota, "1", "W1AW/P"),
+        ];
+        assert_eq!(dedupe_by_external_id(&mut batch), 1);
+        let kept: Vec<_> = batch
+            .iter()
+            .map(|spot| (spot.external_id.as_str(), spot.callsign.as_str()))
+            .collect();
+        assert_eq!(kept, [("2", "K1ABC"), ("1", "W1AW/P")]);
+    }
+
+    #[test]
+    fn dedupe_by_external_id_keys_on_source() {
+        let mut batch = vec![
+            aggregated(SpotSource::Pota, "1", "W1AW"),
+            aggregated(SpotSource::Sota, "1", "W1AW"),
+        ];
+        assert_eq!(dedupe_by_external_id(&mut batch), 0);
+        assert_eq!(batch.len(), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn ttl_cleanup_run_records_its_timestamp() {
+        let pool = test_support::pool().await;
+        let state = ServerState::default();
+        let before = chrono::Utc::now();
+
+        run_ttl_cleanup(&pool, false, &state).await;
+        let first = state.last_cleanup().unwrap();
+        assert!(first.at >= before);
+
+        run_ttl_cleanup(&pool, false, &state).await;
+        assert!(state.last_cleanup().unwrap().at >= first.at);
     }
 }
@@ -0,0 +1,224 @@
+//! Spot mode normalization shared by the POTA/SOTA/WSPR aggregators, and the
+//! per-mode channel plans self-spot frequencies are snapped to.
+//!
+//! Upstreams spell one mode several ways (`USB`, `LSB` and `PHONE` are all
+//! SSB; `FT-8` is FT8) and sometimes send none, which defeats exact-match
+//! mode filters. Each `map_spot` stores the canonical mode and keeps what the
+//! upstream sent in `raw_mode`.
+
+use crate::models::spot::band_for_khz;
+
+/// Every mode an aggregated spot can carry, as listed by `GET /v1/spots/modes`.
+pub const CANONICAL_MODES: &[&str] = &[
+    "CW",
+    "SSB",
+    "AM",
+    "FM",
+    "DV",
+    "FT8",
+    "FT4",
+    "JS8",
+    "JT65",
+    "JT9",
+    "Q65",
+    "MSK144",
+    "PSK31",
+    "RTTY",
+    "OLIVIA",
+    "SSTV",
+    "WSPR",
+    "DATA",
+    "OTHER",
+    UNKNOWN_MODE,
+];
+
+/// Stored for a spot whose upstream sent no mode.
+pub const UNKNOWN_MODE: &str = "UNKNOWN";
+
+/// Map an upstream mode to one of [`CANONICAL_MODES`], ignoring case,
+/// surrounding whitespace, and `-`, `_` or space separators. Blank modes are
+/// [`UNKNOWN_MODE`]; modes not recognized are `OTHER`.
+pub fn normalize_mode(raw: &str) -> &'static str {
+    canonical_mode(raw).unwrap_or("OTHER")
+}
+
+/// A `mode` query filter as matched against stored modes: the canonical mode
+/// when `raw` is recognized, otherwise `raw` as given (uppercased), so an
+/// unknown mode matches no spots rather than every `OTHER` one.
+pub fn mode_filter(raw: &str) -> String {
+    canonical_mode(raw).map_or_else(|| raw.trim().to_ascii_uppercase(), str::to_string)
+}
+
+/// The canonical mode `raw` spells, or `None` when it isn't recognized.
+fn canonical_mode(raw: &str) -> Option<&'static str> {
+    let key: String = raw
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' ' | '\t'))
+        .collect::<String>()
+        .to_ascii_uppercase();
+    let mode = match key.as_str() {
+        "" | "UNKNOWN" => UNKNOWN_MODE,
+        "CW" | "A1A" => "CW",
+        "SSB" | "USB" | "LSB" | "PHONE" | "PH" | "VOICE" => "SSB",
+        "AM" => "AM",
+        "FM" | "NFM" | "WFM" => "FM",
+        "DV" | "DSTAR" | "DMR" | "C4FM" | "FUSION" | "YSF" | "FREEDV" | "M17" => "DV",
+        "FT8" => "FT8",
+        "FT4" => "FT4",
+        "JS8" | "JS8CALL" => "JS8",
+        "JT65" => "JT65",
+        "JT9" => "JT9",
+        "Q65" => "Q65",
+        "MSK144" => "MSK144",
+        "PSK" | "PSK31" | "BPSK" | "BPSK31" => "PSK31",
+        "RTTY" | "RY" => "RTTY",
+        "OLIVIA" => "OLIVIA",
+        "SSTV" => "SSTV",
+        "WSPR" => "WSPR",
+        "DATA" | "DIGI" | "DIGITAL" | "DIG" => "DATA",
+        "OTHER" => "OTHER",
+        _ => return None,
+    };
+    Some(mode)
+}
+
+/// FT8 dial frequencies in kHz, one per band.
+const FT8_DIAL_KHZ: &[f64] = &[
+    1840.0, 3573.0, 5357.0, 7074.0, 10136.0, 14074.0, 18100.0, 21074.0, 24915.0, 28074.0, 50313.0,
+    144174.0,
+];
+
+/// FT4 dial frequencies in kHz, one per band.
+const FT4_DIAL_KHZ: &[f64] = &[
+    3575.0, 7047.5, 10140.0, 14080.0, 18104.0, 21140.0, 24919.0, 28180.0, 50318.0, 144170.0,
+];
+
+/// How far a digital-mode spot may be from a dial frequency and still snap.
+const DIAL_SNAP_KHZ: f64 = 1.0;
+
+/// FM channel spacing in kHz for the bands with channelized FM.
+const FM_CHANNEL_SPACING_KHZ: &[(&str, f64)] =
+    &[("10m", 10.0), ("6m", 5.0), ("2m", 5.0), ("70cm", 12.5)];
+
+/// The standard frequency a spot was probably meant to be on: the nearest
+/// FT8/FT4 dial frequency within 1 kHz, or the nearest FM channel on the
+/// band's spacing. Other modes and frequencies are returned unchanged.
+pub fn snap_to_channel(mode: &str, frequency_khz: f64) -> f64 {
+    let mode = mode.trim();
+    let dials = if mode.eq_ignore_ascii_case("FT8") {
+        FT8_DIAL_KHZ
+    } else if mode.eq_ignore_ascii_case("FT4") {
+        FT4_DIAL_KHZ
+    } else if mode.eq_ignore_ascii_case("FM") {
+        return band_for_khz(frequency_khz)
+            .and_then(|band| {
+                FM_CHANNEL_SPACING_KHZ
+                    .iter()
+                    .find(|(name, _)| *name == band)
+            })
+            .map_or(frequency_khz, |&(_, spacing)| {
+                (frequency_khz / spacing).round() * spacing
+            });
+    } else {
+        return frequency_khz;
+    };
+
+    dials
+        .iter()
+        .copied()
+        .filter(|dial| (dial - frequency_khz).abs() <= DIAL_SNAP_KHZ)
+        .min_by(|a, b| {
+            (a - frequency_khz)
+                .abs()
+                .total_cmp(&(b - frequency_khz).abs())
+        })
+        .unwrap_or(frequency_khz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observed_upstream_modes_normalize() {
+        let cases = [
+            ("CW", "CW"),
+            ("cw", "CW"),
+            (" CW ", "CW"),
+            ("SSB", "SSB"),
+            ("USB", "SSB"),
+            ("LSB", "SSB"),
+            ("PHONE", "SSB"),
+            ("Phone", "SSB"),
+            ("AM", "AM"),
+            ("FM", "FM"),
+            ("NFM", "FM"),
+            ("DSTAR", "DV"),
+            ("D-STAR", "DV"),
+            ("DMR", "DV"),
+            ("C4FM", "DV"),
+            ("FT8", "FT8"),
+            ("FT-8", "FT8"),
+            ("ft8", "FT8"),
+            ("FT 8", "FT8"),
+            ("FT4", "FT4"),
+            ("FT-4", "FT4"),
+            ("JS8", "JS8"),
+            ("JS8Call", "JS8"),
+            ("JT65", "JT65"),
+            ("PSK31", "PSK31"),
+            ("PSK", "PSK31"),
+            ("BPSK31", "PSK31"),
+            ("RTTY", "RTTY"),
+            ("OLIVIA", "OLIVIA"),
+            ("SSTV", "SSTV"),
+            ("WSPR", "WSPR"),
+            ("DATA", "DATA"),
+            ("DIGI", "DATA"),
+            ("", "UNKNOWN"),
+            ("   ", "UNKNOWN"),
+            ("MFSK", "OTHER"),
+            ("HELL", "OTHER"),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(normalize_mode(raw), expected, "raw mode {raw:?}");
+        }
+    }
+
+    #[test]
+    fn normalized_modes_are_canonical_and_stable() {
+        for raw in ["usb", "FT-8", "", "MFSK", "js8call", "D-STAR"] {
+            let mode = normalize_mode(raw);
+            assert!(CANONICAL_MODES.contains(&mode), "{mode} not canonical");
+            assert_eq!(normalize_mode(mode), mode);
+        }
+        for mode in CANONICAL_MODES {
+            assert_eq!(normalize_mode(mode), *mode);
+        }
+    }
+
+    #[test]
+    fn mode_filters_pass_unrecognized_modes_through() {
+        assert_eq!(mode_filter("ft-8"), "FT8");
+        assert_eq!(mode_filter("usb"), "SSB");
+        assert_eq!(mode_filter("other"), "OTHER");
+        assert_eq!(mode_filter(" xyz "), "XYZ");
+    }
+
+    #[test]
+    fn digital_spots_snap_to_dial_within_one_khz() {
+        assert_eq!(snap_to_channel("FT8", 14074.3), 14074.0);
+        assert_eq!(snap_to_channel("ft4", 7048.2), 7047.5);
+        assert_eq!(snap_to_channel("FT8", 14076.0), 14076.0);
+        assert_eq!(snap_to_channel("CW", 14074.3), 14074.3);
+    }
+
+    #[test]
+    fn fm_spots_snap_to_band_channel_spacing() {
+        assert_eq!(snap_to_channel("FM", 146521.0), 146520.0);
+        assert_eq!(snap_to_channel("FM", 446006.0), 446000.0);
+        assert_eq!(snap_to_channel("fm", 29604.0), 29600.0);
+        // No channel plan on HF below 10m.
+        assert_eq!(snap_to_channel("FM", 14321.0), 14321.0);
+    }
+}
@@ -7,6 +7,7 @@ use crate::aggregators::state_park_sources;
 use crate::db::park_boundaries::{self, UnfetchedPark};
 use crate::metrics as app_metrics;
 use crate::models::park_boundary::{ArcGisFeature, ArcGisResponse};
+use crate::server::ServerState;
 
 const PADUS_URL: &str = "https://services.arcgis.com/v01gqwM5QqNysAAi/arcgis/rest/services/Manager_Name_PADUS/FeatureServer/0";
 
@@ -51,7 +52,12 @@ enum FetchResult {
 }
 
 /// Main poll loop — fetches boundaries for unmatched parks, then re-checks stale ones.
-pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: ParkBoundariesConfig) {
+pub async fn poll_loop(
+    pool: PgPool,
+    client: reqwest::Client,
+    config: ParkBoundariesConfig,
+    state: ServerState,
+) {
     // Wait for POTA stats aggregator to populate pota_parks first
     tracing::info!("Park boundaries: waiting 120s for POTA stats to populate park catalog");
     tokio::time::sleep(std::time::Duration::from_secs(120)).await;
@@ -59,6 +65,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: ParkBounda
     let semaphore = Arc::new(Semaphore::new(config.concurrency));
 
     loop {
+        let cycle = state.begin_cycle().await;
         let batch_start = std::time::Instant::now();
         let total_cached = park_boundaries::count_boundaries(&pool).await.unwrap_or(0);
 
@@ -140,6 +147,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: ParkBounda
             "Park boundaries: sleeping {}h until next cycle",
             config.cycle_hours
         );
+        drop(cycle);
         tokio::time::sleep(std::time::Duration::from_secs(config.cycle_hours * 3600)).await;
     }
 }
@@ -6,6 +6,7 @@ use tokio::sync::Semaphore;
 use crate::db::park_boundaries::{self, UnfetchedPark};
 use crate::metrics as app_metrics;
 use crate::models::park_boundary::{WfsFeature, WfsFeatureCollection};
+use crate::server::ServerState;
 
 use super::park_boundaries::merge_geojson_geometries;
 
@@ -48,7 +49,12 @@ enum FetchResult {
 }
 
 /// Main poll loop — fetches boundaries for unmatched Polish parks, then re-checks stale ones.
-pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PolishParkBoundariesConfig) {
+pub async fn poll_loop(
+    pool: PgPool,
+    client: reqwest::Client,
+    config: PolishParkBoundariesConfig,
+    state: ServerState,
+) {
     // Wait for POTA stats aggregator to populate park catalog
     tracing::info!("Polish park boundaries: waiting 180s for POTA stats to populate park catalog");
     tokio::time::sleep(std::time::Duration::from_secs(180)).await;
@@ -56,6 +62,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PolishPark
     let semaphore = Arc::new(Semaphore::new(config.concurrency));
 
     loop {
+        let cycle = state.begin_cycle().await;
         let batch_start = std::time::Instant::now();
         let total_cached = park_boundaries::count_boundaries(&pool).await.unwrap_or(0);
 
@@ -139,6 +146,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PolishPark
             "Polish park boundaries: sleeping {}h until next cycle",
             config.cycle_hours
         );
+        drop(cycle);
         tokio::time::sleep(std::time::Duration::from_secs(config.cycle_hours * 3600)).await;
     }
 }
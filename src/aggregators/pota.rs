@@ -1,12 +1,46 @@
-use chrono::{Duration, NaiveDateTime, Utc};
+use std::fmt;
+use std::ops::Range;
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
 use serde::Deserialize;
 use sqlx::PgPool;
 
+use crate::config::Config;
 use crate::db::upsert_aggregated_spot;
+use crate::feature_flags::{Feature, FeatureFlags};
+use crate::http_client::{HttpClient, HttpError, RetryPolicy};
 use crate::metrics as app_metrics;
-use crate::models::spot::{AggregatedSpot, SpotSource};
+use crate::models::spot::{sanitize_comment, AggregatedSpot, RespotTtl, SpotSource};
+use crate::server::ServerState;
+
+use super::callsign_filter::CallsignFilter;
+use super::frequency_range::FrequencyRange;
+use super::mode::normalize_mode;
+use super::RawFetch;
+
+pub const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Fits within the 60s poll interval even when every attempt times out.
+const FETCH_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff: std::time::Duration::from_secs(1),
+    max_backoff: std::time::Duration::from_secs(5),
+    timeout: std::time::Duration::from_secs(15),
+};
 
-const POTA_SPOTS_URL: &str = "https://api.pota.app/spot/activator";
+/// Primary activator spots endpoint; `POTA_SECONDARY_SPOT_URLS` adds mirrors.
+pub const POTA_SPOTS_URL: &str = "https://api.pota.app/spot/activator";
+
+/// Longest window one backfill may cover.
+pub const BACKFILL_MAX_WINDOW: Duration = Duration::hours(24);
+
+/// Most pages one backfill fetches, so an upstream that never returns an
+/// empty page can't keep the job running forever.
+const BACKFILL_MAX_PAGES: u32 = 500;
+
+/// Frequencies below this are taken as MHz; no amateur band starts under
+/// 1000 kHz, so a value like `14.074` can only be MHz.
+const MHZ_BELOW: f64 = 1000.0;
 
 /// Upstream JSON shape from the POTA activator spots endpoint.
 #[derive(Debug, Deserialize)]
@@ -14,8 +48,10 @@ const POTA_SPOTS_URL: &str = "https://api.pota.app/spot/activator";
 struct PotaSpot {
     spot_id: i64,
     activator: String,
-    frequency: String,
-    mode: String,
+    frequency: PotaFrequency,
+    /// Spelled variously (`USB`, `FT-8`, ...); normalized by `map_spot`.
+    #[serde(default)]
+    mode: Option<String>,
     reference: String,
     #[serde(default)]
     park_name: Option<String>,
@@ -31,13 +67,62 @@ struct PotaSpot {
     expire: Option<i64>,
 }
 
+/// `frequency` is normally a string of kHz (`"14074"`), but has been seen as a
+/// bare JSON number too.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PotaFrequency {
+    Text(String),
+    Number(f64),
+}
+
+impl fmt::Display for PotaFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{text:?}"),
+            Self::Number(number) => write!(f, "{number}"),
+        }
+    }
+}
+
 /// Poll POTA activator spots every 60 seconds.
-pub async fn poll_loop(pool: PgPool, client: reqwest::Client) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+/// Cycles are skipped while the `spot_ingest` feature flag is off.
+#[allow(clippy::too_many_arguments)]
+pub async fn poll_loop(
+    pool: PgPool,
+    client: HttpClient,
+    urls: Vec<String>,
+    program_slug: String,
+    comment_max_len: usize,
+    max_spots_per_cycle: usize,
+    respot_ttl: RespotTtl,
+    callsign_filter: CallsignFilter,
+    frequency_range: FrequencyRange,
+    flags: FeatureFlags,
+    state: ServerState,
+) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
 
     loop {
         interval.tick().await;
-        if let Err(e) = fetch_and_upsert(&pool, &client).await {
+        let _cycle = state.begin_cycle().await;
+        if !flags.is_enabled(Feature::SpotIngest) {
+            continue;
+        }
+        let result = fetch_and_upsert(
+            &pool,
+            &client,
+            &urls,
+            &program_slug,
+            comment_max_len,
+            max_spots_per_cycle,
+            &respot_ttl,
+            &callsign_filter,
+            frequency_range,
+        )
+        .await;
+        state.record_fetch(SpotSource::Pota.as_str(), result.is_ok());
+        if let Err(e) = result {
             tracing::error!("POTA aggregator error: {}", e);
             metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "pota_spots")
                 .increment(1);
@@ -45,26 +130,120 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client) {
     }
 }
 
-async fn fetch_and_upsert(
+/// Run one poll: fetch from every endpoint, cap, drop callsigns the filter
+/// rejects and frequencies out of range, merge by spot ID, and upsert.
+/// Returns how many spots were upserted.
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_and_upsert(
     pool: &PgPool,
-    client: &reqwest::Client,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let spots: Vec<PotaSpot> = client
-        .get(POTA_SPOTS_URL)
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await?;
-
-    tracing::debug!("POTA: fetched {} spots", spots.len());
+    client: &HttpClient,
+    urls: &[String],
+    program_slug: &str,
+    comment_max_len: usize,
+    max_spots_per_cycle: usize,
+    respot_ttl: &RespotTtl,
+    callsign_filter: &CallsignFilter,
+    frequency_range: FrequencyRange,
+) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+    let spots = fetch_all_spots(client, urls, max_spots_per_cycle).await?;
+    let respot_expires_at = respot_ttl.respot_expiry(&SpotSource::Pota, Utc::now());
+    Ok(upsert_spots(
+        pool,
+        &spots,
+        program_slug,
+        comment_max_len,
+        respot_expires_at,
+        callsign_filter,
+        frequency_range,
+        None,
+    )
+    .await)
+}
 
-    let mut upserted = 0u32;
-    for spot in &spots {
-        match map_spot(spot) {
-            Ok(agg) => match upsert_aggregated_spot(pool, &agg).await {
-                Ok(_) => upserted += 1,
-                Err(e) => tracing::warn!("POTA upsert error for {}: {}", spot.activator, e),
+/// Backfill the spots POTA reports for `window`, paging through the history
+/// endpoint at `url` until it returns an empty page, with
+/// `POTA_BACKFILL_PAGE_DELAY_MS` between pages. Spots go through the same
+/// filters as a poll but are stored as already expired. Progress is recorded on job `job_id`; each page counts as
+/// an aggregator cycle, so draining pauses the backfill between pages.
+pub async fn backfill(
+    pool: &PgPool,
+    client: &HttpClient,
+    config: &Config,
+    url: &str,
+    window: Range<DateTime<Utc>>,
+    state: &ServerState,
+    job_id: uuid::Uuid,
+) -> Result<(), HttpError> {
+    let page_delay = std::time::Duration::from_millis(config.pota_backfill_page_delay_ms);
+    for page in 1..=BACKFILL_MAX_PAGES {
+        if page > 1 {
+            tokio::time::sleep(page_delay).await;
+        }
+        let _cycle = state.begin_cycle().await;
+        let page_url = backfill_page_url(url, &window, page);
+        let spots: Vec<PotaSpot> = client
+            .get_json("pota_backfill", &page_url, &FETCH_POLICY)
+            .await?;
+        if spots.is_empty() {
+            return Ok(());
+        }
+        let upserted = upsert_spots(
+            pool,
+            &spots,
+            &config.pota_program_slug,
+            config.spot_comment_max_len,
+            None,
+            &config.aggregator_callsign_filter,
+            config.spot_frequency_range,
+            Some(&window),
+        )
+        .await;
+        state.jobs().record_page(job_id, upserted);
+    }
+    tracing::warn!(
+        "POTA backfill stopped after {} pages without reaching an empty page",
+        BACKFILL_MAX_PAGES
+    );
+    Ok(())
+}
+
+/// One page of the history endpoint: `<url>?from=...&to=...&page=N`, with
+/// RFC 3339 UTC bounds and pages counted from 1.
+fn backfill_page_url(url: &str, window: &Range<DateTime<Utc>>, page: u32) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!(
+        "{url}{separator}from={}&to={}&page={page}",
+        window.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+        window.end.to_rfc3339_opts(SecondsFormat::Secs, true),
+    )
+}
+
+/// Map, filter, dedupe, and upsert one batch of upstream spots. With a
+/// `backfill_window`, spots outside it are skipped and the rest are stored
+/// expiring their usual lifetime after they were spotted. Returns how many spots were upserted.
+#[allow(clippy::too_many_arguments)]
+async fn upsert_spots(
+    pool: &PgPool,
+    spots: &[PotaSpot],
+    program_slug: &str,
+    comment_max_len: usize,
+    respot_expires_at: Option<DateTime<Utc>>,
+    callsign_filter: &CallsignFilter,
+    frequency_range: FrequencyRange,
+    backfill_window: Option<&Range<DateTime<Utc>>>,
+) -> u32 {
+    let mut batch = Vec::with_capacity(spots.len());
+    let mut filtered = 0u32;
+    for spot in spots {
+        match map_spot(spot, program_slug, comment_max_len) {
+            Ok(agg) if !callsign_filter.allows(&agg.callsign) => filtered += 1,
+            Ok(mut agg) => match backfill_window {
+                Some(window) if !window.contains(&agg.spotted_at) => filtered += 1,
+                Some(_) => {
+                    agg.expires_at = agg.spotted_at + spot_lifetime(spot);
+                    batch.push(agg);
+                }
+                None => batch.push(agg),
             },
             Err(e) => {
                 tracing::warn!("POTA parse error spotId={}: {}", spot.spot_id, e);
@@ -72,42 +251,169 @@ async fn fetch_and_upsert(
         }
     }
 
+    let out_of_range = frequency_range.retain(&mut batch, "POTA");
+    let duplicates = super::dedupe_by_external_id(&mut batch);
+
+    let mut upserted = 0u32;
+    for agg in &batch {
+        match upsert_aggregated_spot(pool, agg, respot_expires_at).await {
+            Ok(_) => upserted += 1,
+            Err(e) => tracing::warn!("POTA upsert error for {}: {}", agg.callsign, e),
+        }
+    }
+
+    if filtered > 0 {
+        tracing::debug!(
+            "POTA: skipped {} spots by callsign filter or backfill window",
+            filtered
+        );
+    }
+    if out_of_range > 0 {
+        tracing::debug!(
+            "POTA: skipped {} spots outside the frequency range",
+            out_of_range
+        );
+    }
+    if duplicates > 0 {
+        tracing::debug!("POTA: skipped {} duplicate spots in the batch", duplicates);
+    }
     tracing::debug!("POTA: upserted {}/{} spots", upserted, spots.len());
-    Ok(())
+    upserted
+}
+
+/// Fetch and cap the spots of each endpoint in turn. An endpoint that fails is
+/// skipped as long as another one answers; the spots are deduped later.
+async fn fetch_all_spots(
+    client: &HttpClient,
+    urls: &[String],
+    max_spots_per_cycle: usize,
+) -> Result<Vec<PotaSpot>, HttpError> {
+    let mut spots = Vec::new();
+    let mut last_error = None;
+    let mut succeeded = 0;
+    for url in urls {
+        match fetch_spots(client, url).await {
+            Ok(mut fetched) => {
+                tracing::debug!("POTA: fetched {} spots from {}", fetched.len(), url);
+                limit_spots(&mut fetched, max_spots_per_cycle);
+                spots.append(&mut fetched);
+                succeeded += 1;
+            }
+            Err(e) => {
+                tracing::warn!("POTA: fetching {} failed: {}", url, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) if succeeded == 0 => Err(e),
+        _ => Ok(spots),
+    }
 }
 
-fn map_spot(spot: &PotaSpot) -> Result<AggregatedSpot, Box<dyn std::error::Error + Send + Sync>> {
-    let frequency_khz: f64 = spot.frequency.parse()?;
+async fn fetch_spots(client: &HttpClient, url: &str) -> Result<Vec<PotaSpot>, HttpError> {
+    client.get_json("pota_spots", url, &FETCH_POLICY).await
+}
 
-    // spotTime is UTC but has no Z suffix
-    let spotted_at = NaiveDateTime::parse_from_str(&spot.spot_time, "%Y-%m-%dT%H:%M:%S")
-        .map(|naive| naive.and_utc())?;
+/// Fetch one endpoint as plain JSON for the admin raw endpoint, with the same
+/// upstream label and retry policy as a poll.
+pub async fn fetch_raw(
+    client: &HttpClient,
+    url: &str,
+    program_slug: &str,
+    comment_max_len: usize,
+) -> Result<RawFetch, HttpError> {
+    let body = client.get_json("pota_spots", url, &FETCH_POLICY).await?;
+    Ok(RawFetch::new(url, body, |spot: &PotaSpot| {
+        map_spot(spot, program_slug, comment_max_len).is_ok()
+    }))
+}
 
-    // expire = seconds remaining; fallback 30 min
-    let expires_at = match spot.expire {
-        Some(secs) if secs > 0 => Utc::now() + Duration::seconds(secs),
-        _ => Utc::now() + Duration::minutes(30),
+/// Cap an oversized response at the newest `max` spots so an upstream anomaly
+/// can't flood the upsert loop.
+fn limit_spots(spots: &mut Vec<PotaSpot>, max: usize) {
+    let dropped = super::keep_newest_spots(spots, max, |spot| {
+        super::parse_upstream_time(&spot.spot_time).ok()
+    });
+    if dropped > 0 {
+        tracing::warn!(
+            "POTA: response had {} spots, over the limit of {}; dropped the {} oldest",
+            max + dropped,
+            max,
+            dropped
+        );
+    }
+}
+
+/// Split a POTA locationDesc into country and state: `"US-WY"` → (US, WY),
+/// `"US"` → (US, None), `"CA-NS-X"` → (CA, NS). Empty segments are `None`.
+fn split_location_desc(desc: &str) -> (Option<String>, Option<String>) {
+    let mut parts = desc.split('-').map(|part| {
+        Some(part.trim())
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+    });
+    let country = parts.next().flatten();
+    let state = parts.next().flatten();
+    (country, state)
+}
+
+/// Frequency in kHz from the POTA `frequency` field. Surrounding whitespace
+/// and a trailing `.0` are tolerated, and values under `MHZ_BELOW` are
+/// converted from MHz.
+fn parse_frequency_khz(frequency: &PotaFrequency) -> Result<f64, String> {
+    let value = match frequency {
+        PotaFrequency::Text(text) => text
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid frequency {frequency}"))?,
+        PotaFrequency::Number(number) => *number,
     };
+    if !value.is_finite() || value <= 0.0 {
+        return Err(format!("invalid frequency {frequency}"));
+    }
+    if value < MHZ_BELOW {
+        // Round to the Hz so 14.074 MHz is exactly 14074 kHz
+        Ok((value * 1_000_000.0).round() / 1000.0)
+    } else {
+        Ok(value)
+    }
+}
+
+/// How long a spot stays up: `expire` seconds, or 30 minutes without one.
+fn spot_lifetime(spot: &PotaSpot) -> Duration {
+    match spot.expire {
+        Some(secs) if secs > 0 => Duration::seconds(secs),
+        _ => Duration::minutes(30),
+    }
+}
+
+fn map_spot(
+    spot: &PotaSpot,
+    program_slug: &str,
+    comment_max_len: usize,
+) -> Result<AggregatedSpot, Box<dyn std::error::Error + Send + Sync>> {
+    let frequency_khz = parse_frequency_khz(&spot.frequency)?;
+
+    // spotTime is UTC but has no Z suffix; an explicit offset is converted
+    let spotted_at = super::parse_upstream_time(&spot.spot_time)?;
+
+    let expires_at = Utc::now() + spot_lifetime(spot);
 
-    // Split locationDesc (e.g. "US-WY") into country / state
     let (country_code, state_abbr) = spot
         .location_desc
         .as_deref()
-        .map(|desc| {
-            let mut parts = desc.splitn(2, '-');
-            let country = parts.next().map(str::to_string);
-            let state = parts.next().map(str::to_string);
-            (country, state)
-        })
+        .map(split_location_desc)
         .unwrap_or((None, None));
 
     Ok(AggregatedSpot {
         callsign: spot.activator.clone(),
-        program_slug: Some("pota".to_string()),
+        program_slug: Some(program_slug.to_string()),
         source: SpotSource::Pota,
         external_id: spot.spot_id.to_string(),
         frequency_khz,
-        mode: spot.mode.clone(),
+        mode: normalize_mode(spot.mode.as_deref().unwrap_or_default()).to_string(),
+        raw_mode: spot.mode.clone(),
         reference: Some(spot.reference.clone()),
         reference_name: spot.park_name.clone(),
         spotter: spot.spotter.clone(),
@@ -115,10 +421,396 @@ fn map_spot(spot: &PotaSpot) -> Result<AggregatedSpot, Box<dyn std::error::Error
         location_desc: spot.location_desc.clone(),
         country_code,
         state_abbr,
-        comments: spot.comments.clone(),
+        comments: spot
+            .comments
+            .as_deref()
+            .and_then(|c| sanitize_comment(c, comment_max_len)),
         snr: None,
         wpm: None,
         spotted_at,
         expires_at,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use crate::http_client::mock::{self, MockTransport};
+    use chrono::SubsecRound;
+
+    /// Upstream response with `count` spots one second apart, in scrambled order.
+    fn oversized_response(count: i64) -> String {
+        let base =
+            chrono::NaiveDateTime::parse_from_str("2025-06-01T00:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap();
+        let spots: Vec<_> = (0..count)
+            .map(|i| (i * 7919) % count)
+            .map(|i| {
+                serde_json::json!({
+                    "spotId": i,
+                    "activator": format!("K{i}ABC"),
+                    "frequency": "14062",
+                    "mode": "CW",
+                    "reference": "US-0001",
+                    "spotTime": (base + Duration::seconds(i))
+                        .format("%Y-%m-%dT%H:%M:%S")
+                        .to_string(),
+                })
+            })
+            .collect();
+        serde_json::to_string(&spots).unwrap()
+    }
+
+    #[test]
+    fn test_split_location_desc_country_only() {
+        assert_eq!(split_location_desc("US"), (Some("US".to_string()), None));
+    }
+
+    #[test]
+    fn test_split_location_desc_country_and_state() {
+        assert_eq!(
+            split_location_desc("US-WY"),
+            (Some("US".to_string()), Some("WY".to_string()))
+        );
+        assert_eq!(
+            split_location_desc("CA-NS"),
+            (Some("CA".to_string()), Some("NS".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_split_location_desc_ignores_extra_segments() {
+        assert_eq!(
+            split_location_desc("CA-NS-XX"),
+            (Some("CA".to_string()), Some("NS".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_split_location_desc_empty() {
+        assert_eq!(split_location_desc(""), (None, None));
+        assert_eq!(split_location_desc("US-"), (Some("US".to_string()), None));
+    }
+
+    fn text(raw: &str) -> PotaFrequency {
+        PotaFrequency::Text(raw.to_string())
+    }
+
+    #[test]
+    fn test_parse_frequency_khz() {
+        assert_eq!(parse_frequency_khz(&text("14074")), Ok(14074.0));
+        assert_eq!(parse_frequency_khz(&text("14074.0")), Ok(14074.0));
+        assert_eq!(parse_frequency_khz(&text(" 7074 ")), Ok(7074.0));
+        assert_eq!(parse_frequency_khz(&text("7074\t")), Ok(7074.0));
+        assert_eq!(parse_frequency_khz(&text("14062.5")), Ok(14062.5));
+        assert_eq!(
+            parse_frequency_khz(&PotaFrequency::Number(14074.0)),
+            Ok(14074.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_frequency_khz_converts_mhz() {
+        assert_eq!(parse_frequency_khz(&text("14.074")), Ok(14074.0));
+        assert_eq!(parse_frequency_khz(&text("7.0325")), Ok(7032.5));
+        assert_eq!(parse_frequency_khz(&text("146.52")), Ok(146520.0));
+        assert_eq!(parse_frequency_khz(&PotaFrequency::Number(3.5)), Ok(3500.0));
+        // 1000 and up is already kHz
+        assert_eq!(parse_frequency_khz(&text("1000")), Ok(1000.0));
+    }
+
+    #[test]
+    fn test_parse_frequency_khz_rejects_garbage() {
+        for raw in ["", "  ", "abc", "14,074", "-7074", "0", "NaN", "inf"] {
+            assert!(parse_frequency_khz(&text(raw)).is_err(), "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn test_pota_spot_accepts_numeric_frequency() {
+        let spot: PotaSpot = serde_json::from_value(serde_json::json!({
+            "spotId": 1, "activator": "K1ABC", "frequency": 14074, "mode": "FT8",
+            "reference": "US-0001", "spotTime": "2025-06-01T00:00:00"
+        }))
+        .unwrap();
+        assert_eq!(map_spot(&spot, "pota", 200).unwrap().frequency_khz, 14074.0);
+    }
+
+    #[test]
+    fn test_map_spot_normalizes_mode_and_keeps_raw() {
+        let spot = |mode: serde_json::Value| -> PotaSpot {
+            serde_json::from_value(serde_json::json!({
+                "spotId": 1, "activator": "K1ABC", "frequency": "14285", "mode": mode,
+                "reference": "US-0001", "spotTime": "2025-06-01T00:00:00"
+            }))
+            .unwrap()
+        };
+        let usb = map_spot(&spot("USB".into()), "pota", 200).unwrap();
+        assert_eq!(usb.mode, "SSB");
+        assert_eq!(usb.raw_mode.as_deref(), Some("USB"));
+
+        let blank = map_spot(&spot("".into()), "pota", 200).unwrap();
+        assert_eq!(blank.mode, "UNKNOWN");
+        assert_eq!(blank.raw_mode.as_deref(), Some(""));
+
+        let null = map_spot(&spot(serde_json::Value::Null), "pota", 200).unwrap();
+        assert_eq!(null.mode, "UNKNOWN");
+        assert_eq!(null.raw_mode, None);
+    }
+
+    #[test]
+    fn test_map_spot_uses_configured_program_slug() {
+        let spot: PotaSpot = serde_json::from_value(serde_json::json!({
+            "spotId": 1, "activator": "K1ABC", "frequency": "14062", "mode": "CW",
+            "reference": "US-0001", "spotTime": "2025-06-01T00:00:00"
+        }))
+        .unwrap();
+        let config = crate::config::Config::from_lookup(|var| match var {
+            "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+            "ADMIN_TOKEN" => Some("admin".to_string()),
+            "POTA_PROGRAM_SLUG" => Some("parks".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        let mapped = map_spot(&spot, &config.pota_program_slug, 200).unwrap();
+        assert_eq!(mapped.program_slug.as_deref(), Some("parks"));
+    }
+
+    #[test]
+    fn test_limit_spots_keeps_newest_of_oversized_response() {
+        let mut spots: Vec<PotaSpot> = serde_json::from_str(&oversized_response(20_000)).unwrap();
+        limit_spots(&mut spots, 500);
+
+        assert_eq!(spots.len(), 500);
+        assert_eq!(spots[0].spot_id, 19_999);
+        assert!(spots.iter().all(|spot| spot.spot_id >= 19_500));
+    }
+
+    #[test]
+    fn test_limit_spots_within_limit_is_noop() {
+        let mut spots: Vec<PotaSpot> = serde_json::from_str(&oversized_response(10)).unwrap();
+        limit_spots(&mut spots, 10);
+        assert_eq!(spots.len(), 10);
+    }
+
+    #[test]
+    fn test_backfill_page_url_appends_window_and_page() {
+        let from = "2025-06-01T14:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let window = from..from + Duration::hours(1);
+        assert_eq!(
+            backfill_page_url("https://pota-history.example/spots", &window, 1),
+            "https://pota-history.example/spots?from=2025-06-01T14:00:00Z&to=2025-06-01T15:00:00Z&page=1"
+        );
+        assert!(
+            backfill_page_url("https://pota-history.example/spots?key=abc", &window, 3)
+                .starts_with("https://pota-history.example/spots?key=abc&from=")
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_spots_retries_server_errors() {
+        let (client, transport) = mock::client(MockTransport::new([
+            MockTransport::status(502),
+            MockTransport::body(200, &oversized_response(2)),
+        ]));
+        let spots = fetch_spots(&client, POTA_SPOTS_URL).await.unwrap();
+        assert_eq!(spots.len(), 2);
+        assert_eq!(transport.calls(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_spots_gives_up_after_max_attempts() {
+        let (client, transport) =
+            mock::client(MockTransport::new(vec![MockTransport::status(503); 3]));
+        let err = fetch_spots(&client, POTA_SPOTS_URL).await.unwrap_err();
+        assert!(matches!(err, HttpError::Status { status: 503, .. }));
+        assert_eq!(transport.calls(), 3);
+    }
+
+    fn endpoints() -> Vec<String> {
+        vec![
+            POTA_SPOTS_URL.to_string(),
+            "https://pota-mirror.example/spot/activator".to_string(),
+        ]
+    }
+
+    #[tokio::test]
+    async fn fetch_all_spots_merges_endpoints_deduped_by_spot_id() {
+        // The mirror repeats spots 0-2 and adds 3-4
+        let primary = oversized_response(3);
+        let mirror = oversized_response(5);
+        let (client, transport) = mock::client(MockTransport::new([
+            MockTransport::body(200, &primary),
+            MockTransport::body(200, &mirror),
+        ]));
+
+        let spots = fetch_all_spots(&client, &endpoints(), 100).await.unwrap();
+        assert_eq!(spots.len(), 8);
+        assert_eq!(*transport.urls.lock().unwrap(), endpoints());
+
+        let mut batch: Vec<_> = spots
+            .iter()
+            .map(|s| map_spot(s, "pota", 200).unwrap())
+            .collect();
+        assert_eq!(super::super::dedupe_by_external_id(&mut batch), 3);
+        let mut ids: Vec<_> = batch.iter().map(|s| s.external_id.as_str()).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, ["0", "1", "2", "3", "4"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_all_spots_tolerates_a_failing_endpoint() {
+        let mut results = vec![MockTransport::status(503); 3];
+        results.push(MockTransport::body(200, &oversized_response(2)));
+        let (client, _) = mock::client(MockTransport::new(results));
+        let spots = fetch_all_spots(&client, &endpoints(), 100).await.unwrap();
+        assert_eq!(spots.len(), 2);
+
+        let (client, _) = mock::client(MockTransport::new(vec![MockTransport::status(503); 6]));
+        let err = fetch_all_spots(&client, &endpoints(), 100)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::Status { status: 503, .. }));
+    }
+
+    #[tokio::test]
+    async fn fetch_raw_returns_body_and_counts_mappable_spots() {
+        let upstream = serde_json::json!([
+            { "spotId": 1, "activator": "K1ABC", "frequency": "14074", "mode": "FT8",
+              "reference": "US-0001", "spotTime": "2025-06-01T00:00:00", "extra": [1, 2] },
+            { "spotId": 2, "activator": "K2ABC", "frequency": "abc", "mode": "CW",
+              "reference": "US-0002", "spotTime": "2025-06-01T00:00:00" },
+            { "spotId": 3, "activator": "K3ABC", "mode": "CW" },
+            "not a spot",
+        ]);
+        let (client, transport) = mock::client(MockTransport::new([MockTransport::body(
+            200,
+            &upstream.to_string(),
+        )]));
+
+        let fetch = fetch_raw(&client, POTA_SPOTS_URL, "pota", 200)
+            .await
+            .unwrap();
+        assert_eq!(fetch.url, POTA_SPOTS_URL);
+        assert_eq!(fetch.body, upstream);
+        assert_eq!((fetch.total, fetch.mapped), (4, 1));
+        assert_eq!(*transport.urls.lock().unwrap(), [POTA_SPOTS_URL]);
+    }
+
+    #[tokio::test]
+    async fn fetch_raw_of_non_array_counts_nothing() {
+        let (client, _) = mock::client(MockTransport::new([MockTransport::body(
+            200,
+            r#"{"error":"maintenance"}"#,
+        )]));
+        let fetch = fetch_raw(&client, POTA_SPOTS_URL, "pota", 200)
+            .await
+            .unwrap();
+        assert_eq!(fetch.body["error"], "maintenance");
+        assert_eq!((fetch.total, fetch.mapped), (0, 0));
+    }
+
+    /// One history page of `callsign`'s spots as `(spotId, spotted_at)`.
+    fn history_page(callsign: &str, spots: &[(i64, DateTime<Utc>)]) -> String {
+        let spots: Vec<_> = spots
+            .iter()
+            .map(|(id, spotted_at)| {
+                serde_json::json!({
+                    "spotId": id,
+                    "activator": callsign,
+                    "frequency": "14062",
+                    "mode": "CW",
+                    "reference": "US-0001",
+                    "spotTime": spotted_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                })
+            })
+            .collect();
+        serde_json::to_string(&spots).unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn backfill_pages_until_empty_and_keeps_the_window() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let now = Utc::now().trunc_subsecs(0);
+        let window = now - Duration::hours(3)..now - Duration::hours(1);
+        // Upstream IDs no other test or earlier run uses
+        let base = i64::from(rand::random::<u32>()) * 10;
+        let (first, before, second) = (base + 1, base + 2, base + 3);
+        let (client, transport) = mock::client(MockTransport::new([
+            MockTransport::body(
+                200,
+                &history_page(
+                    &callsign,
+                    &[
+                        (first, now - Duration::hours(2)),
+                        (before, now - Duration::hours(4)),
+                    ],
+                ),
+            ),
+            MockTransport::body(
+                200,
+                &history_page(&callsign, &[(second, now - Duration::minutes(90))]),
+            ),
+            MockTransport::body(200, "[]"),
+        ]));
+        let config = Config::from_lookup(|var| match var {
+            "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+            "ADMIN_TOKEN" => Some("admin".to_string()),
+            "POTA_BACKFILL_PAGE_DELAY_MS" => Some("0".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        let state = ServerState::default();
+        let job_id = state
+            .jobs()
+            .start("potaBackfill", window.start, window.end)
+            .unwrap();
+
+        backfill(
+            &pool,
+            &client,
+            &config,
+            "https://pota-history.example/spots",
+            window.clone(),
+            &state,
+            job_id,
+        )
+        .await
+        .unwrap();
+
+        let stored: Vec<(String, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT external_id, expires_at - interval '30 minutes' FROM spots
+             WHERE callsign = $1 ORDER BY spotted_at",
+        )
+        .bind(&callsign)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        sqlx::query("DELETE FROM spots WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Stops at the empty third page; the spot before the window is
+        // skipped and the others expire 30 minutes after they were spotted
+        let urls = transport.urls.lock().unwrap().clone();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls[2],
+            backfill_page_url("https://pota-history.example/spots", &window, 3)
+        );
+        assert_eq!(
+            stored,
+            vec![
+                (first.to_string(), now - Duration::hours(2)),
+                (second.to_string(), now - Duration::minutes(90)),
+            ]
+        );
+        let job = state.jobs().get(job_id).unwrap();
+        assert_eq!((job.pages_fetched, job.spots_upserted), (2, 2));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::io::Cursor;
 use std::sync::Arc;
 
@@ -5,18 +6,38 @@ use chrono::{NaiveDate, Utc};
 use sqlx::PgPool;
 use tokio::sync::Semaphore;
 
-use crate::db::pota_stats;
+use crate::db::{self, pota_stats};
+use crate::http_client::{HttpClient, HttpError, RetryPolicy};
 use crate::metrics as app_metrics;
 use crate::models::pota_stats::{PotaApiActivation, PotaApiLeaderboard, PotaApiStats, PotaCsvPark};
+use crate::models::program_reference::NewProgramReference;
+use crate::server::ServerState;
 
 const ALL_PARKS_CSV_URL: &str = "https://pota.app/all_parks_ext.csv";
 const POTA_API_BASE: &str = "https://api.pota.app";
 
+/// The park catalog CSV is several megabytes.
+const CATALOG_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff: std::time::Duration::from_secs(5),
+    max_backoff: std::time::Duration::from_secs(30),
+    timeout: std::time::Duration::from_secs(120),
+};
+
+const PARK_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff: std::time::Duration::from_secs(2),
+    max_backoff: std::time::Duration::from_secs(10),
+    timeout: std::time::Duration::from_secs(30),
+};
+
 /// Configuration for the POTA stats aggregator.
 pub struct PotaStatsConfig {
     pub concurrency: usize,
     pub batch_size: i64,
     pub cycle_hours: u64,
+    /// Program whose reference catalog the park list is written to.
+    pub program_slug: String,
 }
 
 impl Default for PotaStatsConfig {
@@ -25,15 +46,21 @@ impl Default for PotaStatsConfig {
             concurrency: 3,
             batch_size: 50,
             cycle_hours: 24,
+            program_slug: "pota".to_string(),
         }
     }
 }
 
 /// Main poll loop — runs forever, syncing park catalog then fetching stats in batches.
-pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PotaStatsConfig) {
+pub async fn poll_loop(
+    pool: PgPool,
+    client: HttpClient,
+    config: PotaStatsConfig,
+    state: ServerState,
+) {
     // Phase 1: Initial catalog sync
     loop {
-        match sync_park_catalog(&pool, &client).await {
+        match sync_park_catalog(&pool, &client, &config.program_slug).await {
             Ok(count) => {
                 tracing::info!("POTA stats: synced {} parks from catalog", count);
                 break;
@@ -51,6 +78,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PotaStatsC
 
     // Phase 2: Continuous batch fetching
     loop {
+        let cycle = state.begin_cycle().await;
         let batch_start = std::time::Instant::now();
         let total_parks = match pota_stats::count_parks(&pool).await {
             Ok(n) => n.max(1),
@@ -58,6 +86,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PotaStatsC
                 tracing::error!("POTA stats: count_parks failed: {}", e);
                 metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "pota_stats")
                     .increment(1);
+                drop(cycle);
                 tokio::time::sleep(std::time::Duration::from_secs(60)).await;
                 continue;
             }
@@ -81,6 +110,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PotaStatsC
                 tracing::error!("POTA stats: get_stalest_parks failed: {}", e);
                 metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "pota_stats")
                     .increment(1);
+                drop(cycle);
                 tokio::time::sleep(std::time::Duration::from_secs(60)).await;
                 continue;
             }
@@ -88,6 +118,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PotaStatsC
 
         if stalest.is_empty() {
             tracing::debug!("POTA stats: no parks to fetch, sleeping");
+            drop(cycle);
             tokio::time::sleep(std::time::Duration::from_secs(sleep_secs)).await;
             continue;
         }
@@ -144,7 +175,7 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PotaStatsC
         // We do this every cycle_hours by running it once at the top
         if !is_initial {
             // Periodic catalog re-sync every cycle
-            if let Err(e) = sync_park_catalog(&pool, &client).await {
+            if let Err(e) = sync_park_catalog(&pool, &client, &config.program_slug).await {
                 tracing::warn!("POTA stats: periodic catalog sync failed: {}", e);
             }
             // Reset consecutive error counters so previously-failing parks
@@ -167,25 +198,27 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client, config: PotaStatsC
             .set(Utc::now().timestamp() as f64);
 
         // Phase 3: sleep between batches
+        drop(cycle);
         tokio::time::sleep(std::time::Duration::from_secs(sleep_secs)).await;
     }
 }
 
-/// Fetch and parse the all_parks_ext.csv, upserting US parks.
+/// Fetch and parse the all_parks_ext.csv, upserting parks in countries with
+/// boundary sources for stats, and every park into the `program_slug`
+/// reference catalog.
 async fn sync_park_catalog(
     pool: &PgPool,
-    client: &reqwest::Client,
+    client: &HttpClient,
+    program_slug: &str,
 ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
     let csv_bytes = client
-        .get(ALL_PARKS_CSV_URL)
-        .send()
+        .get("pota_park_catalog", ALL_PARKS_CSV_URL, &CATALOG_POLICY)
         .await?
-        .error_for_status()?
-        .bytes()
-        .await?;
+        .body;
 
     let mut reader = csv::Reader::from_reader(Cursor::new(&csv_bytes));
     let mut count = 0usize;
+    let mut references: BTreeMap<String, NewProgramReference> = BTreeMap::new();
 
     for result in reader.deserialize::<PotaCsvPark>() {
         let park = match result {
@@ -196,6 +229,19 @@ async fn sync_park_catalog(
             }
         };
 
+        let active = park.active == "1";
+        references.insert(
+            park.reference.clone(),
+            NewProgramReference {
+                reference: park.reference.clone(),
+                name: park.name.clone(),
+                grid: park.grid.clone(),
+                latitude: park.lat,
+                longitude: park.lon,
+                active,
+            },
+        );
+
         // Only include parks for countries with boundary data sources
         if !park.reference.starts_with("US-")
             && !park.reference.starts_with("GB-")
@@ -205,8 +251,6 @@ async fn sync_park_catalog(
             continue;
         }
 
-        let active = park.active == "1";
-
         // Use locationDesc directly as the state key (e.g., "US-CA")
         let state = park.location_desc.clone();
 
@@ -228,13 +272,21 @@ async fn sync_park_catalog(
         count += 1;
     }
 
+    let references: Vec<NewProgramReference> = references.into_values().collect();
+    let written = db::upsert_program_references(pool, program_slug, &references).await?;
+    tracing::debug!(
+        "POTA stats: wrote {} references to the {} catalog",
+        written,
+        program_slug
+    );
+
     Ok(count)
 }
 
 /// Fetch all data for a single park: stats, activations, and leaderboard.
 async fn fetch_park_data(
     pool: &PgPool,
-    client: &reqwest::Client,
+    client: &HttpClient,
     park_reference: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Get the park's state for denormalization
@@ -359,52 +411,35 @@ async fn fetch_park_data(
 }
 
 async fn fetch_park_stats(
-    client: &reqwest::Client,
+    client: &HttpClient,
     park_reference: &str,
-) -> Result<PotaApiStats, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<PotaApiStats, HttpError> {
     let url = format!("{}/park/stats/{}", POTA_API_BASE, park_reference);
-    let stats: PotaApiStats = client
-        .get(&url)
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await?;
-    Ok(stats)
+    client.get_json("pota_park_stats", &url, &PARK_POLICY).await
 }
 
 async fn fetch_park_activations(
-    client: &reqwest::Client,
+    client: &HttpClient,
     park_reference: &str,
-) -> Result<Vec<PotaApiActivation>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Vec<PotaApiActivation>, HttpError> {
     let url = format!(
         "{}/park/activations/{}?count=all",
         POTA_API_BASE, park_reference
     );
-    let activations: Vec<PotaApiActivation> = client
-        .get(&url)
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await?;
-    Ok(activations)
+    client
+        .get_json("pota_park_activations", &url, &PARK_POLICY)
+        .await
 }
 
 async fn fetch_park_leaderboard(
-    client: &reqwest::Client,
+    client: &HttpClient,
     park_reference: &str,
-) -> Result<PotaApiLeaderboard, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<PotaApiLeaderboard, HttpError> {
     let url = format!(
         "{}/park/leaderboard/{}?count=all",
         POTA_API_BASE, park_reference
     );
-    let leaderboard: PotaApiLeaderboard = client
-        .get(&url)
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await?;
-    Ok(leaderboard)
+    client
+        .get_json("pota_park_leaderboard", &url, &PARK_POLICY)
+        .await
 }
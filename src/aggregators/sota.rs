@@ -1,10 +1,28 @@
-use chrono::{Duration, NaiveDateTime};
+use chrono::{Duration, Utc};
 use serde::Deserialize;
 use sqlx::PgPool;
 
 use crate::db::upsert_aggregated_spot;
+use crate::feature_flags::{Feature, FeatureFlags};
+use crate::http_client::{HttpClient, HttpError, RetryPolicy};
 use crate::metrics as app_metrics;
-use crate::models::spot::{AggregatedSpot, SpotSource};
+use crate::models::spot::{sanitize_comment, AggregatedSpot, RespotTtl, SpotSource};
+use crate::server::ServerState;
+
+use super::callsign_filter::CallsignFilter;
+use super::frequency_range::FrequencyRange;
+use super::mode::normalize_mode;
+use super::RawFetch;
+
+pub const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(90);
+
+/// Fits within the 90s poll interval even when every attempt times out.
+const FETCH_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff: std::time::Duration::from_secs(1),
+    max_backoff: std::time::Duration::from_secs(5),
+    timeout: std::time::Duration::from_secs(15),
+};
 
 const SOTA_SPOTS_URL: &str = "https://api2.sota.org.uk/api/spots/-1";
 
@@ -18,7 +36,9 @@ struct SotaSpot {
     activator_callsign: String,
     /// Frequency in **MHz** (must multiply by 1000 for kHz).
     frequency: String,
-    mode: String,
+    /// Spelled variously (`USB`, `FT-8`, ...); normalized by `map_spot`.
+    #[serde(default)]
+    mode: Option<String>,
     association_code: String,
     summit_code: String,
     #[serde(default)]
@@ -30,12 +50,41 @@ struct SotaSpot {
 }
 
 /// Poll SOTA spots every 90 seconds.
-pub async fn poll_loop(pool: PgPool, client: reqwest::Client) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(90));
+/// Cycles are skipped while the `spot_ingest` feature flag is off.
+#[allow(clippy::too_many_arguments)]
+pub async fn poll_loop(
+    pool: PgPool,
+    client: HttpClient,
+    program_slug: String,
+    comment_max_len: usize,
+    max_spots_per_cycle: usize,
+    respot_ttl: RespotTtl,
+    callsign_filter: CallsignFilter,
+    frequency_range: FrequencyRange,
+    flags: FeatureFlags,
+    state: ServerState,
+) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
 
     loop {
         interval.tick().await;
-        if let Err(e) = fetch_and_upsert(&pool, &client).await {
+        let _cycle = state.begin_cycle().await;
+        if !flags.is_enabled(Feature::SpotIngest) {
+            continue;
+        }
+        let result = fetch_and_upsert(
+            &pool,
+            &client,
+            &program_slug,
+            comment_max_len,
+            max_spots_per_cycle,
+            &respot_ttl,
+            &callsign_filter,
+            frequency_range,
+        )
+        .await;
+        state.record_fetch(SpotSource::Sota.as_str(), result.is_ok());
+        if let Err(e) = result {
             tracing::error!("SOTA aggregator error: {}", e);
             metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "sota_spots")
                 .increment(1);
@@ -43,29 +92,30 @@ pub async fn poll_loop(pool: PgPool, client: reqwest::Client) {
     }
 }
 
-async fn fetch_and_upsert(
+/// Run one poll: fetch, cap, drop callsigns the filter rejects and
+/// frequencies out of range, and upsert. Returns how many spots were upserted.
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_and_upsert(
     pool: &PgPool,
-    client: &reqwest::Client,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let spots: Vec<SotaSpot> = client
-        .get(SOTA_SPOTS_URL)
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await?;
+    client: &HttpClient,
+    program_slug: &str,
+    comment_max_len: usize,
+    max_spots_per_cycle: usize,
+    respot_ttl: &RespotTtl,
+    callsign_filter: &CallsignFilter,
+    frequency_range: FrequencyRange,
+) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+    let mut spots = fetch_spots(client).await?;
 
     tracing::debug!("SOTA: fetched {} spots", spots.len());
+    limit_spots(&mut spots, max_spots_per_cycle);
 
-    let mut upserted = 0u32;
+    let mut batch = Vec::with_capacity(spots.len());
+    let mut filtered = 0u32;
     for spot in &spots {
-        match map_spot(spot) {
-            Ok(agg) => match upsert_aggregated_spot(pool, &agg).await {
-                Ok(_) => upserted += 1,
-                Err(e) => {
-                    tracing::warn!("SOTA upsert error for {}: {}", spot.activator_callsign, e);
-                }
-            },
+        match map_spot(spot, program_slug, comment_max_len) {
+            Ok(agg) if !callsign_filter.allows(&agg.callsign) => filtered += 1,
+            Ok(agg) => batch.push(agg),
             Err(e) => {
                 if spot.frequency.is_empty() {
                     tracing::debug!("SOTA spot id={}: empty frequency, skipping", spot.id);
@@ -76,17 +126,112 @@ async fn fetch_and_upsert(
         }
     }
 
+    let out_of_range = frequency_range.retain(&mut batch, "SOTA");
+    let duplicates = super::dedupe_by_external_id(&mut batch);
+
+    let respot_expires_at = respot_ttl.respot_expiry(&SpotSource::Sota, Utc::now());
+    let mut upserted = 0u32;
+    for agg in &batch {
+        match upsert_aggregated_spot(pool, agg, respot_expires_at).await {
+            Ok(_) => upserted += 1,
+            Err(e) => tracing::warn!("SOTA upsert error for {}: {}", agg.callsign, e),
+        }
+    }
+
+    if filtered > 0 {
+        tracing::debug!("SOTA: skipped {} spots by callsign filter", filtered);
+    }
+    if out_of_range > 0 {
+        tracing::debug!(
+            "SOTA: skipped {} spots outside the frequency range",
+            out_of_range
+        );
+    }
+    if duplicates > 0 {
+        tracing::debug!("SOTA: skipped {} duplicate spots in the batch", duplicates);
+    }
     tracing::debug!("SOTA: upserted {}/{} spots", upserted, spots.len());
-    Ok(())
+    Ok(upserted)
+}
+
+/// Longest body excerpt logged when the endpoint doesn't return JSON.
+const BODY_SNIPPET_LEN: usize = 200;
+
+/// Fetch and decode the spots. The endpoint sometimes answers 200 with an
+/// empty body or an HTML error page; that is logged and read as no spots, so
+/// the cycle doesn't count as a failed fetch. JSON of the wrong shape is
+/// still an error.
+async fn fetch_spots(client: &HttpClient) -> Result<Vec<SotaSpot>, HttpError> {
+    let response = client
+        .get("sota_spots", SOTA_SPOTS_URL, &FETCH_POLICY)
+        .await?;
+    let body = match serde_json::from_slice::<serde_json::Value>(&response.body) {
+        Ok(body) => body,
+        Err(_) => {
+            tracing::warn!(
+                "SOTA: response is not JSON, treating as no spots: {:?}",
+                body_snippet(&response.body)
+            );
+            return Ok(Vec::new());
+        }
+    };
+    serde_json::from_value(body).map_err(|e| HttpError::Decode {
+        upstream: "sota_spots",
+        message: e.to_string(),
+    })
 }
 
-fn map_spot(spot: &SotaSpot) -> Result<AggregatedSpot, Box<dyn std::error::Error + Send + Sync>> {
+/// The start of `body` for logging, with whitespace runs collapsed.
+fn body_snippet(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(BODY_SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &collapsed[..end]),
+        None => collapsed,
+    }
+}
+
+/// Fetch the endpoint as plain JSON for the admin raw endpoint, with the same
+/// upstream label and retry policy as a poll.
+pub async fn fetch_raw(
+    client: &HttpClient,
+    program_slug: &str,
+    comment_max_len: usize,
+) -> Result<RawFetch, HttpError> {
+    let body = client
+        .get_json("sota_spots", SOTA_SPOTS_URL, &FETCH_POLICY)
+        .await?;
+    Ok(RawFetch::new(SOTA_SPOTS_URL, body, |spot: &SotaSpot| {
+        map_spot(spot, program_slug, comment_max_len).is_ok()
+    }))
+}
+
+/// Cap an oversized response at the newest `max` spots so an upstream anomaly
+/// can't flood the upsert loop.
+fn limit_spots(spots: &mut Vec<SotaSpot>, max: usize) {
+    let dropped = super::keep_newest_spots(spots, max, |spot| {
+        super::parse_upstream_time(&spot.time_stamp).ok()
+    });
+    if dropped > 0 {
+        tracing::warn!(
+            "SOTA: response had {} spots, over the limit of {}; dropped the {} oldest",
+            max + dropped,
+            max,
+            dropped
+        );
+    }
+}
+
+fn map_spot(
+    spot: &SotaSpot,
+    program_slug: &str,
+    comment_max_len: usize,
+) -> Result<AggregatedSpot, Box<dyn std::error::Error + Send + Sync>> {
     // Frequency is in MHz — convert to kHz
     let frequency_khz: f64 = spot.frequency.parse::<f64>()? * 1000.0;
 
-    // timeStamp is UTC but has no Z suffix
-    let spotted_at = NaiveDateTime::parse_from_str(&spot.time_stamp, "%Y-%m-%dT%H:%M:%S")
-        .map(|naive| naive.and_utc())?;
+    // timeStamp is UTC but has no Z suffix; an explicit offset is converted
+    let spotted_at = super::parse_upstream_time(&spot.time_stamp)?;
 
     let expires_at = spotted_at + Duration::minutes(30);
 
@@ -94,11 +239,12 @@ fn map_spot(spot: &SotaSpot) -> Result<AggregatedSpot, Box<dyn std::error::Error
 
     Ok(AggregatedSpot {
         callsign: spot.activator_callsign.clone(),
-        program_slug: Some("sota".to_string()),
+        program_slug: Some(program_slug.to_string()),
         source: SpotSource::Sota,
         external_id: spot.id.to_string(),
         frequency_khz,
-        mode: spot.mode.clone(),
+        mode: normalize_mode(spot.mode.as_deref().unwrap_or_default()).to_string(),
+        raw_mode: spot.mode.clone(),
         reference: Some(reference),
         reference_name: spot.summit_details.clone(),
         spotter: Some(spot.callsign.clone()),
@@ -106,10 +252,94 @@ fn map_spot(spot: &SotaSpot) -> Result<AggregatedSpot, Box<dyn std::error::Error
         location_desc: None,
         country_code: None,
         state_abbr: None,
-        comments: spot.comments.clone(),
+        comments: spot
+            .comments
+            .as_deref()
+            .and_then(|c| sanitize_comment(c, comment_max_len)),
         snr: None,
         wpm: None,
         spotted_at,
         expires_at,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::mock::{self, MockTransport};
+
+    #[test]
+    fn test_map_spot_uses_program_slug() {
+        let spot: SotaSpot = serde_json::from_value(serde_json::json!({
+            "id": 1, "callsign": "W1AW", "activatorCallsign": "K1ABC", "frequency": "14.062",
+            "mode": "CW", "associationCode": "W7W", "summitCode": "LC-001",
+            "timeStamp": "2025-06-01T10:00:00"
+        }))
+        .unwrap();
+        let mapped = map_spot(&spot, "summits", 200).unwrap();
+        assert_eq!(mapped.program_slug.as_deref(), Some("summits"));
+        assert_eq!(mapped.reference.as_deref(), Some("W7W/LC-001"));
+    }
+
+    #[test]
+    fn test_limit_spots_drops_oldest_and_unparseable() {
+        let response = serde_json::json!([
+            { "id": 1, "callsign": "W1AW", "activatorCallsign": "K1ABC", "frequency": "14.062",
+              "mode": "CW", "associationCode": "W7W", "summitCode": "LC-001",
+              "timeStamp": "2025-06-01T10:00:00" },
+            { "id": 2, "callsign": "W1AW", "activatorCallsign": "K2ABC", "frequency": "7.032",
+              "mode": "CW", "associationCode": "W7W", "summitCode": "LC-002",
+              "timeStamp": "not a time" },
+            { "id": 3, "callsign": "W1AW", "activatorCallsign": "K3ABC", "frequency": "10.118",
+              "mode": "CW", "associationCode": "W7W", "summitCode": "LC-003",
+              "timeStamp": "2025-06-01T10:05:00" },
+        ]);
+        let mut spots: Vec<SotaSpot> = serde_json::from_value(response).unwrap();
+        limit_spots(&mut spots, 1);
+
+        assert_eq!(spots.len(), 1);
+        assert_eq!(spots[0].id, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_spots_does_not_retry_not_found() {
+        let (client, transport) = mock::client(MockTransport::new([MockTransport::status(404)]));
+        let err = fetch_spots(&client).await.unwrap_err();
+        assert!(matches!(err, HttpError::Status { status: 404, .. }));
+        assert_eq!(transport.calls(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_spots_reads_empty_and_html_bodies_as_no_spots() {
+        let html = "<!DOCTYPE html>\n<html><body><h1>Service Unavailable</h1></body></html>";
+        for body in ["", "  \n", html] {
+            let (client, transport) =
+                mock::client(MockTransport::new([MockTransport::body(200, body)]));
+            let spots = fetch_spots(&client).await.unwrap();
+            assert!(spots.is_empty(), "body {body:?}");
+            assert_eq!(transport.calls(), 1);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_spots_rejects_json_of_the_wrong_shape() {
+        let (client, _) = mock::client(MockTransport::new([MockTransport::body(
+            200,
+            r#"{"error": "maintenance"}"#,
+        )]));
+        let err = fetch_spots(&client).await.unwrap_err();
+        assert!(matches!(err, HttpError::Decode { .. }));
+    }
+
+    #[test]
+    fn body_snippet_collapses_whitespace_and_truncates() {
+        assert_eq!(
+            body_snippet(b"<html>\n  <body>oops</body>"),
+            "<html> <body>oops</body>"
+        );
+        let long = "x".repeat(BODY_SNIPPET_LEN + 50);
+        let snippet = body_snippet(long.as_bytes());
+        assert_eq!(snippet.len(), BODY_SNIPPET_LEN + 3);
+        assert!(snippet.ends_with("..."));
+    }
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::db::upsert_aggregated_spot;
+use crate::feature_flags::{Feature, FeatureFlags};
+use crate::http_client::{HttpClient, HttpError, RetryPolicy};
+use crate::metrics as app_metrics;
+use crate::models::spot::{sanitize_comment, AggregatedSpot, RespotTtl, SpotSource};
+use crate::server::ServerState;
+
+use super::callsign_filter::CallsignFilter;
+use super::frequency_range::FrequencyRange;
+use super::RawFetch;
+
+/// Fits within the 120s poll interval even when every attempt times out.
+const FETCH_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff: std::time::Duration::from_secs(1),
+    max_backoff: std::time::Duration::from_secs(5),
+    timeout: std::time::Duration::from_secs(30),
+};
+
+/// Reports from the last 10 minutes on all bands.
+const WSPR_SPOTS_URL: &str =
+    "https://www.wsprnet.org/drupal/wsprnet/spots/json?band=All&minutes=10";
+
+/// Upstream JSON shape from the wsprnet spots endpoint. Every value arrives
+/// as a string.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct WsprReport {
+    spotnum: String,
+    /// Unix timestamp (seconds) of the 2-minute WSPR slot.
+    date: String,
+    /// The receiving station, stored as the spotter.
+    reporter: String,
+    #[serde(default)]
+    reporter_grid: Option<String>,
+    #[serde(rename = "dB")]
+    db: String,
+    /// Frequency in **MHz** (must multiply by 1000 for kHz).
+    #[serde(rename = "MHz")]
+    mhz: String,
+    /// The transmitting station.
+    call_sign: String,
+    #[serde(default)]
+    grid: Option<String>,
+    /// Transmit power in dBm.
+    #[serde(default)]
+    power: Option<String>,
+}
+
+/// Poll wsprnet reports every 120 seconds, once per WSPR cycle.
+/// Cycles are skipped while the `spot_ingest` feature flag is off.
+#[allow(clippy::too_many_arguments)]
+pub async fn poll_loop(
+    pool: PgPool,
+    client: HttpClient,
+    comment_max_len: usize,
+    max_spots_per_cycle: usize,
+    respot_ttl: RespotTtl,
+    callsign_filter: CallsignFilter,
+    frequency_range: FrequencyRange,
+    flags: FeatureFlags,
+    state: ServerState,
+) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(120));
+
+    loop {
+        interval.tick().await;
+        let _cycle = state.begin_cycle().await;
+        if !flags.is_enabled(Feature::SpotIngest) {
+            continue;
+        }
+        if let Err(e) = fetch_and_upsert(
+            &pool,
+            &client,
+            comment_max_len,
+            max_spots_per_cycle,
+            &respot_ttl,
+            &callsign_filter,
+            frequency_range,
+        )
+        .await
+        {
+            tracing::error!("WSPR aggregator error: {}", e);
+            metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "wspr_spots")
+                .increment(1);
+        }
+    }
+}
+
+/// Run one poll: fetch, cap, drop callsigns the filter rejects and
+/// frequencies out of range, and upsert. Returns how many spots were upserted.
+pub async fn fetch_and_upsert(
+    pool: &PgPool,
+    client: &HttpClient,
+    comment_max_len: usize,
+    max_spots_per_cycle: usize,
+    respot_ttl: &RespotTtl,
+    callsign_filter: &CallsignFilter,
+    frequency_range: FrequencyRange,
+) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+    let mut reports = fetch_reports(client).await?;
+
+    tracing::debug!("WSPR: fetched {} reports", reports.len());
+    limit_reports(&mut reports, max_spots_per_cycle);
+
+    let mut batch = Vec::with_capacity(reports.len());
+    let mut filtered = 0u32;
+    for report in &reports {
+        match map_report(report, comment_max_len) {
+            Ok(agg) if !callsign_filter.allows(&agg.callsign) => filtered += 1,
+            Ok(agg) => batch.push(agg),
+            Err(e) => {
+                tracing::warn!("WSPR parse error spotnum={}: {}", report.spotnum, e);
+            }
+        }
+    }
+
+    let out_of_range = frequency_range.retain(&mut batch, "WSPR");
+    let duplicates = super::dedupe_by_external_id(&mut batch);
+
+    let respot_expires_at = respot_ttl.respot_expiry(&SpotSource::Wspr, Utc::now());
+    let mut upserted = 0u32;
+    for agg in &batch {
+        match upsert_aggregated_spot(pool, agg, respot_expires_at).await {
+            Ok(_) => upserted += 1,
+            Err(e) => tracing::warn!("WSPR upsert error for {}: {}", agg.callsign, e),
+        }
+    }
+
+    if filtered > 0 {
+        tracing::debug!("WSPR: skipped {} reports by callsign filter", filtered);
+    }
+    if out_of_range > 0 {
+        tracing::debug!(
+            "WSPR: skipped {} reports outside the frequency range",
+            out_of_range
+        );
+    }
+    if duplicates > 0 {
+        tracing::debug!(
+            "WSPR: skipped {} duplicate reports in the batch",
+            duplicates
+        );
+    }
+    tracing::debug!("WSPR: upserted {}/{} reports", upserted, reports.len());
+    Ok(upserted)
+}
+
+async fn fetch_reports(client: &HttpClient) -> Result<Vec<WsprReport>, HttpError> {
+    client
+        .get_json("wspr_spots", WSPR_SPOTS_URL, &FETCH_POLICY)
+        .await
+}
+
+/// Fetch the endpoint as plain JSON for the admin raw endpoint, with the same
+/// upstream label and retry policy as a poll.
+pub async fn fetch_raw(client: &HttpClient, comment_max_len: usize) -> Result<RawFetch, HttpError> {
+    let body = client
+        .get_json("wspr_spots", WSPR_SPOTS_URL, &FETCH_POLICY)
+        .await?;
+    Ok(RawFetch::new(
+        WSPR_SPOTS_URL,
+        body,
+        |report: &WsprReport| map_report(report, comment_max_len).is_ok(),
+    ))
+}
+
+/// Cap an oversized response at the newest `max` reports so an upstream
+/// anomaly can't flood the upsert loop.
+fn limit_reports(reports: &mut Vec<WsprReport>, max: usize) {
+    let dropped = super::keep_newest_spots(reports, max, |report| report.date.parse::<i64>().ok());
+    if dropped > 0 {
+        tracing::warn!(
+            "WSPR: response had {} reports, over the limit of {}; dropped the {} oldest",
+            max + dropped,
+            max,
+            dropped
+        );
+    }
+}
+
+fn map_report(
+    report: &WsprReport,
+    comment_max_len: usize,
+) -> Result<AggregatedSpot, Box<dyn std::error::Error + Send + Sync>> {
+    // Frequency is in MHz — convert to kHz
+    let frequency_khz: f64 = report.mhz.parse::<f64>()? * 1000.0;
+
+    let spotted_at = DateTime::from_timestamp(report.date.parse()?, 0)
+        .ok_or_else(|| format!("timestamp out of range: {}", report.date))?;
+
+    let expires_at = spotted_at + Duration::minutes(30);
+
+    // Transmit power and grid, e.g. "23 dBm FN42"
+    let details = [
+        report.power.as_deref().map(|p| format!("{p} dBm")),
+        report.grid.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    Ok(AggregatedSpot {
+        callsign: report.call_sign.clone(),
+        program_slug: None,
+        source: SpotSource::Wspr,
+        external_id: report.spotnum.clone(),
+        frequency_khz,
+        // WSPR reports carry no mode of their own
+        mode: "WSPR".to_string(),
+        raw_mode: None,
+        reference: None,
+        reference_name: None,
+        spotter: Some(report.reporter.clone()),
+        spotter_grid: report.reporter_grid.clone().filter(|g| !g.is_empty()),
+        location_desc: None,
+        country_code: None,
+        state_abbr: None,
+        comments: sanitize_comment(&details, comment_max_len),
+        snr: Some(report.db.parse()?),
+        wpm: None,
+        spotted_at,
+        expires_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::mock::{self, MockTransport};
+
+    fn report(spotnum: &str, date: &str) -> serde_json::Value {
+        serde_json::json!({
+            "Spotnum": spotnum, "Date": date, "Reporter": "DL1ABC", "ReporterGrid": "JO62",
+            "dB": "-21", "MHz": "14.097093", "CallSign": "K1ABC", "Grid": "FN42",
+            "Power": "23", "Drift": "0", "distance": "6012", "azimuth": "48", "Band": "14",
+            "version": "2.6.1", "code": "1"
+        })
+    }
+
+    fn parse(value: serde_json::Value) -> WsprReport {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_map_report_converts_units_and_fields() {
+        let spot = map_report(&parse(report("4471837721", "1748772120")), 120).unwrap();
+
+        assert_eq!(spot.source, SpotSource::Wspr);
+        assert_eq!(spot.external_id, "4471837721");
+        assert_eq!(spot.callsign, "K1ABC");
+        assert_eq!(spot.mode, "WSPR");
+        assert!((spot.frequency_khz - 14097.093).abs() < 1e-6);
+        assert_eq!(spot.snr, Some(-21));
+        assert_eq!(spot.spotter.as_deref(), Some("DL1ABC"));
+        assert_eq!(spot.spotter_grid.as_deref(), Some("JO62"));
+        assert_eq!(spot.comments.as_deref(), Some("23 dBm FN42"));
+        assert_eq!(spot.program_slug, None);
+        assert_eq!(spot.spotted_at.to_rfc3339(), "2025-06-01T10:02:00+00:00");
+        assert_eq!(spot.expires_at - spot.spotted_at, Duration::minutes(30));
+    }
+
+    #[test]
+    fn test_map_report_without_optional_fields() {
+        let mut value = report("1", "1748772120");
+        let fields = value.as_object_mut().unwrap();
+        fields.remove("Power");
+        fields.remove("Grid");
+        fields.insert("ReporterGrid".into(), "".into());
+
+        let spot = map_report(&parse(value), 120).unwrap();
+        assert_eq!(spot.comments, None);
+        assert_eq!(spot.spotter_grid, None);
+    }
+
+    #[test]
+    fn test_map_report_rejects_bad_numbers() {
+        let mut bad_freq = report("1", "1748772120");
+        bad_freq["MHz"] = "".into();
+        assert!(map_report(&parse(bad_freq), 120).is_err());
+
+        let mut bad_snr = report("1", "1748772120");
+        bad_snr["dB"] = "loud".into();
+        assert!(map_report(&parse(bad_snr), 120).is_err());
+
+        assert!(map_report(&parse(report("1", "yesterday")), 120).is_err());
+    }
+
+    #[test]
+    fn test_limit_reports_drops_oldest_and_unparseable() {
+        let mut reports = vec![
+            parse(report("1", "1748772000")),
+            parse(report("2", "not a time")),
+            parse(report("3", "1748772120")),
+        ];
+        limit_reports(&mut reports, 1);
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].spotnum, "3");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_reports_retries_server_errors() {
+        let body = serde_json::Value::Array(vec![report("1", "1748772120")]).to_string();
+        let (client, transport) = mock::client(MockTransport::new([
+            MockTransport::status(503),
+            MockTransport::body(200, &body),
+        ]));
+        let reports = fetch_reports(&client).await.unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(transport.calls(), 2);
+    }
+}
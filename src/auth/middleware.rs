@@ -5,18 +5,29 @@ use axum::{
 };
 use sqlx::{FromRow, PgPool};
 
+use crate::db;
 use crate::error::AppError;
 
+use super::is_valid_integration_key_format;
+
 #[derive(Debug, Clone)]
 pub struct AuthContext {
     pub callsign: String,
     pub participant_id: uuid::Uuid,
 }
 
+/// The partner behind an integrations request.
+#[derive(Debug, Clone)]
+pub struct IntegrationContext {
+    pub key_id: uuid::Uuid,
+    pub partner: String,
+}
+
 #[derive(Debug, FromRow)]
 struct ParticipantRow {
     id: uuid::Uuid,
     callsign: String,
+    suspended_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 pub async fn optional_auth(
@@ -63,17 +74,26 @@ pub async fn require_auth(
 async fn validate_token(pool: &PgPool, token: &str) -> Result<Option<AuthContext>, AppError> {
     let participant = sqlx::query_as::<_, ParticipantRow>(
         r#"
-        UPDATE participants
+        UPDATE participants p
         SET last_seen_at = now()
-        WHERE device_token = $1
-        RETURNING id, callsign
+        WHERE p.device_token = $1
+        RETURNING p.id, p.callsign,
+            (SELECT u.suspended_at FROM users u WHERE u.callsign = p.callsign) AS suspended_at
         "#,
     )
     .bind(token)
     .fetch_optional(pool)
     .await?;
 
-    Ok(participant.map(|p| AuthContext {
+    let Some(p) = participant else {
+        return Ok(None);
+    };
+
+    if p.suspended_at.is_some() {
+        return Err(AppError::AccountSuspended);
+    }
+
+    Ok(Some(AuthContext {
         callsign: p.callsign,
         participant_id: p.id,
     }))
@@ -101,3 +121,33 @@ pub async fn require_admin(
 
     Ok(next.run(req).await)
 }
+
+/// Accept only a known, unrevoked partner API key. Malformed keys are refused
+/// without a database lookup.
+pub async fn require_integration_key(
+    State(pool): State<PgPool>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let auth_header = req
+        .headers()
+        .get("authorization")
+        .ok_or(AppError::InvalidToken)?;
+
+    let auth_str = auth_header.to_str().map_err(|_| AppError::InvalidToken)?;
+
+    let key = auth_str
+        .strip_prefix("Bearer ")
+        .filter(|key| is_valid_integration_key_format(key))
+        .ok_or(AppError::InvalidToken)?;
+
+    let key = db::use_integration_key(&pool, key)
+        .await?
+        .ok_or(AppError::InvalidToken)?;
+
+    req.extensions_mut().insert(IntegrationContext {
+        key_id: key.id,
+        partner: key.partner,
+    });
+    Ok(next.run(req).await)
+}
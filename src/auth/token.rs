@@ -1,10 +1,11 @@
 use rand::Rng;
 
 const TOKEN_PREFIX: &str = "fd_";
+const INTEGRATION_KEY_PREFIX: &str = "ik_";
 const TOKEN_LENGTH: usize = 32;
 const TOKEN_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
 
-pub fn generate_device_token() -> String {
+fn generate_with_prefix(prefix: &str) -> String {
     let mut rng = rand::thread_rng();
     let token: String = (0..TOKEN_LENGTH)
         .map(|_| {
@@ -12,18 +13,34 @@ pub fn generate_device_token() -> String {
             TOKEN_CHARS[idx] as char
         })
         .collect();
-    format!("{}{}", TOKEN_PREFIX, token)
+    format!("{}{}", prefix, token)
 }
 
-#[allow(dead_code)]
-pub fn is_valid_token_format(token: &str) -> bool {
-    if !token.starts_with(TOKEN_PREFIX) {
+pub fn generate_device_token() -> String {
+    generate_with_prefix(TOKEN_PREFIX)
+}
+
+/// A partner API key for the integrations endpoints.
+pub fn generate_integration_key() -> String {
+    generate_with_prefix(INTEGRATION_KEY_PREFIX)
+}
+
+fn has_token_format(token: &str, prefix: &str) -> bool {
+    let Some(suffix) = token.strip_prefix(prefix) else {
         return false;
-    }
-    let suffix = &token[TOKEN_PREFIX.len()..];
+    };
     suffix.len() == TOKEN_LENGTH && suffix.chars().all(|c| c.is_ascii_alphanumeric())
 }
 
+#[allow(dead_code)]
+pub fn is_valid_token_format(token: &str) -> bool {
+    has_token_format(token, TOKEN_PREFIX)
+}
+
+pub fn is_valid_integration_key_format(key: &str) -> bool {
+    has_token_format(key, INTEGRATION_KEY_PREFIX)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +68,13 @@ mod tests {
             "xx_abcdefghijklmnopqrstuvwxyz123456"
         ));
     }
+
+    #[test]
+    fn integration_keys_have_their_own_prefix() {
+        let key = generate_integration_key();
+        assert!(is_valid_integration_key_format(&key));
+        assert!(!is_valid_token_format(&key));
+        assert!(!is_valid_integration_key_format(&generate_device_token()));
+        assert!(!is_valid_integration_key_format("ik_short"));
+    }
 }
@@ -0,0 +1,205 @@
+//! Who each user is blocked with, for the friend, spot, and search handlers.
+//!
+//! Block lists are read through [`BlockCache`], which keeps each user's list
+//! for [`CACHE_TTL`]. Blocking or unblocking drops both users' entries on this
+//! instance; other instances see the change once their entries expire.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::db;
+use crate::error::AppError;
+use crate::models::User;
+
+/// How long a user's block list is reused before it is read again.
+pub const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Users someone has blocked or been blocked by.
+#[derive(Debug, Default)]
+pub struct BlockedUsers {
+    pub ids: Vec<Uuid>,
+    pub callsigns: Vec<String>,
+}
+
+impl BlockedUsers {
+    pub fn contains(&self, user_id: Uuid) -> bool {
+        self.ids.contains(&user_id)
+    }
+}
+
+impl From<Vec<User>> for BlockedUsers {
+    fn from(users: Vec<User>) -> Self {
+        Self {
+            ids: users.iter().map(|u| u.id).collect(),
+            callsigns: users.into_iter().map(|u| u.callsign).collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Entries {
+    lists: HashMap<Uuid, (Arc<BlockedUsers>, Instant)>,
+    /// Bumped on every invalidation, so a read that raced one isn't cached.
+    generation: u64,
+}
+
+/// Per-user block lists. Cheap to clone.
+#[derive(Clone)]
+pub struct BlockCache {
+    entries: Arc<Mutex<Entries>>,
+    ttl: Duration,
+}
+
+impl BlockCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::default(),
+            ttl,
+        }
+    }
+
+    /// Whether either user has blocked the other.
+    pub async fn is_blocked(&self, pool: &PgPool, a: Uuid, b: Uuid) -> Result<bool, AppError> {
+        Ok(self.blocked_with(pool, a).await?.contains(b))
+    }
+
+    /// Everyone `user_id` has blocked or been blocked by.
+    pub async fn blocked_with(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Arc<BlockedUsers>, AppError> {
+        let generation = {
+            let mut entries = self.entries.lock().unwrap();
+            match entries.lists.get(&user_id) {
+                Some((blocked, read_at)) if read_at.elapsed() < self.ttl => {
+                    return Ok(blocked.clone());
+                }
+                Some(_) => {
+                    entries.lists.remove(&user_id);
+                }
+                None => {}
+            }
+            entries.generation
+        };
+
+        let blocked = Arc::new(BlockedUsers::from(
+            db::get_blocked_with(pool, user_id).await?,
+        ));
+        self.store(user_id, generation, blocked.clone());
+        Ok(blocked)
+    }
+
+    /// The block list of an authenticated caller; empty for anonymous callers
+    /// and callsigns without a user record.
+    pub async fn for_viewer(
+        &self,
+        pool: &PgPool,
+        auth: Option<&AuthContext>,
+    ) -> Result<Arc<BlockedUsers>, AppError> {
+        let Some(auth) = auth else {
+            return Ok(Arc::default());
+        };
+        match db::get_user_by_callsign(pool, &auth.callsign).await? {
+            Some(user) => self.blocked_with(pool, user.id).await,
+            None => Ok(Arc::default()),
+        }
+    }
+
+    /// Forget both users' lists after a block between them changed.
+    pub fn invalidate(&self, a: Uuid, b: Uuid) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.lists.remove(&a);
+        entries.lists.remove(&b);
+        entries.generation += 1;
+    }
+
+    /// Cache a list read at `generation`, unless an invalidation came since.
+    /// Expired lists are swept at the same time.
+    fn store(&self, user_id: Uuid, generation: u64, blocked: Arc<BlockedUsers>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.generation != generation {
+            return;
+        }
+        let ttl = self.ttl;
+        entries
+            .lists
+            .retain(|_, (_, read_at)| read_at.elapsed() < ttl);
+        entries.lists.insert(user_id, (blocked, Instant::now()));
+    }
+
+    #[cfg(test)]
+    fn cached(&self, user_id: Uuid) -> Option<Arc<BlockedUsers>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .lists
+            .get(&user_id)
+            .filter(|(_, read_at)| read_at.elapsed() < self.ttl)
+            .map(|(blocked, _)| blocked.clone())
+    }
+
+    #[cfg(test)]
+    fn generation(&self) -> u64 {
+        self.entries.lock().unwrap().generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn blocked(ids: &[Uuid]) -> Arc<BlockedUsers> {
+        let users = ids
+            .iter()
+            .map(|&id| User {
+                id,
+                callsign: format!("K{}", &id.simple().to_string()[..4]).to_uppercase(),
+                created_at: Utc::now(),
+            })
+            .collect::<Vec<_>>();
+        Arc::new(BlockedUsers::from(users))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lists_expire_and_are_dropped_on_invalidation() {
+        let cache = BlockCache::new(CACHE_TTL);
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        cache.store(a, cache.generation(), blocked(&[b]));
+        cache.store(c, cache.generation(), blocked(&[]));
+        let list = cache.cached(a).unwrap();
+        assert!(list.contains(b));
+        assert!(!list.contains(c));
+        assert_eq!(list.callsigns.len(), 1);
+
+        // Blocking between A and B drops A's list but leaves C's
+        cache.invalidate(a, b);
+        assert!(cache.cached(a).is_none());
+        assert!(cache.cached(c).is_some());
+
+        tokio::time::advance(CACHE_TTL).await;
+        assert!(cache.cached(c).is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn read_that_raced_an_invalidation_is_not_cached() {
+        let cache = BlockCache::new(CACHE_TTL);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // A read starts, then a block lands before its result is stored
+        let generation = cache.generation();
+        cache.invalidate(a, b);
+        cache.store(a, generation, blocked(&[]));
+        assert!(cache.cached(a).is_none());
+
+        cache.store(a, cache.generation(), blocked(&[b]));
+        assert!(cache.cached(a).unwrap().contains(b));
+    }
+}
@@ -1,18 +1,93 @@
 // src/config.rs
 use std::env;
+use std::str::FromStr;
+
+use serde::Serialize;
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Config {
     pub database_url: String,
     pub admin_token: String,
     pub port: u16,
+    pub metrics_port: Option<u16>,
+    /// Accept HTTP/2 with prior knowledge (h2c) alongside HTTP/1.1.
+    pub http2_enabled: bool,
+    /// Allow HTTP/1.1 connections to be reused for multiple requests.
+    pub http_keep_alive_enabled: bool,
+    /// Close HTTP/1.1 connections idle (or sending headers) longer than this; 0 disables.
+    pub http_idle_timeout_secs: u64,
+    /// Interval between HTTP/2 PING frames on idle connections; 0 disables.
+    pub http2_keep_alive_interval_secs: u64,
+    /// Close an HTTP/2 connection when a keep-alive PING isn't acknowledged in time.
+    pub http2_keep_alive_timeout_secs: u64,
+    /// Queries running longer than this are logged as slow; 0 disables.
+    pub db_slow_query_ms: u64,
+    /// Requests taking longer than this log a warning with route and params; 0 disables.
+    pub slow_request_ms: u64,
+    /// Response headers browsers may read cross-origin (`Access-Control-Expose-Headers`).
+    pub cors_expose_headers: Vec<String>,
+    /// How long browsers may cache a CORS preflight; 0 omits `Access-Control-Max-Age`.
+    pub cors_max_age_secs: u64,
     pub base_url: Option<String>,
+    /// Path every route is nested under (e.g. `/challenges`); empty mounts at the root.
+    pub route_prefix: String,
     pub invite_base_url: String,
     pub invite_expiry_days: i64,
+    /// Friendships one user may have; accepting a request past it is refused.
+    pub max_friends: i64,
+    /// How many days back the first feed page reaches; 0 for no limit.
+    pub feed_default_lookback_days: i32,
+    /// Activities a user may report per hour; 0 for no limit.
+    pub max_activities_per_hour: i64,
+    /// Add `docUrl` links to error bodies.
+    pub error_doc_links_enabled: bool,
+    /// Docs root that error links point into, as `<base>/errors#<code>`.
+    pub error_doc_base_url: String,
     pub spots_enabled: bool,
+    /// Default for the `clubs` runtime feature flag when its row is first seeded.
+    pub clubs_enabled: bool,
     pub pota_aggregator_enabled: bool,
     pub sota_aggregator_enabled: bool,
+    pub wspr_aggregator_enabled: bool,
+    /// Minimum time between admin-triggered polls of one aggregator source.
+    pub manual_poll_cooldown_secs: u64,
+    /// Fail startup (instead of warning) when an enabled aggregator's program is missing or inactive.
+    pub strict_program_check: bool,
+    /// Collapse POTA and SOTA spots of one station on one frequency in the spot list.
+    pub pota_sota_merge_enabled: bool,
+    /// How far apart in time a POTA and a SOTA spot may be and still merge.
+    pub pota_sota_merge_window_mins: i64,
+    pub spot_comment_max_len: usize,
+    /// POTA activator spot endpoints polled each cycle, primary first. Spots
+    /// are merged and deduped by spot ID.
+    pub pota_spot_urls: Vec<String>,
+    /// Upper bound on spots processed from one POTA endpoint per poll; the
+    /// newest are kept.
+    pub pota_max_spots_per_cycle: usize,
+    /// Upstream POTA spot history paged through by the admin backfill; the
+    /// backfill is unavailable when unset.
+    pub pota_backfill_url: Option<String>,
+    /// Pause between backfill page fetches, to stay under upstream rate limits.
+    pub pota_backfill_page_delay_ms: u64,
+    /// Upper bound on spots processed from one SOTA poll; the newest are kept.
+    pub sota_max_spots_per_cycle: usize,
+    /// Program that POTA spots reference, for deployments naming it other than `pota`.
+    pub pota_program_slug: String,
+    /// Program that SOTA spots reference.
+    pub sota_program_slug: String,
+    /// Upper bound on reports processed from one WSPR poll; the newest are kept.
+    pub wspr_max_spots_per_cycle: usize,
+    pub public_spot_redacted_fields: Vec<String>,
+    /// Per-program patterns deriving a self-spot's country from its reference.
+    pub reference_country_patterns: crate::models::spot::ReferenceCountryPatterns,
+    /// Per-source minutes a re-spotted spot's expiry is pushed out to.
+    pub spot_respot_ttl: crate::models::spot::RespotTtl,
+    /// Activator callsigns aggregated spots are kept or dropped for.
+    pub aggregator_callsign_filter: crate::aggregators::callsign_filter::CallsignFilter,
+    /// Frequencies aggregated spots must fall within; others are dropped.
+    pub spot_frequency_range: crate::aggregators::frequency_range::FrequencyRange,
     pub pota_stats_aggregator_enabled: bool,
     pub pota_stats_concurrency: usize,
     pub pota_stats_batch_size: i64,
@@ -38,179 +113,405 @@ pub struct Config {
     pub snapshot_dir: String,
     pub snapshot_interval_hours: u64,
     pub snapshot_max_age_hours: u64,
+    pub maintenance_enabled: bool,
+    /// UTC hour (0-23) at which nightly maintenance runs.
+    pub maintenance_hour_utc: u32,
+    /// Telemetry and audit rows older than this are pruned by maintenance.
+    pub maintenance_retention_days: i64,
+    /// Copy expired spots into `spots_archive` before TTL cleanup deletes them.
+    pub archive_spots: bool,
+    /// Archived spots spotted longer ago than this are pruned by maintenance.
+    pub spots_archive_retention_months: i64,
+    /// Archived comments older than this are shortened by maintenance; unset
+    /// keeps them whole.
+    pub spots_archive_comment_retention_days: Option<i32>,
 }
 
 impl Config {
-    pub fn from_env() -> Result<Self, ConfigError> {
-        let database_url =
-            env::var("DATABASE_URL").map_err(|_| ConfigError::Missing("DATABASE_URL"))?;
-
-        let admin_token =
-            env::var("ADMIN_TOKEN").map_err(|_| ConfigError::Missing("ADMIN_TOKEN"))?;
-
-        let port = env::var("PORT")
-            .unwrap_or_else(|_| "8080".to_string())
-            .parse()
-            .map_err(|_| ConfigError::Invalid("PORT must be a number"))?;
-
-        let base_url = env::var("BASE_URL").ok();
-
-        let invite_base_url = env::var("INVITE_BASE_URL")
-            .unwrap_or_else(|_| "https://activities.carrierwave.app".to_string());
-
-        let invite_expiry_days = env::var("INVITE_EXPIRY_DAYS")
-            .unwrap_or_else(|_| "7".to_string())
-            .parse()
-            .map_err(|_| ConfigError::Invalid("INVITE_EXPIRY_DAYS must be a number"))?;
-
-        let spots_enabled = env::var("SPOTS_ENABLED")
-            .unwrap_or_else(|_| "true".to_string())
-            .parse()
-            .unwrap_or(true);
-
-        let pota_aggregator_enabled = env::var("POTA_AGGREGATOR_ENABLED")
-            .unwrap_or_else(|_| "false".to_string())
-            .parse()
-            .unwrap_or(false);
-
-        let sota_aggregator_enabled = env::var("SOTA_AGGREGATOR_ENABLED")
-            .unwrap_or_else(|_| "false".to_string())
-            .parse()
-            .unwrap_or(false);
-
-        let pota_stats_aggregator_enabled = env::var("POTA_STATS_AGGREGATOR_ENABLED")
-            .unwrap_or_else(|_| "false".to_string())
-            .parse()
-            .unwrap_or(false);
-
-        let pota_stats_concurrency: usize = env::var("POTA_STATS_CONCURRENCY")
-            .unwrap_or_else(|_| "3".to_string())
-            .parse()
-            .unwrap_or(3);
-
-        let pota_stats_batch_size: i64 = env::var("POTA_STATS_BATCH_SIZE")
-            .unwrap_or_else(|_| "50".to_string())
-            .parse()
-            .unwrap_or(50);
-
-        let pota_stats_cycle_hours: u64 = env::var("POTA_STATS_CYCLE_HOURS")
-            .unwrap_or_else(|_| "24".to_string())
-            .parse()
-            .unwrap_or(24);
-
-        let park_boundaries_enabled = env::var("PARK_BOUNDARIES_ENABLED")
-            .unwrap_or_else(|_| "false".to_string())
-            .parse()
-            .unwrap_or(false);
-
-        let park_boundaries_batch_size: i64 = env::var("PARK_BOUNDARIES_BATCH_SIZE")
-            .unwrap_or_else(|_| "20".to_string())
-            .parse()
-            .unwrap_or(20);
-
-        let park_boundaries_cycle_hours: u64 = env::var("PARK_BOUNDARIES_CYCLE_HOURS")
-            .unwrap_or_else(|_| "24".to_string())
-            .parse()
-            .unwrap_or(24);
-
-        let park_boundaries_stale_days: i64 = env::var("PARK_BOUNDARIES_STALE_DAYS")
-            .unwrap_or_else(|_| "90".to_string())
-            .parse()
-            .unwrap_or(90);
-
-        let park_boundaries_concurrency: usize = env::var("PARK_BOUNDARIES_CONCURRENCY")
-            .unwrap_or_else(|_| "5".to_string())
-            .parse()
-            .unwrap_or(5);
-
-        let polish_park_boundaries_enabled = env::var("POLISH_PARK_BOUNDARIES_ENABLED")
-            .unwrap_or_else(|_| "false".to_string())
-            .parse()
-            .unwrap_or(false);
-
-        let historic_trails_enabled = env::var("HISTORIC_TRAILS_ENABLED")
-            .unwrap_or_else(|_| "false".to_string())
-            .parse()
-            .unwrap_or(false);
-
-        let polish_park_boundaries_batch_size: i64 = env::var("POLISH_PARK_BOUNDARIES_BATCH_SIZE")
-            .unwrap_or_else(|_| "20".to_string())
-            .parse()
-            .unwrap_or(20);
+    pub fn from_env() -> Result<Self, Vec<ConfigError>> {
+        Self::from_lookup(|var| env::var(var).ok())
+    }
+
+    /// Build a config from any variable source, collecting every missing,
+    /// invalid, or conflicting setting instead of stopping at the first.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, Vec<ConfigError>> {
+        let mut vars = Vars {
+            lookup: &lookup,
+            errors: Vec::new(),
+        };
+
+        let database_url = vars.required("DATABASE_URL");
+
+        let admin_token = vars.required("ADMIN_TOKEN");
+
+        let port: u16 = vars.parse("PORT", 8080);
+
+        let metrics_port: Option<u16> = vars.parse_optional("METRICS_PORT");
+
+        let http2_enabled = vars.flag("HTTP2_ENABLED", false);
+
+        let http_keep_alive_enabled = vars.flag("HTTP_KEEPALIVE_ENABLED", true);
+
+        // Longer than the usual 60s load balancer idle timeout so the LB closes first
+        let http_idle_timeout_secs: u64 = vars.parse("HTTP_IDLE_TIMEOUT_SECS", 75);
+
+        let http2_keep_alive_interval_secs: u64 = vars.parse("HTTP2_KEEPALIVE_INTERVAL_SECS", 20);
+
+        let http2_keep_alive_timeout_secs: u64 = vars.parse("HTTP2_KEEPALIVE_TIMEOUT_SECS", 20);
+
+        let db_slow_query_ms: u64 = vars.parse("DB_SLOW_QUERY_MS", 250);
+
+        let slow_request_ms: u64 = vars.parse("SLOW_REQUEST_MS", 1000);
+
+        let cors_expose_headers: Vec<String> = vars
+            .string("CORS_EXPOSE_HEADERS", "x-request-id,etag")
+            .split(',')
+            .map(|s| s.trim().to_ascii_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if let Some(bad) = cors_expose_headers
+            .iter()
+            .find(|h| axum::http::HeaderName::from_str(h).is_err())
+        {
+            vars.invalid(
+                "CORS_EXPOSE_HEADERS",
+                format!("'{bad}' is not a header name"),
+            );
+        }
+
+        let cors_max_age_secs: u64 = vars.parse("CORS_MAX_AGE_SECS", 600);
+
+        let base_url = vars.get("BASE_URL");
+
+        let route_prefix = match vars
+            .get("ROUTE_PREFIX")
+            .map(|raw| normalize_route_prefix(&raw))
+        {
+            Some(Ok(prefix)) => prefix,
+            Some(Err(message)) => {
+                vars.invalid("ROUTE_PREFIX", message);
+                String::new()
+            }
+            None => String::new(),
+        };
+
+        let invite_base_url = vars.string("INVITE_BASE_URL", "https://activities.carrierwave.app");
+
+        let invite_expiry_days = vars.parse("INVITE_EXPIRY_DAYS", 7);
+
+        let max_friends: i64 = vars.parse("MAX_FRIENDS", 500);
+        if max_friends < 1 {
+            vars.invalid("MAX_FRIENDS", "must be at least 1");
+        }
+
+        let feed_default_lookback_days: i32 = vars.parse("FEED_DEFAULT_LOOKBACK_DAYS", 30);
+        if feed_default_lookback_days < 0 {
+            vars.invalid("FEED_DEFAULT_LOOKBACK_DAYS", "must not be negative");
+        }
+
+        let max_activities_per_hour: i64 = vars.parse("MAX_ACTIVITIES_PER_HOUR", 60);
+        if max_activities_per_hour < 0 {
+            vars.invalid("MAX_ACTIVITIES_PER_HOUR", "must not be negative");
+        }
+
+        let error_doc_links_enabled = vars.flag("ERROR_DOC_LINKS_ENABLED", false);
+        let error_doc_base_url = vars.string(
+            "ERROR_DOC_BASE_URL",
+            "https://activities.carrierwave.app/docs",
+        );
+
+        let spots_enabled = vars.flag("SPOTS_ENABLED", true);
+
+        let clubs_enabled = vars.flag("CLUBS_ENABLED", true);
+
+        let pota_aggregator_enabled = vars.flag("POTA_AGGREGATOR_ENABLED", false);
+
+        let sota_aggregator_enabled = vars.flag("SOTA_AGGREGATOR_ENABLED", false);
+
+        let wspr_aggregator_enabled = vars.flag("WSPR_AGGREGATOR_ENABLED", false);
+
+        let manual_poll_cooldown_secs: u64 = vars.parse("MANUAL_POLL_COOLDOWN_SECS", 30);
+
+        let strict_program_check = vars.flag("STRICT_PROGRAM_CHECK", false);
+
+        let pota_sota_merge_enabled = vars.flag("POTA_SOTA_MERGE_ENABLED", false);
+        let pota_sota_merge_window_mins: i64 = vars.parse("POTA_SOTA_MERGE_WINDOW_MINS", 10);
+        if pota_sota_merge_window_mins < 1 {
+            vars.invalid("POTA_SOTA_MERGE_WINDOW_MINS", "must be at least 1");
+        }
+
+        let spot_comment_max_len = vars.parse("SPOT_COMMENT_MAX_LEN", 120);
+
+        let pota_max_spots_per_cycle: usize = vars.parse("POTA_MAX_SPOTS_PER_CYCLE", 5000);
+
+        let sota_max_spots_per_cycle: usize = vars.parse("SOTA_MAX_SPOTS_PER_CYCLE", 5000);
+
+        let wspr_max_spots_per_cycle: usize = vars.parse("WSPR_MAX_SPOTS_PER_CYCLE", 5000);
+
+        // Whether the programs exist is checked by startup::check_required_programs
+        let pota_program_slug = vars.string("POTA_PROGRAM_SLUG", "pota").trim().to_string();
+        let sota_program_slug = vars.string("SOTA_PROGRAM_SLUG", "sota").trim().to_string();
+
+        // Spot fields nulled for unauthenticated requests (JSON names, comma-separated)
+        let public_spot_redacted_fields: Vec<String> = vars
+            .string("PUBLIC_SPOT_FIELDS", "spotterGrid,comments")
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if let Err(message) =
+            crate::models::spot::validate_redacted_fields(&public_spot_redacted_fields)
+        {
+            vars.invalid("PUBLIC_SPOT_FIELDS", message);
+        }
+
+        let reference_country_patterns =
+            crate::models::spot::ReferenceCountryPatterns::parse(&vars.string(
+                "REFERENCE_COUNTRY_PATTERNS",
+                DEFAULT_REFERENCE_COUNTRY_PATTERNS,
+            ))
+            .unwrap_or_else(|message| {
+                vars.invalid("REFERENCE_COUNTRY_PATTERNS", message);
+                Default::default()
+            });
+
+        // POTA spots expire from their original timestamp but stay in the
+        // upstream list while the activator is on the air, so keep them listed
+        let spot_respot_ttl = crate::models::spot::RespotTtl::parse(
+            &vars.string("SPOT_RESPOT_TTL_MINUTES", "pota=10"),
+        )
+        .unwrap_or_else(|message| {
+            vars.invalid("SPOT_RESPOT_TTL_MINUTES", message);
+            Default::default()
+        });
+
+        let aggregator_callsign_filter = crate::aggregators::callsign_filter::CallsignFilter::new(
+            vars.callsign_list(
+                "AGGREGATOR_CALLSIGN_ALLOWLIST",
+                "AGGREGATOR_CALLSIGN_ALLOWLIST_FILE",
+            ),
+            vars.callsign_list(
+                "AGGREGATOR_CALLSIGN_BLOCKLIST",
+                "AGGREGATOR_CALLSIGN_BLOCKLIST_FILE",
+            )
+            .unwrap_or_default(),
+        );
+
+        // Drops upstream glitches such as 0 kHz spots
+        let spot_frequency_range = {
+            use crate::aggregators::frequency_range::{
+                FrequencyRange, DEFAULT_MAX_KHZ, DEFAULT_MIN_KHZ,
+            };
+            FrequencyRange::new(
+                vars.parse("SPOT_MIN_FREQUENCY_KHZ", DEFAULT_MIN_KHZ),
+                vars.parse("SPOT_MAX_FREQUENCY_KHZ", DEFAULT_MAX_KHZ),
+            )
+            .unwrap_or_else(|message| {
+                vars.invalid("SPOT_MIN_FREQUENCY_KHZ", message);
+                FrequencyRange::default()
+            })
+        };
+
+        let mut pota_spot_urls = vec![crate::aggregators::pota::POTA_SPOTS_URL.to_string()];
+        if let Some(raw) = vars.get("POTA_SECONDARY_SPOT_URLS") {
+            for url in raw.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+                match validate_https_url(url) {
+                    Ok(()) => pota_spot_urls.push(url.to_string()),
+                    Err(message) => vars.invalid("POTA_SECONDARY_SPOT_URLS", message),
+                }
+            }
+        }
+
+        let pota_backfill_url = vars
+            .get("POTA_BACKFILL_URL")
+            .map(|url| url.trim().to_string());
+        if let Some(Err(message)) = pota_backfill_url.as_deref().map(validate_https_url) {
+            vars.invalid("POTA_BACKFILL_URL", message);
+        }
+
+        let pota_backfill_page_delay_ms: u64 = vars.parse("POTA_BACKFILL_PAGE_DELAY_MS", 2000);
+
+        let pota_stats_aggregator_enabled = vars.flag("POTA_STATS_AGGREGATOR_ENABLED", false);
+
+        let pota_stats_concurrency: usize = vars.parse("POTA_STATS_CONCURRENCY", 3);
+
+        let pota_stats_batch_size: i64 = vars.parse("POTA_STATS_BATCH_SIZE", 50);
+
+        let pota_stats_cycle_hours: u64 = vars.parse("POTA_STATS_CYCLE_HOURS", 24);
+
+        let park_boundaries_enabled = vars.flag("PARK_BOUNDARIES_ENABLED", false);
+
+        let park_boundaries_batch_size: i64 = vars.parse("PARK_BOUNDARIES_BATCH_SIZE", 20);
+
+        let park_boundaries_cycle_hours: u64 = vars.parse("PARK_BOUNDARIES_CYCLE_HOURS", 24);
+
+        let park_boundaries_stale_days: i64 = vars.parse("PARK_BOUNDARIES_STALE_DAYS", 90);
+
+        let park_boundaries_concurrency: usize = vars.parse("PARK_BOUNDARIES_CONCURRENCY", 5);
+
+        let polish_park_boundaries_enabled = vars.flag("POLISH_PARK_BOUNDARIES_ENABLED", false);
+
+        let historic_trails_enabled = vars.flag("HISTORIC_TRAILS_ENABLED", false);
+
+        let polish_park_boundaries_batch_size: i64 =
+            vars.parse("POLISH_PARK_BOUNDARIES_BATCH_SIZE", 20);
 
         let polish_park_boundaries_cycle_hours: u64 =
-            env::var("POLISH_PARK_BOUNDARIES_CYCLE_HOURS")
-                .unwrap_or_else(|_| "24".to_string())
-                .parse()
-                .unwrap_or(24);
+            vars.parse("POLISH_PARK_BOUNDARIES_CYCLE_HOURS", 24);
 
-        let polish_park_boundaries_stale_days: i64 = env::var("POLISH_PARK_BOUNDARIES_STALE_DAYS")
-            .unwrap_or_else(|_| "90".to_string())
-            .parse()
-            .unwrap_or(90);
+        let polish_park_boundaries_stale_days: i64 =
+            vars.parse("POLISH_PARK_BOUNDARIES_STALE_DAYS", 90);
 
         let polish_park_boundaries_concurrency: usize =
-            env::var("POLISH_PARK_BOUNDARIES_CONCURRENCY")
-                .unwrap_or_else(|_| "3".to_string())
-                .parse()
-                .unwrap_or(3);
-
-        let historic_trails_batch_size: i64 = env::var("HISTORIC_TRAILS_BATCH_SIZE")
-            .unwrap_or_else(|_| "20".to_string())
-            .parse()
-            .unwrap_or(20);
-
-        let historic_trails_cycle_hours: u64 = env::var("HISTORIC_TRAILS_CYCLE_HOURS")
-            .unwrap_or_else(|_| "168".to_string())
-            .parse()
-            .unwrap_or(168);
-
-        let historic_trails_stale_days: i64 = env::var("HISTORIC_TRAILS_STALE_DAYS")
-            .unwrap_or_else(|_| "180".to_string())
-            .parse()
-            .unwrap_or(180);
-
-        let historic_trails_concurrency: usize = env::var("HISTORIC_TRAILS_CONCURRENCY")
-            .unwrap_or_else(|_| "5".to_string())
-            .parse()
-            .unwrap_or(5);
-
-        let rbn_proxy_enabled = env::var("RBN_PROXY_ENABLED")
-            .unwrap_or_else(|_| "false".to_string())
-            .parse()
-            .unwrap_or(false);
-
-        let rbn_proxy_callsign =
-            env::var("RBN_PROXY_CALLSIGN").unwrap_or_else(|_| "W6JSV".to_string());
-
-        let snapshot_enabled = env::var("SNAPSHOT_ENABLED")
-            .unwrap_or_else(|_| "true".to_string())
-            .parse()
-            .unwrap_or(true);
-
-        let snapshot_dir =
-            env::var("SNAPSHOT_DIR").unwrap_or_else(|_| "data/snapshots".to_string());
-
-        let snapshot_interval_hours: u64 = env::var("SNAPSHOT_INTERVAL_HOURS")
-            .unwrap_or_else(|_| "1".to_string())
-            .parse()
-            .unwrap_or(1);
-
-        let snapshot_max_age_hours: u64 = env::var("SNAPSHOT_MAX_AGE_HOURS")
-            .unwrap_or_else(|_| "24".to_string())
-            .parse()
-            .unwrap_or(24);
+            vars.parse("POLISH_PARK_BOUNDARIES_CONCURRENCY", 3);
+
+        let historic_trails_batch_size: i64 = vars.parse("HISTORIC_TRAILS_BATCH_SIZE", 20);
+
+        let historic_trails_cycle_hours: u64 = vars.parse("HISTORIC_TRAILS_CYCLE_HOURS", 168);
+
+        let historic_trails_stale_days: i64 = vars.parse("HISTORIC_TRAILS_STALE_DAYS", 180);
+
+        let historic_trails_concurrency: usize = vars.parse("HISTORIC_TRAILS_CONCURRENCY", 5);
+
+        let rbn_proxy_enabled = vars.flag("RBN_PROXY_ENABLED", false);
+
+        let rbn_proxy_callsign = vars.string("RBN_PROXY_CALLSIGN", "W6JSV");
+
+        let snapshot_enabled = vars.flag("SNAPSHOT_ENABLED", true);
+
+        let snapshot_dir = vars.string("SNAPSHOT_DIR", "data/snapshots");
+
+        let snapshot_interval_hours: u64 = vars.parse("SNAPSHOT_INTERVAL_HOURS", 1);
+
+        let snapshot_max_age_hours: u64 = vars.parse("SNAPSHOT_MAX_AGE_HOURS", 24);
+
+        let maintenance_enabled = vars.flag("MAINTENANCE_ENABLED", true);
+
+        let maintenance_hour_utc: u32 = vars.parse("MAINTENANCE_HOUR_UTC", 3);
+        if maintenance_hour_utc > 23 {
+            vars.invalid(
+                "MAINTENANCE_HOUR_UTC",
+                format!("must be an hour from 0 to 23, got {maintenance_hour_utc}"),
+            );
+        }
+
+        let maintenance_retention_days: i64 = vars.parse("MAINTENANCE_RETENTION_DAYS", 180);
+        if maintenance_retention_days < 1 {
+            vars.invalid("MAINTENANCE_RETENTION_DAYS", "must be at least 1");
+        }
+
+        let archive_spots = vars.flag("ARCHIVE_SPOTS", false);
+
+        let spots_archive_retention_months: i64 = vars.parse("SPOTS_ARCHIVE_RETENTION_MONTHS", 12);
+        if spots_archive_retention_months < 1 {
+            vars.invalid("SPOTS_ARCHIVE_RETENTION_MONTHS", "must be at least 1");
+        }
+
+        let spots_archive_comment_retention_days: Option<i32> =
+            vars.parse_optional("SPOTS_ARCHIVE_COMMENT_RETENTION_DAYS");
+        if spots_archive_comment_retention_days.is_some_and(|days| days < 1) {
+            vars.invalid("SPOTS_ARCHIVE_COMMENT_RETENTION_DAYS", "must be at least 1");
+        }
+
+        let mut errors = vars.errors;
+
+        if !spots_enabled {
+            for (var, enabled) in [
+                ("POTA_AGGREGATOR_ENABLED", pota_aggregator_enabled),
+                ("SOTA_AGGREGATOR_ENABLED", sota_aggregator_enabled),
+                ("WSPR_AGGREGATOR_ENABLED", wspr_aggregator_enabled),
+            ] {
+                if enabled {
+                    errors.push(ConfigError::Conflict(format!(
+                        "{var}=true requires SPOTS_ENABLED=true"
+                    )));
+                }
+            }
+        }
+
+        for (var, max) in [
+            ("POTA_MAX_SPOTS_PER_CYCLE", pota_max_spots_per_cycle),
+            ("SOTA_MAX_SPOTS_PER_CYCLE", sota_max_spots_per_cycle),
+            ("WSPR_MAX_SPOTS_PER_CYCLE", wspr_max_spots_per_cycle),
+        ] {
+            if max == 0 {
+                errors.push(ConfigError::Invalid {
+                    var,
+                    message: "must be at least 1".to_string(),
+                });
+            }
+        }
+
+        if let Err(message) = validate_https_url(&invite_base_url) {
+            errors.push(ConfigError::Invalid {
+                var: "INVITE_BASE_URL",
+                message,
+            });
+        }
+
+        if let Err(message) = validate_https_url(&error_doc_base_url) {
+            errors.push(ConfigError::Invalid {
+                var: "ERROR_DOC_BASE_URL",
+                message,
+            });
+        }
+
+        if metrics_port == Some(port) {
+            errors.push(ConfigError::Conflict(format!(
+                "METRICS_PORT must differ from PORT (both {port})"
+            )));
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
         Ok(Self {
             database_url,
             admin_token,
             port,
+            metrics_port,
+            http2_enabled,
+            http_keep_alive_enabled,
+            http_idle_timeout_secs,
+            http2_keep_alive_interval_secs,
+            http2_keep_alive_timeout_secs,
+            db_slow_query_ms,
+            slow_request_ms,
+            cors_expose_headers,
+            cors_max_age_secs,
             base_url,
+            route_prefix,
             invite_base_url,
             invite_expiry_days,
+            max_friends,
+            feed_default_lookback_days,
+            max_activities_per_hour,
+            error_doc_links_enabled,
+            error_doc_base_url,
             spots_enabled,
+            clubs_enabled,
             pota_aggregator_enabled,
             sota_aggregator_enabled,
+            wspr_aggregator_enabled,
+            manual_poll_cooldown_secs,
+            strict_program_check,
+            pota_sota_merge_enabled,
+            pota_sota_merge_window_mins,
+            spot_comment_max_len,
+            pota_spot_urls,
+            pota_max_spots_per_cycle,
+            pota_backfill_url,
+            pota_backfill_page_delay_ms,
+            sota_max_spots_per_cycle,
+            pota_program_slug,
+            sota_program_slug,
+            wspr_max_spots_per_cycle,
+            public_spot_redacted_fields,
+            reference_country_patterns,
+            spot_respot_ttl,
+            aggregator_callsign_filter,
+            spot_frequency_range,
             pota_stats_aggregator_enabled,
             pota_stats_concurrency,
             pota_stats_batch_size,
@@ -236,14 +537,186 @@ impl Config {
             snapshot_dir,
             snapshot_interval_hours,
             snapshot_max_age_hours,
+            maintenance_enabled,
+            maintenance_hour_utc,
+            maintenance_retention_days,
+            archive_spots,
+            spots_archive_retention_months,
+            spots_archive_comment_retention_days,
         })
     }
+
+    /// Copy with `admin_token` masked and the `database_url` password masked,
+    /// safe to log or return from the admin config endpoint.
+    pub fn redacted_view(&self) -> Self {
+        let mut redacted = self.clone();
+        redacted.admin_token = REDACTED.to_string();
+        redacted.database_url = redact_url_password(&self.database_url);
+        redacted
+    }
+
+    /// `Debug` rendering of [`Config::redacted_view`], for startup logging.
+    pub fn redacted_debug(&self) -> String {
+        format!("{:?}", self.redacted_view())
+    }
+}
+
+const REDACTED: &str = "****";
+
+/// POTA references start with the two-letter country code (`US-1234`).
+const DEFAULT_REFERENCE_COUNTRY_PATTERNS: &str = r"pota=^([A-Z]{2})-\d{4,5}$";
+
+/// Reads variables from a lookup and accumulates errors as it goes.
+/// Empty values are treated as unset.
+struct Vars<'a> {
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+    errors: Vec<ConfigError>,
+}
+
+impl Vars<'_> {
+    fn get(&self, var: &str) -> Option<String> {
+        (self.lookup)(var).filter(|v| !v.trim().is_empty())
+    }
+
+    fn invalid(&mut self, var: &'static str, message: impl Into<String>) {
+        self.errors.push(ConfigError::Invalid {
+            var,
+            message: message.into(),
+        });
+    }
+
+    fn required(&mut self, var: &'static str) -> String {
+        self.get(var).unwrap_or_else(|| {
+            self.errors.push(ConfigError::Missing(var));
+            String::new()
+        })
+    }
+
+    fn string(&self, var: &str, default: &str) -> String {
+        self.get(var).unwrap_or_else(|| default.to_string())
+    }
+
+    fn parse_optional<T: FromStr>(&mut self, var: &'static str) -> Option<T> {
+        let raw = self.get(var)?;
+        match raw.trim().parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.invalid(var, format!("expected a number, got {raw:?}"));
+                None
+            }
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, var: &'static str, default: T) -> T {
+        self.parse_optional(var).unwrap_or(default)
+    }
+
+    /// Callsigns listed inline in `var` or in the file named by `file_var`,
+    /// or `None` when neither is set.
+    fn callsign_list(&mut self, var: &'static str, file_var: &'static str) -> Option<Vec<String>> {
+        let raw = match (self.get(var), self.get(file_var)) {
+            (None, None) => return None,
+            (Some(_), Some(_)) => {
+                self.invalid(
+                    file_var,
+                    format!("set either {var} or {file_var}, not both"),
+                );
+                return None;
+            }
+            (Some(raw), None) => raw,
+            (None, Some(path)) => match std::fs::read_to_string(path.trim()) {
+                Ok(raw) => raw,
+                Err(e) => {
+                    self.invalid(file_var, format!("cannot read {path:?}: {e}"));
+                    return None;
+                }
+            },
+        };
+        let calls = crate::aggregators::callsign_filter::CallsignFilter::parse_list(&raw);
+        if calls.is_empty() {
+            let source = if self.get(var).is_some() {
+                var
+            } else {
+                file_var
+            };
+            self.invalid(source, "lists no callsigns");
+            return None;
+        }
+        Some(calls)
+    }
+
+    fn flag(&mut self, var: &'static str, default: bool) -> bool {
+        let Some(raw) = self.get(var) else {
+            return default;
+        };
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" => true,
+            "false" | "0" | "no" => false,
+            _ => {
+                self.invalid(var, format!("expected true or false, got {raw:?}"));
+                default
+            }
+        }
+    }
+}
+
+fn validate_https_url(raw: &str) -> Result<(), String> {
+    let url =
+        reqwest::Url::parse(raw).map_err(|e| format!("{raw:?} is not an absolute URL: {e}"))?;
+    if url.scheme() != "https" {
+        return Err(format!("{raw:?} must use https"));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(format!("{raw:?} has no host"));
+    }
+    Ok(())
+}
+
+/// `/challenges/` and `challenges` both become `/challenges`; `/` alone means
+/// no prefix. Segments are plain path text so they can't act as route captures.
+fn normalize_route_prefix(raw: &str) -> Result<String, String> {
+    let trimmed = raw.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+    for segment in trimmed.split('/') {
+        if segment.is_empty() {
+            return Err(format!("{raw:?} has an empty path segment"));
+        }
+        if let Some(bad) = segment
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~')))
+        {
+            return Err(format!(
+                "{raw:?} contains {bad:?}; use letters, digits, '-', '_', '.' or '~'"
+            ));
+        }
+    }
+    Ok(format!("/{trimmed}"))
+}
+
+/// Mask the password in a connection URL; unparseable values are masked entirely.
+fn redact_url_password(raw: &str) -> String {
+    match reqwest::Url::parse(raw) {
+        Ok(mut url) => {
+            if url.password().is_some() {
+                let _ = url.set_password(Some(REDACTED));
+            }
+            url.to_string()
+        }
+        Err(_) => REDACTED.to_string(),
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Missing required environment variable: {0}")]
     Missing(&'static str),
-    #[error("Invalid configuration: {0}")]
-    Invalid(&'static str),
+    #[error("Invalid configuration for {var}: {message}")]
+    Invalid { var: &'static str, message: String },
+    #[error("Conflicting configuration: {0}")]
+    Conflict(String),
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,432 @@
+use super::*;
+use crate::models::spot::SpotSource;
+use std::collections::HashMap;
+
+fn load(pairs: &[(&str, &str)]) -> Result<Config, Vec<ConfigError>> {
+    let mut vars: HashMap<String, String> = [
+        ("DATABASE_URL", "postgres://app:hunter2@db:5432/activities"),
+        ("ADMIN_TOKEN", "admin-secret"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    for (k, v) in pairs {
+        vars.insert(k.to_string(), v.to_string());
+    }
+    Config::from_lookup(|var| vars.get(var).cloned())
+}
+
+fn errors(pairs: &[(&str, &str)]) -> Vec<String> {
+    load(pairs)
+        .expect_err("config should be rejected")
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn defaults_load() {
+    let config = load(&[]).unwrap();
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.metrics_port, None);
+    assert!(config.spots_enabled);
+    assert_eq!(config.invite_base_url, "https://activities.carrierwave.app");
+}
+
+#[test]
+fn reports_all_missing_required_vars() {
+    let err = Config::from_lookup(|_| None).unwrap_err();
+    assert!(matches!(
+        err[..],
+        [
+            ConfigError::Missing("DATABASE_URL"),
+            ConfigError::Missing("ADMIN_TOKEN")
+        ]
+    ));
+}
+
+#[test]
+fn empty_required_var_is_missing() {
+    let err = load(&[("ADMIN_TOKEN", "  ")]).unwrap_err();
+    assert!(matches!(err[..], [ConfigError::Missing("ADMIN_TOKEN")]));
+}
+
+#[test]
+fn collects_invalid_values_together() {
+    let err = load(&[
+        ("PORT", "eighty"),
+        ("INVITE_EXPIRY_DAYS", "7d"),
+        ("SNAPSHOT_ENABLED", "maybe"),
+    ])
+    .unwrap_err();
+    let vars: Vec<_> = err
+        .iter()
+        .map(|e| match e {
+            ConfigError::Invalid { var, .. } => *var,
+            other => panic!("unexpected {other:?}"),
+        })
+        .collect();
+    assert_eq!(vars, ["PORT", "INVITE_EXPIRY_DAYS", "SNAPSHOT_ENABLED"]);
+}
+
+#[test]
+fn missing_and_invalid_reported_together() {
+    let err = Config::from_lookup(|var| (var == "PORT").then(|| "99999".to_string())).unwrap_err();
+    assert_eq!(err.len(), 3);
+}
+
+#[test]
+fn flags_accept_common_spellings() {
+    let config = load(&[("SNAPSHOT_ENABLED", "0"), ("RBN_PROXY_ENABLED", "Yes")]).unwrap();
+    assert!(!config.snapshot_enabled);
+    assert!(config.rbn_proxy_enabled);
+}
+
+#[test]
+fn invalid_redacted_fields() {
+    let err = errors(&[("PUBLIC_SPOT_FIELDS", "bogusField")]);
+    assert!(err[0].contains("PUBLIC_SPOT_FIELDS"), "{err:?}");
+}
+
+#[test]
+fn aggregators_require_spots() {
+    let err = errors(&[
+        ("SPOTS_ENABLED", "false"),
+        ("POTA_AGGREGATOR_ENABLED", "true"),
+        ("SOTA_AGGREGATOR_ENABLED", "true"),
+        ("WSPR_AGGREGATOR_ENABLED", "true"),
+    ]);
+    assert_eq!(err.len(), 3);
+    assert!(err[0].contains("POTA_AGGREGATOR_ENABLED"));
+    assert!(err[1].contains("SOTA_AGGREGATOR_ENABLED"));
+    assert!(err[2].contains("WSPR_AGGREGATOR_ENABLED"));
+}
+
+#[test]
+fn spots_disabled_without_aggregators_is_fine() {
+    assert!(!load(&[("SPOTS_ENABLED", "false")]).unwrap().spots_enabled);
+}
+
+#[test]
+fn invite_base_url_must_be_absolute_https() {
+    for bad in [
+        "activities.carrierwave.app",
+        "http://activities.carrierwave.app",
+        "/invite",
+    ] {
+        let err = errors(&[("INVITE_BASE_URL", bad)]);
+        assert!(err[0].contains("INVITE_BASE_URL"), "{bad}: {err:?}");
+    }
+}
+
+#[test]
+fn route_prefix_is_normalized() {
+    assert_eq!(load(&[]).unwrap().route_prefix, "");
+    for raw in ["/challenges", "challenges/", "/challenges/"] {
+        let config = load(&[("ROUTE_PREFIX", raw)]).unwrap();
+        assert_eq!(config.route_prefix, "/challenges", "{raw}");
+    }
+    assert_eq!(load(&[("ROUTE_PREFIX", "/")]).unwrap().route_prefix, "");
+    assert_eq!(
+        load(&[("ROUTE_PREFIX", "/api/activities")])
+            .unwrap()
+            .route_prefix,
+        "/api/activities"
+    );
+}
+
+#[test]
+fn route_prefix_rejects_captures_and_empty_segments() {
+    for bad in ["/:tenant", "/api/*rest", "/api//v2", "/a b"] {
+        let err = errors(&[("ROUTE_PREFIX", bad)]);
+        assert!(err[0].contains("ROUTE_PREFIX"), "{bad}: {err:?}");
+    }
+}
+
+#[test]
+fn pota_sota_merge_is_opt_in() {
+    let config = load(&[]).unwrap();
+    assert!(!config.pota_sota_merge_enabled);
+    assert_eq!(config.pota_sota_merge_window_mins, 10);
+
+    let err = errors(&[("POTA_SOTA_MERGE_WINDOW_MINS", "0")]);
+    assert!(err[0].contains("POTA_SOTA_MERGE_WINDOW_MINS"), "{err:?}");
+}
+
+#[test]
+fn max_spots_per_cycle_must_be_positive() {
+    let err = errors(&[("SOTA_MAX_SPOTS_PER_CYCLE", "0")]);
+    assert_eq!(err.len(), 1);
+    assert!(err[0].contains("SOTA_MAX_SPOTS_PER_CYCLE"), "{err:?}");
+
+    let config = load(&[("POTA_MAX_SPOTS_PER_CYCLE", "250")]).unwrap();
+    assert_eq!(config.pota_max_spots_per_cycle, 250);
+    assert_eq!(config.sota_max_spots_per_cycle, 5000);
+    assert_eq!(config.wspr_max_spots_per_cycle, 5000);
+}
+
+#[test]
+fn feed_default_lookback_days_default_and_invalid() {
+    assert_eq!(load(&[]).unwrap().feed_default_lookback_days, 30);
+    let config = load(&[("FEED_DEFAULT_LOOKBACK_DAYS", "0")]).unwrap();
+    assert_eq!(config.feed_default_lookback_days, 0);
+
+    let err = errors(&[("FEED_DEFAULT_LOOKBACK_DAYS", "-1")]);
+    assert!(err[0].contains("FEED_DEFAULT_LOOKBACK_DAYS"), "{err:?}");
+}
+
+#[test]
+fn max_activities_per_hour_default_and_invalid() {
+    assert_eq!(load(&[]).unwrap().max_activities_per_hour, 60);
+    let config = load(&[("MAX_ACTIVITIES_PER_HOUR", "0")]).unwrap();
+    assert_eq!(config.max_activities_per_hour, 0);
+
+    let err = errors(&[("MAX_ACTIVITIES_PER_HOUR", "-5")]);
+    assert!(err[0].contains("MAX_ACTIVITIES_PER_HOUR"), "{err:?}");
+}
+
+#[test]
+fn max_friends_default_and_invalid() {
+    assert_eq!(load(&[]).unwrap().max_friends, 500);
+    assert_eq!(load(&[("MAX_FRIENDS", "50")]).unwrap().max_friends, 50);
+
+    let err = errors(&[("MAX_FRIENDS", "0")]);
+    assert_eq!(err.len(), 1);
+    assert!(err[0].contains("MAX_FRIENDS"), "{err:?}");
+}
+
+#[test]
+fn pota_secondary_spot_urls_follow_primary() {
+    let config = load(&[]).unwrap();
+    assert_eq!(
+        config.pota_spot_urls,
+        [crate::aggregators::pota::POTA_SPOTS_URL]
+    );
+
+    let config = load(&[(
+        "POTA_SECONDARY_SPOT_URLS",
+        " https://pota-mirror.example/spot/activator ,",
+    )])
+    .unwrap();
+    assert_eq!(config.pota_spot_urls.len(), 2);
+    assert_eq!(
+        config.pota_spot_urls[1],
+        "https://pota-mirror.example/spot/activator"
+    );
+
+    let err = errors(&[("POTA_SECONDARY_SPOT_URLS", "http://pota-mirror.example")]);
+    assert!(err[0].contains("POTA_SECONDARY_SPOT_URLS"), "{err:?}");
+}
+
+#[test]
+fn pota_backfill_url_is_optional_but_https() {
+    let config = load(&[]).unwrap();
+    assert_eq!(config.pota_backfill_url, None);
+    assert_eq!(config.pota_backfill_page_delay_ms, 2000);
+
+    let config = load(&[
+        ("POTA_BACKFILL_URL", "https://pota-history.example/spots"),
+        ("POTA_BACKFILL_PAGE_DELAY_MS", "500"),
+    ])
+    .unwrap();
+    assert_eq!(
+        config.pota_backfill_url.as_deref(),
+        Some("https://pota-history.example/spots")
+    );
+    assert_eq!(config.pota_backfill_page_delay_ms, 500);
+
+    let err = errors(&[("POTA_BACKFILL_URL", "http://pota-history.example")]);
+    assert!(err[0].contains("POTA_BACKFILL_URL"), "{err:?}");
+}
+
+#[test]
+fn maintenance_hour_and_retention_validated() {
+    let err = errors(&[
+        ("MAINTENANCE_HOUR_UTC", "24"),
+        ("MAINTENANCE_RETENTION_DAYS", "0"),
+    ]);
+    assert_eq!(err.len(), 2);
+    assert!(err[0].contains("MAINTENANCE_HOUR_UTC"), "{err:?}");
+    assert!(err[1].contains("MAINTENANCE_RETENTION_DAYS"), "{err:?}");
+
+    let config = load(&[("MAINTENANCE_HOUR_UTC", "0")]).unwrap();
+    assert_eq!(config.maintenance_hour_utc, 0);
+    assert_eq!(config.maintenance_retention_days, 180);
+}
+
+#[test]
+fn spots_archive_defaults_and_retention_validated() {
+    let config = load(&[]).unwrap();
+    assert!(!config.archive_spots);
+    assert_eq!(config.spots_archive_retention_months, 12);
+
+    let config = load(&[
+        ("ARCHIVE_SPOTS", "true"),
+        ("SPOTS_ARCHIVE_RETENTION_MONTHS", "24"),
+    ])
+    .unwrap();
+    assert!(config.archive_spots);
+    assert_eq!(config.spots_archive_retention_months, 24);
+
+    let err = errors(&[("SPOTS_ARCHIVE_RETENTION_MONTHS", "0")]);
+    assert!(err[0].contains("SPOTS_ARCHIVE_RETENTION_MONTHS"), "{err:?}");
+}
+
+#[test]
+fn spots_archive_comment_retention_is_optional() {
+    let config = load(&[]).unwrap();
+    assert_eq!(config.spots_archive_comment_retention_days, None);
+
+    let config = load(&[("SPOTS_ARCHIVE_COMMENT_RETENTION_DAYS", "90")]).unwrap();
+    assert_eq!(config.spots_archive_comment_retention_days, Some(90));
+
+    for days in ["0", "2147483648"] {
+        let err = errors(&[("SPOTS_ARCHIVE_COMMENT_RETENTION_DAYS", days)]);
+        assert!(
+            err[0].contains("SPOTS_ARCHIVE_COMMENT_RETENTION_DAYS"),
+            "{err:?}"
+        );
+    }
+}
+
+#[test]
+fn reference_country_patterns_default_and_invalid() {
+    let config = load(&[]).unwrap();
+    assert_eq!(
+        config
+            .reference_country_patterns
+            .country_for("pota", "US-1234")
+            .as_deref(),
+        Some("US")
+    );
+
+    let err = errors(&[("REFERENCE_COUNTRY_PATTERNS", "pota=[")]);
+    assert!(err[0].contains("REFERENCE_COUNTRY_PATTERNS"), "{err:?}");
+}
+
+#[test]
+fn cors_expose_headers_default_and_invalid() {
+    let config = load(&[]).unwrap();
+    assert_eq!(config.cors_expose_headers, ["x-request-id", "etag"]);
+    assert_eq!(config.cors_max_age_secs, 600);
+    assert_eq!(config.manual_poll_cooldown_secs, 30);
+
+    let config = load(&[("CORS_EXPOSE_HEADERS", " ETag , X-Total-Count,")]).unwrap();
+    assert_eq!(config.cors_expose_headers, ["etag", "x-total-count"]);
+
+    let err = errors(&[("CORS_EXPOSE_HEADERS", "etag,bad header")]);
+    assert!(err[0].contains("CORS_EXPOSE_HEADERS"), "{err:?}");
+}
+
+#[test]
+fn spot_respot_ttl_default_and_invalid() {
+    let now = chrono::Utc::now();
+    let config = load(&[]).unwrap();
+    assert!(config
+        .spot_respot_ttl
+        .respot_expiry(&SpotSource::Pota, now)
+        .is_some());
+    assert!(config
+        .spot_respot_ttl
+        .respot_expiry(&SpotSource::Sota, now)
+        .is_none());
+
+    let err = errors(&[("SPOT_RESPOT_TTL_MINUTES", "rbn=-5")]);
+    assert!(err[0].contains("SPOT_RESPOT_TTL_MINUTES"), "{err:?}");
+}
+
+#[test]
+fn spot_frequency_range_from_env() {
+    use crate::aggregators::frequency_range::FrequencyRange;
+    assert_eq!(
+        load(&[]).unwrap().spot_frequency_range,
+        FrequencyRange::default()
+    );
+
+    let config = load(&[("SPOT_MAX_FREQUENCY_KHZ", "1300000")]).unwrap();
+    assert!(config.spot_frequency_range.contains(1_296_000.0));
+    assert!(!config.spot_frequency_range.contains(0.0));
+
+    let err = errors(&[
+        ("SPOT_MIN_FREQUENCY_KHZ", "54000"),
+        ("SPOT_MAX_FREQUENCY_KHZ", "1800"),
+    ]);
+    assert!(err[0].contains("SPOT_MIN_FREQUENCY_KHZ"), "{err:?}");
+}
+
+#[test]
+fn aggregator_callsign_lists_from_env_and_file() {
+    let config = load(&[]).unwrap();
+    assert_eq!(config.aggregator_callsign_filter, Default::default());
+
+    let path = std::env::temp_dir().join(format!("blocklist-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "# known bad\nN0CALL\n").unwrap();
+    let config = load(&[
+        ("AGGREGATOR_CALLSIGN_ALLOWLIST", "W1AW,K1ABC,N0CALL"),
+        ("AGGREGATOR_CALLSIGN_BLOCKLIST_FILE", path.to_str().unwrap()),
+    ])
+    .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let filter = &config.aggregator_callsign_filter;
+    assert!(filter.allows("k1abc"));
+    assert!(!filter.allows("N0CALL"));
+    assert!(!filter.allows("W2XYZ"));
+
+    let err = errors(&[
+        ("AGGREGATOR_CALLSIGN_ALLOWLIST", "W1AW"),
+        ("AGGREGATOR_CALLSIGN_ALLOWLIST_FILE", "/etc/club-calls.txt"),
+    ]);
+    assert!(err[0].contains("not both"), "{err:?}");
+    let err = errors(&[(
+        "AGGREGATOR_CALLSIGN_BLOCKLIST_FILE",
+        "/nonexistent/blocklist.txt",
+    )]);
+    assert!(
+        err[0].contains("AGGREGATOR_CALLSIGN_BLOCKLIST_FILE"),
+        "{err:?}"
+    );
+    let err = errors(&[("AGGREGATOR_CALLSIGN_ALLOWLIST", " # nobody")]);
+    assert!(err[0].contains("lists no callsigns"), "{err:?}");
+}
+
+#[test]
+fn metrics_port_must_differ_from_port() {
+    let err = errors(&[("PORT", "9000"), ("METRICS_PORT", "9000")]);
+    assert!(err[0].contains("METRICS_PORT"), "{err:?}");
+
+    let config = load(&[("PORT", "9000"), ("METRICS_PORT", "9100")]).unwrap();
+    assert_eq!(config.metrics_port, Some(9100));
+}
+
+#[test]
+fn redacted_debug_masks_secrets() {
+    let debug = load(&[]).unwrap().redacted_debug();
+    assert!(!debug.contains("admin-secret"), "{debug}");
+    assert!(!debug.contains("hunter2"), "{debug}");
+    assert!(
+        debug.contains("postgres://app:****@db:5432/activities"),
+        "{debug}"
+    );
+}
+
+#[test]
+fn redacted_view_serializes_without_secrets() {
+    let config = load(&[("METRICS_PORT", "9100")]).unwrap();
+    let json = serde_json::to_value(config.redacted_view()).unwrap();
+    let text = json.to_string();
+    assert!(!text.contains("admin-secret"), "{text}");
+    assert!(!text.contains("hunter2"), "{text}");
+    assert_eq!(json["adminToken"], "****");
+    assert_eq!(
+        json["databaseUrl"],
+        "postgres://app:****@db:5432/activities"
+    );
+    assert_eq!(json["metricsPort"], 9100);
+    assert_eq!(json["spotsEnabled"], true);
+}
+
+#[test]
+fn redacted_view_masks_unparseable_database_url() {
+    let config = load(&[("DATABASE_URL", "host=db password=hunter2")]).unwrap();
+    assert_eq!(config.redacted_view().database_url, "****");
+}
@@ -0,0 +1,191 @@
+//! Opaque pagination cursors.
+//!
+//! A cursor is URL-safe base64 (no padding) of a small JSON payload
+//! `{"v": <version>, "k": <key>}`, where the key is whatever the list is
+//! ordered by. Clients should treat cursors as opaque and pass back the
+//! `nextCursor` they were given; anything that doesn't decode is rejected
+//! with 400 `INVALID_CURSOR` rather than silently restarting from the top.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Payload version written by [`Cursor::encode`]. Bump when the key shape of
+/// any cursor changes so stale cursors fail loudly.
+pub const CURSOR_VERSION: u32 = 1;
+
+/// Cursors longer than this are rejected before decoding.
+const MAX_CURSOR_LEN: usize = 512;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CursorError {
+    #[error("cursor is too long")]
+    TooLong,
+    #[error("cursor is not valid base64")]
+    Encoding,
+    #[error("cursor payload is malformed")]
+    Payload,
+    #[error("cursor version {0} is not supported")]
+    Version(u32),
+}
+
+impl From<CursorError> for AppError {
+    fn from(e: CursorError) -> Self {
+        AppError::InvalidCursor {
+            message: e.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Payload<T> {
+    v: u32,
+    k: T,
+}
+
+/// A decoded cursor holding the sort key of the last item on the previous page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor<T>(pub T);
+
+impl<T: Serialize + DeserializeOwned> Cursor<T> {
+    pub fn encode(&self) -> String {
+        let payload = Payload {
+            v: CURSOR_VERSION,
+            k: &self.0,
+        };
+        let json = serde_json::to_vec(&payload).expect("cursor keys serialize to JSON");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(raw: &str) -> Result<Self, CursorError> {
+        if raw.len() > MAX_CURSOR_LEN {
+            return Err(CursorError::TooLong);
+        }
+        let bytes = URL_SAFE_NO_PAD
+            .decode(raw)
+            .map_err(|_| CursorError::Encoding)?;
+
+        // Check the version before the key so a future key shape reports
+        // a version mismatch rather than a malformed payload
+        #[derive(Deserialize)]
+        struct Versioned {
+            v: u32,
+        }
+        let Versioned { v } = serde_json::from_slice(&bytes).map_err(|_| CursorError::Payload)?;
+        if v != CURSOR_VERSION {
+            return Err(CursorError::Version(v));
+        }
+
+        let payload: Payload<T> =
+            serde_json::from_slice(&bytes).map_err(|_| CursorError::Payload)?;
+        Ok(Self(payload.k))
+    }
+}
+
+impl Cursor<DateTime<Utc>> {
+    /// Decode a timestamp cursor, also accepting the bare RFC 3339 timestamps
+    /// returned before opaque cursors. Legacy cursors are deprecated and will
+    /// be rejected once clients have moved to passing back `nextCursor`.
+    pub fn decode_timestamp(raw: &str) -> Result<Self, CursorError> {
+        if let Ok(legacy) = DateTime::parse_from_rfc3339(raw) {
+            tracing::debug!("Accepted legacy RFC 3339 cursor");
+            return Ok(Self(legacy.with_timezone(&Utc)));
+        }
+        Self::decode(raw)
+    }
+}
+
+/// Parse an optional timestamp cursor query parameter.
+pub fn timestamp_param(raw: Option<&str>) -> Result<Option<DateTime<Utc>>, AppError> {
+    raw.map(|s| Cursor::decode_timestamp(s).map(|c| c.0))
+        .transpose()
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn timestamp_round_trips() {
+        let at = DateTime::parse_from_rfc3339("2025-06-01T12:34:56.789123Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let encoded = Cursor(at).encode();
+        assert!(!encoded.contains(['+', '/', '=']));
+        assert_eq!(Cursor::decode_timestamp(&encoded).unwrap().0, at);
+    }
+
+    #[test]
+    fn legacy_rfc3339_accepted() {
+        let cursor = Cursor::decode_timestamp("2025-06-01T12:00:00+02:00").unwrap();
+        assert_eq!(cursor.0.to_rfc3339(), "2025-06-01T10:00:00+00:00");
+    }
+
+    #[test]
+    fn garbage_is_rejected_with_distinct_errors() {
+        assert_eq!(
+            Cursor::<i64>::decode("not base64!").unwrap_err(),
+            CursorError::Encoding
+        );
+        assert_eq!(
+            Cursor::<i64>::decode(&URL_SAFE_NO_PAD.encode("{}")).unwrap_err(),
+            CursorError::Payload
+        );
+        assert_eq!(
+            Cursor::<i64>::decode(&URL_SAFE_NO_PAD.encode(r#"{"v":99,"k":1}"#)).unwrap_err(),
+            CursorError::Version(99)
+        );
+        assert_eq!(
+            Cursor::<i64>::decode(&"A".repeat(MAX_CURSOR_LEN + 1)).unwrap_err(),
+            CursorError::TooLong
+        );
+    }
+
+    #[test]
+    fn wrong_key_type_is_payload_error() {
+        let encoded = Cursor("abc".to_string()).encode();
+        assert_eq!(
+            Cursor::<i64>::decode(&encoded).unwrap_err(),
+            CursorError::Payload
+        );
+    }
+
+    #[test]
+    fn timestamp_param_maps_to_invalid_cursor() {
+        assert_eq!(timestamp_param(None).unwrap(), None);
+        let err = timestamp_param(Some("yesterday")).unwrap_err();
+        assert!(matches!(err, AppError::InvalidCursor { .. }));
+    }
+
+    proptest! {
+        #[test]
+        fn decode_inverts_encode(key: (i64, String)) {
+            let cursor = Cursor(key);
+            prop_assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        }
+
+        #[test]
+        fn timestamps_round_trip(secs in 0i64..4_102_444_800, nanos in 0u32..1_000_000_000) {
+            let at = DateTime::from_timestamp(secs, nanos).unwrap();
+            prop_assert_eq!(Cursor::decode_timestamp(&Cursor(at).encode()).unwrap().0, at);
+        }
+
+        #[test]
+        fn random_bytes_never_panic(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let raw = String::from_utf8_lossy(&bytes);
+            let _ = Cursor::<DateTime<Utc>>::decode_timestamp(&raw);
+            let _ = Cursor::<(i64, String)>::decode(&raw);
+        }
+
+        #[test]
+        fn random_payloads_never_panic(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = Cursor::<DateTime<Utc>>::decode(&URL_SAFE_NO_PAD.encode(&bytes));
+        }
+    }
+}
@@ -3,7 +3,7 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::models::activity::{Activity, FeedItemRow};
+use crate::models::activity::{Activity, FeedItemRow, FeedOrder};
 
 /// Insert a new activity and return the created row.
 pub async fn insert_activity(
@@ -57,51 +57,465 @@ pub async fn delete_activity(
     Ok(())
 }
 
+/// How many activities a user has added in the last hour, leaving out
+/// `exempt_types`, and when the oldest of them was created.
+pub async fn count_recent_activities(
+    pool: &PgPool,
+    user_id: Uuid,
+    exempt_types: &[&str],
+) -> Result<(i64, Option<DateTime<Utc>>), AppError> {
+    let counted = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+        r#"
+        SELECT COUNT(*), MIN(created_at)
+        FROM activities
+        WHERE user_id = $1
+          AND created_at > now() - interval '1 hour'
+          AND activity_type <> ALL($2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(exempt_types)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(counted)
+}
+
+/// Delete every activity of a user created after `after`, e.g. a broken
+/// client's flood. Returns how many were deleted.
+pub async fn delete_user_activities_after(
+    pool: &PgPool,
+    user_id: Uuid,
+    after: DateTime<Utc>,
+) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM activities
+        WHERE user_id = $1 AND created_at > $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(after)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Whose activities a feed page lists.
+enum FeedScope {
+    /// The friends of this user.
+    FriendsOf(Uuid),
+    /// One friend of this user; nothing if they aren't friends.
+    FriendOf { user_id: Uuid, friend_id: Uuid },
+    /// This user alone.
+    User(Uuid),
+}
+
+/// One page of activities in `scope`, newest first by `order`, before the
+/// cursor and, with `lookback_days`, created within that many days.
+/// Activities of suspended users are skipped.
+async fn get_feed_page(
+    pool: &PgPool,
+    scope: FeedScope,
+    order: FeedOrder,
+    limit: i64,
+    before: Option<DateTime<Utc>>,
+    lookback_days: Option<i32>,
+) -> Result<Vec<FeedItemRow>, AppError> {
+    let limit = limit.clamp(1, 100);
+    let friends_sql = "JOIN friendships f ON f.friend_id = a.user_id WHERE f.user_id = $1";
+    let (scope_sql, user_id, only_user) = match scope {
+        FeedScope::FriendsOf(user_id) => (friends_sql, user_id, None),
+        FeedScope::FriendOf { user_id, friend_id } => (friends_sql, user_id, Some(friend_id)),
+        FeedScope::User(user_id) => ("WHERE a.user_id = $1", user_id, None),
+    };
+    let order_column = match order {
+        FeedOrder::Created => "a.created_at",
+        FeedOrder::EventTime => "a.timestamp",
+    };
+
+    let rows = sqlx::query_as::<_, FeedItemRow>(&format!(
+        r#"
+        SELECT a.id, a.callsign, a.user_id, a.activity_type,
+               a.timestamp, a.details, a.created_at
+        FROM activities a
+        JOIN users u ON u.id = a.user_id
+        {scope_sql}
+          AND u.suspended_at IS NULL
+          AND ($2::timestamptz IS NULL OR {order_column} < $2)
+          AND ($4::uuid IS NULL OR a.user_id = $4)
+          AND ($5::int IS NULL OR a.created_at >= now() - make_interval(days => $5))
+        ORDER BY {order_column} DESC
+        LIMIT $3
+        "#
+    ))
+    .bind(user_id)
+    .bind(before)
+    .bind(limit)
+    .bind(only_user)
+    .bind(lookback_days)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
 /// Get the activity feed for a user: activities from their friends,
-/// cursor-paginated by created_at DESC.
+/// cursor-paginated newest first by `order`, optionally only those created in
+/// the last `lookback_days`. Suspended friends are skipped.
 pub async fn get_feed_for_user(
     pool: &PgPool,
     user_id: Uuid,
+    order: FeedOrder,
     limit: i64,
     before: Option<DateTime<Utc>>,
+    lookback_days: Option<i32>,
 ) -> Result<Vec<FeedItemRow>, AppError> {
-    let limit = limit.clamp(1, 100);
+    get_feed_page(
+        pool,
+        FeedScope::FriendsOf(user_id),
+        order,
+        limit,
+        before,
+        lookback_days,
+    )
+    .await
+}
 
-    let rows = if let Some(cursor) = before {
-        sqlx::query_as::<_, FeedItemRow>(
-            r#"
-            SELECT a.id, a.callsign, a.user_id, a.activity_type,
-                   a.timestamp, a.details, a.created_at
-            FROM activities a
-            JOIN friendships f ON f.friend_id = a.user_id
-            WHERE f.user_id = $1
-              AND a.created_at < $2
-            ORDER BY a.created_at DESC
-            LIMIT $3
-            "#,
+/// Get the activity feed for a user narrowed to one friend, paginated like
+/// `get_feed_for_user`. Empty if `friend_id` isn't their friend.
+pub async fn get_feed_for_friend(
+    pool: &PgPool,
+    user_id: Uuid,
+    friend_id: Uuid,
+    order: FeedOrder,
+    limit: i64,
+    before: Option<DateTime<Utc>>,
+    lookback_days: Option<i32>,
+) -> Result<Vec<FeedItemRow>, AppError> {
+    get_feed_page(
+        pool,
+        FeedScope::FriendOf { user_id, friend_id },
+        order,
+        limit,
+        before,
+        lookback_days,
+    )
+    .await
+}
+
+/// Get one user's own activities, paginated like `get_feed_for_user`.
+/// Empty for a suspended user.
+pub async fn get_activities_for_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    order: FeedOrder,
+    limit: i64,
+    before: Option<DateTime<Utc>>,
+) -> Result<Vec<FeedItemRow>, AppError> {
+    get_feed_page(pool, FeedScope::User(user_id), order, limit, before, None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn user_activities_are_paginated_and_hidden_when_suspended() {
+        let pool = test_support::pool().await;
+        let user = test_support::users(&pool, 1).await.remove(0);
+        for hour in 0..3 {
+            insert_activity(
+                &pool,
+                user.id,
+                &user.callsign,
+                "personalBest",
+                Utc::now() - chrono::Duration::hours(hour),
+                &serde_json::json!({ "hour": hour }),
+            )
+            .await
+            .unwrap();
+        }
+
+        let first = get_activities_for_user(&pool, user.id, FeedOrder::Created, 2, None)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first[0].created_at >= first[1].created_at);
+        let rest = get_activities_for_user(
+            &pool,
+            user.id,
+            FeedOrder::Created,
+            2,
+            Some(first[1].created_at),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(rest.iter().all(|row| row.user_id == user.id));
+
+        sqlx::query("UPDATE users SET suspended_at = now() WHERE id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let hidden = get_activities_for_user(&pool, user.id, FeedOrder::Created, 10, None)
+            .await
+            .unwrap();
+
+        test_support::cleanup_users(&pool, [user.id]).await;
+        assert!(hidden.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn friend_feed_lists_only_that_friend() {
+        let pool = test_support::pool().await;
+        let users = test_support::users(&pool, 3).await;
+        for user in &users {
+            for hour in 0..2 {
+                insert_activity(
+                    &pool,
+                    user.id,
+                    &user.callsign,
+                    "personalBest",
+                    Utc::now() - chrono::Duration::hours(hour),
+                    &serde_json::json!({ "hour": hour }),
+                )
+                .await
+                .unwrap();
+            }
+        }
+        let (viewer, friend, stranger) = (&users[0], &users[1], &users[2]);
+        sqlx::query("INSERT INTO friendships (user_id, friend_id) VALUES ($1, $2), ($2, $1)")
+            .bind(viewer.id)
+            .bind(friend.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let first = get_feed_for_friend(
+            &pool,
+            viewer.id,
+            friend.id,
+            FeedOrder::Created,
+            1,
+            None,
+            None,
         )
-        .bind(user_id)
-        .bind(cursor)
-        .bind(limit)
-        .fetch_all(pool)
-        .await?
-    } else {
-        sqlx::query_as::<_, FeedItemRow>(
+        .await
+        .unwrap();
+        let rest = get_feed_for_friend(
+            &pool,
+            viewer.id,
+            friend.id,
+            FeedOrder::Created,
+            10,
+            Some(first[0].created_at),
+            None,
+        )
+        .await
+        .unwrap();
+        let not_friend = get_feed_for_friend(
+            &pool,
+            viewer.id,
+            stranger.id,
+            FeedOrder::Created,
+            10,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+        assert_eq!(first.len(), 1);
+        assert_eq!(rest.len(), 1);
+        assert!(first
+            .iter()
+            .chain(&rest)
+            .all(|row| row.user_id == friend.id));
+        assert!(not_friend.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn event_time_order_pages_by_activity_timestamp() {
+        let pool = test_support::pool().await;
+        let users = test_support::users(&pool, 2).await;
+        let (viewer, friend) = (&users[0], &users[1]);
+        sqlx::query("INSERT INTO friendships (user_id, friend_id) VALUES ($1, $2)")
+            .bind(viewer.id)
+            .bind(friend.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        // Reported first and happened last, then a backdated one synced later
+        let recent = insert_activity(
+            &pool,
+            friend.id,
+            &friend.callsign,
+            "personalBest",
+            Utc::now(),
+            &serde_json::json!({}),
+        )
+        .await
+        .unwrap();
+        let backdated = insert_activity(
+            &pool,
+            friend.id,
+            &friend.callsign,
+            "personalBest",
+            Utc::now() - chrono::Duration::days(2),
+            &serde_json::json!({}),
+        )
+        .await
+        .unwrap();
+
+        let by_created = get_feed_for_user(&pool, viewer.id, FeedOrder::Created, 10, None, None)
+            .await
+            .unwrap();
+        let first = get_feed_for_user(&pool, viewer.id, FeedOrder::EventTime, 1, None, None)
+            .await
+            .unwrap();
+        let cursor = FeedOrder::EventTime.key(&first[0]);
+        let rest = get_feed_for_user(
+            &pool,
+            viewer.id,
+            FeedOrder::EventTime,
+            10,
+            Some(cursor),
+            None,
+        )
+        .await
+        .unwrap();
+
+        test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+        let ids = |rows: &[FeedItemRow]| rows.iter().map(|row| row.id).collect::<Vec<_>>();
+        assert_eq!(ids(&by_created), [backdated.id, recent.id]);
+        assert_eq!(ids(&first), [recent.id]);
+        assert_eq!(ids(&rest), [backdated.id]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn lookback_window_excludes_older_activities() {
+        let pool = test_support::pool().await;
+        let users = test_support::users(&pool, 2).await;
+        let (viewer, friend) = (&users[0], &users[1]);
+        sqlx::query("INSERT INTO friendships (user_id, friend_id) VALUES ($1, $2)")
+            .bind(viewer.id)
+            .bind(friend.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut activities = Vec::new();
+        for _ in 0..2 {
+            activities.push(
+                insert_activity(
+                    &pool,
+                    friend.id,
+                    &friend.callsign,
+                    "personalBest",
+                    Utc::now(),
+                    &serde_json::json!({}),
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        let (recent, old) = (&activities[0], &activities[1]);
+        sqlx::query("UPDATE activities SET created_at = now() - interval '40 days' WHERE id = $1")
+            .bind(old.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let windowed = get_feed_for_user(&pool, viewer.id, FeedOrder::Created, 10, None, Some(30))
+            .await
+            .unwrap();
+        let unlimited = get_feed_for_user(&pool, viewer.id, FeedOrder::Created, 10, None, None)
+            .await
+            .unwrap();
+
+        test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+        let ids = |rows: &[FeedItemRow]| rows.iter().map(|row| row.id).collect::<Vec<_>>();
+        assert_eq!(ids(&windowed), [recent.id]);
+        assert_eq!(ids(&unlimited), [recent.id, old.id]);
+    }
+
+    /// A user with four activities: two reported just now, one reported two
+    /// hours ago, and one server-generated `overtaken` just now.
+    async fn user_with_recent_activities(pool: &PgPool) -> Uuid {
+        let user = test_support::users(pool, 1).await.remove(0);
+        for activity_type in ["personalBest", "personalBest", "personalBest", "overtaken"] {
+            insert_activity(
+                pool,
+                user.id,
+                &user.callsign,
+                activity_type,
+                Utc::now(),
+                &serde_json::json!({}),
+            )
+            .await
+            .unwrap();
+        }
+        sqlx::query(
             r#"
-            SELECT a.id, a.callsign, a.user_id, a.activity_type,
-                   a.timestamp, a.details, a.created_at
-            FROM activities a
-            JOIN friendships f ON f.friend_id = a.user_id
-            WHERE f.user_id = $1
-            ORDER BY a.created_at DESC
-            LIMIT $2
+            UPDATE activities SET created_at = now() - interval '2 hours'
+            WHERE id = (SELECT id FROM activities
+                        WHERE user_id = $1 AND activity_type = 'personalBest' LIMIT 1)
             "#,
         )
-        .bind(user_id)
-        .bind(limit)
-        .fetch_all(pool)
-        .await?
-    };
+        .bind(user.id)
+        .execute(pool)
+        .await
+        .unwrap();
+        user.id
+    }
 
-    Ok(rows)
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn recent_activity_count_skips_older_and_exempt_types() {
+        let pool = test_support::pool().await;
+        let user_id = user_with_recent_activities(&pool).await;
+
+        let (exempting, oldest) = count_recent_activities(&pool, user_id, &["overtaken"])
+            .await
+            .unwrap();
+        let (all, _) = count_recent_activities(&pool, user_id, &[]).await.unwrap();
+
+        test_support::cleanup_users(&pool, [user_id]).await;
+        assert_eq!(exempting, 2);
+        assert_eq!(all, 3);
+        assert!(oldest.unwrap() > Utc::now() - chrono::Duration::minutes(5));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn bulk_delete_removes_only_activities_after_the_cutoff() {
+        let pool = test_support::pool().await;
+        let user_id = user_with_recent_activities(&pool).await;
+        let bystander = user_with_recent_activities(&pool).await;
+
+        let cutoff = Utc::now() - chrono::Duration::hours(1);
+        let deleted = delete_user_activities_after(&pool, user_id, cutoff)
+            .await
+            .unwrap();
+        let remaining = get_activities_for_user(&pool, user_id, FeedOrder::Created, 10, None)
+            .await
+            .unwrap();
+        let untouched = get_activities_for_user(&pool, bystander, FeedOrder::Created, 10, None)
+            .await
+            .unwrap();
+
+        test_support::cleanup_users(&pool, [user_id, bystander]).await;
+        assert_eq!(deleted, 3);
+        assert_eq!(remaining.len(), 1);
+        assert!(remaining[0].created_at < cutoff);
+        assert_eq!(untouched.len(), 4);
+    }
 }
@@ -0,0 +1,196 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::User;
+
+/// Block a user. Any friendship between the pair, and any pending request in
+/// either direction, is removed with it. Blocking twice is a no-op.
+pub async fn block_user(pool: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO blocks (blocker_id, blocked_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM friendships
+        WHERE (user_id = $1 AND friend_id = $2)
+           OR (user_id = $2 AND friend_id = $1)
+        "#,
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM friend_requests
+        WHERE status = 'pending'
+          AND ((from_user_id = $1 AND to_user_id = $2)
+               OR (from_user_id = $2 AND to_user_id = $1))
+        "#,
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Lift a block. Returns whether there was one.
+pub async fn unblock_user(
+    pool: &PgPool,
+    blocker_id: Uuid,
+    blocked_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM blocks WHERE blocker_id = $1 AND blocked_id = $2")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Users this user has blocked or been blocked by.
+pub async fn get_blocked_with(pool: &PgPool, user_id: Uuid) -> Result<Vec<User>, AppError> {
+    let users = sqlx::query_as::<_, User>(
+        r#"
+        SELECT u.id, u.callsign, u.created_at
+        FROM users u
+        WHERE u.id IN (
+            SELECT blocked_id FROM blocks WHERE blocker_id = $1
+            UNION
+            SELECT blocker_id FROM blocks WHERE blocked_id = $1
+        )
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::spots::ListSpotsParams;
+    use crate::db::test_support;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn block_removes_the_pair_from_friends_spots_and_search() {
+        let pool = test_support::pool().await;
+        let users = test_support::users(&pool, 3).await;
+        let (a, b, c) = (&users[0], &users[1], &users[2]);
+
+        // A and B are friends, and C has a request pending with B
+        let request = crate::db::create_friend_request(&pool, a.id, b.id)
+            .await
+            .unwrap();
+        crate::db::accept_friend_request(&pool, request.id, 500)
+            .await
+            .unwrap();
+        let pending = crate::db::create_friend_request(&pool, c.id, b.id)
+            .await
+            .unwrap();
+
+        block_user(&pool, b.id, a.id).await.unwrap();
+        block_user(&pool, b.id, a.id).await.unwrap();
+        block_user(&pool, b.id, c.id).await.unwrap();
+        let still_friends = crate::db::are_friends(&pool, a.id, b.id).await.unwrap();
+        let request_left = crate::db::get_friend_request(&pool, pending.id)
+            .await
+            .unwrap();
+        let blocked_by_b = get_blocked_with(&pool, b.id).await.unwrap().len();
+        let blocked_with_a: Vec<Uuid> = get_blocked_with(&pool, a.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|u| u.id)
+            .collect();
+
+        // Search and the spot list leave out whoever the viewer is blocked with
+        let found_unfiltered = crate::db::search_callsigns(&pool, &b.callsign, &[], 10)
+            .await
+            .unwrap()
+            .len();
+        let found = crate::db::search_callsigns(&pool, &b.callsign, &blocked_with_a, 10)
+            .await
+            .unwrap()
+            .len();
+        let found_by_users_search =
+            crate::db::search_users(&pool, &b.callsign, &blocked_with_a, 10)
+                .await
+                .unwrap()
+                .len();
+        sqlx::query(
+            r#"
+            INSERT INTO spots (callsign, program_slug, source, frequency_khz, mode, spotted_at, expires_at)
+            VALUES ($1, 'pota', 'other', 14062, 'CW', now(), now() + interval '10 minutes')
+            "#,
+        )
+        .bind(&b.callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let b_spots = |hidden_callsigns: Vec<String>| ListSpotsParams {
+            program: None,
+            callsign: Some(b.callsign.clone()),
+            source: None,
+            mode: None,
+            state: None,
+            max_age_minutes: 30,
+            limit: 10,
+            cursor: None,
+            bbox: None,
+            hidden_callsigns,
+            excluded_submitter: None,
+        };
+        let spots_for_c = crate::db::list_spots(&pool, &b_spots(vec![]))
+            .await
+            .unwrap()
+            .len();
+        let spots_for_a = crate::db::list_spots(&pool, &b_spots(vec![b.callsign.clone()]))
+            .await
+            .unwrap()
+            .len();
+
+        let unblocked = unblock_user(&pool, b.id, a.id).await.unwrap();
+        let unblocked_twice = unblock_user(&pool, b.id, a.id).await.unwrap();
+        let blocked_after_unblock = get_blocked_with(&pool, a.id).await.unwrap().len();
+
+        sqlx::query("DELETE FROM spots WHERE callsign = $1")
+            .bind(&b.callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+        assert!(!still_friends);
+        assert!(request_left.is_none());
+        assert_eq!(blocked_by_b, 2);
+        assert_eq!(blocked_with_a, vec![b.id]);
+        assert_eq!(found_unfiltered, 1);
+        assert_eq!(found, 0);
+        assert_eq!(found_by_users_search, 0);
+        assert_eq!(spots_for_c, 1);
+        assert_eq!(spots_for_a, 0);
+        assert!(unblocked);
+        assert!(!unblocked_twice);
+        assert_eq!(blocked_after_unblock, 0);
+    }
+}
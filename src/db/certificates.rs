@@ -0,0 +1,173 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::certificate::{ChallengeResultRow, FinalStandingRow};
+
+/// Every participant with progress, ranked as on the leaderboard, with what
+/// the completion check needs.
+pub async fn get_final_standings(
+    pool: &PgPool,
+    challenge_id: Uuid,
+) -> Result<Vec<FinalStandingRow>, AppError> {
+    let rows = sqlx::query_as::<_, FinalStandingRow>(
+        r#"
+        SELECT
+            p.callsign,
+            p.score,
+            RANK() OVER (ORDER BY p.score DESC, p.updated_at ASC) as rank,
+            p.completed_goals,
+            p.current_value,
+            cp.status as participation_status,
+            p.updated_at
+        FROM progress p
+        LEFT JOIN challenge_participants cp
+            ON cp.challenge_id = p.challenge_id AND cp.callsign = p.callsign
+        WHERE p.challenge_id = $1
+        ORDER BY p.score DESC, p.updated_at ASC
+        "#,
+    )
+    .bind(challenge_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Store a challenge's final results, each standing with whether it
+/// completed the challenge. Does nothing once the challenge has results, so
+/// the first finalization is the one that stays. Returns count of stored rows.
+pub async fn insert_challenge_results(
+    pool: &PgPool,
+    challenge_id: Uuid,
+    standings: &[(FinalStandingRow, bool)],
+) -> Result<u64, AppError> {
+    let callsigns: Vec<&str> = standings.iter().map(|(s, _)| s.callsign.as_str()).collect();
+    let scores: Vec<i32> = standings.iter().map(|(s, _)| s.score).collect();
+    let ranks: Vec<i64> = standings.iter().map(|(s, _)| s.rank).collect();
+    let completed: Vec<bool> = standings.iter().map(|&(_, done)| done).collect();
+    let reported_at: Vec<_> = standings.iter().map(|(s, _)| s.updated_at).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO challenge_results
+            (challenge_id, callsign, score, rank, completed, last_reported_at)
+        SELECT $1, r.callsign, r.score, r.rank, r.completed, r.last_reported_at
+        FROM UNNEST($2::text[], $3::int[], $4::bigint[], $5::bool[], $6::timestamptz[])
+            AS r(callsign, score, rank, completed, last_reported_at)
+        WHERE NOT EXISTS (SELECT 1 FROM challenge_results WHERE challenge_id = $1)
+        ON CONFLICT (challenge_id, callsign) DO NOTHING
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(&callsigns)
+    .bind(&scores)
+    .bind(&ranks)
+    .bind(&completed)
+    .bind(&reported_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// A challenge's stored final results, best rank first; empty until it is
+/// finalized.
+pub async fn get_challenge_results(
+    pool: &PgPool,
+    challenge_id: Uuid,
+) -> Result<Vec<ChallengeResultRow>, AppError> {
+    let rows = sqlx::query_as::<_, ChallengeResultRow>(
+        r#"
+        SELECT callsign, score, rank, completed, last_reported_at, finalized_at
+        FROM challenge_results
+        WHERE challenge_id = $1
+        ORDER BY rank, callsign
+        "#,
+    )
+    .bind(challenge_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use crate::models::CreateChallengeRequest;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn results_are_written_once() {
+        let pool = test_support::pool().await;
+        let (leader, runner_up) = (test_support::callsign(), test_support::callsign());
+        let challenge_id = crate::db::create_challenge(
+            &pool,
+            &CreateChallengeRequest {
+                name: format!("Results test {}", Uuid::new_v4()),
+                description: "Final results".to_string(),
+                author: None,
+                category: "award".to_string(),
+                challenge_type: "collection".to_string(),
+                configuration: serde_json::json!({}),
+                invite_config: None,
+                hamalert_config: None,
+                is_active: Some(false),
+            },
+        )
+        .await
+        .unwrap()
+        .id;
+        let set_score = |callsign: String, score: i32| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query(
+                    "INSERT INTO progress (id, challenge_id, callsign, score) VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (challenge_id, callsign)
+                     DO UPDATE SET score = EXCLUDED.score, updated_at = now()",
+                )
+                .bind(Uuid::new_v4())
+                .bind(challenge_id)
+                .bind(&callsign)
+                .bind(score)
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        };
+        set_score(leader.clone(), 9).await;
+        set_score(runner_up.clone(), 5).await;
+
+        let standings = get_final_standings(&pool, challenge_id).await.unwrap();
+        let marked: Vec<_> = standings.into_iter().map(|s| (s, true)).collect();
+        let stored = insert_challenge_results(&pool, challenge_id, &marked)
+            .await
+            .unwrap();
+        // A late report overtakes the leader, but the results are already final
+        set_score(runner_up.clone(), 20).await;
+        let standings = get_final_standings(&pool, challenge_id).await.unwrap();
+        let remarked: Vec<_> = standings.into_iter().map(|s| (s, false)).collect();
+        let restored = insert_challenge_results(&pool, challenge_id, &remarked)
+            .await
+            .unwrap();
+        let results = get_challenge_results(&pool, challenge_id).await.unwrap();
+
+        sqlx::query("DELETE FROM challenges WHERE id = $1")
+            .bind(challenge_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!((stored, restored), (2, 0));
+        let standings: Vec<_> = results
+            .iter()
+            .map(|r| (r.callsign.clone(), r.score, r.rank, r.completed))
+            .collect();
+        assert_eq!(
+            standings,
+            vec![(leader, 9, 1, true), (runner_up, 5, 2, true)]
+        );
+    }
+}
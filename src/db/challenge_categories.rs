@@ -0,0 +1,154 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::challenge_category::{CategoryRow, UpdateCategoryRequest};
+
+/// All categories in display order with challenge counts. With `active_only`,
+/// only active challenges are counted.
+pub async fn list_categories(
+    pool: &PgPool,
+    active_only: bool,
+) -> Result<Vec<CategoryRow>, AppError> {
+    let rows = sqlx::query_as::<_, CategoryRow>(
+        r#"
+        SELECT cc.name, cc.description, cc.sort_order, cc.updated_at,
+               COUNT(c.id) AS challenge_count
+        FROM challenge_categories cc
+        LEFT JOIN challenges c
+          ON c.category = cc.name AND (NOT $1 OR c.is_active)
+        GROUP BY cc.name
+        ORDER BY cc.sort_order, cc.name
+        "#,
+    )
+    .bind(active_only)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Look up a category by name, case-insensitively.
+pub async fn get_category(pool: &PgPool, name: &str) -> Result<Option<CategoryRow>, AppError> {
+    let row = sqlx::query_as::<_, CategoryRow>(
+        r#"
+        SELECT cc.name, cc.description, cc.sort_order, cc.updated_at,
+               (SELECT COUNT(*) FROM challenges c WHERE c.category = cc.name) AS challenge_count
+        FROM challenge_categories cc
+        WHERE lower(cc.name) = lower($1)
+        "#,
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Canonical spelling of a category name, matched case-insensitively.
+pub async fn find_canonical_category(
+    pool: &PgPool,
+    name: &str,
+) -> Result<Option<String>, AppError> {
+    let canonical = sqlx::query_scalar::<_, String>(
+        "SELECT name FROM challenge_categories WHERE lower(name) = lower($1)",
+    )
+    .bind(name.trim())
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(canonical)
+}
+
+pub async fn list_category_names(pool: &PgPool) -> Result<Vec<String>, AppError> {
+    let names = sqlx::query_scalar::<_, String>(
+        "SELECT name FROM challenge_categories ORDER BY sort_order, name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(names)
+}
+
+pub async fn create_category(
+    pool: &PgPool,
+    name: &str,
+    description: Option<&str>,
+    sort_order: i32,
+) -> Result<CategoryRow, AppError> {
+    let row = sqlx::query_as::<_, CategoryRow>(
+        r#"
+        INSERT INTO challenge_categories (name, description, sort_order)
+        VALUES ($1, $2, $3)
+        RETURNING name, description, sort_order, updated_at, 0::bigint AS challenge_count
+        "#,
+    )
+    .bind(name)
+    .bind(description)
+    .bind(sort_order)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Update a category by its exact name. A new name cascades to challenges
+/// through the foreign key.
+pub async fn update_category(
+    pool: &PgPool,
+    name: &str,
+    req: &UpdateCategoryRequest,
+) -> Result<Option<CategoryRow>, AppError> {
+    let row = sqlx::query_as::<_, CategoryRow>(
+        r#"
+        UPDATE challenge_categories SET
+            name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            sort_order = COALESCE($4, sort_order),
+            updated_at = now()
+        WHERE name = $1
+        RETURNING name, description, sort_order, updated_at,
+                  (SELECT COUNT(*) FROM challenges c WHERE c.category = $1) AS challenge_count
+        "#,
+    )
+    .bind(name)
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(req.description.as_deref())
+    .bind(req.sort_order)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Delete an unused category by its exact name. Returns true if deleted.
+pub async fn delete_category(pool: &PgPool, name: &str) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM challenge_categories WHERE name = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Move every challenge in `from` to `into` and delete `from`, atomically.
+/// Both are exact (canonical) names. Returns the number of challenges moved.
+pub async fn merge_categories(pool: &PgPool, from: &str, into: &str) -> Result<u64, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let moved =
+        sqlx::query("UPDATE challenges SET category = $2, updated_at = now() WHERE category = $1")
+            .bind(from)
+            .bind(into)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+    sqlx::query("DELETE FROM challenge_categories WHERE name = $1")
+        .bind(from)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(moved)
+}
@@ -106,10 +106,14 @@ pub async fn create_challenge(
     Ok(challenge)
 }
 
+/// Replace a challenge and bump its version. With `expected_versions`, only
+/// a challenge currently at one of those versions is updated; `None` is
+/// returned for a missing challenge and for one at another version alike.
 pub async fn update_challenge(
     pool: &PgPool,
     id: Uuid,
     req: &CreateChallengeRequest,
+    expected_versions: Option<&[i32]>,
 ) -> Result<Option<Challenge>, AppError> {
     let challenge = sqlx::query_as::<_, Challenge>(
         r#"
@@ -118,7 +122,7 @@ pub async fn update_challenge(
             challenge_type = $6, configuration = $7, invite_config = $8,
             hamalert_config = $9, is_active = COALESCE($10, is_active),
             version = version + 1, updated_at = now()
-        WHERE id = $1
+        WHERE id = $1 AND ($11::int[] IS NULL OR version = ANY($11))
         RETURNING id, version, name, description, author, category, challenge_type,
                   configuration, invite_config, hamalert_config, is_active,
                   created_at, updated_at
@@ -134,6 +138,7 @@ pub async fn update_challenge(
     .bind(&req.invite_config)
     .bind(&req.hamalert_config)
     .bind(req.is_active)
+    .bind(expected_versions)
     .fetch_optional(pool)
     .await?;
 
@@ -0,0 +1,259 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::digest::{ActivityCountRow, DigestStandingRow, FriendActivityRow};
+use crate::models::User;
+
+/// A user's own activities in `[start, end)`, counted per activity type.
+pub async fn get_digest_activity_counts(
+    pool: &PgPool,
+    user_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<ActivityCountRow>, AppError> {
+    let rows = sqlx::query_as::<_, ActivityCountRow>(
+        r#"
+        SELECT activity_type, COUNT(*) AS count
+        FROM activities
+        WHERE user_id = $1 AND timestamp >= $2 AND timestamp < $3
+        GROUP BY activity_type
+        "#,
+    )
+    .bind(user_id)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Activities of a user's friends in `[start, end)`, counted per friend and
+/// activity type. Suspended friends are skipped, as in the feed.
+pub async fn get_digest_friend_activity(
+    pool: &PgPool,
+    user_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<FriendActivityRow>, AppError> {
+    let rows = sqlx::query_as::<_, FriendActivityRow>(
+        r#"
+        SELECT u.callsign, a.activity_type, COUNT(*) AS count
+        FROM activities a
+        JOIN friendships f ON f.friend_id = a.user_id
+        JOIN users u ON u.id = a.user_id
+        WHERE f.user_id = $1
+          AND u.suspended_at IS NULL
+          AND a.timestamp >= $2 AND a.timestamp < $3
+        GROUP BY u.callsign, a.activity_type
+        "#,
+    )
+    .bind(user_id)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Number of challenges whose progress for `callsign` was updated in `[start, end)`.
+pub async fn count_challenges_progressed(
+    pool: &PgPool,
+    callsign: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<i64, AppError> {
+    let count: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM progress
+        WHERE callsign = $1 AND updated_at >= $2 AND updated_at < $3
+        "#,
+    )
+    .bind(callsign.to_uppercase())
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count.0)
+}
+
+/// Per-challenge rank for `callsign` from the last `rank_history` sample in
+/// `[first_day, last_day]`, with the last sample before `first_day` as the
+/// previous rank. Challenges without a sample in the week are left out.
+pub async fn get_digest_standings(
+    pool: &PgPool,
+    callsign: &str,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+) -> Result<Vec<DigestStandingRow>, AppError> {
+    let rows = sqlx::query_as::<_, DigestStandingRow>(
+        r#"
+        WITH week_end AS (
+            SELECT DISTINCT ON (challenge_id) challenge_id, rank, score
+            FROM rank_history
+            WHERE callsign = $1 AND sampled_on BETWEEN $2 AND $3
+            ORDER BY challenge_id, sampled_on DESC
+        ),
+        week_start AS (
+            SELECT DISTINCT ON (challenge_id) challenge_id, rank
+            FROM rank_history
+            WHERE callsign = $1 AND sampled_on < $2
+            ORDER BY challenge_id, sampled_on DESC
+        )
+        SELECT e.challenge_id, c.name, e.rank, e.score, s.rank AS previous_rank
+        FROM week_end e
+        JOIN challenges c ON c.id = e.challenge_id
+        LEFT JOIN week_start s ON s.challenge_id = e.challenge_id
+        ORDER BY e.rank, c.name
+        "#,
+    )
+    .bind(callsign.to_uppercase())
+    .bind(first_day)
+    .bind(last_day)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Sample today's leaderboard rank for every participant in an active
+/// challenge. A second sample on the same day is ignored. Returns the
+/// number of rows recorded.
+pub async fn record_rank_history(pool: &PgPool) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO rank_history (challenge_id, callsign, sampled_on, rank, score)
+        SELECT p.challenge_id, p.callsign, (now() AT TIME ZONE 'UTC')::date,
+               RANK() OVER (PARTITION BY p.challenge_id ORDER BY p.score DESC, p.updated_at ASC),
+               p.score
+        FROM progress p
+        JOIN challenges c ON c.id = p.challenge_id
+        WHERE c.is_active
+        ON CONFLICT (challenge_id, callsign, sampled_on) DO NOTHING
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Users who get a precomputed digest: everyone not suspended.
+pub async fn list_digest_users(pool: &PgPool) -> Result<Vec<User>, AppError> {
+    let users = sqlx::query_as::<_, User>(
+        r#"
+        SELECT id, callsign, created_at
+        FROM users
+        WHERE suspended_at IS NULL
+        ORDER BY callsign
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+/// Store a user's digest for `week`, replacing an earlier one.
+pub async fn upsert_weekly_digest(
+    pool: &PgPool,
+    user_id: Uuid,
+    week: &str,
+    digest: &serde_json::Value,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO weekly_digests (user_id, week, digest)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, week)
+        DO UPDATE SET digest = EXCLUDED.digest, computed_at = now()
+        "#,
+    )
+    .bind(user_id)
+    .bind(week)
+    .bind(digest)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn standings_compare_week_end_with_earlier_sample() {
+        let pool = test_support::pool().await;
+        let challenge_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO challenges (id, name, description, category, challenge_type, configuration)
+             VALUES ($1, 'Digest Test', '', 'award', 'cumulative', '{}')",
+        )
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let first_day: NaiveDate = "2024-08-05".parse().unwrap();
+        let last_day: NaiveDate = "2024-08-11".parse().unwrap();
+        // K1DROP was 1st before the week and 3rd by its end; K1NEW was
+        // first ranked mid-week
+        for (callsign, day, rank) in [
+            ("K1DROP", "2024-08-01", 1),
+            ("K1DROP", "2024-08-07", 2),
+            ("K1DROP", "2024-08-11", 3),
+            ("K1DROP", "2024-08-12", 5),
+            ("K1NEW", "2024-08-09", 4),
+        ] {
+            sqlx::query(
+                "INSERT INTO rank_history (challenge_id, callsign, sampled_on, rank, score)
+                 VALUES ($1, $2, $3::date, $4, 0)",
+            )
+            .bind(challenge_id)
+            .bind(callsign)
+            .bind(day)
+            .bind(rank)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let dropped = get_digest_standings(&pool, "k1drop", first_day, last_day)
+            .await
+            .unwrap();
+        let new = get_digest_standings(&pool, "K1NEW", first_day, last_day)
+            .await
+            .unwrap();
+        let unranked = get_digest_standings(&pool, "K1NONE", first_day, last_day)
+            .await
+            .unwrap();
+
+        sqlx::query("DELETE FROM challenges WHERE id = $1")
+            .bind(challenge_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let dropped: Vec<_> = dropped
+            .into_iter()
+            .filter(|row| row.challenge_id == challenge_id)
+            .collect();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!((dropped[0].rank, dropped[0].previous_rank), (3, Some(1)));
+
+        let new: Vec<_> = new
+            .into_iter()
+            .filter(|row| row.challenge_id == challenge_id)
+            .collect();
+        assert_eq!((new[0].rank, new[0].previous_rank), (4, None));
+
+        assert!(unranked.is_empty());
+    }
+}
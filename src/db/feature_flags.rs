@@ -0,0 +1,52 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::feature_flag::FeatureFlagRow;
+
+/// Insert any flags that don't have a row yet. Existing rows keep their
+/// admin-set value.
+pub async fn seed_feature_flags(pool: &PgPool, defaults: &[(&str, bool)]) -> Result<(), AppError> {
+    let (names, enabled): (Vec<&str>, Vec<bool>) = defaults.iter().copied().unzip();
+    sqlx::query(
+        r#"
+        INSERT INTO feature_flags (name, enabled)
+        SELECT * FROM UNNEST($1::text[], $2::bool[])
+        ON CONFLICT (name) DO NOTHING
+        "#,
+    )
+    .bind(&names)
+    .bind(&enabled)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn list_feature_flags(pool: &PgPool) -> Result<Vec<FeatureFlagRow>, AppError> {
+    let rows = sqlx::query_as::<_, FeatureFlagRow>(
+        "SELECT name, enabled, updated_at FROM feature_flags ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Set several flags in one statement, creating rows as needed.
+pub async fn set_feature_flags(pool: &PgPool, flags: &[(&str, bool)]) -> Result<(), AppError> {
+    let (names, enabled): (Vec<&str>, Vec<bool>) = flags.iter().copied().unzip();
+    sqlx::query(
+        r#"
+        INSERT INTO feature_flags (name, enabled)
+        SELECT * FROM UNNEST($1::text[], $2::bool[])
+        ON CONFLICT (name) DO UPDATE
+        SET enabled = EXCLUDED.enabled, updated_at = now()
+        "#,
+    )
+    .bind(&names)
+    .bind(&enabled)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
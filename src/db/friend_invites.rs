@@ -1,47 +1,79 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{Duration, Utc};
-use rand::Rng;
+use rand::RngCore;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppError;
 use crate::models::FriendInvite;
 
-fn generate_friend_invite_token() -> String {
-    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-    let mut rng = rand::thread_rng();
-
-    let token: String = (0..24)
-        .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect();
+/// Attempts at a fresh token before a unique-key collision is reported.
+const TOKEN_ATTEMPTS: usize = 3;
 
-    format!("inv_{}", token)
+/// `inv_` plus 18 random bytes from the thread CSPRNG as URL-safe base64.
+fn generate_friend_invite_token() -> String {
+    let mut bytes = [0u8; 18];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("inv_{}", URL_SAFE_NO_PAD.encode(bytes))
 }
 
+/// Create an invite expiring in `expiry_days`, retrying with a new token if
+/// one collides with an existing invite.
 pub async fn create_friend_invite(
     pool: &PgPool,
     user_id: Uuid,
     expiry_days: i64,
+    note: Option<&str>,
 ) -> Result<FriendInvite, AppError> {
-    let token = generate_friend_invite_token();
     let expires_at = Utc::now() + Duration::days(expiry_days);
 
-    let invite = sqlx::query_as::<_, FriendInvite>(
+    let mut attempt = 1;
+    loop {
+        let result = sqlx::query_as::<_, FriendInvite>(
+            r#"
+            INSERT INTO friend_invites (token, user_id, expires_at, note)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, token, user_id, created_at, expires_at, used_at, used_by_user_id, note
+            "#,
+        )
+        .bind(generate_friend_invite_token())
+        .bind(user_id)
+        .bind(expires_at)
+        .bind(note)
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Err(sqlx::Error::Database(e))
+                if e.is_unique_violation() && attempt < TOKEN_ATTEMPTS =>
+            {
+                tracing::warn!("Friend invite token collision, retrying");
+                attempt += 1;
+            }
+            result => return Ok(result?),
+        }
+    }
+}
+
+/// Invites from `user_id` that are neither used nor expired.
+pub async fn count_outstanding_friend_invites(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<i64, AppError> {
+    let count: i64 = sqlx::query_scalar(
         r#"
-        INSERT INTO friend_invites (token, user_id, expires_at)
-        VALUES ($1, $2, $3)
-        RETURNING id, token, user_id, created_at, expires_at, used_at, used_by_user_id
+        SELECT COUNT(*)
+        FROM friend_invites
+        WHERE user_id = $1
+          AND expires_at > now()
+          AND used_at IS NULL
         "#,
     )
-    .bind(&token)
     .bind(user_id)
-    .bind(expires_at)
     .fetch_one(pool)
     .await?;
 
-    Ok(invite)
+    Ok(count)
 }
 
 pub async fn get_friend_invite(
@@ -50,7 +82,7 @@ pub async fn get_friend_invite(
 ) -> Result<Option<FriendInvite>, AppError> {
     let invite = sqlx::query_as::<_, FriendInvite>(
         r#"
-        SELECT id, token, user_id, created_at, expires_at, used_at, used_by_user_id
+        SELECT id, token, user_id, created_at, expires_at, used_at, used_by_user_id, note
         FROM friend_invites
         WHERE token = $1
         "#,
@@ -68,7 +100,7 @@ pub async fn get_valid_friend_invite(
 ) -> Result<Option<FriendInvite>, AppError> {
     let invite = sqlx::query_as::<_, FriendInvite>(
         r#"
-        SELECT id, token, user_id, created_at, expires_at, used_at, used_by_user_id
+        SELECT id, token, user_id, created_at, expires_at, used_at, used_by_user_id, note
         FROM friend_invites
         WHERE token = $1
           AND expires_at > now()
@@ -92,7 +124,7 @@ pub async fn mark_invite_used(
         UPDATE friend_invites
         SET used_at = now(), used_by_user_id = $2
         WHERE token = $1
-        RETURNING id, token, user_id, created_at, expires_at, used_at, used_by_user_id
+        RETURNING id, token, user_id, created_at, expires_at, used_at, used_by_user_id, note
         "#,
     )
     .bind(token)
@@ -103,7 +135,7 @@ pub async fn mark_invite_used(
     Ok(invite)
 }
 
-#[allow(dead_code)]
+/// Delete invites that expired or were used more than 30 days ago.
 pub async fn cleanup_expired_invites(pool: &PgPool) -> Result<u64, AppError> {
     let result = sqlx::query(
         r#"
@@ -117,3 +149,41 @@ pub async fn cleanup_expired_invites(pool: &PgPool) -> Result<u64, AppError> {
 
     Ok(result.rows_affected())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn invites_keep_their_note_and_count_while_outstanding() {
+        let pool = test_support::pool().await;
+        let user = test_support::users(&pool, 1).await.remove(0);
+
+        // Base64 tokens satisfy the table's token format check
+        let mut tokens = Vec::new();
+        for _ in 0..20 {
+            let invite = create_friend_invite(&pool, user.id, 3, Some("met you at the hamfest"))
+                .await
+                .unwrap();
+            tokens.push(invite.token);
+        }
+        let fetched = get_friend_invite(&pool, &tokens[0]).await.unwrap().unwrap();
+        let outstanding = count_outstanding_friend_invites(&pool, user.id)
+            .await
+            .unwrap();
+        mark_invite_used(&pool, &tokens[0], user.id).await.unwrap();
+        let after_use = count_outstanding_friend_invites(&pool, user.id)
+            .await
+            .unwrap();
+
+        test_support::cleanup_users(&pool, [user.id]).await;
+        assert_eq!(fetched.note.as_deref(), Some("met you at the hamfest"));
+        assert!(tokens
+            .iter()
+            .all(|t| t.starts_with("inv_") && t.len() == 28));
+        assert_eq!(outstanding, 20);
+        assert_eq!(after_use, 19);
+    }
+}
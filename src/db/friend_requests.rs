@@ -1,9 +1,12 @@
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppError;
 use crate::models::{FriendRequest, FriendRequestWithCallsigns, Friendship};
 
+/// Create a pending request. A sender's earlier request to the same user,
+/// declined or accepted before an unfriend, is reopened in place.
 pub async fn create_friend_request(
     pool: &PgPool,
     from_user_id: Uuid,
@@ -13,6 +16,8 @@ pub async fn create_friend_request(
         r#"
         INSERT INTO friend_requests (from_user_id, to_user_id)
         VALUES ($1, $2)
+        ON CONFLICT (from_user_id, to_user_id) DO UPDATE
+        SET status = 'pending', requested_at = now(), responded_at = NULL
         RETURNING
             friend_requests.id,
             friend_requests.from_user_id,
@@ -72,6 +77,28 @@ pub async fn get_pending_request_between(
     Ok(request)
 }
 
+/// When the recipient last declined a request from this sender, if they did.
+pub async fn get_declined_at(
+    pool: &PgPool,
+    from_user_id: Uuid,
+    to_user_id: Uuid,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let declined_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+        r#"
+        SELECT responded_at
+        FROM friend_requests
+        WHERE from_user_id = $1 AND to_user_id = $2
+          AND status = 'declined' AND responded_at IS NOT NULL
+        "#,
+    )
+    .bind(from_user_id)
+    .bind(to_user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(declined_at)
+}
+
 pub async fn are_friends(
     pool: &PgPool,
     user_id_1: Uuid,
@@ -93,9 +120,14 @@ pub async fn are_friends(
     Ok(friendship.is_some())
 }
 
+/// Accept a pending request, creating the friendship in both directions and
+/// a `friendAdded` activity for each user.
+/// Refused with a validation error, leaving the request pending, when either
+/// user already has `max_friends` friends.
 pub async fn accept_friend_request(
     pool: &PgPool,
     request_id: Uuid,
+    max_friends: i64,
 ) -> Result<Option<FriendRequestWithCallsigns>, AppError> {
     let mut tx = pool.begin().await?;
 
@@ -121,8 +153,24 @@ pub async fn accept_friend_request(
     .await?;
 
     if let Some(ref req) = request {
+        let full =
+            users_at_friend_limit(&mut tx, req.from_user_id, req.to_user_id, max_friends).await?;
+        if full.contains(&req.to_user_id) {
+            return Err(AppError::Validation {
+                message: format!("You already have the maximum of {max_friends} friends"),
+            });
+        }
+        if full.contains(&req.from_user_id) {
+            return Err(AppError::Validation {
+                message: format!(
+                    "{} already has the maximum of {max_friends} friends",
+                    req.from_callsign
+                ),
+            });
+        }
+
         // Create bidirectional friendship entries
-        sqlx::query(
+        let created = sqlx::query(
             r#"
             INSERT INTO friendships (user_id, friend_id)
             VALUES ($1, $2), ($2, $1)
@@ -133,12 +181,64 @@ pub async fn accept_friend_request(
         .bind(req.to_user_id)
         .execute(&mut *tx)
         .await?;
+
+        if created.rows_affected() > 0 {
+            sqlx::query(
+                r#"
+                INSERT INTO activities (user_id, callsign, activity_type, timestamp, details)
+                VALUES
+                    ($1, $2, 'friendAdded', now(),
+                     jsonb_build_object('friendUserId', $3::uuid, 'friendCallsign', $4::text)),
+                    ($3, $4, 'friendAdded', now(),
+                     jsonb_build_object('friendUserId', $1::uuid, 'friendCallsign', $2::text))
+                "#,
+            )
+            .bind(req.from_user_id)
+            .bind(&req.from_callsign)
+            .bind(req.to_user_id)
+            .bind(&req.to_callsign)
+            .execute(&mut *tx)
+            .await?;
+        }
     }
 
     tx.commit().await?;
     Ok(request)
 }
 
+/// Which of two users already have `max_friends` friends, not counting each
+/// other. Locks both user rows so concurrent accepts can't both slip under
+/// the limit.
+async fn users_at_friend_limit(
+    conn: &mut sqlx::PgConnection,
+    user_id_1: Uuid,
+    user_id_2: Uuid,
+    max_friends: i64,
+) -> Result<Vec<Uuid>, AppError> {
+    sqlx::query("SELECT id FROM users WHERE id IN ($1, $2) ORDER BY id FOR UPDATE")
+        .bind(user_id_1)
+        .bind(user_id_2)
+        .execute(&mut *conn)
+        .await?;
+
+    let full = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT user_id
+        FROM friendships
+        WHERE user_id IN ($1, $2) AND friend_id NOT IN ($1, $2)
+        GROUP BY user_id
+        HAVING COUNT(*) >= $3
+        "#,
+    )
+    .bind(user_id_1)
+    .bind(user_id_2)
+    .bind(max_friends)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(full)
+}
+
 pub async fn decline_friend_request(
     pool: &PgPool,
     request_id: Uuid,
@@ -297,3 +397,95 @@ pub async fn remove_friendship(
 
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn accept_stops_at_the_friend_limit() {
+        let pool = test_support::pool().await;
+        let users = test_support::users(&pool, 3).await;
+        let (a, b, c) = (&users[0], &users[1], &users[2]);
+
+        // A's first friend fits under a limit of one; the second doesn't
+        let first = create_friend_request(&pool, b.id, a.id).await.unwrap();
+        let accepted = accept_friend_request(&pool, first.id, 1).await;
+        let second = create_friend_request(&pool, c.id, a.id).await.unwrap();
+        let over_limit = accept_friend_request(&pool, second.id, 1).await;
+        let still_pending = get_friend_request(&pool, second.id).await.unwrap();
+        let friends_at_limit = get_friends_for_user(&pool, a.id).await.unwrap().len();
+        let under_raised_limit = accept_friend_request(&pool, second.id, 2).await;
+
+        test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+        assert!(accepted.unwrap().is_some());
+        assert!(matches!(over_limit, Err(AppError::Validation { .. })));
+        assert_eq!(still_pending.unwrap().status, "pending");
+        assert_eq!(friends_at_limit, 1);
+        assert!(under_raised_limit.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn request_moves_through_decline_resend_accept_and_unfriend() {
+        let pool = test_support::pool().await;
+        let users = test_support::users(&pool, 2).await;
+        let (a, b) = (&users[0], &users[1]);
+        let friend_added = |user_id: Uuid| {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM activities WHERE user_id = $1 AND activity_type = 'friendAdded'",
+            )
+            .bind(user_id)
+            .fetch_one(&pool)
+        };
+
+        // pending -> declined; a declined request can't be answered again
+        let sent = create_friend_request(&pool, a.id, b.id).await.unwrap();
+        let declined = decline_friend_request(&pool, sent.id).await.unwrap();
+        let declined_at = get_declined_at(&pool, a.id, b.id).await.unwrap();
+        let declined_twice = decline_friend_request(&pool, sent.id).await.unwrap();
+        let accepted_after_decline = accept_friend_request(&pool, sent.id, 500).await.unwrap();
+
+        // Re-sending reopens the same row; accepting befriends both ways once
+        let resent = create_friend_request(&pool, a.id, b.id).await.unwrap();
+        let declined_after_resend = get_declined_at(&pool, a.id, b.id).await.unwrap();
+        let accepted = accept_friend_request(&pool, resent.id, 500).await.unwrap();
+        let accepted_twice = accept_friend_request(&pool, resent.id, 500).await.unwrap();
+        let friends = are_friends(&pool, b.id, a.id).await.unwrap();
+        let added = (
+            friend_added(a.id).await.unwrap(),
+            friend_added(b.id).await.unwrap(),
+        );
+
+        // After an unfriend the pair can go through it again
+        let friendship_id = get_friends_for_user(&pool, a.id).await.unwrap()[0].friendship_id;
+        let removed = remove_friendship(&pool, friendship_id, a.id).await.unwrap();
+        let friends_after_remove = are_friends(&pool, a.id, b.id).await.unwrap();
+        let reopened = create_friend_request(&pool, a.id, b.id).await.unwrap();
+        let reaccepted = accept_friend_request(&pool, reopened.id, 500)
+            .await
+            .unwrap();
+        let added_again = friend_added(b.id).await.unwrap();
+
+        test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+        assert_eq!(sent.status, "pending");
+        assert_eq!(declined.unwrap().status, "declined");
+        assert!(declined_at.is_some());
+        assert!(declined_twice.is_none());
+        assert!(accepted_after_decline.is_none());
+        assert_eq!(resent.id, sent.id);
+        assert_eq!(resent.status, "pending");
+        assert!(declined_after_resend.is_none());
+        assert_eq!(accepted.unwrap().status, "accepted");
+        assert!(accepted_twice.is_none());
+        assert!(friends);
+        assert_eq!(added, (1, 1));
+        assert!(removed);
+        assert!(!friends_after_remove);
+        assert_eq!(reopened.status, "pending");
+        assert!(reaccepted.is_some());
+        assert_eq!(added_again, 2);
+    }
+}
@@ -0,0 +1,79 @@
+use sqlx::PgPool;
+
+use crate::auth::generate_integration_key;
+use crate::error::AppError;
+use crate::models::integration_key::IntegrationKey;
+
+pub async fn create_integration_key(
+    pool: &PgPool,
+    partner: &str,
+) -> Result<IntegrationKey, AppError> {
+    let key = sqlx::query_as::<_, IntegrationKey>(
+        r#"
+        INSERT INTO integration_keys (partner, key)
+        VALUES ($1, $2)
+        RETURNING id, partner, key, created_at, last_used_at, revoked_at
+        "#,
+    )
+    .bind(partner)
+    .bind(generate_integration_key())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(key)
+}
+
+/// Look up an unrevoked key and note that it was used.
+pub async fn use_integration_key(
+    pool: &PgPool,
+    key: &str,
+) -> Result<Option<IntegrationKey>, AppError> {
+    let key = sqlx::query_as::<_, IntegrationKey>(
+        r#"
+        UPDATE integration_keys
+        SET last_used_at = now()
+        WHERE key = $1 AND revoked_at IS NULL
+        RETURNING id, partner, key, created_at, last_used_at, revoked_at
+        "#,
+    )
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn only_known_unrevoked_keys_are_accepted() {
+        let pool = test_support::pool().await;
+        let created = create_integration_key(&pool, "Test Logger").await.unwrap();
+
+        let found = use_integration_key(&pool, &created.key).await.unwrap();
+        let unknown = use_integration_key(&pool, &generate_integration_key())
+            .await
+            .unwrap();
+        sqlx::query("UPDATE integration_keys SET revoked_at = now() WHERE id = $1")
+            .bind(created.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let revoked = use_integration_key(&pool, &created.key).await.unwrap();
+
+        sqlx::query("DELETE FROM integration_keys WHERE id = $1")
+            .bind(created.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let found = found.unwrap();
+        assert_eq!(found.partner, "Test Logger");
+        assert!(found.last_used_at.is_some());
+        assert!(unknown.is_none());
+        assert!(revoked.is_none());
+    }
+}
@@ -0,0 +1,116 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::maintenance::{MaintenanceRunRow, TableStatRow};
+
+/// Claim the current UTC hour for a maintenance run. Returns `None` when a run
+/// already exists for this hour (e.g. before a restart, or on another instance).
+pub async fn claim_maintenance_run(pool: &PgPool) -> Result<Option<Uuid>, AppError> {
+    let id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO maintenance_runs (run_hour)
+        VALUES (date_trunc('hour', now()))
+        ON CONFLICT (run_hour) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(id)
+}
+
+pub async fn finish_maintenance_run(
+    pool: &PgPool,
+    id: Uuid,
+    pruned: &serde_json::Value,
+    error: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE maintenance_runs SET finished_at = now(), pruned = $2, error = $3 WHERE id = $1",
+    )
+    .bind(id)
+    .bind(pruned)
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Refresh planner statistics for every table in the database.
+pub async fn analyze_database(pool: &PgPool) -> Result<(), AppError> {
+    sqlx::query("ANALYZE").execute(pool).await?;
+    Ok(())
+}
+
+/// Delete rows whose `column` is older than `days`. Only called with the
+/// fixed table list in `crate::maintenance`, never with user input.
+pub async fn prune_older_than(
+    pool: &PgPool,
+    table: &str,
+    column: &str,
+    days: i64,
+) -> Result<u64, AppError> {
+    let result = sqlx::query(&format!(
+        "DELETE FROM {table} WHERE {column} < now() - make_interval(days => $1)"
+    ))
+    .bind(days as i32)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Record live row counts for every application table, as refreshed by the
+/// preceding `ANALYZE`. Returns the number of tables recorded.
+pub async fn record_table_stats(pool: &PgPool, run_id: Uuid) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO table_stats (run_id, table_name, row_count)
+        SELECT $1, relname, n_live_tup
+        FROM pg_stat_user_tables
+        WHERE schemaname = current_schema() AND relname NOT LIKE '\_sqlx%'
+        "#,
+    )
+    .bind(run_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn get_last_maintenance_run(
+    pool: &PgPool,
+) -> Result<Option<MaintenanceRunRow>, AppError> {
+    let row = sqlx::query_as::<_, MaintenanceRunRow>(
+        r#"
+        SELECT id, started_at, finished_at, pruned, error
+        FROM maintenance_runs
+        ORDER BY run_hour DESC
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Row counts recorded by one run, largest first.
+pub async fn list_table_stats(pool: &PgPool, run_id: Uuid) -> Result<Vec<TableStatRow>, AppError> {
+    let rows = sqlx::query_as::<_, TableStatRow>(
+        r#"
+        SELECT table_name, row_count
+        FROM table_stats
+        WHERE run_id = $1
+        ORDER BY row_count DESC, table_name
+        "#,
+    )
+    .bind(run_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
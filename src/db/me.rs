@@ -0,0 +1,115 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::me::ProgramSpotStatsRow;
+
+/// Self-spot counts per program for a user, most recently spotted first.
+pub async fn get_self_spot_stats(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ProgramSpotStatsRow>, AppError> {
+    let rows = sqlx::query_as::<_, ProgramSpotStatsRow>(
+        r#"
+        SELECT program_slug,
+               COUNT(*) AS spot_count,
+               COUNT(*) FILTER (WHERE spotted_at >= now() - interval '30 days') AS spots_last_30_days,
+               MAX(spotted_at) AS last_spotted_at
+        FROM self_spot_history
+        WHERE user_id = $1
+        GROUP BY program_slug
+        ORDER BY last_spotted_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::spots::InsertSelfSpotParams;
+    use crate::db::test_support;
+    use crate::models::spot::{IngestChannel, SpotRow};
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn self_spot_stats_outlive_the_spots() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let (participant, _) = crate::db::get_or_create_participant(&pool, &callsign, None)
+            .await
+            .unwrap();
+        let user = crate::db::get_or_create_user(&pool, &callsign)
+            .await
+            .unwrap();
+        let delete_spots = || {
+            sqlx::query("DELETE FROM spots WHERE callsign = $1")
+                .bind(&callsign)
+                .execute(&pool)
+        };
+        let self_spot = |program_slug: &'static str| {
+            let pool = pool.clone();
+            let callsign = callsign.clone();
+            async move {
+                crate::db::insert_self_spot(
+                    &pool,
+                    &InsertSelfSpotParams {
+                        participant_id: participant.id,
+                        user_id: user.id,
+                        callsign: &callsign,
+                        program_slug,
+                        frequency_khz: 14062.0,
+                        mode: "CW",
+                        raw_mode: None,
+                        reference: None,
+                        country_code: None,
+                        comments: None,
+                        ingest_channel: IngestChannel::default(),
+                    },
+                )
+                .await
+                .unwrap()
+            }
+        };
+        // An older POTA spot from before the 30-day window
+        sqlx::query(
+            "INSERT INTO self_spot_history (user_id, spot_id, program_slug, frequency_khz, mode, spotted_at)
+             VALUES ($1, gen_random_uuid(), 'pota', 7030, 'CW', now() - interval '40 days')",
+        )
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        // Two POTA activations, the first cleaned up before the second, then SOTA
+        self_spot("pota").await;
+        delete_spots().await.unwrap();
+        let last_pota: SpotRow = self_spot("pota").await;
+        let last_sota: SpotRow = self_spot("sota").await;
+        delete_spots().await.unwrap();
+
+        let stats = get_self_spot_stats(&pool, user.id).await.unwrap();
+        test_support::cleanup_users(&pool, [user.id]).await;
+        let summary: Vec<_> = stats
+            .iter()
+            .map(|row| {
+                (
+                    row.program_slug.as_str(),
+                    row.spot_count,
+                    row.spots_last_30_days,
+                    row.last_spotted_at,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("sota", 1, 1, last_sota.spotted_at),
+                ("pota", 3, 2, last_pota.spotted_at),
+            ]
+        );
+    }
+}
@@ -1,34 +1,62 @@
 pub mod activities;
 pub mod badges;
+pub mod blocks;
+pub mod certificates;
+pub mod challenge_categories;
 pub mod challenges;
 pub mod clubs;
 pub mod contest_definitions;
+pub mod digests;
 pub mod equipment;
 pub mod events;
+pub mod feature_flags;
 pub mod friend_invites;
 pub mod friend_requests;
 pub mod historic_trails;
+pub mod integration_keys;
 pub mod invites;
+pub mod maintenance;
+pub mod me;
 pub mod park_boundaries;
 pub mod participants;
 pub mod pota_stats;
+pub mod program_references;
 pub mod programs;
 pub mod progress;
+pub mod search;
 pub mod spot_markers;
+pub mod spot_reports;
 pub mod spots;
+pub mod spots_archive;
+pub mod spots_delta;
 pub mod metrickit_telemetry;
 pub mod equipment_usage;
 pub mod upload_error_telemetry;
 pub mod users;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 pub use activities::*;
 pub use badges::*;
+pub use blocks::*;
+pub use certificates::*;
+pub use challenge_categories::*;
 pub use challenges::*;
+pub use digests::*;
+pub use feature_flags::*;
 pub use friend_invites::*;
 pub use friend_requests::*;
+pub use integration_keys::*;
 pub use invites::*;
+pub use maintenance::*;
+pub use me::*;
 pub use participants::*;
+pub use program_references::*;
 pub use programs::*;
 pub use progress::*;
+pub use search::*;
+pub use spot_reports::*;
 pub use spots::*;
+pub use spots_archive::*;
+pub use spots_delta::*;
 pub use users::*;
@@ -3,6 +3,7 @@ use uuid::Uuid;
 
 use crate::auth::generate_device_token;
 use crate::error::AppError;
+use crate::models::me::{JoinedChallengeRow, MyChallengesQuery};
 use crate::models::{ChallengeParticipant, ChallengeParticipation, Participant};
 
 pub async fn get_or_create_participant(
@@ -94,26 +95,119 @@ pub async fn get_challenges_for_callsign(
     Ok(challenges)
 }
 
+/// Every challenge the callsign has joined and not left, with its progress and
+/// rank, most recently active first, and how many there are in all. Rank is
+/// computed only over the joined challenges' progress rows, in one query.
+pub async fn get_joined_challenges(
+    pool: &PgPool,
+    callsign: &str,
+    query: &MyChallengesQuery,
+) -> Result<(Vec<JoinedChallengeRow>, i64), AppError> {
+    let callsign_upper = callsign.to_uppercase();
+
+    let rows = sqlx::query_as::<_, JoinedChallengeRow>(
+        r#"
+        WITH joined AS (
+            SELECT challenge_id, status, joined_at
+            FROM challenge_participants
+            WHERE callsign = $1 AND status <> 'left'
+        ),
+        ranked AS (
+            SELECT
+                p.challenge_id,
+                p.callsign,
+                p.score,
+                p.completed_goals,
+                p.current_value,
+                p.current_tier,
+                p.updated_at,
+                RANK() OVER (
+                    PARTITION BY p.challenge_id ORDER BY p.score DESC, p.updated_at ASC
+                ) as rank
+            FROM progress p
+            JOIN joined j ON j.challenge_id = p.challenge_id
+        )
+        SELECT
+            c.id as challenge_id,
+            c.name,
+            c.category,
+            c.challenge_type,
+            c.is_active,
+            c.configuration,
+            j.status as participation_status,
+            j.joined_at,
+            r.score,
+            r.rank,
+            r.completed_goals,
+            r.current_value,
+            r.current_tier,
+            GREATEST(j.joined_at, r.updated_at) as last_activity_at
+        FROM joined j
+        JOIN challenges c ON c.id = j.challenge_id
+        LEFT JOIN ranked r ON r.challenge_id = j.challenge_id AND r.callsign = $1
+        WHERE ($2::bool IS NULL OR c.is_active = $2)
+        ORDER BY last_activity_at DESC, c.id
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(&callsign_upper)
+    .bind(query.active)
+    .bind(query.page_limit())
+    .bind(query.page_offset())
+    .fetch_all(pool)
+    .await?;
+
+    // Counted separately so a page past the end still reports the total
+    let total = count_joined_challenges(pool, &callsign_upper, query.active).await?;
+
+    Ok((rows, total))
+}
+
+/// Challenges the callsign has joined and not left, optionally only active ones.
+pub async fn count_joined_challenges(
+    pool: &PgPool,
+    callsign: &str,
+    active: Option<bool>,
+) -> Result<i64, AppError> {
+    let total: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*)
+        FROM challenge_participants cp
+        JOIN challenges c ON c.id = cp.challenge_id
+        WHERE cp.callsign = $1 AND cp.status <> 'left'
+          AND ($2::bool IS NULL OR c.is_active = $2)
+        "#,
+    )
+    .bind(callsign.to_uppercase())
+    .bind(active)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total.0)
+}
+
 pub async fn join_challenge(
     pool: &PgPool,
     challenge_id: Uuid,
     callsign: &str,
     invite_token: Option<&str>,
+    division: Option<&str>,
 ) -> Result<ChallengeParticipant, AppError> {
     let id = Uuid::new_v4();
     let callsign_upper = callsign.to_uppercase();
 
     let participation = sqlx::query_as::<_, ChallengeParticipant>(
         r#"
-        INSERT INTO challenge_participants (id, challenge_id, callsign, invite_token)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id, challenge_id, callsign, invite_token, joined_at, status
+        INSERT INTO challenge_participants (id, challenge_id, callsign, invite_token, division)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, challenge_id, callsign, invite_token, joined_at, status, division
         "#,
     )
     .bind(id)
     .bind(challenge_id)
     .bind(&callsign_upper)
     .bind(invite_token)
+    .bind(division)
     .fetch_one(pool)
     .await
     .map_err(|e| {
@@ -137,7 +231,7 @@ pub async fn get_participation(
 
     let participation = sqlx::query_as::<_, ChallengeParticipant>(
         r#"
-        SELECT id, challenge_id, callsign, invite_token, joined_at, status
+        SELECT id, challenge_id, callsign, invite_token, joined_at, status, division
         FROM challenge_participants
         WHERE challenge_id = $1 AND callsign = $2
         "#,
@@ -150,6 +244,38 @@ pub async fn get_participation(
     Ok(participation)
 }
 
+/// Move an active participant to another division, as long as they haven't
+/// submitted any progress. `None` when there's no active participation or
+/// progress already exists; the check and update are one statement.
+pub async fn set_division(
+    pool: &PgPool,
+    challenge_id: Uuid,
+    callsign: &str,
+    division: &str,
+) -> Result<Option<ChallengeParticipant>, AppError> {
+    let callsign_upper = callsign.to_uppercase();
+
+    let participation = sqlx::query_as::<_, ChallengeParticipant>(
+        r#"
+        UPDATE challenge_participants cp
+        SET division = $3
+        WHERE cp.challenge_id = $1 AND cp.callsign = $2 AND cp.status = 'active'
+          AND NOT EXISTS (
+              SELECT 1 FROM progress p
+              WHERE p.challenge_id = cp.challenge_id AND p.callsign = cp.callsign
+          )
+        RETURNING id, challenge_id, callsign, invite_token, joined_at, status, division
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(&callsign_upper)
+    .bind(division)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(participation)
+}
+
 pub async fn leave_challenge(
     pool: &PgPool,
     challenge_id: Uuid,
@@ -206,3 +332,145 @@ pub async fn refresh_participant_token(
 
     Ok(participant)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use crate::models::CreateChallengeRequest;
+
+    async fn test_challenge(pool: &PgPool, is_active: bool) -> Uuid {
+        crate::db::create_challenge(
+            pool,
+            &CreateChallengeRequest {
+                name: format!("Joined test {}", Uuid::new_v4()),
+                description: "Joined challenges".to_string(),
+                author: None,
+                category: "award".to_string(),
+                challenge_type: "collection".to_string(),
+                configuration: serde_json::json!({}),
+                invite_config: None,
+                hamalert_config: None,
+                is_active: Some(is_active),
+            },
+        )
+        .await
+        .unwrap()
+        .id
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn joined_challenges_exclude_left_and_filter_inactive() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let rival = format!("N{}", &Uuid::new_v4().simple().to_string()[..8]).to_uppercase();
+        let (kept, left, inactive) = (
+            test_challenge(&pool, true).await,
+            test_challenge(&pool, true).await,
+            test_challenge(&pool, false).await,
+        );
+        for challenge_id in [kept, left, inactive] {
+            join_challenge(&pool, challenge_id, &callsign, None, None)
+                .await
+                .unwrap();
+        }
+        join_challenge(&pool, kept, &rival, None, None)
+            .await
+            .unwrap();
+        for (who, score) in [(&callsign, 5), (&rival, 9)] {
+            sqlx::query(
+                "INSERT INTO progress (id, challenge_id, callsign, score) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(kept)
+            .bind(who)
+            .bind(score)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        assert!(leave_challenge(&pool, left, &callsign).await.unwrap());
+
+        let (rows, total) = get_joined_challenges(&pool, &callsign, &MyChallengesQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        let ids: Vec<Uuid> = rows.iter().map(|r| r.challenge_id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&kept) && ids.contains(&inactive));
+        let row = rows.iter().find(|r| r.challenge_id == kept).unwrap();
+        assert_eq!((row.score, row.rank), (Some(5), Some(2)));
+        assert_eq!(row.participation_status, "active");
+
+        let active_only = MyChallengesQuery {
+            active: Some(true),
+            ..Default::default()
+        };
+        let (rows, total) = get_joined_challenges(&pool, &callsign, &active_only)
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = rows.iter().map(|r| r.challenge_id).collect();
+        assert_eq!((ids, total), (vec![kept], 1));
+
+        // A page past the end is empty but still reports the total
+        let past_end = MyChallengesQuery {
+            offset: Some(5),
+            ..Default::default()
+        };
+        let (rows, total) = get_joined_challenges(&pool, &callsign, &past_end)
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+        assert_eq!(total, 2);
+
+        sqlx::query("DELETE FROM challenges WHERE id = ANY($1)")
+            .bind(vec![kept, left, inactive])
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn division_is_locked_once_progress_exists() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let challenge_id = test_challenge(&pool, true).await;
+        join_challenge(&pool, challenge_id, &callsign, None, Some("QRO"))
+            .await
+            .unwrap();
+
+        // Free to switch before any progress
+        let switched = set_division(&pool, challenge_id, &callsign, "QRP")
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO progress (id, challenge_id, callsign, score) VALUES ($1, $2, $3, 4)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(challenge_id)
+        .bind(&callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let locked = set_division(&pool, challenge_id, &callsign, "QRO")
+            .await
+            .unwrap();
+        let stored = get_participation(&pool, challenge_id, &callsign)
+            .await
+            .unwrap()
+            .unwrap();
+        let stranger = set_division(&pool, challenge_id, "N0BODY", "QRP")
+            .await
+            .unwrap();
+        crate::db::delete_challenge(&pool, challenge_id)
+            .await
+            .unwrap();
+
+        assert_eq!(switched.unwrap().division.as_deref(), Some("QRP"));
+        assert!(locked.is_none());
+        assert_eq!(stored.division.as_deref(), Some("QRP"));
+        assert!(stranger.is_none());
+    }
+}
@@ -0,0 +1,185 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::program_reference::{NewProgramReference, ProgramReferenceRow};
+
+/// References written per statement during an import.
+const UPSERT_CHUNK_SIZE: usize = 5000;
+
+/// Insert or update references for a program in one transaction, returning
+/// how many rows were written. Nothing is written when the program doesn't
+/// exist. `references` must not repeat a reference within a chunk.
+pub async fn upsert_program_references(
+    pool: &PgPool,
+    program_slug: &str,
+    references: &[NewProgramReference],
+) -> Result<u64, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut written = 0;
+
+    for chunk in references.chunks(UPSERT_CHUNK_SIZE) {
+        let references: Vec<&str> = chunk.iter().map(|r| r.reference.as_str()).collect();
+        let names: Vec<&str> = chunk.iter().map(|r| r.name.as_str()).collect();
+        let grids: Vec<Option<&str>> = chunk.iter().map(|r| r.grid.as_deref()).collect();
+        let latitudes: Vec<Option<f64>> = chunk.iter().map(|r| r.latitude).collect();
+        let longitudes: Vec<Option<f64>> = chunk.iter().map(|r| r.longitude).collect();
+        let active: Vec<bool> = chunk.iter().map(|r| r.active).collect();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO program_references
+                (program_slug, reference, name, grid, latitude, longitude, active)
+            SELECT p.slug, r.reference, r.name, r.grid, r.latitude, r.longitude, r.active
+            FROM programs p
+            CROSS JOIN UNNEST($2::text[], $3::text[], $4::text[], $5::float8[], $6::float8[], $7::bool[])
+                AS r(reference, name, grid, latitude, longitude, active)
+            WHERE p.slug = $1
+            ON CONFLICT (program_slug, reference) DO UPDATE SET
+                name = EXCLUDED.name,
+                grid = EXCLUDED.grid,
+                latitude = EXCLUDED.latitude,
+                longitude = EXCLUDED.longitude,
+                active = EXCLUDED.active,
+                updated_at = now()
+            "#,
+        )
+        .bind(program_slug)
+        .bind(&references)
+        .bind(&names)
+        .bind(&grids)
+        .bind(&latitudes)
+        .bind(&longitudes)
+        .bind(&active)
+        .execute(&mut *tx)
+        .await?;
+
+        written += result.rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(written)
+}
+
+/// Search a program's active references by trigram name similarity (or an
+/// exact reference, case-insensitive) and/or distance from a point. With a
+/// point, results are nearest first and references without coordinates come
+/// last; otherwise best name match first.
+pub async fn search_program_references(
+    pool: &PgPool,
+    program_slug: &str,
+    query: Option<&str>,
+    near: Option<(f64, f64)>,
+    limit: i64,
+) -> Result<Vec<ProgramReferenceRow>, AppError> {
+    let (near_lat, near_lon) = near.unzip();
+
+    let rows = sqlx::query_as::<_, ProgramReferenceRow>(
+        r#"
+        SELECT reference, name, grid, latitude, longitude, distance_km
+        FROM (
+            SELECT reference, name, grid, latitude, longitude,
+                   CASE WHEN $3::float8 IS NOT NULL THEN
+                       6371.0 * 2 * asin(sqrt(
+                           power(sin(radians(latitude - $3) / 2), 2)
+                           + cos(radians($3)) * cos(radians(latitude))
+                             * power(sin(radians(longitude - $4) / 2), 2)
+                       ))
+                   END AS distance_km,
+                   CASE WHEN $2::text IS NOT NULL THEN
+                       GREATEST(word_similarity($2, name),
+                                CASE WHEN upper(reference) = upper($2) THEN 1.0 ELSE 0.0 END)
+                   END AS score
+            FROM program_references
+            WHERE program_slug = $1
+              AND active
+              AND ($2::text IS NULL OR $2 <% name OR upper(reference) = upper($2))
+        ) matches
+        ORDER BY distance_km ASC NULLS LAST, score DESC NULLS LAST, reference
+        LIMIT $5
+        "#,
+    )
+    .bind(program_slug)
+    .bind(query)
+    .bind(near_lat)
+    .bind(near_lon)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    fn reference(reference: &str, name: &str, location: Option<(f64, f64)>) -> NewProgramReference {
+        let (latitude, longitude) = location.unzip();
+        NewProgramReference {
+            reference: reference.to_string(),
+            name: name.to_string(),
+            grid: None,
+            latitude,
+            longitude,
+            active: true,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn search_matches_names_and_sorts_by_distance() {
+        let pool = test_support::pool().await;
+        let slug = format!("reftest{}", uuid::Uuid::new_v4().simple());
+        sqlx::query(
+            "INSERT INTO programs (slug, name, short_name, icon, reference_label)
+             VALUES ($1, 'Ref Test', 'RT', 'mountain', 'Summit')",
+        )
+        .bind(&slug)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let written = upsert_program_references(
+            &pool,
+            &slug,
+            &[
+                reference("W6/SS-001", "Mount Whitney", Some((36.58, -118.29))),
+                reference("W6/SS-002", "Mount Langley", Some((36.52, -118.24))),
+                reference("W1/HA-001", "Mount Washington", Some((44.27, -71.30))),
+                reference("W6/SS-999", "Unlocated Peak", None),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(written, 4);
+
+        let by_name = search_program_references(&pool, &slug, Some("whitney"), None, 10)
+            .await
+            .unwrap();
+        assert_eq!(by_name.len(), 1);
+        assert_eq!(by_name[0].reference, "W6/SS-001");
+        assert_eq!(by_name[0].distance_km, None);
+
+        let exact = search_program_references(&pool, &slug, Some("w1/ha-001"), None, 10)
+            .await
+            .unwrap();
+        assert_eq!(exact[0].name, "Mount Washington");
+
+        // Near Boston: Washington first, the unlocated peak last
+        let near = search_program_references(&pool, &slug, None, Some((42.36, -71.06)), 10)
+            .await
+            .unwrap();
+        let order: Vec<_> = near.iter().map(|r| r.reference.as_str()).collect();
+        assert_eq!(order[0], "W1/HA-001");
+        assert_eq!(order[3], "W6/SS-999");
+        assert!(near[0].distance_km.unwrap() < 250.0);
+        assert!(near[3].distance_km.is_none());
+
+        sqlx::query("DELETE FROM programs WHERE slug = $1")
+            .bind(&slug)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
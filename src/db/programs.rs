@@ -1,7 +1,10 @@
 use sqlx::PgPool;
 
 use crate::error::AppError;
-use crate::models::program::{CreateProgramRequest, ProgramRow, UpdateProgramRequest};
+use crate::models::program::{
+    CreateProgramRequest, ProgramIcon, ProgramRow, ProgramSummary, UpdateProgramRequest,
+    ICON_VERSION_LEN,
+};
 
 /// List all active programs ordered by sort_order.
 pub async fn list_programs(pool: &PgPool) -> Result<Vec<ProgramRow>, AppError> {
@@ -45,6 +48,25 @@ pub async fn get_program(pool: &PgPool, slug: &str) -> Result<Option<ProgramRow>
     Ok(row)
 }
 
+/// Summaries of the given programs (including inactive), in one query.
+pub async fn get_program_summaries(
+    pool: &PgPool,
+    slugs: &[String],
+) -> Result<Vec<ProgramSummary>, AppError> {
+    let rows = sqlx::query_as::<_, ProgramSummary>(
+        r#"
+        SELECT slug, short_name, icon, icon_url
+        FROM programs
+        WHERE slug = ANY($1)
+        "#,
+    )
+    .bind(slugs)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
 /// List all programs (including inactive) ordered by sort_order. Admin use.
 pub async fn list_all_programs(pool: &PgPool) -> Result<Vec<ProgramRow>, AppError> {
     let rows = sqlx::query_as::<_, ProgramRow>(
@@ -223,6 +245,66 @@ pub async fn update_program(
     Ok(row)
 }
 
+/// Add one capability to a program, leaving the others alone. Adding one it
+/// already has changes nothing, including `updated_at`. `None` if no such
+/// program.
+pub async fn add_program_capability(
+    pool: &PgPool,
+    slug: &str,
+    capability: &str,
+) -> Result<Option<ProgramRow>, AppError> {
+    let row = sqlx::query_as::<_, ProgramRow>(
+        r#"
+        UPDATE programs SET
+            capabilities = CASE WHEN $2 = ANY(capabilities)
+                THEN capabilities ELSE array_append(capabilities, $2) END,
+            updated_at = CASE WHEN $2 = ANY(capabilities) THEN updated_at ELSE now() END
+        WHERE slug = $1
+        RETURNING slug, name, short_name, icon, icon_url, website, server_base_url,
+                  reference_label, reference_format, reference_example,
+                  multi_ref_allowed, activation_threshold, supports_rove, capabilities,
+                  adif_my_sig, adif_my_sig_info, adif_sig_field, adif_sig_info_field,
+                  data_entry_label, data_entry_placeholder, data_entry_format,
+                  sort_order, is_active, created_at, updated_at
+        "#,
+    )
+    .bind(slug)
+    .bind(capability)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Remove one capability from a program, leaving the others alone. Removing
+/// one it doesn't have changes nothing. `None` if no such program.
+pub async fn remove_program_capability(
+    pool: &PgPool,
+    slug: &str,
+    capability: &str,
+) -> Result<Option<ProgramRow>, AppError> {
+    let row = sqlx::query_as::<_, ProgramRow>(
+        r#"
+        UPDATE programs SET
+            capabilities = array_remove(capabilities, $2),
+            updated_at = CASE WHEN $2 = ANY(capabilities) THEN now() ELSE updated_at END
+        WHERE slug = $1
+        RETURNING slug, name, short_name, icon, icon_url, website, server_base_url,
+                  reference_label, reference_format, reference_example,
+                  multi_ref_allowed, activation_threshold, supports_rove, capabilities,
+                  adif_my_sig, adif_my_sig_info, adif_sig_field, adif_sig_info_field,
+                  data_entry_label, data_entry_placeholder, data_entry_format,
+                  sort_order, is_active, created_at, updated_at
+        "#,
+    )
+    .bind(slug)
+    .bind(capability)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
 /// Delete a program by slug. Returns true if deleted.
 pub async fn delete_program(pool: &PgPool, slug: &str) -> Result<bool, AppError> {
     let result = sqlx::query("DELETE FROM programs WHERE slug = $1")
@@ -243,3 +325,141 @@ pub async fn get_programs_version(pool: &PgPool) -> Result<i64, AppError> {
 
     Ok(version.unwrap_or(0))
 }
+
+/// Store a program's icon and point its `icon_url` at `icon_path` with a
+/// `v` query of the content hash, so each upload gets a fresh URL. `None` if
+/// no such program.
+pub async fn set_program_icon(
+    pool: &PgPool,
+    slug: &str,
+    content_type: &str,
+    image_data: &[u8],
+    icon_path: &str,
+) -> Result<Option<ProgramRow>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query_as::<_, ProgramRow>(
+        r#"
+        UPDATE programs SET
+            icon_url = $2 || '?v=' || left(md5($3), $4),
+            updated_at = now()
+        WHERE slug = $1
+        RETURNING slug, name, short_name, icon, icon_url, website, server_base_url,
+                  reference_label, reference_format, reference_example,
+                  multi_ref_allowed, activation_threshold, supports_rove, capabilities,
+                  adif_my_sig, adif_my_sig_info, adif_sig_field, adif_sig_info_field,
+                  data_entry_label, data_entry_placeholder, data_entry_format,
+                  sort_order, is_active, created_at, updated_at
+        "#,
+    )
+    .bind(slug)
+    .bind(icon_path)
+    .bind(image_data)
+    .bind(ICON_VERSION_LEN as i32)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO program_icons (program_slug, content_type, image_data, etag)
+        VALUES ($1, $2, $3, md5($3))
+        ON CONFLICT (program_slug) DO UPDATE
+        SET content_type = $2, image_data = $3, etag = md5($3), updated_at = now()
+        "#,
+    )
+    .bind(slug)
+    .bind(content_type)
+    .bind(image_data)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(row))
+}
+
+/// The uploaded icon of an active program, if it has one.
+pub async fn get_program_icon(pool: &PgPool, slug: &str) -> Result<Option<ProgramIcon>, AppError> {
+    let icon = sqlx::query_as::<_, ProgramIcon>(
+        r#"
+        SELECT i.content_type, i.image_data, i.etag
+        FROM program_icons i
+        JOIN programs p ON p.slug = i.program_slug
+        WHERE i.program_slug = $1 AND p.is_active = true
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(icon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use uuid::Uuid;
+
+    async fn insert_test_program(pool: &PgPool, capabilities: &[&str]) -> String {
+        let slug = format!("test-{}", &Uuid::new_v4().simple().to_string()[..8]);
+        sqlx::query(
+            "INSERT INTO programs (slug, name, short_name, icon, reference_label, capabilities, is_active)
+             VALUES ($1, 'Test Program', 'TEST', 'star', 'Reference', $2, false)",
+        )
+        .bind(&slug)
+        .bind(capabilities)
+        .execute(pool)
+        .await
+        .unwrap();
+        slug
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn capability_add_is_idempotent() {
+        let pool = test_support::pool().await;
+        let slug = insert_test_program(&pool, &["referenceField"]).await;
+
+        let added = add_program_capability(&pool, &slug, "selfSpot")
+            .await
+            .unwrap()
+            .unwrap();
+        let again = add_program_capability(&pool, &slug, "selfSpot")
+            .await
+            .unwrap()
+            .unwrap();
+        let missing = add_program_capability(&pool, "no-such-program", "selfSpot")
+            .await
+            .unwrap();
+        delete_program(&pool, &slug).await.unwrap();
+
+        assert_eq!(added.capabilities, ["referenceField", "selfSpot"]);
+        assert_eq!(again.capabilities, added.capabilities);
+        assert_eq!(again.updated_at, added.updated_at);
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn capability_remove_keeps_the_others() {
+        let pool = test_support::pool().await;
+        let slug = insert_test_program(&pool, &["referenceField", "hunter", "selfSpot"]).await;
+
+        let removed = remove_program_capability(&pool, &slug, "hunter")
+            .await
+            .unwrap()
+            .unwrap();
+        let again = remove_program_capability(&pool, &slug, "hunter")
+            .await
+            .unwrap()
+            .unwrap();
+        delete_program(&pool, &slug).await.unwrap();
+
+        assert_eq!(removed.capabilities, ["referenceField", "selfSpot"]);
+        assert_eq!(again.capabilities, removed.capabilities);
+        assert_eq!(again.updated_at, removed.updated_at);
+    }
+}
@@ -2,7 +2,10 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::models::{LeaderboardEntry, LeaderboardQuery, Progress, ReportProgressRequest};
+use crate::models::{
+    overtaken_on_board, BoardPosition, LeaderboardEntry, LeaderboardQuery, Progress,
+    ReportProgressRequest,
+};
 
 pub async fn get_progress(
     pool: &PgPool,
@@ -27,6 +30,12 @@ pub async fn get_progress(
     Ok(progress)
 }
 
+/// Participants of the top board checked for overtakes on each progress write.
+const OVERTAKE_BOARD_SIZE: i64 = 100;
+
+/// Store a participant's scored progress. In the same transaction, friends
+/// the write passes on the top board get an `overtaken` activity, at most
+/// one per pair of users and challenge every 6 hours.
 pub async fn upsert_progress(
     pool: &PgPool,
     challenge_id: Uuid,
@@ -39,6 +48,16 @@ pub async fn upsert_progress(
     let callsign_upper = callsign.to_uppercase();
     let completed_goals = serde_json::to_value(&req.completed_goals)?;
 
+    let mut tx = pool.begin().await?;
+
+    // Serialize progress writes per challenge so the board read before the
+    // write is the one the write changes
+    sqlx::query("SELECT 1 FROM challenges WHERE id = $1 FOR NO KEY UPDATE")
+        .bind(challenge_id)
+        .execute(&mut *tx)
+        .await?;
+    let before = top_board(&mut tx, challenge_id).await?;
+
     let progress = sqlx::query_as::<_, Progress>(
         r#"
         INSERT INTO progress (id, challenge_id, callsign, completed_goals, current_value, score, current_tier, last_qso_date)
@@ -58,12 +77,106 @@ pub async fn upsert_progress(
     .bind(score)
     .bind(current_tier)
     .bind(req.last_qso_date)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    let after = top_board(&mut tx, challenge_id).await?;
+    let passed = overtaken_on_board(&before, &after, &callsign_upper);
+    if !passed.is_empty() {
+        let (callsigns, scores): (Vec<String>, Vec<i32>) = passed
+            .into_iter()
+            .map(|entry| (entry.callsign, entry.score))
+            .unzip();
+        sqlx::query(
+            r#"
+            INSERT INTO activities (user_id, callsign, activity_type, timestamp, details)
+            SELECT friend.id, friend.callsign, 'overtaken', now(),
+                   jsonb_build_object(
+                       'challengeId', $1::uuid,
+                       'score', passed.score,
+                       'overtakenByUserId', passer.id,
+                       'overtakenByCallsign', passer.callsign,
+                       'overtakenByScore', $3::int)
+            FROM unnest($4::text[], $5::int[]) AS passed (callsign, score)
+            JOIN users friend ON friend.callsign = passed.callsign
+            JOIN users passer ON passer.callsign = $2
+            JOIN friendships f ON f.user_id = passer.id AND f.friend_id = friend.id
+            WHERE NOT EXISTS (
+                SELECT 1 FROM activities a
+                WHERE a.activity_type = 'overtaken'
+                  AND a.details->>'challengeId' = $1::text
+                  AND a.created_at > now() - interval '6 hours'
+                  AND ((a.user_id = friend.id
+                        AND a.details->>'overtakenByUserId' = passer.id::text)
+                    OR (a.user_id = passer.id
+                        AND a.details->>'overtakenByUserId' = friend.id::text))
+            )
+            "#,
+        )
+        .bind(challenge_id)
+        .bind(&callsign_upper)
+        .bind(score)
+        .bind(&callsigns)
+        .bind(&scores)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
     Ok(progress)
 }
 
+/// The challenge's top board in rank order, as the leaderboard ranks it.
+async fn top_board(
+    conn: &mut sqlx::PgConnection,
+    challenge_id: Uuid,
+) -> Result<Vec<BoardPosition>, AppError> {
+    let board = sqlx::query_as::<_, BoardPosition>(
+        r#"
+        SELECT callsign, score
+        FROM progress
+        WHERE challenge_id = $1
+        ORDER BY score DESC, updated_at ASC, callsign
+        LIMIT $2
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(OVERTAKE_BOARD_SIZE)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(board)
+}
+
+/// Record one submission that changed a participant's progress. `source`
+/// says where it came from (`dataEntry`); `details` holds what was submitted.
+pub async fn insert_progress_entry(
+    pool: &PgPool,
+    challenge_id: Uuid,
+    callsign: &str,
+    source: &str,
+    goal_id: Option<&str>,
+    details: &serde_json::Value,
+) -> Result<Uuid, AppError> {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO progress_entries (id, challenge_id, callsign, source, goal_id, details)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(id)
+    .bind(challenge_id)
+    .bind(callsign.to_uppercase())
+    .bind(source)
+    .bind(goal_id)
+    .bind(details)
+    .execute(pool)
+    .await?;
+
+    Ok(id)
+}
+
 pub async fn get_rank(
     pool: &PgPool,
     challenge_id: Uuid,
@@ -89,6 +202,12 @@ pub async fn get_rank(
     Ok(row.and_then(|r| r.0))
 }
 
+/// A page of the leaderboard and the participant count. With
+/// `query.division`, only that division's participants are ranked and
+/// counted. With `query.movement`, each entry's `rank_delta_24h` comes from
+/// joining yesterday's `rank_history` sample onto the page; otherwise that
+/// join is left out of the query entirely. Samples are overall ranks, so the
+/// delta stays `None` when a division is set.
 pub async fn get_leaderboard(
     pool: &PgPool,
     challenge_id: Uuid,
@@ -97,39 +216,102 @@ pub async fn get_leaderboard(
     let limit = query.limit.unwrap_or(100).min(100);
     let offset = query.offset.unwrap_or(0);
 
-    let entries = sqlx::query_as::<_, LeaderboardEntry>(
+    let sql = if query.movement {
+        r#"
+        WITH page AS (
+            SELECT
+                RANK() OVER (ORDER BY p.score DESC, p.updated_at ASC) as rank,
+                p.callsign,
+                p.score,
+                p.current_tier,
+                p.updated_at,
+                cp.division
+            FROM progress p
+            LEFT JOIN challenge_participants cp
+                ON cp.challenge_id = p.challenge_id AND cp.callsign = p.callsign
+            WHERE p.challenge_id = $1 AND ($4::text IS NULL OR cp.division = $4)
+            ORDER BY p.score DESC, p.updated_at ASC
+            LIMIT $2 OFFSET $3
+        )
+        SELECT
+            p.rank,
+            p.callsign,
+            p.score,
+            p.current_tier,
+            CASE WHEN p.score > 0 THEN p.updated_at ELSE NULL END as completed_at,
+            h.rank - p.rank as rank_delta_24h,
+            p.division
+        FROM page p
+        LEFT JOIN rank_history h
+            ON $4::text IS NULL
+            AND h.challenge_id = $1
+            AND h.callsign = p.callsign
+            AND h.sampled_on = (now() AT TIME ZONE 'UTC')::date - 1
+        ORDER BY p.score DESC, p.updated_at ASC
+        "#
+    } else {
         r#"
         SELECT
-            RANK() OVER (ORDER BY score DESC, updated_at ASC) as rank,
-            callsign,
-            score,
-            current_tier,
-            CASE WHEN score > 0 THEN updated_at ELSE NULL END as completed_at
-        FROM progress
-        WHERE challenge_id = $1
-        ORDER BY score DESC, updated_at ASC
+            RANK() OVER (ORDER BY p.score DESC, p.updated_at ASC) as rank,
+            p.callsign,
+            p.score,
+            p.current_tier,
+            CASE WHEN p.score > 0 THEN p.updated_at ELSE NULL END as completed_at,
+            cp.division
+        FROM progress p
+        LEFT JOIN challenge_participants cp
+            ON cp.challenge_id = p.challenge_id AND cp.callsign = p.callsign
+        WHERE p.challenge_id = $1 AND ($4::text IS NULL OR cp.division = $4)
+        ORDER BY p.score DESC, p.updated_at ASC
         LIMIT $2 OFFSET $3
+        "#
+    };
+    let entries = sqlx::query_as::<_, LeaderboardEntry>(sql)
+        .bind(challenge_id)
+        .bind(limit)
+        .bind(offset)
+        .bind(&query.division)
+        .fetch_all(pool)
+        .await?;
+
+    let total = count_leaderboard(pool, challenge_id, query.division.as_deref()).await?;
+
+    Ok((entries, total))
+}
+
+/// Participants with progress on the leaderboard, or in one division of it.
+pub async fn count_leaderboard(
+    pool: &PgPool,
+    challenge_id: Uuid,
+    division: Option<&str>,
+) -> Result<i64, AppError> {
+    let total: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*)
+        FROM progress p
+        LEFT JOIN challenge_participants cp
+            ON cp.challenge_id = p.challenge_id AND cp.callsign = p.callsign
+        WHERE p.challenge_id = $1 AND ($2::text IS NULL OR cp.division = $2)
         "#,
     )
     .bind(challenge_id)
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(pool)
+    .bind(division)
+    .fetch_one(pool)
     .await?;
 
-    let total: (i64,) = sqlx::query_as(r#"SELECT COUNT(*) FROM progress WHERE challenge_id = $1"#)
-        .bind(challenge_id)
-        .fetch_one(pool)
-        .await?;
-
-    Ok((entries, total.0))
+    Ok(total.0)
 }
 
+/// Entries ranked within `range` places of `callsign`, among `division`'s
+/// participants when given; `movement` fills in `rank_delta_24h` as in
+/// [`get_leaderboard`], so not within a division.
 pub async fn get_leaderboard_around(
     pool: &PgPool,
     challenge_id: Uuid,
     callsign: &str,
     range: i64,
+    movement: bool,
+    division: Option<&str>,
 ) -> Result<Vec<LeaderboardEntry>, AppError> {
     let callsign_upper = callsign.to_uppercase();
 
@@ -137,31 +319,44 @@ pub async fn get_leaderboard_around(
         r#"
         WITH ranked AS (
             SELECT
-                RANK() OVER (ORDER BY score DESC, updated_at ASC) as rank,
-                callsign,
-                score,
-                current_tier,
-                CASE WHEN score > 0 THEN updated_at ELSE NULL END as completed_at
-            FROM progress
-            WHERE challenge_id = $1
+                RANK() OVER (ORDER BY p.score DESC, p.updated_at ASC) as rank,
+                p.callsign,
+                p.score,
+                p.current_tier,
+                CASE WHEN p.score > 0 THEN p.updated_at ELSE NULL END as completed_at,
+                cp.division
+            FROM progress p
+            LEFT JOIN challenge_participants cp
+                ON cp.challenge_id = p.challenge_id AND cp.callsign = p.callsign
+            WHERE p.challenge_id = $1 AND ($5::text IS NULL OR cp.division = $5)
         )
         SELECT
-            rank,
-            callsign,
-            score,
-            current_tier,
-            completed_at
-        FROM ranked
-        WHERE rank BETWEEN
+            r.rank,
+            r.callsign,
+            r.score,
+            r.current_tier,
+            r.completed_at,
+            h.rank - r.rank as rank_delta_24h,
+            r.division
+        FROM ranked r
+        LEFT JOIN rank_history h
+            ON $4
+            AND $5::text IS NULL
+            AND h.challenge_id = $1
+            AND h.callsign = r.callsign
+            AND h.sampled_on = (now() AT TIME ZONE 'UTC')::date - 1
+        WHERE r.rank BETWEEN
             (SELECT rank FROM ranked WHERE callsign = $2) - $3
             AND
             (SELECT rank FROM ranked WHERE callsign = $2) + $3
-        ORDER BY rank
+        ORDER BY r.rank
         "#,
     )
     .bind(challenge_id)
     .bind(&callsign_upper)
     .bind(range)
+    .bind(movement)
+    .bind(division)
     .fetch_all(pool)
     .await?;
 
@@ -173,3 +368,277 @@ impl From<serde_json::Error> for AppError {
         AppError::Internal(e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use crate::models::CreateChallengeRequest;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn passing_a_friend_records_one_overtaken_activity() {
+        let pool = test_support::pool().await;
+        let challenge = crate::db::create_challenge(
+            &pool,
+            &CreateChallengeRequest {
+                name: format!("Overtake test {}", Uuid::new_v4()),
+                description: "Overtakes".to_string(),
+                author: None,
+                category: "award".to_string(),
+                challenge_type: "collection".to_string(),
+                configuration: serde_json::json!({}),
+                invite_config: None,
+                hamalert_config: None,
+                is_active: Some(false),
+            },
+        )
+        .await
+        .unwrap();
+        let users = test_support::users(&pool, 3).await;
+        let (passer, friend, stranger) = (&users[0], &users[1], &users[2]);
+        sqlx::query("INSERT INTO friendships (user_id, friend_id) VALUES ($1, $2), ($2, $1)")
+            .bind(passer.id)
+            .bind(friend.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let report = |callsign: &str, score: i32| {
+            let pool = pool.clone();
+            let callsign = callsign.to_string();
+            async move {
+                let req = ReportProgressRequest {
+                    completed_goals: vec![],
+                    current_value: score,
+                    qualifying_qso_count: 0,
+                    last_qso_date: None,
+                };
+                upsert_progress(&pool, challenge.id, &callsign, &req, score, None)
+                    .await
+                    .unwrap();
+            }
+        };
+        let overtaken = |user_id: Uuid| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, serde_json::Value>(
+                    "SELECT details FROM activities
+                     WHERE user_id = $1 AND activity_type = 'overtaken'
+                       AND details->>'challengeId' = $2::text",
+                )
+                .bind(user_id)
+                .bind(challenge.id)
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+            }
+        };
+
+        report(&friend.callsign, 20).await;
+        report(&stranger.callsign, 15).await;
+        report(&passer.callsign, 10).await;
+        // Passes the friend and the stranger; only the friend hears of it
+        report(&passer.callsign, 30).await;
+        // Passing back within the window is throttled for the pair
+        report(&friend.callsign, 40).await;
+        report(&passer.callsign, 50).await;
+
+        let friend_activities = overtaken(friend.id).await;
+        let passer_activities = overtaken(passer.id).await;
+        let stranger_activities = overtaken(stranger.id).await;
+        crate::db::delete_challenge(&pool, challenge.id)
+            .await
+            .unwrap();
+
+        assert_eq!(friend_activities.len(), 1);
+        let details = &friend_activities[0];
+        assert_eq!(details["score"], 20);
+        assert_eq!(details["overtakenByCallsign"], passer.callsign.as_str());
+        assert_eq!(details["overtakenByScore"], 30);
+        assert!(passer_activities.is_empty());
+        assert!(stranger_activities.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn leaderboard_movement_compares_with_yesterdays_sample() {
+        let pool = test_support::pool().await;
+        let challenge = crate::db::create_challenge(
+            &pool,
+            &CreateChallengeRequest {
+                name: format!("Movement test {}", Uuid::new_v4()),
+                description: "Movement".to_string(),
+                author: None,
+                category: "award".to_string(),
+                challenge_type: "collection".to_string(),
+                configuration: serde_json::json!({}),
+                invite_config: None,
+                hamalert_config: None,
+                is_active: Some(false),
+            },
+        )
+        .await
+        .unwrap();
+        let callsigns: Vec<String> = (0..3).map(|_| test_support::callsign()).collect();
+        // Ranked climber, faller, newcomer today
+        for (callsign, score) in callsigns.iter().zip([30, 20, 10]) {
+            let req = ReportProgressRequest {
+                completed_goals: vec![],
+                current_value: score,
+                qualifying_qso_count: 0,
+                last_qso_date: None,
+            };
+            upsert_progress(&pool, challenge.id, callsign, &req, score, None)
+                .await
+                .unwrap();
+        }
+        // Yesterday the faller led and the newcomer had no sample
+        for (callsign, rank) in [(&callsigns[0], 2), (&callsigns[1], 1)] {
+            sqlx::query(
+                "INSERT INTO rank_history (challenge_id, callsign, sampled_on, rank, score)
+                 VALUES ($1, $2, (now() AT TIME ZONE 'UTC')::date - 1, $3, 0)",
+            )
+            .bind(challenge.id)
+            .bind(callsign)
+            .bind(rank)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let deltas = |entries: Vec<LeaderboardEntry>| {
+            entries
+                .into_iter()
+                .map(|e| (e.callsign, e.rank_delta_24h))
+                .collect::<Vec<_>>()
+        };
+        let query = |movement| LeaderboardQuery {
+            movement,
+            ..Default::default()
+        };
+        let (with_movement, _) = get_leaderboard(&pool, challenge.id, &query(true))
+            .await
+            .unwrap();
+        let (without_movement, _) = get_leaderboard(&pool, challenge.id, &query(false))
+            .await
+            .unwrap();
+        let around = get_leaderboard_around(&pool, challenge.id, &callsigns[2], 1, true, None)
+            .await
+            .unwrap();
+        crate::db::delete_challenge(&pool, challenge.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            deltas(with_movement),
+            vec![
+                (callsigns[0].clone(), Some(1)),
+                (callsigns[1].clone(), Some(-1)),
+                (callsigns[2].clone(), None),
+            ]
+        );
+        assert!(without_movement.iter().all(|e| e.rank_delta_24h.is_none()));
+        assert_eq!(
+            deltas(around),
+            vec![
+                (callsigns[1].clone(), Some(-1)),
+                (callsigns[2].clone(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn division_leaderboards_leave_movement_empty() {
+        let pool = test_support::pool().await;
+        let challenge = crate::db::create_challenge(
+            &pool,
+            &CreateChallengeRequest {
+                name: format!("Division movement test {}", Uuid::new_v4()),
+                description: "Division movement".to_string(),
+                author: None,
+                category: "award".to_string(),
+                challenge_type: "collection".to_string(),
+                configuration: serde_json::json!({}),
+                invite_config: None,
+                hamalert_config: None,
+                is_active: Some(false),
+            },
+        )
+        .await
+        .unwrap();
+        let callsigns: Vec<String> = (0..4).map(|_| test_support::callsign()).collect();
+        // Overall ranks 1-4 today, alternating divisions; yesterday reversed
+        for (i, (callsign, division)) in
+            callsigns.iter().zip(["qrp", "lp", "qrp", "lp"]).enumerate()
+        {
+            let score = 40 - 10 * i as i32;
+            let req = ReportProgressRequest {
+                completed_goals: vec![],
+                current_value: score,
+                qualifying_qso_count: 0,
+                last_qso_date: None,
+            };
+            upsert_progress(&pool, challenge.id, callsign, &req, score, None)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO challenge_participants (id, challenge_id, callsign, division)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(challenge.id)
+            .bind(callsign)
+            .bind(division)
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO rank_history (challenge_id, callsign, sampled_on, rank, score)
+                 VALUES ($1, $2, (now() AT TIME ZONE 'UTC')::date - 1, $3, 0)",
+            )
+            .bind(challenge.id)
+            .bind(callsign)
+            .bind(4 - i as i32)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let deltas = |entries: Vec<LeaderboardEntry>| {
+            entries
+                .into_iter()
+                .map(|e| (e.rank, e.rank_delta_24h))
+                .collect::<Vec<_>>()
+        };
+        let query = |division: Option<&str>| LeaderboardQuery {
+            movement: true,
+            division: division.map(str::to_string),
+            ..Default::default()
+        };
+        let (overall, _) = get_leaderboard(&pool, challenge.id, &query(None))
+            .await
+            .unwrap();
+        let (qrp, _) = get_leaderboard(&pool, challenge.id, &query(Some("qrp")))
+            .await
+            .unwrap();
+        let (lp, _) = get_leaderboard(&pool, challenge.id, &query(Some("lp")))
+            .await
+            .unwrap();
+        let around =
+            get_leaderboard_around(&pool, challenge.id, &callsigns[2], 1, true, Some("qrp"))
+                .await
+                .unwrap();
+        crate::db::delete_challenge(&pool, challenge.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            deltas(overall),
+            vec![(1, Some(3)), (2, Some(1)), (3, Some(-1)), (4, Some(-3))]
+        );
+        assert_eq!(deltas(qrp), vec![(1, None), (2, None)]);
+        assert_eq!(deltas(lp), vec![(1, None), (2, None)]);
+        assert_eq!(deltas(around), vec![(1, None), (2, None)]);
+    }
+}
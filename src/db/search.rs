@@ -0,0 +1,131 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::spot::SpotRow;
+use crate::models::{ChallengeListItem, User};
+
+/// Escape LIKE/ILIKE wildcards so user input is matched literally.
+pub fn escape_like(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Users whose callsign starts with `query`. Suspended users and those in
+/// `excluded` (the searcher's blocks) are left out.
+pub async fn search_callsigns(
+    pool: &PgPool,
+    query: &str,
+    excluded: &[Uuid],
+    limit: i64,
+) -> Result<Vec<User>, AppError> {
+    let pattern = format!("{}%", escape_like(&query.to_uppercase()));
+    let users = sqlx::query_as::<_, User>(
+        r#"
+        SELECT id, callsign, created_at
+        FROM users
+        WHERE callsign LIKE $1
+          AND suspended_at IS NULL
+          AND NOT (id = ANY($3))
+        ORDER BY callsign
+        LIMIT $2
+        "#,
+    )
+    .bind(&pattern)
+    .bind(limit)
+    .bind(excluded)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+/// Active spots whose callsign starts with, or reference contains, `query`,
+/// leaving out spots of `hidden_callsigns`.
+pub async fn search_active_spots(
+    pool: &PgPool,
+    query: &str,
+    hidden_callsigns: &[String],
+    limit: i64,
+) -> Result<Vec<SpotRow>, AppError> {
+    let escaped = escape_like(&query.to_uppercase());
+    let rows = sqlx::query_as::<_, SpotRow>(
+        r#"
+        SELECT id, callsign, program_slug, source, external_id,
+               frequency_khz, mode, reference, reference_name,
+               spotter, spotter_grid, location_desc, country_code, state_abbr,
+               comments, snr, wpm, submitted_by, latitude, longitude,
+               spotted_at, expires_at, created_at, updated_at
+        FROM spots
+        WHERE expires_at > now()
+          AND (callsign LIKE $1 OR UPPER(reference) LIKE $2)
+          AND NOT (callsign = ANY($4))
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+        ORDER BY spotted_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(format!("{escaped}%"))
+    .bind(format!("%{escaped}%"))
+    .bind(limit)
+    .bind(hidden_callsigns)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Challenges whose name contains `query` (case-insensitive), active first.
+pub async fn search_challenges(
+    pool: &PgPool,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<ChallengeListItem>, AppError> {
+    let pattern = format!("%{}%", escape_like(query));
+    let rows = sqlx::query_as::<_, ChallengeListItem>(
+        r#"
+        SELECT
+            c.id,
+            c.name,
+            c.description,
+            c.category,
+            c.challenge_type,
+            c.is_active,
+            COALESCE(COUNT(cp.id), 0) as participant_count
+        FROM challenges c
+        LEFT JOIN challenge_participants cp ON cp.challenge_id = c.id AND cp.status = 'active'
+        WHERE c.name ILIKE $1
+        GROUP BY c.id
+        ORDER BY c.is_active DESC, c.name
+        LIMIT $2
+        "#,
+    )
+    .bind(&pattern)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_like_escapes_wildcards() {
+        assert_eq!(escape_like("K1_A%"), "K1\\_A\\%");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+        assert_eq!(escape_like("W1AW"), "W1AW");
+    }
+}
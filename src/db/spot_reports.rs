@@ -0,0 +1,145 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::spot_report::{ReportedSpotRow, SpotReportRow};
+
+/// Record a report against a spot. A reporter re-reporting the same spot
+/// replaces their earlier reason. Returns false if the spot does not exist.
+pub async fn insert_spot_report(
+    pool: &PgPool,
+    spot_id: Uuid,
+    reporter_callsign: &str,
+    reason: &str,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO spot_reports (spot_id, reporter_callsign, reason)
+        SELECT id, $2, $3 FROM spots WHERE id = $1
+        ON CONFLICT (spot_id, reporter_callsign)
+        DO UPDATE SET reason = EXCLUDED.reason, created_at = now()
+        "#,
+    )
+    .bind(spot_id)
+    .bind(reporter_callsign)
+    .bind(reason)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// List reported spots, most-reported first, then by the newest report.
+pub async fn list_reported_spots(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<ReportedSpotRow>, AppError> {
+    let rows = sqlx::query_as::<_, ReportedSpotRow>(
+        r#"
+        SELECT s.id, s.callsign, s.program_slug, s.source, s.external_id,
+               s.frequency_khz, s.mode, s.reference, s.reference_name,
+               s.spotter, s.spotter_grid, s.location_desc, s.country_code, s.state_abbr,
+               s.comments, s.snr, s.wpm, s.submitted_by, s.latitude, s.longitude,
+               s.spotted_at, s.expires_at, s.created_at, s.updated_at,
+               s.ingest_channel, r.report_count
+        FROM spots s
+        JOIN (
+            SELECT spot_id, COUNT(*) AS report_count, MAX(created_at) AS last_reported_at
+            FROM spot_reports
+            GROUP BY spot_id
+        ) r ON r.spot_id = s.id
+        ORDER BY r.report_count DESC, r.last_reported_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Fetch all reports for the given spots, oldest first.
+pub async fn get_reports_for_spots(
+    pool: &PgPool,
+    spot_ids: &[Uuid],
+) -> Result<Vec<SpotReportRow>, AppError> {
+    let rows = sqlx::query_as::<_, SpotReportRow>(
+        r#"
+        SELECT spot_id, reporter_callsign, reason, created_at
+        FROM spot_reports
+        WHERE spot_id = ANY($1)
+        ORDER BY created_at
+        "#,
+    )
+    .bind(spot_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn reported_spots_order_by_count_then_newest_report() {
+        let pool = test_support::pool().await;
+        let now = Utc::now();
+        let mut spot_ids = Vec::new();
+        for _ in 0..4 {
+            let spot = test_support::aggregated_spot(&format!("test-{}", Uuid::new_v4()), now);
+            spot_ids.push(
+                crate::db::upsert_aggregated_spot(&pool, &spot, None)
+                    .await
+                    .unwrap()
+                    .id,
+            );
+        }
+        // Minutes ago of each spot's reports: two ties on count, the one with
+        // the newer last report first; the newest report of all has the fewest
+        let reports: [&[i64]; 4] = [&[30, 29, 28], &[20, 2], &[7, 6], &[1]];
+        for (spot_id, ages) in spot_ids.iter().zip(reports) {
+            for minutes_ago in ages {
+                sqlx::query(
+                    "INSERT INTO spot_reports (spot_id, reporter_callsign, reason, created_at)
+                     VALUES ($1, $2, 'wrong frequency', $3)",
+                )
+                .bind(spot_id)
+                .bind(test_support::callsign())
+                .bind(now - Duration::minutes(*minutes_ago))
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        }
+
+        let listed: Vec<(Uuid, i64)> = list_reported_spots(&pool, 10_000)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|row| spot_ids.contains(&row.spot.id))
+            .map(|row| (row.spot.id, row.report_count))
+            .collect();
+
+        // Reports go with the spots
+        sqlx::query("DELETE FROM spots WHERE id = ANY($1)")
+            .bind(&spot_ids)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            listed,
+            [
+                (spot_ids[0], 3),
+                (spot_ids[1], 2),
+                (spot_ids[2], 2),
+                (spot_ids[3], 1)
+            ]
+        );
+    }
+}
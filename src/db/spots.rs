@@ -3,21 +3,34 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::models::spot::{AggregatedSpot, SpotRow, SpotSource};
+use crate::grid::grid_center;
+use crate::models::spot::{
+    band_for_khz, ActivatorRow, AggregatedSpot, BoundingBox, CallsignSpotSourceRow, IngestChannel,
+    SpotHeatmapCellRow, SpotRow, SpotSource,
+};
 
 /// Query parameters for listing spots (pre-validated by handler).
 pub struct ListSpotsParams {
     pub program: Option<String>,
     pub callsign: Option<String>,
     pub source: Option<SpotSource>,
+    /// As from `mode_filter`; matched against the stored mode in any case.
     pub mode: Option<String>,
     pub state: Option<String>,
     pub max_age_minutes: i64,
     pub limit: i64,
     pub cursor: Option<DateTime<Utc>>,
+    pub bbox: Option<BoundingBox>,
+    /// Activators the viewer is blocked with.
+    pub hidden_callsigns: Vec<String>,
+    /// Leave out spots submitted by this participant (the viewer's own).
+    pub excluded_submitter: Option<Uuid>,
 }
 
-/// List active spots with filters and cursor pagination.
+/// List active spots with filters and cursor pagination. Self-spots from
+/// suspended users are hidden, as are spots of `hidden_callsigns` and spots
+/// submitted by `excluded_submitter`. With a
+/// bounding box, only spots with a resolved location inside it are returned.
 /// Returns up to `limit + 1` rows so the caller can determine `has_more`.
 pub async fn list_spots(pool: &PgPool, params: &ListSpotsParams) -> Result<Vec<SpotRow>, AppError> {
     let cutoff = Utc::now() - Duration::minutes(params.max_age_minutes);
@@ -27,7 +40,7 @@ pub async fn list_spots(pool: &PgPool, params: &ListSpotsParams) -> Result<Vec<S
         SELECT id, callsign, program_slug, source, external_id,
                frequency_khz, mode, reference, reference_name,
                spotter, spotter_grid, location_desc, country_code, state_abbr,
-               comments, snr, wpm, submitted_by,
+               comments, snr, wpm, submitted_by, latitude, longitude,
                spotted_at, expires_at, created_at, updated_at
         FROM spots
         WHERE expires_at > now()
@@ -35,9 +48,23 @@ pub async fn list_spots(pool: &PgPool, params: &ListSpotsParams) -> Result<Vec<S
           AND ($2::text IS NULL OR program_slug = $2)
           AND ($3::text IS NULL OR callsign = $3)
           AND ($4::spot_source IS NULL OR source = $4)
-          AND ($5::text IS NULL OR mode = $5)
+          AND ($5::text IS NULL OR upper(mode) = $5)
           AND ($6::text IS NULL OR state_abbr = $6)
           AND ($7::timestamptz IS NULL OR spotted_at < $7)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+          AND ($9::float8 IS NULL OR (
+              latitude BETWEEN $9 AND $10
+              AND CASE WHEN $11 <= $12
+                  THEN longitude BETWEEN $11 AND $12
+                  ELSE longitude >= $11 OR longitude <= $12
+              END
+          ))
+          AND NOT (callsign = ANY($13))
+          AND ($14::uuid IS NULL OR submitted_by IS DISTINCT FROM $14)
         ORDER BY spotted_at DESC
         LIMIT $8
         "#,
@@ -50,28 +77,243 @@ pub async fn list_spots(pool: &PgPool, params: &ListSpotsParams) -> Result<Vec<S
     .bind(&params.state)
     .bind(params.cursor)
     .bind(params.limit + 1)
+    .bind(params.bbox.map(|b| b.min_lat))
+    .bind(params.bbox.map(|b| b.max_lat))
+    .bind(params.bbox.map(|b| b.min_lon))
+    .bind(params.bbox.map(|b| b.max_lon))
+    .bind(&params.hidden_callsigns)
+    .bind(params.excluded_submitter)
     .fetch_all(pool)
     .await?;
 
     Ok(rows)
 }
 
+/// Every unexpired spot of one callsign, across programs and sources, newest
+/// first. The callsign is matched case-insensitively; self-spots from
+/// suspended users are hidden as in [`list_spots`].
+pub async fn list_callsign_spots(pool: &PgPool, callsign: &str) -> Result<Vec<SpotRow>, AppError> {
+    let rows = sqlx::query_as::<_, SpotRow>(
+        r#"
+        SELECT id, callsign, program_slug, source, external_id,
+               frequency_khz, mode, reference, reference_name,
+               spotter, spotter_grid, location_desc, country_code, state_abbr,
+               comments, snr, wpm, submitted_by, latitude, longitude,
+               spotted_at, expires_at, created_at, updated_at
+        FROM spots
+        WHERE expires_at > now()
+          AND upper(callsign) = upper($1)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+        ORDER BY spotted_at DESC
+        "#,
+    )
+    .bind(callsign)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// The newest unexpired spot of each of `callsigns` (uppercase), for
+/// POST /v1/callsigns/status. Callsigns are matched case-insensitively;
+/// those with no spot, or only self-spots from suspended users, are absent.
+pub async fn get_newest_spots_for_callsigns(
+    pool: &PgPool,
+    callsigns: &[String],
+) -> Result<Vec<SpotRow>, AppError> {
+    let rows = sqlx::query_as::<_, SpotRow>(
+        r#"
+        SELECT DISTINCT ON (upper(callsign))
+               id, callsign, program_slug, source, external_id,
+               frequency_khz, mode, reference, reference_name,
+               spotter, spotter_grid, location_desc, country_code, state_abbr,
+               comments, snr, wpm, submitted_by, latitude, longitude,
+               spotted_at, expires_at, created_at, updated_at
+        FROM spots
+        WHERE expires_at > now()
+          AND upper(callsign) = ANY($1)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+        ORDER BY upper(callsign), spotted_at DESC
+        "#,
+    )
+    .bind(callsigns)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// A callsign's unexpired spots grouped by source: counts, spotters, the
+/// strongest report, and the newest frequency and mode. Matching and the
+/// suspended-user filter are the same as [`list_callsign_spots`].
+pub async fn get_callsign_spot_summary(
+    pool: &PgPool,
+    callsign: &str,
+) -> Result<Vec<CallsignSpotSourceRow>, AppError> {
+    let rows = sqlx::query_as::<_, CallsignSpotSourceRow>(
+        r#"
+        SELECT source,
+               count(*) AS spot_count,
+               array_remove(array_agg(DISTINCT upper(spotter)), NULL) AS spotters,
+               max(snr) AS best_snr,
+               (array_agg(spotter ORDER BY snr DESC NULLS LAST, spotted_at DESC))[1]
+                   AS best_snr_spotter,
+               (array_agg(spotter_grid ORDER BY snr DESC NULLS LAST, spotted_at DESC))[1]
+                   AS best_snr_spotter_grid,
+               max(spotted_at) AS newest_spotted_at,
+               (array_agg(frequency_khz ORDER BY spotted_at DESC))[1] AS newest_frequency_khz,
+               (array_agg(mode ORDER BY spotted_at DESC))[1] AS newest_mode
+        FROM spots
+        WHERE expires_at > now()
+          AND upper(callsign) = upper($1)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+        GROUP BY source
+        ORDER BY source
+        "#,
+    )
+    .bind(callsign)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Filters for listing active activators (pre-validated by handler).
+pub struct ListActivatorsParams {
+    pub program: Option<String>,
+    /// As from `mode_filter`; matched against the stored mode in any case.
+    pub mode: Option<String>,
+    /// `(low, high)` kHz of the requested band.
+    pub band: Option<(f64, f64)>,
+}
+
+/// List activators currently on the air: one row per (callsign, program,
+/// reference) from unexpired spots, with the latest spot's frequency and mode,
+/// most recently spotted first. The band filter applies to the latest
+/// frequency, so an activator who changed bands is listed on the new one only.
+pub async fn list_activators(
+    pool: &PgPool,
+    params: &ListActivatorsParams,
+) -> Result<Vec<ActivatorRow>, AppError> {
+    let rows = sqlx::query_as::<_, ActivatorRow>(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (callsign, program_slug, reference)
+                   callsign, program_slug, reference, reference_name, source,
+                   frequency_khz, mode,
+                   MIN(spotted_at) OVER activator AS first_spotted_at,
+                   spotted_at AS last_spotted_at,
+                   COUNT(*) OVER activator AS spot_count
+            FROM spots
+            WHERE expires_at > now()
+              AND ($1::text IS NULL OR program_slug = $1)
+              AND NOT EXISTS (
+                  SELECT 1 FROM participants p
+                  JOIN users u ON u.callsign = p.callsign
+                  WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+              )
+            WINDOW activator AS (PARTITION BY callsign, program_slug, reference)
+            ORDER BY callsign, program_slug, reference, spotted_at DESC
+        ) latest
+        WHERE ($2::text IS NULL OR upper(mode) = $2)
+          AND ($3::float8 IS NULL OR frequency_khz BETWEEN $3 AND $4)
+        ORDER BY last_spotted_at DESC, callsign
+        "#,
+    )
+    .bind(&params.program)
+    .bind(&params.mode)
+    .bind(params.band.map(|(low, _)| low))
+    .bind(params.band.map(|(_, high)| high))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Spot counts per band and UTC hour of day for spots seen since `since`,
+/// optionally for one program. Spots outside every band are skipped, and
+/// suspended users' self-spots are hidden as in `list_spots`.
+pub async fn get_spot_heatmap(
+    pool: &PgPool,
+    program: Option<&str>,
+    since: DateTime<Utc>,
+) -> Result<Vec<SpotHeatmapCellRow>, AppError> {
+    let rows = sqlx::query_as::<_, SpotHeatmapCellRow>(
+        r#"
+        SELECT band,
+               EXTRACT(HOUR FROM spotted_at AT TIME ZONE 'UTC')::int AS hour,
+               COUNT(*) AS count
+        FROM spots
+        WHERE band IS NOT NULL
+          AND spotted_at >= $1
+          AND ($2::text IS NULL OR program_slug = $2)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+        GROUP BY band, hour
+        "#,
+    )
+    .bind(since)
+    .bind(program)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Minutes a self-spot or partner-pushed spot stays active.
+const SUBMITTED_SPOT_TTL_MINUTES: i64 = 30;
+
 /// Parameters for creating a self-spot.
 pub struct InsertSelfSpotParams<'a> {
     pub participant_id: Uuid,
+    pub user_id: Uuid,
     pub callsign: &'a str,
     pub program_slug: &'a str,
     pub frequency_khz: f64,
+    /// One of `CANONICAL_MODES`; `raw_mode` keeps what the client sent.
     pub mode: &'a str,
+    pub raw_mode: Option<&'a str>,
     pub reference: Option<&'a str>,
+    /// Derived from the reference by the program's country pattern.
+    pub country_code: Option<&'a str>,
     pub comments: Option<&'a str>,
+    pub ingest_channel: IngestChannel,
 }
 
 /// Insert a self-spot. Enforces one unexpired self-spot per user+program.
+/// The reference name and location come from `program_references`.
+/// Also records the spot in `self_spot_history`, which outlives TTL cleanup.
+///
+/// Runs in a transaction holding a lock on the participant row, so concurrent
+/// requests from one participant take turns and only the first passes the check.
 pub async fn insert_self_spot(
     pool: &PgPool,
     params: &InsertSelfSpotParams<'_>,
 ) -> Result<SpotRow, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // Locking the existing spot rows wouldn't stop two first spots racing, so
+    // serialize on the participant instead
+    sqlx::query("SELECT 1 FROM participants WHERE id = $1 FOR UPDATE")
+        .bind(params.participant_id)
+        .execute(&mut *tx)
+        .await?;
+
     // Check for existing unexpired self-spot
     let existing = sqlx::query_scalar::<_, i64>(
         r#"
@@ -84,26 +326,32 @@ pub async fn insert_self_spot(
     )
     .bind(params.participant_id)
     .bind(params.program_slug)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
     if existing > 0 {
         return Err(AppError::SelfSpotExists);
     }
 
-    let expires_at = Utc::now() + Duration::minutes(30);
+    let expires_at = Utc::now() + Duration::minutes(SUBMITTED_SPOT_TTL_MINUTES);
 
     let row = sqlx::query_as::<_, SpotRow>(
         r#"
         INSERT INTO spots (
             callsign, program_slug, source, frequency_khz, mode,
-            reference, comments, submitted_by, spotted_at, expires_at
+            reference, comments, submitted_by, spotted_at, expires_at,
+            country_code, band, ingest_channel, raw_mode, reference_name, latitude, longitude
+        )
+        VALUES (
+            $1, $2, 'self', $3, $4, $5, $6, $7, now(), $8, $9, $10, $11, $12,
+            (SELECT name FROM program_references WHERE program_slug = $2 AND reference = $5),
+            (SELECT latitude FROM program_references WHERE program_slug = $2 AND reference = $5),
+            (SELECT longitude FROM program_references WHERE program_slug = $2 AND reference = $5)
         )
-        VALUES ($1, $2, 'self', $3, $4, $5, $6, $7, now(), $8)
         RETURNING id, callsign, program_slug, source, external_id,
                   frequency_khz, mode, reference, reference_name,
                   spotter, spotter_grid, location_desc, country_code, state_abbr,
-                  comments, snr, wpm, submitted_by,
+                  comments, snr, wpm, submitted_by, latitude, longitude,
                   spotted_at, expires_at, created_at, updated_at
         "#,
     )
@@ -115,24 +363,117 @@ pub async fn insert_self_spot(
     .bind(params.comments)
     .bind(params.participant_id)
     .bind(expires_at)
+    .bind(params.country_code)
+    .bind(band_for_khz(params.frequency_khz))
+    .bind(params.ingest_channel)
+    .bind(params.raw_mode)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO self_spot_history (
+            user_id, spot_id, program_slug, reference, frequency_khz, mode, spotted_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(params.user_id)
+    .bind(row.id)
+    .bind(params.program_slug)
+    .bind(&row.reference)
+    .bind(row.frequency_khz)
+    .bind(&row.mode)
+    .bind(row.spotted_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    crate::spot_stream::announce(pool, &row).await;
+
+    Ok(row)
+}
+
+/// Parameters for a spot pushed by an integration partner.
+pub struct InsertIntegrationSpotParams<'a> {
+    pub integration_key_id: Uuid,
+    pub callsign: &'a str,
+    pub program_slug: &'a str,
+    pub frequency_khz: f64,
+    /// One of `CANONICAL_MODES`; `raw_mode` keeps what the partner sent.
+    pub mode: &'a str,
+    pub raw_mode: Option<&'a str>,
+    pub reference: Option<&'a str>,
+    /// Derived from the reference by the program's country pattern.
+    pub country_code: Option<&'a str>,
+    pub spotter: Option<&'a str>,
+    pub comments: Option<&'a str>,
+}
+
+/// Insert a partner-pushed spot with source `other` and ingest channel
+/// `integration`, recording the key it came in with. Reference name and location come from `program_references`,
+/// as for self-spots.
+pub async fn insert_integration_spot(
+    pool: &PgPool,
+    params: &InsertIntegrationSpotParams<'_>,
+) -> Result<SpotRow, AppError> {
+    let expires_at = Utc::now() + Duration::minutes(SUBMITTED_SPOT_TTL_MINUTES);
+
+    let row = sqlx::query_as::<_, SpotRow>(
+        r#"
+        INSERT INTO spots (
+            callsign, program_slug, source, frequency_khz, mode,
+            reference, comments, spotter, integration_key_id, spotted_at, expires_at,
+            country_code, band, ingest_channel, raw_mode, reference_name, latitude, longitude
+        )
+        VALUES (
+            $1, $2, 'other', $3, $4, $5, $6, $7, $8, now(), $9, $10, $11, 'integration', $12,
+            (SELECT name FROM program_references WHERE program_slug = $2 AND reference = $5),
+            (SELECT latitude FROM program_references WHERE program_slug = $2 AND reference = $5),
+            (SELECT longitude FROM program_references WHERE program_slug = $2 AND reference = $5)
+        )
+        RETURNING id, callsign, program_slug, source, external_id,
+                  frequency_khz, mode, reference, reference_name,
+                  spotter, spotter_grid, location_desc, country_code, state_abbr,
+                  comments, snr, wpm, submitted_by, latitude, longitude,
+                  spotted_at, expires_at, created_at, updated_at
+        "#,
+    )
+    .bind(params.callsign)
+    .bind(params.program_slug)
+    .bind(params.frequency_khz)
+    .bind(params.mode)
+    .bind(params.reference)
+    .bind(params.comments)
+    .bind(params.spotter)
+    .bind(params.integration_key_id)
+    .bind(expires_at)
+    .bind(params.country_code)
+    .bind(band_for_khz(params.frequency_khz))
+    .bind(params.raw_mode)
     .fetch_one(pool)
     .await?;
+    crate::spot_stream::announce(pool, &row).await;
 
     Ok(row)
 }
 
-/// Get a single spot by ID.
-#[allow(dead_code)]
+/// Get a single spot by ID. Self-spots from suspended users are hidden.
 pub async fn get_spot(pool: &PgPool, spot_id: Uuid) -> Result<Option<SpotRow>, AppError> {
     let row = sqlx::query_as::<_, SpotRow>(
         r#"
         SELECT id, callsign, program_slug, source, external_id,
                frequency_khz, mode, reference, reference_name,
                spotter, spotter_grid, location_desc, country_code, state_abbr,
-               comments, snr, wpm, submitted_by,
+               comments, snr, wpm, submitted_by, latitude, longitude,
                spotted_at, expires_at, created_at, updated_at
         FROM spots
         WHERE id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
         "#,
     )
     .bind(spot_id)
@@ -142,6 +483,32 @@ pub async fn get_spot(pool: &PgPool, spot_id: Uuid) -> Result<Option<SpotRow>, A
     Ok(row)
 }
 
+/// Spots by ID, expired ones included, for POST /v1/spots/lookup. IDs with no
+/// stored spot, or whose self-spotter is suspended, are simply absent.
+pub async fn get_spots_by_ids(pool: &PgPool, ids: &[Uuid]) -> Result<Vec<SpotRow>, AppError> {
+    let rows = sqlx::query_as::<_, SpotRow>(
+        r#"
+        SELECT id, callsign, program_slug, source, external_id,
+               frequency_khz, mode, reference, reference_name,
+               spotter, spotter_grid, location_desc, country_code, state_abbr,
+               comments, snr, wpm, submitted_by, latitude, longitude,
+               spotted_at, expires_at, created_at, updated_at
+        FROM spots
+        WHERE id = ANY($1)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+        "#,
+    )
+    .bind(ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
 /// Delete a spot by ID, verifying ownership (submitted_by must match).
 pub async fn delete_own_spot(
     pool: &PgPool,
@@ -150,8 +517,13 @@ pub async fn delete_own_spot(
 ) -> Result<bool, AppError> {
     let result = sqlx::query(
         r#"
-        DELETE FROM spots
-        WHERE id = $1 AND submitted_by = $2
+        WITH deleted AS (
+            DELETE FROM spots
+            WHERE id = $1 AND submitted_by = $2
+            RETURNING id
+        )
+        INSERT INTO spot_tombstones (spot_id)
+        SELECT id FROM deleted
         "#,
     )
     .bind(spot_id)
@@ -162,31 +534,57 @@ pub async fn delete_own_spot(
     Ok(result.rows_affected() > 0)
 }
 
-/// Admin delete: remove any spot by ID.
-pub async fn admin_delete_spot(pool: &PgPool, spot_id: Uuid) -> Result<bool, AppError> {
-    let result = sqlx::query("DELETE FROM spots WHERE id = $1")
-        .bind(spot_id)
-        .execute(pool)
-        .await?;
+/// Delete every unexpired self-spot the participant submitted, leaving
+/// tombstones as [`delete_own_spot`] does. Returns how many were deleted.
+pub async fn delete_own_spots_all(pool: &PgPool, participant_id: Uuid) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        WITH deleted AS (
+            DELETE FROM spots
+            WHERE submitted_by = $1 AND source = 'self' AND expires_at > now()
+            RETURNING id
+        )
+        INSERT INTO spot_tombstones (spot_id)
+        SELECT id FROM deleted
+        "#,
+    )
+    .bind(participant_id)
+    .execute(pool)
+    .await?;
 
-    Ok(result.rows_affected() > 0)
+    Ok(result.rows_affected())
 }
 
-/// Delete all expired spots. Returns count of deleted rows.
-pub async fn delete_expired_spots(pool: &PgPool) -> Result<u64, AppError> {
-    let result = sqlx::query("DELETE FROM spots WHERE expires_at < now()")
-        .execute(pool)
-        .await?;
+/// Admin delete: remove any spot by ID.
+pub async fn admin_delete_spot(pool: &PgPool, spot_id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+        WITH deleted AS (DELETE FROM spots WHERE id = $1 RETURNING id)
+        INSERT INTO spot_tombstones (spot_id)
+        SELECT id FROM deleted
+        "#,
+    )
+    .bind(spot_id)
+    .execute(pool)
+    .await?;
 
-    Ok(result.rows_affected())
+    Ok(result.rows_affected() > 0)
 }
 
 /// Upsert an aggregated spot from an external source.
-/// Uses (source, external_id) for conflict resolution.
+/// Uses (source, external_id) for conflict resolution. The spot's location is
+/// the spotter grid center when known, else the reference's catalog
+/// location; a missing reference name is also filled from the catalog.
+/// A re-issued spot never moves backwards: `spotted_at` and `expires_at` only
+/// advance, to the incoming values or (for `expires_at`) `respot_expires_at`
+/// when later.
 pub async fn upsert_aggregated_spot(
     pool: &PgPool,
     spot: &AggregatedSpot,
+    respot_expires_at: Option<DateTime<Utc>>,
 ) -> Result<SpotRow, AppError> {
+    let grid_location = spot.spotter_grid.as_deref().and_then(grid_center);
+
     let row = sqlx::query_as::<_, SpotRow>(
         r#"
         INSERT INTO spots (
@@ -194,21 +592,41 @@ pub async fn upsert_aggregated_spot(
             frequency_khz, mode, reference, reference_name,
             spotter, spotter_grid, location_desc, country_code, state_abbr,
             comments, snr, wpm,
-            spotted_at, expires_at
+            spotted_at, expires_at,
+            latitude, longitude, band, raw_mode
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7,
+            COALESCE($8, (SELECT name FROM program_references WHERE program_slug = $2 AND reference = $7)),
+            $9, $10, $11, $12, $13, $14, $15, $16, $17, $18,
+            COALESCE($19, (SELECT latitude FROM program_references WHERE program_slug = $2 AND reference = $7)),
+            COALESCE($20, (SELECT longitude FROM program_references WHERE program_slug = $2 AND reference = $7)),
+            $22, $23
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
         ON CONFLICT (source, external_id) WHERE external_id IS NOT NULL
         DO UPDATE SET
-            frequency_khz = EXCLUDED.frequency_khz,
-            mode = EXCLUDED.mode,
-            reference = EXCLUDED.reference,
-            reference_name = EXCLUDED.reference_name,
-            comments = EXCLUDED.comments,
+            -- A stale re-delivery keeps the newer sighting's data as well as
+            -- its timestamps
+            frequency_khz = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.frequency_khz ELSE spots.frequency_khz END,
+            band = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.band ELSE spots.band END,
+            mode = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.mode ELSE spots.mode END,
+            raw_mode = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.raw_mode ELSE spots.raw_mode END,
+            reference = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.reference ELSE spots.reference END,
+            reference_name = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.reference_name ELSE spots.reference_name END,
+            comments = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.comments ELSE spots.comments END,
+            spotter = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.spotter ELSE spots.spotter END,
+            spotter_grid = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.spotter_grid ELSE spots.spotter_grid END,
+            snr = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.snr ELSE spots.snr END,
+            wpm = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.wpm ELSE spots.wpm END,
+            latitude = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.latitude ELSE spots.latitude END,
+            longitude = CASE WHEN EXCLUDED.spotted_at >= spots.spotted_at THEN EXCLUDED.longitude ELSE spots.longitude END,
+            spotted_at = GREATEST(spots.spotted_at, EXCLUDED.spotted_at),
+            expires_at = GREATEST(spots.expires_at, EXCLUDED.expires_at, $21),
             updated_at = now()
         RETURNING id, callsign, program_slug, source, external_id,
                   frequency_khz, mode, reference, reference_name,
                   spotter, spotter_grid, location_desc, country_code, state_abbr,
-                  comments, snr, wpm, submitted_by,
+                  comments, snr, wpm, submitted_by, latitude, longitude,
                   spotted_at, expires_at, created_at, updated_at
         "#,
     )
@@ -230,8 +648,17 @@ pub async fn upsert_aggregated_spot(
     .bind(spot.wpm)
     .bind(spot.spotted_at)
     .bind(spot.expires_at)
+    .bind(grid_location.map(|(lat, _)| lat))
+    .bind(grid_location.map(|(_, lon)| lon))
+    .bind(respot_expires_at)
+    .bind(band_for_khz(spot.frequency_khz))
+    .bind(&spot.raw_mode)
     .fetch_one(pool)
     .await?;
+    crate::spot_stream::announce(pool, &row).await;
 
     Ok(row)
 }
+
+#[cfg(test)]
+mod tests;
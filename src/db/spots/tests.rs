@@ -0,0 +1,582 @@
+use super::*;
+use crate::db::test_support;
+use chrono::SubsecRound;
+use std::collections::HashMap;
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn upsert_refreshes_reissued_spot() {
+    let pool = test_support::pool().await;
+    let external_id = format!("test-{}", Uuid::new_v4());
+    // Whole seconds, so values round-trip through timestamptz exactly
+    let first_at = Utc::now().trunc_subsecs(0);
+
+    let first = upsert_aggregated_spot(
+        &pool,
+        &test_support::aggregated_spot(&external_id, first_at),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let mut again = test_support::aggregated_spot(&external_id, first_at + Duration::minutes(5));
+    again.spotter = Some("KM3T".to_string());
+    again.snr = Some(20);
+    let second = upsert_aggregated_spot(&pool, &again, None).await.unwrap();
+
+    // A stale copy of the first sighting must not move the row back
+    let stale = upsert_aggregated_spot(
+        &pool,
+        &test_support::aggregated_spot(&external_id, first_at),
+        None,
+    )
+    .await
+    .unwrap();
+
+    sqlx::query("DELETE FROM spots WHERE id = $1")
+        .bind(first.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(second.id, first.id);
+    assert_eq!(second.expires_at, again.expires_at);
+    assert_eq!(second.spotted_at, again.spotted_at);
+    assert_eq!(second.spotter.as_deref(), Some("KM3T"));
+    assert_eq!(second.snr, Some(20));
+    assert_eq!(stale.expires_at, again.expires_at);
+    assert_eq!(stale.spotted_at, again.spotted_at);
+    assert_eq!(stale.spotter.as_deref(), Some("KM3T"));
+    assert_eq!(stale.snr, Some(20));
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn respot_ttl_extends_expiry_on_reupsert() {
+    let pool = test_support::pool().await;
+    let external_id = format!("test-{}", Uuid::new_v4());
+    let now = Utc::now().trunc_subsecs(0);
+    // Spotted 8 minutes ago, so it would expire in 2
+    let mut spot = test_support::aggregated_spot(&external_id, now - Duration::minutes(8));
+    spot.source = SpotSource::Pota;
+
+    let first = upsert_aggregated_spot(&pool, &spot, None).await.unwrap();
+    let respot_at = now + Duration::minutes(10);
+    let respotted = upsert_aggregated_spot(&pool, &spot, Some(respot_at))
+        .await
+        .unwrap();
+    // An earlier re-spot expiry never pulls it back in
+    let earlier = upsert_aggregated_spot(&pool, &spot, Some(now))
+        .await
+        .unwrap();
+
+    sqlx::query("DELETE FROM spots WHERE id = $1")
+        .bind(first.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(first.expires_at, spot.expires_at);
+    assert_eq!(respotted.id, first.id);
+    assert_eq!(respotted.expires_at, respot_at);
+    assert_eq!(respotted.spotted_at, spot.spotted_at);
+    assert_eq!(earlier.expires_at, respot_at);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn activators_collapse_to_their_newest_spot() {
+    let pool = test_support::pool().await;
+    let callsign = test_support::callsign();
+    let now = Utc::now().trunc_subsecs(0);
+    // Three spots at one park, the newest on 20m, and one later at another park
+    for (reference, minutes_ago, frequency_khz) in [
+        ("US-0001", 20, 7030.0),
+        ("US-0001", 5, 14062.0),
+        ("US-0001", 10, 7032.0),
+        ("US-0002", 3, 10110.0),
+    ] {
+        let mut spot = test_support::aggregated_spot(
+            &format!("test-{}", Uuid::new_v4()),
+            now - Duration::minutes(minutes_ago),
+        );
+        spot.callsign = callsign.clone();
+        spot.source = SpotSource::Pota;
+        spot.program_slug = Some("pota".to_string());
+        spot.reference = Some(reference.to_string());
+        spot.frequency_khz = frequency_khz;
+        spot.expires_at = now + Duration::minutes(30);
+        upsert_aggregated_spot(&pool, &spot, None).await.unwrap();
+    }
+
+    let activators = |band: Option<(f64, f64)>| {
+        let pool = pool.clone();
+        let callsign = callsign.clone();
+        async move {
+            list_activators(
+                &pool,
+                &ListActivatorsParams {
+                    program: Some("pota".to_string()),
+                    mode: None,
+                    band,
+                },
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|row| row.callsign == callsign)
+            .collect::<Vec<_>>()
+        }
+    };
+    let all = activators(None).await;
+    let on_40m = activators(Some((7000.0, 7300.0))).await;
+
+    sqlx::query("DELETE FROM spots WHERE callsign = $1")
+        .bind(&callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let references: Vec<_> = all.iter().map(|row| row.reference.as_deref()).collect();
+    assert_eq!(references, [Some("US-0002"), Some("US-0001")]);
+    let park = &all[1];
+    assert_eq!(park.spot_count, 3);
+    assert_eq!(park.frequency_khz, 14062.0);
+    assert_eq!(park.first_spotted_at, now - Duration::minutes(20));
+    assert_eq!(park.last_spotted_at, now - Duration::minutes(5));
+    assert_eq!(all[0].spot_count, 1);
+    // Earlier 40m spots don't list the activator there once they moved on
+    assert!(on_40m.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn callsign_spots_span_programs_newest_first() {
+    let pool = test_support::pool().await;
+    let callsign = test_support::callsign();
+    let now = Utc::now().trunc_subsecs(0);
+
+    let mut spots = Vec::new();
+    for (program, source, minutes_ago) in [
+        ("pota", SpotSource::Pota, 5),
+        ("sota", SpotSource::Sota, 2),
+        ("pota", SpotSource::Pota, 40),
+    ] {
+        let mut spot = test_support::aggregated_spot(
+            &format!("test-{}", Uuid::new_v4()),
+            now - Duration::minutes(minutes_ago),
+        );
+        spot.callsign = callsign.clone();
+        spot.program_slug = Some(program.to_string());
+        spot.source = source;
+        spots.push(upsert_aggregated_spot(&pool, &spot, None).await.unwrap());
+    }
+
+    let found = list_callsign_spots(&pool, &callsign.to_lowercase())
+        .await
+        .unwrap();
+
+    sqlx::query("DELETE FROM spots WHERE callsign = $1")
+        .bind(&callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // The 40-minute-old spot expired after ten minutes
+    let ids: Vec<Uuid> = found.iter().map(|s| s.id).collect();
+    assert_eq!(ids, vec![spots[1].id, spots[0].id]);
+    assert_eq!(found[0].program_slug.as_deref(), Some("sota"));
+    assert_eq!(found[1].program_slug.as_deref(), Some("pota"));
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn callsign_spot_summary_groups_by_source() {
+    let pool = test_support::pool().await;
+    let callsign = test_support::callsign();
+    let now = Utc::now().trunc_subsecs(0);
+
+    for (source, spotter, snr, minutes_ago, khz) in [
+        (SpotSource::Rbn, "w3lpl", Some(18), 4, 14062.0),
+        (SpotSource::Rbn, "K9TM", Some(25), 3, 14062.0),
+        (SpotSource::Rbn, "K9TM", None, 1, 7030.0),
+        (SpotSource::Wspr, "VE3EID", Some(-12), 2, 14097.1),
+    ] {
+        let mut spot = test_support::aggregated_spot(
+            &format!("test-{}", Uuid::new_v4()),
+            now - Duration::minutes(minutes_ago),
+        );
+        spot.callsign = callsign.clone();
+        spot.source = source;
+        spot.spotter = Some(spotter.to_string());
+        spot.spotter_grid = Some(format!("{spotter}-grid"));
+        spot.snr = snr;
+        spot.frequency_khz = khz;
+        upsert_aggregated_spot(&pool, &spot, None).await.unwrap();
+    }
+
+    let rows = get_callsign_spot_summary(&pool, &callsign.to_lowercase())
+        .await
+        .unwrap();
+
+    sqlx::query("DELETE FROM spots WHERE callsign = $1")
+        .bind(&callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 2);
+    let rbn = &rows[0];
+    assert_eq!(rbn.source, SpotSource::Rbn);
+    assert_eq!(rbn.spot_count, 3);
+    assert_eq!(rbn.spotters, ["K9TM", "W3LPL"]);
+    assert_eq!(rbn.best_snr, Some(25));
+    assert_eq!(rbn.best_snr_spotter.as_deref(), Some("K9TM"));
+    assert_eq!(rbn.best_snr_spotter_grid.as_deref(), Some("K9TM-grid"));
+    assert_eq!(rbn.newest_spotted_at, now - Duration::minutes(1));
+    assert_eq!(rbn.newest_frequency_khz, 7030.0);
+    assert_eq!(rows[1].source, SpotSource::Wspr);
+    assert_eq!(rows[1].best_snr, Some(-12));
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn newest_spots_for_callsigns_returns_one_per_callsign() {
+    let pool = test_support::pool().await;
+    let active = test_support::callsign();
+    let single = format!("N{}", &Uuid::new_v4().simple().to_string()[..8]).to_uppercase();
+    let quiet = format!("W{}", &Uuid::new_v4().simple().to_string()[..8]).to_uppercase();
+    for (callsign, minutes_ago, frequency_khz) in [
+        (&active, 5, 7030.0),
+        (&active, 1, 14062.0),
+        (&single, 2, 10110.0),
+    ] {
+        let mut spot = test_support::aggregated_spot(
+            &format!("test-{}", Uuid::new_v4()),
+            Utc::now() - Duration::minutes(minutes_ago),
+        );
+        spot.callsign = callsign.to_lowercase();
+        spot.frequency_khz = frequency_khz;
+        upsert_aggregated_spot(&pool, &spot, None).await.unwrap();
+    }
+
+    let rows =
+        get_newest_spots_for_callsigns(&pool, &[active.clone(), single.clone(), quiet.clone()])
+            .await
+            .unwrap();
+
+    let frequency = |callsign: &str| {
+        rows.iter()
+            .filter(|row| row.callsign.eq_ignore_ascii_case(callsign))
+            .map(|row| row.frequency_khz)
+            .collect::<Vec<_>>()
+    };
+    sqlx::query("DELETE FROM spots WHERE upper(callsign) = ANY($1)")
+        .bind(vec![active.clone(), single.clone()])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(frequency(&active), vec![14062.0]);
+    assert_eq!(frequency(&single), vec![10110.0]);
+    assert!(frequency(&quiet).is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn self_spot_ingest_channel_defaults_to_app() {
+    let pool = test_support::pool().await;
+    let callsign = test_support::callsign();
+    let (participant, _) = crate::db::get_or_create_participant(&pool, &callsign, None)
+        .await
+        .unwrap();
+    let user = crate::db::get_or_create_user(&pool, &callsign)
+        .await
+        .unwrap();
+    let self_spot = insert_self_spot(
+        &pool,
+        &InsertSelfSpotParams {
+            participant_id: participant.id,
+            user_id: user.id,
+            callsign: &callsign,
+            program_slug: "pota",
+            frequency_khz: 14062.0,
+            mode: "CW",
+            raw_mode: None,
+            reference: None,
+            country_code: None,
+            comments: None,
+            ingest_channel: IngestChannel::default(),
+        },
+    )
+    .await
+    .unwrap();
+    let mut aggregated =
+        test_support::aggregated_spot(&format!("test-{}", Uuid::new_v4()), Utc::now());
+    aggregated.callsign = callsign.clone();
+    let aggregated = upsert_aggregated_spot(&pool, &aggregated, None)
+        .await
+        .unwrap();
+
+    let channel = |id: Uuid| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, Option<IngestChannel>>(
+                "SELECT ingest_channel FROM spots WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    let self_channel = channel(self_spot.id).await;
+    let aggregated_channel = channel(aggregated.id).await;
+
+    sqlx::query("DELETE FROM spots WHERE callsign = $1")
+        .bind(&callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+    test_support::cleanup_users(&pool, [user.id]).await;
+
+    assert_eq!(self_channel, Some(IngestChannel::App));
+    assert_eq!(aggregated_channel, None);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn delete_own_spots_all_leaves_other_users_spots() {
+    let pool = test_support::pool().await;
+    let mut activators = Vec::new();
+    for programs in [&["pota", "sota"][..], &["pota"][..]] {
+        let callsign = test_support::callsign();
+        let (participant, _) = crate::db::get_or_create_participant(&pool, &callsign, None)
+            .await
+            .unwrap();
+        let user = crate::db::get_or_create_user(&pool, &callsign)
+            .await
+            .unwrap();
+        for program_slug in programs {
+            insert_self_spot(
+                &pool,
+                &InsertSelfSpotParams {
+                    participant_id: participant.id,
+                    user_id: user.id,
+                    callsign: &callsign,
+                    program_slug,
+                    frequency_khz: 14062.0,
+                    mode: "CW",
+                    raw_mode: None,
+                    reference: None,
+                    country_code: None,
+                    comments: None,
+                    ingest_channel: IngestChannel::default(),
+                },
+            )
+            .await
+            .unwrap();
+        }
+        activators.push((callsign, participant.id, user.id));
+    }
+    let spot_count = |callsign: String| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM spots WHERE callsign = $1")
+                .bind(callsign)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+
+    let deleted = delete_own_spots_all(&pool, activators[0].1).await.unwrap();
+    let again = delete_own_spots_all(&pool, activators[0].1).await.unwrap();
+    let own_left = spot_count(activators[0].0.clone()).await;
+    let other_left = spot_count(activators[1].0.clone()).await;
+
+    for (callsign, _, user_id) in &activators {
+        sqlx::query("DELETE FROM spots WHERE callsign = $1")
+            .bind(callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        test_support::cleanup_users(&pool, [*user_id]).await;
+    }
+    assert_eq!(deleted, 2);
+    assert_eq!(again, 0);
+    assert_eq!(own_left, 0);
+    assert_eq!(other_left, 1);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn excluded_submitter_hides_own_self_spot() {
+    let pool = test_support::pool().await;
+    let callsign = test_support::callsign();
+    let (participant, _) = crate::db::get_or_create_participant(&pool, &callsign, None)
+        .await
+        .unwrap();
+    let user = crate::db::get_or_create_user(&pool, &callsign)
+        .await
+        .unwrap();
+    insert_self_spot(
+        &pool,
+        &InsertSelfSpotParams {
+            participant_id: participant.id,
+            user_id: user.id,
+            callsign: &callsign,
+            program_slug: "pota",
+            frequency_khz: 14062.0,
+            mode: "CW",
+            raw_mode: None,
+            reference: None,
+            country_code: None,
+            comments: None,
+            ingest_channel: IngestChannel::default(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let own_spots = |excluded_submitter: Option<Uuid>| ListSpotsParams {
+        program: None,
+        callsign: Some(callsign.clone()),
+        source: None,
+        mode: None,
+        state: None,
+        max_age_minutes: 30,
+        limit: 10,
+        cursor: None,
+        bbox: None,
+        hidden_callsigns: vec![],
+        excluded_submitter,
+    };
+    let by_default = list_spots(&pool, &own_spots(None)).await.unwrap().len();
+    let excluded = list_spots(&pool, &own_spots(Some(participant.id)))
+        .await
+        .unwrap()
+        .len();
+    let other_excluded = list_spots(&pool, &own_spots(Some(Uuid::new_v4())))
+        .await
+        .unwrap()
+        .len();
+
+    sqlx::query("DELETE FROM spots WHERE callsign = $1")
+        .bind(&callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+    test_support::cleanup_users(&pool, [user.id]).await;
+    assert_eq!(by_default, 1);
+    assert_eq!(excluded, 0);
+    assert_eq!(other_excluded, 1);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn spots_by_ids_include_expired_and_skip_unknown() {
+    let pool = test_support::pool().await;
+    let now = Utc::now().trunc_subsecs(0);
+    let active = upsert_aggregated_spot(
+        &pool,
+        &test_support::aggregated_spot(&format!("test-{}", Uuid::new_v4()), now),
+        None,
+    )
+    .await
+    .unwrap();
+    let expired = upsert_aggregated_spot(
+        &pool,
+        &test_support::aggregated_spot(
+            &format!("test-{}", Uuid::new_v4()),
+            now - Duration::minutes(15),
+        ),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let mut found = get_spots_by_ids(&pool, &[expired.id, Uuid::new_v4(), active.id])
+        .await
+        .unwrap();
+
+    sqlx::query("DELETE FROM spots WHERE id = ANY($1)")
+        .bind(vec![active.id, expired.id])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    found.sort_by_key(|s| s.spotted_at);
+    let ids: Vec<Uuid> = found.iter().map(|s| s.id).collect();
+    assert_eq!(ids, vec![expired.id, active.id]);
+    assert!(found[0].expires_at <= now);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn bbox_keeps_located_spots_inside_including_across_the_antimeridian() {
+    let pool = test_support::pool().await;
+    let callsign = test_support::callsign();
+    // Located at the spotter's grid square center; no grid, no location
+    let mut ids = HashMap::new();
+    for grid in [Some("FN31"), Some("RK39"), Some("AK09"), Some("QK39"), None] {
+        let mut spot =
+            test_support::aggregated_spot(&format!("test-{}", Uuid::new_v4()), Utc::now());
+        spot.callsign = callsign.clone();
+        spot.spotter_grid = grid.map(str::to_string);
+        let row = upsert_aggregated_spot(&pool, &spot, None).await.unwrap();
+        ids.insert(row.id, grid.unwrap_or("none"));
+    }
+
+    let listed = |bbox: Option<BoundingBox>| {
+        let pool = pool.clone();
+        let params = ListSpotsParams {
+            program: None,
+            callsign: Some(callsign.clone()),
+            source: None,
+            mode: None,
+            state: None,
+            max_age_minutes: 30,
+            limit: 10,
+            cursor: None,
+            bbox,
+            hidden_callsigns: vec![],
+            excluded_submitter: None,
+        };
+        let ids = ids.clone();
+        async move {
+            let mut grids: Vec<&str> = list_spots(&pool, &params)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| ids[&row.id])
+                .collect();
+            grids.sort_unstable();
+            grids
+        }
+    };
+    let bbox = |min_lat, max_lat, min_lon, max_lon| {
+        Some(BoundingBox {
+            min_lat,
+            max_lat,
+            min_lon,
+            max_lon,
+        })
+    };
+    let unbounded = listed(None).await;
+    let connecticut = listed(bbox(40.0, 43.0, -75.0, -71.0)).await;
+    // 160°E eastward to 170°W
+    let antimeridian = listed(bbox(10.0, 30.0, 160.0, -170.0)).await;
+    let empty = listed(bbox(-60.0, -50.0, 0.0, 10.0)).await;
+
+    sqlx::query("DELETE FROM spots WHERE callsign = $1")
+        .bind(&callsign)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(unbounded, ["AK09", "FN31", "QK39", "RK39", "none"]);
+    assert_eq!(connecticut, ["FN31"]);
+    assert_eq!(antimeridian, ["AK09", "RK39"]);
+    assert!(empty.is_empty());
+}
@@ -0,0 +1,521 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+use crate::db::spots::ListSpotsParams;
+use crate::db::spots_delta::SPOT_TOMBSTONE_RETENTION_HOURS;
+use crate::error::AppError;
+use crate::models::spot::{SpotRow, SpotsArchiveCountRow};
+
+/// List spots spotted in `[from, to)` with the same filters and cursor as
+/// [`list_spots`], `max_age_minutes` aside. Expired spots still in the live
+/// table count, and so do archived ones: `spots_archive` is read in the same
+/// query, so a window reaching back past TTL cleanup falls through to the
+/// archive transparently, while a recent one finds nothing there. Archived
+/// spots keep only their core columns; the rest read as null, `expires_at`
+/// and `updated_at` are the time they were archived, and `state` or bounding
+/// box filters leave them out. Suspended users' self-spots and the excluded
+/// submitter's spots are left out of both tables. Ordered newest first, then by id, across both
+/// tables. Returns up to `limit + 1` rows so the caller can determine
+/// `has_more`.
+pub async fn list_spots_between(
+    pool: &PgPool,
+    params: &ListSpotsParams,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<SpotRow>, AppError> {
+    let rows = sqlx::query_as::<_, SpotRow>(
+        r#"
+        SELECT id, callsign, program_slug, source, external_id,
+               frequency_khz, mode, reference, reference_name,
+               spotter, spotter_grid, location_desc, country_code, state_abbr,
+               comments, snr, wpm, submitted_by, latitude, longitude,
+               spotted_at, expires_at, created_at, updated_at
+        FROM spots
+        WHERE spotted_at >= $1 AND spotted_at < $2
+          AND ($3::text IS NULL OR program_slug = $3)
+          AND ($4::text IS NULL OR callsign = $4)
+          AND ($5::spot_source IS NULL OR source = $5)
+          AND ($6::text IS NULL OR upper(mode) = $6)
+          AND ($7::text IS NULL OR state_abbr = $7)
+          AND ($8::timestamptz IS NULL OR spotted_at < $8)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+          AND ($10::float8 IS NULL OR (
+              latitude BETWEEN $10 AND $11
+              AND CASE WHEN $12 <= $13
+                  THEN longitude BETWEEN $12 AND $13
+                  ELSE longitude >= $12 OR longitude <= $13
+              END
+          ))
+          AND NOT (callsign = ANY($14))
+          AND ($15::uuid IS NULL OR submitted_by IS DISTINCT FROM $15)
+        UNION ALL
+        SELECT id, callsign, program_slug, source, NULL,
+               frequency_khz, mode, reference, NULL,
+               spotter, NULL, NULL, NULL, NULL,
+               comments, NULL, NULL, submitted_by, NULL, NULL,
+               spotted_at, archived_at, spotted_at, archived_at
+        FROM spots_archive
+        WHERE spotted_at >= $1 AND spotted_at < $2
+          AND ($3::text IS NULL OR program_slug = $3)
+          AND ($4::text IS NULL OR callsign = $4)
+          AND ($5::spot_source IS NULL OR source = $5)
+          AND ($6::text IS NULL OR upper(mode) = $6)
+          AND $7::text IS NULL
+          AND ($8::timestamptz IS NULL OR spotted_at < $8)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots_archive.submitted_by AND u.suspended_at IS NOT NULL
+          )
+          AND $10::float8 IS NULL
+          AND NOT (callsign = ANY($14))
+          AND ($15::uuid IS NULL OR submitted_by IS DISTINCT FROM $15)
+        ORDER BY spotted_at DESC, id
+        LIMIT $9
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(&params.program)
+    .bind(&params.callsign)
+    .bind(&params.source)
+    .bind(&params.mode)
+    .bind(&params.state)
+    .bind(params.cursor)
+    .bind(params.limit + 1)
+    .bind(params.bbox.map(|b| b.min_lat))
+    .bind(params.bbox.map(|b| b.max_lat))
+    .bind(params.bbox.map(|b| b.min_lon))
+    .bind(params.bbox.map(|b| b.max_lon))
+    .bind(&params.hidden_callsigns)
+    .bind(params.excluded_submitter)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Delete all expired spots, leaving tombstones for delta clients. With
+/// `archive`, each spot is first copied into `spots_archive` in the same
+/// statement, so a spot is never deleted unless it was archived. Returns
+/// count of deleted rows.
+pub async fn delete_expired_spots(pool: &PgPool, archive: bool) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        WITH deleted AS (
+            DELETE FROM spots WHERE expires_at < now()
+            RETURNING id, source, program_slug, callsign, reference, frequency_khz,
+                      band, mode, raw_mode, spotter, comments, spotted_at, submitted_by
+        ),
+        archived AS (
+            INSERT INTO spots_archive (
+                id, source, program_slug, callsign, reference, frequency_khz,
+                band, mode, raw_mode, spotter, comments, spotted_at, submitted_by
+            )
+            SELECT id, source, program_slug, callsign, reference, frequency_khz,
+                   band, mode, raw_mode, spotter, comments, spotted_at, submitted_by
+            FROM deleted
+            WHERE $1
+        )
+        INSERT INTO spot_tombstones (spot_id)
+        SELECT id FROM deleted
+        "#,
+    )
+    .bind(archive)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete archived spots spotted more than `months` ago. Returns count of
+/// deleted rows.
+pub async fn prune_spots_archive(pool: &PgPool, months: i64) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        "DELETE FROM spots_archive WHERE spotted_at < now() - make_interval(months => $1)",
+    )
+    .bind(months as i32)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Characters an archived comment keeps once it is past its retention.
+pub const ARCHIVED_COMMENT_KEEP_CHARS: i32 = 32;
+
+/// Shorten the comments of archived spots spotted more than `days` ago to
+/// their first [`ARCHIVED_COMMENT_KEEP_CHARS`] characters. Returns count of
+/// shortened rows.
+pub async fn truncate_archived_comments(pool: &PgPool, days: i32) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE spots_archive SET comments = left(comments, $2)
+        WHERE spotted_at < now() - make_interval(days => $1)
+          AND char_length(comments) > $2
+        "#,
+    )
+    .bind(days)
+    .bind(ARCHIVED_COMMENT_KEEP_CHARS)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Archived spots spotted in `[from, to)`, counted by source and program.
+pub async fn get_spots_archive_counts(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<SpotsArchiveCountRow>, AppError> {
+    let rows = sqlx::query_as::<_, SpotsArchiveCountRow>(
+        r#"
+        SELECT source, program_slug, COUNT(*) AS count
+        FROM spots_archive
+        WHERE spotted_at >= $1 AND spotted_at < $2
+        GROUP BY source, program_slug
+        ORDER BY count DESC, source, program_slug NULLS LAST
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Delete spot tombstones past the retention window. Returns count of deleted rows.
+pub async fn prune_spot_tombstones(pool: &PgPool) -> Result<u64, AppError> {
+    let result = sqlx::query("DELETE FROM spot_tombstones WHERE deleted_at < $1")
+        .bind(Utc::now() - Duration::hours(SPOT_TOMBSTONE_RETENTION_HOURS))
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::spots::upsert_aggregated_spot;
+    use crate::db::test_support;
+    use crate::models::spot::SpotSource;
+    use chrono::SubsecRound;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn expired_spots_are_archived_and_deleted_together() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let now = Utc::now().trunc_subsecs(0);
+        let mut spots = Vec::new();
+        for minutes_ago in [60, 40, 1] {
+            let mut spot = test_support::aggregated_spot(
+                &format!("test-{}", Uuid::new_v4()),
+                now - Duration::minutes(minutes_ago),
+            );
+            spot.callsign = callsign.clone();
+            spot.program_slug = Some("pota".to_string());
+            spot.source = SpotSource::Pota;
+            spot.reference = Some("US-0001".to_string());
+            spots.push(upsert_aggregated_spot(&pool, &spot, None).await.unwrap());
+        }
+        let (archived_id, unarchived_id, live_id) = (spots[0].id, spots[1].id, spots[2].id);
+
+        let count_rows = |table: &'static str, column: &'static str, id: Uuid| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>(&format!(
+                    "SELECT COUNT(*) FROM {table} WHERE {column} = $1"
+                ))
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+            }
+        };
+
+        // An archive row in the way fails the archive insert, so nothing is
+        // deleted or tombstoned either
+        sqlx::query(
+            "INSERT INTO spots_archive (id, source, callsign, frequency_khz, mode, spotted_at)
+             VALUES ($1, 'pota', 'BLOCKER', 14062, 'CW', now())",
+        )
+        .bind(archived_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(delete_expired_spots(&pool, true).await.is_err());
+        assert_eq!(count_rows("spots", "id", archived_id).await, 1);
+        assert_eq!(count_rows("spots", "id", unarchived_id).await, 1);
+        assert_eq!(
+            count_rows("spot_tombstones", "spot_id", archived_id).await,
+            0
+        );
+
+        sqlx::query("DELETE FROM spots_archive WHERE id = $1")
+            .bind(archived_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        // Archiving off: the 40-minute-old spot is deleted without a copy
+        sqlx::query("UPDATE spots SET expires_at = now() + interval '1 hour' WHERE id = $1")
+            .bind(archived_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        delete_expired_spots(&pool, false).await.unwrap();
+        sqlx::query("UPDATE spots SET expires_at = now() - interval '1 minute' WHERE id = $1")
+            .bind(archived_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        delete_expired_spots(&pool, true).await.unwrap();
+
+        let archived = get_spots_archive_counts(&pool, now - Duration::hours(2), now)
+            .await
+            .unwrap();
+        let archived_callsign: Option<String> =
+            sqlx::query_scalar("SELECT callsign FROM spots_archive WHERE id = $1")
+                .bind(archived_id)
+                .fetch_optional(&pool)
+                .await
+                .unwrap();
+        let live_count = count_rows("spots", "id", live_id).await;
+        let unarchived_count = count_rows("spots_archive", "id", unarchived_id).await;
+        let tombstones = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM spot_tombstones WHERE spot_id = ANY($1)",
+        )
+        .bind(vec![archived_id, unarchived_id])
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query("DELETE FROM spots WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM spots_archive WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM spot_tombstones WHERE spot_id = ANY($1)")
+            .bind(vec![archived_id, unarchived_id])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(archived_callsign, Some(callsign));
+        assert_eq!(unarchived_count, 0);
+        assert_eq!(live_count, 1);
+        assert_eq!(tombstones, 2);
+        assert!(archived.iter().any(
+            |row| row.source == SpotSource::Pota && row.program_slug.as_deref() == Some("pota")
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn old_archived_comments_are_truncated() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let long = "CQ POTA US-0001 QRT at 1900Z, thanks for all the contacts".to_string();
+        let short = "TU 73".to_string();
+        let mut fixtures = Vec::new();
+        for (days_ago, comments) in [
+            (40, Some(long.clone())),
+            (40, Some(short.clone())),
+            (40, None),
+            (5, Some(long.clone())),
+        ] {
+            let id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO spots_archive (id, source, callsign, frequency_khz, mode, comments, spotted_at)
+                 VALUES ($1, 'pota', $2, 14062, 'CW', $3, $4)",
+            )
+            .bind(id)
+            .bind(&callsign)
+            .bind(&comments)
+            .bind(Utc::now() - Duration::days(days_ago))
+            .execute(&pool)
+            .await
+            .unwrap();
+            fixtures.push(id);
+        }
+
+        let truncated = truncate_archived_comments(&pool, 30).await.unwrap();
+        let mut comments = Vec::new();
+        for id in &fixtures {
+            let comment: Option<String> =
+                sqlx::query_scalar("SELECT comments FROM spots_archive WHERE id = $1")
+                    .bind(id)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            comments.push(comment);
+        }
+
+        sqlx::query("DELETE FROM spots_archive WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(truncated >= 1);
+        assert_eq!(
+            comments,
+            vec![
+                Some(long[..ARCHIVED_COMMENT_KEEP_CHARS as usize].to_string()),
+                Some(short),
+                None,
+                Some(long),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn spots_between_spans_live_and_archived_spots() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let now = Utc::now().trunc_subsecs(0);
+        // One spot long since archived and two live ones, an hour old and
+        // recent
+        let archived_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO spots_archive (id, source, program_slug, callsign, frequency_khz, mode, spotted_at)
+             VALUES ($1, 'pota', 'pota', $2, 7030, 'CW', $3)",
+        )
+        .bind(archived_id)
+        .bind(&callsign)
+        .bind(now - Duration::hours(3))
+        .execute(&pool)
+        .await
+        .unwrap();
+        // An archived self-spot whose submitter has since been suspended
+        let (participant, _) = crate::db::get_or_create_participant(&pool, &callsign, None)
+            .await
+            .unwrap();
+        let user = crate::db::get_or_create_user(&pool, &callsign)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE users SET suspended_at = now() WHERE id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let self_spot_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO spots_archive (id, source, program_slug, callsign, frequency_khz, mode, spotted_at, submitted_by)
+             VALUES ($1, 'self', 'pota', $2, 14062, 'CW', $3, $4)",
+        )
+        .bind(self_spot_id)
+        .bind(&callsign)
+        .bind(now - Duration::hours(2) - Duration::minutes(30))
+        .bind(participant.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let mut live_ids = Vec::new();
+        for minutes_ago in [60, 5] {
+            let mut spot = test_support::aggregated_spot(
+                &format!("test-{}", Uuid::new_v4()),
+                now - Duration::minutes(minutes_ago),
+            );
+            spot.callsign = callsign.clone();
+            // Unexpired, so another test's expiry cleanup can't delete or
+            // archive it mid-test
+            spot.expires_at = now + Duration::minutes(30);
+            live_ids.push(upsert_aggregated_spot(&pool, &spot, None).await.unwrap().id);
+        }
+
+        let params = |cursor: Option<DateTime<Utc>>| ListSpotsParams {
+            program: None,
+            callsign: Some(callsign.clone()),
+            source: None,
+            mode: None,
+            state: None,
+            max_age_minutes: 30,
+            limit: 10,
+            cursor,
+            bbox: None,
+            hidden_callsigns: vec![],
+            excluded_submitter: None,
+        };
+        let ids = |rows: Vec<SpotRow>| rows.into_iter().map(|row| row.id).collect::<Vec<_>>();
+        let spanning = list_spots_between(&pool, &params(None), now - Duration::hours(4), now)
+            .await
+            .unwrap();
+        let recent = list_spots_between(&pool, &params(None), now - Duration::hours(2), now)
+            .await
+            .unwrap();
+        let older_page = list_spots_between(
+            &pool,
+            &params(Some(now - Duration::minutes(60))),
+            now - Duration::hours(4),
+            now,
+        )
+        .await
+        .unwrap();
+        // Once unsuspended the self-spot is back, except for its submitter
+        // excluding their own spots
+        sqlx::query("UPDATE users SET suspended_at = NULL WHERE id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let unsuspended = list_spots_between(&pool, &params(None), now - Duration::hours(4), now)
+            .await
+            .unwrap();
+        let own_excluded = list_spots_between(
+            &pool,
+            &ListSpotsParams {
+                excluded_submitter: Some(participant.id),
+                ..params(None)
+            },
+            now - Duration::hours(4),
+            now,
+        )
+        .await
+        .unwrap();
+
+        sqlx::query("DELETE FROM spots WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM spots_archive WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        test_support::cleanup_users(&pool, [user.id]).await;
+
+        // The suspended user's archived self-spot is left out
+        assert_eq!(
+            ids(spanning.clone()),
+            vec![live_ids[1], live_ids[0], archived_id]
+        );
+        assert_eq!(spanning[2].frequency_khz, 7030.0);
+        assert_eq!(spanning[2].spotter, None);
+        assert_eq!(ids(recent), vec![live_ids[1], live_ids[0]]);
+        assert_eq!(ids(older_page), vec![archived_id]);
+        assert_eq!(
+            ids(unsuspended),
+            vec![live_ids[1], live_ids[0], self_spot_id, archived_id]
+        );
+        assert_eq!(
+            ids(own_excluded),
+            vec![live_ids[1], live_ids[0], archived_id]
+        );
+    }
+}
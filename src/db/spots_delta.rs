@@ -0,0 +1,283 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::spots::ListSpotsParams;
+use crate::error::AppError;
+use crate::models::spot::{GroupedSpotRow, SpotRow, MAX_GROUPED_OTHER_SPOTS};
+
+/// How long deleted spot IDs are kept for delta clients.
+pub const SPOT_TOMBSTONE_RETENTION_HOURS: i64 = 24;
+
+/// Changes to the spots table since a delta cursor, read from one snapshot.
+pub struct SpotsDelta {
+    /// Database time the snapshot was taken.
+    pub as_of: DateTime<Utc>,
+    pub spots: Vec<SpotRow>,
+    pub deleted: Vec<Uuid>,
+}
+
+/// Active spots updated after `since` and tombstoned IDs deleted after it.
+/// Without `since`, every active spot and no tombstones. Suspended users'
+/// self-spots are hidden as in `list_spots`.
+pub async fn get_spots_delta(
+    pool: &PgPool,
+    since: Option<DateTime<Utc>>,
+) -> Result<SpotsDelta, AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .execute(&mut *tx)
+        .await?;
+
+    let as_of = sqlx::query_scalar::<_, DateTime<Utc>>("SELECT now()")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let spots = sqlx::query_as::<_, SpotRow>(
+        r#"
+        SELECT id, callsign, program_slug, source, external_id,
+               frequency_khz, mode, reference, reference_name,
+               spotter, spotter_grid, location_desc, country_code, state_abbr,
+               comments, snr, wpm, submitted_by, latitude, longitude,
+               spotted_at, expires_at, created_at, updated_at
+        FROM spots
+        WHERE expires_at > now()
+          AND ($1::timestamptz IS NULL OR updated_at > $1)
+          AND NOT EXISTS (
+              SELECT 1 FROM participants p
+              JOIN users u ON u.callsign = p.callsign
+              WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+          )
+        ORDER BY updated_at
+        "#,
+    )
+    .bind(since)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let deleted = match since {
+        Some(since) => {
+            sqlx::query_scalar::<_, Uuid>(
+                "SELECT spot_id FROM spot_tombstones WHERE deleted_at > $1 ORDER BY deleted_at",
+            )
+            .bind(since)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+        None => Vec::new(),
+    };
+
+    tx.commit().await?;
+
+    Ok(SpotsDelta {
+        as_of,
+        spots,
+        deleted,
+    })
+}
+
+/// List active spots grouped by callsign, with the same filters as
+/// [`list_spots`] applied before grouping. Groups are ordered by their newest
+/// spot and paged by `after`, the newest `spotted_at` and callsign of the last
+/// group on the previous page; `params.cursor` is ignored. Each group returns
+/// its newest spot and up to [`MAX_GROUPED_OTHER_SPOTS`] more, with
+/// `spot_count` covering all of them. Returns up to `limit + 1` groups.
+pub async fn list_spot_groups(
+    pool: &PgPool,
+    params: &ListSpotsParams,
+    after: Option<&(DateTime<Utc>, String)>,
+) -> Result<Vec<GroupedSpotRow>, AppError> {
+    let cutoff = Utc::now() - Duration::minutes(params.max_age_minutes);
+
+    let rows = sqlx::query_as::<_, GroupedSpotRow>(
+        r#"
+        WITH ranked AS (
+            SELECT id, callsign, program_slug, source, external_id,
+                   frequency_khz, mode, reference, reference_name,
+                   spotter, spotter_grid, location_desc, country_code, state_abbr,
+                   comments, snr, wpm, submitted_by, latitude, longitude,
+                   spotted_at, expires_at, created_at, updated_at,
+                   ROW_NUMBER() OVER (
+                       PARTITION BY callsign ORDER BY spotted_at DESC, id
+                   ) AS group_rank,
+                   COUNT(*) OVER (PARTITION BY callsign) AS spot_count
+            FROM spots
+            WHERE expires_at > now()
+              AND spotted_at >= $1
+              AND ($2::text IS NULL OR program_slug = $2)
+              AND ($3::text IS NULL OR callsign = $3)
+              AND ($4::spot_source IS NULL OR source = $4)
+              AND ($5::text IS NULL OR upper(mode) = $5)
+              AND ($6::text IS NULL OR state_abbr = $6)
+              AND NOT EXISTS (
+                  SELECT 1 FROM participants p
+                  JOIN users u ON u.callsign = p.callsign
+                  WHERE p.id = spots.submitted_by AND u.suspended_at IS NOT NULL
+              )
+              AND ($9::float8 IS NULL OR (
+                  latitude BETWEEN $9 AND $10
+                  AND CASE WHEN $11 <= $12
+                      THEN longitude BETWEEN $11 AND $12
+                      ELSE longitude >= $11 OR longitude <= $12
+                  END
+              ))
+              AND NOT (callsign = ANY($13))
+              AND ($14::uuid IS NULL OR submitted_by IS DISTINCT FROM $14)
+        ),
+        groups AS (
+            SELECT callsign, spotted_at AS group_spotted_at
+            FROM ranked
+            WHERE group_rank = 1
+              AND ($7::timestamptz IS NULL OR (spotted_at, callsign) < ($7, $15))
+            ORDER BY spotted_at DESC, callsign DESC
+            LIMIT $8
+        )
+        SELECT r.id, r.callsign, r.program_slug, r.source, r.external_id,
+               r.frequency_khz, r.mode, r.reference, r.reference_name,
+               r.spotter, r.spotter_grid, r.location_desc, r.country_code, r.state_abbr,
+               r.comments, r.snr, r.wpm, r.submitted_by, r.latitude, r.longitude,
+               r.spotted_at, r.expires_at, r.created_at, r.updated_at, r.spot_count
+        FROM ranked r
+        JOIN groups g ON g.callsign = r.callsign
+        WHERE r.group_rank <= $16
+        ORDER BY g.group_spotted_at DESC, r.callsign DESC, r.group_rank
+        "#,
+    )
+    .bind(cutoff)
+    .bind(&params.program)
+    .bind(&params.callsign)
+    .bind(&params.source)
+    .bind(&params.mode)
+    .bind(&params.state)
+    .bind(after.map(|(spotted_at, _)| *spotted_at))
+    .bind(params.limit + 1)
+    .bind(params.bbox.map(|b| b.min_lat))
+    .bind(params.bbox.map(|b| b.max_lat))
+    .bind(params.bbox.map(|b| b.min_lon))
+    .bind(params.bbox.map(|b| b.max_lon))
+    .bind(&params.hidden_callsigns)
+    .bind(params.excluded_submitter)
+    .bind(after.map(|(_, callsign)| callsign.as_str()))
+    .bind(MAX_GROUPED_OTHER_SPOTS + 1)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::spots::upsert_aggregated_spot;
+    use crate::db::test_support;
+    use chrono::SubsecRound;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn spot_groups_apply_filters_before_grouping_and_page_by_group() {
+        let pool = test_support::pool().await;
+        // A state no other test uses keeps this test's spots to themselves
+        let state = format!("T{}", &Uuid::new_v4().simple().to_string()[..6]);
+        let call = |prefix: &str| {
+            format!("{prefix}{}", &Uuid::new_v4().simple().to_string()[..6]).to_uppercase()
+        };
+        let (busy, quiet, blocked) = (call("K"), call("W"), call("N"));
+        let now = Utc::now().trunc_subsecs(0);
+        let spot = |callsign: &str, mode: &str, minutes_ago: i64| {
+            let mut spot = test_support::aggregated_spot(
+                &format!("test-{}", Uuid::new_v4()),
+                now - Duration::minutes(minutes_ago),
+            );
+            spot.callsign = callsign.to_string();
+            spot.mode = mode.to_string();
+            spot.state_abbr = Some(state.clone());
+            spot.expires_at = now + Duration::minutes(30);
+            spot
+        };
+        let mut spots = vec![
+            spot(&busy, "SSB", 1),
+            spot(&blocked, "CW", 3),
+            spot(&quiet, "CW", 5),
+        ];
+        spots.extend((2..14).map(|minutes_ago| spot(&busy, "CW", minutes_ago)));
+        for spot in &spots {
+            upsert_aggregated_spot(&pool, spot, None).await.unwrap();
+        }
+
+        let params = |mode: Option<&str>, limit: i64, hidden: &[&String]| ListSpotsParams {
+            program: None,
+            callsign: None,
+            source: None,
+            mode: mode.map(str::to_string),
+            state: Some(state.clone()),
+            max_age_minutes: 30,
+            limit,
+            cursor: None,
+            bbox: None,
+            hidden_callsigns: hidden.iter().map(|c| c.to_string()).collect(),
+            excluded_submitter: None,
+        };
+        let groups = |rows: Vec<GroupedSpotRow>| {
+            crate::models::spot::group_spot_rows(rows)
+                .into_iter()
+                .map(|g| {
+                    (
+                        g.newest.callsign,
+                        g.newest.mode,
+                        g.spot_count,
+                        g.others.len(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let all = groups(
+            list_spot_groups(&pool, &params(None, 10, &[]), None)
+                .await
+                .unwrap(),
+        );
+        let cw = groups(
+            list_spot_groups(&pool, &params(Some("CW"), 10, &[&blocked]), None)
+                .await
+                .unwrap(),
+        );
+        // limit + 1 groups come back, so one group per page leaves a second
+        let first_page = list_spot_groups(&pool, &params(None, 1, &[]), None)
+            .await
+            .unwrap();
+        let after = (now - Duration::minutes(3), blocked.clone());
+        let after_blocked = groups(
+            list_spot_groups(&pool, &params(None, 10, &[]), Some(&after))
+                .await
+                .unwrap(),
+        );
+
+        sqlx::query("DELETE FROM spots WHERE state_abbr = $1")
+            .bind(&state)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            all,
+            [
+                (busy.clone(), "SSB".to_string(), 13, 10),
+                (blocked.clone(), "CW".to_string(), 1, 0),
+                (quiet.clone(), "CW".to_string(), 1, 0),
+            ]
+        );
+        // The mode filter applies before grouping, so it changes both the
+        // newest spot and the count
+        assert_eq!(
+            cw,
+            [
+                (busy.clone(), "CW".to_string(), 12, 10),
+                (quiet.clone(), "CW".to_string(), 1, 0),
+            ]
+        );
+        let first_groups = groups(first_page);
+        assert_eq!(first_groups.len(), 2);
+        assert_eq!(first_groups[0].0, busy);
+        assert_eq!(after_blocked, [(quiet, "CW".to_string(), 1, 0)]);
+    }
+}
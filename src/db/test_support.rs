@@ -0,0 +1,79 @@
+//! Shared setup for the database tests, which are `#[ignore]`d and run
+//! against a migrated database with `DATABASE_URL=... cargo test -- --ignored`.
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::spot::{AggregatedSpot, SpotSource};
+use crate::models::User;
+
+/// A pool on the database in `DATABASE_URL`.
+pub(crate) async fn pool() -> PgPool {
+    PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+        .await
+        .unwrap()
+}
+
+/// A random callsign, so tests don't collide with each other or real data.
+pub(crate) fn callsign() -> String {
+    format!("K{}", &Uuid::new_v4().simple().to_string()[..8]).to_uppercase()
+}
+
+/// `n` new users with random callsigns.
+pub(crate) async fn users(pool: &PgPool, n: usize) -> Vec<User> {
+    let mut users = Vec::with_capacity(n);
+    for _ in 0..n {
+        users.push(
+            crate::db::get_or_create_user(pool, &callsign())
+                .await
+                .unwrap(),
+        );
+    }
+    users
+}
+
+/// Delete the given users and any participants under their callsigns. Rows
+/// referencing the users (activities, friendships, requests, blocks) go with
+/// them; spots are left to the test.
+pub(crate) async fn cleanup_users(pool: &PgPool, user_ids: impl IntoIterator<Item = Uuid>) {
+    let user_ids: Vec<Uuid> = user_ids.into_iter().collect();
+    sqlx::query(
+        "DELETE FROM participants
+         WHERE callsign IN (SELECT callsign FROM users WHERE id = ANY($1))",
+    )
+    .bind(&user_ids)
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)")
+        .bind(&user_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+/// An RBN spot of K1ABC, expiring 10 minutes after `spotted_at`.
+pub(crate) fn aggregated_spot(external_id: &str, spotted_at: DateTime<Utc>) -> AggregatedSpot {
+    AggregatedSpot {
+        callsign: "K1ABC".to_string(),
+        program_slug: None,
+        source: SpotSource::Rbn,
+        external_id: external_id.to_string(),
+        frequency_khz: 14062.0,
+        mode: "CW".to_string(),
+        raw_mode: None,
+        reference: None,
+        reference_name: None,
+        spotter: Some("W1NT".to_string()),
+        spotter_grid: None,
+        location_desc: None,
+        country_code: None,
+        state_abbr: None,
+        comments: None,
+        snr: Some(12),
+        wpm: Some(22),
+        spotted_at,
+        expires_at: spotted_at + Duration::minutes(10),
+    }
+}
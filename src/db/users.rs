@@ -2,7 +2,7 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::models::User;
+use crate::models::{AdminUserRow, MergeCounts, MergeTableCount, User};
 
 pub async fn get_user_by_callsign(pool: &PgPool, callsign: &str) -> Result<Option<User>, AppError> {
     let user = sqlx::query_as::<_, User>(
@@ -34,19 +34,27 @@ pub async fn get_user_by_id(pool: &PgPool, user_id: Uuid) -> Result<Option<User>
     Ok(user)
 }
 
-pub async fn search_users(pool: &PgPool, query: &str, limit: i64) -> Result<Vec<User>, AppError> {
+/// Users whose callsign contains `query`, leaving out those in `excluded`.
+pub async fn search_users(
+    pool: &PgPool,
+    query: &str,
+    excluded: &[Uuid],
+    limit: i64,
+) -> Result<Vec<User>, AppError> {
     let pattern = format!("%{}%", query.to_uppercase());
     let users = sqlx::query_as::<_, User>(
         r#"
         SELECT id, callsign, created_at
         FROM users
         WHERE UPPER(callsign) LIKE $1
+          AND NOT (id = ANY($3))
         ORDER BY callsign
         LIMIT $2
         "#,
     )
     .bind(&pattern)
     .bind(limit)
+    .bind(excluded)
     .fetch_all(pool)
     .await?;
 
@@ -249,7 +257,7 @@ pub async fn change_callsign(
 }
 
 /// Merge an old (previous callsign) user account into the current user.
-/// Transfers all UUID-keyed data (friendships, friend_requests, activities)
+/// Transfers all UUID-keyed data (friendships, friend_requests, blocks, activities)
 /// and callsign-keyed data from the old user to the current user,
 /// then deletes the old user record.
 pub async fn merge_previous_account(
@@ -325,6 +333,38 @@ pub async fn merge_previous_account(
     .execute(&mut *tx)
     .await?;
 
+    // Transfer blocks both ways; a block between the two accounts is dropped
+    // with the old user
+    sqlx::query(
+        r#"
+        UPDATE blocks SET blocker_id = $1
+        WHERE blocker_id = $2 AND blocked_id <> $1
+        AND NOT EXISTS (
+            SELECT 1 FROM blocks b2
+            WHERE b2.blocker_id = $1 AND b2.blocked_id = blocks.blocked_id
+        )
+        "#,
+    )
+    .bind(current_user_id)
+    .bind(old_user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE blocks SET blocked_id = $1
+        WHERE blocked_id = $2 AND blocker_id <> $1
+        AND NOT EXISTS (
+            SELECT 1 FROM blocks b2
+            WHERE b2.blocked_id = $1 AND b2.blocker_id = blocks.blocker_id
+        )
+        "#,
+    )
+    .bind(current_user_id)
+    .bind(old_user_id)
+    .execute(&mut *tx)
+    .await?;
+
     // Transfer activities (UUID-keyed via user_id)
     sqlx::query("UPDATE activities SET user_id = $1, callsign = $3 WHERE user_id = $2")
         .bind(current_user_id)
@@ -333,6 +373,12 @@ pub async fn merge_previous_account(
         .execute(&mut *tx)
         .await?;
 
+    sqlx::query("UPDATE self_spot_history SET user_id = $1 WHERE user_id = $2")
+        .bind(current_user_id)
+        .bind(old_user_id)
+        .execute(&mut *tx)
+        .await?;
+
     // Transfer callsign-keyed data from old callsign
     sqlx::query(
         "UPDATE challenge_participants SET callsign = $1 WHERE callsign = $2",
@@ -396,6 +442,223 @@ pub async fn merge_previous_account(
     Ok(())
 }
 
+/// Merge one account into another in a single transaction: activities,
+/// challenge participations and progress, earned badges, club memberships,
+/// self-spot history, submitted spots, friendships, friend requests, blocks,
+/// and friend invites move to `to`, then `from` is suspended.
+///
+/// Where both accounts have a row for the same thing (a challenge, badge,
+/// club, or friend), one is dropped: for progress the higher score wins,
+/// otherwise the target's row is kept. With `dry_run` the transaction is
+/// rolled back, so the counts preview the merge without changing anything.
+pub async fn merge_users(
+    pool: &PgPool,
+    from: &AdminUserRow,
+    to: &AdminUserRow,
+    dry_run: bool,
+) -> Result<MergeCounts, AppError> {
+    let mut counts = MergeCounts::new();
+    let mut tx = pool.begin().await?;
+
+    // Progress: keep the better score per challenge (the target's on a tie)
+    let mut dropped = sqlx::query(
+        r#"
+        DELETE FROM progress t
+        USING progress s
+        WHERE t.callsign = $2 AND s.callsign = $1
+          AND s.challenge_id = t.challenge_id AND s.score > t.score
+        "#,
+    )
+    .bind(&from.callsign)
+    .bind(&to.callsign)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    dropped += sqlx::query(
+        r#"
+        DELETE FROM progress s
+        USING progress t
+        WHERE s.callsign = $1 AND t.callsign = $2 AND t.challenge_id = s.challenge_id
+        "#,
+    )
+    .bind(&from.callsign)
+    .bind(&to.callsign)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let moved = sqlx::query("UPDATE progress SET callsign = $2 WHERE callsign = $1")
+        .bind(&from.callsign)
+        .bind(&to.callsign)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    counts.insert("progress", MergeTableCount { moved, dropped });
+
+    // Challenge participations: keep the target's row, with the earlier join
+    // and the source's status if the target had left
+    sqlx::query(
+        r#"
+        UPDATE challenge_participants t
+        SET joined_at = LEAST(t.joined_at, s.joined_at),
+            status = CASE WHEN t.status = 'left' THEN s.status ELSE t.status END
+        FROM challenge_participants s
+        WHERE t.callsign = $2 AND s.callsign = $1 AND s.challenge_id = t.challenge_id
+        "#,
+    )
+    .bind(&from.callsign)
+    .bind(&to.callsign)
+    .execute(&mut *tx)
+    .await?;
+    let dropped = sqlx::query(
+        r#"
+        DELETE FROM challenge_participants s
+        USING challenge_participants t
+        WHERE s.callsign = $1 AND t.callsign = $2 AND t.challenge_id = s.challenge_id
+        "#,
+    )
+    .bind(&from.callsign)
+    .bind(&to.callsign)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let moved = sqlx::query("UPDATE challenge_participants SET callsign = $2 WHERE callsign = $1")
+        .bind(&from.callsign)
+        .bind(&to.callsign)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    counts.insert("challenge_participants", MergeTableCount { moved, dropped });
+
+    // Callsign-keyed tables unique per (thing, callsign): drop the source's duplicates
+    for (table, key) in [("earned_badges", "badge_id"), ("club_members", "club_id")] {
+        let dropped = sqlx::query(&format!(
+            "DELETE FROM {table} s USING {table} t \
+             WHERE s.callsign = $1 AND t.callsign = $2 AND t.{key} = s.{key}"
+        ))
+        .bind(&from.callsign)
+        .bind(&to.callsign)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let moved = sqlx::query(&format!(
+            "UPDATE {table} SET callsign = $2 WHERE callsign = $1"
+        ))
+        .bind(&from.callsign)
+        .bind(&to.callsign)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        counts.insert(table, MergeTableCount { moved, dropped });
+    }
+
+    let moved = sqlx::query("UPDATE activities SET user_id = $2, callsign = $3 WHERE user_id = $1")
+        .bind(from.id)
+        .bind(to.id)
+        .bind(&to.callsign)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    counts.insert("activities", MergeTableCount { moved, dropped: 0 });
+
+    let moved = sqlx::query("UPDATE self_spot_history SET user_id = $2 WHERE user_id = $1")
+        .bind(from.id)
+        .bind(to.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    counts.insert("self_spot_history", MergeTableCount { moved, dropped: 0 });
+
+    // Spots point at a participant (device registration). Without one for
+    // the target they stay with the source and expire as usual.
+    let moved = sqlx::query(
+        r#"
+        UPDATE spots
+        SET submitted_by = (
+            SELECT id FROM participants WHERE callsign = $2
+            ORDER BY last_seen_at DESC NULLS LAST, created_at DESC
+            LIMIT 1
+        )
+        WHERE submitted_by IN (SELECT id FROM participants WHERE callsign = $1)
+          AND EXISTS (SELECT 1 FROM participants WHERE callsign = $2)
+        "#,
+    )
+    .bind(&from.callsign)
+    .bind(&to.callsign)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    counts.insert("spots", MergeTableCount { moved, dropped: 0 });
+
+    // Friendships, friend requests, and blocks, one row per direction: drop
+    // rows that would duplicate one the target has, or link the target to itself
+    for (table, left, right) in [
+        ("friendships", "user_id", "friend_id"),
+        ("friend_requests", "from_user_id", "to_user_id"),
+        ("blocks", "blocker_id", "blocked_id"),
+    ] {
+        let mut dropped = 0;
+        let mut moved = 0;
+        for (side, other) in [(left, right), (right, left)] {
+            dropped += sqlx::query(&format!(
+                "DELETE FROM {table} s \
+                 WHERE s.{side} = $1 \
+                   AND (s.{other} = $2 OR EXISTS ( \
+                       SELECT 1 FROM {table} t WHERE t.{side} = $2 AND t.{other} = s.{other}))"
+            ))
+            .bind(from.id)
+            .bind(to.id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            moved += sqlx::query(&format!("UPDATE {table} SET {side} = $2 WHERE {side} = $1"))
+                .bind(from.id)
+                .bind(to.id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        counts.insert(table, MergeTableCount { moved, dropped });
+    }
+
+    let mut moved = 0;
+    for column in ["user_id", "used_by_user_id"] {
+        moved += sqlx::query(&format!(
+            "UPDATE friend_invites SET {column} = $2 WHERE {column} = $1"
+        ))
+        .bind(from.id)
+        .bind(to.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    counts.insert("friend_invites", MergeTableCount { moved, dropped: 0 });
+
+    sqlx::query(
+        r#"
+        INSERT INTO callsign_history (user_id, old_callsign, new_callsign)
+        VALUES ($1, $2, $3)
+        "#,
+    )
+    .bind(to.id)
+    .bind(&from.callsign)
+    .bind(&to.callsign)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE users SET suspended_at = COALESCE(suspended_at, now()) WHERE id = $1")
+        .bind(from.id)
+        .execute(&mut *tx)
+        .await?;
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+
+    Ok(counts)
+}
+
 pub async fn get_or_create_user(pool: &PgPool, callsign: &str) -> Result<User, AppError> {
     let user = sqlx::query_as::<_, User>(
         r#"
@@ -411,3 +674,75 @@ pub async fn get_or_create_user(pool: &PgPool, callsign: &str) -> Result<User, A
 
     Ok(user)
 }
+
+/// List users for the admin console, optionally filtered by suspension state.
+/// `suspended = Some(true)` returns only suspended users, `Some(false)` only active ones.
+/// `callsign` matches the current callsign or any earlier one in `callsign_history`.
+pub async fn list_users_admin(
+    pool: &PgPool,
+    suspended: Option<bool>,
+    callsign: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AdminUserRow>, AppError> {
+    let users = sqlx::query_as::<_, AdminUserRow>(
+        r#"
+        SELECT id, callsign, created_at, suspended_at
+        FROM users
+        WHERE ($1::bool IS NULL OR (suspended_at IS NOT NULL) = $1)
+          AND ($2::text IS NULL
+               OR callsign = $2
+               OR id IN (SELECT user_id FROM callsign_history WHERE old_callsign = $2))
+        ORDER BY COALESCE(suspended_at, created_at) DESC, callsign
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(suspended)
+    .bind(callsign.map(str::to_uppercase))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+pub async fn get_admin_user(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<AdminUserRow>, AppError> {
+    let user = sqlx::query_as::<_, AdminUserRow>(
+        "SELECT id, callsign, created_at, suspended_at FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Suspend or unsuspend a user. Suspending an already-suspended user keeps the
+/// original timestamp. Returns `None` if the user does not exist.
+pub async fn set_user_suspended(
+    pool: &PgPool,
+    user_id: Uuid,
+    suspended: bool,
+) -> Result<Option<AdminUserRow>, AppError> {
+    let user = sqlx::query_as::<_, AdminUserRow>(
+        r#"
+        UPDATE users
+        SET suspended_at = CASE WHEN $2 THEN COALESCE(suspended_at, now()) ELSE NULL END
+        WHERE id = $1
+        RETURNING id, callsign, created_at, suspended_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(suspended)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,438 @@
+use super::*;
+use crate::db::spots::{InsertSelfSpotParams, ListSpotsParams};
+use crate::db::test_support;
+use crate::models::activity::FeedOrder;
+use crate::models::spot::IngestChannel;
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn suspension_hides_self_spots_and_feed_activity_until_lifted() {
+    let pool = test_support::pool().await;
+    let users = test_support::users(&pool, 2).await;
+    let (suspended, friend) = (&users[0], &users[1]);
+    sqlx::query("INSERT INTO friendships (user_id, friend_id) VALUES ($1, $2), ($2, $1)")
+        .bind(suspended.id)
+        .bind(friend.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let (participant, _) = crate::db::get_or_create_participant(&pool, &suspended.callsign, None)
+        .await
+        .unwrap();
+    let spot = crate::db::insert_self_spot(
+        &pool,
+        &InsertSelfSpotParams {
+            participant_id: participant.id,
+            user_id: suspended.id,
+            callsign: &suspended.callsign,
+            program_slug: "pota",
+            frequency_khz: 14062.0,
+            mode: "CW",
+            raw_mode: None,
+            reference: None,
+            country_code: None,
+            comments: None,
+            ingest_channel: IngestChannel::default(),
+        },
+    )
+    .await
+    .unwrap();
+    let activity = crate::db::insert_activity(
+        &pool,
+        suspended.id,
+        &suspended.callsign,
+        "personalBest",
+        chrono::Utc::now(),
+        &serde_json::json!({}),
+    )
+    .await
+    .unwrap();
+
+    // Whether the spot and the activity are visible to others
+    let visible = || {
+        let pool = pool.clone();
+        let callsign = suspended.callsign.clone();
+        async move {
+            let spots = crate::db::list_spots(
+                &pool,
+                &ListSpotsParams {
+                    program: None,
+                    callsign: Some(callsign),
+                    source: None,
+                    mode: None,
+                    state: None,
+                    max_age_minutes: 30,
+                    limit: 10,
+                    cursor: None,
+                    bbox: None,
+                    hidden_callsigns: vec![],
+                    excluded_submitter: None,
+                },
+            )
+            .await
+            .unwrap();
+            let feed =
+                crate::db::get_feed_for_user(&pool, friend.id, FeedOrder::Created, 10, None, None)
+                    .await
+                    .unwrap();
+            (
+                spots.iter().any(|row| row.id == spot.id),
+                feed.iter().any(|row| row.id == activity.id),
+            )
+        }
+    };
+
+    let before = visible().await;
+    set_user_suspended(&pool, suspended.id, true).await.unwrap();
+    let while_suspended = visible().await;
+    set_user_suspended(&pool, suspended.id, false)
+        .await
+        .unwrap();
+    let after = visible().await;
+
+    sqlx::query("DELETE FROM spots WHERE id = $1")
+        .bind(spot.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+    assert_eq!(before, (true, true));
+    assert_eq!(while_suspended, (false, false));
+    assert_eq!(after, (true, true));
+}
+
+async fn admin_user(pool: &PgPool, user: &User) -> AdminUserRow {
+    get_admin_user(pool, user.id).await.unwrap().unwrap()
+}
+
+async fn test_challenge(pool: &PgPool) -> Uuid {
+    crate::db::create_challenge(
+        pool,
+        &crate::models::CreateChallengeRequest {
+            name: format!("Merge test {}", Uuid::new_v4()),
+            description: "Account merge".to_string(),
+            author: None,
+            category: "award".to_string(),
+            challenge_type: "collection".to_string(),
+            configuration: serde_json::json!({}),
+            invite_config: None,
+            hamalert_config: None,
+            is_active: Some(true),
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+async fn delete_challenges(pool: &PgPool, ids: &[Uuid]) {
+    // Progress, badges, and earned badges go with them
+    sqlx::query("DELETE FROM challenges WHERE id = ANY($1)")
+        .bind(ids)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn befriend(pool: &PgPool, a: Uuid, b: Uuid) {
+    sqlx::query("INSERT INTO friendships (user_id, friend_id) VALUES ($1, $2), ($2, $1)")
+        .bind(a)
+        .bind(b)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn set_progress(pool: &PgPool, challenge_id: Uuid, callsign: &str, score: i32) {
+    sqlx::query("INSERT INTO progress (id, challenge_id, callsign, score) VALUES ($1, $2, $3, $4)")
+        .bind(Uuid::new_v4())
+        .bind(challenge_id)
+        .bind(callsign)
+        .bind(score)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn merge_dedupes_friendships_in_both_directions() {
+    let pool = test_support::pool().await;
+    let users = test_support::users(&pool, 4).await;
+    let (from, to, shared, other) = (&users[0], &users[1], &users[2], &users[3]);
+    befriend(&pool, from.id, shared.id).await;
+    befriend(&pool, to.id, shared.id).await;
+    befriend(&pool, from.id, other.id).await;
+    befriend(&pool, from.id, to.id).await;
+
+    let counts = merge_users(
+        &pool,
+        &admin_user(&pool, from).await,
+        &admin_user(&pool, to).await,
+        false,
+    )
+    .await
+    .unwrap();
+    let mut pairs: Vec<(Uuid, Uuid)> = sqlx::query_as(
+        "SELECT user_id, friend_id FROM friendships WHERE user_id = ANY($1) OR friend_id = ANY($1)",
+    )
+    .bind([from.id, to.id])
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+    // Both directions of the shared friendship and the pair's own friendship
+    // are dropped; the other friendship moves over
+    assert_eq!(
+        counts["friendships"],
+        MergeTableCount {
+            moved: 2,
+            dropped: 4
+        }
+    );
+    pairs.sort();
+    let mut expected = vec![
+        (to.id, shared.id),
+        (shared.id, to.id),
+        (to.id, other.id),
+        (other.id, to.id),
+    ];
+    expected.sort();
+    assert_eq!(pairs, expected);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn merge_moves_badges_and_club_memberships() {
+    let pool = test_support::pool().await;
+    let users = test_support::users(&pool, 2).await;
+    let (from, to) = (&users[0], &users[1]);
+    let challenge = test_challenge(&pool).await;
+    let mut badges = Vec::new();
+    let mut clubs = Vec::new();
+    for _ in 0..2 {
+        badges.push(
+            sqlx::query_scalar::<_, Uuid>(
+                "INSERT INTO badges (id, challenge_id, name, image_data, content_type)
+                 VALUES ($1, $2, 'Merge badge', '\\x00', 'image/png') RETURNING id",
+            )
+            .bind(Uuid::new_v4())
+            .bind(challenge)
+            .fetch_one(&pool)
+            .await
+            .unwrap(),
+        );
+        clubs.push(
+            sqlx::query_scalar::<_, Uuid>(
+                "INSERT INTO clubs (name) VALUES ('Merge club') RETURNING id",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap(),
+        );
+    }
+    // The source has both, the target only the first
+    for (callsign, count) in [(&from.callsign, 2), (&to.callsign, 1)] {
+        for i in 0..count {
+            sqlx::query("INSERT INTO earned_badges (id, badge_id, callsign) VALUES ($1, $2, $3)")
+                .bind(Uuid::new_v4())
+                .bind(badges[i])
+                .bind(callsign)
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO club_members (club_id, callsign) VALUES ($1, $2)")
+                .bind(clubs[i])
+                .bind(callsign)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    let counts = merge_users(
+        &pool,
+        &admin_user(&pool, from).await,
+        &admin_user(&pool, to).await,
+        false,
+    )
+    .await
+    .unwrap();
+    let held = |table: &'static str, key: &'static str| {
+        let pool = pool.clone();
+        async move {
+            let mut rows: Vec<(Uuid, String)> = sqlx::query_as(&format!(
+                "SELECT {key}, callsign FROM {table} WHERE callsign = ANY($1)"
+            ))
+            .bind([&from.callsign, &to.callsign])
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            rows.sort();
+            rows
+        }
+    };
+    let earned = held("earned_badges", "badge_id").await;
+    let members = held("club_members", "club_id").await;
+
+    sqlx::query("DELETE FROM clubs WHERE id = ANY($1)")
+        .bind(&clubs)
+        .execute(&pool)
+        .await
+        .unwrap();
+    delete_challenges(&pool, &[challenge]).await;
+    test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+    let one_each = MergeTableCount {
+        moved: 1,
+        dropped: 1,
+    };
+    assert_eq!(counts["earned_badges"], one_each);
+    assert_eq!(counts["club_members"], one_each);
+    let mut expected_badges = vec![
+        (badges[0], to.callsign.clone()),
+        (badges[1], to.callsign.clone()),
+    ];
+    expected_badges.sort();
+    let mut expected_clubs = vec![
+        (clubs[0], to.callsign.clone()),
+        (clubs[1], to.callsign.clone()),
+    ];
+    expected_clubs.sort();
+    assert_eq!(earned, expected_badges);
+    assert_eq!(members, expected_clubs);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn merge_keeps_the_better_progress_score() {
+    let pool = test_support::pool().await;
+    let users = test_support::users(&pool, 2).await;
+    let (from, to) = (&users[0], &users[1]);
+    let (source_better, target_better, source_only) = (
+        test_challenge(&pool).await,
+        test_challenge(&pool).await,
+        test_challenge(&pool).await,
+    );
+    set_progress(&pool, source_better, &from.callsign, 9).await;
+    set_progress(&pool, source_better, &to.callsign, 5).await;
+    set_progress(&pool, target_better, &from.callsign, 3).await;
+    set_progress(&pool, target_better, &to.callsign, 7).await;
+    set_progress(&pool, source_only, &from.callsign, 4).await;
+
+    let counts = merge_users(
+        &pool,
+        &admin_user(&pool, from).await,
+        &admin_user(&pool, to).await,
+        false,
+    )
+    .await
+    .unwrap();
+    let scores: Vec<(Uuid, String, i32)> = sqlx::query_as(
+        "SELECT challenge_id, callsign, score FROM progress WHERE challenge_id = ANY($1)",
+    )
+    .bind([source_better, target_better, source_only])
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    delete_challenges(&pool, &[source_better, target_better, source_only]).await;
+    test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+    assert_eq!(
+        counts["progress"],
+        MergeTableCount {
+            moved: 2,
+            dropped: 2
+        }
+    );
+    let score_for = |challenge_id: Uuid| {
+        let rows: Vec<_> = scores.iter().filter(|r| r.0 == challenge_id).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1, to.callsign);
+        rows[0].2
+    };
+    assert_eq!(score_for(source_better), 9);
+    assert_eq!(score_for(target_better), 7);
+    assert_eq!(score_for(source_only), 4);
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn dry_run_merge_rolls_back() {
+    let pool = test_support::pool().await;
+    let users = test_support::users(&pool, 3).await;
+    let (from, to, friend) = (&users[0], &users[1], &users[2]);
+    let challenge = test_challenge(&pool).await;
+    set_progress(&pool, challenge, &from.callsign, 3).await;
+    befriend(&pool, from.id, friend.id).await;
+
+    let counts = merge_users(
+        &pool,
+        &admin_user(&pool, from).await,
+        &admin_user(&pool, to).await,
+        true,
+    )
+    .await
+    .unwrap();
+    let progress_owner: String =
+        sqlx::query_scalar("SELECT callsign FROM progress WHERE challenge_id = $1")
+            .bind(challenge)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let friendships: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM friendships WHERE user_id = $1 OR friend_id = $1")
+            .bind(from.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let history: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM callsign_history WHERE user_id = $1")
+            .bind(to.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let source = admin_user(&pool, from).await;
+
+    delete_challenges(&pool, &[challenge]).await;
+    test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+    // The counts preview the merge...
+    assert_eq!(counts["progress"].moved, 1);
+    assert_eq!(counts["friendships"].moved, 2);
+    // ...but nothing changed
+    assert_eq!(progress_owner, from.callsign);
+    assert_eq!(friendships, 2);
+    assert_eq!(history, 0);
+    assert!(source.suspended_at.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs a migrated database in DATABASE_URL"]
+async fn merge_suspends_the_source_and_records_its_callsign() {
+    let pool = test_support::pool().await;
+    let users = test_support::users(&pool, 2).await;
+    let (from, to) = (&users[0], &users[1]);
+
+    merge_users(
+        &pool,
+        &admin_user(&pool, from).await,
+        &admin_user(&pool, to).await,
+        false,
+    )
+    .await
+    .unwrap();
+    let source = admin_user(&pool, from).await;
+    let target = admin_user(&pool, to).await;
+    let history: Vec<(String, String)> = sqlx::query_as(
+        "SELECT old_callsign, new_callsign FROM callsign_history WHERE user_id = $1",
+    )
+    .bind(to.id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+    assert!(source.suspended_at.is_some());
+    assert!(target.suspended_at.is_none());
+    assert_eq!(history, vec![(from.callsign.clone(), to.callsign.clone())]);
+}
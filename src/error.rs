@@ -1,6 +1,9 @@
 // src/error.rs
 use axum::{
-    http::StatusCode,
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
@@ -16,12 +19,18 @@ pub enum AppError {
     #[error("Badge not found")]
     BadgeNotFound { badge_id: Uuid },
 
+    #[error("No certificate for this callsign")]
+    CertificateNotFound { callsign: String },
+
     #[error("Invite not found")]
     InviteNotFound { token: String },
 
     #[error("User not found")]
     UserNotFound { user_id: Uuid },
 
+    #[error("No user with this callsign")]
+    CallsignNotFound { callsign: String },
+
     #[error("Friend invite not found or expired")]
     FriendInviteNotFound { token: String },
 
@@ -34,6 +43,36 @@ pub enum AppError {
     #[error("Program not found")]
     ProgramNotFound { slug: String },
 
+    #[error("Program has no uploaded icon")]
+    ProgramIconNotFound { slug: String },
+
+    #[error("Invalid icon: {message}")]
+    InvalidIcon { message: String },
+
+    #[error("Icon is larger than {max_bytes} bytes")]
+    IconTooLarge { max_bytes: usize },
+
+    #[error("Job not found")]
+    JobNotFound { job_id: Uuid },
+
+    #[error("A backfill is already running")]
+    BackfillRunning { job_id: Uuid },
+
+    #[error("Category not found")]
+    CategoryNotFound { name: String },
+
+    #[error("Category already exists")]
+    CategoryExists { name: String },
+
+    #[error("Category is used by existing challenges")]
+    CategoryInUse { name: String, challenge_count: i64 },
+
+    #[error("Unknown category: {category}")]
+    UnknownCategory {
+        category: String,
+        valid: Vec<String>,
+    },
+
     #[error("Spot not found")]
     SpotNotFound { spot_id: uuid::Uuid },
 
@@ -70,6 +109,9 @@ pub enum AppError {
     #[error("Maximum pending events reached (10)")]
     MaxPendingEvents,
 
+    #[error("Maximum outstanding friend invites reached ({max})")]
+    MaxOutstandingInvites { max: i64 },
+
     #[error("Invalid review action")]
     InvalidEventReview { message: String },
 
@@ -100,12 +142,23 @@ pub enum AppError {
     #[error("Cannot send friend request to yourself")]
     CannotFriendSelf,
 
+    #[error("Friend request was declined recently")]
+    FriendRequestCooldown {
+        retry_after: chrono::DateTime<chrono::Utc>,
+    },
+
+    #[error("One of these users has blocked the other")]
+    UserBlocked,
+
     #[error("Already joined this challenge")]
     AlreadyJoined,
 
     #[error("Not participating in this challenge")]
     NotParticipating,
 
+    #[error("Division can't change after progress has been submitted")]
+    DivisionLocked,
+
     #[error("Invite token required")]
     InviteRequired,
 
@@ -121,17 +174,41 @@ pub enum AppError {
     #[error("Challenge has ended")]
     ChallengeEnded,
 
+    #[error("Challenge results aren't final until it ends")]
+    ChallengeNotEnded,
+
     #[error("Invalid or revoked token")]
     InvalidToken,
 
     #[error("Forbidden")]
     Forbidden,
 
+    #[error("Account is suspended")]
+    AccountSuspended,
+
+    #[error("Feature is disabled")]
+    FeatureDisabled { feature: &'static str },
+
+    #[error("Invalid cursor: {message}")]
+    InvalidCursor { message: String },
+
+    #[error("Cursor is too old; refetch the full list")]
+    CursorExpired,
+
+    #[error("Endpoint not found")]
+    NotFound,
+
+    #[error("Method not allowed")]
+    MethodNotAllowed,
+
     #[error("Not modified")]
     NotModified,
 
-    #[error("Rate limit exceeded")]
-    RateLimited,
+    #[error("Precondition failed; current ETag is {current_etag}")]
+    PreconditionFailed { current_etag: String },
+
+    #[error("Rate limit exceeded; retry in {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
 
     #[error("Validation error: {message}")]
     Validation { message: String },
@@ -179,11 +256,60 @@ impl IntoResponse for AppError {
                 "BADGE_NOT_FOUND",
                 Some(serde_json::json!({ "badgeId": badge_id })),
             ),
+            Self::CertificateNotFound { callsign } => (
+                StatusCode::NOT_FOUND,
+                "CERTIFICATE_NOT_FOUND",
+                Some(serde_json::json!({ "callsign": callsign })),
+            ),
             Self::ProgramNotFound { slug } => (
                 StatusCode::NOT_FOUND,
                 "PROGRAM_NOT_FOUND",
                 Some(serde_json::json!({ "slug": slug })),
             ),
+            Self::ProgramIconNotFound { slug } => (
+                StatusCode::NOT_FOUND,
+                "PROGRAM_ICON_NOT_FOUND",
+                Some(serde_json::json!({ "slug": slug })),
+            ),
+            Self::InvalidIcon { .. } => (StatusCode::BAD_REQUEST, "INVALID_ICON", None),
+            Self::IconTooLarge { max_bytes } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "ICON_TOO_LARGE",
+                Some(serde_json::json!({ "maxBytes": max_bytes })),
+            ),
+            Self::JobNotFound { job_id } => (
+                StatusCode::NOT_FOUND,
+                "JOB_NOT_FOUND",
+                Some(serde_json::json!({ "jobId": job_id })),
+            ),
+            Self::BackfillRunning { job_id } => (
+                StatusCode::CONFLICT,
+                "BACKFILL_RUNNING",
+                Some(serde_json::json!({ "jobId": job_id })),
+            ),
+            Self::CategoryNotFound { name } => (
+                StatusCode::NOT_FOUND,
+                "CATEGORY_NOT_FOUND",
+                Some(serde_json::json!({ "name": name })),
+            ),
+            Self::CategoryExists { name } => (
+                StatusCode::CONFLICT,
+                "CATEGORY_EXISTS",
+                Some(serde_json::json!({ "name": name })),
+            ),
+            Self::CategoryInUse {
+                name,
+                challenge_count,
+            } => (
+                StatusCode::CONFLICT,
+                "CATEGORY_IN_USE",
+                Some(serde_json::json!({ "name": name, "challengeCount": challenge_count })),
+            ),
+            Self::UnknownCategory { category, valid } => (
+                StatusCode::BAD_REQUEST,
+                "UNKNOWN_CATEGORY",
+                Some(serde_json::json!({ "category": category, "validCategories": valid })),
+            ),
             Self::SpotNotFound { spot_id } => (
                 StatusCode::NOT_FOUND,
                 "SPOT_NOT_FOUND",
@@ -241,6 +367,11 @@ impl IntoResponse for AppError {
                 "MAX_PENDING_EVENTS",
                 None,
             ),
+            Self::MaxOutstandingInvites { max } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "MAX_OUTSTANDING_INVITES",
+                Some(serde_json::json!({ "max": max })),
+            ),
             Self::InvalidEventReview { .. } => (
                 StatusCode::BAD_REQUEST,
                 "INVALID_EVENT_REVIEW",
@@ -273,6 +404,11 @@ impl IntoResponse for AppError {
                 "USER_NOT_FOUND",
                 Some(serde_json::json!({ "userId": user_id })),
             ),
+            Self::CallsignNotFound { callsign } => (
+                StatusCode::NOT_FOUND,
+                "USER_NOT_FOUND",
+                Some(serde_json::json!({ "callsign": callsign })),
+            ),
             Self::FriendInviteNotFound { token } => (
                 StatusCode::NOT_FOUND,
                 "FRIEND_INVITE_NOT_FOUND",
@@ -295,20 +431,47 @@ impl IntoResponse for AppError {
             ),
             Self::AlreadyFriends => (StatusCode::CONFLICT, "ALREADY_FRIENDS", None),
             Self::FriendRequestExists => (StatusCode::CONFLICT, "FRIEND_REQUEST_EXISTS", None),
+            Self::FriendRequestCooldown { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "FRIEND_REQUEST_COOLDOWN",
+                Some(serde_json::json!({ "retryAfter": retry_after })),
+            ),
+            Self::UserBlocked => (StatusCode::FORBIDDEN, "USER_BLOCKED", None),
             Self::CannotFriendSelf => {
                 (StatusCode::UNPROCESSABLE_ENTITY, "CANNOT_FRIEND_SELF", None)
             }
             Self::AlreadyJoined => (StatusCode::CONFLICT, "ALREADY_JOINED", None),
             Self::NotParticipating => (StatusCode::FORBIDDEN, "NOT_PARTICIPATING", None),
+            Self::DivisionLocked => (StatusCode::CONFLICT, "DIVISION_LOCKED", None),
             Self::InviteRequired => (StatusCode::FORBIDDEN, "INVITE_REQUIRED", None),
             Self::InviteExpired => (StatusCode::FORBIDDEN, "INVITE_EXPIRED", None),
             Self::InviteExhausted => (StatusCode::FORBIDDEN, "INVITE_EXHAUSTED", None),
             Self::MaxParticipants => (StatusCode::FORBIDDEN, "MAX_PARTICIPANTS", None),
             Self::ChallengeEnded => (StatusCode::BAD_REQUEST, "CHALLENGE_ENDED", None),
+            Self::ChallengeNotEnded => (StatusCode::CONFLICT, "CHALLENGE_NOT_ENDED", None),
             Self::InvalidToken => (StatusCode::UNAUTHORIZED, "INVALID_TOKEN", None),
             Self::Forbidden => (StatusCode::FORBIDDEN, "FORBIDDEN", None),
+            Self::AccountSuspended => (StatusCode::FORBIDDEN, "ACCOUNT_SUSPENDED", None),
+            Self::FeatureDisabled { feature } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "FEATURE_DISABLED",
+                Some(serde_json::json!({ "feature": feature })),
+            ),
+            Self::InvalidCursor { .. } => (StatusCode::BAD_REQUEST, "INVALID_CURSOR", None),
+            Self::CursorExpired => (StatusCode::GONE, "CURSOR_EXPIRED", None),
+            Self::NotFound => (StatusCode::NOT_FOUND, "NOT_FOUND", None),
+            Self::MethodNotAllowed => (StatusCode::METHOD_NOT_ALLOWED, "METHOD_NOT_ALLOWED", None),
             Self::NotModified => unreachable!("handled above"),
-            Self::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED", None),
+            Self::PreconditionFailed { current_etag } => (
+                StatusCode::PRECONDITION_FAILED,
+                "PRECONDITION_FAILED",
+                Some(serde_json::json!({ "currentEtag": current_etag })),
+            ),
+            Self::RateLimited { retry_after_secs } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "RATE_LIMITED",
+                Some(serde_json::json!({ "retryAfterSecs": retry_after_secs })),
+            ),
             Self::Validation { .. } => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", None),
             Self::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", None),
             Self::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", None),
@@ -322,6 +485,83 @@ impl IntoResponse for AppError {
             },
         };
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        response.extensions_mut().insert(ErrorCode(code));
+        if let Self::RateLimited { retry_after_secs } = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
+
+/// Code of an `AppError` response, left in its extensions for `add_doc_links`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode(pub &'static str);
+
+/// Where error codes are documented, for `docUrl` links in error bodies.
+#[derive(Debug, Clone)]
+pub struct ErrorDocs {
+    base_url: String,
+}
+
+impl ErrorDocs {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Docs anchor for an error code: `SELF_SPOT_EXISTS` →
+    /// `<base>/errors#self-spot-exists`.
+    pub fn url(&self, code: &str) -> String {
+        let anchor = code.to_ascii_lowercase().replace('_', "-");
+        format!("{}/errors#{anchor}", self.base_url)
+    }
+}
+
+/// Error bodies are a few hundred bytes; anything larger isn't ours.
+const ERROR_BODY_MAX_BYTES: usize = 64 * 1024;
+
+/// Middleware adding `error.docUrl` to `AppError` responses.
+pub async fn add_doc_links(State(docs): State<ErrorDocs>, req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let Some(ErrorCode(code)) = response.extensions().get::<ErrorCode>().copied() else {
+        return response;
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, ERROR_BODY_MAX_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Could not read {} error body: {}", code, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let mut body: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(body) => body,
+        Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+    };
+    if let Some(error) = body.get_mut("error").and_then(|e| e.as_object_mut()) {
+        error.insert("docUrl".to_string(), docs.url(code).into());
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_responses_link_to_their_code_anchor() {
+        let response = AppError::SelfSpotExists.into_response();
+        let code = response.extensions().get::<ErrorCode>().unwrap();
+        assert_eq!(
+            ErrorDocs::new("https://docs.example/api/").url(code.0),
+            "https://docs.example/api/errors#self-spot-exists"
+        );
     }
 }
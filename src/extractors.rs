@@ -29,6 +29,37 @@ where
     }
 }
 
+/// Query string extractor that returns JSON errors naming the parameter that
+/// failed to parse, instead of axum's plain-text rejection.
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parse_query(parts.uri.query().unwrap_or_default()).map(Query)
+    }
+}
+
+fn parse_query<T: DeserializeOwned>(query: &str) -> Result<T, AppError> {
+    let deserializer =
+        serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+    serde_path_to_error::deserialize(deserializer).map_err(|err| {
+        // The path is "." when the error isn't tied to one parameter, such as
+        // a duplicate, whose message names it anyway
+        let message = match err.path().to_string().as_str() {
+            "." => format!("Invalid query string: {}", err.inner()),
+            param => format!("Invalid query parameter '{param}': {}", err.inner()),
+        };
+        AppError::Validation { message }
+    })
+}
+
 /// JSON extractor and response wrapper that returns JSON errors instead of plain text.
 pub struct Json<T>(pub T);
 
@@ -55,3 +86,72 @@ impl<T: Serialize> IntoResponse for Json<T> {
         axum::Json(self.0).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::spot::SpotSource;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ListQuery {
+        limit: Option<i64>,
+        source: Option<SpotSource>,
+        max_age_minutes: Option<i64>,
+    }
+
+    fn rejection(query: &str) -> String {
+        match parse_query::<ListQuery>(query) {
+            Err(AppError::Validation { message }) => message,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_query_parses() {
+        let query: ListQuery = parse_query("limit=5&source=self&maxAgeMinutes=10").unwrap();
+        assert_eq!(query.limit, Some(5));
+        assert_eq!(query.source, Some(SpotSource::SelfSpot));
+        assert_eq!(query.max_age_minutes, Some(10));
+
+        let empty: ListQuery = parse_query("").unwrap();
+        assert_eq!(empty.limit, None);
+    }
+
+    #[test]
+    fn type_mismatch_names_the_parameter() {
+        let message = rejection("limit=abc");
+        assert!(
+            message.starts_with("Invalid query parameter 'limit':"),
+            "{message}"
+        );
+        assert!(message.contains("invalid digit"), "{message}");
+
+        let message = rejection("maxAgeMinutes=1.5");
+        assert!(
+            message.starts_with("Invalid query parameter 'maxAgeMinutes':"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn unknown_enum_value_lists_the_variants() {
+        let message = rejection("source=bogus");
+        assert_eq!(
+            message,
+            "Invalid query parameter 'source': Unknown spot source 'bogus'; \
+             valid sources: pota, rbn, sota, wspr, self, other"
+        );
+
+        let query: ListQuery = parse_query("source=SELF").unwrap();
+        assert_eq!(query.source, Some(SpotSource::SelfSpot));
+    }
+
+    #[test]
+    fn duplicate_parameter_is_rejected() {
+        assert_eq!(
+            rejection("limit=5&limit=6"),
+            "Invalid query string: duplicate field `limit`"
+        );
+    }
+}
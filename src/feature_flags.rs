@@ -0,0 +1,234 @@
+//! Runtime feature flags backed by the `feature_flags` table.
+//!
+//! Handlers consult an in-memory copy, so a check never touches the database.
+//! The copy is refreshed from the table every [`REFRESH_INTERVAL`] and updated
+//! immediately when an admin toggles a flag on this instance.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::error::AppError;
+
+/// How often each instance re-reads the table to pick up toggles made elsewhere.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Features that can be switched at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// User-facing club routes (`/v1/clubs*`).
+    Clubs,
+    /// Self-spot creation and the POTA/SOTA/WSPR spot aggregators.
+    SpotIngest,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 2] = [Feature::Clubs, Feature::SpotIngest];
+
+    /// Name used in the table, the admin API, and `FEATURE_DISABLED` details.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Clubs => "clubs",
+            Self::SpotIngest => "spot_ingest",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.key() == key)
+    }
+
+    /// Value seeded into the table when the flag has no row yet.
+    pub fn default_enabled(self, config: &Config) -> bool {
+        match self {
+            Self::Clubs => config.clubs_enabled,
+            Self::SpotIngest => config.spots_enabled,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FeatureFlags {
+    inner: Arc<RwLock<HashMap<Feature, bool>>>,
+}
+
+impl FeatureFlags {
+    /// Flags initialized to their config defaults, before the table is read.
+    pub fn from_config(config: &Config) -> Self {
+        let flags = Feature::ALL
+            .into_iter()
+            .map(|f| (f, f.default_enabled(config)))
+            .collect();
+        Self {
+            inner: Arc::new(RwLock::new(flags)),
+        }
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.inner
+            .read()
+            .unwrap()
+            .get(&feature)
+            .copied()
+            .unwrap_or(true)
+    }
+
+    /// `Err(FEATURE_DISABLED)` when the feature is switched off.
+    pub fn require(&self, feature: Feature) -> Result<(), AppError> {
+        if self.is_enabled(feature) {
+            Ok(())
+        } else {
+            Err(AppError::FeatureDisabled {
+                feature: feature.key(),
+            })
+        }
+    }
+
+    /// Overwrite cached values from `(name, enabled)` pairs. Unknown names are ignored.
+    pub fn apply<'a>(&self, values: impl IntoIterator<Item = (&'a str, bool)>) {
+        let mut flags = self.inner.write().unwrap();
+        for (name, enabled) in values {
+            if let Some(feature) = Feature::from_key(name) {
+                flags.insert(feature, enabled);
+            }
+        }
+    }
+
+    /// Seed missing rows from config defaults, then load the table.
+    pub async fn load(&self, pool: &PgPool, config: &Config) -> Result<(), AppError> {
+        let defaults: Vec<(&str, bool)> = Feature::ALL
+            .into_iter()
+            .map(|f| (f.key(), f.default_enabled(config)))
+            .collect();
+        crate::db::seed_feature_flags(pool, &defaults).await?;
+        self.refresh(pool).await
+    }
+
+    pub async fn refresh(&self, pool: &PgPool) -> Result<(), AppError> {
+        let rows = crate::db::list_feature_flags(pool).await?;
+        self.apply(rows.iter().map(|r| (r.name.as_str(), r.enabled)));
+        Ok(())
+    }
+}
+
+/// Re-read the table every [`REFRESH_INTERVAL`].
+pub fn spawn_refresh(flags: FeatureFlags, pool: PgPool) {
+    tokio::spawn(refresh_loop(flags, REFRESH_INTERVAL, move || {
+        let pool = pool.clone();
+        async move {
+            let rows = crate::db::list_feature_flags(&pool).await?;
+            Ok(rows.into_iter().map(|r| (r.name, r.enabled)).collect())
+        }
+    }));
+}
+
+async fn refresh_loop<F, Fut>(flags: FeatureFlags, period: Duration, mut load: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Vec<(String, bool)>, AppError>>,
+{
+    let mut interval = tokio::time::interval(period);
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        match load().await {
+            Ok(values) => flags.apply(values.iter().map(|(name, on)| (name.as_str(), *on))),
+            Err(e) => tracing::warn!("Feature flag refresh failed, keeping cached values: {e}"),
+        }
+    }
+}
+
+/// Middleware rejecting requests with `FEATURE_DISABLED` while a feature is off.
+pub async fn require_feature(
+    State((flags, feature)): State<(FeatureFlags, Feature)>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    flags.require(feature)?;
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn config(pairs: &[(&str, &str)]) -> Config {
+        Config::from_lookup(|var| match var {
+            "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+            "ADMIN_TOKEN" => Some("admin".to_string()),
+            _ => pairs
+                .iter()
+                .find(|(k, _)| *k == var)
+                .map(|(_, v)| v.to_string()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn defaults_follow_config() {
+        let flags = FeatureFlags::from_config(&config(&[("CLUBS_ENABLED", "false")]));
+        assert!(!flags.is_enabled(Feature::Clubs));
+        assert!(flags.is_enabled(Feature::SpotIngest));
+    }
+
+    #[test]
+    fn require_reports_feature_key() {
+        let flags = FeatureFlags::from_config(&config(&[]));
+        flags.apply([("spot_ingest", false), ("unknown_flag", false)]);
+        assert!(flags.require(Feature::Clubs).is_ok());
+        assert!(matches!(
+            flags.require(Feature::SpotIngest),
+            Err(AppError::FeatureDisabled {
+                feature: "spot_ingest"
+            })
+        ));
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        for feature in Feature::ALL {
+            assert_eq!(Feature::from_key(feature.key()), Some(feature));
+        }
+        assert_eq!(Feature::from_key("push"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn toggle_takes_effect_within_refresh_window() {
+        let flags = FeatureFlags::from_config(&config(&[]));
+        let table = Arc::new(Mutex::new(vec![("clubs".to_string(), true)]));
+
+        let source = table.clone();
+        tokio::spawn(refresh_loop(flags.clone(), REFRESH_INTERVAL, move || {
+            let values = source.lock().unwrap().clone();
+            async move { Ok(values) }
+        }));
+        tokio::task::yield_now().await;
+
+        // Another instance flips the row
+        table.lock().unwrap()[0].1 = false;
+        assert!(flags.is_enabled(Feature::Clubs));
+
+        tokio::time::sleep(REFRESH_INTERVAL + Duration::from_millis(1)).await;
+        assert!(!flags.is_enabled(Feature::Clubs));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_refresh_keeps_cached_values() {
+        let flags = FeatureFlags::from_config(&config(&[]));
+        flags.apply([("clubs", false)]);
+        tokio::spawn(refresh_loop(flags.clone(), REFRESH_INTERVAL, || async {
+            Err(AppError::Internal("db down".to_string()))
+        }));
+
+        tokio::time::sleep(REFRESH_INTERVAL * 2).await;
+        assert!(!flags.is_enabled(Feature::Clubs));
+    }
+}
@@ -0,0 +1,86 @@
+//! Maidenhead grid locator resolution.
+
+/// Center of a Maidenhead locator as `(latitude, longitude)`.
+///
+/// Accepts 2 (field), 4 (square), 6 (subsquare), or 8 (extended square)
+/// characters, case-insensitive. Returns `None` for anything malformed.
+pub fn grid_center(grid: &str) -> Option<(f64, f64)> {
+    let chars: Vec<char> = grid.trim().chars().collect();
+    if chars.is_empty() || !chars.len().is_multiple_of(2) || chars.len() > 8 {
+        return None;
+    }
+
+    // (lon, lat) cell size in degrees at each pair, and the valid character range
+    let mut lon = -180.0;
+    let mut lat = -90.0;
+    let mut lon_size = 360.0;
+    let mut lat_size = 180.0;
+
+    for (pair, chunk) in chars.chunks(2).enumerate() {
+        let (divisions, base) = match pair {
+            0 => (18u32, 'A'),
+            1 | 3 => (10, '0'),
+            _ => (24, 'A'),
+        };
+        lon_size /= f64::from(divisions);
+        lat_size /= f64::from(divisions);
+
+        let lon_idx = pair_index(chunk[0], base, divisions)?;
+        let lat_idx = pair_index(chunk[1], base, divisions)?;
+        lon += f64::from(lon_idx) * lon_size;
+        lat += f64::from(lat_idx) * lat_size;
+    }
+
+    Some((lat + lat_size / 2.0, lon + lon_size / 2.0))
+}
+
+fn pair_index(c: char, base: char, divisions: u32) -> Option<u32> {
+    let idx = (c.to_ascii_uppercase() as u32).checked_sub(base as u32)?;
+    (idx < divisions).then_some(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-6 && (actual.1 - expected.1).abs() < 1e-6,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn field_center() {
+        approx(grid_center("FN").unwrap(), (45.0, -70.0));
+    }
+
+    #[test]
+    fn square_center() {
+        approx(grid_center("FN31").unwrap(), (41.5, -73.0));
+    }
+
+    #[test]
+    fn subsquare_center_is_case_insensitive() {
+        let expected = (41.72916666666667, -72.70833333333333);
+        approx(grid_center("FN31pr").unwrap(), expected);
+        approx(grid_center("fn31PR").unwrap(), expected);
+    }
+
+    #[test]
+    fn extended_square_center() {
+        let (lat, lon) = grid_center("JO62qm25").unwrap();
+        assert!((52.5..52.55).contains(&lat));
+        assert!((13.3..13.45).contains(&lon));
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        assert_eq!(grid_center(""), None);
+        assert_eq!(grid_center("F"), None);
+        assert_eq!(grid_center("SZ"), None);
+        assert_eq!(grid_center("FNAA"), None);
+        assert_eq!(grid_center("FN31pz"), None);
+        assert_eq!(grid_center("FN31pr12ab"), None);
+    }
+}
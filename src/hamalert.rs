@@ -0,0 +1,234 @@
+//! HamAlert integration: translating a challenge's `hamalert_config` into
+//! the trigger definitions that would be created in HamAlert.
+//!
+//! Translation is pure, so `GET /v1/challenges/:id/hamalert/preview` can show
+//! organizers the triggers without contacting HamAlert, and challenge
+//! create/update can reject a config that wouldn't translate.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::spot::band_name;
+
+/// Spot sources a HamAlert trigger can watch.
+pub const SPOT_SOURCES: &[&str] = &["cluster", "rbn", "pskreporter", "pota", "sota", "wwff"];
+
+/// A challenge's `hamalertConfig`. Unknown fields are rejected so a typo
+/// doesn't silently widen the triggers.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct HamAlertConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// HamAlert action each trigger fires, e.g. `app`.
+    pub alert_type: String,
+    #[serde(default)]
+    pub spot_sources: Vec<String>,
+    /// Whether the server keeps the triggers in sync as the challenge changes.
+    #[serde(default)]
+    pub auto_manage: bool,
+    /// Bands to alert on; empty for all.
+    #[serde(default)]
+    pub bands: Vec<String>,
+    /// Modes to alert on; empty for all.
+    #[serde(default)]
+    pub modes: Vec<String>,
+}
+
+/// Conditions of one trigger. Empty lists match anything and are omitted.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TriggerConditions {
+    pub source: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub band: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mode: Vec<String>,
+}
+
+/// A HamAlert trigger definition, one per spot source.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Trigger {
+    pub comment: String,
+    pub conditions: TriggerConditions,
+    pub actions: Vec<String>,
+}
+
+/// API response for GET /v1/challenges/:id/hamalert/preview.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerPreview {
+    pub enabled: bool,
+    pub auto_manage: bool,
+    pub triggers: Vec<Trigger>,
+}
+
+/// Parse and validate a stored or submitted `hamalertConfig`.
+pub fn parse_config(raw: &serde_json::Value) -> Result<HamAlertConfig, String> {
+    let config: HamAlertConfig =
+        serde_json::from_value(raw.clone()).map_err(|e| format!("hamalertConfig: {e}"))?;
+    if config.alert_type.trim().is_empty() {
+        return Err("hamalertConfig: alertType must not be empty".to_string());
+    }
+    for source in &config.spot_sources {
+        if !SPOT_SOURCES.contains(&source.trim().to_lowercase().as_str()) {
+            return Err(format!(
+                "hamalertConfig: unknown spot source '{source}', expected one of {}",
+                SPOT_SOURCES.join(", ")
+            ));
+        }
+    }
+    for band in &config.bands {
+        band_name(band).map_err(|e| format!("hamalertConfig: {e}"))?;
+    }
+    if config.modes.iter().any(|mode| mode.trim().is_empty()) {
+        return Err("hamalertConfig: modes must not contain empty names".to_string());
+    }
+    Ok(config)
+}
+
+/// The triggers a config translates to for the named challenge: one per spot
+/// source, with bands in canonical spelling, modes uppercased, and
+/// duplicates dropped.
+pub fn translate(challenge_name: &str, config: &HamAlertConfig) -> Vec<Trigger> {
+    let bands = dedup(config.bands.iter().filter_map(|band| band_name(band).ok()));
+    let modes = dedup(config.modes.iter().map(|mode| mode.trim().to_uppercase()));
+    let sources = dedup(
+        config
+            .spot_sources
+            .iter()
+            .map(|source| source.trim().to_lowercase()),
+    );
+
+    sources
+        .into_iter()
+        .map(|source| Trigger {
+            comment: format!("{challenge_name} ({source})"),
+            conditions: TriggerConditions {
+                source,
+                band: bands.clone(),
+                mode: modes.clone(),
+            },
+            actions: vec![config.alert_type.trim().to_string()],
+        })
+        .collect()
+}
+
+/// The preview for a challenge's config; no config means no triggers.
+pub fn preview(
+    challenge_name: &str,
+    raw: Option<&serde_json::Value>,
+) -> Result<TriggerPreview, String> {
+    let Some(raw) = raw else {
+        return Ok(TriggerPreview {
+            enabled: false,
+            auto_manage: false,
+            triggers: Vec::new(),
+        });
+    };
+    let config = parse_config(raw)?;
+    Ok(TriggerPreview {
+        enabled: config.enabled,
+        auto_manage: config.auto_manage,
+        triggers: translate(challenge_name, &config),
+    })
+}
+
+/// Keep the first occurrence of each item, in order.
+fn dedup<T: Into<String>>(items: impl IntoIterator<Item = T>) -> Vec<String> {
+    let mut seen: Vec<String> = Vec::new();
+    for item in items {
+        let item = item.into();
+        if !seen.contains(&item) {
+            seen.push(item);
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn configs_translate_to_one_trigger_per_source() {
+        let trigger = |source: &str, band: &[&str], mode: &[&str], action: &str| Trigger {
+            comment: format!("Parks on Air ({source})"),
+            conditions: TriggerConditions {
+                source: source.to_string(),
+                band: band.iter().map(|b| b.to_string()).collect(),
+                mode: mode.iter().map(|m| m.to_string()).collect(),
+            },
+            actions: vec![action.to_string()],
+        };
+
+        for (config, expected) in [
+            (
+                json!({ "alertType": "app", "spotSources": ["pota"] }),
+                vec![trigger("pota", &[], &[], "app")],
+            ),
+            (
+                json!({
+                    "alertType": "app",
+                    "spotSources": ["pota", "RBN"],
+                    "bands": ["20M", "40m", "20m"],
+                    "modes": ["cw", " ssb "]
+                }),
+                vec![
+                    trigger("pota", &["20m", "40m"], &["CW", "SSB"], "app"),
+                    trigger("rbn", &["20m", "40m"], &["CW", "SSB"], "app"),
+                ],
+            ),
+            (
+                json!({ "alertType": "url", "spotSources": ["sota", "sota"], "enabled": true }),
+                vec![trigger("sota", &[], &[], "url")],
+            ),
+            (json!({ "alertType": "app" }), vec![]),
+        ] {
+            let parsed = parse_config(&config).unwrap();
+            assert_eq!(translate("Parks on Air", &parsed), expected, "{config}");
+        }
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected_with_the_reason() {
+        for (config, reason) in [
+            (
+                json!({ "alertType": "app", "bandz": ["20m"] }),
+                "unknown field `bandz`",
+            ),
+            (
+                json!({ "alertType": "app", "bands": ["20m", "11m"] }),
+                "unknown band '11m'",
+            ),
+            (
+                json!({ "alertType": "app", "spotSources": ["qrz"] }),
+                "unknown spot source 'qrz'",
+            ),
+            (json!({ "alertType": " " }), "alertType must not be empty"),
+            (
+                json!({ "spotSources": ["pota"] }),
+                "missing field `alertType`",
+            ),
+            (
+                json!({ "alertType": "app", "modes": ["CW", ""] }),
+                "empty names",
+            ),
+            (json!(["pota"]), "hamalertConfig"),
+        ] {
+            let err = parse_config(&config).unwrap_err();
+            assert!(err.starts_with("hamalertConfig: "), "{err}");
+            assert!(err.contains(reason), "{config}: {err}");
+        }
+    }
+
+    #[test]
+    fn missing_config_previews_no_triggers() {
+        let preview = preview("Parks on Air", None).unwrap();
+        assert!(!preview.enabled);
+        assert!(preview.triggers.is_empty());
+
+        let config = json!({ "enabled": true, "autoManage": true, "alertType": "app" });
+        let preview = super::preview("Parks on Air", Some(&config)).unwrap();
+        assert!(preview.enabled && preview.auto_manage);
+    }
+}
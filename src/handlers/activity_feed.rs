@@ -1,27 +1,66 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, State},
     http::StatusCode,
 };
 
-use crate::extractors::Json;
+use crate::extractors::{Json, Query};
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
 use crate::auth::AuthContext;
+use crate::config::Config;
+use crate::cursor::{self, Cursor};
 use crate::db;
 use crate::error::AppError;
-use crate::models::activity::{ActivityResponse, FeedItemResponse, ReportActivityRequest};
+use crate::models::activity::{
+    link_qso_context, ActivityResponse, FeedItemResponse, FeedItemRow, FeedOrder,
+    ReportActivityRequest, SERVER_GENERATED_ACTIVITY_TYPES,
+};
 
 use super::DataResponse;
 
 /// POST /v1/activities
-/// Report a notable activity.
+/// Report a notable activity. A `qso` activity may link the spot it was
+/// worked from (`spotId`, which must exist) and the reference worked; both
+/// are added to its details. A user who has already added
+/// `MAX_ACTIVITIES_PER_HOUR` in the last hour is rate limited until the
+/// oldest of them is an hour old.
 pub async fn report_activity(
     State(pool): State<PgPool>,
     Extension(auth): Extension<AuthContext>,
-    Json(body): Json<ReportActivityRequest>,
+    Extension(config): Extension<Config>,
+    Json(mut body): Json<ReportActivityRequest>,
 ) -> Result<(StatusCode, Json<DataResponse<ActivityResponse>>), AppError> {
+    let spot = match body.spot_id {
+        Some(spot_id) => Some(
+            db::get_spot(&pool, spot_id)
+                .await?
+                .ok_or(AppError::SpotNotFound { spot_id })?,
+        ),
+        None => None,
+    };
+    link_qso_context(
+        &body.activity_type,
+        &mut body.details,
+        spot.as_ref(),
+        body.reference.as_deref(),
+    )
+    .map_err(|message| AppError::Validation { message })?;
+
     let user = db::get_or_create_user(&pool, &auth.callsign).await?;
 
+    if config.max_activities_per_hour > 0 {
+        let (count, oldest) =
+            db::count_recent_activities(&pool, user.id, SERVER_GENERATED_ACTIVITY_TYPES).await?;
+        if count >= config.max_activities_per_hour {
+            let frees_up_at = oldest.unwrap_or_else(Utc::now) + chrono::Duration::hours(1);
+            tracing::warn!(user_id = %user.id, count, "activity rate limit reached");
+            return Err(AppError::RateLimited {
+                retry_after_secs: (frees_up_at - Utc::now()).num_seconds().max(1) as u64,
+            });
+        }
+    }
+
     let activity = db::insert_activity(
         &pool,
         user.id,
@@ -48,12 +87,49 @@ pub async fn delete_activity(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[derive(serde::Deserialize)]
+pub struct DeleteUserActivitiesQuery {
+    pub after: DateTime<Utc>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteUserActivitiesResponse {
+    pub deleted: u64,
+}
+
+/// DELETE /v1/admin/users/:id/activities?after= — remove every activity a
+/// user added after a time, e.g. a broken client's flood (admin only)
+pub async fn admin_delete_user_activities(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<uuid::Uuid>,
+    Query(params): Query<DeleteUserActivitiesQuery>,
+) -> Result<Json<DataResponse<DeleteUserActivitiesResponse>>, AppError> {
+    db::get_admin_user(&pool, user_id)
+        .await?
+        .ok_or(AppError::UserNotFound { user_id })?;
+
+    let deleted = db::delete_user_activities_after(&pool, user_id, params.after).await?;
+    tracing::info!(%user_id, after = %params.after, deleted, "user activities deleted");
+
+    Ok(Json(DataResponse {
+        data: DeleteUserActivitiesResponse { deleted },
+    }))
+}
+
 #[allow(dead_code)]
 #[derive(serde::Deserialize)]
 pub struct FeedQuery {
     pub limit: Option<i64>,
     pub filter: Option<String>,
     pub before: Option<String>,
+    /// Narrow the feed to this friend's activities.
+    pub callsign: Option<String>,
+    /// `created` (default) or `event_time`.
+    pub order_by: Option<String>,
+    /// Days back the first page reaches, overriding
+    /// `FEED_DEFAULT_LOOKBACK_DAYS`; 0 for no limit.
+    pub lookback_days: Option<i32>,
 }
 
 #[derive(serde::Serialize)]
@@ -71,44 +147,106 @@ pub struct FeedPagination {
 }
 
 /// GET /v1/feed
-/// Get activity feed from friends, with cursor-based pagination.
+/// Get activity feed from friends, with cursor-based pagination. With
+/// `callsign`, only that friend's activities; empty for anyone else. The
+/// first page only reaches back `FEED_DEFAULT_LOOKBACK_DAYS` unless
+/// `lookback_days` says otherwise; pages after a cursor aren't limited.
 pub async fn get_feed(
     State(pool): State<PgPool>,
     Extension(auth): Extension<AuthContext>,
+    Extension(config): Extension<Config>,
     Query(params): Query<FeedQuery>,
 ) -> Result<Json<DataResponse<FeedResponse>>, AppError> {
     let user = db::get_or_create_user(&pool, &auth.callsign).await?;
 
     let limit = params.limit.unwrap_or(50).clamp(1, 100);
 
-    // Parse cursor (ISO 8601 timestamp)
-    let before = params.before.as_deref().and_then(|s| {
-        chrono::DateTime::parse_from_rfc3339(s)
-            .ok()
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-    });
+    let before = cursor::timestamp_param(params.before.as_deref())?;
+    let order = FeedOrder::parse(params.order_by.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
+    let lookback_days = params
+        .lookback_days
+        .unwrap_or(config.feed_default_lookback_days);
+    if lookback_days < 0 {
+        return Err(AppError::Validation {
+            message: "lookback_days must not be negative".to_string(),
+        });
+    }
+    let lookback_days = (before.is_none() && lookback_days > 0).then_some(lookback_days);
+
+    // Fetch one extra to determine hasMore
+    let rows = match params.callsign.as_deref() {
+        None => {
+            db::get_feed_for_user(&pool, user.id, order, limit + 1, before, lookback_days).await?
+        }
+        Some(callsign) => match db::get_user_by_callsign(&pool, &callsign.to_uppercase()).await? {
+            Some(friend) => {
+                db::get_feed_for_friend(
+                    &pool,
+                    user.id,
+                    friend.id,
+                    order,
+                    limit + 1,
+                    before,
+                    lookback_days,
+                )
+                .await?
+            }
+            None => Vec::new(),
+        },
+    };
+
+    Ok(Json(DataResponse {
+        data: feed_page(rows, order, limit),
+    }))
+}
+
+/// GET /v1/users/:userId/activities
+/// A friend's (or the caller's own) activities, paginated like the feed.
+/// Anyone else is reported as not found.
+pub async fn get_user_activities(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthContext>,
+    Path(user_id): Path<uuid::Uuid>,
+    Query(params): Query<FeedQuery>,
+) -> Result<Json<DataResponse<FeedResponse>>, AppError> {
+    let viewer = db::get_or_create_user(&pool, &auth.callsign).await?;
+    if user_id != viewer.id && !db::are_friends(&pool, viewer.id, user_id).await? {
+        return Err(AppError::UserNotFound { user_id });
+    }
+
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+
+    let before = cursor::timestamp_param(params.before.as_deref())?;
+    let order = FeedOrder::parse(params.order_by.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
 
     // Fetch one extra to determine hasMore
-    let rows = db::get_feed_for_user(&pool, user.id, limit + 1, before).await?;
+    let rows = db::get_activities_for_user(&pool, user_id, order, limit + 1, before).await?;
+
+    Ok(Json(DataResponse {
+        data: feed_page(rows, order, limit),
+    }))
+}
 
+/// Build a feed response from up to `limit + 1` rows in `order`.
+fn feed_page(rows: Vec<FeedItemRow>, order: FeedOrder, limit: i64) -> FeedResponse {
     let has_more = rows.len() as i64 > limit;
     let truncated: Vec<_> = rows.into_iter().take(limit as usize).collect();
 
     let next_cursor = if has_more {
-        truncated.last().map(|row| row.created_at.to_rfc3339())
+        truncated.last().map(|row| Cursor(order.key(row)).encode())
     } else {
         None
     };
 
     let items: Vec<FeedItemResponse> = truncated.into_iter().map(Into::into).collect();
 
-    Ok(Json(DataResponse {
-        data: FeedResponse {
-            items,
-            pagination: FeedPagination {
-                has_more,
-                next_cursor,
-            },
+    FeedResponse {
+        items,
+        pagination: FeedPagination {
+            has_more,
+            next_cursor,
         },
-    }))
+    }
 }
@@ -0,0 +1,16 @@
+use axum::extract::Extension;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::extractors::Json;
+
+use super::DataResponse;
+
+/// GET /v1/admin/config — effective configuration with secrets redacted (admin only)
+pub async fn get_admin_config(
+    Extension(config): Extension<Config>,
+) -> Result<Json<DataResponse<Config>>, AppError> {
+    Ok(Json(DataResponse {
+        data: config.redacted_view(),
+    }))
+}
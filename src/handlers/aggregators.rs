@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use axum::extract::{Extension, State};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::aggregators::{pota, start_reported, SpotAggregator};
+use crate::config::Config;
+use crate::error::AppError;
+use crate::extractors::{Json, Path, Query};
+use crate::feature_flags::{Feature, FeatureFlags};
+use crate::http_client::HttpClient;
+use crate::jobs::Job;
+use crate::server::ServerState;
+
+use super::DataResponse;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManualPollResponse {
+    pub source: &'static str,
+    pub upserted: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawFetchResponse {
+    pub source: &'static str,
+    pub url: String,
+    pub total: usize,
+    pub mapped: usize,
+    pub body: serde_json::Value,
+}
+
+/// POST /v1/admin/aggregators/:source/poll — run one spot poll now (admin
+/// only). Each source can be polled once per `MANUAL_POLL_COOLDOWN_SECS` so
+/// the endpoint can't be used to hammer upstreams. An aggregator that failed
+/// to start is started again first.
+pub async fn poll_aggregator(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
+    Extension(flags): Extension<FeatureFlags>,
+    Extension(state): Extension<ServerState>,
+    Extension(client): Extension<HttpClient>,
+    Path(source): Path<String>,
+) -> Result<Json<DataResponse<ManualPollResponse>>, AppError> {
+    let aggregator = enabled_aggregator(&source, &config)?;
+    flags.require(Feature::SpotIngest)?;
+
+    let cooldown = Duration::from_secs(config.manual_poll_cooldown_secs);
+    state
+        .claim_manual_poll(aggregator.key(), cooldown)
+        .map_err(rate_limited)?;
+
+    tracing::info!(
+        source = aggregator.key(),
+        "Manual aggregator poll requested"
+    );
+    if state
+        .aggregator_start(aggregator.key())
+        .is_some_and(|report| report.is_failed())
+    {
+        let report = start_reported(aggregator.key(), || {
+            aggregator.start(&pool, &config, &flags, &state)
+        });
+        report.log();
+        let reason = report.reason.clone();
+        state.record_aggregator_start(report);
+        if let Some(reason) = reason {
+            return Err(AppError::Internal(format!(
+                "{} aggregator failed to start: {}",
+                aggregator.key(),
+                reason
+            )));
+        }
+    }
+    let result = aggregator.poll_once(&pool, &client, &config).await;
+    state.record_fetch(aggregator.key(), result.is_ok());
+    let upserted = result.map_err(|e| {
+        tracing::error!("Manual {} poll failed: {}", aggregator.key(), e);
+        AppError::Internal(e.to_string())
+    })?;
+
+    Ok(Json(DataResponse {
+        data: ManualPollResponse {
+            source: aggregator.key(),
+            upserted,
+        },
+    }))
+}
+
+/// GET /v1/admin/aggregators/:source/raw — fetch the upstream response now
+/// and return it pretty-printed with how many entries would map to spots
+/// (admin only). Nothing is stored, but the fetch shares the manual poll
+/// cooldown since it hits the same upstream.
+pub async fn fetch_aggregator_raw(
+    Extension(config): Extension<Config>,
+    Extension(state): Extension<ServerState>,
+    Extension(client): Extension<HttpClient>,
+    Path(source): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let aggregator = enabled_aggregator(&source, &config)?;
+
+    let cooldown = Duration::from_secs(config.manual_poll_cooldown_secs);
+    state
+        .claim_manual_poll(aggregator.key(), cooldown)
+        .map_err(rate_limited)?;
+
+    tracing::info!(source = aggregator.key(), "Raw aggregator fetch requested");
+    let fetch = aggregator.fetch_raw(&client, &config).await.map_err(|e| {
+        tracing::error!("Raw {} fetch failed: {}", aggregator.key(), e);
+        AppError::Internal(e.to_string())
+    })?;
+
+    let response = DataResponse {
+        data: RawFetchResponse {
+            source: aggregator.key(),
+            url: fetch.url,
+            total: fetch.total,
+            mapped: fetch.mapped,
+            body: fetch.body,
+        },
+    };
+    let body =
+        serde_json::to_string_pretty(&response).map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], body))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BackfillQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// POST /v1/admin/aggregators/pota/backfill — fetch the POTA spots of a
+/// missed window of up to 24 hours from `POTA_BACKFILL_URL` in the background
+/// (admin only). Returns the job, whose progress is at `GET /v1/admin/jobs/:id`.
+/// One backfill runs at a time.
+pub async fn backfill_pota(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
+    Extension(flags): Extension<FeatureFlags>,
+    Extension(state): Extension<ServerState>,
+    Extension(client): Extension<HttpClient>,
+    Query(params): Query<BackfillQuery>,
+) -> Result<(StatusCode, Json<DataResponse<Job>>), AppError> {
+    let aggregator = enabled_aggregator("pota", &config)?;
+    flags.require(Feature::SpotIngest)?;
+    let Some(url) = config.pota_backfill_url.clone() else {
+        return Err(AppError::Validation {
+            message: "POTA backfill is not configured; set POTA_BACKFILL_URL".to_string(),
+        });
+    };
+    let (from, to) = (params.from, params.to);
+    if from >= to {
+        return Err(AppError::Validation {
+            message: "from must be before to".to_string(),
+        });
+    }
+    if to - from > pota::BACKFILL_MAX_WINDOW {
+        return Err(AppError::Validation {
+            message: format!(
+                "Backfill window must be at most {} hours",
+                pota::BACKFILL_MAX_WINDOW.num_hours()
+            ),
+        });
+    }
+    if to > Utc::now() {
+        return Err(AppError::Validation {
+            message: "to must not be in the future".to_string(),
+        });
+    }
+
+    let job_id = state
+        .jobs()
+        .start("potaBackfill", from, to)
+        .map_err(|job_id| AppError::BackfillRunning { job_id })?;
+    tracing::info!(source = aggregator.key(), %job_id, %from, %to, "Backfill started");
+
+    let job = state
+        .jobs()
+        .get(job_id)
+        .ok_or(AppError::JobNotFound { job_id })?;
+    tokio::spawn(async move {
+        let result = pota::backfill(&pool, &client, &config, &url, from..to, &state, job_id).await;
+        if let Err(e) = &result {
+            tracing::error!(%job_id, "POTA backfill failed: {}", e);
+        }
+        state
+            .jobs()
+            .finish(job_id, result.err().map(|e| e.to_string()));
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(DataResponse { data: job })))
+}
+
+/// The aggregator for `source`, if it exists and is enabled.
+fn enabled_aggregator(source: &str, config: &Config) -> Result<SpotAggregator, AppError> {
+    let aggregator = SpotAggregator::from_key(source).ok_or_else(|| AppError::Validation {
+        message: format!("Unknown aggregator source '{source}'; expected pota, sota, or wspr"),
+    })?;
+    if !config.spots_enabled || !aggregator.is_enabled(config) {
+        return Err(AppError::Validation {
+            message: format!("The {} aggregator is not enabled", aggregator.key()),
+        });
+    }
+    Ok(aggregator)
+}
+
+fn rate_limited(remaining: Duration) -> AppError {
+    AppError::RateLimited {
+        retry_after_secs: remaining.as_secs_f64().ceil() as u64,
+    }
+}
@@ -2,6 +2,7 @@ use axum::{
     body::Body,
     extract::State,
     http::{header, Response, StatusCode},
+    Extension,
 };
 
 use crate::extractors::{Json, Path};
@@ -10,6 +11,7 @@ use serde::Serialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::config::Config;
 use crate::db;
 use crate::error::AppError;
 use crate::models::BadgeResponse;
@@ -27,6 +29,7 @@ pub struct BadgeListResponse {
 
 pub async fn upload_badge(
     State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
     Path(challenge_id): Path<Uuid>,
     mut multipart: Multipart,
 ) -> Result<(StatusCode, Json<DataResponse<BadgeResponse>>), AppError> {
@@ -117,8 +120,7 @@ pub async fn upload_badge(
     )
     .await?;
 
-    // Use empty base URL for now - will be configured via env var
-    let base_url = "";
+    let base_url = &config.route_prefix;
 
     Ok((
         StatusCode::CREATED,
@@ -130,6 +132,7 @@ pub async fn upload_badge(
 
 pub async fn list_badges(
     State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
     Path(challenge_id): Path<Uuid>,
 ) -> Result<Json<DataResponse<BadgeListResponse>>, AppError> {
     // Verify challenge exists
@@ -138,7 +141,7 @@ pub async fn list_badges(
         .ok_or(AppError::ChallengeNotFound { challenge_id })?;
 
     let badges = db::list_badges(&pool, challenge_id).await?;
-    let base_url = "";
+    let base_url = &config.route_prefix;
 
     Ok(Json(DataResponse {
         data: BadgeListResponse {
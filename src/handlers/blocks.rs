@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::blocks::BlockCache;
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::Path;
+
+/// PUT /v1/users/:id/block
+/// Block a user, ending any friendship and pending requests with them
+pub async fn block_user(
+    State(pool): State<PgPool>,
+    Extension(blocks): Extension<BlockCache>,
+    Extension(auth): Extension<AuthContext>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let user = db::get_or_create_user(&pool, &auth.callsign).await?;
+    if user.id == user_id {
+        return Err(AppError::Validation {
+            message: "Cannot block yourself".to_string(),
+        });
+    }
+    db::get_user_by_id(&pool, user_id)
+        .await?
+        .ok_or(AppError::UserNotFound { user_id })?;
+
+    db::block_user(&pool, user.id, user_id).await?;
+    blocks.invalidate(user.id, user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /v1/users/:id/block
+/// Unblock a user. Friendships and requests removed by the block stay removed.
+pub async fn unblock_user(
+    State(pool): State<PgPool>,
+    Extension(blocks): Extension<BlockCache>,
+    Extension(auth): Extension<AuthContext>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let user = db::get_or_create_user(&pool, &auth.callsign).await?;
+
+    if db::unblock_user(&pool, user.id, user_id).await? {
+        blocks.invalidate(user.id, user_id);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,104 @@
+use axum::Extension;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::extractors::Json;
+use crate::feature_flags::{Feature, FeatureFlags};
+use crate::models::activity::SERVER_GENERATED_ACTIVITY_TYPES;
+use crate::models::spot::SpotSource;
+
+use super::spots::{
+    HEATMAP_MAX_DAYS, LOOKUP_MAX_IDS, MAX_SPOT_WINDOW_HOURS, SPOTS_MAX_AGE_MINUTES,
+    SPOTS_MAX_LIMIT, SPOT_LIST_FILTERS, STATUS_MAX_CALLSIGNS,
+};
+use super::DataResponse;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilitiesResponse {
+    pub version: &'static str,
+    pub spots: SpotCapabilities,
+    pub activities: ActivityCapabilities,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotCapabilities {
+    pub enabled: bool,
+    /// Sources new spots currently arrive from.
+    pub sources: Vec<&'static str>,
+    pub filters: &'static [&'static str],
+    pub limits: SpotLimits,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotLimits {
+    pub max_page_size: i64,
+    pub max_age_minutes: i64,
+    pub max_window_hours: i64,
+    pub max_lookup_ids: usize,
+    pub max_status_callsigns: usize,
+    pub max_heatmap_days: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityCapabilities {
+    /// Types the server records itself; clients may report any other type.
+    pub server_generated_types: &'static [&'static str],
+    /// 0 when unlimited.
+    pub max_per_hour: i64,
+}
+
+/// The spot sources producing new spots: each enabled aggregator, the RBN
+/// proxy, and self-spots and partner pushes while spot ingest is on. None
+/// when the spots system is off.
+fn enabled_sources(config: &Config, flags: &FeatureFlags) -> Vec<&'static str> {
+    if !config.spots_enabled {
+        return Vec::new();
+    }
+    let ingest = flags.is_enabled(Feature::SpotIngest);
+    SpotSource::ALL
+        .into_iter()
+        .filter(|source| match source {
+            SpotSource::Pota => ingest && config.pota_aggregator_enabled,
+            SpotSource::Sota => ingest && config.sota_aggregator_enabled,
+            SpotSource::Wspr => ingest && config.wspr_aggregator_enabled,
+            SpotSource::Rbn => config.rbn_proxy_enabled,
+            SpotSource::SelfSpot | SpotSource::Other => ingest,
+        })
+        .map(|source| source.as_str())
+        .collect()
+}
+
+/// GET /v1/capabilities — machine-readable manifest of what this server
+/// supports: spot sources currently enabled, spot list filters and limits,
+/// and activity types. Unauthenticated; reflects runtime feature flags.
+pub async fn get_capabilities(
+    Extension(config): Extension<Config>,
+    Extension(flags): Extension<FeatureFlags>,
+) -> Json<DataResponse<CapabilitiesResponse>> {
+    Json(DataResponse {
+        data: CapabilitiesResponse {
+            version: env!("CARGO_PKG_VERSION"),
+            spots: SpotCapabilities {
+                enabled: config.spots_enabled,
+                sources: enabled_sources(&config, &flags),
+                filters: SPOT_LIST_FILTERS,
+                limits: SpotLimits {
+                    max_page_size: SPOTS_MAX_LIMIT,
+                    max_age_minutes: SPOTS_MAX_AGE_MINUTES,
+                    max_window_hours: MAX_SPOT_WINDOW_HOURS,
+                    max_lookup_ids: LOOKUP_MAX_IDS,
+                    max_status_callsigns: STATUS_MAX_CALLSIGNS,
+                    max_heatmap_days: HEATMAP_MAX_DAYS,
+                },
+            },
+            activities: ActivityCapabilities {
+                server_generated_types: SERVER_GENERATED_ACTIVITY_TYPES,
+                max_per_hour: config.max_activities_per_hour,
+            },
+        },
+    })
+}
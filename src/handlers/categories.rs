@@ -0,0 +1,172 @@
+use axum::{extract::State, http::StatusCode};
+use sqlx::PgPool;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::{Json, Path};
+use crate::models::challenge_category::{
+    normalize_category_name, CategoryResponse, CreateCategoryRequest, MergeCategoriesRequest,
+    MergeCategoriesResponse, UpdateCategoryRequest,
+};
+
+use super::DataResponse;
+
+/// Resolve a challenge's category to its canonical spelling, case-insensitively.
+/// Unknown categories fail with the list of valid ones.
+pub(crate) async fn resolve_category(pool: &PgPool, raw: &str) -> Result<String, AppError> {
+    if let Some(canonical) = db::find_canonical_category(pool, raw).await? {
+        return Ok(canonical);
+    }
+    Err(AppError::UnknownCategory {
+        category: raw.to_string(),
+        valid: db::list_category_names(pool).await?,
+    })
+}
+
+/// GET /v1/categories (also GET /v1/challenge-categories) — categories with
+/// active challenge counts, for the client picker.
+pub async fn list_categories(
+    State(pool): State<PgPool>,
+) -> Result<Json<DataResponse<Vec<CategoryResponse>>>, AppError> {
+    let rows = db::list_categories(&pool, true).await?;
+
+    Ok(Json(DataResponse {
+        data: rows.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// GET /v1/admin/categories — categories with counts of all challenges (admin only)
+pub async fn admin_list_categories(
+    State(pool): State<PgPool>,
+) -> Result<Json<DataResponse<Vec<CategoryResponse>>>, AppError> {
+    let rows = db::list_categories(&pool, false).await?;
+
+    Ok(Json(DataResponse {
+        data: rows.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// POST /v1/admin/categories — create a category (admin only)
+pub async fn create_category(
+    State(pool): State<PgPool>,
+    Json(req): Json<CreateCategoryRequest>,
+) -> Result<(StatusCode, Json<DataResponse<CategoryResponse>>), AppError> {
+    let name =
+        normalize_category_name(&req.name).map_err(|message| AppError::Validation { message })?;
+    if let Some(existing) = db::find_canonical_category(&pool, &name).await? {
+        return Err(AppError::CategoryExists { name: existing });
+    }
+
+    let row = db::create_category(&pool, &name, req.description.as_deref(), req.sort_order).await?;
+
+    Ok((StatusCode::CREATED, Json(DataResponse { data: row.into() })))
+}
+
+/// PUT /v1/admin/categories/:name — update or rename a category (admin only).
+/// A rename carries over to every challenge in the category.
+pub async fn update_category(
+    State(pool): State<PgPool>,
+    Path(name): Path<String>,
+    Json(mut req): Json<UpdateCategoryRequest>,
+) -> Result<Json<DataResponse<CategoryResponse>>, AppError> {
+    let current = db::find_canonical_category(&pool, &name)
+        .await?
+        .ok_or(AppError::CategoryNotFound { name })?;
+
+    if let Some(new_name) = &req.name {
+        let new_name = normalize_category_name(new_name)
+            .map_err(|message| AppError::Validation { message })?;
+        // Changing only the case of the current name is allowed
+        if let Some(existing) = db::find_canonical_category(&pool, &new_name).await? {
+            if existing != current {
+                return Err(AppError::CategoryExists { name: existing });
+            }
+        }
+        req.name = Some(new_name);
+    }
+
+    let row = db::update_category(&pool, &current, &req)
+        .await?
+        .ok_or(AppError::CategoryNotFound { name: current })?;
+
+    Ok(Json(DataResponse { data: row.into() }))
+}
+
+/// DELETE /v1/admin/categories/:name — delete an unused category (admin only)
+pub async fn delete_category(
+    State(pool): State<PgPool>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let category = db::get_category(&pool, &name)
+        .await?
+        .ok_or(AppError::CategoryNotFound { name })?;
+
+    if category.challenge_count > 0 {
+        return Err(AppError::CategoryInUse {
+            name: category.name,
+            challenge_count: category.challenge_count,
+        });
+    }
+
+    if db::delete_category(&pool, &category.name).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::CategoryNotFound {
+            name: category.name,
+        })
+    }
+}
+
+/// POST /v1/admin/categories/merge — move every challenge from one category
+/// into another and delete the first, in one transaction (admin only)
+pub async fn merge_categories(
+    State(pool): State<PgPool>,
+    Json(req): Json<MergeCategoriesRequest>,
+) -> Result<Json<DataResponse<MergeCategoriesResponse>>, AppError> {
+    let from = db::find_canonical_category(&pool, &req.from)
+        .await?
+        .ok_or(AppError::CategoryNotFound { name: req.from })?;
+    let into = db::find_canonical_category(&pool, &req.into)
+        .await?
+        .ok_or(AppError::CategoryNotFound { name: req.into })?;
+    if from == into {
+        return Err(AppError::Validation {
+            message: "cannot merge a category into itself".to_string(),
+        });
+    }
+
+    let challenges_updated = db::merge_categories(&pool, &from, &into).await?;
+    tracing::info!(%from, %into, challenges_updated, "Merged challenge categories");
+
+    Ok(Json(DataResponse {
+        data: MergeCategoriesResponse {
+            from,
+            into,
+            challenges_updated,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn challenge_category_must_exist() {
+        let pool = test_support::pool().await;
+
+        let canonical = resolve_category(&pool, " AWARD ").await.unwrap();
+        let unknown = resolve_category(&pool, "POTA-ish").await;
+
+        assert_eq!(canonical, "award");
+        match unknown {
+            Err(AppError::UnknownCategory { category, valid }) => {
+                assert_eq!(category, "POTA-ish");
+                assert!(valid.contains(&"award".to_string()), "{valid:?}");
+            }
+            other => panic!("expected UnknownCategory, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use axum::extract::{Extension, State};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::{Json, Path};
+use crate::models::certificate::{
+    CertificateResponse, CertificatesResponse, ChallengeResultRow, FinalStandingRow,
+};
+use crate::models::me::{ChallengeSchedule, ChallengeState};
+use crate::models::Challenge;
+
+use super::progress::percentage_complete;
+use super::DataResponse;
+
+/// GET /v1/challenges/:id/certificates — certificate data for every finisher
+/// of an ended challenge, best rank first (admin only).
+pub async fn list_certificates(
+    State(pool): State<PgPool>,
+    Path(challenge_id): Path<Uuid>,
+) -> Result<Json<DataResponse<CertificatesResponse>>, AppError> {
+    let (challenge, results) = final_results(&pool, challenge_id).await?;
+
+    Ok(Json(DataResponse {
+        data: CertificatesResponse {
+            challenge_id,
+            challenge_name: challenge.name,
+            finalized_at: results.first().map(|r| r.finalized_at),
+            certificates: results
+                .into_iter()
+                .filter(|r| r.completed)
+                .map(|r| CertificateResponse::from_result(challenge_id, r))
+                .collect(),
+        },
+    }))
+}
+
+/// GET /v1/challenges/:id/certificates/:callsign — the caller's own
+/// certificate for an ended challenge (auth required).
+pub async fn get_certificate(
+    State(pool): State<PgPool>,
+    Path((challenge_id, callsign)): Path<(Uuid, String)>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<DataResponse<CertificateResponse>>, AppError> {
+    if !auth.callsign.eq_ignore_ascii_case(&callsign) {
+        return Err(AppError::Forbidden);
+    }
+
+    let (_, results) = final_results(&pool, challenge_id).await?;
+    let result = results
+        .into_iter()
+        .find(|r| r.completed && r.callsign.eq_ignore_ascii_case(&callsign))
+        .ok_or(AppError::CertificateNotFound {
+            callsign: callsign.to_uppercase(),
+        })?;
+
+    Ok(Json(DataResponse {
+        data: CertificateResponse::from_result(challenge_id, result),
+    }))
+}
+
+/// An ended challenge and its final results. The first request after it ends
+/// stores the current standings; later ones read them back, so ranks and
+/// serials don't move.
+async fn final_results(
+    pool: &PgPool,
+    challenge_id: Uuid,
+) -> Result<(Challenge, Vec<ChallengeResultRow>), AppError> {
+    let challenge = db::get_challenge(pool, challenge_id)
+        .await?
+        .ok_or(AppError::ChallengeNotFound { challenge_id })?;
+    let schedule = ChallengeSchedule::from_configuration(
+        &challenge.configuration,
+        challenge.is_active,
+        Utc::now(),
+    );
+    if schedule.status != ChallengeState::Ended {
+        return Err(AppError::ChallengeNotEnded);
+    }
+
+    let results = db::get_challenge_results(pool, challenge_id).await?;
+    if !results.is_empty() {
+        return Ok((challenge, results));
+    }
+
+    let standings: Vec<_> = db::get_final_standings(pool, challenge_id)
+        .await?
+        .into_iter()
+        .map(|standing| {
+            let completed = has_completed(&challenge.configuration, &standing);
+            (standing, completed)
+        })
+        .collect();
+    db::insert_challenge_results(pool, challenge_id, &standings).await?;
+    // Read back rather than use `standings`: a concurrent request may have
+    // finalized first
+    let results = db::get_challenge_results(pool, challenge_id).await?;
+
+    Ok((challenge, results))
+}
+
+/// Completed as on GET /v1/me/challenges: marked completed, or at 100% of the
+/// goal. Participants who left never complete.
+fn has_completed(config: &serde_json::Value, standing: &FinalStandingRow) -> bool {
+    match standing.participation_status.as_deref() {
+        Some("left") => false,
+        Some("completed") => true,
+        _ => {
+            let completed_goals = standing.completed_goals.as_array().map_or(0, |g| g.len());
+            percentage_complete(config, completed_goals, standing.current_value) >= 100.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standing(status: Option<&str>, completed_goals: usize) -> FinalStandingRow {
+        FinalStandingRow {
+            callsign: "K1ABC".to_string(),
+            score: 10,
+            rank: 1,
+            completed_goals: serde_json::json!(vec!["US-0001"; completed_goals]),
+            current_value: 0,
+            participation_status: status.map(str::to_string),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn completion_follows_status_then_goal() {
+        let config = serde_json::json!({
+            "goals": { "type": "collection", "items": [{ "id": "a" }, { "id": "b" }] }
+        });
+        assert!(has_completed(&config, &standing(Some("active"), 2)));
+        assert!(!has_completed(&config, &standing(Some("active"), 1)));
+        assert!(has_completed(&config, &standing(Some("completed"), 0)));
+        assert!(!has_completed(&config, &standing(Some("left"), 2)));
+        assert!(has_completed(&config, &standing(None, 2)));
+    }
+}
@@ -1,19 +1,28 @@
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::{header, HeaderMap, StatusCode},
 };
 
-use crate::extractors::{Json, Path};
+use crate::extractors::{Json, Path, Query};
 use serde::Serialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::db;
 use crate::error::AppError;
+use crate::hamalert::{self, TriggerPreview};
 use crate::models::{
-    ChallengeListItem, ChallengeResponse, CreateChallengeRequest, ListChallengesQuery,
+    validate_configuration, ChallengeListItem, ChallengeResponse, CreateChallengeRequest,
+    ListChallengesQuery, PreviewScoreRequest, PreviewScoreResponse, ValidateChallengeRequest,
+    ValidateChallengeResponse,
 };
 
+use super::categories::resolve_category;
+use super::progress::preview_score;
+
+/// Most sample entries POST /v1/challenges/preview-score accepts.
+const PREVIEW_MAX_ENTRIES: usize = 500;
+
 #[derive(Serialize)]
 pub struct DataResponse<T> {
     pub data: T,
@@ -47,39 +56,150 @@ pub async fn list_challenges(
     }))
 }
 
+/// GET /v1/challenges/:id — `ETag` is `"v{version}"`; a matching
+/// `If-None-Match` gets 304 with no body.
 pub async fn get_challenge(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Result<(HeaderMap, Json<DataResponse<ChallengeResponse>>), AppError> {
     let challenge = db::get_challenge(&pool, id)
         .await?
         .ok_or(AppError::ChallengeNotFound { challenge_id: id })?;
 
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "X-Challenge-Version",
-        challenge.version.to_string().parse().unwrap(),
-    );
-
-    let etag = format!(
-        "\"{}:{}\"",
-        challenge.version,
-        challenge.updated_at.timestamp()
-    );
-    headers.insert(header::ETAG, etag.parse().unwrap());
+    let etag = challenge_etag(challenge.version);
+    if header_matches(&headers, header::IF_NONE_MATCH, &etag) == Some(true) {
+        return Err(AppError::NotModified);
+    }
 
     Ok((
-        headers,
+        version_headers(challenge.version),
         Json(DataResponse {
             data: challenge.into(),
         }),
     ))
 }
 
+/// The strong ETag of a challenge version.
+fn challenge_etag(version: i32) -> String {
+    format!("\"v{version}\"")
+}
+
+/// `ETag` and `X-Challenge-Version` for a challenge version.
+fn version_headers(version: i32) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("X-Challenge-Version", version.into());
+    headers.insert(header::ETAG, challenge_etag(version).parse().unwrap());
+    headers
+}
+
+/// Whether a conditional header lists `etag` (or is `*`); `None` when the
+/// header is absent. A weak `W/` prefix is ignored, since the ETags served
+/// are only ever strong.
+pub(super) fn header_matches(
+    headers: &HeaderMap,
+    name: header::HeaderName,
+    etag: &str,
+) -> Option<bool> {
+    let value = headers.get(name)?.to_str().unwrap_or_default();
+    Some(
+        value
+            .split(',')
+            .map(str::trim)
+            .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag),
+    )
+}
+
+/// Versions an `If-Match` header accepts, or `None` for any version (header
+/// absent or `*`). Entries that aren't challenge ETags match nothing.
+fn if_match_versions(headers: &HeaderMap) -> Option<Vec<i32>> {
+    let value = headers.get(header::IF_MATCH)?.to_str().unwrap_or_default();
+    let entries: Vec<&str> = value.split(',').map(str::trim).collect();
+    if entries.contains(&"*") {
+        return None;
+    }
+    Some(
+        entries
+            .iter()
+            .filter_map(|entry| entry.strip_prefix("\"v")?.strip_suffix('"')?.parse().ok())
+            .collect(),
+    )
+}
+
+/// POST /v1/challenges/validate
+///
+/// Check a challenge type and configuration without saving anything.
+pub async fn validate_challenge(
+    Json(req): Json<ValidateChallengeRequest>,
+) -> Result<Json<DataResponse<ValidateChallengeResponse>>, AppError> {
+    let errors = validate_configuration(&req.challenge_type, &req.configuration);
+
+    Ok(Json(DataResponse {
+        data: ValidateChallengeResponse {
+            valid: errors.is_empty(),
+            errors,
+        },
+    }))
+}
+
+/// POST /v1/challenges/preview-score
+///
+/// Score sample entries against a draft configuration without saving anything.
+pub async fn preview_challenge_score(
+    Json(req): Json<PreviewScoreRequest>,
+) -> Result<Json<DataResponse<PreviewScoreResponse>>, AppError> {
+    if req.entries.len() > PREVIEW_MAX_ENTRIES {
+        return Err(AppError::Validation {
+            message: format!("entries may list at most {PREVIEW_MAX_ENTRIES} samples"),
+        });
+    }
+
+    let errors = validate_configuration(&req.challenge_type, &req.configuration);
+    let preview = errors
+        .is_empty()
+        .then(|| preview_score(&req.configuration, &req.entries));
+
+    Ok(Json(DataResponse {
+        data: PreviewScoreResponse {
+            valid: errors.is_empty(),
+            errors,
+            preview,
+        },
+    }))
+}
+
+/// GET /v1/challenges/:id/hamalert/preview — the HamAlert triggers the
+/// challenge's `hamalertConfig` translates to, without contacting HamAlert.
+/// A stored config that doesn't translate is reported as a validation error.
+pub async fn preview_hamalert_triggers(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataResponse<TriggerPreview>>, AppError> {
+    let challenge = db::get_challenge(&pool, id)
+        .await?
+        .ok_or(AppError::ChallengeNotFound { challenge_id: id })?;
+
+    let preview = hamalert::preview(&challenge.name, challenge.hamalert_config.as_ref())
+        .map_err(|message| AppError::Validation { message })?;
+
+    Ok(Json(DataResponse { data: preview }))
+}
+
+/// Reject a `hamalertConfig` that wouldn't translate to triggers.
+fn validate_hamalert_config(req: &CreateChallengeRequest) -> Result<(), AppError> {
+    if let Some(config) = &req.hamalert_config {
+        hamalert::parse_config(config).map_err(|message| AppError::Validation { message })?;
+    }
+    Ok(())
+}
+
 pub async fn create_challenge(
     State(pool): State<PgPool>,
-    Json(req): Json<CreateChallengeRequest>,
+    Json(mut req): Json<CreateChallengeRequest>,
 ) -> Result<(StatusCode, Json<DataResponse<ChallengeResponse>>), AppError> {
+    validate_hamalert_config(&req)?;
+    req.category = resolve_category(&pool, &req.category).await?;
+    req.canonicalize_json();
     let challenge = db::create_challenge(&pool, &req).await?;
 
     Ok((
@@ -90,18 +210,36 @@ pub async fn create_challenge(
     ))
 }
 
+/// PUT /v1/admin/challenges/:id — replace a challenge, bumping its version.
+/// With `If-Match`, a challenge at any other version is left alone and 412 is
+/// returned with its current ETag.
 pub async fn update_challenge(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
-    Json(req): Json<CreateChallengeRequest>,
-) -> Result<Json<DataResponse<ChallengeResponse>>, AppError> {
-    let challenge = db::update_challenge(&pool, id, &req)
-        .await?
-        .ok_or(AppError::ChallengeNotFound { challenge_id: id })?;
+    headers: HeaderMap,
+    Json(mut req): Json<CreateChallengeRequest>,
+) -> Result<(HeaderMap, Json<DataResponse<ChallengeResponse>>), AppError> {
+    validate_hamalert_config(&req)?;
+    req.category = resolve_category(&pool, &req.category).await?;
+    req.canonicalize_json();
+    let expected_versions = if_match_versions(&headers);
+    let Some(challenge) =
+        db::update_challenge(&pool, id, &req, expected_versions.as_deref()).await?
+    else {
+        return Err(match db::get_challenge(&pool, id).await? {
+            Some(current) => AppError::PreconditionFailed {
+                current_etag: challenge_etag(current.version),
+            },
+            None => AppError::ChallengeNotFound { challenge_id: id },
+        });
+    };
 
-    Ok(Json(DataResponse {
-        data: challenge.into(),
-    }))
+    Ok((
+        version_headers(challenge.version),
+        Json(DataResponse {
+            data: challenge.into(),
+        }),
+    ))
 }
 
 pub async fn delete_challenge(
@@ -116,3 +254,114 @@ pub async fn delete_challenge(
         Err(AppError::ChallengeNotFound { challenge_id: id })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+
+    fn headers(name: header::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn if_none_match_hits_listed_weak_or_wildcard_etags() {
+        let etag = challenge_etag(7);
+        assert_eq!(etag, "\"v7\"");
+        for value in ["\"v7\"", "\"v6\", \"v7\"", "W/\"v7\"", "*"] {
+            let headers = headers(header::IF_NONE_MATCH, value);
+            assert_eq!(
+                header_matches(&headers, header::IF_NONE_MATCH, &etag),
+                Some(true),
+                "{value}"
+            );
+        }
+        let stale = headers(header::IF_NONE_MATCH, "\"v6\"");
+        assert_eq!(
+            header_matches(&stale, header::IF_NONE_MATCH, &etag),
+            Some(false)
+        );
+        assert_eq!(
+            header_matches(&HeaderMap::new(), header::IF_NONE_MATCH, &etag),
+            None
+        );
+    }
+
+    #[test]
+    fn if_match_lists_versions_and_wildcard_accepts_any() {
+        assert_eq!(if_match_versions(&HeaderMap::new()), None);
+        assert_eq!(if_match_versions(&headers(header::IF_MATCH, "*")), None);
+        assert_eq!(
+            if_match_versions(&headers(header::IF_MATCH, "\"v3\", \"v4\"")),
+            Some(vec![3, 4])
+        );
+        // Old-style or weak tags never match a strong comparison
+        assert_eq!(
+            if_match_versions(&headers(header::IF_MATCH, "\"3:1700000000\", W/\"v3\"")),
+            Some(vec![])
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn challenge_etag_conditional_requests() {
+        let pool = test_support::pool().await;
+        let req = || CreateChallengeRequest {
+            name: format!("ETag test {}", Uuid::new_v4()),
+            description: "Conditional requests".to_string(),
+            author: None,
+            category: "award".to_string(),
+            challenge_type: "collection".to_string(),
+            configuration: serde_json::json!({}),
+            invite_config: None,
+            hamalert_config: None,
+            is_active: Some(false),
+        };
+        let challenge = db::create_challenge(&pool, &req()).await.unwrap();
+        let (id, etag) = (challenge.id, challenge_etag(challenge.version));
+
+        // Hit: the current ETag gets 304
+        let hit = get_challenge(
+            State(pool.clone()),
+            Path(id),
+            headers(header::IF_NONE_MATCH, "*"),
+        )
+        .await;
+        // Miss: a stale ETag gets the challenge and its current ETag
+        let miss = get_challenge(
+            State(pool.clone()),
+            Path(id),
+            headers(header::IF_NONE_MATCH, "\"v0\""),
+        )
+        .await;
+        // A stale If-Match leaves the challenge alone
+        let stale_update = update_challenge(
+            State(pool.clone()),
+            Path(id),
+            headers(header::IF_MATCH, "\"v0\""),
+            Json(req()),
+        )
+        .await;
+        let mut current = HeaderMap::new();
+        current.insert(header::IF_MATCH, etag.parse().unwrap());
+        let update = update_challenge(State(pool.clone()), Path(id), current, Json(req())).await;
+
+        db::delete_challenge(&pool, id).await.unwrap();
+
+        assert!(matches!(hit, Err(AppError::NotModified)));
+        let (miss_headers, _) = miss.unwrap();
+        assert_eq!(miss_headers[header::ETAG], etag.as_str());
+        match stale_update {
+            Err(AppError::PreconditionFailed { current_etag }) => assert_eq!(current_etag, etag),
+            other => panic!("expected PreconditionFailed, got {:?}", other.map(|_| ())),
+        }
+        let (update_headers, Json(updated)) = update.unwrap();
+        assert_eq!(updated.data.version, challenge.version + 1);
+        assert_eq!(
+            update_headers[header::ETAG],
+            challenge_etag(challenge.version + 1).as_str()
+        );
+    }
+}
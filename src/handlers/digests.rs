@@ -0,0 +1,88 @@
+use axum::extract::{Extension, Query, State};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::auth::AuthContext;
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::Json;
+use crate::models::digest::{DigestPrecomputeResponse, DigestQuery, IsoWeek, WeeklyDigest};
+use crate::models::User;
+
+use super::DataResponse;
+
+/// Parse `?week=`, defaulting to `default` when absent. Weeks that haven't
+/// started yet are rejected.
+fn requested_week(query: &DigestQuery, default: IsoWeek) -> Result<IsoWeek, AppError> {
+    let week = match query.week.as_deref() {
+        Some(week) => IsoWeek::parse(week).map_err(|message| AppError::Validation { message })?,
+        None => default,
+    };
+    if week.starts_at() > Utc::now() {
+        return Err(AppError::Validation {
+            message: format!("Week {week} hasn't started yet"),
+        });
+    }
+    Ok(week)
+}
+
+/// Run the digest's grouped queries for one user and assemble the sections.
+async fn compute_digest(
+    pool: &PgPool,
+    user: &User,
+    week: IsoWeek,
+) -> Result<WeeklyDigest, AppError> {
+    let (start, end) = (week.starts_at(), week.ends_at());
+    let own = db::get_digest_activity_counts(pool, user.id, start, end).await?;
+    let progressed = db::count_challenges_progressed(pool, &user.callsign, start, end).await?;
+    let friends = db::get_digest_friend_activity(pool, user.id, start, end).await?;
+    let standings =
+        db::get_digest_standings(pool, &user.callsign, week.first_day(), week.last_day()).await?;
+
+    Ok(WeeklyDigest::build(
+        week, own, progressed, friends, standings,
+    ))
+}
+
+/// GET /v1/me/digest — the caller's weekly summary: own activity, friend
+/// highlights, and challenge rank movement for an ISO week, defaulting to the
+/// current one (auth required).
+pub async fn get_my_digest(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<DigestQuery>,
+) -> Result<Json<DataResponse<WeeklyDigest>>, AppError> {
+    let week = requested_week(&query, IsoWeek::containing(Utc::now()))?;
+    let user = db::get_or_create_user(&pool, &auth.callsign).await?;
+    let digest = compute_digest(&pool, &user, week).await?;
+
+    Ok(Json(DataResponse { data: digest }))
+}
+
+/// POST /v1/admin/digests/precompute — compute and store every active
+/// user's digest in `weekly_digests`, defaulting to the last completed week
+/// (admin only). Re-running replaces the stored digests.
+pub async fn precompute_digests(
+    State(pool): State<PgPool>,
+    Query(query): Query<DigestQuery>,
+) -> Result<Json<DataResponse<DigestPrecomputeResponse>>, AppError> {
+    let week = requested_week(&query, IsoWeek::containing(Utc::now()).previous())?;
+    let week_key = week.to_string();
+
+    let users = db::list_digest_users(&pool).await?;
+    for user in &users {
+        let digest = compute_digest(&pool, user, week).await?;
+        let json = serde_json::to_value(&digest)
+            .map_err(|e| AppError::Internal(format!("serialize digest: {e}")))?;
+        db::upsert_weekly_digest(&pool, user.id, &week_key, &json).await?;
+    }
+
+    tracing::info!(week = %week_key, users = users.len(), "Precomputed weekly digests");
+
+    Ok(Json(DataResponse {
+        data: DigestPrecomputeResponse {
+            week: week_key,
+            users: users.len() as u64,
+        },
+    }))
+}
@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+use axum::extract::{Extension, Query};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::extractors::Json;
+use crate::server::ServerState;
+
+use super::DataResponse;
+
+const DEFAULT_DRAIN_THRESHOLD: usize = 0;
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+const MAX_DRAIN_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrainQuery {
+    /// Return once at most this many other requests are still in flight.
+    pub threshold: Option<usize>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrainResponse {
+    pub draining: bool,
+    /// False if the timeout passed before requests and aggregator cycles settled.
+    pub idle: bool,
+    /// Other requests still in flight (excludes this one).
+    pub in_flight: usize,
+    pub active_cycles: usize,
+    pub waited_ms: u64,
+}
+
+impl DrainResponse {
+    fn snapshot(state: &ServerState, idle: bool, waited: Duration) -> Self {
+        Self {
+            draining: state.is_draining(),
+            idle,
+            in_flight: state.in_flight().saturating_sub(1),
+            active_cycles: state.active_cycles(),
+            waited_ms: waited.as_millis() as u64,
+        }
+    }
+}
+
+/// POST /v1/admin/drain — fail readiness, pause aggregators after their current
+/// cycle, and wait for in-flight work to settle (admin only)
+pub async fn drain(
+    Extension(state): Extension<ServerState>,
+    Query(query): Query<DrainQuery>,
+) -> Result<Json<DataResponse<DrainResponse>>, AppError> {
+    let threshold = query.threshold.unwrap_or(DEFAULT_DRAIN_THRESHOLD);
+    let timeout_secs = query.timeout_secs.unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECS);
+    if timeout_secs > MAX_DRAIN_TIMEOUT_SECS {
+        return Err(AppError::Validation {
+            message: format!("timeoutSecs must be at most {MAX_DRAIN_TIMEOUT_SECS}"),
+        });
+    }
+
+    state.set_draining(true);
+    tracing::warn!(
+        threshold,
+        timeout_secs,
+        "Drain requested; readiness now failing"
+    );
+
+    let started = Instant::now();
+    // +1: this request is itself in flight
+    let idle = state
+        .wait_until_idle(threshold + 1, Duration::from_secs(timeout_secs))
+        .await;
+    let response = DrainResponse::snapshot(&state, idle, started.elapsed());
+    if !idle {
+        tracing::warn!(
+            in_flight = response.in_flight,
+            active_cycles = response.active_cycles,
+            "Drain timed out before work settled"
+        );
+    }
+
+    Ok(Json(DataResponse { data: response }))
+}
+
+/// POST /v1/admin/undrain — restore readiness and resume aggregators (admin only)
+pub async fn undrain(
+    Extension(state): Extension<ServerState>,
+) -> Result<Json<DataResponse<DrainResponse>>, AppError> {
+    state.set_draining(false);
+    tracing::info!("Undrained; readiness restored");
+
+    Ok(Json(DataResponse {
+        data: DrainResponse::snapshot(&state, true, Duration::ZERO),
+    }))
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use axum::extract::{Extension, State};
+use sqlx::PgPool;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::Json;
+use crate::feature_flags::{Feature, FeatureFlags};
+use crate::models::feature_flag::FeatureFlagResponse;
+
+use super::DataResponse;
+
+/// GET /v1/admin/features — all runtime feature flags (admin only)
+pub async fn list_feature_flags(
+    State(pool): State<PgPool>,
+) -> Result<Json<DataResponse<Vec<FeatureFlagResponse>>>, AppError> {
+    let rows = db::list_feature_flags(&pool).await?;
+
+    Ok(Json(DataResponse {
+        data: rows.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// PUT /v1/admin/features — set flags from a `{"name": enabled}` map (admin only).
+/// Takes effect immediately on this instance and within a minute on others.
+pub async fn update_feature_flags(
+    State(pool): State<PgPool>,
+    Extension(flags): Extension<FeatureFlags>,
+    Json(body): Json<BTreeMap<String, bool>>,
+) -> Result<Json<DataResponse<Vec<FeatureFlagResponse>>>, AppError> {
+    if body.is_empty() {
+        return Err(AppError::Validation {
+            message: "at least one flag is required".to_string(),
+        });
+    }
+    if let Some(unknown) = body.keys().find(|k| Feature::from_key(k).is_none()) {
+        return Err(AppError::Validation {
+            message: format!("unknown feature flag: {unknown}"),
+        });
+    }
+
+    let updates: Vec<(&str, bool)> = body.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    db::set_feature_flags(&pool, &updates).await?;
+    flags.apply(updates);
+
+    let rows = db::list_feature_flags(&pool).await?;
+    Ok(Json(DataResponse {
+        data: rows.into_iter().map(Into::into).collect(),
+    }))
+}
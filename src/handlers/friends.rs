@@ -2,15 +2,19 @@ use axum::{
     extract::{Extension, State},
     http::StatusCode,
 };
+use chrono::{Duration, Utc};
 
 use crate::extractors::{Json, Path};
 use sqlx::PgPool;
 
 use crate::auth::AuthContext;
+use crate::blocks::BlockCache;
 use crate::config::Config;
 use crate::db;
 use crate::error::AppError;
-use crate::models::{CreateFriendRequestBody, FriendInviteResponse, FriendRequestResponse};
+use crate::models::{
+    CreateFriendInviteRequest, CreateFriendRequestBody, FriendInviteResponse, FriendRequestResponse,
+};
 
 use super::DataResponse;
 
@@ -25,57 +29,124 @@ pub async fn get_invite_link(
     let user = db::get_or_create_user(&pool, &auth.callsign).await?;
 
     // Create a new invite
-    let invite = db::create_friend_invite(&pool, user.id, config.invite_expiry_days).await?;
+    let invite = db::create_friend_invite(&pool, user.id, config.invite_expiry_days, None).await?;
 
     Ok(Json(DataResponse {
         data: invite.into_response(&config.invite_base_url),
     }))
 }
 
+/// Invites a user may have outstanding (unused and unexpired) at once.
+const MAX_OUTSTANDING_INVITES: i64 = 20;
+
+/// POST /v1/invites
+/// Create a friend invite with an optional note and a shorter expiry
+pub async fn create_friend_invite(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
+    Extension(auth): Extension<AuthContext>,
+    Json(body): Json<CreateFriendInviteRequest>,
+) -> Result<(StatusCode, Json<DataResponse<FriendInviteResponse>>), AppError> {
+    let max_days = config.invite_expiry_days;
+    let expires_in_days = body.expires_in_days.unwrap_or(max_days);
+    if !(1..=max_days).contains(&expires_in_days) {
+        return Err(AppError::Validation {
+            message: format!("expiresInDays must be between 1 and {max_days}"),
+        });
+    }
+    let note = body
+        .sanitized_note()
+        .map_err(|message| AppError::Validation { message })?;
+
+    let user = db::get_or_create_user(&pool, &auth.callsign).await?;
+    if db::count_outstanding_friend_invites(&pool, user.id).await? >= MAX_OUTSTANDING_INVITES {
+        return Err(AppError::MaxOutstandingInvites {
+            max: MAX_OUTSTANDING_INVITES,
+        });
+    }
+
+    let invite = db::create_friend_invite(&pool, user.id, expires_in_days, note.as_deref()).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(DataResponse {
+            data: invite.into_response(&config.invite_base_url),
+        }),
+    ))
+}
+
+/// Days a declined sender must wait before asking the same user again.
+const DECLINED_REQUEST_COOLDOWN_DAYS: i64 = 7;
+
 /// POST /v1/friends/requests
-/// Create a friend request either by user ID or invite token
+/// Create a friend request by user ID, callsign, or invite token
 pub async fn create_friend_request(
     State(pool): State<PgPool>,
+    Extension(blocks): Extension<BlockCache>,
     Extension(auth): Extension<AuthContext>,
     Json(body): Json<CreateFriendRequestBody>,
 ) -> Result<(StatusCode, Json<DataResponse<FriendRequestResponse>>), AppError> {
     // Get or create user record for the sender
     let sender = db::get_or_create_user(&pool, &auth.callsign).await?;
 
-    // Determine the target user
-    let target_user_id = match (&body.to_user_id, &body.invite_token) {
-        (Some(user_id), None) => {
-            // Direct user ID specified
-            let target = db::get_user_by_id(&pool, *user_id)
-                .await?
-                .ok_or(AppError::UserNotFound { user_id: *user_id })?;
-            target.id
-        }
-        (None, Some(token)) => {
-            // Invite token specified
-            let invite = db::get_valid_friend_invite(&pool, token)
-                .await?
-                .ok_or_else(|| AppError::FriendInviteNotFound {
-                    token: token.clone(),
-                })?;
-
-            // Mark the invite as used
-            db::mark_invite_used(&pool, token, sender.id).await?;
-
-            invite.user_id
-        }
-        (Some(_), Some(_)) => {
+    let targets = [
+        body.to_user_id.is_some(),
+        body.invite_token.is_some(),
+        body.to_callsign.is_some(),
+    ];
+    match targets.iter().filter(|given| **given).count() {
+        0 => {
             return Err(AppError::Validation {
-                message: "Provide either toUserId or inviteToken, not both".to_string(),
+                message: "One of toUserId, toCallsign, or inviteToken is required".to_string(),
             });
         }
-        (None, None) => {
+        1 => {}
+        _ => {
             return Err(AppError::Validation {
-                message: "Either toUserId or inviteToken is required".to_string(),
+                message: "Provide only one of toUserId, toCallsign, or inviteToken".to_string(),
             });
         }
+    }
+
+    // Determine the target user, and the invite's note when there is one
+    let (target_user_id, invite_note) = if let Some(user_id) = body.to_user_id {
+        // Direct user ID specified
+        let target = db::get_user_by_id(&pool, user_id)
+            .await?
+            .ok_or(AppError::UserNotFound { user_id })?;
+        (target.id, None)
+    } else if let Some(callsign) = &body.to_callsign {
+        // Existing user by callsign
+        let callsign = callsign.trim().to_uppercase();
+        let target = db::get_user_by_callsign(&pool, &callsign)
+            .await?
+            .ok_or(AppError::CallsignNotFound { callsign })?;
+        (target.id, None)
+    } else {
+        // Invite token specified
+        let token = body.invite_token.as_deref().unwrap_or_default();
+        let invite = db::get_valid_friend_invite(&pool, token)
+            .await?
+            .ok_or_else(|| AppError::FriendInviteNotFound {
+                token: token.to_string(),
+            })?;
+
+        // A blocked pair can't use each other's invites; check before spending it
+        if blocks.is_blocked(&pool, sender.id, invite.user_id).await? {
+            return Err(AppError::UserBlocked);
+        }
+
+        // Mark the invite as used
+        db::mark_invite_used(&pool, token, sender.id).await?;
+
+        (invite.user_id, invite.note)
     };
 
+    // Invites were checked above
+    if body.invite_token.is_none() && blocks.is_blocked(&pool, sender.id, target_user_id).await? {
+        return Err(AppError::UserBlocked);
+    }
+
     // Cannot friend yourself
     if sender.id == target_user_id {
         return Err(AppError::CannotFriendSelf);
@@ -94,15 +165,25 @@ pub async fn create_friend_request(
         return Err(AppError::FriendRequestExists);
     }
 
+    // A declined sender waits before asking directly again; an invite from
+    // the recipient overrides that
+    if body.invite_token.is_none() {
+        if let Some(declined_at) = db::get_declined_at(&pool, sender.id, target_user_id).await? {
+            let retry_after = declined_at + Duration::days(DECLINED_REQUEST_COOLDOWN_DAYS);
+            if retry_after > Utc::now() {
+                return Err(AppError::FriendRequestCooldown { retry_after });
+            }
+        }
+    }
+
     // Create the friend request
     let request = db::create_friend_request(&pool, sender.id, target_user_id).await?;
+    let response = FriendRequestResponse {
+        invite_note,
+        ..request.into()
+    };
 
-    Ok((
-        StatusCode::CREATED,
-        Json(DataResponse {
-            data: request.into(),
-        }),
-    ))
+    Ok((StatusCode::CREATED, Json(DataResponse { data: response })))
 }
 
 /// POST /v1/friends/suggestions
@@ -159,7 +240,7 @@ pub async fn list_friends(
     Ok(Json(DataResponse { data }))
 }
 
-/// GET /v1/friends/requests/pending
+/// GET /v1/friends/requests, GET /v1/friends/requests/pending
 /// List all pending friend requests (incoming and outgoing) for the authenticated user
 pub async fn list_pending_requests(
     State(pool): State<PgPool>,
@@ -189,6 +270,8 @@ pub async fn list_pending_requests(
 /// Accept a pending friend request
 pub async fn accept_friend_request(
     State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
+    Extension(blocks): Extension<BlockCache>,
     Path(request_id): Path<uuid::Uuid>,
     Extension(auth): Extension<AuthContext>,
 ) -> Result<
@@ -208,8 +291,14 @@ pub async fn accept_friend_request(
     if request.to_user_id != user.id {
         return Err(AppError::Forbidden);
     }
+    if blocks
+        .is_blocked(&pool, user.id, request.from_user_id)
+        .await?
+    {
+        return Err(AppError::UserBlocked);
+    }
 
-    let accepted = db::accept_friend_request(&pool, request_id)
+    let accepted = db::accept_friend_request(&pool, request_id, config.max_friends)
         .await?
         .ok_or(AppError::FriendRequestNotFound { request_id })?;
 
@@ -263,3 +352,65 @@ pub async fn remove_friend(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use crate::models::CreateFriendRequestBody;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn declined_sender_waits_out_the_cooldown() {
+        let pool = test_support::pool().await;
+        let users = test_support::users(&pool, 2).await;
+        let (sender, recipient) = (&users[0], &users[1]);
+        let send = || {
+            create_friend_request(
+                State(pool.clone()),
+                Extension(BlockCache::new(std::time::Duration::from_secs(60))),
+                Extension(AuthContext {
+                    callsign: sender.callsign.clone(),
+                    participant_id: uuid::Uuid::new_v4(),
+                }),
+                Json(CreateFriendRequestBody {
+                    to_user_id: Some(recipient.id),
+                    invite_token: None,
+                    to_callsign: None,
+                }),
+            )
+        };
+        let declined_days_ago = |days: i64| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query(
+                    "UPDATE friend_requests
+                     SET status = 'declined', responded_at = now() - make_interval(days => $3)
+                     WHERE from_user_id = $1 AND to_user_id = $2",
+                )
+                .bind(sender.id)
+                .bind(recipient.id)
+                .bind(days as i32)
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        };
+
+        let first = send().await;
+        declined_days_ago(DECLINED_REQUEST_COOLDOWN_DAYS - 1).await;
+        let within_cooldown = send().await;
+        declined_days_ago(DECLINED_REQUEST_COOLDOWN_DAYS + 1).await;
+        let after_cooldown = send().await;
+
+        test_support::cleanup_users(&pool, users.iter().map(|u| u.id)).await;
+        assert_eq!(first.unwrap().0, StatusCode::CREATED);
+        assert!(matches!(
+            within_cooldown,
+            Err(AppError::FriendRequestCooldown { .. })
+        ));
+        let (status, Json(resent)) = after_cooldown.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(resent.data.status, "pending");
+    }
+}
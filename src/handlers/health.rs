@@ -1,17 +1,45 @@
+use axum::http::StatusCode;
 use axum::{Extension, Json};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 
+use crate::aggregators::AggregatorStartReport;
+use crate::config::Config;
 use crate::rbn::SpotStore;
+use crate::server::ServerState;
 
 #[derive(Serialize)]
 pub struct HealthResponse {
     pub status: &'static str,
     pub version: &'static str,
+    pub features: FeatureHealth,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rbn: Option<RbnHealth>,
 }
 
+/// Whether optional subsystems are switched on. A disabled feature is not a failure.
+#[derive(Serialize)]
+pub struct FeatureHealth {
+    pub spots: FeatureState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureState {
+    Enabled,
+    Disabled,
+}
+
+impl From<bool> for FeatureState {
+    fn from(enabled: bool) -> Self {
+        if enabled {
+            Self::Enabled
+        } else {
+            Self::Disabled
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct RbnHealth {
     pub connected: bool,
@@ -21,7 +49,10 @@ pub struct RbnHealth {
     pub spots_per_minute: f64,
 }
 
-pub async fn health_check(Extension(rbn_store): Extension<SpotStore>) -> Json<HealthResponse> {
+pub async fn health_check(
+    Extension(rbn_store): Extension<SpotStore>,
+    Extension(config): Extension<Config>,
+) -> Json<HealthResponse> {
     let (size, oldest) = rbn_store.health_info();
     let stats = rbn_store.stats(1);
 
@@ -39,6 +70,56 @@ pub async fn health_check(Extension(rbn_store): Extension<SpotStore>) -> Json<He
     Json(HealthResponse {
         status: "ok",
         version: env!("CARGO_PKG_VERSION"),
+        features: FeatureHealth {
+            spots: config.spots_enabled.into(),
+        },
         rbn,
     })
 }
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    pub status: &'static str,
+}
+
+/// GET /readyz — 503 while the server is draining so load balancers stop
+/// sending new traffic. `/v1/health` remains the liveness probe.
+pub async fn readiness_check(
+    Extension(state): Extension<ServerState>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    if state.is_draining() {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ReadinessResponse { status: "draining" }),
+        )
+    } else {
+        (StatusCode::OK, Json(ReadinessResponse { status: "ready" }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    pub cleanup: Option<CleanupStatus>,
+    pub aggregators: Vec<AggregatorStartReport>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupStatus {
+    pub last_run_at: DateTime<Utc>,
+    pub last_deleted: u64,
+}
+
+/// GET /v1/status — background job status. `cleanup` is null until the
+/// TTL cleanup has completed a run; `aggregators` lists the latest start
+/// attempt of each enabled spot aggregator.
+pub async fn status(Extension(state): Extension<ServerState>) -> Json<StatusResponse> {
+    Json(StatusResponse {
+        cleanup: state.last_cleanup().map(|run| CleanupStatus {
+            last_run_at: run.at,
+            last_deleted: run.deleted,
+        }),
+        aggregators: state.aggregator_starts(),
+    })
+}
@@ -0,0 +1,96 @@
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+};
+use sqlx::PgPool;
+
+use crate::aggregators::mode::normalize_mode;
+use crate::auth::IntegrationContext;
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::Json;
+use crate::models::integration_key::{CreateIntegrationKeyRequest, IntegrationKeyResponse};
+use crate::models::spot::{sanitize_comment, CreateIntegrationSpotRequest, SpotResponse};
+
+use super::DataResponse;
+
+/// POST /v1/integrations/spots — push a spot (partner API key required).
+pub async fn create_integration_spot(
+    State(pool): State<PgPool>,
+    Extension(partner): Extension<IntegrationContext>,
+    Extension(config): Extension<Config>,
+    Json(req): Json<CreateIntegrationSpotRequest>,
+) -> Result<(StatusCode, Json<DataResponse<SpotResponse>>), AppError> {
+    let callsign = req.callsign.trim().to_uppercase();
+    if callsign.is_empty() {
+        return Err(AppError::Validation {
+            message: "callsign is required".to_string(),
+        });
+    }
+
+    // The program must exist, as for self-spots
+    db::get_program(&pool, &req.program_slug)
+        .await?
+        .ok_or(AppError::ProgramNotFound {
+            slug: req.program_slug.clone(),
+        })?;
+
+    let comments = req
+        .comments
+        .as_deref()
+        .and_then(|c| sanitize_comment(c, config.spot_comment_max_len));
+
+    let country_code = req.reference.as_deref().and_then(|reference| {
+        config
+            .reference_country_patterns
+            .country_for(&req.program_slug, reference)
+    });
+
+    let spotter = req
+        .spotter
+        .as_deref()
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty());
+
+    let spot = db::insert_integration_spot(
+        &pool,
+        &db::spots::InsertIntegrationSpotParams {
+            integration_key_id: partner.key_id,
+            callsign: &callsign,
+            program_slug: &req.program_slug,
+            frequency_khz: req.frequency_khz,
+            mode: normalize_mode(&req.mode),
+            raw_mode: Some(&req.mode),
+            reference: req.reference.as_deref(),
+            country_code: country_code.as_deref(),
+            spotter: spotter.as_deref(),
+            comments: comments.as_deref(),
+        },
+    )
+    .await?;
+
+    tracing::debug!(partner = %partner.partner, spot_id = %spot.id, "Integration spot pushed");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(DataResponse { data: spot.into() }),
+    ))
+}
+
+/// POST /v1/admin/integration-keys — issue a partner API key (admin).
+pub async fn create_integration_key(
+    State(pool): State<PgPool>,
+    Json(req): Json<CreateIntegrationKeyRequest>,
+) -> Result<(StatusCode, Json<DataResponse<IntegrationKeyResponse>>), AppError> {
+    let partner = req.partner.trim();
+    if partner.is_empty() {
+        return Err(AppError::Validation {
+            message: "partner is required".to_string(),
+        });
+    }
+
+    let key = db::create_integration_key(&pool, partner).await?;
+
+    Ok((StatusCode::CREATED, Json(DataResponse { data: key.into() })))
+}
@@ -1,61 +1,239 @@
 use axum::{
-    extract::{Path, State},
-    http::{header, StatusCode},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
+use serde::Deserialize;
 use sqlx::PgPool;
 
 use crate::db;
+use crate::i18n::Locale;
+
+#[derive(Debug, Deserialize)]
+pub struct InvitePageQuery {
+    pub lang: Option<String>,
+}
+
+fn request_locale(query: &InvitePageQuery, headers: &HeaderMap) -> Locale {
+    let accept_language = headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok());
+    Locale::negotiate(query.lang.as_deref(), accept_language)
+}
+
+fn html_response(page: String) -> Response {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+        page,
+    )
+        .into_response()
+}
 
 /// GET /invite/:token
 /// Renders an HTML page for friend invite links opened in a browser.
-/// Shows the inviter's callsign and a deep link to open in Carrier Wave.
-pub async fn invite_page(State(pool): State<PgPool>, Path(token): Path<String>) -> Response {
+/// Shows the inviter's callsign, their note if any, and a deep link to open
+/// in Carrier Wave. Localized from `Accept-Language`, overridable with `?lang=`.
+pub async fn invite_page(
+    State(pool): State<PgPool>,
+    Path(token): Path<String>,
+    Query(query): Query<InvitePageQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let locale = request_locale(&query, &headers);
+
     // Look up the invite and the inviter's callsign
-    let page = match build_invite_page(&pool, &token).await {
+    let page = match build_invite_page(&pool, &token, locale).await {
         Ok(html) => html,
-        Err(_) => render_invite_page(None, &token),
+        Err(_) => render_invite_page(locale, None, None, &token),
     };
 
-    (StatusCode::OK, [(header::CONTENT_TYPE, "text/html")], page).into_response()
+    html_response(page)
 }
 
 async fn build_invite_page(
     pool: &PgPool,
     token: &str,
+    locale: Locale,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let invite = db::get_friend_invite(pool, token).await?;
 
-    let callsign = match invite {
-        Some(ref inv) if inv.used_at.is_none() && inv.expires_at > chrono::Utc::now() => {
+    let (callsign, note) = match invite {
+        Some(inv) if inv.used_at.is_none() && inv.expires_at > chrono::Utc::now() => {
             let user = db::get_user_by_id(pool, inv.user_id).await?;
-            user.map(|u| u.callsign)
+            (user.map(|u| u.callsign), inv.note)
+        }
+        _ => (None, None),
+    };
+
+    Ok(render_invite_page(
+        locale,
+        callsign.as_deref(),
+        note.as_deref(),
+        token,
+    ))
+}
+
+/// GET /join/:token
+/// Renders an HTML page for challenge invite links opened in a browser.
+/// Shows the challenge name and a deep link to open in Carrier Wave.
+pub async fn challenge_invite_page(
+    State(pool): State<PgPool>,
+    Path(token): Path<String>,
+    Query(query): Query<InvitePageQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let locale = request_locale(&query, &headers);
+
+    let page = match build_challenge_invite_page(&pool, &token, locale).await {
+        Ok(html) => html,
+        Err(_) => render_challenge_invite_page(locale, None, &token),
+    };
+
+    html_response(page)
+}
+
+async fn build_challenge_invite_page(
+    pool: &PgPool,
+    token: &str,
+    locale: Locale,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let invite = db::get_invite(pool, token).await?;
+
+    let challenge_name = match invite {
+        Some(ref inv)
+            if inv.expires_at.is_none_or(|e| e > chrono::Utc::now())
+                && inv.max_uses.is_none_or(|max| inv.use_count < max) =>
+        {
+            let challenge = db::get_challenge(pool, inv.challenge_id).await?;
+            challenge.filter(|c| c.is_active).map(|c| c.name)
         }
         _ => None,
     };
 
-    Ok(render_invite_page(callsign.as_deref(), token))
+    Ok(render_challenge_invite_page(
+        locale,
+        challenge_name.as_deref(),
+        token,
+    ))
 }
 
-fn render_invite_page(callsign: Option<&str>, token: &str) -> String {
-    let deep_link = format!("carrierwave://invite/{}", token);
+fn render_invite_page(
+    locale: Locale,
+    callsign: Option<&str>,
+    note: Option<&str>,
+    token: &str,
+) -> String {
+    let t = locale.strings();
+    let deep_link = deep_link("invite", token);
 
     let (title, heading, description) = match callsign {
-        Some(cs) => (
-            format!("{} wants to be friends on Carrier Wave", cs),
-            format!("{} wants to be friends!", cs),
-            format!("Open this link in Carrier Wave to add {} as a friend.", cs),
+        Some(cs) => {
+            let cs = escape_html(cs);
+            (
+                t.friend_title.replace("{callsign}", &cs),
+                t.friend_heading.replace("{callsign}", &cs),
+                t.friend_description.replace("{callsign}", &cs),
+            )
+        }
+        None => (
+            t.friend_title_generic.to_string(),
+            t.invited_heading.to_string(),
+            t.friend_description_generic.to_string(),
         ),
+    };
+
+    let note = note.map(escape_html);
+    render_page(
+        locale,
+        &title,
+        &heading,
+        &description,
+        note.as_deref(),
+        &deep_link,
+    )
+}
+
+fn render_challenge_invite_page(
+    locale: Locale,
+    challenge_name: Option<&str>,
+    token: &str,
+) -> String {
+    let t = locale.strings();
+    let deep_link = deep_link("join", token);
+
+    let (title, heading, description) = match challenge_name {
+        Some(name) => {
+            let name = escape_html(name);
+            (
+                t.challenge_title.replace("{challenge}", &name),
+                t.challenge_heading.replace("{challenge}", &name),
+                t.challenge_description.to_string(),
+            )
+        }
         None => (
-            "Friend invite on Carrier Wave".to_string(),
-            "You've been invited!".to_string(),
-            "Open this link in Carrier Wave to accept this friend invite.".to_string(),
+            t.challenge_title_generic.to_string(),
+            t.invited_heading.to_string(),
+            t.challenge_description_generic.to_string(),
         ),
     };
 
+    render_page(locale, &title, &heading, &description, None, &deep_link)
+}
+
+/// `carrierwave://{kind}/{token}` with the token percent-encoded. The token
+/// comes straight from the request path, so anything outside the unreserved
+/// URL characters is encoded before the link lands in an `href`.
+fn deep_link(kind: &str, token: &str) -> String {
+    let mut link = format!("carrierwave://{kind}/");
+    for b in token.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            link.push(b as char);
+        } else {
+            link.push_str(&format!("%{b:02X}"));
+        }
+    }
+    link
+}
+
+/// Escape text for safe interpolation into HTML element and attribute content.
+fn escape_html(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn render_page(
+    locale: Locale,
+    title: &str,
+    heading: &str,
+    description: &str,
+    note: Option<&str>,
+    deep_link: &str,
+) -> String {
+    let t = locale.strings();
+    // Already escaped; quoted under the description
+    let note = note
+        .map(|note| {
+            format!(
+                r#"
+        <p style="font-style: italic; color: #e2e8f0;">“{note}”</p>"#
+            )
+        })
+        .unwrap_or_default();
+
     format!(
         r#"<!DOCTYPE html>
-<html lang="en">
+<html lang="{lang}">
 <head>
     <meta charset="utf-8">
     <meta name="viewport" content="width=device-width, initial-scale=1">
@@ -123,15 +301,70 @@ fn render_invite_page(callsign: Option<&str>, token: &str) -> String {
     <div class="card">
         <div class="icon">📡</div>
         <h1>{heading}</h1>
-        <p>{description}</p>
-        <a class="open-btn" href="{deep_link}">Open in Carrier Wave</a>
-        <div class="footer">Carrier Wave &mdash; Ham Radio Challenges</div>
+        <p>{description}</p>{note}
+        <a class="open-btn" href="{deep_link}">{open_button}</a>
+        <div class="footer">{footer}</div>
     </div>
 </body>
 </html>"#,
+        lang = locale.code(),
         title = title,
         description = description,
+        note = note,
         heading = heading,
         deep_link = deep_link,
+        open_button = t.open_button,
+        footer = t.footer,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn german_friend_invite_matches_snapshot() {
+        let page = render_invite_page(Locale::De, Some("DL1ABC"), None, "tok123");
+        assert_eq!(
+            page,
+            include_str!("../../tests/snapshots/invite_page_de.html")
+        );
+    }
+
+    #[test]
+    fn html_lang_follows_locale() {
+        let page = render_challenge_invite_page(Locale::Ja, Some("Summit Sprint"), "inv_1");
+        assert!(page.contains(r#"<html lang="ja">"#));
+        assert!(page.contains("Summit Sprintに招待されました！"));
+        assert!(page.contains("carrierwave://join/inv_1"));
+    }
+
+    #[test]
+    fn invite_note_is_quoted_and_escaped() {
+        let page = render_invite_page(Locale::En, Some("K1ABC"), Some("<3 the hamfest"), "t");
+        assert!(page.contains("“&lt;3 the hamfest”</p>"));
+        assert!(!page.contains("<3 the"));
+    }
+
+    #[test]
+    fn deep_link_token_is_percent_encoded() {
+        let token = r#"x"><script>alert(1)</script>"#;
+        let friend = render_invite_page(Locale::En, None, None, token);
+        let challenge = render_challenge_invite_page(Locale::En, None, token);
+
+        for (page, kind) in [(friend, "invite"), (challenge, "join")] {
+            assert!(page.contains(&format!(
+                r#"href="carrierwave://{kind}/x%22%3E%3Cscript%3Ealert%281%29%3C%2Fscript%3E""#
+            )));
+            assert!(!page.contains("<script>"));
+        }
+        assert_eq!(deep_link("join", "inv_Ab-9"), "carrierwave://join/inv_Ab-9");
+    }
+
+    #[test]
+    fn interpolated_names_are_escaped() {
+        let page = render_challenge_invite_page(Locale::En, Some("<b>Rock & Roll</b>"), "t");
+        assert!(page.contains("&lt;b&gt;Rock &amp; Roll&lt;/b&gt;"));
+        assert!(!page.contains("<b>Rock"));
+    }
+}
@@ -1,10 +1,11 @@
-use axum::{extract::State, http::StatusCode};
+use axum::{extract::State, http::StatusCode, Extension};
 
 use crate::extractors::{Json, Path};
 use serde::Serialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::config::Config;
 use crate::db;
 use crate::error::AppError;
 use crate::models::{CreateInviteRequest, InviteResponse};
@@ -19,6 +20,7 @@ pub struct InviteListResponse {
 
 pub async fn generate_invite(
     State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
     Path(challenge_id): Path<Uuid>,
     Json(req): Json<CreateInviteRequest>,
 ) -> Result<(StatusCode, Json<DataResponse<InviteResponse>>), AppError> {
@@ -28,7 +30,7 @@ pub async fn generate_invite(
         .ok_or(AppError::ChallengeNotFound { challenge_id })?;
 
     let invite = db::create_invite(&pool, challenge_id, req.max_uses, req.expires_at).await?;
-    let base_url = "";
+    let base_url = &config.route_prefix;
 
     Ok((
         StatusCode::CREATED,
@@ -40,6 +42,7 @@ pub async fn generate_invite(
 
 pub async fn list_invites(
     State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
     Path(challenge_id): Path<Uuid>,
 ) -> Result<Json<DataResponse<InviteListResponse>>, AppError> {
     // Verify challenge exists
@@ -48,7 +51,7 @@ pub async fn list_invites(
         .ok_or(AppError::ChallengeNotFound { challenge_id })?;
 
     let invites = db::list_invites(&pool, challenge_id).await?;
-    let base_url = "";
+    let base_url = &config.route_prefix;
 
     Ok(Json(DataResponse {
         data: InviteListResponse {
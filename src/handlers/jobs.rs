@@ -0,0 +1,22 @@
+use axum::extract::Extension;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::extractors::{Json, Path};
+use crate::jobs::Job;
+use crate::server::ServerState;
+
+use super::DataResponse;
+
+/// GET /v1/admin/jobs/:id — progress of a background job started from the
+/// admin API on this instance (admin only).
+pub async fn get_job(
+    Extension(state): Extension<ServerState>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<DataResponse<Job>>, AppError> {
+    let job = state
+        .jobs()
+        .get(job_id)
+        .ok_or(AppError::JobNotFound { job_id })?;
+    Ok(Json(DataResponse { data: job }))
+}
@@ -10,7 +10,10 @@ use uuid::Uuid;
 use crate::auth::AuthContext;
 use crate::db;
 use crate::error::AppError;
-use crate::models::{JoinChallengeRequest, JoinChallengeResponse};
+use crate::models::{
+    pick_division, ChangeDivisionRequest, JoinChallengeRequest, JoinChallengeResponse,
+    ParticipationResponse,
+};
 
 use super::DataResponse;
 
@@ -39,6 +42,9 @@ pub async fn join_challenge(
         }
     }
 
+    let division = pick_division(&challenge.configuration, req.division.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
+
     let (mut participant, is_new) =
         db::get_or_create_participant(&pool, &req.callsign, req.device_name.as_deref()).await?;
 
@@ -52,6 +58,7 @@ pub async fn join_challenge(
         challenge_id,
         &req.callsign,
         req.invite_token.as_deref(),
+        division.as_ref().map(|d| d.name.as_str()),
     )
     .await?;
 
@@ -70,11 +77,52 @@ pub async fn join_challenge(
                 joined_at: participation.joined_at,
                 status: participation.status,
                 historical_allowed,
+                division: participation.division,
             },
         }),
     ))
 }
 
+/// PUT /v1/challenges/:id/division — switch to another of the challenge's
+/// divisions. Only allowed until the first progress submission, since
+/// scores are stored with the division's multiplier applied.
+pub async fn change_division(
+    State(pool): State<PgPool>,
+    Path(challenge_id): Path<Uuid>,
+    Extension(auth): Extension<AuthContext>,
+    Json(req): Json<ChangeDivisionRequest>,
+) -> Result<Json<DataResponse<ParticipationResponse>>, AppError> {
+    let challenge = db::get_challenge(&pool, challenge_id)
+        .await?
+        .ok_or(AppError::ChallengeNotFound { challenge_id })?;
+
+    let division = pick_division(&challenge.configuration, Some(&req.division))
+        .map_err(|message| AppError::Validation { message })?
+        .map(|d| d.name)
+        .unwrap_or_default();
+
+    let Some(participation) =
+        db::set_division(&pool, challenge_id, &auth.callsign, &division).await?
+    else {
+        return Err(
+            match db::get_participation(&pool, challenge_id, &auth.callsign).await? {
+                Some(p) if p.status == "active" => AppError::DivisionLocked,
+                _ => AppError::NotParticipating,
+            },
+        );
+    };
+
+    Ok(Json(DataResponse {
+        data: ParticipationResponse {
+            participation_id: participation.id,
+            challenge_id: participation.challenge_id,
+            joined_at: participation.joined_at,
+            status: participation.status,
+            division: participation.division,
+        },
+    }))
+}
+
 pub async fn leave_challenge(
     State(pool): State<PgPool>,
     Path(challenge_id): Path<Uuid>,
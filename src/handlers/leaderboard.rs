@@ -1,4 +1,6 @@
-use axum::extract::{Query, State};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Extension, Query, State};
+use axum::response::Response;
 
 use crate::extractors::{Json, Path};
 use chrono::Utc;
@@ -7,27 +9,34 @@ use uuid::Uuid;
 
 use crate::db;
 use crate::error::AppError;
-use crate::models::{LeaderboardQuery, LeaderboardResponse};
+use crate::leaderboard_live::{LeaderboardHub, Snapshot};
+use crate::models::{pick_division, LeaderboardQuery, LeaderboardResponse};
 
 use super::DataResponse;
 
 pub async fn get_leaderboard(
     State(pool): State<PgPool>,
     Path(challenge_id): Path<Uuid>,
-    Query(query): Query<LeaderboardQuery>,
+    Query(mut query): Query<LeaderboardQuery>,
 ) -> Result<Json<DataResponse<LeaderboardResponse>>, AppError> {
-    let _challenge = db::get_challenge(&pool, challenge_id)
+    let challenge = db::get_challenge(&pool, challenge_id)
         .await?
         .ok_or(AppError::ChallengeNotFound { challenge_id })?;
 
+    // Rank within one division, matched in any case against the configuration
+    if let Some(requested) = query.division.take() {
+        let division = pick_division(&challenge.configuration, Some(&requested))
+            .map_err(|message| AppError::Validation { message })?;
+        query.division = division.map(|d| d.name);
+    }
+
     let (leaderboard, total) = if let Some(ref around) = query.around {
-        let entries = db::get_leaderboard_around(&pool, challenge_id, around, 5).await?;
-        let total: (i64,) =
-            sqlx::query_as(r#"SELECT COUNT(*) FROM progress WHERE challenge_id = $1"#)
-                .bind(challenge_id)
-                .fetch_one(&pool)
+        let division = query.division.as_deref();
+        let entries =
+            db::get_leaderboard_around(&pool, challenge_id, around, 5, query.movement, division)
                 .await?;
-        (entries, total.0)
+        let total = db::count_leaderboard(&pool, challenge_id, division).await?;
+        (entries, total)
     } else {
         db::get_leaderboard(&pool, challenge_id, &query).await?
     };
@@ -47,3 +56,86 @@ pub async fn get_leaderboard(
         },
     }))
 }
+
+/// GET /v1/challenges/:id/leaderboard/ws — WebSocket pushing the top of the
+/// leaderboard (`{"data": ...}` as in the REST response) on connect and after
+/// progress changes, at most once per `PUSH_INTERVAL`. Refused for inactive
+/// challenges. Messages from the client are ignored.
+pub async fn leaderboard_ws(
+    State(pool): State<PgPool>,
+    Path(challenge_id): Path<Uuid>,
+    Extension(hub): Extension<LeaderboardHub>,
+    ws: Option<WebSocketUpgrade>,
+) -> Result<Response, AppError> {
+    let challenge = db::get_challenge(&pool, challenge_id)
+        .await?
+        .ok_or(AppError::ChallengeNotFound { challenge_id })?;
+    if !challenge.is_active {
+        return Err(AppError::ChallengeEnded);
+    }
+    let ws = ws.ok_or_else(|| AppError::Validation {
+        message: "Expected a WebSocket upgrade request".to_string(),
+    })?;
+
+    Ok(ws.on_upgrade(move |socket| stream_leaderboard(socket, pool, hub, challenge_id)))
+}
+
+async fn stream_leaderboard(
+    mut socket: WebSocket,
+    pool: PgPool,
+    hub: LeaderboardHub,
+    challenge_id: Uuid,
+) {
+    // Subscribe before the initial snapshot so no write in between is missed
+    let mut subscription = hub.subscribe(challenge_id, {
+        let pool = pool.clone();
+        move || leaderboard_snapshot(pool.clone(), challenge_id)
+    });
+
+    let Some(initial) = leaderboard_snapshot(pool, challenge_id).await else {
+        return;
+    };
+    if socket
+        .send(Message::Text(initial.to_string()))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            snapshot = subscription.recv() => {
+                let Some(snapshot) = snapshot else { break };
+                if socket.send(Message::Text(snapshot.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// The default leaderboard page serialized as a push message.
+async fn leaderboard_snapshot(pool: PgPool, challenge_id: Uuid) -> Option<Snapshot> {
+    let result = db::get_leaderboard(&pool, challenge_id, &LeaderboardQuery::default()).await;
+    let (leaderboard, total) = match result {
+        Ok(page) => page,
+        Err(e) => {
+            tracing::warn!("Leaderboard snapshot for {} failed: {}", challenge_id, e);
+            return None;
+        }
+    };
+    let message = DataResponse {
+        data: LeaderboardResponse {
+            leaderboard,
+            total,
+            user_position: None,
+            last_updated: Utc::now(),
+        },
+    };
+    serde_json::to_string(&message).ok().map(Snapshot::from)
+}
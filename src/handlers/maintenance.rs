@@ -0,0 +1,38 @@
+use axum::extract::{Extension, State};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::Json;
+use crate::maintenance::next_run_after;
+use crate::models::maintenance::MaintenanceResponse;
+
+use super::DataResponse;
+
+/// GET /v1/admin/maintenance — schedule, last run, and the row counts it
+/// recorded (admin only)
+pub async fn get_maintenance_status(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
+) -> Result<Json<DataResponse<MaintenanceResponse>>, AppError> {
+    let last_run = db::get_last_maintenance_run(&pool).await?;
+    let tables = match &last_run {
+        Some(run) => db::list_table_stats(&pool, run.id).await?,
+        None => Vec::new(),
+    };
+
+    Ok(Json(DataResponse {
+        data: MaintenanceResponse {
+            enabled: config.maintenance_enabled,
+            hour_utc: config.maintenance_hour_utc,
+            retention_days: config.maintenance_retention_days,
+            next_run_at: config
+                .maintenance_enabled
+                .then(|| next_run_after(Utc::now(), config.maintenance_hour_utc)),
+            last_run: last_run.map(Into::into),
+            tables: tables.into_iter().map(Into::into).collect(),
+        },
+    }))
+}
@@ -0,0 +1,83 @@
+use axum::extract::{Extension, Query, State};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::auth::AuthContext;
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::Json;
+use crate::models::me::{
+    ChallengeSchedule, MyChallenge, MyChallengesQuery, MyChallengesResponse, MyStatsResponse,
+};
+
+use super::progress::percentage_complete;
+use super::DataResponse;
+
+/// GET /v1/me/stats — self-spot counts per program for the caller (auth required).
+pub async fn get_my_stats(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<DataResponse<MyStatsResponse>>, AppError> {
+    let user = db::get_or_create_user(&pool, &auth.callsign).await?;
+    let rows = db::get_self_spot_stats(&pool, user.id).await?;
+
+    Ok(Json(DataResponse { data: rows.into() }))
+}
+
+/// GET /v1/me/challenges — the caller's joined challenges with progress and
+/// rank, most recently active first (auth required).
+pub async fn get_my_challenges(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<MyChallengesQuery>,
+) -> Result<Json<DataResponse<MyChallengesResponse>>, AppError> {
+    let limit = query.page_limit();
+    let offset = query.page_offset();
+
+    let (rows, total) = db::get_joined_challenges(&pool, &auth.callsign, &query).await?;
+    let now = Utc::now();
+
+    let challenges = rows
+        .into_iter()
+        .map(|row| {
+            let completed_goals = row
+                .completed_goals
+                .as_ref()
+                .and_then(|g| g.as_array())
+                .map_or(0, |g| g.len());
+            let percentage = percentage_complete(
+                &row.configuration,
+                completed_goals,
+                row.current_value.unwrap_or(0),
+            );
+            MyChallenge {
+                challenge_id: row.challenge_id,
+                name: row.name,
+                category: row.category,
+                challenge_type: row.challenge_type,
+                schedule: ChallengeSchedule::from_configuration(
+                    &row.configuration,
+                    row.is_active,
+                    now,
+                ),
+                completed: row.participation_status == "completed" || percentage >= 100.0,
+                status: row.participation_status,
+                joined_at: row.joined_at,
+                score: row.score.unwrap_or(0),
+                rank: row.rank,
+                current_tier: row.current_tier,
+                percentage,
+                last_activity_at: row.last_activity_at,
+            }
+        })
+        .collect();
+
+    Ok(Json(DataResponse {
+        data: MyChallengesResponse {
+            challenges,
+            total,
+            limit,
+            offset,
+        },
+    }))
+}
@@ -1,18 +1,31 @@
+pub mod admin_config;
+pub mod aggregators;
 pub mod badges;
+pub mod blocks;
+pub mod capabilities;
+pub mod categories;
+pub mod certificates;
 pub mod challenges;
 pub mod clubs;
 pub mod clubs_admin;
 pub mod contests;
+pub mod digests;
+pub mod drain;
 pub mod equipment;
 pub mod events;
 pub mod events_admin;
+pub mod feature_flags;
 pub mod friends;
 pub mod health;
 pub mod historic_trails;
+pub mod integrations;
 pub mod invite_page;
 pub mod invites;
+pub mod jobs;
 pub mod join;
 pub mod leaderboard;
+pub mod maintenance;
+pub mod me;
 pub mod metrickit_telemetry;
 pub mod metrics;
 pub mod park_boundaries;
@@ -21,29 +34,43 @@ pub mod pota_stats;
 pub mod programs;
 pub mod progress;
 pub mod rbn;
+pub mod search;
 pub mod spots;
 pub mod twilio_webhook;
 pub mod upload_error_telemetry;
 pub mod users;
 
+pub use admin_config::*;
+pub use aggregators::*;
 pub use badges::*;
+pub use blocks::*;
+pub use capabilities::*;
+pub use categories::*;
+pub use certificates::*;
 pub use challenges::*;
 pub use clubs::*;
 pub use clubs_admin::*;
 pub use contests::*;
+pub use digests::*;
+pub use drain::*;
 pub use equipment::*;
 pub use events::*;
 pub use events_admin::*;
+pub use feature_flags::*;
 pub use pota_stats::*;
 pub mod activity_feed;
 pub use activity_feed::*;
 pub use friends::*;
 pub use health::*;
 pub use historic_trails::*;
+pub use integrations::*;
 pub use invite_page::*;
 pub use invites::*;
+pub use jobs::*;
 pub use join::*;
 pub use leaderboard::*;
+pub use maintenance::*;
+pub use me::*;
 pub use metrickit_telemetry::*;
 pub use metrics::*;
 pub use park_boundaries::*;
@@ -51,6 +78,7 @@ pub use participants::*;
 pub use programs::*;
 pub use progress::*;
 pub use rbn::*;
+pub use search::*;
 pub use spots::*;
 pub use twilio_webhook::*;
 pub mod equipment_usage;
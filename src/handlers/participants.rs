@@ -31,6 +31,7 @@ pub async fn get_participation_status(
             challenge_id: participation.challenge_id,
             joined_at: participation.joined_at,
             status: participation.status,
+            division: participation.division,
         },
     }))
 }
@@ -1,15 +1,34 @@
-use axum::{extract::State, http::StatusCode};
+use axum::{
+    body::{Body, Bytes},
+    extract::{rejection::BytesRejection, Query, State},
+    http::{header, HeaderMap, Response, StatusCode},
+    Extension,
+};
 use sqlx::PgPool;
 
+use crate::config::Config;
+
 use crate::db;
 use crate::error::AppError;
 use crate::extractors::{Json, Path};
+use crate::grid::grid_center;
+use crate::models::program_reference::{
+    parse_reference_csv, ReferenceImportResponse, ReferenceSearchQuery, ReferenceSearchResponse,
+};
 use crate::models::{
-    CreateProgramRequest, ProgramListResponse, ProgramResponse, UpdateProgramRequest,
+    known_capability, sniff_icon_type, AddCapabilityRequest, CreateProgramRequest,
+    ProgramIconQuery, ProgramListResponse, ProgramResponse, UpdateProgramRequest,
 };
 
 use super::DataResponse;
 
+/// Largest CSV body POST /v1/admin/programs/:slug/references accepts; a full
+/// summit list is tens of megabytes.
+pub const REFERENCE_IMPORT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Largest icon PUT /v1/admin/programs/:slug/icon accepts.
+pub const ICON_MAX_BYTES: usize = 256 * 1024;
+
 /// GET /v1/programs — list all active programs.
 pub async fn list_programs(
     State(pool): State<PgPool>,
@@ -98,6 +117,43 @@ pub async fn update_program(
     }))
 }
 
+/// POST /v1/admin/programs/:slug/capabilities — add one known capability
+/// without resending the whole list. Adding one the program has is a no-op.
+pub async fn add_program_capability(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    Json(req): Json<AddCapabilityRequest>,
+) -> Result<Json<DataResponse<ProgramResponse>>, AppError> {
+    let capability =
+        known_capability(&req.capability).map_err(|message| AppError::Validation { message })?;
+    let program = db::add_program_capability(&pool, &slug, capability)
+        .await?
+        .ok_or(AppError::ProgramNotFound { slug })?;
+
+    Ok(Json(DataResponse {
+        data: program.into(),
+    }))
+}
+
+/// DELETE /v1/admin/programs/:slug/capabilities/:cap — remove one capability.
+/// Names outside the known set are accepted so stale ones can be cleaned up;
+/// removing one the program lacks is a no-op.
+pub async fn remove_program_capability(
+    State(pool): State<PgPool>,
+    Path((slug, capability)): Path<(String, String)>,
+) -> Result<Json<DataResponse<ProgramResponse>>, AppError> {
+    let capability = known_capability(&capability)
+        .map(str::to_string)
+        .unwrap_or(capability);
+    let program = db::remove_program_capability(&pool, &slug, &capability)
+        .await?
+        .ok_or(AppError::ProgramNotFound { slug })?;
+
+    Ok(Json(DataResponse {
+        data: program.into(),
+    }))
+}
+
 /// DELETE /v1/admin/programs/:slug — delete a program.
 pub async fn delete_program(
     State(pool): State<PgPool>,
@@ -111,3 +167,249 @@ pub async fn delete_program(
         Err(AppError::ProgramNotFound { slug })
     }
 }
+
+/// PUT /v1/admin/programs/:slug/icon — upload a PNG or SVG icon as the raw
+/// body. The type is sniffed from the bytes; the request's content type is
+/// ignored. The program's `icon_url` is pointed at the served icon.
+pub async fn upload_program_icon(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<Config>,
+    Path(slug): Path<String>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Json<DataResponse<ProgramResponse>>, AppError> {
+    let body = body.map_err(|rejection| match rejection.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => AppError::IconTooLarge {
+            max_bytes: ICON_MAX_BYTES,
+        },
+        _ => AppError::InvalidIcon {
+            message: rejection.body_text(),
+        },
+    })?;
+    let content_type = sniff_icon_type(&body).ok_or_else(|| AppError::InvalidIcon {
+        message: "Icon must be a PNG or SVG image".to_string(),
+    })?;
+
+    let icon_path = format!("{}/v1/programs/{}/icon", config.route_prefix, slug);
+    let program = db::set_program_icon(&pool, &slug, content_type, &body, &icon_path)
+        .await?
+        .ok_or(AppError::ProgramNotFound { slug })?;
+
+    Ok(Json(DataResponse {
+        data: program.into(),
+    }))
+}
+
+/// GET /v1/programs/:slug/icon — an active program's uploaded icon. With the
+/// current `v` from `icon_url` it may be cached for good; otherwise caches
+/// revalidate with the strong `ETag`.
+pub async fn get_program_icon(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    Query(query): Query<ProgramIconQuery>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, AppError> {
+    let Some(icon) = db::get_program_icon(&pool, &slug).await? else {
+        return Err(match db::get_program(&pool, &slug).await? {
+            Some(_) => AppError::ProgramIconNotFound { slug },
+            None => AppError::ProgramNotFound { slug },
+        });
+    };
+
+    let etag = icon.etag_header();
+    let cache_control = match query.v.as_deref() {
+        Some(version) if icon.is_version(version) => "public, max-age=31536000, immutable",
+        _ => "public, no-cache",
+    };
+    let not_modified =
+        super::challenges::header_matches(&headers, header::IF_NONE_MATCH, &etag) == Some(true);
+
+    let response = Response::builder()
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control);
+    let response = if not_modified {
+        response
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+    } else {
+        // SVGs can carry scripts; never let them run on this origin
+        response
+            .header(header::CONTENT_TYPE, icon.content_type)
+            .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+            .header(
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'; sandbox",
+            )
+            .body(Body::from(icon.image_data))
+    };
+
+    response.map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// GET /v1/programs/:slug/references — search an active program's references
+/// by name (`q`) and/or distance from a grid square (`nearGrid`).
+pub async fn search_references(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    Query(query): Query<ReferenceSearchQuery>,
+) -> Result<Json<DataResponse<ReferenceSearchResponse>>, AppError> {
+    let q = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let near_grid = query
+        .near_grid
+        .as_deref()
+        .map(str::trim)
+        .filter(|g| !g.is_empty());
+    if q.is_none() && near_grid.is_none() {
+        return Err(AppError::Validation {
+            message: "q or nearGrid is required".to_string(),
+        });
+    }
+    let near = match near_grid {
+        Some(grid) => Some(grid_center(grid).ok_or_else(|| AppError::Validation {
+            message: format!("invalid nearGrid '{grid}'"),
+        })?),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    if db::get_program(&pool, &slug).await?.is_none() {
+        return Err(AppError::ProgramNotFound { slug });
+    }
+
+    let rows = db::search_program_references(&pool, &slug, q, near, limit).await?;
+
+    Ok(Json(DataResponse {
+        data: ReferenceSearchResponse {
+            references: rows.into_iter().map(Into::into).collect(),
+        },
+    }))
+}
+
+/// POST /v1/admin/programs/:slug/references — bulk import references from a
+/// CSV body. Existing references are updated; references missing from the
+/// file are left as they are.
+pub async fn import_references(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    body: String,
+) -> Result<Json<DataResponse<ReferenceImportResponse>>, AppError> {
+    if db::get_any_program(&pool, &slug).await?.is_none() {
+        return Err(AppError::ProgramNotFound { slug });
+    }
+
+    let references =
+        parse_reference_csv(&body).map_err(|message| AppError::Validation { message })?;
+    if references.is_empty() {
+        return Err(AppError::Validation {
+            message: "CSV has no references".to_string(),
+        });
+    }
+
+    let imported = db::upsert_program_references(&pool, &slug, &references).await?;
+    tracing::info!(program = %slug, imported, "Imported program references");
+
+    Ok(Json(DataResponse {
+        data: ReferenceImportResponse {
+            program_slug: slug,
+            imported,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use axum::http::HeaderValue;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn program_icon_upload_and_revalidation() {
+        let pool = test_support::pool().await;
+        let slug = format!("test-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        sqlx::query(
+            "INSERT INTO programs (slug, name, short_name, icon, reference_label, is_active)
+             VALUES ($1, 'Icon Test', 'ICON', 'star', 'Reference', true)",
+        )
+        .bind(&slug)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let config = Config::from_lookup(|var| match var {
+            "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+            "ADMIN_TOKEN" => Some("admin".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        let upload = |data: &'static [u8]| {
+            upload_program_icon(
+                State(pool.clone()),
+                Extension(config.clone()),
+                Path(slug.clone()),
+                Ok(Bytes::from_static(data)),
+            )
+        };
+        let fetch = |version: Option<String>, if_none_match: Option<HeaderValue>| {
+            let mut headers = HeaderMap::new();
+            if let Some(etag) = if_none_match {
+                headers.insert(header::IF_NONE_MATCH, etag);
+            }
+            get_program_icon(
+                State(pool.clone()),
+                Path(slug.clone()),
+                Query(ProgramIconQuery { v: version }),
+                headers,
+            )
+        };
+
+        let missing = fetch(None, None).await;
+        let mut results = Vec::new();
+        for (data, content_type) in [
+            (&b"\x89PNG\r\n\x1a\nicon"[..], "image/png"),
+            (
+                &b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"[..],
+                "image/svg+xml",
+            ),
+        ] {
+            let Json(program) = upload(data).await.unwrap();
+            let icon_url = program.data.icon_url.unwrap();
+            let version = icon_url.split_once("?v=").unwrap().1.to_string();
+
+            let current = fetch(Some(version), None).await.unwrap();
+            let etag = current.headers()[header::ETAG].clone();
+            let unversioned = fetch(None, Some(etag.clone())).await.unwrap();
+            let stale = fetch(None, Some(HeaderValue::from_static("\"stale\"")))
+                .await
+                .unwrap();
+            results.push((icon_url, content_type, data, current, unversioned, stale));
+        }
+        sqlx::query("DELETE FROM programs WHERE slug = $1")
+            .bind(&slug)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(missing, Err(AppError::ProgramIconNotFound { .. })));
+        assert_ne!(results[0].0, results[1].0, "each upload gets a fresh URL");
+        for (icon_url, content_type, data, current, unversioned, stale) in results {
+            assert!(icon_url.starts_with(&format!("/v1/programs/{slug}/icon?v=")));
+            assert_eq!(current.status(), StatusCode::OK);
+            assert_eq!(current.headers()[header::CONTENT_TYPE], content_type);
+            assert_eq!(
+                current.headers()[header::CACHE_CONTROL],
+                "public, max-age=31536000, immutable"
+            );
+            let body = current.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(&body[..], data);
+
+            // Revalidation: a current ETag gets 304, a stale one the icon
+            assert_eq!(unversioned.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(
+                unversioned.headers()[header::CACHE_CONTROL],
+                "public, no-cache"
+            );
+            assert_eq!(stale.status(), StatusCode::OK);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use axum::extract::{Extension, State};
+use axum::http::StatusCode;
 
 use crate::extractors::{Json, Path};
 use sqlx::PgPool;
@@ -7,7 +8,12 @@ use uuid::Uuid;
 use crate::auth::AuthContext;
 use crate::db;
 use crate::error::AppError;
-use crate::models::{Progress, ProgressResponse, ReportProgressRequest, ReportProgressResponse};
+use crate::leaderboard_live::LeaderboardHub;
+use crate::models::{
+    apply_multiplier, data_entry_program, division_multiplier, DataEntryRequest, DataEntryResponse,
+    PreviewEntryOutcome, PreviewScoreEntry, Progress, ProgressResponse, ReportProgressRequest,
+    ReportProgressResponse, ScorePreview,
+};
 
 use super::DataResponse;
 
@@ -15,50 +21,206 @@ pub async fn report_progress(
     State(pool): State<PgPool>,
     Path(challenge_id): Path<Uuid>,
     Extension(auth): Extension<AuthContext>,
+    Extension(leaderboard_hub): Extension<LeaderboardHub>,
     Json(req): Json<ReportProgressRequest>,
 ) -> Result<Json<DataResponse<ReportProgressResponse>>, AppError> {
     let challenge = db::get_challenge(&pool, challenge_id)
         .await?
         .ok_or(AppError::ChallengeNotFound { challenge_id })?;
 
-    let _participation = db::get_participation(&pool, challenge_id, &auth.callsign)
+    let participation = db::get_participation(&pool, challenge_id, &auth.callsign)
         .await?
         .ok_or(AppError::NotParticipating)?;
 
-    let score = calculate_score(&challenge.configuration, &req);
-    let current_tier = determine_tier(&challenge.configuration, score);
-
-    let _progress = db::upsert_progress(
+    let server_progress = save_progress(
         &pool,
+        &leaderboard_hub,
+        &challenge.configuration,
         challenge_id,
         &auth.callsign,
+        participation.division.as_deref(),
+        req,
+    )
+    .await?;
+    let new_badges = vec![];
+
+    Ok(Json(DataResponse {
+        data: ReportProgressResponse {
+            accepted: true,
+            server_progress,
+            new_badges,
+        },
+    }))
+}
+
+/// Score and store a participant's progress, returning it with their rank.
+/// The stored score carries the multiplier of the participant's division;
+/// tiers are reached on the unscaled score.
+async fn save_progress(
+    pool: &PgPool,
+    leaderboard_hub: &LeaderboardHub,
+    config: &serde_json::Value,
+    challenge_id: Uuid,
+    callsign: &str,
+    division: Option<&str>,
+    req: ReportProgressRequest,
+) -> Result<ProgressResponse, AppError> {
+    let base_score = calculate_score(config, req.completed_goals.len(), req.current_value);
+    let current_tier = determine_tier(config, base_score);
+    let score = apply_multiplier(base_score, division_multiplier(config, division));
+
+    let _progress = db::upsert_progress(
+        pool,
+        challenge_id,
+        callsign,
         &req,
         score,
         current_tier.as_deref(),
     )
     .await?;
+    leaderboard_hub.invalidate(challenge_id);
 
-    let rank = db::get_rank(&pool, challenge_id, &auth.callsign)
+    let rank = db::get_rank(pool, challenge_id, callsign)
         .await?
         .unwrap_or(0);
 
-    let percentage = calculate_percentage(&challenge.configuration, &req);
-    let new_badges = vec![];
+    let percentage = calculate_percentage(config, &req);
 
-    Ok(Json(DataResponse {
-        data: ReportProgressResponse {
-            accepted: true,
-            server_progress: ProgressResponse {
-                completed_goals: req.completed_goals,
-                current_value: req.current_value,
-                percentage,
-                score,
-                rank,
-                current_tier,
+    Ok(ProgressResponse {
+        completed_goals: req.completed_goals,
+        current_value: req.current_value,
+        percentage,
+        score,
+        rank,
+        current_tier,
+    })
+}
+
+/// POST /v1/challenges/:id/data-entries — submit a value typed into the
+/// data-entry field of the program the challenge names in
+/// `configuration.dataEntry.program`. The value is checked against the
+/// program's `dataEntryFormat`, recorded as a progress entry, and applied to
+/// the caller's progress: for collection goals it must name a goal item,
+/// for cumulative goals it adds 1 to `currentValue`.
+pub async fn submit_data_entry(
+    State(pool): State<PgPool>,
+    Path(challenge_id): Path<Uuid>,
+    Extension(auth): Extension<AuthContext>,
+    Extension(leaderboard_hub): Extension<LeaderboardHub>,
+    Json(req): Json<DataEntryRequest>,
+) -> Result<(StatusCode, Json<DataResponse<DataEntryResponse>>), AppError> {
+    let challenge = db::get_challenge(&pool, challenge_id)
+        .await?
+        .ok_or(AppError::ChallengeNotFound { challenge_id })?;
+
+    let participation = db::get_participation(&pool, challenge_id, &auth.callsign)
+        .await?
+        .ok_or(AppError::NotParticipating)?;
+
+    let Some(slug) = data_entry_program(&challenge.configuration) else {
+        return Err(AppError::Validation {
+            message: "This challenge doesn't accept data entries".to_string(),
+        });
+    };
+    let program = db::get_program(&pool, slug)
+        .await?
+        .ok_or_else(|| AppError::ProgramNotFound {
+            slug: slug.to_string(),
+        })?;
+    if !program.capabilities.iter().any(|c| c == "dataEntry") {
+        return Err(AppError::CapabilityNotSupported {
+            capability: "dataEntry".to_string(),
+            program_slug: program.slug,
+        });
+    }
+
+    let value = program
+        .normalize_data_entry(&req.value)
+        .map_err(|message| AppError::Validation { message })?;
+
+    let progress = db::get_progress(&pool, challenge_id, &auth.callsign).await?;
+    let mut progress_req = ReportProgressRequest {
+        completed_goals: progress
+            .as_ref()
+            .and_then(|p| serde_json::from_value(p.completed_goals.clone()).ok())
+            .unwrap_or_default(),
+        current_value: progress.as_ref().map_or(0, |p| p.current_value),
+        qualifying_qso_count: 0,
+        last_qso_date: progress.as_ref().and_then(|p| p.last_qso_date),
+    };
+    let (goal_id, counted) = apply_data_entry(
+        &challenge.configuration,
+        &mut progress_req.completed_goals,
+        &mut progress_req.current_value,
+        &value,
+    )
+    .map_err(|message| AppError::Validation { message })?;
+
+    let details = serde_json::json!({ "programSlug": program.slug, "value": value });
+    let entry_id = db::insert_progress_entry(
+        &pool,
+        challenge_id,
+        &auth.callsign,
+        "dataEntry",
+        goal_id.as_deref(),
+        &details,
+    )
+    .await?;
+    let server_progress = save_progress(
+        &pool,
+        &leaderboard_hub,
+        &challenge.configuration,
+        challenge_id,
+        &auth.callsign,
+        participation.division.as_deref(),
+        progress_req,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(DataResponse {
+            data: DataEntryResponse {
+                entry_id,
+                program_slug: program.slug,
+                value,
+                goal_id,
+                counted,
+                server_progress,
             },
-            new_badges,
-        },
-    }))
+        }),
+    ))
+}
+
+/// Apply a normalized data-entry value to progress. Collection goals match
+/// the value to a goal item ID in any case and complete that goal, unless it
+/// already was (`counted` false); cumulative goals add 1 to `current_value`.
+/// Returns the goal completed, if any, and whether progress changed.
+fn apply_data_entry(
+    config: &serde_json::Value,
+    completed_goals: &mut Vec<String>,
+    current_value: &mut i32,
+    value: &str,
+) -> Result<(Option<String>, bool), String> {
+    let goals = config.get("goals");
+    if goals.and_then(|g| g.get("type")).and_then(|t| t.as_str()) == Some("cumulative") {
+        *current_value = current_value.saturating_add(1);
+        return Ok((None, true));
+    }
+
+    let goal_id = goals
+        .and_then(|g| g.get("items"))
+        .and_then(|i| i.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.get("id")?.as_str())
+        .find(|id| id.eq_ignore_ascii_case(value))
+        .ok_or_else(|| format!("'{value}' is not one of this challenge's goals"))?;
+    let counted = !completed_goals.iter().any(|done| done == goal_id);
+    if counted {
+        completed_goals.push(goal_id.to_string());
+    }
+    Ok((Some(goal_id.to_string()), counted))
 }
 
 pub async fn get_progress(
@@ -95,7 +257,7 @@ pub async fn get_progress(
     }))
 }
 
-fn calculate_score(config: &serde_json::Value, req: &ReportProgressRequest) -> i32 {
+fn calculate_score(config: &serde_json::Value, completed_goals: usize, current_value: i32) -> i32 {
     let scoring = config.get("scoring");
     let method = scoring
         .and_then(|s| s.get("method"))
@@ -106,49 +268,34 @@ fn calculate_score(config: &serde_json::Value, req: &ReportProgressRequest) -> i
         "percentage" => {
             let total = get_total_goals(config);
             if total > 0 {
-                (req.completed_goals.len() as f64 / total as f64 * 100.0) as i32
+                (completed_goals as f64 / total as f64 * 100.0) as i32
             } else {
                 0
             }
         }
-        "count" => req.completed_goals.len() as i32,
-        "points" => req.current_value,
-        _ => req.completed_goals.len() as i32,
+        "count" => completed_goals as i32,
+        "points" => current_value,
+        _ => completed_goals as i32,
     }
 }
 
 fn calculate_percentage(config: &serde_json::Value, req: &ReportProgressRequest) -> f64 {
-    let goals = config.get("goals");
-    let goal_type = goals
-        .and_then(|g| g.get("type"))
-        .and_then(|t| t.as_str())
-        .unwrap_or("collection");
-
-    match goal_type {
-        "collection" => {
-            let total = get_total_goals(config);
-            if total > 0 {
-                req.completed_goals.len() as f64 / total as f64 * 100.0
-            } else {
-                0.0
-            }
-        }
-        "cumulative" => {
-            let target = goals
-                .and_then(|g| g.get("targetValue"))
-                .and_then(|t| t.as_i64())
-                .unwrap_or(100) as f64;
-            if target > 0.0 {
-                req.current_value as f64 / target * 100.0
-            } else {
-                0.0
-            }
-        }
-        _ => 0.0,
-    }
+    percentage_complete(config, req.completed_goals.len(), req.current_value)
 }
 
 fn calculate_percentage_from_progress(config: &serde_json::Value, progress: &Progress) -> f64 {
+    let completed: Vec<String> =
+        serde_json::from_value(progress.completed_goals.clone()).unwrap_or_default();
+    percentage_complete(config, completed.len(), progress.current_value)
+}
+
+/// Percentage toward the challenge's goal: completed items for collection
+/// goals, `current_value` against `targetValue` for cumulative ones.
+pub(crate) fn percentage_complete(
+    config: &serde_json::Value,
+    completed_goals: usize,
+    current_value: i32,
+) -> f64 {
     let goals = config.get("goals");
     let goal_type = goals
         .and_then(|g| g.get("type"))
@@ -158,10 +305,8 @@ fn calculate_percentage_from_progress(config: &serde_json::Value, progress: &Pro
     match goal_type {
         "collection" => {
             let total = get_total_goals(config);
-            let completed: Vec<String> =
-                serde_json::from_value(progress.completed_goals.clone()).unwrap_or_default();
             if total > 0 {
-                completed.len() as f64 / total as f64 * 100.0
+                completed_goals as f64 / total as f64 * 100.0
             } else {
                 0.0
             }
@@ -172,7 +317,7 @@ fn calculate_percentage_from_progress(config: &serde_json::Value, progress: &Pro
                 .and_then(|t| t.as_i64())
                 .unwrap_or(100) as f64;
             if target > 0.0 {
-                progress.current_value as f64 / target * 100.0
+                current_value as f64 / target * 100.0
             } else {
                 0.0
             }
@@ -206,3 +351,184 @@ fn determine_tier(config: &serde_json::Value, score: i32) -> Option<String> {
         .and_then(|id| id.as_str())
         .map(String::from)
 }
+
+/// Score sample entries against a configuration the way reported progress is
+/// scored, without touching the database. For collection goals each entry
+/// must name a goal item, and only the first entry per goal counts; for
+/// cumulative goals every entry counts. Counted entries add their `value`
+/// to `currentValue`.
+pub(crate) fn preview_score(
+    config: &serde_json::Value,
+    entries: &[PreviewScoreEntry],
+) -> ScorePreview {
+    let goals = config.get("goals");
+    let collection =
+        goals.and_then(|g| g.get("type")).and_then(|t| t.as_str()) != Some("cumulative");
+    let goal_ids: Vec<&str> = goals
+        .and_then(|g| g.get("items"))
+        .and_then(|i| i.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.get("id")?.as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut completed_goals: Vec<String> = Vec::new();
+    let mut current_value: i32 = 0;
+    let outcomes = entries
+        .iter()
+        .map(|entry| {
+            let invalid = |reason: String| PreviewEntryOutcome {
+                valid: false,
+                counted: false,
+                reason: Some(reason),
+            };
+            let value = entry.value.unwrap_or(0);
+            if value < 0 {
+                return invalid("value must not be negative".to_string());
+            }
+            if collection {
+                let Some(goal_id) = entry.goal_id.as_deref() else {
+                    return invalid("goalId is required for collection goals".to_string());
+                };
+                if !goal_ids.contains(&goal_id) {
+                    return invalid(format!("Unknown goal '{goal_id}'"));
+                }
+                if completed_goals.iter().any(|id| id == goal_id) {
+                    return PreviewEntryOutcome {
+                        valid: true,
+                        counted: false,
+                        reason: Some(format!("Goal '{goal_id}' was already completed")),
+                    };
+                }
+                completed_goals.push(goal_id.to_string());
+            }
+            current_value = current_value.saturating_add(value);
+            PreviewEntryOutcome {
+                valid: true,
+                counted: true,
+                reason: None,
+            }
+        })
+        .collect();
+
+    let score = calculate_score(config, completed_goals.len(), current_value);
+    ScorePreview {
+        percentage: percentage_complete(config, completed_goals.len(), current_value),
+        current_tier: determine_tier(config, score),
+        score,
+        completed_goals,
+        current_value,
+        entries: outcomes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(goal_id: Option<&str>, value: Option<i32>) -> PreviewScoreEntry {
+        PreviewScoreEntry {
+            goal_id: goal_id.map(String::from),
+            value,
+        }
+    }
+
+    #[test]
+    fn data_entry_completes_a_collection_goal_once() {
+        let config = json!({
+            "goals": { "type": "collection", "items": [{ "id": "M-a01f" }, { "id": "M-b02e" }] }
+        });
+        let mut completed = Vec::new();
+        let mut value = 0;
+
+        let applied = apply_data_entry(&config, &mut completed, &mut value, "m-A01F");
+        assert_eq!(applied, Ok((Some("M-a01f".to_string()), true)));
+        let applied = apply_data_entry(&config, &mut completed, &mut value, "M-a01f");
+        assert_eq!(applied, Ok((Some("M-a01f".to_string()), false)));
+        assert_eq!(completed, ["M-a01f"]);
+
+        let err = apply_data_entry(&config, &mut completed, &mut value, "M-zzzz").unwrap_err();
+        assert_eq!(err, "'M-zzzz' is not one of this challenge's goals");
+        assert_eq!(value, 0);
+    }
+
+    #[test]
+    fn data_entry_adds_one_to_a_cumulative_goal() {
+        let config = json!({ "goals": { "type": "cumulative", "targetValue": 10 } });
+        let mut completed = Vec::new();
+        let mut value = 4;
+        let applied = apply_data_entry(&config, &mut completed, &mut value, "123456");
+        assert_eq!(applied, Ok((None, true)));
+        assert_eq!(value, 5);
+        assert!(completed.is_empty());
+    }
+
+    #[test]
+    fn collection_preview_counts_each_goal_once() {
+        let config = json!({
+            "goals": { "type": "collection", "items": [{ "id": "AL" }, { "id": "AK" }, { "id": "AZ" }, { "id": "AR" }] },
+            "tiers": [{ "id": "bronze", "threshold": 1 }, { "id": "silver", "threshold": 2 }],
+            "scoring": { "method": "count" }
+        });
+        let entries = [
+            entry(Some("AL"), None),
+            entry(Some("AK"), None),
+            entry(Some("AL"), None),
+            entry(Some("ZZ"), None),
+            entry(None, Some(1)),
+        ];
+
+        let preview = preview_score(&config, &entries);
+
+        assert_eq!(preview.completed_goals, vec!["AL", "AK"]);
+        assert_eq!(preview.score, 2);
+        assert_eq!(preview.percentage, 50.0);
+        assert_eq!(preview.current_tier.as_deref(), Some("silver"));
+        let flags: Vec<(bool, bool)> = preview
+            .entries
+            .iter()
+            .map(|e| (e.valid, e.counted))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                (true, true),
+                (true, true),
+                (true, false),
+                (false, false),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            preview.entries[3].reason.as_deref(),
+            Some("Unknown goal 'ZZ'")
+        );
+    }
+
+    #[test]
+    fn cumulative_preview_sums_values_for_points() {
+        let config = json!({
+            "goals": { "type": "cumulative", "targetValue": 20 },
+            "scoring": { "method": "points" }
+        });
+        let entries = [
+            entry(None, Some(5)),
+            entry(Some("ignored"), Some(3)),
+            entry(None, Some(-2)),
+        ];
+
+        let preview = preview_score(&config, &entries);
+
+        assert!(preview.completed_goals.is_empty());
+        assert_eq!(preview.current_value, 8);
+        assert_eq!(preview.score, 8);
+        assert_eq!(preview.percentage, 40.0);
+        assert_eq!(preview.current_tier, None);
+        assert!(preview.entries[1].counted);
+        assert!(!preview.entries[2].valid);
+    }
+}
@@ -0,0 +1,91 @@
+use std::future::Future;
+use std::time::Duration;
+
+use axum::extract::{Extension, Query, State};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::auth::AuthContext;
+use crate::blocks::BlockCache;
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+use crate::extractors::Json;
+use crate::models::search::{SearchGroup, SearchResponse};
+use crate::models::spot::SpotResponse;
+
+use super::DataResponse;
+
+/// Max items returned per result group.
+const GROUP_CAP: usize = 10;
+
+/// Time budget for each group's query.
+const GROUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+}
+
+/// GET /v1/search?q=... — callsigns, active spots, and challenges in one request.
+/// Each group is queried concurrently with its own timeout; a group that times
+/// out comes back empty with `timedOut: true` instead of failing the request.
+/// Spot results follow the same public redaction as GET /v1/spots. Users the
+/// caller is blocked with, and their spots, are left out.
+pub async fn search(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Extension(blocks): Extension<BlockCache>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<DataResponse<SearchResponse>>, AppError> {
+    let q = query.q.trim();
+    if q.chars().count() < 2 {
+        return Err(AppError::Validation {
+            message: "q must be at least 2 characters".to_string(),
+        });
+    }
+
+    let blocked = blocks.for_viewer(&pool, auth.as_deref()).await?;
+    let fetch = GROUP_CAP as i64 + 1;
+    let (callsigns, spots, challenges) = tokio::join!(
+        run_group(
+            "callsigns",
+            db::search_callsigns(&pool, q, &blocked.ids, fetch)
+        ),
+        run_group(
+            "spots",
+            db::search_active_spots(&pool, q, &blocked.callsigns, fetch)
+        ),
+        run_group("challenges", db::search_challenges(&pool, q, fetch)),
+    );
+
+    let mut spots: SearchGroup<SpotResponse> = spots?;
+    for spot in &mut spots.items {
+        spot.redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+    }
+
+    Ok(Json(DataResponse {
+        data: SearchResponse {
+            callsigns: callsigns?,
+            spots,
+            challenges: challenges?,
+        },
+    }))
+}
+
+async fn run_group<R, T>(
+    name: &'static str,
+    fut: impl Future<Output = Result<Vec<R>, AppError>>,
+) -> Result<SearchGroup<T>, AppError>
+where
+    R: Into<T>,
+{
+    match tokio::time::timeout(GROUP_TIMEOUT, fut).await {
+        Ok(rows) => Ok(SearchGroup::from_rows(rows?, GROUP_CAP)),
+        Err(_) => {
+            tracing::warn!(group = name, "search group timed out");
+            Ok(SearchGroup::timed_out())
+        }
+    }
+}
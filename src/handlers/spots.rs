@@ -1,19 +1,59 @@
+use std::time::{Duration, Instant};
+
 use axum::{
-    extract::{Extension, Query, State},
-    http::StatusCode,
+    extract::{Extension, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
 };
 use sqlx::PgPool;
 
+use crate::aggregators::mode::{mode_filter, normalize_mode, snap_to_channel, CANONICAL_MODES};
+use crate::aggregators::{pota, sota};
 use crate::auth::AuthContext;
+use crate::blocks::BlockCache;
+use crate::config::Config;
+use crate::cursor::{self, Cursor};
 use crate::db;
 use crate::error::AppError;
-use crate::extractors::{Json, Path};
+use crate::extractors::{Json, Path, Query};
 use crate::models::spot::{
-    CreateSelfSpotRequest, SpotResponse, SpotSource, SpotsListResponse, SpotsPagination,
+    band_range_khz, group_spot_rows, merge_park_summit_spots, sanitize_comment, ActivatorResponse,
+    BoundingBox, CallsignSpotSummary, CallsignStatus, CallsignStatusRequest, CreateSelfSpotRequest,
+    DeletedSpotsResponse, IngestChannel, SelfSpotResponse, SparseSpotResponse, SpotDetailResponse,
+    SpotFieldSelection, SpotGroupResponse, SpotGrouping, SpotHeatmapResponse, SpotIncludes,
+    SpotLookupRequest, SpotLookupResponse, SpotPrograms, SpotResponse, SpotSource, SpotUnits,
+    SpotsArchiveStatsResponse, SpotsDeltaResponse, SpotsListResponse, SpotsPagination,
 };
+use crate::models::spot_report::{build_report_queue, ReportSpotRequest, ReportedSpotResponse};
+use crate::rbn::ingester::FRESHNESS_INTERVAL as RBN_FRESHNESS_INTERVAL;
+use crate::server::ServerState;
 
 use super::DataResponse;
 
+/// Query parameters GET /v1/spots filters by, as advertised in
+/// GET /v1/capabilities. Keep in step with [`SpotsQuery`].
+pub const SPOT_LIST_FILTERS: &[&str] = &[
+    "program",
+    "callsign",
+    "source",
+    "mode",
+    "state",
+    "maxAgeMinutes",
+    "from",
+    "to",
+    "minLat",
+    "maxLat",
+    "minLon",
+    "maxLon",
+    "excludeSelf",
+];
+
+/// Most spots one GET /v1/spots page returns.
+pub const SPOTS_MAX_LIMIT: i64 = 250;
+
+/// Furthest back `maxAgeMinutes` reaches.
+pub const SPOTS_MAX_AGE_MINUTES: i64 = 1440;
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SpotsQuery {
@@ -23,65 +63,615 @@ pub struct SpotsQuery {
     pub mode: Option<String>,
     pub state: Option<String>,
     pub max_age_minutes: Option<i64>,
+    /// Start of an explicit `[from, to)` window, instead of `maxAgeMinutes`.
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// End of the window; defaults to now.
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
     pub limit: Option<i64>,
     pub cursor: Option<String>,
+    pub min_lat: Option<f64>,
+    pub max_lat: Option<f64>,
+    pub min_lon: Option<f64>,
+    pub max_lon: Option<f64>,
+    pub include: Option<String>,
+    pub fields: Option<String>,
+    pub units: Option<String>,
+    /// Leave out the caller's own spots; ignored for anonymous requests.
+    #[serde(default)]
+    pub exclude_self: bool,
+    /// `callsign` to return one entry per activator instead of per spot.
+    pub group_by: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SpotIncludeQuery {
+    pub include: Option<String>,
+}
+
+/// With `include=program`, summaries of the programs the given spots belong
+/// to, fetched in one query. Spots whose `programSlug` was redacted don't
+/// contribute.
+async fn spot_programs(
+    pool: &PgPool,
+    includes: &SpotIncludes,
+    spots: impl IntoIterator<Item = &SpotResponse>,
+) -> Result<Option<SpotPrograms>, AppError> {
+    if !includes.program {
+        return Ok(None);
+    }
+    let mut slugs: Vec<String> = spots
+        .into_iter()
+        .filter_map(|spot| spot.program_slug.clone())
+        .collect();
+    slugs.sort_unstable();
+    slugs.dedup();
+    if slugs.is_empty() {
+        return Ok(Some(SpotPrograms::new()));
+    }
+
+    let programs = db::get_program_summaries(pool, &slugs)
+        .await?
+        .into_iter()
+        .map(|program| (program.slug.clone(), program))
+        .collect();
+    Ok(Some(programs))
+}
+
+/// Upstream sources whose freshness `GET /v1/spots` reports: the age header
+/// and how often each is expected to deliver.
+const FRESHNESS_SOURCES: [(SpotSource, &str, Duration); 3] = [
+    (
+        SpotSource::Pota,
+        "x-aggregator-pota-age",
+        pota::POLL_INTERVAL,
+    ),
+    (
+        SpotSource::Sota,
+        "x-aggregator-sota-age",
+        sota::POLL_INTERVAL,
+    ),
+    (
+        SpotSource::Rbn,
+        "x-aggregator-rbn-age",
+        RBN_FRESHNESS_INTERVAL,
+    ),
+];
+
+/// Longest `from`/`to` window GET /v1/spots serves.
+pub const MAX_SPOT_WINDOW_HOURS: i64 = 6;
+
+/// An explicit `[from, to)` window for GET /v1/spots.
+type SpotWindow = (chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>);
+
+/// The explicit window `from`/`to` ask for, if any: `to` defaults to now,
+/// and the window must be non-empty, at most [`MAX_SPOT_WINDOW_HOURS`] long,
+/// and not combined with `maxAgeMinutes`.
+fn spot_window(params: &SpotsQuery) -> Result<Option<SpotWindow>, AppError> {
+    let invalid = |message: &str| AppError::Validation {
+        message: message.to_string(),
+    };
+    let Some(from) = params.from else {
+        return match params.to {
+            Some(_) => Err(invalid("to requires from")),
+            None => Ok(None),
+        };
+    };
+    if params.max_age_minutes.is_some() {
+        return Err(invalid("from/to cannot be combined with maxAgeMinutes"));
+    }
+    let to = params.to.unwrap_or_else(chrono::Utc::now);
+    if from >= to {
+        return Err(invalid("from must be before to"));
+    }
+    if to - from > chrono::Duration::hours(MAX_SPOT_WINDOW_HOURS) {
+        return Err(AppError::Validation {
+            message: format!("from/to window must be at most {MAX_SPOT_WINDOW_HOURS} hours"),
+        });
+    }
+    Ok(Some((from, to)))
+}
+
+/// Missed intervals after which a source is listed in `staleSources`.
+const STALE_AFTER_INTERVALS: u32 = 3;
+
+/// Age headers (seconds since the last successful fetch) and stale sources,
+/// covering only the sources that have been fetched from.
+fn freshness_report(state: &ServerState, now: Instant) -> (HeaderMap, Vec<&'static str>) {
+    let mut headers = HeaderMap::new();
+    let mut stale = Vec::new();
+    for (source, header_name, interval) in FRESHNESS_SOURCES {
+        let Some(freshness) = state.freshness(source.as_str()) else {
+            continue;
+        };
+        if let Some(age) = freshness.age(now) {
+            headers.insert(
+                HeaderName::from_static(header_name),
+                HeaderValue::from(age.as_secs()),
+            );
+        }
+        if freshness.is_stale(now, interval * STALE_AFTER_INTERVALS) {
+            stale.push(source.as_str());
+        }
+    }
+    (headers, stale)
 }
 
 /// GET /v1/spots — list active spots with optional filters.
+/// Unauthenticated callers get the fields in `PUBLIC_SPOT_FIELDS` nulled.
+/// `fields` limits each spot to the named fields plus `id` and `spottedAt`.
 pub async fn list_spots(
     State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Extension(blocks): Extension<BlockCache>,
+    Extension(state): Extension<ServerState>,
     Query(params): Query<SpotsQuery>,
-) -> Result<Json<DataResponse<SpotsListResponse>>, AppError> {
-    let limit = params.limit.unwrap_or(100).clamp(1, 250);
-    let max_age_minutes = params.max_age_minutes.unwrap_or(30).clamp(1, 1440);
-
-    let cursor = params.cursor.as_deref().and_then(|s| {
-        chrono::DateTime::parse_from_rfc3339(s)
-            .ok()
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-    });
+) -> Result<Response, AppError> {
+    let limit = params.limit.unwrap_or(100).clamp(1, SPOTS_MAX_LIMIT);
+    let max_age_minutes = params
+        .max_age_minutes
+        .unwrap_or(30)
+        .clamp(1, SPOTS_MAX_AGE_MINUTES);
+    let window = spot_window(&params)?;
+
+    let grouping = SpotGrouping::parse(params.group_by.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
+    if window.is_some() && grouping == SpotGrouping::Callsign {
+        return Err(AppError::Validation {
+            message: "groupBy cannot be combined with from/to".to_string(),
+        });
+    }
+    // Grouped pages are keyed by a group's newest spot and its callsign, so
+    // the two modes' cursors are not interchangeable
+    let (cursor, group_cursor) = match grouping {
+        SpotGrouping::None => (cursor::timestamp_param(params.cursor.as_deref())?, None),
+        SpotGrouping::Callsign => (
+            None,
+            params
+                .cursor
+                .as_deref()
+                .map(Cursor::<(chrono::DateTime<chrono::Utc>, String)>::decode)
+                .transpose()?
+                .map(|cursor| cursor.0),
+        ),
+    };
+    let includes = SpotIncludes::parse(params.include.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
+    let fields = SpotFieldSelection::parse(params.fields.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
+    let units = SpotUnits::parse(params.units.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
+
+    let bbox = BoundingBox::from_bounds(
+        params.min_lat,
+        params.max_lat,
+        params.min_lon,
+        params.max_lon,
+    )
+    .map_err(|message| AppError::Validation { message })?;
+    let blocked = blocks.for_viewer(&pool, auth.as_deref()).await?;
 
     let db_params = db::spots::ListSpotsParams {
         program: params.program,
         callsign: params.callsign,
         source: params.source,
-        mode: params.mode,
+        mode: params.mode.as_deref().map(mode_filter),
         state: params.state,
         max_age_minutes,
         limit,
         cursor,
+        bbox,
+        hidden_callsigns: blocked.callsigns.clone(),
+        excluded_submitter: auth
+            .as_ref()
+            .filter(|_| params.exclude_self)
+            .map(|auth| auth.participant_id),
+    };
+
+    let to_response = |row| {
+        let mut spot = SpotResponse::from(row);
+        spot.redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+        spot.apply_units(units);
+        spot
     };
 
-    let rows = db::list_spots(&pool, &db_params).await?;
+    let (mut headers, stale_sources) = freshness_report(&state, Instant::now());
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=15"),
+    );
+
+    if grouping == SpotGrouping::Callsign {
+        let rows = db::list_spot_groups(&pool, &db_params, group_cursor.as_ref()).await?;
+        let mut groups = group_spot_rows(rows);
+
+        let has_more = groups.len() as i64 > limit;
+        groups.truncate(limit as usize);
+        let next_cursor = if has_more {
+            groups.last().map(|group| {
+                Cursor((group.newest.spotted_at, group.newest.callsign.clone())).encode()
+            })
+        } else {
+            None
+        };
+
+        let groups: Vec<_> = groups
+            .into_iter()
+            .map(|group| (to_response(group.newest), group.spot_count, group.others))
+            .collect();
+        let programs =
+            spot_programs(&pool, &includes, groups.iter().map(|(spot, ..)| spot)).await?;
+        let spots = groups
+            .into_iter()
+            .map(|(spot, spot_count, others)| SpotGroupResponse {
+                spot: SparseSpotResponse::new(spot, &fields),
+                spot_count,
+                other_spots: others.into_iter().map(Into::into).collect(),
+            })
+            .collect();
+
+        return Ok((
+            headers,
+            Json(DataResponse {
+                data: SpotsListResponse {
+                    spots,
+                    pagination: SpotsPagination {
+                        has_more,
+                        next_cursor,
+                    },
+                    programs,
+                    stale_sources,
+                },
+            }),
+        )
+            .into_response());
+    }
+
+    let rows = match window {
+        Some((from, to)) => db::list_spots_between(&pool, &db_params, from, to).await?,
+        None => db::list_spots(&pool, &db_params).await?,
+    };
 
     let has_more = rows.len() as i64 > limit;
     let truncated: Vec<_> = rows.into_iter().take(limit as usize).collect();
 
     let next_cursor = if has_more {
-        truncated.last().map(|row| row.spotted_at.to_rfc3339())
+        truncated.last().map(|row| Cursor(row.spotted_at).encode())
     } else {
         None
     };
+    let truncated = if config.pota_sota_merge_enabled {
+        merge_park_summit_spots(
+            truncated,
+            chrono::Duration::minutes(config.pota_sota_merge_window_mins),
+        )
+    } else {
+        truncated
+    };
+
+    let spots: Vec<SpotResponse> = truncated.into_iter().map(to_response).collect();
+    let programs = spot_programs(&pool, &includes, &spots).await?;
+    let spots = spots
+        .into_iter()
+        .map(|spot| SparseSpotResponse::new(spot, &fields))
+        .collect();
+
+    Ok((
+        headers,
+        Json(DataResponse {
+            data: SpotsListResponse {
+                spots,
+                pagination: SpotsPagination {
+                    has_more,
+                    next_cursor,
+                },
+                programs,
+                stale_sources,
+            },
+        }),
+    )
+        .into_response())
+}
+
+#[derive(serde::Deserialize)]
+pub struct ActivatorsQuery {
+    pub program: Option<String>,
+    pub mode: Option<String>,
+    pub band: Option<String>,
+}
+
+/// GET /v1/activators — who is on the air now, one entry per activator and
+/// reference rather than per spot. Redacted like the spot list.
+pub async fn list_activators(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Query(params): Query<ActivatorsQuery>,
+) -> Result<Json<DataResponse<Vec<ActivatorResponse>>>, AppError> {
+    let band = params
+        .band
+        .as_deref()
+        .map(band_range_khz)
+        .transpose()
+        .map_err(|message| AppError::Validation { message })?;
+
+    let db_params = db::spots::ListActivatorsParams {
+        program: params.program,
+        mode: params.mode.as_deref().map(mode_filter),
+        band,
+    };
+
+    let now = chrono::Utc::now();
+    let activators = db::list_activators(&pool, &db_params)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let mut activator = ActivatorResponse::from_row(row, now);
+            activator
+                .redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+            activator
+        })
+        .collect();
+
+    Ok(Json(DataResponse { data: activators }))
+}
+
+/// GET /v1/callsigns/:callsign/spots — where a callsign is on the air now:
+/// its unexpired spots across every program, newest first. Redacted like the
+/// spot list; empty when the viewer is blocked with the callsign.
+pub async fn list_callsign_spots(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Extension(blocks): Extension<BlockCache>,
+    Path(callsign): Path<String>,
+) -> Result<Json<DataResponse<Vec<SpotResponse>>>, AppError> {
+    let callsign = callsign.trim().to_uppercase();
+    let blocked = blocks.for_viewer(&pool, auth.as_deref()).await?;
+    if blocked.callsigns.contains(&callsign) {
+        return Ok(Json(DataResponse { data: Vec::new() }));
+    }
+
+    let spots = db::list_callsign_spots(&pool, &callsign)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let mut spot = SpotResponse::from(row);
+            spot.redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+            spot
+        })
+        .collect();
+
+    Ok(Json(DataResponse { data: spots }))
+}
+
+/// GET /v1/callsigns/:callsign/spot-summary — how a callsign is being heard
+/// right now: spot counts per source, distinct spotters, the best SNR, and
+/// the newest frequency and mode. Activators poll this while calling CQ, so
+/// clients may cache it for 15 seconds.
+pub async fn get_callsign_spot_summary(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Extension(blocks): Extension<BlockCache>,
+    Path(callsign): Path<String>,
+) -> Result<(HeaderMap, Json<DataResponse<CallsignSpotSummary>>), AppError> {
+    let callsign = callsign.trim().to_uppercase();
+    let blocked = blocks.for_viewer(&pool, auth.as_deref()).await?;
+    let rows = if blocked.callsigns.contains(&callsign) {
+        Vec::new()
+    } else {
+        db::get_callsign_spot_summary(&pool, &callsign).await?
+    };
+
+    let mut summary = CallsignSpotSummary::from_rows(callsign, rows);
+    summary.redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=15"),
+    );
+    Ok((headers, Json(DataResponse { data: summary })))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SpotHeatmapQuery {
+    pub program: Option<String>,
+    pub days: Option<i64>,
+}
+
+/// Longest window GET /v1/spots/heatmap accepts, in days.
+pub const HEATMAP_MAX_DAYS: i64 = 30;
+
+/// GET /v1/spots/heatmap — spot counts by band and UTC hour of day over the
+/// last `days` (default 7), e.g. to chart when 20m POTA is busiest.
+pub async fn spot_heatmap(
+    State(pool): State<PgPool>,
+    Query(params): Query<SpotHeatmapQuery>,
+) -> Result<Json<DataResponse<SpotHeatmapResponse>>, AppError> {
+    let days = params.days.unwrap_or(7);
+    if !(1..=HEATMAP_MAX_DAYS).contains(&days) {
+        return Err(AppError::Validation {
+            message: format!("days must be between 1 and {HEATMAP_MAX_DAYS}"),
+        });
+    }
+
+    let since = chrono::Utc::now() - chrono::Duration::days(days);
+    let cells = db::get_spot_heatmap(&pool, params.program.as_deref(), since).await?;
+
+    Ok(Json(DataResponse {
+        data: SpotHeatmapResponse::from_cells(params.program, days, since, cells),
+    }))
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SpotModesResponse {
+    pub modes: &'static [&'static str],
+}
+
+/// GET /v1/spots/modes — the canonical modes aggregated spots are stored
+/// with, for building mode filters.
+pub async fn spot_modes() -> Json<DataResponse<SpotModesResponse>> {
+    Json(DataResponse {
+        data: SpotModesResponse {
+            modes: CANONICAL_MODES,
+        },
+    })
+}
+
+#[derive(serde::Deserialize)]
+pub struct SpotsDeltaQuery {
+    pub since: Option<String>,
+}
+
+/// Seconds the next delta cursor is rewound so rows from transactions still
+/// in flight at snapshot time are picked up by the following poll.
+const DELTA_CURSOR_OVERLAP_SECS: i64 = 5;
 
-    let spots: Vec<SpotResponse> = truncated.into_iter().map(Into::into).collect();
+/// GET /v1/spots/delta — spots added or updated and IDs deleted since the
+/// `since` cursor. Without `since`, returns every active spot as a baseline.
+/// Overlapping polls may repeat a spot; clients upsert by ID.
+pub async fn spots_delta(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Query(params): Query<SpotsDeltaQuery>,
+) -> Result<Json<DataResponse<SpotsDeltaResponse>>, AppError> {
+    let since = cursor::timestamp_param(params.since.as_deref())?;
+    let oldest = chrono::Utc::now() - chrono::Duration::hours(db::SPOT_TOMBSTONE_RETENTION_HOURS);
+    if since.is_some_and(|since| since < oldest) {
+        return Err(AppError::CursorExpired);
+    }
+
+    let delta = db::get_spots_delta(&pool, since).await?;
+
+    let spots = delta
+        .spots
+        .into_iter()
+        .map(|row| {
+            let mut spot = SpotResponse::from(row);
+            spot.redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+            spot
+        })
+        .collect();
+    let next_cursor =
+        Cursor(delta.as_of - chrono::Duration::seconds(DELTA_CURSOR_OVERLAP_SECS)).encode();
 
     Ok(Json(DataResponse {
-        data: SpotsListResponse {
+        data: SpotsDeltaResponse {
             spots,
-            pagination: SpotsPagination {
-                has_more,
-                next_cursor,
-            },
+            deleted: delta.deleted,
+            next_cursor,
         },
     }))
 }
 
-/// POST /v1/spots — create a self-spot (auth required).
+/// GET /v1/spots/:id — get a single spot, redacted like the list.
+pub async fn get_spot(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Path(spot_id): Path<uuid::Uuid>,
+    Query(params): Query<SpotIncludeQuery>,
+) -> Result<Json<DataResponse<SpotDetailResponse>>, AppError> {
+    let includes = SpotIncludes::parse(params.include.as_deref())
+        .map_err(|message| AppError::Validation { message })?;
+    let row = db::get_spot(&pool, spot_id)
+        .await?
+        .ok_or(AppError::SpotNotFound { spot_id })?;
+
+    let mut spot = SpotResponse::from(row);
+    spot.redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+    let programs = spot_programs(&pool, &includes, [&spot]).await?;
+
+    Ok(Json(DataResponse {
+        data: SpotDetailResponse { spot, programs },
+    }))
+}
+
+/// Most IDs POST /v1/spots/lookup accepts in one request.
+pub const LOOKUP_MAX_IDS: usize = 100;
+
+/// POST /v1/spots/lookup — fetch several spots by ID in one call, e.g. after
+/// a batch of spot-watch notifications. Expired spots still stored are
+/// returned flagged; IDs with no spot are listed in `missingIds`.
+pub async fn lookup_spots(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Json(req): Json<SpotLookupRequest>,
+) -> Result<Json<DataResponse<SpotLookupResponse>>, AppError> {
+    if req.ids.is_empty() || req.ids.len() > LOOKUP_MAX_IDS {
+        return Err(AppError::Validation {
+            message: format!("ids must list between 1 and {LOOKUP_MAX_IDS} spot IDs"),
+        });
+    }
+
+    let rows = db::get_spots_by_ids(&pool, &req.ids).await?;
+    let mut lookup = SpotLookupResponse::build(&req.ids, rows, chrono::Utc::now());
+    for entry in &mut lookup.spots {
+        entry
+            .spot
+            .redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+    }
+
+    Ok(Json(DataResponse { data: lookup }))
+}
+
+/// Most callsigns POST /v1/callsigns/status takes, enough for a full friends
+/// list at the default `MAX_FRIENDS`.
+pub const STATUS_MAX_CALLSIGNS: usize = 500;
+
+/// POST /v1/callsigns/status — whether each of several callsigns is on the
+/// air, and where, from their newest unexpired spot. Lets the feed badge
+/// friends who are active in one call. Redacted like the spot list; callsigns
+/// the viewer is blocked with read as off the air.
+pub async fn callsign_statuses(
+    State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(config): Extension<Config>,
+    Extension(blocks): Extension<BlockCache>,
+    Json(req): Json<CallsignStatusRequest>,
+) -> Result<Json<DataResponse<Vec<CallsignStatus>>>, AppError> {
+    if req.callsigns.is_empty() || req.callsigns.len() > STATUS_MAX_CALLSIGNS {
+        return Err(AppError::Validation {
+            message: format!("callsigns must list between 1 and {STATUS_MAX_CALLSIGNS} callsigns"),
+        });
+    }
+
+    let blocked = blocks.for_viewer(&pool, auth.as_deref()).await?;
+    let visible: Vec<String> = req
+        .callsigns
+        .iter()
+        .map(|callsign| callsign.trim().to_uppercase())
+        .filter(|callsign| !blocked.callsigns.contains(callsign))
+        .collect();
+    let rows = db::get_newest_spots_for_callsigns(&pool, &visible).await?;
+
+    let mut statuses = CallsignStatus::build(&req.callsigns, rows);
+    for status in &mut statuses {
+        status.redact_unless_authenticated(auth.is_some(), &config.public_spot_redacted_fields);
+    }
+
+    Ok(Json(DataResponse { data: statuses }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SelfSpotQuery {
+    pub normalize: Option<bool>,
+}
+
+/// POST /v1/spots — create a self-spot (auth required). FT8/FT4 and FM
+/// frequencies are snapped to the nearest standard channel unless
+/// `normalize=false`.
 pub async fn create_self_spot(
     State(pool): State<PgPool>,
     Extension(auth): Extension<AuthContext>,
+    Extension(config): Extension<Config>,
+    Query(params): Query<SelfSpotQuery>,
     Json(req): Json<CreateSelfSpotRequest>,
-) -> Result<(StatusCode, Json<DataResponse<SpotResponse>>), AppError> {
+) -> Result<(StatusCode, Json<DataResponse<SelfSpotResponse>>), AppError> {
     // Verify program exists and has selfSpot capability
     let program =
         db::get_program(&pool, &req.program_slug)
@@ -97,23 +687,53 @@ pub async fn create_self_spot(
         });
     }
 
+    let comments = req
+        .comments
+        .as_deref()
+        .and_then(|c| sanitize_comment(c, config.spot_comment_max_len));
+
+    let country_code = req.reference.as_deref().and_then(|reference| {
+        config
+            .reference_country_patterns
+            .country_for(&req.program_slug, reference)
+    });
+
+    let mode = normalize_mode(&req.mode);
+    let frequency_khz = if params.normalize.unwrap_or(true) {
+        snap_to_channel(mode, req.frequency_khz)
+    } else {
+        req.frequency_khz
+    };
+
+    let user = db::get_or_create_user(&pool, &auth.callsign).await?;
+
     let spot = db::insert_self_spot(
         &pool,
         &db::spots::InsertSelfSpotParams {
             participant_id: auth.participant_id,
+            user_id: user.id,
             callsign: &auth.callsign,
             program_slug: &req.program_slug,
-            frequency_khz: req.frequency_khz,
-            mode: &req.mode,
+            frequency_khz,
+            mode,
+            raw_mode: Some(&req.mode),
             reference: req.reference.as_deref(),
-            comments: req.comments.as_deref(),
+            country_code: country_code.as_deref(),
+            comments: comments.as_deref(),
+            ingest_channel: IngestChannel::App,
         },
     )
     .await?;
 
     Ok((
         StatusCode::CREATED,
-        Json(DataResponse { data: spot.into() }),
+        Json(DataResponse {
+            data: SelfSpotResponse {
+                spot: spot.into(),
+                submitted_frequency_khz: req.frequency_khz,
+                normalized_frequency_khz: frequency_khz,
+            },
+        }),
     ))
 }
 
@@ -132,6 +752,19 @@ pub async fn delete_own_spot(
     }
 }
 
+/// DELETE /v1/spots/mine — delete all of the caller's unexpired self-spots at
+/// once, e.g. after a multi-reference activation (auth required).
+pub async fn delete_all_own_spots(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<DataResponse<DeletedSpotsResponse>>, AppError> {
+    let deleted = db::delete_own_spots_all(&pool, auth.participant_id).await?;
+
+    Ok(Json(DataResponse {
+        data: DeletedSpotsResponse { deleted },
+    }))
+}
+
 /// DELETE /v1/admin/spots/:id — admin delete any spot.
 pub async fn admin_delete_spot(
     State(pool): State<PgPool>,
@@ -145,3 +778,202 @@ pub async fn admin_delete_spot(
         Err(AppError::SpotNotFound { spot_id })
     }
 }
+
+/// POST /v1/spots/:id/report — flag a spot for moderator review (auth required).
+pub async fn report_spot(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthContext>,
+    Path(spot_id): Path<uuid::Uuid>,
+    Json(req): Json<ReportSpotRequest>,
+) -> Result<StatusCode, AppError> {
+    let reason = req.reason.trim();
+    if reason.is_empty() || reason.chars().count() > 500 {
+        return Err(AppError::Validation {
+            message: "reason must be 1-500 characters".to_string(),
+        });
+    }
+
+    let recorded = db::insert_spot_report(&pool, spot_id, &auth.callsign, reason).await?;
+
+    if recorded {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::SpotNotFound { spot_id })
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct SpotReportsQuery {
+    pub limit: Option<i64>,
+}
+
+/// GET /v1/admin/spots/reports — moderation queue of reported spots.
+pub async fn admin_list_spot_reports(
+    State(pool): State<PgPool>,
+    Query(params): Query<SpotReportsQuery>,
+) -> Result<Json<DataResponse<Vec<ReportedSpotResponse>>>, AppError> {
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+
+    let spots = db::list_reported_spots(&pool, limit).await?;
+    let spot_ids: Vec<uuid::Uuid> = spots.iter().map(|s| s.spot.id).collect();
+    let reports = db::get_reports_for_spots(&pool, &spot_ids).await?;
+
+    Ok(Json(DataResponse {
+        data: build_report_queue(spots, reports, chrono::Utc::now()),
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SpotsArchiveStatsQuery {
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Window GET /v1/admin/spots/archive/stats covers when `from` is omitted.
+const ARCHIVE_STATS_DEFAULT_DAYS: i64 = 30;
+
+/// GET /v1/admin/spots/archive/stats — archived spots spotted in
+/// `[from, to)` by source and program. `to` defaults to now and `from` to 30
+/// days before `to`.
+pub async fn admin_spots_archive_stats(
+    State(pool): State<PgPool>,
+    Query(params): Query<SpotsArchiveStatsQuery>,
+) -> Result<Json<DataResponse<SpotsArchiveStatsResponse>>, AppError> {
+    let to = params.to.unwrap_or_else(chrono::Utc::now);
+    let from = params
+        .from
+        .unwrap_or(to - chrono::Duration::days(ARCHIVE_STATS_DEFAULT_DAYS));
+    if from >= to {
+        return Err(AppError::Validation {
+            message: "from must be before to".to_string(),
+        });
+    }
+
+    let counts = db::get_spots_archive_counts(&pool, from, to).await?;
+
+    Ok(Json(DataResponse {
+        data: SpotsArchiveStatsResponse::from_counts(from, to, counts),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use http_body_util::BodyExt;
+
+    #[test]
+    fn freshness_report_covers_fetched_sources() {
+        let state = ServerState::default();
+        let (headers, stale) = freshness_report(&state, Instant::now());
+        assert!(headers.is_empty());
+        assert!(stale.is_empty());
+
+        state.record_fetch("pota", true);
+        state.record_fetch("rbn", false);
+        let pota = state.freshness("pota").unwrap().last_success.unwrap();
+        let rbn_since = state.freshness("rbn").unwrap().tracked_since;
+
+        // Two minutes on, neither is three intervals behind; RBN has no age yet
+        let now = pota.max(rbn_since) + Duration::from_secs(120);
+        let (headers, stale) = freshness_report(&state, now);
+        assert_eq!(
+            headers["x-aggregator-pota-age"],
+            (now - pota).as_secs().to_string()
+        );
+        assert!(!headers.contains_key("x-aggregator-rbn-age"));
+        assert!(!headers.contains_key("x-aggregator-sota-age"));
+        assert!(stale.is_empty());
+
+        // Four minutes on, both are
+        let now = now + Duration::from_secs(120);
+        let (_, stale) = freshness_report(&state, now);
+        assert_eq!(stale, ["pota", "rbn"]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn usb_self_spots_are_stored_and_filtered_as_ssb() {
+        let pool = test_support::pool().await;
+        let callsign = test_support::callsign();
+        let (participant, _) = db::get_or_create_participant(&pool, &callsign, None)
+            .await
+            .unwrap();
+        let config = Config::from_lookup(|var| match var {
+            "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+            "ADMIN_TOKEN" => Some("admin".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        let (_, Json(created)) = create_self_spot(
+            State(pool.clone()),
+            Extension(AuthContext {
+                callsign: callsign.clone(),
+                participant_id: participant.id,
+            }),
+            Extension(config.clone()),
+            Query(SelfSpotQuery { normalize: None }),
+            Json(CreateSelfSpotRequest {
+                program_slug: "pota".to_string(),
+                frequency_khz: 14250.0,
+                mode: "usb".to_string(),
+                reference: None,
+                comments: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let raw_mode: Option<String> =
+            sqlx::query_scalar("SELECT raw_mode FROM spots WHERE id = $1")
+                .bind(created.data.spot.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let list = |query: String| {
+            list_spots(
+                State(pool.clone()),
+                None,
+                Extension(config.clone()),
+                Extension(BlockCache::new(Duration::from_secs(60))),
+                Extension(ServerState::default()),
+                Query(serde_urlencoded::from_str(&query).unwrap()),
+            )
+        };
+        let listed = |response: Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            body["data"]["spots"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|spot| spot["mode"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let usb = listed(list(format!("callsign={callsign}&mode=usb")).await.unwrap()).await;
+        let ssb = listed(list(format!("callsign={callsign}&mode=SSB")).await.unwrap()).await;
+        let cw = listed(list(format!("callsign={callsign}&mode=cw")).await.unwrap()).await;
+
+        sqlx::query("DELETE FROM spots WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM users WHERE callsign = $1")
+            .bind(&callsign)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM participants WHERE id = $1")
+            .bind(participant.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(created.data.spot.mode, "SSB");
+        assert_eq!(raw_mode.as_deref(), Some("usb"));
+        assert_eq!(usb, ["SSB"]);
+        assert_eq!(ssb, ["SSB"]);
+        assert!(cw.is_empty());
+    }
+}
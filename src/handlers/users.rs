@@ -2,6 +2,7 @@ use axum::extract::{Query, State};
 use serde::Deserialize;
 use sqlx::PgPool;
 
+use crate::blocks::BlockCache;
 use crate::db;
 use crate::error::AppError;
 use crate::extractors::Json;
@@ -15,16 +16,20 @@ pub struct SearchUsersQuery {
 }
 
 /// GET /v1/users/search?q=...
-/// Search for users by callsign (public, no auth required)
+/// Search for users by callsign (public, no auth required). Authenticated
+/// callers don't see users they are blocked with.
 pub async fn search_users(
     State(pool): State<PgPool>,
+    auth: Option<Extension<AuthContext>>,
+    Extension(blocks): Extension<BlockCache>,
     Query(query): Query<SearchUsersQuery>,
 ) -> Result<Json<DataResponse<Vec<UserSearchResponse>>>, AppError> {
     if query.q.len() < 2 {
         return Ok(Json(DataResponse { data: vec![] }));
     }
 
-    let users = db::search_users(&pool, &query.q, 20).await?;
+    let blocked = blocks.for_viewer(&pool, auth.as_deref()).await?;
+    let users = db::search_users(&pool, &query.q, &blocked.ids, 20).await?;
 
     let results: Vec<UserSearchResponse> = users.into_iter().map(|u| u.into()).collect();
 
@@ -32,7 +37,12 @@ pub async fn search_users(
 }
 
 use crate::auth::AuthContext;
-use crate::models::{AdminStatsResponse, RegisterRequest, RegisterResponse, UserCountByHour};
+use crate::extractors::Path;
+use crate::models::{
+    check_merge, AdminStatsResponse, AdminUserResponse, MergeUsersQuery, MergeUsersRequest,
+    MergeUsersResponse, RegisterRequest, RegisterResponse, UserCountByHour, UserStatusFilter,
+};
+use uuid::Uuid;
 use axum::http::StatusCode;
 use axum::Extension;
 use serde::Serialize;
@@ -231,3 +241,100 @@ pub async fn claim_previous_account(
         },
     }))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct AdminUsersQuery {
+    pub status: Option<UserStatusFilter>,
+    pub callsign: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// GET /v1/admin/users?status=suspended|active|all&callsign=... — list users (admin only)
+pub async fn admin_list_users(
+    State(pool): State<PgPool>,
+    Query(query): Query<AdminUsersQuery>,
+) -> Result<Json<DataResponse<Vec<AdminUserResponse>>>, AppError> {
+    let status = query.status.unwrap_or(UserStatusFilter::All);
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let callsign = query
+        .callsign
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+
+    let users = db::list_users_admin(&pool, status.as_suspended(), callsign, limit, offset).await?;
+
+    Ok(Json(DataResponse {
+        data: users.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// POST /v1/admin/users/:id/suspend — hide a user and reject their tokens (admin only)
+pub async fn suspend_user(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<DataResponse<AdminUserResponse>>, AppError> {
+    let user = db::set_user_suspended(&pool, user_id, true)
+        .await?
+        .ok_or(AppError::UserNotFound { user_id })?;
+
+    tracing::info!(%user_id, callsign = %user.callsign, "user suspended");
+
+    Ok(Json(DataResponse { data: user.into() }))
+}
+
+/// POST /v1/admin/users/:id/unsuspend — restore a suspended user (admin only)
+pub async fn unsuspend_user(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<DataResponse<AdminUserResponse>>, AppError> {
+    let user = db::set_user_suspended(&pool, user_id, false)
+        .await?
+        .ok_or(AppError::UserNotFound { user_id })?;
+
+    tracing::info!(%user_id, callsign = %user.callsign, "user unsuspended");
+
+    Ok(Json(DataResponse { data: user.into() }))
+}
+
+/// POST /v1/admin/users/merge?dryRun=true — merge one account into another (admin only)
+pub async fn merge_users(
+    State(pool): State<PgPool>,
+    Query(query): Query<MergeUsersQuery>,
+    Json(body): Json<MergeUsersRequest>,
+) -> Result<Json<DataResponse<MergeUsersResponse>>, AppError> {
+    let from = db::get_admin_user(&pool, body.from)
+        .await?
+        .ok_or(AppError::UserNotFound { user_id: body.from })?;
+    let to = db::get_admin_user(&pool, body.to)
+        .await?
+        .ok_or(AppError::UserNotFound { user_id: body.to })?;
+    check_merge(&from, &to).map_err(|message| AppError::Validation { message })?;
+
+    let tables = db::merge_users(&pool, &from, &to, query.dry_run).await?;
+
+    // Re-read after a real merge so the response shows the source suspended
+    let from = if query.dry_run {
+        from
+    } else {
+        tracing::info!(
+            from = %from.callsign,
+            to = %to.callsign,
+            tables = ?tables,
+            "users merged"
+        );
+        db::get_admin_user(&pool, from.id).await?.unwrap_or(from)
+    };
+
+    Ok(Json(DataResponse {
+        data: MergeUsersResponse {
+            from: from.into(),
+            to: to.into(),
+            dry_run: query.dry_run,
+            tables,
+        },
+    }))
+}
@@ -0,0 +1,386 @@
+//! Outbound HTTP for aggregators and integrations.
+//!
+//! [`HttpClient`] sends requests through a [`Transport`] (reqwest in
+//! production, a scripted mock in tests) and applies a [`RetryPolicy`] per
+//! request: connection failures, timeouts, and 5xx responses are retried with
+//! capped exponential backoff; 4xx responses are returned immediately. Each
+//! attempt is recorded in `http_client_*` metrics labelled by the named
+//! upstream, e.g. `pota_spots`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+
+use crate::metrics as app_metrics;
+
+/// A boxed future returned by [`Transport::send`].
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<HttpResponse, TransportError>> + Send + 'a>>;
+
+/// Sends one request attempt. Implemented by [`ReqwestTransport`] and by the
+/// mock in tests.
+pub trait Transport: Send + Sync {
+    fn send<'a>(&'a self, url: &'a str, timeout: Duration) -> TransportFuture<'a>;
+}
+
+/// A completed response with its body read into memory.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// Why an attempt produced no response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportError {
+    Connect(String),
+    Timeout,
+    Other(String),
+}
+
+impl TransportError {
+    fn is_retryable(&self) -> bool {
+        matches!(self, Self::Connect(_) | Self::Timeout)
+    }
+
+    fn outcome(&self) -> &'static str {
+        match self {
+            Self::Connect(_) => "connect_error",
+            Self::Timeout => "timeout",
+            Self::Other(_) => "error",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    #[error("{upstream}: connection failed: {message}")]
+    Connect {
+        upstream: &'static str,
+        message: String,
+    },
+    #[error("{upstream}: request timed out")]
+    Timeout { upstream: &'static str },
+    #[error("{upstream}: request failed: {message}")]
+    Request {
+        upstream: &'static str,
+        message: String,
+    },
+    #[error("{upstream}: HTTP {status}")]
+    Status { upstream: &'static str, status: u16 },
+    #[error("{upstream}: invalid response body: {message}")]
+    Decode {
+        upstream: &'static str,
+        message: String,
+    },
+}
+
+impl HttpError {
+    fn from_transport(upstream: &'static str, e: TransportError) -> Self {
+        match e {
+            TransportError::Connect(message) => Self::Connect { upstream, message },
+            TransportError::Timeout => Self::Timeout { upstream },
+            TransportError::Other(message) => Self::Request { upstream, message },
+        }
+    }
+}
+
+/// Per-request timeout and retry settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Timeout for each attempt, including reading the body.
+    pub timeout: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (1-based): doubles from
+    /// `initial_backoff`, capped at `max_backoff`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Shared outbound client; cheap to clone.
+#[derive(Clone)]
+pub struct HttpClient {
+    transport: Arc<dyn Transport>,
+}
+
+impl HttpClient {
+    /// A reqwest-backed client identifying itself as this server.
+    pub fn new() -> Self {
+        Self::try_new().expect("failed to build HTTP client")
+    }
+
+    /// Like [`HttpClient::new`], but returns the error when the underlying
+    /// client can't be built (e.g. no TLS backend).
+    pub fn try_new() -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .user_agent(format!(
+                "{}/{}",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()?;
+        Ok(Self::with_transport(Arc::new(ReqwestTransport(client))))
+    }
+
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Self {
+        Self { transport }
+    }
+
+    /// GET `url`, retrying per `policy`. Non-2xx responses that aren't
+    /// retried (or are still failing after the last attempt) are errors.
+    pub async fn get(
+        &self,
+        upstream: &'static str,
+        url: &str,
+        policy: &RetryPolicy,
+    ) -> Result<HttpResponse, HttpError> {
+        let mut attempt = 1;
+        loop {
+            let started = Instant::now();
+            let result = self.transport.send(url, policy.timeout).await;
+            let (outcome, retryable) = match &result {
+                Ok(response) if (200..300).contains(&response.status) => ("success", false),
+                Ok(response) if response.status >= 500 => ("server_error", true),
+                Ok(_) => ("client_error", false),
+                Err(e) => (e.outcome(), e.is_retryable()),
+            };
+            metrics::counter!(app_metrics::HTTP_CLIENT_REQUESTS_TOTAL, "upstream" => upstream, "outcome" => outcome)
+                .increment(1);
+            metrics::histogram!(app_metrics::HTTP_CLIENT_REQUEST_DURATION_SECONDS, "upstream" => upstream)
+                .record(started.elapsed().as_secs_f64());
+
+            if retryable && attempt < policy.max_attempts {
+                let delay = policy.backoff(attempt);
+                tracing::debug!(
+                    upstream,
+                    attempt,
+                    outcome,
+                    ?delay,
+                    "Retrying upstream request"
+                );
+                metrics::counter!(app_metrics::HTTP_CLIENT_RETRIES_TOTAL, "upstream" => upstream)
+                    .increment(1);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+
+            return match result {
+                Ok(response) if outcome == "success" => Ok(response),
+                Ok(response) => Err(HttpError::Status {
+                    upstream,
+                    status: response.status,
+                }),
+                Err(e) => Err(HttpError::from_transport(upstream, e)),
+            };
+        }
+    }
+
+    /// GET `url` and decode the body as JSON.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        upstream: &'static str,
+        url: &str,
+        policy: &RetryPolicy,
+    ) -> Result<T, HttpError> {
+        let response = self.get(upstream, url, policy).await?;
+        serde_json::from_slice(&response.body).map_err(|e| HttpError::Decode {
+            upstream,
+            message: e.to_string(),
+        })
+    }
+}
+
+/// The production [`Transport`].
+pub struct ReqwestTransport(pub reqwest::Client);
+
+impl Transport for ReqwestTransport {
+    fn send<'a>(&'a self, url: &'a str, timeout: Duration) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let classify = |e: reqwest::Error| {
+                if e.is_timeout() {
+                    TransportError::Timeout
+                } else if e.is_connect() {
+                    TransportError::Connect(e.to_string())
+                } else {
+                    TransportError::Other(e.to_string())
+                }
+            };
+            let response = self
+                .0
+                .get(url)
+                .timeout(timeout)
+                .send()
+                .await
+                .map_err(classify)?;
+            let status = response.status().as_u16();
+            let body = response.bytes().await.map_err(classify)?;
+            Ok(HttpResponse {
+                status,
+                body: body.to_vec(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Replays scripted results in order and records requested URLs.
+    #[derive(Default)]
+    pub struct MockTransport {
+        results: Mutex<VecDeque<Result<HttpResponse, TransportError>>>,
+        pub urls: Mutex<Vec<String>>,
+    }
+
+    impl MockTransport {
+        pub fn new(
+            results: impl IntoIterator<Item = Result<HttpResponse, TransportError>>,
+        ) -> Self {
+            Self {
+                results: Mutex::new(results.into_iter().collect()),
+                urls: Mutex::default(),
+            }
+        }
+
+        pub fn status(status: u16) -> Result<HttpResponse, TransportError> {
+            Self::body(status, "")
+        }
+
+        pub fn body(status: u16, body: &str) -> Result<HttpResponse, TransportError> {
+            Ok(HttpResponse {
+                status,
+                body: body.as_bytes().to_vec(),
+            })
+        }
+
+        pub fn calls(&self) -> usize {
+            self.urls.lock().unwrap().len()
+        }
+    }
+
+    impl Transport for MockTransport {
+        fn send<'a>(&'a self, url: &'a str, _timeout: Duration) -> TransportFuture<'a> {
+            self.urls.lock().unwrap().push(url.to_string());
+            let result = self
+                .results
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("mock transport ran out of scripted results");
+            Box::pin(async move { result })
+        }
+    }
+
+    /// A client over `transport`, keeping a handle for assertions.
+    pub fn client(transport: MockTransport) -> (HttpClient, Arc<MockTransport>) {
+        let transport = Arc::new(transport);
+        (HttpClient::with_transport(transport.clone()), transport)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{client, MockTransport};
+    use super::*;
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4), Duration::from_secs(8));
+        assert_eq!(policy.backoff(5), Duration::from_secs(10));
+        assert_eq!(policy.backoff(40), Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_errors_retry_until_success() {
+        let (http, transport) = client(MockTransport::new([
+            MockTransport::status(503),
+            MockTransport::status(502),
+            MockTransport::body(200, "[1,2]"),
+        ]));
+        let body: Vec<i32> = http
+            .get_json("test", "http://x/", &policy(3))
+            .await
+            .unwrap();
+        assert_eq!(body, [1, 2]);
+        assert_eq!(transport.calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_stop_at_max_attempts() {
+        let (http, transport) = client(MockTransport::new([
+            MockTransport::status(500),
+            Err(TransportError::Connect("refused".into())),
+            Err(TransportError::Timeout),
+        ]));
+        let err = http.get("test", "http://x/", &policy(3)).await.unwrap_err();
+        assert!(matches!(err, HttpError::Timeout { upstream: "test" }));
+        assert_eq!(transport.calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn not_found_is_not_retried() {
+        let (http, transport) = client(MockTransport::new([MockTransport::status(404)]));
+        let err = http.get("test", "http://x/", &policy(3)).await.unwrap_err();
+        assert!(matches!(err, HttpError::Status { status: 404, .. }));
+        assert_eq!(transport.calls(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn other_transport_errors_are_not_retried() {
+        let (http, transport) = client(MockTransport::new([Err(TransportError::Other(
+            "bad redirect".into(),
+        ))]));
+        let err = http.get("test", "http://x/", &policy(3)).await.unwrap_err();
+        assert!(matches!(err, HttpError::Request { .. }));
+        assert_eq!(transport.calls(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn undecodable_body_is_decode_error() {
+        let (http, _) = client(MockTransport::new([MockTransport::body(200, "<html>")]));
+        let err = http
+            .get_json::<Vec<i32>>("test", "http://x/", &policy(3))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::Decode { .. }));
+    }
+}
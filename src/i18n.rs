@@ -0,0 +1,247 @@
+//! Translations for server-rendered HTML pages (invite links opened in a browser).
+//!
+//! Strings live in one static table per locale. Placeholders such as
+//! `{callsign}` and `{challenge}` are substituted by the caller.
+
+/// Locales with translated page strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+    Es,
+    Fr,
+    Ja,
+}
+
+impl Locale {
+    /// BCP 47 tag used for `<html lang>`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::De => "de",
+            Self::Es => "es",
+            Self::Fr => "fr",
+            Self::Ja => "ja",
+        }
+    }
+
+    /// Match a language tag (`de`, `de-AT`, `DE_de`) on its primary subtag.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Self::En),
+            "de" => Some(Self::De),
+            "es" => Some(Self::Es),
+            "fr" => Some(Self::Fr),
+            "ja" => Some(Self::Ja),
+            _ => None,
+        }
+    }
+
+    /// Pick a locale: a supported `?lang=` override wins, then the highest-q
+    /// supported entry in `Accept-Language`, then English.
+    pub fn negotiate(lang_override: Option<&str>, accept_language: Option<&str>) -> Self {
+        if let Some(locale) = lang_override.and_then(Self::from_tag) {
+            return locale;
+        }
+
+        accept_language
+            .map(parse_accept_language)
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|tag| Self::from_tag(&tag))
+            .unwrap_or(Self::En)
+    }
+
+    pub fn strings(self) -> &'static PageStrings {
+        match self {
+            Self::En => &EN,
+            Self::De => &DE,
+            Self::Es => &ES,
+            Self::Fr => &FR,
+            Self::Ja => &JA,
+        }
+    }
+}
+
+/// Language tags from an `Accept-Language` header, most preferred first.
+/// Entries with `q=0` or an unparseable q-value are dropped; ties keep header order.
+fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut entries: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim();
+            if tag.is_empty() || tag == "*" {
+                return None;
+            }
+            let mut q = 1.0;
+            for param in parts {
+                if let Some(value) = param.trim().strip_prefix("q=") {
+                    q = value.trim().parse().ok()?;
+                }
+            }
+            (q > 0.0).then(|| (tag.to_string(), q))
+        })
+        .collect();
+
+    entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+    entries.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Strings for the friend and challenge invite pages.
+pub struct PageStrings {
+    pub friend_title: &'static str,
+    pub friend_heading: &'static str,
+    pub friend_description: &'static str,
+    pub friend_title_generic: &'static str,
+    pub friend_description_generic: &'static str,
+    pub challenge_title: &'static str,
+    pub challenge_heading: &'static str,
+    pub challenge_description: &'static str,
+    pub challenge_title_generic: &'static str,
+    pub challenge_description_generic: &'static str,
+    pub invited_heading: &'static str,
+    pub open_button: &'static str,
+    pub footer: &'static str,
+}
+
+static EN: PageStrings = PageStrings {
+    friend_title: "{callsign} wants to be friends on Carrier Wave",
+    friend_heading: "{callsign} wants to be friends!",
+    friend_description: "Open this link in Carrier Wave to add {callsign} as a friend.",
+    friend_title_generic: "Friend invite on Carrier Wave",
+    friend_description_generic: "Open this link in Carrier Wave to accept this friend invite.",
+    challenge_title: "Join {challenge} on Carrier Wave",
+    challenge_heading: "You're invited to {challenge}!",
+    challenge_description: "Open this link in Carrier Wave to join the challenge.",
+    challenge_title_generic: "Challenge invite on Carrier Wave",
+    challenge_description_generic: "Open this link in Carrier Wave to join a challenge.",
+    invited_heading: "You've been invited!",
+    open_button: "Open in Carrier Wave",
+    footer: "Carrier Wave &mdash; Ham Radio Challenges",
+};
+
+static DE: PageStrings = PageStrings {
+    friend_title: "{callsign} möchte auf Carrier Wave mit dir befreundet sein",
+    friend_heading: "{callsign} möchte mit dir befreundet sein!",
+    friend_description: "Öffne diesen Link in Carrier Wave, um {callsign} als Freund hinzuzufügen.",
+    friend_title_generic: "Freundschaftseinladung auf Carrier Wave",
+    friend_description_generic:
+        "Öffne diesen Link in Carrier Wave, um die Freundschaftseinladung anzunehmen.",
+    challenge_title: "Tritt {challenge} auf Carrier Wave bei",
+    challenge_heading: "Du bist zu {challenge} eingeladen!",
+    challenge_description: "Öffne diesen Link in Carrier Wave, um an der Challenge teilzunehmen.",
+    challenge_title_generic: "Challenge-Einladung auf Carrier Wave",
+    challenge_description_generic:
+        "Öffne diesen Link in Carrier Wave, um an einer Challenge teilzunehmen.",
+    invited_heading: "Du wurdest eingeladen!",
+    open_button: "In Carrier Wave öffnen",
+    footer: "Carrier Wave &mdash; Amateurfunk-Challenges",
+};
+
+static ES: PageStrings = PageStrings {
+    friend_title: "{callsign} quiere ser tu amigo en Carrier Wave",
+    friend_heading: "¡{callsign} quiere ser tu amigo!",
+    friend_description: "Abre este enlace en Carrier Wave para añadir a {callsign} como amigo.",
+    friend_title_generic: "Invitación de amistad en Carrier Wave",
+    friend_description_generic:
+        "Abre este enlace en Carrier Wave para aceptar esta invitación de amistad.",
+    challenge_title: "Únete a {challenge} en Carrier Wave",
+    challenge_heading: "¡Estás invitado a {challenge}!",
+    challenge_description: "Abre este enlace en Carrier Wave para unirte al desafío.",
+    challenge_title_generic: "Invitación a un desafío en Carrier Wave",
+    challenge_description_generic: "Abre este enlace en Carrier Wave para unirte a un desafío.",
+    invited_heading: "¡Has recibido una invitación!",
+    open_button: "Abrir en Carrier Wave",
+    footer: "Carrier Wave &mdash; Desafíos de radioaficionados",
+};
+
+static FR: PageStrings = PageStrings {
+    friend_title: "{callsign} souhaite devenir votre ami sur Carrier Wave",
+    friend_heading: "{callsign} souhaite devenir votre ami !",
+    friend_description: "Ouvrez ce lien dans Carrier Wave pour ajouter {callsign} à vos amis.",
+    friend_title_generic: "Invitation d'ami sur Carrier Wave",
+    friend_description_generic: "Ouvrez ce lien dans Carrier Wave pour accepter cette invitation.",
+    challenge_title: "Rejoignez {challenge} sur Carrier Wave",
+    challenge_heading: "Vous êtes invité à {challenge} !",
+    challenge_description: "Ouvrez ce lien dans Carrier Wave pour rejoindre le défi.",
+    challenge_title_generic: "Invitation à un défi sur Carrier Wave",
+    challenge_description_generic: "Ouvrez ce lien dans Carrier Wave pour rejoindre un défi.",
+    invited_heading: "Vous avez été invité !",
+    open_button: "Ouvrir dans Carrier Wave",
+    footer: "Carrier Wave &mdash; Défis radioamateurs",
+};
+
+static JA: PageStrings = PageStrings {
+    friend_title: "{callsign}さんがCarrier Waveで友達になりたがっています",
+    friend_heading: "{callsign}さんから友達申請が届きました！",
+    friend_description: "Carrier Waveでこのリンクを開いて、{callsign}さんを友達に追加しましょう。",
+    friend_title_generic: "Carrier Waveの友達招待",
+    friend_description_generic: "Carrier Waveでこのリンクを開いて、友達招待を承認しましょう。",
+    challenge_title: "Carrier Waveで{challenge}に参加しよう",
+    challenge_heading: "{challenge}に招待されました！",
+    challenge_description: "Carrier Waveでこのリンクを開いて、チャレンジに参加しましょう。",
+    challenge_title_generic: "Carrier Waveのチャレンジ招待",
+    challenge_description_generic: "Carrier Waveでこのリンクを開いて、チャレンジに参加しましょう。",
+    invited_heading: "招待が届きました！",
+    open_button: "Carrier Waveで開く",
+    footer: "Carrier Wave &mdash; アマチュア無線チャレンジ",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_beats_header() {
+        assert_eq!(
+            Locale::negotiate(Some("fr"), Some("de-DE,de;q=0.9")),
+            Locale::Fr
+        );
+    }
+
+    #[test]
+    fn unsupported_override_falls_back_to_header() {
+        assert_eq!(Locale::negotiate(Some("pt"), Some("es-MX")), Locale::Es);
+    }
+
+    #[test]
+    fn highest_q_value_wins_regardless_of_order() {
+        assert_eq!(
+            Locale::negotiate(None, Some("en;q=0.5, ja;q=0.9, de;q=0.7")),
+            Locale::Ja
+        );
+    }
+
+    #[test]
+    fn missing_q_means_one() {
+        assert_eq!(Locale::negotiate(None, Some("fr;q=0.9, de")), Locale::De);
+    }
+
+    #[test]
+    fn unsupported_preferred_language_skips_to_next_supported() {
+        assert_eq!(
+            Locale::negotiate(None, Some("pt-BR, es;q=0.8, en;q=0.5")),
+            Locale::Es
+        );
+    }
+
+    #[test]
+    fn zero_q_is_not_acceptable() {
+        assert_eq!(
+            Locale::negotiate(None, Some("de;q=0, fr;q=0.1")),
+            Locale::Fr
+        );
+    }
+
+    #[test]
+    fn falls_back_to_english() {
+        assert_eq!(Locale::negotiate(None, None), Locale::En);
+        assert_eq!(Locale::negotiate(None, Some("pt-BR, *;q=0.1")), Locale::En);
+        assert_eq!(
+            Locale::negotiate(Some("xx"), Some("garbage;q=abc")),
+            Locale::En
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Background jobs started from the admin API, such as a POTA spot backfill.
+//!
+//! Jobs live in [`JobRegistry`] on this instance only, so their progress can be
+//! polled at `GET /v1/admin/jobs/:id`. Finished jobs are kept for
+//! [`FINISHED_JOB_RETENTION`] and lost on restart.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// How long a finished job stays visible after it completes or fails.
+pub const FINISHED_JOB_RETENTION: Duration = Duration::hours(24);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// One job and how far it has got.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: &'static str,
+    pub status: JobStatus,
+    /// The window the job covers.
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub pages_fetched: u32,
+    pub spots_upserted: u32,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.finished_at
+            .is_some_and(|finished| now - finished > FINISHED_JOB_RETENTION)
+    }
+}
+
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<Uuid, Job>>,
+}
+
+impl JobRegistry {
+    /// Register a running job of `kind` over `[from, to)`, unless one of the
+    /// same kind is still running, in which case returns that job's ID.
+    pub fn start(
+        &self,
+        kind: &'static str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Uuid, Uuid> {
+        let now = Utc::now();
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|_, job| !job.is_expired(now));
+        if let Some(running) = jobs
+            .values()
+            .find(|job| job.kind == kind && job.status == JobStatus::Running)
+        {
+            return Err(running.id);
+        }
+
+        let id = Uuid::new_v4();
+        jobs.insert(
+            id,
+            Job {
+                id,
+                kind,
+                status: JobStatus::Running,
+                from,
+                to,
+                pages_fetched: 0,
+                spots_upserted: 0,
+                error: None,
+                started_at: now,
+                finished_at: None,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Count one more fetched page and the spots it upserted.
+    pub fn record_page(&self, id: Uuid, upserted: u32) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            job.pages_fetched += 1;
+            job.spots_upserted += upserted;
+        }
+    }
+
+    /// Mark a job completed, or failed with `error`.
+    pub fn finish(&self, id: Uuid, error: Option<String>) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            job.status = if error.is_some() {
+                JobStatus::Failed
+            } else {
+                JobStatus::Completed
+            };
+            job.error = error;
+            job.finished_at = Some(Utc::now());
+        }
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Job> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_running_job_per_kind() {
+        let registry = JobRegistry::default();
+        let to = Utc::now();
+        let from = to - Duration::hours(1);
+        let first = registry.start("potaBackfill", from, to).unwrap();
+        assert_eq!(registry.start("potaBackfill", from, to), Err(first));
+
+        registry.record_page(first, 12);
+        registry.record_page(first, 3);
+        registry.finish(first, None);
+        let job = registry.get(first).unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!((job.pages_fetched, job.spots_upserted), (2, 15));
+
+        let second = registry.start("potaBackfill", from, to).unwrap();
+        registry.finish(second, Some("upstream returned 503".to_string()));
+        assert_eq!(registry.get(second).unwrap().status, JobStatus::Failed);
+        assert!(registry.get(Uuid::new_v4()).is_none());
+    }
+}
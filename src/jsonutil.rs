@@ -0,0 +1,118 @@
+//! Canonical JSON for stored challenge settings, so values that mean the same
+//! thing are stored (and compare) the same whatever client wrote them.
+
+use serde_json::{Map, Number, Value};
+
+/// Largest integer an f64 holds exactly (2^53).
+const MAX_EXACT_FLOAT_INT: f64 = 9_007_199_254_740_992.0;
+
+/// Rewrite a value into canonical form: object keys sorted at every level,
+/// and floats with no fractional part (`2.0`, `1e3`, `-0.0`) stored as
+/// integers. Other floats are kept; serde_json already prints them in their
+/// shortest form.
+pub fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let sorted: Map<String, Value> = entries
+                .into_iter()
+                .map(|(key, value)| (key, canonicalize(value)))
+                .collect();
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        Value::Number(number) => Value::Number(canonical_number(number)),
+        other => other,
+    }
+}
+
+fn canonical_number(number: Number) -> Number {
+    match number.as_f64() {
+        Some(float)
+            if number.is_f64() && float.fract() == 0.0 && float.abs() <= MAX_EXACT_FLOAT_INT =>
+        {
+            Number::from(float as i64)
+        }
+        _ => number,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn integral_floats_become_integers() {
+        let value: Value = serde_json::from_str(r#"[2.0, 1e3, -0.0, -4.0, 2.5, 7]"#).unwrap();
+        assert_eq!(canonicalize(value).to_string(), "[2,1000,0,-4,2.5,7]");
+    }
+
+    #[test]
+    fn nested_keys_are_sorted() {
+        let value = json!({ "tiers": [{ "threshold": 1.0, "id": "a" }], "goals": { "type": "x" } });
+        assert_eq!(
+            canonicalize(value).to_string(),
+            r#"{"goals":{"type":"x"},"tiers":[{"id":"a","threshold":1}]}"#
+        );
+    }
+
+    /// Arbitrary JSON whose numbers are integers small enough to also be
+    /// written exactly as floats.
+    fn json_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            (-1_000_000i64..1_000_000).prop_map(|n| json!(n)),
+            (-1_000_000i64..1_000_000).prop_map(|n| json!(n as f64 / 8.0)),
+            "[a-z]{0,6}".prop_map(Value::String),
+        ];
+        leaf.prop_recursive(4, 48, 6, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+                prop::collection::btree_map("[a-z]{1,4}", inner, 0..6)
+                    .prop_map(|map| Value::Object(map.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// The same value as another client might send it: keys written in
+    /// reverse order and integers written as floats.
+    fn rewritten(value: &Value) -> String {
+        match value {
+            Value::Object(map) => {
+                let fields: Vec<String> = map
+                    .iter()
+                    .rev()
+                    .map(|(key, value)| format!("{}:{}", json!(key), rewritten(value)))
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            }
+            Value::Array(items) => {
+                let items: Vec<String> = items.iter().map(rewritten).collect();
+                format!("[{}]", items.join(","))
+            }
+            Value::Number(number) if number.is_i64() || number.is_u64() => {
+                format!("{}.0", number)
+            }
+            other => other.to_string(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn equal_values_canonicalize_identically(value in json_value()) {
+            let other: Value = serde_json::from_str(&rewritten(&value)).unwrap();
+            let canonical = canonicalize(value);
+            prop_assert_eq!(canonicalize(other).to_string(), canonical.to_string());
+        }
+
+        #[test]
+        fn canonicalize_is_idempotent(value in json_value()) {
+            let once = canonicalize(value);
+            prop_assert_eq!(canonicalize(once.clone()), once);
+        }
+    }
+}
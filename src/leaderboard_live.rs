@@ -0,0 +1,223 @@
+//! Live leaderboard pushes for `GET /v1/challenges/:id/leaderboard/ws`.
+//!
+//! Each challenge with at least one subscriber has a publisher task. Progress
+//! writes call [`LeaderboardHub::invalidate`]; the publisher then builds one
+//! snapshot, at most once per push interval, and broadcasts it to every
+//! subscriber. The publisher stops and the challenge's entry is removed when
+//! the last subscriber goes away.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{broadcast, watch};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Minimum time between two pushes for one challenge.
+pub const PUSH_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Snapshots a slow subscriber may fall behind by; it skips to the newest.
+const SNAPSHOT_BUFFER: usize = 4;
+
+/// A serialized leaderboard message, shared by all subscribers.
+pub type Snapshot = Arc<str>;
+
+struct Channel {
+    /// Marked changed on every progress write; dropping it stops the publisher.
+    invalidated: watch::Sender<()>,
+    snapshots: broadcast::Sender<Snapshot>,
+}
+
+/// Per-challenge subscriber sets. Cheap to clone.
+#[derive(Clone)]
+pub struct LeaderboardHub {
+    channels: Arc<Mutex<HashMap<Uuid, Channel>>>,
+    push_interval: Duration,
+}
+
+impl LeaderboardHub {
+    pub fn new(push_interval: Duration) -> Self {
+        Self {
+            channels: Arc::default(),
+            push_interval,
+        }
+    }
+
+    /// Subscribe to a challenge's pushes. The first subscriber starts its
+    /// publisher, which calls `snapshot` once per push; `None` skips the push.
+    pub fn subscribe<F, Fut>(&self, challenge_id: Uuid, snapshot: F) -> LeaderboardSubscription
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Option<Snapshot>> + Send + 'static,
+    {
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels.entry(challenge_id).or_insert_with(|| {
+            let (sender, _) = broadcast::channel(SNAPSHOT_BUFFER);
+            let (invalidated, changes) = watch::channel(());
+            tokio::spawn(publish(
+                changes,
+                sender.clone(),
+                self.push_interval,
+                snapshot,
+            ));
+            Channel {
+                invalidated,
+                snapshots: sender,
+            }
+        });
+        LeaderboardSubscription {
+            receiver: channel.snapshots.subscribe(),
+            challenge_id,
+            hub: self.clone(),
+        }
+    }
+
+    /// Note that a challenge's progress changed. A no-op without subscribers.
+    pub fn invalidate(&self, challenge_id: Uuid) {
+        if let Some(channel) = self.channels.lock().unwrap().get(&challenge_id) {
+            channel.invalidated.send_modify(|_| {});
+        }
+    }
+
+    #[cfg(test)]
+    fn challenge_count(&self) -> usize {
+        self.channels.lock().unwrap().len()
+    }
+}
+
+/// Push a snapshot after each invalidation, throttled to one per `interval`,
+/// until no subscribers are left.
+async fn publish<F, Fut>(
+    mut changes: watch::Receiver<()>,
+    sender: broadcast::Sender<Snapshot>,
+    interval: Duration,
+    snapshot: F,
+) where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Option<Snapshot>>,
+{
+    let mut next_push = Instant::now();
+    loop {
+        if changes.changed().await.is_err() {
+            // The challenge's entry was removed
+            return;
+        }
+        tokio::time::sleep_until(next_push).await;
+        // Writes arriving while throttled are folded into this push
+        changes.borrow_and_update();
+        if sender.receiver_count() == 0 {
+            return;
+        }
+        if let Some(snapshot) = snapshot().await {
+            let _ = sender.send(snapshot);
+        }
+        next_push = Instant::now() + interval;
+    }
+}
+
+/// A subscriber's receiving end; dropping it unsubscribes.
+pub struct LeaderboardSubscription {
+    receiver: broadcast::Receiver<Snapshot>,
+    challenge_id: Uuid,
+    hub: LeaderboardHub,
+}
+
+impl LeaderboardSubscription {
+    /// The next snapshot, skipping any this subscriber was too slow to take.
+    pub async fn recv(&mut self) -> Option<Snapshot> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(snapshot) => return Some(snapshot),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for LeaderboardSubscription {
+    fn drop(&mut self) {
+        let mut channels = self.hub.channels.lock().unwrap();
+        let Some(channel) = channels.get(&self.challenge_id) else {
+            return;
+        };
+        // Our own receiver is still counted
+        if channel.snapshots.receiver_count() <= 1 {
+            channels.remove(&self.challenge_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A snapshot source numbering its snapshots, like successive leaderboard
+    /// reads after progress writes.
+    fn counter() -> (
+        Arc<AtomicUsize>,
+        impl Fn() -> std::future::Ready<Option<Snapshot>>,
+    ) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let source = {
+            let count = count.clone();
+            move || {
+                let n = count.fetch_add(1, Ordering::SeqCst) + 1;
+                std::future::ready(Some(Snapshot::from(format!("snapshot {n}"))))
+            }
+        };
+        (count, source)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn progress_write_pushes_throttled_snapshots() {
+        let hub = LeaderboardHub::new(PUSH_INTERVAL);
+        let challenge_id = Uuid::new_v4();
+        let (count, source) = counter();
+        let mut first = hub.subscribe(challenge_id, source);
+        let (_, unused) = counter();
+        let mut second = hub.subscribe(challenge_id, unused);
+
+        hub.invalidate(challenge_id);
+        assert_eq!(first.recv().await.as_deref(), Some("snapshot 1"));
+        assert_eq!(second.recv().await.as_deref(), Some("snapshot 1"));
+
+        // A burst of writes inside the interval becomes one push, 3s later
+        let start = Instant::now();
+        hub.invalidate(challenge_id);
+        hub.invalidate(challenge_id);
+        hub.invalidate(challenge_id);
+        assert_eq!(first.recv().await.as_deref(), Some("snapshot 2"));
+        assert!(start.elapsed() >= PUSH_INTERVAL - Duration::from_millis(1));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        // Other challenges' writes don't reach these subscribers
+        hub.invalidate(Uuid::new_v4());
+        tokio::time::sleep(PUSH_INTERVAL * 2).await;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn last_unsubscribe_removes_the_challenge() {
+        let hub = LeaderboardHub::new(PUSH_INTERVAL);
+        let challenge_id = Uuid::new_v4();
+        let (count, source) = counter();
+        let first = hub.subscribe(challenge_id, source);
+        let (_, unused) = counter();
+        let second = hub.subscribe(challenge_id, unused);
+        assert_eq!(hub.challenge_count(), 1);
+
+        drop(first);
+        assert_eq!(hub.challenge_count(), 1);
+        drop(second);
+        assert_eq!(hub.challenge_count(), 0);
+
+        // The publisher exited without building a snapshot
+        hub.invalidate(challenge_id);
+        tokio::time::sleep(PUSH_INTERVAL).await;
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,53 +1,75 @@
 mod aggregators;
 mod auth;
+mod blocks;
 mod config;
 mod contest;
+mod cursor;
 mod db;
 mod error;
 mod extractors;
+mod feature_flags;
+mod grid;
+mod hamalert;
 mod handlers;
+mod http_client;
+mod i18n;
+mod jobs;
+mod jsonutil;
+mod leaderboard_live;
+mod maintenance;
 mod metrics;
 mod models;
+mod query_timing;
 mod rbn;
+mod routes;
+mod server;
 mod snapshots;
+mod spot_stream;
+mod startup;
 
 use std::net::SocketAddr;
 
-use axum::{
-    http::StatusCode,
-    middleware,
-    response::IntoResponse,
-    routing::{delete, get, post, put},
-    Extension, Json, Router,
-};
+use axum::{routing::get, Extension, Router};
 use tokio::signal;
 use sqlx::postgres::PgPoolOptions;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::services::{ServeDir, ServeFile};
-use tower_http::trace::TraceLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 use config::Config;
+use feature_flags::FeatureFlags;
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
+    // The log filter applies only to output, so statement events still reach
+    // the query timing layer
+    let log_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "activities_server=debug,tower_http=debug,sqlx::query=warn".into());
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "challenges_server=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_filter(log_filter))
+        .with(query_timing::layer())
         .init();
 
     // Load configuration
     dotenvy::dotenv().ok();
-    let config = Config::from_env().expect("Failed to load configuration");
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(errors) => {
+            for error in &errors {
+                tracing::error!("{error}");
+            }
+            eprintln!("Failed to load configuration ({} errors)", errors.len());
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("Loaded configuration: {}", config.redacted_debug());
 
     // Create database pool
+    let connect_options =
+        query_timing::connect_options(&config.database_url, config.db_slow_query_ms)
+            .expect("Invalid DATABASE_URL");
     let pool = PgPoolOptions::new()
         .max_connections(5)
-        .connect(&config.database_url)
+        .connect_with(connect_options)
         .await
         .expect("Failed to connect to database");
 
@@ -59,6 +81,12 @@ async fn main() {
 
     tracing::info!("Database connected and migrations complete");
 
+    // Verify programs referenced by enabled aggregators exist and are active
+    if let Err(e) = startup::check_required_programs(&pool, &config).await {
+        tracing::error!("Startup validation failed: {e}");
+        std::process::exit(1);
+    }
+
     // Restore from snapshot if tables are empty and a fresh snapshot exists
     if config.snapshot_enabled {
         match snapshots::try_restore(&pool, &config).await {
@@ -72,36 +100,63 @@ async fn main() {
     let metrics_handle = metrics::install();
     metrics::spawn_pool_metrics(pool.clone());
 
-    // Spawn spot aggregators and TTL cleanup
+    // Shared serving state: drain flag, in-flight requests, running aggregator
+    // cycles, upstream fetch freshness
+    let server_state = server::ServerState::default();
+
+    // Load runtime feature flags (seeding defaults from config) and keep them fresh
+    let flags = FeatureFlags::from_config(&config);
+    if let Err(e) = flags.load(&pool, &config).await {
+        tracing::warn!("Failed to load feature flags, using config defaults: {e}");
+    }
+    feature_flags::spawn_refresh(flags.clone(), pool.clone());
+
+    // Spawn spot aggregators and TTL cleanup. One that fails to start is
+    // reported in /v1/status and can be retried with a manual poll.
+    for report in
+        aggregators::spawn_aggregators(pool.clone(), &config, flags.clone(), server_state.clone())
+    {
+        report.log();
+        server_state.record_aggregator_start(report);
+    }
+
+    // Relay spots stored by other instances to this one's subscribers
     if config.spots_enabled {
-        aggregators::spawn_aggregators(pool.clone(), &config);
-        tracing::info!("Spots system enabled");
+        spot_stream::spawn_listener(pool.clone());
     }
 
     // Spawn POTA stats aggregator (independent of spots)
     if config.pota_stats_aggregator_enabled {
-        aggregators::spawn_pota_stats_aggregator(pool.clone(), &config);
+        aggregators::spawn_pota_stats_aggregator(pool.clone(), &config, server_state.clone());
     }
 
     // Spawn park boundaries aggregator (requires POTA stats for park catalog)
     if config.park_boundaries_enabled {
-        aggregators::spawn_park_boundaries_aggregator(pool.clone(), &config);
+        aggregators::spawn_park_boundaries_aggregator(pool.clone(), &config, server_state.clone());
     }
 
     // Spawn Polish park boundaries aggregator (requires POTA stats for park catalog)
     if config.polish_park_boundaries_enabled {
-        aggregators::spawn_polish_park_boundaries_aggregator(pool.clone(), &config);
+        aggregators::spawn_polish_park_boundaries_aggregator(
+            pool.clone(),
+            &config,
+            server_state.clone(),
+        );
     }
 
     // Spawn historic trails aggregator
     if config.historic_trails_enabled {
-        aggregators::spawn_historic_trails_aggregator(pool.clone(), &config);
+        aggregators::spawn_historic_trails_aggregator(pool.clone(), &config, server_state.clone());
     }
 
     // Spawn RBN telnet ingester
     let rbn_store = rbn::SpotStore::new();
     if config.rbn_proxy_enabled {
-        rbn::spawn_rbn_ingester(rbn_store.clone(), config.rbn_proxy_callsign.clone());
+        rbn::spawn_rbn_ingester(
+            rbn_store.clone(),
+            config.rbn_proxy_callsign.clone(),
+            server_state.clone(),
+        );
         metrics::spawn_rbn_metrics(rbn_store.clone());
         tracing::info!("RBN proxy enabled (login: {})", config.rbn_proxy_callsign);
     }
@@ -121,18 +176,50 @@ async fn main() {
         );
     }
 
+    // Spawn nightly database maintenance
+    if config.maintenance_enabled {
+        maintenance::spawn_maintenance(pool.clone(), &config, server_state.clone());
+    }
+
+    // Serve /metrics on its own port when configured, otherwise alongside the API
+    if let Some(metrics_port) = config.metrics_port {
+        let metrics_app = Router::new()
+            .route("/metrics", get(handlers::get_metrics))
+            .layer(Extension(metrics_handle.clone()));
+        let metrics_addr = SocketAddr::from(([0, 0, 0, 0], metrics_port));
+        let metrics_listener = tokio::net::TcpListener::bind(metrics_addr).await.unwrap();
+        tracing::info!("Serving metrics on {}", metrics_addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(metrics_listener, metrics_app).await {
+                tracing::error!("Metrics server error: {e}");
+            }
+        });
+    }
+
     // Build router
-    let app = create_router(pool.clone(), config.clone(), rbn_store, metrics_handle);
+    let app = routes::create_router(
+        pool.clone(),
+        config.clone(),
+        rbn_store,
+        flags,
+        server_state,
+        metrics_handle,
+    );
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     tracing::info!("Starting server on {}", addr);
 
+    let server_options = server::ServerOptions::from_config(&config);
+    tracing::info!(
+        http2 = server_options.http2_enabled,
+        keep_alive = server_options.keep_alive,
+        idle_timeout_secs = config.http_idle_timeout_secs,
+        "HTTP server options"
+    );
+
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+    server::serve(listener, app, server_options, shutdown_signal()).await;
 
     tracing::info!("Server shut down gracefully");
 }
@@ -160,288 +247,3 @@ async fn shutdown_signal() {
         _ = terminate => tracing::info!("Received SIGTERM, starting graceful shutdown"),
     }
 }
-
-fn create_router(
-    pool: sqlx::PgPool,
-    config: Config,
-    rbn_store: rbn::SpotStore,
-    metrics_handle: metrics_exporter_prometheus::PrometheusHandle,
-) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    // Public routes
-    let public_routes = Router::new()
-        .route("/challenges", get(handlers::list_challenges))
-        .route("/challenges/:id", get(handlers::get_challenge))
-        .route("/challenges/:id/join", post(handlers::join_challenge))
-        .route(
-            "/challenges/:id/leaderboard",
-            get(handlers::get_leaderboard),
-        )
-        .route("/contests", get(handlers::list_contests))
-        .route("/contests/:id", get(handlers::get_contest))
-        .route("/badges/:id/image", get(handlers::get_badge_image))
-        .route("/programs", get(handlers::list_programs))
-        .route("/programs/:slug", get(handlers::get_program))
-        .route("/spots", get(handlers::list_spots))
-        .route("/health", get(handlers::health_check))
-        .route("/users/search", get(handlers::search_users))
-        .route("/register", post(handlers::register))
-        .route("/pota/stats/activator", get(handlers::get_activator_stats))
-        .route("/pota/stats/hunter", get(handlers::get_hunter_stats))
-        .route("/pota/stats/state/:state", get(handlers::get_state_stats))
-        .route("/pota/stats/park/:reference", get(handlers::get_park_stats))
-        .route(
-            "/pota/stats/rankings/activators",
-            get(handlers::get_activator_rankings),
-        )
-        .route("/pota/stats/status", get(handlers::get_sync_status))
-        .route("/parks/boundaries", get(handlers::get_boundaries))
-        .route(
-            "/parks/boundaries/status",
-            get(handlers::get_boundary_status),
-        )
-        .route("/parks/boundaries/:reference", get(handlers::get_boundary))
-        .route("/trails", get(handlers::get_trails))
-        .route("/trails/status", get(handlers::get_trail_status))
-        .route("/trails/:reference", get(handlers::get_trail))
-        .route("/clubs/:id/logo", get(handlers::get_club_logo))
-        .route("/equipment/catalog", get(handlers::get_catalog))
-        .route("/equipment/search", get(handlers::search_equipment))
-        .route(
-            "/equipment/submissions",
-            post(handlers::submit_equipment),
-        )
-        .route("/events", get(handlers::list_events))
-        .route("/events/:id", get(handlers::get_event))
-        .route("/rbn/spots", get(handlers::rbn_spots))
-        .route("/rbn/stats", get(handlers::rbn_stats))
-        .route("/rbn/skimmers", get(handlers::rbn_skimmers))
-        .route("/twilio/sms", post(handlers::twilio_sms_webhook))
-        .route("/metrics", post(handlers::ingest_metrics))
-        .route("/diagnostics", post(handlers::ingest_diagnostics))
-        .route(
-            "/telemetry/equipment-usage",
-            post(handlers::report_equipment_usage),
-        )
-        .layer(Extension(rbn_store))
-        .layer(middleware::from_fn_with_state(
-            pool.clone(),
-            auth::optional_auth,
-        ));
-
-    // Authenticated routes
-    let auth_routes = Router::new()
-        .route("/challenges/:id/progress", post(handlers::report_progress))
-        .route("/challenges/:id/progress", get(handlers::get_progress))
-        .route("/challenges/:id/leave", delete(handlers::leave_challenge))
-        .route(
-            "/challenges/:id/participants/:callsign",
-            get(handlers::get_participation_status),
-        )
-        .route(
-            "/participants/:callsign/challenges",
-            get(handlers::list_challenges_for_callsign),
-        )
-        .route("/friends/invite-link", get(handlers::get_invite_link))
-        .route("/friends/requests", post(handlers::create_friend_request))
-        .route(
-            "/friends/suggestions",
-            post(handlers::get_friend_suggestions),
-        )
-        .route("/friends", get(handlers::list_friends))
-        .route(
-            "/friends/requests/pending",
-            get(handlers::list_pending_requests),
-        )
-        .route(
-            "/friends/requests/:id/accept",
-            post(handlers::accept_friend_request),
-        )
-        .route(
-            "/friends/requests/:id/decline",
-            post(handlers::decline_friend_request),
-        )
-        .route("/friends/:id", delete(handlers::remove_friend))
-        .route("/activities", post(handlers::report_activity))
-        .route("/activities/:id", delete(handlers::delete_activity))
-        .route("/spots", post(handlers::create_self_spot))
-        .route("/spots/:id", delete(handlers::delete_own_spot))
-        .route("/feed", get(handlers::get_feed))
-        .route("/clubs", get(handlers::get_clubs))
-        .route("/clubs/sync", get(handlers::sync_clubs))
-        .route("/clubs/membership", get(handlers::get_club_membership))
-        .route("/clubs/:id", get(handlers::get_club_details))
-        .route("/clubs/:id/activity", get(handlers::get_club_activity))
-        .route("/clubs/:id/status", get(handlers::get_club_status))
-        .route("/clubs/:id/notes", put(handlers::update_club_notes))
-        .route("/events", post(handlers::create_event))
-        .route("/events/mine", get(handlers::list_my_events))
-        .route(
-            "/events/:id",
-            put(handlers::update_event).delete(handlers::delete_event),
-        )
-        .route(
-            "/telemetry/upload-errors",
-            post(handlers::report_upload_errors),
-        )
-        .route("/spot-markers", post(handlers::create_spot_marker))
-        .route("/account", delete(handlers::delete_account))
-        .route("/account/callsign", put(handlers::change_callsign))
-        .route(
-            "/account/claim-previous",
-            post(handlers::claim_previous_account),
-        )
-        .layer(Extension(config.clone()))
-        .layer(middleware::from_fn_with_state(
-            pool.clone(),
-            auth::require_auth,
-        ));
-
-    // Admin routes
-    let admin_routes = Router::new()
-        .route("/admin/challenges", post(handlers::create_challenge))
-        .route("/admin/challenges/:id", put(handlers::update_challenge))
-        .route("/admin/challenges/:id", delete(handlers::delete_challenge))
-        .route("/admin/contests", post(handlers::upsert_contests))
-        .route("/admin/contests/:id", delete(handlers::delete_contest))
-        .route(
-            "/admin/contests/validate",
-            post(handlers::validate_contests),
-        )
-        .route(
-            "/admin/challenges/:id/badges",
-            post(handlers::upload_badge).get(handlers::list_badges),
-        )
-        .route("/admin/badges/:id", delete(handlers::delete_badge))
-        .route(
-            "/admin/challenges/:id/invites",
-            post(handlers::generate_invite).get(handlers::list_invites),
-        )
-        .route("/admin/invites/:token", delete(handlers::revoke_invite))
-        .route(
-            "/admin/programs",
-            post(handlers::create_program).get(handlers::admin_list_programs),
-        )
-        .route(
-            "/admin/programs/:slug",
-            put(handlers::update_program)
-                .get(handlers::admin_get_program)
-                .delete(handlers::delete_program),
-        )
-        .route(
-            "/admin/clubs",
-            post(handlers::create_club).get(handlers::list_clubs_admin),
-        )
-        .route(
-            "/admin/clubs/:id",
-            put(handlers::update_club).delete(handlers::delete_club),
-        )
-        .route(
-            "/admin/clubs/:id/members",
-            post(handlers::add_club_members).get(handlers::list_club_members_admin),
-        )
-        .route(
-            "/admin/clubs/:id/import-notes",
-            post(handlers::import_notes_members),
-        )
-        .route(
-            "/admin/clubs/:id/logo",
-            put(handlers::upload_club_logo).delete(handlers::delete_club_logo),
-        )
-        .route(
-            "/admin/clubs/:id/members/:callsign",
-            delete(handlers::remove_club_member).put(handlers::update_club_member_role),
-        )
-        .route(
-            "/admin/equipment",
-            post(handlers::create_equipment),
-        )
-        .route(
-            "/admin/equipment/:id",
-            put(handlers::update_equipment).delete(handlers::delete_equipment),
-        )
-        .route(
-            "/admin/equipment/submissions",
-            get(handlers::list_equipment_submissions),
-        )
-        .route(
-            "/admin/equipment/submissions/:id/review",
-            put(handlers::review_equipment_submission),
-        )
-        .route(
-            "/admin/events",
-            get(handlers::list_events_admin),
-        )
-        .route(
-            "/admin/events/:id",
-            get(handlers::admin_get_event)
-                .put(handlers::admin_update_event)
-                .delete(handlers::admin_delete_event),
-        )
-        .route(
-            "/admin/events/:id/review",
-            put(handlers::review_event),
-        )
-        .route(
-            "/admin/events/submitter/:callsign",
-            get(handlers::get_submitter_history),
-        )
-        .route(
-            "/admin/telemetry/upload-errors",
-            get(handlers::get_telemetry_summary),
-        )
-        .route(
-            "/admin/metrickit",
-            get(handlers::get_metrickit_summary),
-        )
-        .route("/admin/spots/:id", delete(handlers::admin_delete_spot))
-        .route("/admin/trails/status", get(handlers::get_trail_status))
-        .route("/admin/stats", get(handlers::admin_stats))
-        .route(
-            "/admin/stats/users-by-hour",
-            get(handlers::admin_users_by_hour),
-        )
-        .layer(middleware::from_fn_with_state(
-            config.admin_token,
-            auth::require_admin,
-        ));
-
-    // Merge all v1 routes with a JSON 404 fallback for unmatched API paths
-    let v1_routes = public_routes
-        .merge(auth_routes)
-        .merge(admin_routes)
-        .fallback(api_not_found)
-        .layer(axum::middleware::from_fn(metrics::http_metrics));
-
-    // Friend invite page (server-rendered HTML for links opened in browsers)
-    let invite_route = Router::new().route("/invite/:token", get(handlers::invite_page));
-
-    // Static file serving for SPA (fallback to index.html for client-side routing)
-    let serve_dir = ServeDir::new("web/dist").fallback(ServeFile::new("web/dist/index.html"));
-
-    Router::new()
-        .nest("/v1", v1_routes)
-        .route("/metrics", get(handlers::get_metrics))
-        .merge(invite_route)
-        .fallback_service(serve_dir)
-        .layer(Extension(metrics_handle))
-        .layer(TraceLayer::new_for_http())
-        .layer(cors)
-        .with_state(pool)
-}
-
-async fn api_not_found() -> impl IntoResponse {
-    (
-        StatusCode::NOT_FOUND,
-        Json(serde_json::json!({
-            "error": {
-                "code": "NOT_FOUND",
-                "message": "Endpoint not found"
-            }
-        })),
-    )
-}
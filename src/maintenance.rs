@@ -0,0 +1,279 @@
+//! Nightly database maintenance: refresh planner statistics with `ANALYZE`,
+//! prune expired and past-retention rows (archived spots by month), shorten
+//! old archived comments, sample leaderboard ranks into
+//! `rank_history`, and record per-table row counts in `table_stats`.
+//!
+//! Runs once a day at `MAINTENANCE_HOUR_UTC`. Each run first claims the
+//! current hour in `maintenance_runs`, so a restart (or a second instance)
+//! during that hour doesn't run it again.
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use chrono::{DateTime, Duration, Timelike, Utc};
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::db;
+use crate::metrics as app_metrics;
+use crate::server::ServerState;
+
+/// Expired challenge invite tokens are kept this long before pruning,
+/// matching the grace period for friend invites.
+const EXPIRED_INVITE_GRACE_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MaintenanceConfig {
+    pub hour_utc: u32,
+    pub retention_days: i64,
+    pub spots_archive_retention_months: i64,
+    pub spots_archive_comment_retention_days: Option<i32>,
+}
+
+impl MaintenanceConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            hour_utc: config.maintenance_hour_utc,
+            retention_days: config.maintenance_retention_days,
+            spots_archive_retention_months: config.spots_archive_retention_months,
+            spots_archive_comment_retention_days: config.spots_archive_comment_retention_days,
+        }
+    }
+}
+
+/// A `(table, timestamp column, days)` rule: rows older than `days` are deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruneRule {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub days: i64,
+}
+
+/// Tables pruned by column age. Friend invites are pruned separately since
+/// they expire on either `expires_at` or `used_at`.
+pub fn prune_rules(retention_days: i64) -> [PruneRule; 6] {
+    [
+        PruneRule {
+            table: "invite_tokens",
+            column: "expires_at",
+            days: EXPIRED_INVITE_GRACE_DAYS,
+        },
+        PruneRule {
+            table: "upload_error_telemetry",
+            column: "created_at",
+            days: retention_days,
+        },
+        PruneRule {
+            table: "metrickit_payloads",
+            column: "created_at",
+            days: retention_days,
+        },
+        PruneRule {
+            table: "callsign_history",
+            column: "changed_at",
+            days: retention_days,
+        },
+        PruneRule {
+            table: "rank_history",
+            column: "sampled_on",
+            days: retention_days,
+        },
+        PruneRule {
+            table: "weekly_digests",
+            column: "computed_at",
+            days: retention_days,
+        },
+    ]
+}
+
+/// The first `hour_utc:00` strictly after `now`.
+pub fn next_run_after(now: DateTime<Utc>, hour_utc: u32) -> DateTime<Utc> {
+    let today = now
+        .date_naive()
+        .and_hms_opt(hour_utc, 0, 0)
+        .expect("maintenance hour is validated at startup")
+        .and_utc();
+    if today > now {
+        today
+    } else {
+        today + Duration::days(1)
+    }
+}
+
+/// Spawn the nightly maintenance scheduler.
+pub fn spawn_maintenance(pool: PgPool, config: &Config, state: ServerState) {
+    let maintenance_config = MaintenanceConfig::from_config(config);
+    tokio::spawn(async move {
+        maintenance_loop(pool, maintenance_config, state).await;
+    });
+    tracing::info!(
+        hour_utc = config.maintenance_hour_utc,
+        retention_days = config.maintenance_retention_days,
+        "Nightly maintenance scheduled"
+    );
+}
+
+async fn maintenance_loop(pool: PgPool, config: MaintenanceConfig, state: ServerState) {
+    // Started during the maintenance hour: run now. If this hour already ran
+    // before a restart, the claim in `run_once` skips it.
+    if Utc::now().hour() == config.hour_utc {
+        run_once(&pool, config, &state).await;
+    }
+
+    loop {
+        let now = Utc::now();
+        let next = next_run_after(now, config.hour_utc);
+        tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+        run_once(&pool, config, &state).await;
+    }
+}
+
+async fn run_once(pool: &PgPool, config: MaintenanceConfig, state: &ServerState) {
+    let _cycle = state.begin_cycle().await;
+
+    let run_id = match db::claim_maintenance_run(pool).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            tracing::info!("Maintenance already ran this hour, skipping");
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Maintenance could not claim run: {}", e);
+            metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "maintenance")
+                .increment(1);
+            return;
+        }
+    };
+
+    let started = Instant::now();
+    let mut errors = Vec::new();
+
+    if let Err(e) = db::analyze_database(pool).await {
+        errors.push(format!("analyze: {e}"));
+    }
+
+    let mut pruned: BTreeMap<&str, u64> = BTreeMap::new();
+    match db::cleanup_expired_invites(pool).await {
+        Ok(count) => {
+            pruned.insert("friend_invites", count);
+        }
+        Err(e) => errors.push(format!("friend_invites: {e}")),
+    }
+    for rule in prune_rules(config.retention_days) {
+        match db::prune_older_than(pool, rule.table, rule.column, rule.days).await {
+            Ok(count) => {
+                pruned.insert(rule.table, count);
+            }
+            Err(e) => errors.push(format!("{}: {e}", rule.table)),
+        }
+    }
+    match db::prune_spots_archive(pool, config.spots_archive_retention_months).await {
+        Ok(count) => {
+            pruned.insert("spots_archive", count);
+        }
+        Err(e) => errors.push(format!("spots_archive: {e}")),
+    }
+    if let Some(days) = config.spots_archive_comment_retention_days {
+        match db::truncate_archived_comments(pool, days).await {
+            Ok(count) => {
+                pruned.insert("spots_archive_comments", count);
+            }
+            Err(e) => errors.push(format!("spots_archive_comments: {e}")),
+        }
+    }
+
+    let rank_samples = match db::record_rank_history(pool).await {
+        Ok(count) => count,
+        Err(e) => {
+            errors.push(format!("rank_history: {e}"));
+            0
+        }
+    };
+
+    let tables = match db::record_table_stats(pool, run_id).await {
+        Ok(count) => count,
+        Err(e) => {
+            errors.push(format!("table_stats: {e}"));
+            0
+        }
+    };
+
+    let error = (!errors.is_empty()).then(|| errors.join("; "));
+    let pruned_json = serde_json::to_value(&pruned).unwrap_or_default();
+    if let Err(e) = db::finish_maintenance_run(pool, run_id, &pruned_json, error.as_deref()).await {
+        tracing::error!("Maintenance could not record run: {}", e);
+    }
+
+    let total_pruned: u64 = pruned.values().sum();
+    match &error {
+        None => {
+            tracing::info!(
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                tables,
+                rank_samples,
+                total_pruned,
+                pruned = ?pruned,
+                "Maintenance finished"
+            );
+            metrics::gauge!(app_metrics::SYNC_LAST_COMPLETED_TIMESTAMP, "aggregator" => "maintenance")
+                .set(Utc::now().timestamp() as f64);
+        }
+        Some(error) => {
+            tracing::error!(
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                tables,
+                rank_samples,
+                total_pruned,
+                pruned = ?pruned,
+                "Maintenance finished with errors: {}",
+                error
+            );
+            metrics::counter!(app_metrics::SYNC_ERRORS_TOTAL, "aggregator" => "maintenance")
+                .increment(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn next_run_later_today() {
+        assert_eq!(
+            next_run_after(at("2025-06-01T01:30:00Z"), 3),
+            at("2025-06-01T03:00:00Z")
+        );
+    }
+
+    #[test]
+    fn next_run_tomorrow_once_hour_has_started() {
+        assert_eq!(
+            next_run_after(at("2025-06-01T03:00:00Z"), 3),
+            at("2025-06-02T03:00:00Z")
+        );
+        assert_eq!(
+            next_run_after(at("2025-06-01T23:59:59Z"), 3),
+            at("2025-06-02T03:00:00Z")
+        );
+    }
+
+    #[test]
+    fn next_run_at_midnight() {
+        assert_eq!(
+            next_run_after(at("2025-12-31T12:00:00Z"), 0),
+            at("2026-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn prune_rules_use_configured_retention() {
+        let rules = prune_rules(45);
+        assert_eq!(rules[0].days, EXPIRED_INVITE_GRACE_DAYS);
+        assert!(rules[1..].iter().all(|rule| rule.days == 45));
+    }
+}
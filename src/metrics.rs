@@ -19,6 +19,12 @@ pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
 pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
 pub const HTTP_REQUESTS_IN_FLIGHT: &str = "http_requests_in_flight";
 
+// ─── Outbound HTTP client metric names ──────────────────────────────────────
+
+pub const HTTP_CLIENT_REQUESTS_TOTAL: &str = "http_client_requests_total";
+pub const HTTP_CLIENT_REQUEST_DURATION_SECONDS: &str = "http_client_request_duration_seconds";
+pub const HTTP_CLIENT_RETRIES_TOTAL: &str = "http_client_retries_total";
+
 // ─── Database pool metric names ─────────────────────────────────────────────
 
 pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
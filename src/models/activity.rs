@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::spot::SpotRow;
+
 /// Database row for an activity.
 #[allow(dead_code)]
 #[derive(Debug, Clone, FromRow)]
@@ -16,6 +18,10 @@ pub struct Activity {
     pub created_at: DateTime<Utc>,
 }
 
+/// Activity types the server writes itself, such as when a friend overtakes
+/// the user on a leaderboard. They don't count towards `MAX_ACTIVITIES_PER_HOUR`.
+pub const SERVER_GENERATED_ACTIVITY_TYPES: &[&str] = &["overtaken"];
+
 /// Request body for POST /v1/activities (matches iOS ReportActivityRequest).
 #[derive(Debug, Deserialize)]
 pub struct ReportActivityRequest {
@@ -23,6 +29,86 @@ pub struct ReportActivityRequest {
     pub activity_type: String,
     pub timestamp: DateTime<Utc>,
     pub details: serde_json::Value,
+    /// A spot the QSO was made from, for `qso` activities.
+    #[serde(rename = "spotId")]
+    pub spot_id: Option<Uuid>,
+    /// The park, summit, or other reference worked, for `qso` activities.
+    pub reference: Option<String>,
+}
+
+/// The only activity type that may link a spot or reference.
+pub const QSO_ACTIVITY_TYPE: &str = "qso";
+
+/// Longest reference accepted on an activity, e.g. `W7A/AM-001` is 10.
+const REFERENCE_MAX_LEN: usize = 20;
+
+/// Uppercase a program reference and check its shape: letters and digits
+/// separated by `-` or `/`, with at least one `-` and one digit, as in
+/// `US-0001`, `W7A/AM-001`, or `KFF-1234`.
+pub fn normalize_reference(raw: &str) -> Result<String, String> {
+    let reference = raw.trim().to_ascii_uppercase();
+    let well_formed = reference.len() <= REFERENCE_MAX_LEN
+        && reference.contains('-')
+        && reference.chars().any(|c| c.is_ascii_digit())
+        && reference
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '/')
+        && reference.split(['-', '/']).all(|part| !part.is_empty());
+    if well_formed {
+        Ok(reference)
+    } else {
+        Err(format!("'{}' is not a valid reference", raw.trim()))
+    }
+}
+
+/// Add what a `qso` activity links to its details, so the feed can show
+/// context after the spot expires: `spotId` with a `spot` summary (callsign,
+/// program, frequency, mode), and `reference`, taken from the spot when not
+/// given. A given reference must match the spot's.
+pub fn link_qso_context(
+    activity_type: &str,
+    details: &mut serde_json::Value,
+    spot: Option<&SpotRow>,
+    reference: Option<&str>,
+) -> Result<(), String> {
+    if spot.is_none() && reference.is_none() {
+        return Ok(());
+    }
+    if activity_type != QSO_ACTIVITY_TYPE {
+        return Err(format!(
+            "spotId and reference only apply to {QSO_ACTIVITY_TYPE} activities"
+        ));
+    }
+    let reference = reference.map(normalize_reference).transpose()?;
+    if let (Some(reference), Some(spot_reference)) =
+        (&reference, spot.and_then(|s| s.reference.as_deref()))
+    {
+        if !reference.eq_ignore_ascii_case(spot_reference) {
+            return Err(format!(
+                "reference {reference} doesn't match the spot's {spot_reference}"
+            ));
+        }
+    }
+    let Some(fields) = details.as_object_mut() else {
+        return Err("details must be an object to link a spot or reference".to_string());
+    };
+
+    if let Some(spot) = spot {
+        fields.insert("spotId".to_string(), spot.id.to_string().into());
+        fields.insert(
+            "spot".to_string(),
+            serde_json::json!({
+                "callsign": spot.callsign,
+                "programSlug": spot.program_slug,
+                "frequencyKhz": spot.frequency_khz,
+                "mode": spot.mode,
+            }),
+        );
+    }
+    if let Some(reference) = reference.or_else(|| spot.and_then(|s| s.reference.clone())) {
+        fields.insert("reference".to_string(), reference.into());
+    }
+    Ok(())
 }
 
 /// Response for a reported activity (matches iOS ReportedActivityDTO).
@@ -60,6 +146,38 @@ pub struct FeedItemRow {
     pub created_at: DateTime<Utc>,
 }
 
+/// Which time a feed is sorted and paginated by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeedOrder {
+    /// When the activity reached the server (`created_at`).
+    #[default]
+    Created,
+    /// When the activity happened (`timestamp`); backdated activities synced
+    /// late sort by when they took place.
+    EventTime,
+}
+
+impl FeedOrder {
+    /// Parse the `order_by` query parameter; absent means `created`.
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value {
+            None | Some("created") => Ok(Self::Created),
+            Some("event_time") => Ok(Self::EventTime),
+            Some(other) => Err(format!(
+                "Unknown order_by '{other}'; expected created or event_time"
+            )),
+        }
+    }
+
+    /// The row's time in this order, which the next-page cursor carries.
+    pub fn key(self, row: &FeedItemRow) -> DateTime<Utc> {
+        match self {
+            Self::Created => row.created_at,
+            Self::EventTime => row.timestamp,
+        }
+    }
+}
+
 /// Response for a feed item (matches iOS FeedItemDTO).
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -86,3 +204,109 @@ impl From<FeedItemRow> for FeedItemResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::spot::SpotSource;
+    use serde_json::json;
+
+    fn pota_spot(reference: Option<&str>) -> SpotRow {
+        let now = Utc::now();
+        SpotRow {
+            id: Uuid::new_v4(),
+            callsign: "K1ABC".to_string(),
+            program_slug: Some("pota".to_string()),
+            source: SpotSource::Pota,
+            external_id: None,
+            frequency_khz: 14062.0,
+            mode: "CW".to_string(),
+            reference: reference.map(String::from),
+            reference_name: None,
+            spotter: None,
+            spotter_grid: None,
+            location_desc: None,
+            country_code: None,
+            state_abbr: None,
+            comments: None,
+            snr: None,
+            wpm: None,
+            submitted_by: None,
+            latitude: None,
+            longitude: None,
+            spotted_at: now,
+            expires_at: now,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn references_are_uppercased_and_shape_checked() {
+        for (raw, normalized) in [
+            ("us-0001", "US-0001"),
+            (" W7A/AM-001 ", "W7A/AM-001"),
+            ("KFF-1234", "KFF-1234"),
+        ] {
+            assert_eq!(normalize_reference(raw).as_deref(), Ok(normalized), "{raw}");
+        }
+        for raw in [
+            "",
+            "US0001",
+            "US-",
+            "-0001",
+            "US--0001",
+            "US-ABCD",
+            "US 0001",
+            "US-000000000000000001",
+        ] {
+            assert!(normalize_reference(raw).is_err(), "{raw}");
+        }
+    }
+
+    #[test]
+    fn unlinked_activity_details_are_untouched() {
+        let mut details = json!({ "qsoCount": 1 });
+        link_qso_context("potaActivation", &mut details, None, None).unwrap();
+        assert_eq!(details, json!({ "qsoCount": 1 }));
+    }
+
+    #[test]
+    fn linked_qso_stores_spot_summary_and_reference() {
+        let spot = pota_spot(Some("US-0001"));
+        let mut details = json!({ "band": "20m" });
+        link_qso_context("qso", &mut details, Some(&spot), None).unwrap();
+        assert_eq!(
+            details,
+            json!({
+                "band": "20m",
+                "spotId": spot.id.to_string(),
+                "spot": {
+                    "callsign": "K1ABC",
+                    "programSlug": "pota",
+                    "frequencyKhz": 14062.0,
+                    "mode": "CW"
+                },
+                "reference": "US-0001"
+            })
+        );
+
+        let mut details = json!({});
+        link_qso_context("qso", &mut details, None, Some("w7a/am-001")).unwrap();
+        assert_eq!(details, json!({ "reference": "W7A/AM-001" }));
+    }
+
+    #[test]
+    fn invalid_links_are_rejected() {
+        let spot = pota_spot(Some("US-0001"));
+        let link = |activity_type, details: serde_json::Value, reference| {
+            let mut details = details;
+            link_qso_context(activity_type, &mut details, Some(&spot), reference)
+        };
+        assert!(link("potaActivation", json!({}), None).is_err());
+        assert!(link("qso", json!({}), Some("US-9999")).is_err());
+        assert!(link("qso", json!({}), Some("not a ref")).is_err());
+        assert!(link("qso", json!([]), None).is_err());
+        assert!(link("qso", json!({}), Some("us-0001")).is_ok());
+    }
+}
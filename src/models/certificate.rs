@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A participant's standing when an ended challenge is finalized, ranked as
+/// on the leaderboard, from `get_final_standings`.
+#[derive(Debug, Clone, FromRow)]
+pub struct FinalStandingRow {
+    pub callsign: String,
+    pub score: i32,
+    pub rank: i64,
+    pub completed_goals: serde_json::Value,
+    pub current_value: i32,
+    /// Participation status; `None` for progress without a participation row.
+    pub participation_status: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One stored row of a challenge's final results.
+#[derive(Debug, Clone, FromRow)]
+pub struct ChallengeResultRow {
+    pub callsign: String,
+    pub score: i32,
+    pub rank: i64,
+    pub completed: bool,
+    pub last_reported_at: DateTime<Utc>,
+    pub finalized_at: DateTime<Utc>,
+}
+
+/// Certificate data for one finisher.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateResponse {
+    /// `<challenge-short-id>-<rank>`, from [`certificate_serial`].
+    pub serial: String,
+    pub callsign: String,
+    pub final_score: i32,
+    pub final_rank: i64,
+    /// The finisher's last progress report before the results were final.
+    pub completed_at: DateTime<Utc>,
+}
+
+impl CertificateResponse {
+    pub fn from_result(challenge_id: Uuid, row: ChallengeResultRow) -> Self {
+        Self {
+            serial: certificate_serial(challenge_id, row.rank),
+            callsign: row.callsign,
+            final_score: row.score,
+            final_rank: row.rank,
+            completed_at: row.last_reported_at,
+        }
+    }
+}
+
+/// API response for GET /v1/challenges/:id/certificates.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificatesResponse {
+    pub challenge_id: Uuid,
+    pub challenge_name: String,
+    /// When the results were stored; `None` while no one has progress.
+    pub finalized_at: Option<DateTime<Utc>>,
+    pub certificates: Vec<CertificateResponse>,
+}
+
+/// A certificate's serial: the first 8 hex digits of the challenge ID,
+/// uppercased, and the final rank, e.g. `1A2B3C4D-3`.
+pub fn certificate_serial(challenge_id: Uuid, rank: i64) -> String {
+    let short_id = &challenge_id.simple().to_string()[..8];
+    format!("{}-{rank}", short_id.to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serial_is_short_challenge_id_and_rank() {
+        let challenge_id: Uuid = "1a2b3c4d-0000-4000-8000-000000000000".parse().unwrap();
+        assert_eq!(certificate_serial(challenge_id, 3), "1A2B3C4D-3");
+        assert_eq!(certificate_serial(challenge_id, 12), "1A2B3C4D-12");
+    }
+}
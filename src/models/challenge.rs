@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::jsonutil::canonicalize;
+
 #[derive(Debug, Clone, FromRow)]
 pub struct Challenge {
     pub id: Uuid,
@@ -87,6 +89,16 @@ pub struct CreateChallengeRequest {
     pub is_active: Option<bool>,
 }
 
+impl CreateChallengeRequest {
+    /// Put the JSON settings in canonical form before they're stored, so
+    /// re-saving an unchanged challenge stores identical JSON.
+    pub fn canonicalize_json(&mut self) {
+        self.configuration = canonicalize(std::mem::take(&mut self.configuration));
+        self.invite_config = self.invite_config.take().map(canonicalize);
+        self.hamalert_config = self.hamalert_config.take().map(canonicalize);
+    }
+}
+
 #[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ListChallengesQuery {
@@ -97,3 +109,430 @@ pub struct ListChallengesQuery {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
+
+/// Challenge types accepted by the `challenges.challenge_type` check.
+pub const CHALLENGE_TYPES: &[&str] = &["collection", "cumulative", "timeBounded"];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateChallengeRequest {
+    #[serde(rename = "type")]
+    pub challenge_type: String,
+    pub configuration: serde_json::Value,
+}
+
+/// One problem with a challenge definition, located by a JSON path.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ConfigurationError {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidateChallengeResponse {
+    pub valid: bool,
+    pub errors: Vec<ConfigurationError>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewScoreRequest {
+    #[serde(rename = "type")]
+    pub challenge_type: String,
+    pub configuration: serde_json::Value,
+    pub entries: Vec<PreviewScoreEntry>,
+}
+
+/// A sample QSO for a score preview: the goal it completes and the value it
+/// adds toward `currentValue`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewScoreEntry {
+    pub goal_id: Option<String>,
+    pub value: Option<i32>,
+}
+
+/// How one sample entry was scored. `counted` is false for invalid entries
+/// and for repeats of a goal an earlier entry already completed.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewEntryOutcome {
+    pub valid: bool,
+    pub counted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The progress and score the sample entries would produce.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScorePreview {
+    pub completed_goals: Vec<String>,
+    pub current_value: i32,
+    pub percentage: f64,
+    pub score: i32,
+    pub current_tier: Option<String>,
+    pub entries: Vec<PreviewEntryOutcome>,
+}
+
+/// Response of POST /v1/challenges/preview-score. Configuration errors are
+/// reported as by POST /v1/challenges/validate, with no preview.
+#[derive(Debug, Serialize)]
+pub struct PreviewScoreResponse {
+    pub valid: bool,
+    pub errors: Vec<ConfigurationError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ScorePreview>,
+}
+
+/// The program whose data-entry values the challenge accepts, from
+/// `configuration.dataEntry.program`.
+pub fn data_entry_program(configuration: &serde_json::Value) -> Option<&str> {
+    configuration.get("dataEntry")?.get("program")?.as_str()
+}
+
+/// A division entrants pick when joining, from `configuration.divisions`.
+/// Leaderboard scores are the scored progress times `multiplier`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Division {
+    pub name: String,
+    pub multiplier: f64,
+}
+
+/// The challenge's divisions, empty when it has none. Entries without a
+/// name are skipped; a missing or non-positive multiplier counts as 1.
+pub fn divisions(configuration: &serde_json::Value) -> Vec<Division> {
+    configuration
+        .get("divisions")
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|division| {
+            let name = division.get("name")?.as_str().filter(|n| !n.is_empty())?;
+            let multiplier = division
+                .get("multiplier")
+                .and_then(|m| m.as_f64())
+                .filter(|m| *m > 0.0)
+                .unwrap_or(1.0);
+            Some(Division {
+                name: name.to_string(),
+                multiplier,
+            })
+        })
+        .collect()
+}
+
+/// The division a joiner asked for, matched in any case. A challenge with
+/// divisions requires one; a challenge without them accepts none.
+pub fn pick_division(
+    configuration: &serde_json::Value,
+    requested: Option<&str>,
+) -> Result<Option<Division>, String> {
+    let divisions = divisions(configuration);
+    match requested {
+        None if divisions.is_empty() => Ok(None),
+        None => Err(format!(
+            "Pick a division: {}",
+            division_names(&divisions).join(", ")
+        )),
+        Some(_) if divisions.is_empty() => Err("This challenge has no divisions".to_string()),
+        Some(name) => divisions
+            .into_iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+            .map(Some)
+            .ok_or_else(|| format!("Unknown division '{name}'")),
+    }
+}
+
+fn division_names(divisions: &[Division]) -> Vec<&str> {
+    divisions.iter().map(|d| d.name.as_str()).collect()
+}
+
+/// Score multiplier of a participant's division; 1 without one, or when
+/// the division has since been removed from the configuration.
+pub fn division_multiplier(configuration: &serde_json::Value, division: Option<&str>) -> f64 {
+    let Some(division) = division else {
+        return 1.0;
+    };
+    divisions(configuration)
+        .into_iter()
+        .find(|d| d.name == division)
+        .map_or(1.0, |d| d.multiplier)
+}
+
+/// A score scaled by a division multiplier, rounded to the nearest point.
+pub fn apply_multiplier(score: i32, multiplier: f64) -> i32 {
+    (f64::from(score) * multiplier).round() as i32
+}
+
+/// Check a challenge type and configuration against the fields progress
+/// scoring reads: `goals`, `tiers`, `scoring.method`, `dataEntry.program`
+/// and `divisions`.
+pub fn validate_configuration(
+    challenge_type: &str,
+    configuration: &serde_json::Value,
+) -> Vec<ConfigurationError> {
+    let mut errors = Vec::new();
+    let mut error = |path: &str, message: String| {
+        errors.push(ConfigurationError {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if !CHALLENGE_TYPES.contains(&challenge_type) {
+        error(
+            "type",
+            format!(
+                "Unknown challenge type '{challenge_type}'; expected one of: {}",
+                CHALLENGE_TYPES.join(", ")
+            ),
+        );
+    }
+    let Some(config) = configuration.as_object() else {
+        error("configuration", "Must be an object".to_string());
+        return errors;
+    };
+
+    match config.get("goals").and_then(|g| g.as_object()) {
+        None => error("configuration.goals", "Must be an object".to_string()),
+        Some(goals) => match goals.get("type").and_then(|t| t.as_str()) {
+            Some("collection") => match goals.get("items").and_then(|i| i.as_array()) {
+                Some(items) if !items.is_empty() => {
+                    let mut seen = std::collections::HashSet::new();
+                    for (i, item) in items.iter().enumerate() {
+                        match item.get("id").and_then(|id| id.as_str()) {
+                            None => error(
+                                &format!("configuration.goals.items[{i}].id"),
+                                "Must be a string".to_string(),
+                            ),
+                            Some(id) if !seen.insert(id) => error(
+                                &format!("configuration.goals.items[{i}].id"),
+                                format!("Duplicate goal id '{id}'"),
+                            ),
+                            Some(_) => {}
+                        }
+                    }
+                }
+                _ => error(
+                    "configuration.goals.items",
+                    "Must be a non-empty array".to_string(),
+                ),
+            },
+            Some("cumulative") => {
+                let target = goals.get("targetValue").and_then(|v| v.as_i64());
+                if target.unwrap_or(0) <= 0 {
+                    error(
+                        "configuration.goals.targetValue",
+                        "Must be a positive integer".to_string(),
+                    );
+                }
+            }
+            _ => error(
+                "configuration.goals.type",
+                "Must be 'collection' or 'cumulative'".to_string(),
+            ),
+        },
+    }
+
+    if let Some(tiers) = config.get("tiers") {
+        match tiers.as_array() {
+            None => error("configuration.tiers", "Must be an array".to_string()),
+            Some(tiers) => {
+                let mut previous: Option<i64> = None;
+                for (i, tier) in tiers.iter().enumerate() {
+                    if tier.get("id").and_then(|id| id.as_str()).is_none() {
+                        error(
+                            &format!("configuration.tiers[{i}].id"),
+                            "Must be a string".to_string(),
+                        );
+                    }
+                    match tier.get("threshold").and_then(|t| t.as_i64()) {
+                        None => error(
+                            &format!("configuration.tiers[{i}].threshold"),
+                            "Must be an integer".to_string(),
+                        ),
+                        Some(threshold) => {
+                            // Tier lookup keeps the last tier reached, so
+                            // thresholds have to climb
+                            if previous.is_some_and(|p| threshold <= p) {
+                                error(
+                                    &format!("configuration.tiers[{i}].threshold"),
+                                    "Must be greater than the previous tier's".to_string(),
+                                );
+                            }
+                            previous = Some(threshold);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(method) = config.get("scoring").and_then(|s| s.get("method")) {
+        if !matches!(method.as_str(), Some("count" | "percentage" | "points")) {
+            error(
+                "configuration.scoring.method",
+                "Must be 'count', 'percentage' or 'points'".to_string(),
+            );
+        }
+    }
+
+    if config.contains_key("dataEntry")
+        && data_entry_program(configuration).is_none_or(str::is_empty)
+    {
+        error(
+            "configuration.dataEntry.program",
+            "Must be a program slug".to_string(),
+        );
+    }
+
+    if let Some(divisions) = config.get("divisions") {
+        match divisions.as_array() {
+            None => error("configuration.divisions", "Must be an array".to_string()),
+            Some(divisions) => {
+                let mut seen = std::collections::HashSet::new();
+                for (i, division) in divisions.iter().enumerate() {
+                    match division.get("name").and_then(|n| n.as_str()) {
+                        None | Some("") => error(
+                            &format!("configuration.divisions[{i}].name"),
+                            "Must be a non-empty string".to_string(),
+                        ),
+                        Some(name) if !seen.insert(name.to_lowercase()) => error(
+                            &format!("configuration.divisions[{i}].name"),
+                            format!("Duplicate division '{name}'"),
+                        ),
+                        Some(_) => {}
+                    }
+                    if let Some(multiplier) = division.get("multiplier") {
+                        if !multiplier.as_f64().is_some_and(|m| m > 0.0) {
+                            error(
+                                &format!("configuration.divisions[{i}].multiplier"),
+                                "Must be a positive number".to_string(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn seeded_style_configuration_is_valid() {
+        let config = json!({
+            "goals": {
+                "type": "collection",
+                "items": [{ "id": "AL", "name": "Alabama" }, { "id": "AK", "name": "Alaska" }]
+            },
+            "tiers": [
+                { "id": "tier-1", "name": "One", "threshold": 1 },
+                { "id": "tier-2", "name": "Both", "threshold": 2 }
+            ],
+            "scoring": { "method": "count", "displayFormat": "{value}/2 states" }
+        });
+        assert_eq!(validate_configuration("collection", &config), vec![]);
+
+        let cumulative = json!({
+            "goals": { "type": "cumulative", "targetValue": 1000 },
+            "dataEntry": { "program": "aoa" }
+        });
+        assert_eq!(validate_configuration("cumulative", &cumulative), vec![]);
+    }
+
+    #[test]
+    fn invalid_configuration_reports_each_path() {
+        let config = json!({
+            "goals": { "type": "collection", "items": [{ "id": "AL" }, { "id": "AL" }, {}] },
+            "tiers": [{ "id": "gold", "threshold": 10 }, { "threshold": 5 }],
+            "scoring": { "method": "fastest" },
+            "dataEntry": { "program": 6 },
+            "divisions": [{ "name": "QRP" }, { "name": "qrp" }, { "name": "High", "multiplier": 0 }]
+        });
+        let paths: Vec<String> = validate_configuration("marathon", &config)
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "type",
+                "configuration.goals.items[1].id",
+                "configuration.goals.items[2].id",
+                "configuration.tiers[1].id",
+                "configuration.tiers[1].threshold",
+                "configuration.scoring.method",
+                "configuration.dataEntry.program",
+                "configuration.divisions[1].name",
+                "configuration.divisions[2].multiplier",
+            ]
+        );
+
+        let errors = validate_configuration("collection", &json!([]));
+        assert_eq!(errors[0].path, "configuration");
+        let errors =
+            validate_configuration("cumulative", &json!({ "goals": { "type": "cumulative" } }));
+        assert_eq!(errors[0].path, "configuration.goals.targetValue");
+    }
+
+    #[test]
+    fn division_multipliers_scale_scores() {
+        let config = json!({
+            "divisions": [
+                { "name": "QRP", "multiplier": 1.5 },
+                { "name": "High Power" },
+                { "name": "" }
+            ]
+        });
+        assert_eq!(
+            divisions(&config),
+            vec![
+                Division {
+                    name: "QRP".to_string(),
+                    multiplier: 1.5
+                },
+                Division {
+                    name: "High Power".to_string(),
+                    multiplier: 1.0
+                },
+            ]
+        );
+        assert_eq!(division_multiplier(&config, Some("QRP")), 1.5);
+        assert_eq!(division_multiplier(&config, Some("High Power")), 1.0);
+        assert_eq!(division_multiplier(&config, Some("Removed")), 1.0);
+        assert_eq!(division_multiplier(&config, None), 1.0);
+
+        assert_eq!(apply_multiplier(10, 1.5), 15);
+        assert_eq!(apply_multiplier(7, 1.5), 11);
+        assert_eq!(apply_multiplier(5, 0.3), 2);
+        assert_eq!(apply_multiplier(0, 2.0), 0);
+        assert_eq!(apply_multiplier(i32::MAX, 2.0), i32::MAX);
+    }
+
+    #[test]
+    fn joiners_pick_an_existing_division() {
+        let config =
+            json!({ "divisions": [{ "name": "QRP", "multiplier": 2 }, { "name": "QRO" }] });
+        let picked = pick_division(&config, Some("qrp")).unwrap().unwrap();
+        assert_eq!((picked.name.as_str(), picked.multiplier), ("QRP", 2.0));
+        assert_eq!(
+            pick_division(&config, None).unwrap_err(),
+            "Pick a division: QRP, QRO"
+        );
+        assert_eq!(
+            pick_division(&config, Some("Legal Limit")).unwrap_err(),
+            "Unknown division 'Legal Limit'"
+        );
+
+        let plain = json!({});
+        assert_eq!(pick_division(&plain, None), Ok(None));
+        assert!(pick_division(&plain, Some("QRP")).is_err());
+    }
+}
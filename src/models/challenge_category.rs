@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Database row for the challenge_categories table, with the number of
+/// challenges using it.
+#[derive(Debug, Clone, FromRow)]
+pub struct CategoryRow {
+    pub name: String,
+    pub description: Option<String>,
+    pub sort_order: i32,
+    pub challenge_count: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// API response for one category.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryResponse {
+    pub name: String,
+    pub description: Option<String>,
+    pub sort_order: i32,
+    /// Challenges in this category (active only on the public endpoint).
+    pub challenge_count: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CategoryRow> for CategoryResponse {
+    fn from(row: CategoryRow) -> Self {
+        Self {
+            name: row.name,
+            description: row.description,
+            sort_order: row.sort_order,
+            challenge_count: row.challenge_count,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Request body for POST /v1/admin/categories.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+/// Request body for PUT /v1/admin/categories/:name. Renaming carries over
+/// to existing challenges.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+/// Request body for POST /v1/admin/categories/merge.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeCategoriesRequest {
+    /// Category to remove; its challenges move to `into`.
+    pub from: String,
+    pub into: String,
+}
+
+/// API response for POST /v1/admin/categories/merge.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeCategoriesResponse {
+    pub from: String,
+    pub into: String,
+    pub challenges_updated: u64,
+}
+
+/// Trim and lowercase a category name, which must then be a slug: lowercase
+/// letters and digits in words joined by single hyphens, at most 50
+/// characters.
+pub fn normalize_category_name(raw: &str) -> Result<String, String> {
+    let name = raw.trim().to_ascii_lowercase();
+    if name.is_empty() {
+        return Err("category name must not be empty".to_string());
+    }
+    if name.chars().count() > 50 {
+        return Err("category name must be at most 50 characters".to_string());
+    }
+    let is_slug = name.split('-').all(|word| {
+        !word.is_empty()
+            && word
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    });
+    if !is_slug {
+        return Err(format!(
+            "category name '{name}' must be a slug of lowercase letters, digits and single hyphens"
+        ));
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_trims_and_rejects_empty() {
+        assert_eq!(normalize_category_name("  Morse ").unwrap(), "morse");
+        assert!(normalize_category_name("   ").is_err());
+        assert!(normalize_category_name(&"x".repeat(51)).is_err());
+    }
+
+    #[test]
+    fn names_must_be_slugs() {
+        assert_eq!(normalize_category_name("POTA").unwrap(), "pota");
+        assert_eq!(
+            normalize_category_name("cw-ops-2025").unwrap(),
+            "cw-ops-2025"
+        );
+        for raw in ["CW Ops", "cw_ops", "-cw", "cw-", "cw--ops", "ümlaut"] {
+            assert!(normalize_category_name(raw).is_err(), "{raw:?}");
+        }
+    }
+}
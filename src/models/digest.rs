@@ -0,0 +1,357 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Friends listed in `friendHighlights.topFriends`.
+const TOP_FRIENDS_LIMIT: usize = 5;
+
+/// An ISO 8601 week such as `2024-W32`, running Monday 00:00 UTC to the
+/// following Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsoWeek {
+    monday: NaiveDate,
+}
+
+impl IsoWeek {
+    /// Parse `YYYY-Www`. Week 53 is only accepted in years that have one.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid week '{s}', expected e.g. 2024-W32");
+        let (year, week) = s.split_once("-W").ok_or_else(invalid)?;
+        if year.len() != 4 || week.len() != 2 {
+            return Err(invalid());
+        }
+        let year: i32 = year.parse().map_err(|_| invalid())?;
+        let week: u32 = week.parse().map_err(|_| invalid())?;
+        let monday = NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)
+            .ok_or_else(|| format!("{year} has no week {week}"))?;
+        Ok(Self { monday })
+    }
+
+    /// The week containing `at`.
+    pub fn containing(at: DateTime<Utc>) -> Self {
+        let date = at.date_naive();
+        Self {
+            monday: date - Duration::days(date.weekday().num_days_from_monday().into()),
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        Self {
+            monday: self.monday - Duration::weeks(1),
+        }
+    }
+
+    pub fn first_day(self) -> NaiveDate {
+        self.monday
+    }
+
+    pub fn last_day(self) -> NaiveDate {
+        self.monday + Duration::days(6)
+    }
+
+    pub fn starts_at(self) -> DateTime<Utc> {
+        self.monday.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+
+    /// Exclusive: midnight on the following Monday.
+    pub fn ends_at(self) -> DateTime<Utc> {
+        self.starts_at() + Duration::weeks(1)
+    }
+}
+
+impl fmt::Display for IsoWeek {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let week = self.monday.iso_week();
+        write!(f, "{}-W{:02}", week.year(), week.week())
+    }
+}
+
+/// Query params for GET /v1/me/digest and POST /v1/admin/digests/precompute.
+#[derive(Debug, Deserialize, Default)]
+pub struct DigestQuery {
+    /// ISO week, e.g. `2024-W32`.
+    pub week: Option<String>,
+}
+
+/// The caller's own activities in the week, grouped by type.
+#[derive(Debug, Clone, FromRow)]
+pub struct ActivityCountRow {
+    pub activity_type: String,
+    pub count: i64,
+}
+
+/// One friend's activities in the week, grouped by type.
+#[derive(Debug, Clone, FromRow)]
+pub struct FriendActivityRow {
+    pub callsign: String,
+    pub activity_type: String,
+    pub count: i64,
+}
+
+/// The caller's rank in one challenge at the end of the week and before it,
+/// from `rank_history`.
+#[derive(Debug, Clone, FromRow)]
+pub struct DigestStandingRow {
+    pub challenge_id: Uuid,
+    pub name: String,
+    pub rank: i32,
+    pub score: i32,
+    pub previous_rank: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestStats {
+    pub activity_count: i64,
+    pub activities_by_type: BTreeMap<String, i64>,
+    /// Challenges whose progress was updated during the week.
+    pub challenges_progressed: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendHighlight {
+    pub callsign: String,
+    pub activity_count: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendHighlights {
+    pub active_friends: i64,
+    pub activity_count: i64,
+    pub activities_by_type: BTreeMap<String, i64>,
+    /// Most active friends first.
+    pub top_friends: Vec<FriendHighlight>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeStanding {
+    pub challenge_id: Uuid,
+    pub name: String,
+    pub rank: i32,
+    pub score: i32,
+    /// Rank before the week started; `None` when first ranked this week.
+    pub previous_rank: Option<i32>,
+    /// Places gained during the week (negative when the caller dropped).
+    pub rank_change: Option<i32>,
+}
+
+/// API response for GET /v1/me/digest; also the JSON stored in
+/// `weekly_digests`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyDigest {
+    pub week: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub your_stats: DigestStats,
+    pub friend_highlights: FriendHighlights,
+    pub challenge_standings: Vec<ChallengeStanding>,
+}
+
+impl WeeklyDigest {
+    /// Assemble a digest from the grouped query rows. A week with no rows
+    /// still has every section, zeroed.
+    pub fn build(
+        week: IsoWeek,
+        own: Vec<ActivityCountRow>,
+        challenges_progressed: i64,
+        friends: Vec<FriendActivityRow>,
+        standings: Vec<DigestStandingRow>,
+    ) -> Self {
+        let activities_by_type: BTreeMap<String, i64> = own
+            .into_iter()
+            .map(|row| (row.activity_type, row.count))
+            .collect();
+
+        let mut friend_highlights = FriendHighlights::default();
+        let mut per_friend: BTreeMap<String, i64> = BTreeMap::new();
+        for row in friends {
+            *friend_highlights
+                .activities_by_type
+                .entry(row.activity_type)
+                .or_default() += row.count;
+            *per_friend.entry(row.callsign).or_default() += row.count;
+            friend_highlights.activity_count += row.count;
+        }
+        friend_highlights.active_friends = per_friend.len() as i64;
+        let mut top_friends: Vec<FriendHighlight> = per_friend
+            .into_iter()
+            .map(|(callsign, activity_count)| FriendHighlight {
+                callsign,
+                activity_count,
+            })
+            .collect();
+        // Stable sort keeps callsign order among ties
+        top_friends.sort_by_key(|friend| Reverse(friend.activity_count));
+        top_friends.truncate(TOP_FRIENDS_LIMIT);
+        friend_highlights.top_friends = top_friends;
+
+        Self {
+            week: week.to_string(),
+            starts_at: week.starts_at(),
+            ends_at: week.ends_at(),
+            your_stats: DigestStats {
+                activity_count: activities_by_type.values().sum(),
+                activities_by_type,
+                challenges_progressed,
+            },
+            friend_highlights,
+            challenge_standings: standings
+                .into_iter()
+                .map(|row| ChallengeStanding {
+                    challenge_id: row.challenge_id,
+                    name: row.name,
+                    rank: row.rank,
+                    score: row.score,
+                    previous_rank: row.previous_rank,
+                    rank_change: row.previous_rank.map(|previous| previous - row.rank),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// API response for POST /v1/admin/digests/precompute.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestPrecomputeResponse {
+    pub week: String,
+    pub users: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_iso_week() {
+        let week = IsoWeek::parse("2024-W32").unwrap();
+        assert_eq!(week.first_day(), date("2024-08-05"));
+        assert_eq!(week.last_day(), date("2024-08-11"));
+        assert_eq!(week.ends_at() - week.starts_at(), Duration::weeks(1));
+        assert_eq!(week.to_string(), "2024-W32");
+
+        // ISO week 1 of 2025 starts in December 2024
+        assert_eq!(
+            IsoWeek::parse("2025-W01").unwrap().first_day(),
+            date("2024-12-30")
+        );
+        assert!(IsoWeek::parse("2020-W53").is_ok());
+    }
+
+    #[test]
+    fn parse_iso_week_rejects_malformed_and_missing_weeks() {
+        for bad in [
+            "",
+            "2024",
+            "2024-32",
+            "2024-W3",
+            "2024-W032",
+            "24-W32",
+            "2024-w32",
+            "2024-W00",
+            "2024-W53",
+            "2024-Wxx",
+        ] {
+            assert!(IsoWeek::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn week_containing_date() {
+        let sunday = date("2024-08-11")
+            .and_hms_opt(23, 59, 59)
+            .unwrap()
+            .and_utc();
+        assert_eq!(IsoWeek::containing(sunday).to_string(), "2024-W32");
+        assert_eq!(
+            IsoWeek::containing(sunday).previous().to_string(),
+            "2024-W31"
+        );
+
+        let new_year = date("2021-01-01").and_hms_opt(12, 0, 0).unwrap().and_utc();
+        assert_eq!(IsoWeek::containing(new_year).to_string(), "2020-W53");
+    }
+
+    #[test]
+    fn empty_week_has_zeroed_sections() {
+        let week = IsoWeek::parse("2024-W32").unwrap();
+        let digest = WeeklyDigest::build(week, vec![], 0, vec![], vec![]);
+
+        let json = serde_json::to_value(&digest).unwrap();
+        assert_eq!(json["week"], "2024-W32");
+        assert_eq!(json["startsAt"], "2024-08-05T00:00:00Z");
+        assert_eq!(
+            json["yourStats"],
+            serde_json::json!({"activityCount": 0, "activitiesByType": {}, "challengesProgressed": 0})
+        );
+        assert_eq!(
+            json["friendHighlights"],
+            serde_json::json!({"activeFriends": 0, "activityCount": 0, "activitiesByType": {}, "topFriends": []})
+        );
+        assert_eq!(json["challengeStandings"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn build_totals_friends_and_rank_movement() {
+        let friend = |callsign: &str, activity_type: &str, count| FriendActivityRow {
+            callsign: callsign.into(),
+            activity_type: activity_type.into(),
+            count,
+        };
+        let standing = |rank, previous_rank| DigestStandingRow {
+            challenge_id: Uuid::nil(),
+            name: "DXCC".into(),
+            rank,
+            score: 10,
+            previous_rank,
+        };
+        let digest = WeeklyDigest::build(
+            IsoWeek::parse("2024-W32").unwrap(),
+            vec![ActivityCountRow {
+                activity_type: "potaActivation".into(),
+                count: 2,
+            }],
+            1,
+            vec![
+                friend("K1ABC", "potaActivation", 7),
+                friend("K1ABC", "newDXCCEntity", 1),
+                friend("W2XYZ", "potaActivation", 5),
+            ],
+            vec![
+                standing(4, Some(6)),
+                standing(3, Some(1)),
+                standing(9, None),
+            ],
+        );
+
+        assert_eq!(digest.your_stats.activity_count, 2);
+        assert_eq!(digest.your_stats.challenges_progressed, 1);
+
+        let friends = &digest.friend_highlights;
+        assert_eq!(friends.active_friends, 2);
+        assert_eq!(friends.activity_count, 13);
+        assert_eq!(friends.activities_by_type["potaActivation"], 12);
+        assert_eq!(friends.top_friends[0].callsign, "K1ABC");
+        assert_eq!(friends.top_friends[0].activity_count, 8);
+
+        let changes: Vec<_> = digest
+            .challenge_standings
+            .iter()
+            .map(|s| s.rank_change)
+            .collect();
+        assert_eq!(changes, [Some(2), Some(-2), None]);
+    }
+}
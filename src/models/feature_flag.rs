@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+/// Database row for the feature_flags table.
+#[derive(Debug, Clone, FromRow)]
+pub struct FeatureFlagRow {
+    pub name: String,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// API response for one flag in GET/PUT /v1/admin/features.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlagResponse {
+    pub name: String,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<FeatureFlagRow> for FeatureFlagResponse {
+    fn from(row: FeatureFlagRow) -> Self {
+        Self {
+            name: row.name,
+            enabled: row.enabled,
+            updated_at: row.updated_at,
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::spot::sanitize_comment;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, FromRow)]
 pub struct FriendInvite {
@@ -13,6 +15,38 @@ pub struct FriendInvite {
     pub expires_at: DateTime<Utc>,
     pub used_at: Option<DateTime<Utc>>,
     pub used_by_user_id: Option<Uuid>,
+    pub note: Option<String>,
+}
+
+/// Longest invite note, in characters.
+pub const INVITE_NOTE_MAX_CHARS: usize = 140;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFriendInviteRequest {
+    /// Days until the invite expires, at most `INVITE_EXPIRY_DAYS`.
+    pub expires_in_days: Option<i64>,
+    pub note: Option<String>,
+}
+
+impl CreateFriendInviteRequest {
+    /// The note with control characters removed and whitespace trimmed;
+    /// `None` when nothing is left. Errors past `INVITE_NOTE_MAX_CHARS`.
+    pub fn sanitized_note(&self) -> Result<Option<String>, String> {
+        let Some(note) = self
+            .note
+            .as_deref()
+            .and_then(|raw| sanitize_comment(raw, usize::MAX))
+        else {
+            return Ok(None);
+        };
+        if note.chars().count() > INVITE_NOTE_MAX_CHARS {
+            return Err(format!(
+                "note must be at most {INVITE_NOTE_MAX_CHARS} characters"
+            ));
+        }
+        Ok(Some(note))
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -21,6 +55,8 @@ pub struct FriendInviteResponse {
     pub token: String,
     pub url: String,
     pub expires_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 
 impl FriendInvite {
@@ -29,6 +65,37 @@ impl FriendInvite {
             url: format!("{}/invite/{}", base_url, self.token),
             token: self.token,
             expires_at: self.expires_at,
+            note: self.note,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(note: Option<&str>) -> CreateFriendInviteRequest {
+        CreateFriendInviteRequest {
+            expires_in_days: None,
+            note: note.map(String::from),
+        }
+    }
+
+    #[test]
+    fn note_is_cleaned_and_capped() {
+        assert_eq!(
+            request(Some("  met you at\nthe hamfest\u{7}  "))
+                .sanitized_note()
+                .unwrap()
+                .as_deref(),
+            Some("met you at the hamfest")
+        );
+        assert_eq!(request(Some(" \t ")).sanitized_note().unwrap(), None);
+        assert_eq!(request(None).sanitized_note().unwrap(), None);
+
+        let longest = "x".repeat(INVITE_NOTE_MAX_CHARS);
+        assert!(request(Some(&longest)).sanitized_note().is_ok());
+        let too_long = "é".repeat(INVITE_NOTE_MAX_CHARS + 1);
+        assert!(request(Some(&too_long)).sanitized_note().is_err());
+    }
+}
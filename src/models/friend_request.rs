@@ -37,6 +37,9 @@ pub struct FriendRequestResponse {
     pub to_callsign: String,
     pub status: String,
     pub requested_at: DateTime<Utc>,
+    /// The note on the invite this request was made with, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invite_note: Option<String>,
 }
 
 impl From<FriendRequestWithCallsigns> for FriendRequestResponse {
@@ -49,6 +52,7 @@ impl From<FriendRequestWithCallsigns> for FriendRequestResponse {
             to_callsign: req.to_callsign,
             status: req.status,
             requested_at: req.requested_at,
+            invite_note: None,
         }
     }
 }
@@ -60,6 +64,8 @@ pub struct CreateFriendRequestBody {
     pub to_user_id: Option<Uuid>,
     #[serde(default)]
     pub invite_token: Option<String>,
+    #[serde(default)]
+    pub to_callsign: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, FromRow)]
+pub struct IntegrationKey {
+    pub id: Uuid,
+    pub partner: String,
+    pub key: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateIntegrationKeyRequest {
+    /// Display name of the partner, e.g. the logger's name.
+    pub partner: String,
+}
+
+/// Returned once, when the key is created.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrationKeyResponse {
+    pub id: Uuid,
+    pub partner: String,
+    pub key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<IntegrationKey> for IntegrationKeyResponse {
+    fn from(key: IntegrationKey) -> Self {
+        Self {
+            id: key.id,
+            partner: key.partner,
+            key: key.key,
+            created_at: key.created_at,
+        }
+    }
+}
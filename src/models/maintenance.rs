@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Database row for the maintenance_runs table.
+#[derive(Debug, Clone, FromRow)]
+pub struct MaintenanceRunRow {
+    pub id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Rows deleted per table, e.g. `{"friend_invites": 12}`.
+    pub pruned: serde_json::Value,
+    pub error: Option<String>,
+}
+
+/// Database row for the table_stats table.
+#[derive(Debug, Clone, FromRow)]
+pub struct TableStatRow {
+    pub table_name: String,
+    pub row_count: i64,
+}
+
+/// API response for GET /v1/admin/maintenance.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceResponse {
+    pub enabled: bool,
+    pub hour_utc: u32,
+    pub retention_days: i64,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run: Option<MaintenanceRunResponse>,
+    /// Row counts recorded by the last run, largest first.
+    pub tables: Vec<TableStatResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceRunResponse {
+    pub id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Rows deleted per table.
+    pub pruned: serde_json::Value,
+    pub error: Option<String>,
+}
+
+impl From<MaintenanceRunRow> for MaintenanceRunResponse {
+    fn from(row: MaintenanceRunRow) -> Self {
+        Self {
+            id: row.id,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+            pruned: row.pruned,
+            error: row.error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableStatResponse {
+    pub table_name: String,
+    pub row_count: i64,
+}
+
+impl From<TableStatRow> for TableStatResponse {
+    fn from(row: TableStatRow) -> Self {
+        Self {
+            table_name: row.table_name,
+            row_count: row.row_count,
+        }
+    }
+}
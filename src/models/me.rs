@@ -0,0 +1,275 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Per-program self-spot totals for one user, from `self_spot_history`.
+#[derive(Debug, Clone, FromRow)]
+pub struct ProgramSpotStatsRow {
+    pub program_slug: String,
+    pub spot_count: i64,
+    pub spots_last_30_days: i64,
+    pub last_spotted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramSpotStats {
+    pub program_slug: String,
+    pub spot_count: i64,
+    pub spots_last_30_days: i64,
+    pub last_spotted_at: DateTime<Utc>,
+}
+
+/// API response for GET /v1/me/stats.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyStatsResponse {
+    pub total_self_spots: i64,
+    pub last_spotted_at: Option<DateTime<Utc>>,
+    pub programs: Vec<ProgramSpotStats>,
+}
+
+impl From<Vec<ProgramSpotStatsRow>> for MyStatsResponse {
+    fn from(rows: Vec<ProgramSpotStatsRow>) -> Self {
+        Self {
+            total_self_spots: rows.iter().map(|r| r.spot_count).sum(),
+            last_spotted_at: rows.iter().map(|r| r.last_spotted_at).max(),
+            programs: rows
+                .into_iter()
+                .map(|r| ProgramSpotStats {
+                    program_slug: r.program_slug,
+                    spot_count: r.spot_count,
+                    spots_last_30_days: r.spots_last_30_days,
+                    last_spotted_at: r.last_spotted_at,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Query params for GET /v1/me/challenges.
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MyChallengesQuery {
+    /// Only challenges that are still active.
+    pub active: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl MyChallengesQuery {
+    /// Page size, 50 by default and between 1 and 100.
+    pub fn page_limit(&self) -> i64 {
+        self.limit.unwrap_or(50).clamp(1, 100)
+    }
+
+    /// Rows to skip; a negative offset starts at the first row.
+    pub fn page_offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+/// One joined challenge with the caller's progress and rank, from
+/// `get_joined_challenges`. Progress columns are `None` before the first report.
+#[derive(Debug, Clone, FromRow)]
+pub struct JoinedChallengeRow {
+    pub challenge_id: Uuid,
+    pub name: String,
+    pub category: String,
+    pub challenge_type: String,
+    pub is_active: bool,
+    pub configuration: serde_json::Value,
+    pub participation_status: String,
+    pub joined_at: DateTime<Utc>,
+    pub score: Option<i32>,
+    pub rank: Option<i64>,
+    pub completed_goals: Option<serde_json::Value>,
+    pub current_value: Option<i32>,
+    pub current_tier: Option<String>,
+    pub last_activity_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChallengeState {
+    Upcoming,
+    Active,
+    Ended,
+}
+
+/// A challenge's calendar window from `configuration.timeConstraints` and
+/// whether it's running now. Challenges without calendar dates are active
+/// until deactivated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeSchedule {
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub status: ChallengeState,
+}
+
+impl ChallengeSchedule {
+    pub fn from_configuration(
+        configuration: &serde_json::Value,
+        is_active: bool,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let date = |field: &str| {
+            configuration
+                .get("timeConstraints")
+                .and_then(|t| t.get(field))
+                .and_then(|d| d.as_str())
+                .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+                .map(|d| d.with_timezone(&Utc))
+        };
+        let starts_at = date("startDate");
+        let ends_at = date("endDate");
+
+        let status = if !is_active || ends_at.is_some_and(|end| end < now) {
+            ChallengeState::Ended
+        } else if starts_at.is_some_and(|start| start > now) {
+            ChallengeState::Upcoming
+        } else {
+            ChallengeState::Active
+        };
+
+        Self {
+            starts_at,
+            ends_at,
+            status,
+        }
+    }
+}
+
+/// One entry in GET /v1/me/challenges.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyChallenge {
+    pub challenge_id: Uuid,
+    pub name: String,
+    pub category: String,
+    #[serde(rename = "type")]
+    pub challenge_type: String,
+    pub schedule: ChallengeSchedule,
+    /// Participation status: `active` or `completed`.
+    pub status: String,
+    pub joined_at: DateTime<Utc>,
+    pub score: i32,
+    /// `None` until the first progress report.
+    pub rank: Option<i64>,
+    pub current_tier: Option<String>,
+    pub percentage: f64,
+    pub completed: bool,
+    pub last_activity_at: DateTime<Utc>,
+}
+
+/// API response for GET /v1/me/challenges.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyChallengesResponse {
+    pub challenges: Vec<MyChallenge>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn totals_span_all_programs() {
+        let now = Utc::now();
+        let rows = vec![
+            ProgramSpotStatsRow {
+                program_slug: "pota".to_string(),
+                spot_count: 12,
+                spots_last_30_days: 3,
+                last_spotted_at: now - Duration::days(2),
+            },
+            ProgramSpotStatsRow {
+                program_slug: "sota".to_string(),
+                spot_count: 4,
+                spots_last_30_days: 4,
+                last_spotted_at: now,
+            },
+        ];
+
+        let stats = MyStatsResponse::from(rows);
+
+        assert_eq!(stats.total_self_spots, 16);
+        assert_eq!(stats.last_spotted_at, Some(now));
+        assert_eq!(stats.programs.len(), 2);
+        assert_eq!(stats.programs[0].program_slug, "pota");
+        assert_eq!(stats.programs[0].spots_last_30_days, 3);
+    }
+
+    #[test]
+    fn no_history_is_empty() {
+        let stats = MyStatsResponse::from(Vec::new());
+        assert_eq!(stats.total_self_spots, 0);
+        assert_eq!(stats.last_spotted_at, None);
+        assert!(stats.programs.is_empty());
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn calendar(start: &str, end: &str) -> serde_json::Value {
+        serde_json::json!({
+            "timeConstraints": { "type": "calendar", "startDate": start, "endDate": end }
+        })
+    }
+
+    #[test]
+    fn schedule_status_follows_calendar_window() {
+        let config = calendar("2025-07-01T00:00:00Z", "2025-07-07T23:59:59Z");
+        let before =
+            ChallengeSchedule::from_configuration(&config, true, at("2025-06-30T12:00:00Z"));
+        assert_eq!(before.status, ChallengeState::Upcoming);
+        assert_eq!(before.starts_at, Some(at("2025-07-01T00:00:00Z")));
+
+        let during =
+            ChallengeSchedule::from_configuration(&config, true, at("2025-07-03T12:00:00Z"));
+        assert_eq!(during.status, ChallengeState::Active);
+
+        let after =
+            ChallengeSchedule::from_configuration(&config, true, at("2025-07-08T00:00:00Z"));
+        assert_eq!(after.status, ChallengeState::Ended);
+    }
+
+    #[test]
+    fn inactive_challenge_has_ended() {
+        let config = calendar("2025-07-01T00:00:00Z", "2025-07-07T23:59:59Z");
+        let schedule =
+            ChallengeSchedule::from_configuration(&config, false, at("2025-07-03T12:00:00Z"));
+        assert_eq!(schedule.status, ChallengeState::Ended);
+    }
+
+    #[test]
+    fn challenge_without_dates_is_active() {
+        let config = serde_json::json!({ "timeConstraints": { "type": "relative", "days": 30 } });
+        let schedule = ChallengeSchedule::from_configuration(&config, true, Utc::now());
+        assert_eq!(schedule.status, ChallengeState::Active);
+        assert_eq!(schedule.starts_at, None);
+        assert_eq!(schedule.ends_at, None);
+    }
+
+    #[test]
+    fn paging_is_clamped() {
+        let query = |limit, offset| MyChallengesQuery {
+            active: None,
+            limit,
+            offset,
+        };
+        assert_eq!(query(None, None).page_limit(), 50);
+        assert_eq!(query(Some(0), None).page_limit(), 1);
+        assert_eq!(query(Some(-5), None).page_limit(), 1);
+        assert_eq!(query(Some(500), None).page_limit(), 100);
+        assert_eq!(query(None, Some(-10)).page_offset(), 0);
+        assert_eq!(query(None, Some(20)).page_offset(), 20);
+    }
+}
@@ -1,21 +1,31 @@
 pub mod activity;
 pub mod badge;
+pub mod certificate;
 pub mod challenge;
+pub mod challenge_category;
 pub mod contest_definition;
+pub mod digest;
 pub mod equipment;
 pub mod event;
+pub mod feature_flag;
 pub mod club;
 pub mod friend_invite;
 pub mod friend_request;
 pub mod historic_trail;
+pub mod integration_key;
 pub mod invite;
+pub mod maintenance;
+pub mod me;
 pub mod park_boundary;
 pub mod participant;
 pub mod pota_stats;
 pub mod program;
+pub mod program_reference;
 pub mod progress;
+pub mod search;
 pub mod spot;
 pub mod spot_marker;
+pub mod spot_report;
 pub mod metrickit_telemetry;
 pub mod equipment_usage;
 pub mod upload_error_telemetry;
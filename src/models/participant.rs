@@ -23,6 +23,7 @@ pub struct ChallengeParticipant {
     pub invite_token: Option<String>,
     pub joined_at: DateTime<Utc>,
     pub status: String,
+    pub division: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -31,6 +32,14 @@ pub struct JoinChallengeRequest {
     pub callsign: String,
     pub device_name: Option<String>,
     pub invite_token: Option<String>,
+    /// Required when the challenge lists `divisions`.
+    pub division: Option<String>,
+}
+
+/// Request body for PUT /v1/challenges/:id/division.
+#[derive(Debug, Deserialize)]
+pub struct ChangeDivisionRequest {
+    pub division: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -41,6 +50,8 @@ pub struct JoinChallengeResponse {
     pub joined_at: DateTime<Utc>,
     pub status: String,
     pub historical_allowed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub division: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -50,6 +61,8 @@ pub struct ParticipationResponse {
     pub challenge_id: Uuid,
     pub joined_at: DateTime<Utc>,
     pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub division: Option<String>,
 }
 
 #[derive(Debug, Serialize, FromRow)]
@@ -1,7 +1,36 @@
 use chrono::{DateTime, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+/// Capabilities a program may declare; clients key features off these names.
+pub const PROGRAM_CAPABILITIES: &[&str] = &[
+    "adifUpload",
+    "browseSpots",
+    "dataEntry",
+    "dataVerification",
+    "hunter",
+    "locationLookup",
+    "progressTracking",
+    "referenceField",
+    "selfSpot",
+];
+
+/// The canonical spelling of a known capability, matched in any case.
+pub fn known_capability(raw: &str) -> Result<&'static str, String> {
+    let raw = raw.trim();
+    PROGRAM_CAPABILITIES
+        .iter()
+        .find(|cap| cap.eq_ignore_ascii_case(raw))
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Unknown capability '{raw}'; valid capabilities: {}",
+                PROGRAM_CAPABILITIES.join(", ")
+            )
+        })
+}
+
 /// Database row for the programs table.
 #[allow(dead_code)]
 #[derive(Debug, Clone, FromRow)]
@@ -79,6 +108,24 @@ pub struct DataEntryConfig {
     pub format: Option<String>,
 }
 
+/// Request body for POST /v1/admin/programs/:slug/capabilities.
+#[derive(Debug, Deserialize)]
+pub struct AddCapabilityRequest {
+    pub capability: String,
+}
+
+/// The fields needed to render a program's icon, embedded in spot responses
+/// with `include=program`.
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramSummary {
+    pub slug: String,
+    pub short_name: String,
+    pub icon: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+}
+
 /// API response for GET /v1/programs.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -191,3 +238,195 @@ impl From<ProgramRow> for ProgramResponse {
         }
     }
 }
+
+impl ProgramRow {
+    /// Check a value typed into the program's data-entry field against
+    /// `data_entry_format`, which must match the whole value. Surrounding and
+    /// inner whitespace is dropped first. Errors end with the placeholder as
+    /// a hint.
+    pub fn normalize_data_entry(&self, raw: &str) -> Result<String, String> {
+        let value: String = raw.split_whitespace().collect();
+        let label = self.data_entry_label.as_deref().unwrap_or("Data entry");
+        let hint = self
+            .data_entry_placeholder
+            .as_deref()
+            .map(|placeholder| format!(" (hint: {placeholder})"))
+            .unwrap_or_default();
+
+        if value.is_empty() {
+            return Err(format!("{label} is required{hint}"));
+        }
+        if let Some(format) = &self.data_entry_format {
+            let pattern = Regex::new(&format!("^(?:{format})$"))
+                .map_err(|_| format!("Program '{}' has an invalid data entry format", self.slug))?;
+            if !pattern.is_match(&value) {
+                return Err(format!("'{value}' is not a valid {label}{hint}"));
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// An uploaded program icon (FromRow over `program_icons`).
+#[derive(Debug, Clone, FromRow)]
+pub struct ProgramIcon {
+    pub content_type: String,
+    pub image_data: Vec<u8>,
+    pub etag: String,
+}
+
+impl ProgramIcon {
+    /// The strong `ETag` header value.
+    pub fn etag_header(&self) -> String {
+        format!("\"{}\"", self.etag)
+    }
+
+    /// Whether `version` is the current content's `icon_url` version.
+    pub fn is_version(&self, version: &str) -> bool {
+        self.etag.get(..ICON_VERSION_LEN) == Some(version)
+    }
+}
+
+/// Hex digits of the content hash in an uploaded icon's `icon_url` version.
+pub const ICON_VERSION_LEN: usize = 12;
+
+/// Query params for GET /v1/programs/:slug/icon.
+#[derive(Debug, Deserialize)]
+pub struct ProgramIconQuery {
+    /// Content version from `icon_url`; a current one allows immutable caching.
+    pub v: Option<String>,
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// The content type of an icon upload judged from its bytes: PNG by its
+/// signature, SVG when the UTF-8 text's root element is `<svg`. `None` for
+/// anything else, whatever the request claims.
+pub fn sniff_icon_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(PNG_SIGNATURE) {
+        return Some("image/png");
+    }
+    let mut text = std::str::from_utf8(data)
+        .ok()?
+        .trim_start_matches('\u{feff}');
+    // Skip the XML declaration, processing instructions, comments and doctype
+    loop {
+        text = text.trim_start();
+        let close = if text.starts_with("<?") {
+            "?>"
+        } else if text.starts_with("<!--") {
+            "-->"
+        } else if text.starts_with("<!") {
+            ">"
+        } else {
+            break;
+        };
+        text = &text[text.find(close)? + close.len()..];
+    }
+    let root = text.strip_prefix("<svg")?;
+    root.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .then_some("image/svg+xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_entry_program(format: &str, placeholder: &str) -> ProgramRow {
+        ProgramRow {
+            slug: "test".to_string(),
+            name: "Test Program".to_string(),
+            short_name: "Test".to_string(),
+            icon: "star".to_string(),
+            icon_url: None,
+            website: None,
+            server_base_url: None,
+            reference_label: "Reference".to_string(),
+            reference_format: None,
+            reference_example: None,
+            multi_ref_allowed: false,
+            activation_threshold: None,
+            supports_rove: false,
+            capabilities: vec!["dataEntry".to_string()],
+            adif_my_sig: None,
+            adif_my_sig_info: None,
+            adif_sig_field: None,
+            adif_sig_info_field: None,
+            data_entry_label: Some("Passkey".to_string()),
+            data_entry_placeholder: Some(placeholder.to_string()),
+            data_entry_format: Some(format.to_string()),
+            sort_order: 0,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn capabilities_match_in_any_case() {
+        assert_eq!(known_capability("selfspot"), Ok("selfSpot"));
+        assert_eq!(known_capability(" dataEntry "), Ok("dataEntry"));
+        let err = known_capability("teleport").unwrap_err();
+        assert!(
+            err.starts_with("Unknown capability 'teleport'; valid capabilities: adifUpload,"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn numeric_data_entry_is_whitespace_normalized() {
+        let program = data_entry_program("[0-9]{6}", "123456");
+        assert_eq!(program.normalize_data_entry(" 123 456 ").unwrap(), "123456");
+
+        // The format must match the whole value
+        let err = program.normalize_data_entry("1234567").unwrap_err();
+        assert_eq!(err, "'1234567' is not a valid Passkey (hint: 123456)");
+        assert!(program.normalize_data_entry("12345a").is_err());
+        assert_eq!(
+            program.normalize_data_entry("   ").unwrap_err(),
+            "Passkey is required (hint: 123456)"
+        );
+    }
+
+    #[test]
+    fn alphanumeric_data_entry_keeps_the_format_case() {
+        let program = data_entry_program("^M-[a-z0-9]{4}$", "M-a01f");
+        assert_eq!(program.normalize_data_entry("M-a01f\n").unwrap(), "M-a01f");
+        assert!(program.normalize_data_entry("M-A01F").is_err());
+        assert!(program.normalize_data_entry("a01f").is_err());
+    }
+
+    #[test]
+    fn data_entry_without_format_accepts_any_value() {
+        let mut program = data_entry_program("", "");
+        program.data_entry_format = None;
+        assert_eq!(program.normalize_data_entry("Q 1").unwrap(), "Q1");
+
+        program.data_entry_format = Some("([".to_string());
+        assert!(program
+            .normalize_data_entry("Q1")
+            .unwrap_err()
+            .contains("invalid data entry format"));
+    }
+
+    #[test]
+    fn icon_type_is_sniffed_from_the_bytes() {
+        assert_eq!(
+            sniff_icon_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some("image/png")
+        );
+        assert_eq!(
+            sniff_icon_type(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            Some("image/svg+xml")
+        );
+        let declared = "\u{feff}<?xml version=\"1.0\"?>\n<!-- logo -->\n\
+                        <!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\" \"x\">\n<svg>";
+        assert_eq!(sniff_icon_type(declared.as_bytes()), Some("image/svg+xml"));
+
+        assert_eq!(sniff_icon_type(b"\xff\xd8\xff\xe0JFIF"), None);
+        assert_eq!(sniff_icon_type(b"<html><svg></svg></html>"), None);
+        assert_eq!(sniff_icon_type(b"<svgfoo>"), None);
+        assert_eq!(sniff_icon_type(b"<!-- unterminated"), None);
+        assert_eq!(sniff_icon_type(b""), None);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::grid::grid_center;
+
+/// Query params for GET /v1/programs/:slug/references.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceSearchQuery {
+    /// Fuzzy name match, or an exact reference.
+    pub q: Option<String>,
+    /// Maidenhead locator; results are sorted by distance from its center.
+    pub near_grid: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// One reference to insert or update, from a CSV import or the POTA park
+/// catalog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewProgramReference {
+    pub reference: String,
+    pub name: String,
+    pub grid: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub active: bool,
+}
+
+/// A CSV line as written; `parse_reference_csv` validates it.
+#[derive(Debug, Deserialize)]
+struct CsvReference {
+    reference: String,
+    name: String,
+    #[serde(default)]
+    grid: Option<String>,
+    #[serde(default)]
+    latitude: Option<f64>,
+    #[serde(default)]
+    longitude: Option<f64>,
+    #[serde(default)]
+    active: Option<String>,
+}
+
+/// Parse an admin reference import. The header must name `reference` and
+/// `name`; `grid`, `latitude`, `longitude`, and `active` (`1`/`0` or
+/// `true`/`false`, default true) are optional. Coordinates default to the
+/// grid center. A reference listed twice keeps its last line.
+pub fn parse_reference_csv(raw: &str) -> Result<Vec<NewProgramReference>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(raw.as_bytes());
+
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+    let mut references: Vec<NewProgramReference> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for result in reader.records() {
+        let record = result.map_err(|e| e.to_string())?;
+        let line = record.position().map_or(0, |position| position.line());
+        let reference = record
+            .deserialize::<CsvReference>(Some(&headers))
+            .map_err(|e| e.to_string())
+            .and_then(validate_row)
+            .map_err(|e| format!("line {line}: {e}"))?;
+
+        match positions.get(&reference.reference) {
+            Some(&index) => references[index] = reference,
+            None => {
+                positions.insert(reference.reference.clone(), references.len());
+                references.push(reference);
+            }
+        }
+    }
+    Ok(references)
+}
+
+fn validate_row(row: CsvReference) -> Result<NewProgramReference, String> {
+    if row.reference.is_empty() {
+        return Err("reference is empty".to_string());
+    }
+    if row.name.is_empty() {
+        return Err(format!("name is empty for {}", row.reference));
+    }
+
+    let grid = row.grid.filter(|grid| !grid.is_empty());
+    let grid_location = match grid.as_deref() {
+        Some(grid) => Some(grid_center(grid).ok_or_else(|| format!("invalid grid '{grid}'"))?),
+        None => None,
+    };
+    let (latitude, longitude) = match (row.latitude, row.longitude) {
+        (Some(lat), Some(lon)) => {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                return Err(format!("coordinates out of range for {}", row.reference));
+            }
+            (Some(lat), Some(lon))
+        }
+        (None, None) => grid_location.unzip(),
+        _ => {
+            return Err(format!(
+                "latitude and longitude must be given together for {}",
+                row.reference
+            ))
+        }
+    };
+    let active = match row
+        .active
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        None | Some("") | Some("1") | Some("true") => true,
+        Some("0") | Some("false") => false,
+        Some(other) => return Err(format!("invalid active value '{other}'")),
+    };
+
+    Ok(NewProgramReference {
+        reference: row.reference,
+        name: row.name,
+        grid,
+        latitude,
+        longitude,
+        active,
+    })
+}
+
+/// Database row for a reference search result.
+#[derive(Debug, Clone, FromRow)]
+pub struct ProgramReferenceRow {
+    pub reference: String,
+    pub name: String,
+    pub grid: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Only set when searching near a grid.
+    pub distance_km: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramReferenceResponse {
+    pub reference: String,
+    pub name: String,
+    pub grid: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
+}
+
+impl From<ProgramReferenceRow> for ProgramReferenceResponse {
+    fn from(row: ProgramReferenceRow) -> Self {
+        Self {
+            reference: row.reference,
+            name: row.name,
+            grid: row.grid,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            distance_km: row.distance_km.map(|km| (km * 10.0).round() / 10.0),
+        }
+    }
+}
+
+/// API response for GET /v1/programs/:slug/references.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceSearchResponse {
+    pub references: Vec<ProgramReferenceResponse>,
+}
+
+/// API response for POST /v1/admin/programs/:slug/references.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceImportResponse {
+    pub program_slug: String,
+    pub imported: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_csv_with_optional_columns() {
+        let raw = "reference,name,grid,latitude,longitude,active\n\
+                   US-0001, Acadia National Park ,FN54,44.35,-68.21,1\n\
+                   US-0002,Closed Park,,,,0\n\
+                   US-0003,Grid Only Park,FN31,,,\n";
+        let refs = parse_reference_csv(raw).unwrap();
+        assert_eq!(refs.len(), 3);
+
+        assert_eq!(refs[0].name, "Acadia National Park");
+        assert_eq!(refs[0].latitude, Some(44.35));
+        assert!(refs[0].active);
+
+        assert!(!refs[1].active);
+        assert_eq!(refs[1].grid, None);
+        assert_eq!(refs[1].latitude, None);
+
+        // Coordinates fall back to the grid center
+        assert_eq!(refs[2].latitude, Some(41.5));
+        assert_eq!(refs[2].longitude, Some(-73.0));
+    }
+
+    #[test]
+    fn parse_csv_needs_only_reference_and_name() {
+        let refs = parse_reference_csv("name,reference\nMount Whitney,W6/SS-001\n").unwrap();
+        assert_eq!(refs[0].reference, "W6/SS-001");
+        assert_eq!(refs[0].grid, None);
+        assert!(refs[0].active);
+    }
+
+    #[test]
+    fn parse_csv_keeps_last_duplicate() {
+        let raw = "reference,name\nUS-0001,Old Name\nUS-0002,Other\nUS-0001,New Name\n";
+        let refs = parse_reference_csv(raw).unwrap();
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].name, "New Name");
+        assert_eq!(refs[1].reference, "US-0002");
+    }
+
+    #[test]
+    fn parse_csv_rejects_bad_lines_with_line_numbers() {
+        let cases = [
+            ("reference\nUS-0001\n", "name"),
+            ("reference,name\n,Nameless\n", "line 2: reference is empty"),
+            (
+                "reference,name,grid\nUS-0001,Park,ZZ99\n",
+                "line 2: invalid grid",
+            ),
+            ("reference,name,latitude\nUS-0001,Park,44.0\n", "together"),
+            (
+                "reference,name,latitude,longitude\nUS-0001,Park,95,0\n",
+                "out of range",
+            ),
+            (
+                "reference,name,latitude,longitude\nUS-0001,Park,north,0\n",
+                "line 2",
+            ),
+            (
+                "reference,name,active\nUS-0001,Park,1\nUS-0002,Park,maybe\n",
+                "line 3",
+            ),
+        ];
+        for (raw, expected) in cases {
+            let err = parse_reference_csv(raw).unwrap_err();
+            assert!(err.contains(expected), "{raw:?}: {err}");
+        }
+    }
+}
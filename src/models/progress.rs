@@ -46,6 +46,26 @@ pub struct ReportProgressResponse {
     pub new_badges: Vec<Uuid>,
 }
 
+/// Request body for POST /v1/challenges/:id/data-entries.
+#[derive(Debug, Deserialize)]
+pub struct DataEntryRequest {
+    pub value: String,
+}
+
+/// API response for POST /v1/challenges/:id/data-entries. `counted` is false
+/// when the value names a goal that was already completed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataEntryResponse {
+    pub entry_id: Uuid,
+    pub program_slug: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goal_id: Option<String>,
+    pub counted: bool,
+    pub server_progress: ProgressResponse,
+}
+
 #[derive(Debug, Serialize, Clone, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct LeaderboardEntry {
@@ -54,6 +74,15 @@ pub struct LeaderboardEntry {
     pub score: i32,
     pub current_tier: Option<String>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// With `movement=true`, places gained since yesterday's `rank_history`
+    /// sample (negative when dropped); null without a sample.
+    #[sqlx(default)]
+    #[serde(rename = "rankDelta24h")]
+    pub rank_delta_24h: Option<i64>,
+    /// The participant's division, for challenges with divisions.
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub division: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -71,4 +100,97 @@ pub struct LeaderboardQuery {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
     pub around: Option<String>,
+    /// Fill in `rankDelta24h`, at the cost of a join on `rank_history`.
+    #[serde(default)]
+    pub movement: bool,
+    /// Rank only this division's participants.
+    pub division: Option<String>,
+}
+
+/// One row of a challenge's top board, in rank order.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct BoardPosition {
+    pub callsign: String,
+    pub score: i32,
+}
+
+/// The participants `callsign` passed between two reads of the top board:
+/// ranked above it before and below it (or pushed off the board) after.
+/// Nobody is passed when `callsign` isn't on the board afterwards; being
+/// absent before counts as ranked below everyone listed.
+pub fn overtaken_on_board(
+    before: &[BoardPosition],
+    after: &[BoardPosition],
+    callsign: &str,
+) -> Vec<BoardPosition> {
+    let position = |board: &[BoardPosition], callsign: &str| {
+        board.iter().position(|entry| entry.callsign == callsign)
+    };
+    let Some(new_position) = position(after, callsign) else {
+        return Vec::new();
+    };
+    let old_position = position(before, callsign).unwrap_or(before.len());
+
+    before[..old_position]
+        .iter()
+        .filter(|entry| position(after, &entry.callsign).is_none_or(|p| p > new_position))
+        .map(|entry| {
+            after
+                .iter()
+                .find(|e| e.callsign == entry.callsign)
+                .unwrap_or(entry)
+                .clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(entries: &[(&str, i32)]) -> Vec<BoardPosition> {
+        entries
+            .iter()
+            .map(|(callsign, score)| BoardPosition {
+                callsign: callsign.to_string(),
+                score: *score,
+            })
+            .collect()
+    }
+
+    fn callsigns(passed: Vec<BoardPosition>) -> Vec<String> {
+        passed.into_iter().map(|entry| entry.callsign).collect()
+    }
+
+    #[test]
+    fn overtaken_lists_everyone_passed() {
+        let before = board(&[("A", 30), ("B", 20), ("C", 10), ("D", 5)]);
+        let after = board(&[("A", 30), ("D", 25), ("B", 20), ("C", 10)]);
+        assert_eq!(
+            callsigns(overtaken_on_board(&before, &after, "D")),
+            ["B", "C"]
+        );
+    }
+
+    #[test]
+    fn overtaken_is_empty_without_a_rank_change() {
+        let before = board(&[("A", 30), ("B", 20)]);
+        let after = board(&[("A", 30), ("B", 25)]);
+        assert!(overtaken_on_board(&before, &after, "B").is_empty());
+
+        // Falling back passes nobody
+        let after = board(&[("B", 20), ("A", 10)]);
+        assert!(overtaken_on_board(&before, &after, "A").is_empty());
+    }
+
+    #[test]
+    fn overtaken_counts_entering_and_pushing_off_the_board() {
+        let before = board(&[("A", 30), ("B", 20)]);
+        let after = board(&[("A", 30), ("N", 25)]);
+        let passed = overtaken_on_board(&before, &after, "N");
+        assert_eq!(passed, board(&[("B", 20)]));
+
+        // Still off the board afterwards passes nobody
+        assert!(overtaken_on_board(&before, &before, "N").is_empty());
+    }
 }
@@ -0,0 +1,71 @@
+use serde::Serialize;
+
+use super::challenge::ChallengeListItem;
+use super::spot::SpotResponse;
+use super::user::UserSearchResponse;
+
+/// One group of results in GET /v1/search.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchGroup<T> {
+    pub items: Vec<T>,
+    pub more_available: bool,
+    /// True when the group's query exceeded its time budget; `items` is empty.
+    pub timed_out: bool,
+}
+
+impl<T> SearchGroup<T> {
+    /// Build a group from up to `cap + 1` rows; the extra row only signals `more_available`.
+    pub fn from_rows<R: Into<T>>(rows: Vec<R>, cap: usize) -> Self {
+        let more_available = rows.len() > cap;
+        Self {
+            items: rows.into_iter().take(cap).map(Into::into).collect(),
+            more_available,
+            timed_out: false,
+        }
+    }
+
+    pub fn timed_out() -> Self {
+        Self {
+            items: Vec::new(),
+            more_available: false,
+            timed_out: true,
+        }
+    }
+}
+
+/// API response for GET /v1/search.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResponse {
+    pub callsigns: SearchGroup<UserSearchResponse>,
+    pub spots: SearchGroup<SpotResponse>,
+    pub challenges: SearchGroup<ChallengeListItem>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_row_sets_more_available_and_is_dropped() {
+        let group: SearchGroup<i32> = SearchGroup::from_rows(vec![1, 2, 3], 2);
+        assert_eq!(group.items, vec![1, 2]);
+        assert!(group.more_available);
+        assert!(!group.timed_out);
+    }
+
+    #[test]
+    fn rows_within_cap_are_not_flagged() {
+        let group: SearchGroup<i32> = SearchGroup::from_rows(vec![1, 2], 2);
+        assert_eq!(group.items.len(), 2);
+        assert!(!group.more_available);
+    }
+
+    #[test]
+    fn timed_out_group_is_empty() {
+        let group: SearchGroup<i32> = SearchGroup::timed_out();
+        assert!(group.items.is_empty());
+        assert!(group.timed_out);
+    }
+}
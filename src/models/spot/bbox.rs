@@ -0,0 +1,74 @@
+/// Map viewport filter for GET /v1/spots. A `min_lon` greater than `max_lon`
+/// describes a box crossing the antimeridian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub max_lat: f64,
+    pub min_lon: f64,
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    /// Build a box from optional query params. All four bounds must be given
+    /// together; none given means no box filter.
+    pub fn from_bounds(
+        min_lat: Option<f64>,
+        max_lat: Option<f64>,
+        min_lon: Option<f64>,
+        max_lon: Option<f64>,
+    ) -> Result<Option<Self>, String> {
+        let (min_lat, max_lat, min_lon, max_lon) = match (min_lat, max_lat, min_lon, max_lon) {
+            (None, None, None, None) => return Ok(None),
+            (Some(a), Some(b), Some(c), Some(d)) => (a, b, c, d),
+            _ => return Err("minLat, maxLat, minLon, and maxLon must be given together".into()),
+        };
+
+        if !(-90.0..=90.0).contains(&min_lat) || !(-90.0..=90.0).contains(&max_lat) {
+            return Err("latitude bounds must be between -90 and 90".into());
+        }
+        if !(-180.0..=180.0).contains(&min_lon) || !(-180.0..=180.0).contains(&max_lon) {
+            return Err("longitude bounds must be between -180 and 180".into());
+        }
+        if min_lat > max_lat {
+            return Err("minLat must not exceed maxLat".into());
+        }
+
+        Ok(Some(Self {
+            min_lat,
+            max_lat,
+            min_lon,
+            max_lon,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounding_box_absent_is_no_filter() {
+        assert_eq!(BoundingBox::from_bounds(None, None, None, None), Ok(None));
+    }
+
+    #[test]
+    fn bounding_box_requires_all_bounds() {
+        assert!(BoundingBox::from_bounds(Some(40.0), Some(45.0), Some(-75.0), None).is_err());
+    }
+
+    #[test]
+    fn bounding_box_rejects_out_of_range_and_inverted_latitudes() {
+        assert!(BoundingBox::from_bounds(Some(-91.0), Some(0.0), Some(0.0), Some(1.0)).is_err());
+        assert!(BoundingBox::from_bounds(Some(0.0), Some(1.0), Some(0.0), Some(181.0)).is_err());
+        assert!(BoundingBox::from_bounds(Some(45.0), Some(40.0), Some(0.0), Some(1.0)).is_err());
+    }
+
+    #[test]
+    fn bounding_box_allows_antimeridian_crossing() {
+        let bbox = BoundingBox::from_bounds(Some(-20.0), Some(10.0), Some(170.0), Some(-170.0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(bbox.min_lon, 170.0);
+        assert_eq!(bbox.max_lon, -170.0);
+    }
+}
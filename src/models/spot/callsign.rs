@@ -0,0 +1,384 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{band_for_khz, SpotRow, SpotSource};
+
+/// One activator on the air, collapsed from their unexpired spots: the latest
+/// spot's frequency and mode, plus when the first one was seen.
+#[derive(Debug, Clone, FromRow)]
+pub struct ActivatorRow {
+    pub callsign: String,
+    pub program_slug: Option<String>,
+    pub reference: Option<String>,
+    pub reference_name: Option<String>,
+    pub source: SpotSource,
+    pub frequency_khz: f64,
+    pub mode: String,
+    pub first_spotted_at: DateTime<Utc>,
+    pub last_spotted_at: DateTime<Utc>,
+    pub spot_count: i64,
+}
+
+/// API response entry for GET /v1/activators.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivatorResponse {
+    pub callsign: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_name: Option<String>,
+    pub source: SpotSource,
+    pub frequency_khz: f64,
+    pub mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub band: Option<&'static str>,
+    pub active_since: DateTime<Utc>,
+    pub last_spotted_at: DateTime<Utc>,
+    pub active_minutes: i64,
+    pub spot_count: i64,
+}
+
+impl ActivatorResponse {
+    pub fn from_row(row: ActivatorRow, now: DateTime<Utc>) -> Self {
+        Self {
+            band: band_for_khz(row.frequency_khz),
+            active_minutes: (now - row.first_spotted_at).num_minutes().max(0),
+            callsign: row.callsign,
+            program_slug: row.program_slug,
+            reference: row.reference,
+            reference_name: row.reference_name,
+            source: row.source,
+            frequency_khz: row.frequency_khz,
+            mode: row.mode,
+            active_since: row.first_spotted_at,
+            last_spotted_at: row.last_spotted_at,
+            spot_count: row.spot_count,
+        }
+    }
+
+    /// Apply the public spot redaction policy to the fields shared with
+    /// `SpotResponse`, so activators don't expose what spots hide.
+    pub fn redact_unless_authenticated(&mut self, authenticated: bool, fields: &[String]) {
+        if authenticated {
+            return;
+        }
+        for field in fields {
+            match field.as_str() {
+                "programSlug" => self.program_slug = None,
+                "reference" => self.reference = None,
+                "referenceName" => self.reference_name = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Request body for POST /v1/callsigns/status.
+#[derive(Debug, Deserialize)]
+pub struct CallsignStatusRequest {
+    pub callsigns: Vec<String>,
+}
+
+/// Whether a callsign is on the air, from its newest unexpired spot. The spot
+/// fields are present only when `onAir` is true.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallsignStatus {
+    pub callsign: String,
+    pub on_air: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_khz: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotted_at: Option<DateTime<Utc>>,
+}
+
+impl CallsignStatus {
+    /// One status per requested callsign, uppercased and in request order,
+    /// with duplicates listed once. `rows` holds at most one spot per
+    /// callsign; callsigns without one are off the air.
+    pub fn build(callsigns: &[String], rows: Vec<SpotRow>) -> Vec<Self> {
+        let mut by_callsign: BTreeMap<String, SpotRow> = rows
+            .into_iter()
+            .map(|row| (row.callsign.to_uppercase(), row))
+            .collect();
+        let mut seen = BTreeSet::new();
+        let mut statuses = Vec::new();
+        for callsign in callsigns {
+            let callsign = callsign.trim().to_uppercase();
+            if !seen.insert(callsign.clone()) {
+                continue;
+            }
+            let spot = by_callsign.remove(&callsign);
+            statuses.push(Self {
+                callsign,
+                on_air: spot.is_some(),
+                program_slug: spot.as_ref().and_then(|s| s.program_slug.clone()),
+                reference: spot.as_ref().and_then(|s| s.reference.clone()),
+                frequency_khz: spot.as_ref().map(|s| s.frequency_khz),
+                mode: spot.as_ref().map(|s| s.mode.clone()),
+                spotted_at: spot.as_ref().map(|s| s.spotted_at),
+            });
+        }
+        statuses
+    }
+
+    /// Apply the public spot redaction policy to the fields shared with
+    /// `SpotResponse`.
+    pub fn redact_unless_authenticated(&mut self, authenticated: bool, fields: &[String]) {
+        if authenticated {
+            return;
+        }
+        for field in fields {
+            match field.as_str() {
+                "programSlug" => self.program_slug = None,
+                "reference" => self.reference = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// One source's share of a callsign's unexpired spots, from
+/// `get_callsign_spot_summary`. `best_snr_spotter*` come from the row with
+/// the highest SNR and are meaningless when `best_snr` is `None`.
+#[derive(Debug, Clone, FromRow)]
+pub struct CallsignSpotSourceRow {
+    pub source: SpotSource,
+    pub spot_count: i64,
+    /// Uppercased spotter callsigns.
+    pub spotters: Vec<String>,
+    pub best_snr: Option<i16>,
+    pub best_snr_spotter: Option<String>,
+    pub best_snr_spotter_grid: Option<String>,
+    pub newest_spotted_at: DateTime<Utc>,
+    pub newest_frequency_khz: f64,
+    pub newest_mode: String,
+}
+
+/// The strongest report of a callsign among its unexpired spots.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BestSnrReport {
+    pub snr: i16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotter_grid: Option<String>,
+}
+
+/// Where a callsign was last heard.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewestSpotReport {
+    pub frequency_khz: f64,
+    pub mode: String,
+    pub spotted_at: DateTime<Utc>,
+}
+
+/// API response for GET /v1/callsigns/:callsign/spot-summary.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallsignSpotSummary {
+    pub callsign: String,
+    pub total_spots: i64,
+    /// Spot counts keyed by source name; sources without spots are omitted.
+    pub by_source: BTreeMap<&'static str, i64>,
+    pub distinct_spotters: usize,
+    pub best_snr: Option<BestSnrReport>,
+    pub newest: Option<NewestSpotReport>,
+}
+
+impl CallsignSpotSummary {
+    /// Combine per-source rows; spotters heard on several sources count once.
+    pub fn from_rows(callsign: String, rows: Vec<CallsignSpotSourceRow>) -> Self {
+        let mut spotters = BTreeSet::new();
+        let mut summary = Self {
+            callsign,
+            total_spots: 0,
+            by_source: BTreeMap::new(),
+            distinct_spotters: 0,
+            best_snr: None,
+            newest: None,
+        };
+        for row in rows {
+            summary.total_spots += row.spot_count;
+            summary
+                .by_source
+                .insert(row.source.as_str(), row.spot_count);
+            spotters.extend(row.spotters);
+
+            if let Some(snr) = row.best_snr {
+                if summary.best_snr.as_ref().is_none_or(|best| snr > best.snr) {
+                    summary.best_snr = Some(BestSnrReport {
+                        snr,
+                        spotter: row.best_snr_spotter,
+                        spotter_grid: row.best_snr_spotter_grid,
+                    });
+                }
+            }
+            if summary
+                .newest
+                .as_ref()
+                .is_none_or(|newest| row.newest_spotted_at > newest.spotted_at)
+            {
+                summary.newest = Some(NewestSpotReport {
+                    frequency_khz: row.newest_frequency_khz,
+                    mode: row.newest_mode,
+                    spotted_at: row.newest_spotted_at,
+                });
+            }
+        }
+        summary.distinct_spotters = spotters.len();
+        summary
+    }
+
+    /// Hide the best report's spotter the way the spot list would.
+    pub fn redact_unless_authenticated(&mut self, authenticated: bool, fields: &[String]) {
+        let Some(best) = self.best_snr.as_mut().filter(|_| !authenticated) else {
+            return;
+        };
+        for field in fields {
+            match field.as_str() {
+                "spotter" => best.spotter = None,
+                "spotterGrid" => best.spotter_grid = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::models::spot::tests::{sample_row, strings};
+
+    #[test]
+    fn callsign_statuses_follow_request_order_once_each() {
+        let now = Utc::now();
+        let on_air = SpotRow {
+            callsign: "w2xyz".to_string(),
+            reference: Some("US-0001".to_string()),
+            ..sample_row(now)
+        };
+        let requested = ["K1ABC", "w2xyz", " W2XYZ "].map(String::from);
+        let statuses = CallsignStatus::build(&requested, vec![on_air]);
+
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(
+            (statuses[0].callsign.as_str(), statuses[0].on_air),
+            ("K1ABC", false)
+        );
+        let json = serde_json::to_value(&statuses[0]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "callsign": "K1ABC", "onAir": false })
+        );
+
+        let mut status = statuses.into_iter().nth(1).unwrap();
+        assert_eq!(status.callsign, "W2XYZ");
+        assert!(status.on_air);
+        assert_eq!(status.frequency_khz, Some(14062.0));
+        status.redact_unless_authenticated(false, &["reference".to_string()]);
+        assert_eq!(status.reference, None);
+        assert_eq!(status.program_slug.as_deref(), Some("pota"));
+    }
+
+    fn source_row(
+        source: SpotSource,
+        spotters: &[&str],
+        best_snr: Option<i16>,
+        newest_spotted_at: DateTime<Utc>,
+    ) -> CallsignSpotSourceRow {
+        CallsignSpotSourceRow {
+            source,
+            spot_count: spotters.len() as i64,
+            spotters: spotters.iter().map(ToString::to_string).collect(),
+            best_snr,
+            best_snr_spotter: spotters.first().map(ToString::to_string),
+            best_snr_spotter_grid: Some("FN31".to_string()),
+            newest_spotted_at,
+            newest_frequency_khz: 14062.0,
+            newest_mode: "CW".to_string(),
+        }
+    }
+
+    #[test]
+    fn callsign_summary_combines_sources() {
+        let now = Utc::now();
+        let mut self_spot = source_row(SpotSource::SelfSpot, &[], None, now - Duration::minutes(1));
+        self_spot.spot_count = 1;
+        self_spot.newest_frequency_khz = 7030.0;
+        let rows = vec![
+            source_row(
+                SpotSource::Rbn,
+                &["W3LPL", "K9TM"],
+                Some(18),
+                now - Duration::minutes(3),
+            ),
+            source_row(SpotSource::Wspr, &["K9TM", "VE3EID"], Some(24), now),
+            self_spot,
+        ];
+        let mut summary = CallsignSpotSummary::from_rows("K1ABC".to_string(), rows);
+
+        assert_eq!(summary.total_spots, 5);
+        assert_eq!(summary.by_source["rbn"], 2);
+        assert_eq!(summary.by_source["wspr"], 2);
+        assert_eq!(summary.by_source["self"], 1);
+        assert_eq!(summary.distinct_spotters, 3);
+        let best = summary.best_snr.as_ref().unwrap();
+        assert_eq!((best.snr, best.spotter.as_deref()), (24, Some("K9TM")));
+        assert_eq!(summary.newest.as_ref().unwrap().frequency_khz, 14062.0);
+
+        summary.redact_unless_authenticated(false, &["spotter".to_string()]);
+        let best = summary.best_snr.unwrap();
+        assert_eq!(
+            (best.spotter, best.spotter_grid.as_deref()),
+            (None, Some("FN31"))
+        );
+    }
+
+    #[test]
+    fn callsign_summary_without_spots_is_empty() {
+        let summary = CallsignSpotSummary::from_rows("K1ABC".to_string(), Vec::new());
+        assert_eq!(summary.total_spots, 0);
+        assert!(summary.by_source.is_empty());
+        assert!(summary.best_snr.is_none() && summary.newest.is_none());
+    }
+    #[test]
+    fn activator_active_minutes_from_first_spot() {
+        let now = Utc::now();
+        let row = ActivatorRow {
+            callsign: "K1ABC".into(),
+            program_slug: Some("pota".into()),
+            reference: Some("US-0001".into()),
+            reference_name: None,
+            source: SpotSource::Pota,
+            frequency_khz: 7032.0,
+            mode: "CW".into(),
+            first_spotted_at: now - Duration::minutes(42),
+            last_spotted_at: now - Duration::minutes(2),
+            spot_count: 5,
+        };
+        let mut activator = ActivatorResponse::from_row(row, now);
+        assert_eq!(activator.active_minutes, 42);
+        assert_eq!(activator.band, Some("40m"));
+
+        activator.redact_unless_authenticated(false, &strings(&["reference", "comments"]));
+        assert_eq!(activator.reference, None);
+        assert_eq!(activator.program_slug.as_deref(), Some("pota"));
+    }
+}
@@ -0,0 +1,66 @@
+/// Clean a spot comment for display: newlines and tabs become spaces, other
+/// control characters are dropped, and the result is trimmed. Comments longer
+/// than `max_len` characters are cut and end with an ellipsis. Returns `None`
+/// when nothing printable is left.
+pub fn sanitize_comment(raw: &str, max_len: usize) -> Option<String> {
+    let cleaned: String = raw
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect();
+    let cleaned = cleaned.trim();
+
+    if cleaned.is_empty() || max_len == 0 {
+        return None;
+    }
+
+    if cleaned.chars().count() <= max_len {
+        return Some(cleaned.to_string());
+    }
+
+    let head: String = cleaned.chars().take(max_len - 1).collect();
+    Some(format!("{}…", head.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_comment_keeps_short_text() {
+        assert_eq!(
+            sanitize_comment("  QRT in 10  ", 120).as_deref(),
+            Some("QRT in 10")
+        );
+    }
+
+    #[test]
+    fn sanitize_comment_replaces_newlines_and_drops_controls() {
+        assert_eq!(
+            sanitize_comment("line one\r\nline\ttwo\u{7}\u{0}", 120).as_deref(),
+            Some("line one  line two")
+        );
+    }
+
+    #[test]
+    fn sanitize_comment_truncates_with_ellipsis() {
+        let out = sanitize_comment("abcdefghij", 5).unwrap();
+        assert_eq!(out, "abcd…");
+        assert_eq!(out.chars().count(), 5);
+    }
+
+    #[test]
+    fn sanitize_comment_truncates_on_char_boundaries() {
+        let out = sanitize_comment("日本語のコメント", 4).unwrap();
+        assert_eq!(out, "日本語…");
+    }
+
+    #[test]
+    fn sanitize_comment_empty_after_cleaning_is_none() {
+        assert_eq!(sanitize_comment("\n\r\t\u{1b}", 120), None);
+        assert_eq!(sanitize_comment("", 120), None);
+    }
+}
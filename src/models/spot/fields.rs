@@ -0,0 +1,352 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{SpotResponse, SpotSource};
+
+/// Check a redaction list against the real `SpotResponse` fields.
+pub fn validate_redacted_fields(fields: &[String]) -> Result<(), String> {
+    let known = SpotResponse::field_names();
+    for field in fields {
+        if !known.contains(field) {
+            return Err(format!("unknown spot field '{field}'"));
+        }
+        if !SpotResponse::populated_sample().redact_field(field) {
+            return Err(format!(
+                "spot field '{field}' is required and cannot be redacted"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Spot fields requested with the comma-separated `fields` query parameter
+/// of GET /v1/spots. Without it every field is returned.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpotFieldSelection(Option<BTreeSet<String>>);
+
+impl SpotFieldSelection {
+    /// Parse a `fields` value, rejecting names `SpotResponse` doesn't have.
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        let known = SpotResponse::field_names();
+        let mut fields = BTreeSet::new();
+        for name in raw.unwrap_or_default().split(',').map(str::trim) {
+            if name.is_empty() {
+                continue;
+            }
+            if !known.contains(name) {
+                let valid: Vec<_> = known.iter().map(String::as_str).collect();
+                return Err(format!(
+                    "Unknown spot field '{name}'; valid fields: {}",
+                    valid.join(", ")
+                ));
+            }
+            fields.insert(name.to_string());
+        }
+        Ok(Self(Some(fields).filter(|fields| !fields.is_empty())))
+    }
+
+    /// Whether the field with this JSON name is returned.
+    pub fn includes(&self, name: &str) -> bool {
+        self.0.as_ref().is_none_or(|fields| fields.contains(name))
+    }
+}
+
+/// A spot in a list, holding only the fields of a `SpotFieldSelection`.
+/// `id` and `spottedAt` are always present; with every field selected it
+/// serializes exactly like `SpotResponse`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SparseSpotResponse {
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callsign: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SpotSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_khz: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_mhz: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotter_grid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location_desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_abbr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snr: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wpm: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    pub spotted_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_seconds: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl SparseSpotResponse {
+    pub fn new(spot: SpotResponse, fields: &SpotFieldSelection) -> Self {
+        let keep = |name: &str| fields.includes(name);
+        Self {
+            id: spot.id,
+            callsign: keep("callsign").then_some(spot.callsign),
+            program_slug: spot.program_slug.filter(|_| keep("programSlug")),
+            source: keep("source").then_some(spot.source),
+            frequency_khz: keep("frequencyKhz").then_some(spot.frequency_khz),
+            frequency_mhz: spot.frequency_mhz.filter(|_| keep("frequencyMhz")),
+            mode: keep("mode").then_some(spot.mode),
+            reference: spot.reference.filter(|_| keep("reference")),
+            reference_name: spot.reference_name.filter(|_| keep("referenceName")),
+            spotter: spot.spotter.filter(|_| keep("spotter")),
+            spotter_grid: spot.spotter_grid.filter(|_| keep("spotterGrid")),
+            location_desc: spot.location_desc.filter(|_| keep("locationDesc")),
+            country_code: spot.country_code.filter(|_| keep("countryCode")),
+            state_abbr: spot.state_abbr.filter(|_| keep("stateAbbr")),
+            comments: spot.comments.filter(|_| keep("comments")),
+            snr: spot.snr.filter(|_| keep("snr")),
+            wpm: spot.wpm.filter(|_| keep("wpm")),
+            latitude: spot.latitude.filter(|_| keep("latitude")),
+            longitude: spot.longitude.filter(|_| keep("longitude")),
+            spotted_at: spot.spotted_at,
+            age_seconds: keep("ageSeconds").then_some(spot.age_seconds),
+            expires_at: keep("expiresAt").then_some(spot.expires_at),
+        }
+    }
+}
+
+/// Related records embedded in spot responses, requested with a
+/// comma-separated `include` query parameter.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SpotIncludes {
+    pub program: bool,
+}
+
+impl SpotIncludes {
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        let mut includes = Self::default();
+        for name in raw.unwrap_or_default().split(',').map(str::trim) {
+            match name {
+                "" => {}
+                "program" => includes.program = true,
+                other => return Err(format!("Unknown include '{other}'; expected program")),
+            }
+        }
+        Ok(includes)
+    }
+}
+
+/// Frequency units in spot list responses, from the `units` query parameter.
+/// Frequencies are stored in kHz either way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpotUnits {
+    /// `frequencyKhz` only.
+    #[default]
+    Khz,
+    /// `frequencyKhz` plus `frequencyMhz`.
+    Both,
+}
+
+impl SpotUnits {
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        match raw.map(str::trim).unwrap_or_default() {
+            "" | "khz" => Ok(Self::Khz),
+            "both" => Ok(Self::Both),
+            other => Err(format!("Unknown units '{other}'; expected khz or both")),
+        }
+    }
+}
+
+/// How `GET /v1/spots` groups its results, from the `groupBy` query parameter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpotGrouping {
+    /// One entry per spot.
+    #[default]
+    None,
+    /// One entry per activator callsign; see [`group_spot_rows`].
+    Callsign,
+}
+
+impl SpotGrouping {
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        match raw.map(str::trim).unwrap_or_default() {
+            "" => Ok(Self::None),
+            "callsign" => Ok(Self::Callsign),
+            other => Err(format!("Unknown groupBy '{other}'; expected callsign")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::models::spot::tests::strings;
+
+    #[test]
+    fn spot_includes_parse() {
+        assert_eq!(SpotIncludes::parse(None).unwrap(), SpotIncludes::default());
+        assert!(SpotIncludes::parse(Some("program")).unwrap().program);
+        assert!(SpotIncludes::parse(Some(" program,")).unwrap().program);
+        assert!(SpotIncludes::parse(Some("program,spotter")).is_err());
+    }
+    #[test]
+    fn field_names_cover_serialized_fields() {
+        let names = SpotResponse::field_names();
+        for expected in [
+            "id",
+            "callsign",
+            "frequencyKhz",
+            "spotterGrid",
+            "comments",
+            "expiresAt",
+        ] {
+            assert!(names.contains(expected), "missing {expected}");
+        }
+        assert!(!names.contains("spotter_grid"));
+    }
+
+    #[test]
+    fn spot_field_selection_rejects_unknown_names() {
+        assert_eq!(
+            SpotFieldSelection::parse(Some(" ,")).unwrap(),
+            SpotFieldSelection::default()
+        );
+        let err = SpotFieldSelection::parse(Some("callsign,frequency")).unwrap_err();
+        assert!(err.starts_with(
+            "Unknown spot field 'frequency'; valid fields: ageSeconds, callsign, comments,"
+        ));
+        assert!(err.contains("frequencyKhz"), "{err}");
+    }
+
+    fn sample_spot() -> SpotResponse {
+        let spotted_at = DateTime::parse_from_rfc3339("2025-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        SpotResponse {
+            id: Uuid::nil(),
+            callsign: "K1ABC".to_string(),
+            program_slug: Some("pota".to_string()),
+            source: SpotSource::Pota,
+            frequency_khz: 14062.0,
+            frequency_mhz: None,
+            mode: "CW".to_string(),
+            reference: Some("US-0001".to_string()),
+            reference_name: Some("Acadia National Park".to_string()),
+            spotter: Some("W1AW".to_string()),
+            spotter_grid: None,
+            location_desc: Some("US-ME".to_string()),
+            country_code: Some("US".to_string()),
+            state_abbr: Some("ME".to_string()),
+            comments: Some("QRP".to_string()),
+            snr: None,
+            wpm: Some(18),
+            latitude: Some(44.35),
+            longitude: Some(-68.21),
+            spotted_at,
+            age_seconds: 300,
+            expires_at: spotted_at + Duration::minutes(30),
+        }
+    }
+
+    #[test]
+    fn sparse_spot_keeps_selected_fields_plus_id_and_spotted_at() {
+        let fields =
+            SpotFieldSelection::parse(Some("callsign,frequencyKhz,mode,reference")).unwrap();
+        let json = serde_json::to_value(SparseSpotResponse::new(sample_spot(), &fields)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000000",
+                "callsign": "K1ABC",
+                "frequencyKhz": 14062.0,
+                "mode": "CW",
+                "reference": "US-0001",
+                "spottedAt": "2025-06-01T12:00:00Z",
+            })
+        );
+
+        // A selected field the spot doesn't have stays absent
+        let fields = SpotFieldSelection::parse(Some("snr,expiresAt")).unwrap();
+        let json = serde_json::to_value(SparseSpotResponse::new(sample_spot(), &fields)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000000",
+                "spottedAt": "2025-06-01T12:00:00Z",
+                "expiresAt": "2025-06-01T12:30:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn sparse_spot_with_all_fields_matches_full_response() {
+        let spot = SpotResponse::populated_sample();
+        let full = serde_json::to_string(&spot).unwrap();
+        let sparse = serde_json::to_string(&SparseSpotResponse::new(
+            spot,
+            &SpotFieldSelection::default(),
+        ))
+        .unwrap();
+        assert_eq!(sparse, full);
+    }
+
+    #[test]
+    fn both_units_add_frequency_in_mhz() {
+        assert_eq!(SpotUnits::parse(None).unwrap(), SpotUnits::Khz);
+        assert_eq!(SpotUnits::parse(Some("khz")).unwrap(), SpotUnits::Khz);
+        assert_eq!(SpotUnits::parse(Some("both")).unwrap(), SpotUnits::Both);
+        assert!(SpotUnits::parse(Some("mhz")).is_err());
+
+        let mut spot = sample_spot();
+        spot.apply_units(SpotUnits::Khz);
+        let json = serde_json::to_value(&spot).unwrap();
+        assert!(json.get("frequencyMhz").is_none());
+
+        spot.frequency_khz = 10136.3;
+        spot.apply_units(SpotUnits::Both);
+        let json = serde_json::to_value(&spot).unwrap();
+        assert_eq!(json["frequencyKhz"], 10136.3);
+        assert_eq!(json["frequencyMhz"], 10.1363);
+
+        // With a field selection it is kept only when listed
+        let fields = SpotFieldSelection::parse(Some("frequencyKhz")).unwrap();
+        let json = serde_json::to_value(SparseSpotResponse::new(spot, &fields)).unwrap();
+        assert!(json.get("frequencyMhz").is_none());
+    }
+    #[test]
+    fn spot_grouping_parse() {
+        assert_eq!(SpotGrouping::parse(None).unwrap(), SpotGrouping::None);
+        assert_eq!(
+            SpotGrouping::parse(Some(" callsign ")).unwrap(),
+            SpotGrouping::Callsign
+        );
+        assert!(SpotGrouping::parse(Some("reference")).is_err());
+    }
+    #[test]
+    fn validate_redacted_fields_rejects_typos_and_required_fields() {
+        assert!(validate_redacted_fields(&strings(&["spotterGrid", "comments"])).is_ok());
+        assert!(validate_redacted_fields(&strings(&["spotter_grid"])).is_err());
+        assert!(validate_redacted_fields(&strings(&["callsign"])).is_err());
+    }
+}
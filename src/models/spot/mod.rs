@@ -0,0 +1,598 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+pub mod bbox;
+pub mod callsign;
+pub mod comment;
+pub mod fields;
+pub mod response;
+
+pub use bbox::*;
+pub use callsign::*;
+pub use comment::*;
+pub use fields::*;
+pub use response::*;
+
+/// Maps to the `spot_source` postgres enum. Deserialized through [`FromStr`],
+/// so input is case-insensitive.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::Type, Serialize)]
+#[sqlx(type_name = "spot_source", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SpotSource {
+    Pota,
+    Rbn,
+    Sota,
+    Wspr,
+    #[serde(rename = "self")]
+    #[sqlx(rename = "self")]
+    SelfSpot,
+    Other,
+}
+
+/// How a submitted spot reached the server, finer-grained than its
+/// [`SpotSource`]. Stored in `spots.ingest_channel`; aggregated spots have none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, sqlx::Type, Serialize)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum IngestChannel {
+    /// POST /v1/spots from the app.
+    #[default]
+    App,
+    Bulk,
+    /// POST /v1/integrations/spots with a partner key.
+    Integration,
+}
+
+impl SpotSource {
+    pub const ALL: [SpotSource; 6] = [
+        Self::Pota,
+        Self::Rbn,
+        Self::Sota,
+        Self::Wspr,
+        Self::SelfSpot,
+        Self::Other,
+    ];
+
+    /// The lowercase name used in the database and API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pota => "pota",
+            Self::Rbn => "rbn",
+            Self::Sota => "sota",
+            Self::Wspr => "wspr",
+            Self::SelfSpot => "self",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl FromStr for SpotSource {
+    type Err = String;
+
+    /// Parse a source name in any case; `selfspot` is accepted for `self`.
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let name = raw.trim().to_ascii_lowercase();
+        if name == "selfspot" {
+            return Ok(Self::SelfSpot);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|source| source.as_str() == name)
+            .ok_or_else(|| {
+                let expected: Vec<_> = Self::ALL.iter().map(SpotSource::as_str).collect();
+                format!(
+                    "Unknown spot source '{raw}'; valid sources: {}",
+                    expected.join(", ")
+                )
+            })
+    }
+}
+
+impl<'de> Deserialize<'de> for SpotSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Database row for the spots table.
+#[allow(dead_code)]
+#[derive(Debug, Clone, FromRow)]
+pub struct SpotRow {
+    pub id: Uuid,
+    pub callsign: String,
+    pub program_slug: Option<String>,
+    pub source: SpotSource,
+    pub external_id: Option<String>,
+    pub frequency_khz: f64,
+    pub mode: String,
+    pub reference: Option<String>,
+    pub reference_name: Option<String>,
+    pub spotter: Option<String>,
+    pub spotter_grid: Option<String>,
+    pub location_desc: Option<String>,
+    pub country_code: Option<String>,
+    pub state_abbr: Option<String>,
+    pub comments: Option<String>,
+    pub snr: Option<i16>,
+    pub wpm: Option<i16>,
+    pub submitted_by: Option<Uuid>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub spotted_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for POST /v1/spots (self-spot).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSelfSpotRequest {
+    pub program_slug: String,
+    pub frequency_khz: f64,
+    pub mode: String,
+    pub reference: Option<String>,
+    pub comments: Option<String>,
+}
+
+/// Request body for POST /v1/integrations/spots.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateIntegrationSpotRequest {
+    /// The activator spotted.
+    pub callsign: String,
+    pub program_slug: String,
+    pub frequency_khz: f64,
+    pub mode: String,
+    pub reference: Option<String>,
+    pub spotter: Option<String>,
+    pub comments: Option<String>,
+}
+
+/// Data structure for aggregator upserts.
+#[derive(Debug)]
+pub struct AggregatedSpot {
+    pub callsign: String,
+    pub program_slug: Option<String>,
+    pub source: SpotSource,
+    pub external_id: String,
+    pub frequency_khz: f64,
+    /// One of `aggregators::mode::CANONICAL_MODES`.
+    pub mode: String,
+    /// The mode as the upstream sent it, kept for debugging.
+    pub raw_mode: Option<String>,
+    pub reference: Option<String>,
+    pub reference_name: Option<String>,
+    pub spotter: Option<String>,
+    pub spotter_grid: Option<String>,
+    pub location_desc: Option<String>,
+    pub country_code: Option<String>,
+    pub state_abbr: Option<String>,
+    pub comments: Option<String>,
+    pub snr: Option<i16>,
+    pub wpm: Option<i16>,
+    pub spotted_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Amateur bands as `(name, low kHz, high kHz)`, for the `band` filter.
+const BANDS: &[(&str, f64, f64)] = &[
+    ("160m", 1800.0, 2000.0),
+    ("80m", 3500.0, 4000.0),
+    ("60m", 5250.0, 5450.0),
+    ("40m", 7000.0, 7300.0),
+    ("30m", 10100.0, 10150.0),
+    ("20m", 14000.0, 14350.0),
+    ("17m", 18068.0, 18168.0),
+    ("15m", 21000.0, 21450.0),
+    ("12m", 24890.0, 24990.0),
+    ("10m", 28000.0, 29700.0),
+    ("6m", 50000.0, 54000.0),
+    ("2m", 144000.0, 148000.0),
+    ("70cm", 420000.0, 450000.0),
+];
+
+/// Look up a band by name like `20m` (case-insensitive).
+fn find_band(band: &str) -> Result<&'static (&'static str, f64, f64), String> {
+    BANDS
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(band.trim()))
+        .ok_or_else(|| {
+            let names: Vec<&str> = BANDS.iter().map(|(name, _, _)| *name).collect();
+            format!(
+                "unknown band '{band}', expected one of {}",
+                names.join(", ")
+            )
+        })
+}
+
+/// Frequency range of a band name like `20m` (case-insensitive).
+pub fn band_range_khz(band: &str) -> Result<(f64, f64), String> {
+    find_band(band).map(|&(_, low, high)| (low, high))
+}
+
+/// The canonical spelling of a band name, e.g. `20m` for `20M`.
+pub fn band_name(band: &str) -> Result<&'static str, String> {
+    find_band(band).map(|&(name, _, _)| name)
+}
+
+/// The band a frequency falls in, if any.
+pub fn band_for_khz(frequency_khz: f64) -> Option<&'static str> {
+    BANDS
+        .iter()
+        .find(|(_, low, high)| (*low..=*high).contains(&frequency_khz))
+        .map(|(name, _, _)| *name)
+}
+
+/// `program_slug` of a spot merged from a POTA and a SOTA spot.
+pub const MULTI_PROGRAM_SLUG: &str = "multi";
+
+/// POTA and SOTA spots this close in frequency count as the same signal.
+const PARK_SUMMIT_MAX_KHZ_APART: f64 = 1.0;
+
+/// Whether two spots are one station activating a park and a summit at once:
+/// the same callsign (in any case), one POTA and one SOTA spot with a
+/// reference each, on the same frequency and spotted within `window`.
+pub fn is_park_summit_pair(a: &SpotRow, b: &SpotRow, window: Duration) -> bool {
+    let programs = matches!(
+        (&a.source, &b.source),
+        (SpotSource::Pota, SpotSource::Sota) | (SpotSource::Sota, SpotSource::Pota)
+    );
+    programs
+        && a.reference.is_some()
+        && b.reference.is_some()
+        && a.callsign.eq_ignore_ascii_case(&b.callsign)
+        && (a.frequency_khz - b.frequency_khz).abs() <= PARK_SUMMIT_MAX_KHZ_APART
+        && (a.spotted_at - b.spotted_at).abs() <= window
+}
+
+/// Collapse each POTA/SOTA pair (see [`is_park_summit_pair`]) into the newer
+/// of the two spots, keeping its place in the list. A spot merges at most once. The merged spot lists
+/// both references, POTA first, and has `program_slug` [`MULTI_PROGRAM_SLUG`].
+pub fn merge_park_summit_spots(rows: Vec<SpotRow>, window: Duration) -> Vec<SpotRow> {
+    let mut merged: Vec<Option<SpotRow>> = rows.into_iter().map(Some).collect();
+    for i in 0..merged.len() {
+        for j in i + 1..merged.len() {
+            let (Some(a), Some(b)) = (&merged[i], &merged[j]) else {
+                continue;
+            };
+            let already_merged = [a, b]
+                .iter()
+                .any(|spot| spot.program_slug.as_deref() == Some(MULTI_PROGRAM_SLUG));
+            if already_merged || !is_park_summit_pair(a, b, window) {
+                continue;
+            }
+            let (keep, drop) = if a.spotted_at >= b.spotted_at {
+                (i, j)
+            } else {
+                (j, i)
+            };
+            let other = merged[drop].take().unwrap();
+            let spot = merged[keep].as_mut().unwrap();
+            let (park, summit) = if spot.source == SpotSource::Pota {
+                (&*spot, &other)
+            } else {
+                (&other, &*spot)
+            };
+            let join =
+                |park: &Option<String>, summit: &Option<String>, sep: &str| match (park, summit) {
+                    (Some(park), Some(summit)) => Some(format!("{park}{sep}{summit}")),
+                    (park, summit) => park.clone().or_else(|| summit.clone()),
+                };
+            let reference = join(&park.reference, &summit.reference, ", ");
+            let reference_name = join(&park.reference_name, &summit.reference_name, " / ");
+            spot.reference = reference;
+            spot.reference_name = reference_name;
+            spot.program_slug = Some(MULTI_PROGRAM_SLUG.to_string());
+            spot.expires_at = spot.expires_at.max(other.expires_at);
+            if spot.latitude.is_none() || spot.longitude.is_none() {
+                spot.latitude = other.latitude;
+                spot.longitude = other.longitude;
+            }
+            break;
+        }
+    }
+    merged.into_iter().flatten().collect()
+}
+
+/// Per-program patterns that derive a self-spot's country from its reference,
+/// e.g. POTA `US-1234` → `US`. Each pattern's first capture group is the
+/// country code.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceCountryPatterns(BTreeMap<String, Regex>);
+
+impl ReferenceCountryPatterns {
+    /// Parse `slug=regex` entries separated by `;` (regexes may contain commas).
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut patterns = BTreeMap::new();
+        for entry in raw.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((slug, pattern)) = entry.split_once('=') else {
+                return Err(format!("expected slug=pattern, got {entry:?}"));
+            };
+            let slug = slug.trim();
+            let regex = Regex::new(pattern.trim())
+                .map_err(|e| format!("invalid pattern for '{slug}': {e}"))?;
+            if regex.captures_len() < 2 {
+                return Err(format!(
+                    "pattern for '{slug}' needs a capture group for the country"
+                ));
+            }
+            patterns.insert(slug.to_string(), regex);
+        }
+        Ok(Self(patterns))
+    }
+
+    /// Country code for a reference, or `None` if the program has no pattern
+    /// or the reference doesn't match it.
+    pub fn country_for(&self, program_slug: &str, reference: &str) -> Option<String> {
+        let reference = reference.trim().to_ascii_uppercase();
+        self.0
+            .get(program_slug)?
+            .captures(&reference)?
+            .get(1)
+            .map(|m| m.as_str().to_string())
+            .filter(|country| !country.is_empty())
+    }
+}
+
+impl Serialize for ReferenceCountryPatterns {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(slug, re)| (slug, re.as_str())))
+    }
+}
+
+/// Per-source minutes a spot stays listed after being upserted again. When a
+/// source has an entry, a re-spot moves `expires_at` to at least `now + ttl`;
+/// other sources keep the expiry they were first stored with.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RespotTtl(BTreeMap<String, i64>);
+
+impl RespotTtl {
+    /// Parse `source=minutes` entries separated by `,`.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut ttls = BTreeMap::new();
+        for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((source, minutes)) = entry.split_once('=') else {
+                return Err(format!("expected source=minutes, got {entry:?}"));
+            };
+            let source = source.trim().to_ascii_lowercase();
+            if !SpotSource::ALL.iter().any(|s| s.as_str() == source) {
+                return Err(format!("unknown spot source '{source}'"));
+            }
+            let minutes: i64 = minutes
+                .trim()
+                .parse()
+                .ok()
+                .filter(|m| *m > 0)
+                .ok_or_else(|| {
+                    format!("TTL for '{source}' must be a positive number of minutes")
+                })?;
+            ttls.insert(source, minutes);
+        }
+        Ok(Self(ttls))
+    }
+
+    /// New expiry for a re-spot from `source` seen at `now`, or `None` if the
+    /// source's expiry isn't extended.
+    pub fn respot_expiry(&self, source: &SpotSource, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.0
+            .get(source.as_str())
+            .map(|minutes| now + Duration::minutes(*minutes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spot_source_parses_any_case() {
+        for (raw, source) in [
+            ("pota", SpotSource::Pota),
+            ("POTA", SpotSource::Pota),
+            ("rbn", SpotSource::Rbn),
+            ("Rbn", SpotSource::Rbn),
+            ("sota", SpotSource::Sota),
+            ("SOTA", SpotSource::Sota),
+            ("wspr", SpotSource::Wspr),
+            ("WSPR", SpotSource::Wspr),
+            ("self", SpotSource::SelfSpot),
+            ("SELF", SpotSource::SelfSpot),
+            ("other", SpotSource::Other),
+            (" Other ", SpotSource::Other),
+        ] {
+            assert_eq!(raw.parse::<SpotSource>().unwrap(), source, "{raw}");
+        }
+        for source in SpotSource::ALL {
+            assert_eq!(source.as_str().parse::<SpotSource>().unwrap(), source);
+        }
+    }
+
+    #[test]
+    fn spot_source_accepts_selfspot_alias() {
+        for raw in ["selfspot", "SelfSpot", "SELFSPOT"] {
+            assert_eq!(raw.parse::<SpotSource>().unwrap(), SpotSource::SelfSpot);
+        }
+        let source: SpotSource = serde_json::from_str("\"SelfSpot\"").unwrap();
+        assert_eq!(source, SpotSource::SelfSpot);
+        assert_eq!(serde_json::to_value(&source).unwrap(), "self");
+    }
+
+    #[test]
+    fn unknown_spot_source_lists_valid_sources() {
+        assert_eq!(
+            "cluster".parse::<SpotSource>().unwrap_err(),
+            "Unknown spot source 'cluster'; valid sources: pota, rbn, sota, wspr, self, other"
+        );
+        assert!("self-spot".parse::<SpotSource>().is_err());
+    }
+
+    pub(super) fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+    pub(super) fn sample_row(spotted_at: DateTime<Utc>) -> SpotRow {
+        SpotRow {
+            id: Uuid::new_v4(),
+            callsign: "K1ABC".to_string(),
+            program_slug: Some("pota".to_string()),
+            source: SpotSource::Pota,
+            external_id: None,
+            frequency_khz: 14062.0,
+            mode: "CW".to_string(),
+            reference: None,
+            reference_name: None,
+            spotter: None,
+            spotter_grid: None,
+            location_desc: None,
+            country_code: None,
+            state_abbr: None,
+            comments: None,
+            snr: None,
+            wpm: None,
+            submitted_by: None,
+            latitude: None,
+            longitude: None,
+            spotted_at,
+            expires_at: spotted_at + Duration::minutes(30),
+            created_at: spotted_at,
+            updated_at: spotted_at,
+        }
+    }
+    fn park_summit_rows(now: DateTime<Utc>) -> (SpotRow, SpotRow) {
+        let mut park = sample_row(now);
+        park.reference = Some("US-4567".to_string());
+        park.reference_name = Some("Mount Rogers NRA".to_string());
+        let mut summit = sample_row(now - Duration::minutes(4));
+        summit.program_slug = Some("sota".to_string());
+        summit.source = SpotSource::Sota;
+        summit.callsign = "k1abc".to_string();
+        summit.frequency_khz = 14062.5;
+        summit.reference = Some("W4V/SH-001".to_string());
+        summit.reference_name = Some("Mount Rogers".to_string());
+        summit.latitude = Some(36.66);
+        summit.longitude = Some(-81.54);
+        (park, summit)
+    }
+
+    #[test]
+    fn park_summit_pair_needs_same_station_frequency_and_window() {
+        let window = Duration::minutes(10);
+        let (park, summit) = park_summit_rows(Utc::now());
+        assert!(is_park_summit_pair(&park, &summit, window));
+        assert!(is_park_summit_pair(&summit, &park, window));
+        assert!(!is_park_summit_pair(&park, &summit, Duration::minutes(3)));
+
+        let mut other = summit.clone();
+        other.frequency_khz = 14070.0;
+        assert!(!is_park_summit_pair(&park, &other, window));
+
+        let mut other = summit.clone();
+        other.callsign = "K1ABD".to_string();
+        assert!(!is_park_summit_pair(&park, &other, window));
+
+        let mut other = summit.clone();
+        other.source = SpotSource::Pota;
+        assert!(!is_park_summit_pair(&park, &other, window));
+
+        let mut other = summit.clone();
+        other.reference = None;
+        assert!(!is_park_summit_pair(&park, &other, window));
+    }
+
+    #[test]
+    fn merged_park_summit_spot_lists_both_references() {
+        let now = Utc::now();
+        let (park, summit) = park_summit_rows(now);
+        let mut unrelated = sample_row(now - Duration::minutes(2));
+        unrelated.callsign = "W1AW".to_string();
+        let park_id = park.id;
+
+        let merged =
+            merge_park_summit_spots(vec![park, unrelated.clone(), summit], Duration::minutes(10));
+
+        assert_eq!(merged.len(), 2);
+        let spot = &merged[0];
+        assert_eq!(spot.id, park_id);
+        assert_eq!(spot.reference.as_deref(), Some("US-4567, W4V/SH-001"));
+        assert_eq!(
+            spot.reference_name.as_deref(),
+            Some("Mount Rogers NRA / Mount Rogers")
+        );
+        assert_eq!(spot.program_slug.as_deref(), Some(MULTI_PROGRAM_SLUG));
+        assert_eq!((spot.latitude, spot.longitude), (Some(36.66), Some(-81.54)));
+        assert_eq!(merged[1].id, unrelated.id);
+    }
+    #[test]
+    fn us_pota_reference_maps_to_us() {
+        let patterns = ReferenceCountryPatterns::parse(r"pota=^([A-Z]{2})-\d{4,5}$").unwrap();
+        assert_eq!(
+            patterns.country_for("pota", "US-1234").as_deref(),
+            Some("US")
+        );
+        assert_eq!(
+            patterns.country_for("pota", " ca-0001 ").as_deref(),
+            Some("CA")
+        );
+    }
+
+    #[test]
+    fn reference_country_unmatched_or_unconfigured() {
+        let patterns = ReferenceCountryPatterns::parse(r"pota=^([A-Z]{2})-\d{4,5}$").unwrap();
+        assert_eq!(patterns.country_for("pota", "K-0001"), None);
+        assert_eq!(patterns.country_for("sota", "W7W/LC-001"), None);
+    }
+
+    #[test]
+    fn reference_country_patterns_rejects_bad_entries() {
+        assert!(ReferenceCountryPatterns::parse("pota").is_err());
+        assert!(ReferenceCountryPatterns::parse("pota=^([A-Z]{2}").is_err());
+        assert!(ReferenceCountryPatterns::parse("pota=^[A-Z]{2}-").is_err());
+        assert!(ReferenceCountryPatterns::parse(" ; ").unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn respot_pushes_rbn_expiry_forward() {
+        let ttl = RespotTtl::parse("rbn=10, sota=30").unwrap();
+        let now = Utc::now();
+        // Expiry was set from the original spot time, 25 minutes ago
+        let stored_expiry = now - Duration::minutes(25) + Duration::minutes(30);
+
+        let extended = ttl.respot_expiry(&SpotSource::Rbn, now).unwrap();
+        assert_eq!(extended, now + Duration::minutes(10));
+        assert!(extended > stored_expiry);
+        assert_eq!(ttl.respot_expiry(&SpotSource::Pota, now), None);
+    }
+
+    #[test]
+    fn respot_ttl_rejects_bad_entries() {
+        assert!(RespotTtl::parse("")
+            .unwrap()
+            .respot_expiry(&SpotSource::Rbn, Utc::now())
+            .is_none());
+        assert!(RespotTtl::parse("rbn")
+            .unwrap_err()
+            .contains("source=minutes"));
+        assert!(RespotTtl::parse("dxcluster=10")
+            .unwrap_err()
+            .contains("unknown"));
+        assert!(RespotTtl::parse("rbn=0").unwrap_err().contains("positive"));
+        assert!(RespotTtl::parse("rbn=soon")
+            .unwrap_err()
+            .contains("positive"));
+    }
+    #[test]
+    fn band_lookup_both_ways() {
+        assert_eq!(band_range_khz("20M"), Ok((14000.0, 14350.0)));
+        assert!(band_range_khz("11m").unwrap_err().contains("160m"));
+        assert_eq!(band_for_khz(14062.0), Some("20m"));
+        assert_eq!(band_for_khz(146520.0), Some("2m"));
+        assert_eq!(band_for_khz(27185.0), None);
+    }
+}
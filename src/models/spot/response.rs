@@ -0,0 +1,619 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::{SparseSpotResponse, SpotRow, SpotSource, SpotUnits, BANDS};
+use crate::models::program::ProgramSummary;
+
+/// API response for a single spot.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotResponse {
+    pub id: Uuid,
+    pub callsign: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_slug: Option<String>,
+    pub source: SpotSource,
+    pub frequency_khz: f64,
+    /// Only with `units=both`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_mhz: Option<f64>,
+    pub mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotter_grid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location_desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_abbr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snr: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wpm: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    pub spotted_at: DateTime<Utc>,
+    /// Seconds since `spotted_at` as of when the response was built, so
+    /// clients can show the age without trusting their own clock.
+    pub age_seconds: i64,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SpotResponse {
+    /// JSON names of every field this response can serialize, generated from a
+    /// fully populated instance so the set tracks the struct definition.
+    pub fn field_names() -> BTreeSet<String> {
+        match serde_json::to_value(Self::populated_sample()) {
+            Ok(serde_json::Value::Object(map)) => map.into_iter().map(|(k, _)| k).collect(),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    /// An instance with every optional field set.
+    pub(super) fn populated_sample() -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::nil(),
+            callsign: String::new(),
+            program_slug: Some(String::new()),
+            source: SpotSource::Other,
+            frequency_khz: 0.0,
+            frequency_mhz: Some(0.0),
+            mode: String::new(),
+            reference: Some(String::new()),
+            reference_name: Some(String::new()),
+            spotter: Some(String::new()),
+            spotter_grid: Some(String::new()),
+            location_desc: Some(String::new()),
+            country_code: Some(String::new()),
+            state_abbr: Some(String::new()),
+            comments: Some(String::new()),
+            snr: Some(0),
+            wpm: Some(0),
+            latitude: Some(0.0),
+            longitude: Some(0.0),
+            spotted_at: now,
+            age_seconds: 0,
+            expires_at: now,
+        }
+    }
+
+    /// Null out a field by its JSON name. Returns false for fields that are
+    /// required in the response and so cannot be redacted.
+    pub fn redact_field(&mut self, name: &str) -> bool {
+        match name {
+            "programSlug" => self.program_slug = None,
+            "reference" => self.reference = None,
+            "referenceName" => self.reference_name = None,
+            "spotter" => self.spotter = None,
+            "spotterGrid" => self.spotter_grid = None,
+            "locationDesc" => self.location_desc = None,
+            "countryCode" => self.country_code = None,
+            "stateAbbr" => self.state_abbr = None,
+            "comments" => self.comments = None,
+            "snr" => self.snr = None,
+            "wpm" => self.wpm = None,
+            "latitude" => self.latitude = None,
+            "longitude" => self.longitude = None,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Add `frequencyMhz` when both units were requested. It is computed from
+    /// whole hertz, so 10136.3 kHz reads 10.1363 rather than 10.136299999999999.
+    pub fn apply_units(&mut self, units: SpotUnits) {
+        if units == SpotUnits::Both {
+            self.frequency_mhz = Some((self.frequency_khz * 1000.0).round() / 1_000_000.0);
+        }
+    }
+
+    /// Apply the public redaction policy unless the request is authenticated.
+    pub fn redact_unless_authenticated(&mut self, authenticated: bool, fields: &[String]) {
+        if authenticated {
+            return;
+        }
+        for field in fields {
+            self.redact_field(field);
+        }
+    }
+}
+
+impl From<SpotRow> for SpotResponse {
+    fn from(row: SpotRow) -> Self {
+        Self::from_row(row, Utc::now())
+    }
+}
+
+impl SpotResponse {
+    /// Build the response for a row, aging it as of `now`. A spot timestamped
+    /// ahead of `now` by an upstream clock reads as age 0.
+    pub fn from_row(row: SpotRow, now: DateTime<Utc>) -> Self {
+        Self {
+            age_seconds: (now - row.spotted_at).num_seconds().max(0),
+            id: row.id,
+            callsign: row.callsign,
+            program_slug: row.program_slug,
+            source: row.source,
+            frequency_khz: row.frequency_khz,
+            frequency_mhz: None,
+            mode: row.mode,
+            reference: row.reference,
+            reference_name: row.reference_name,
+            spotter: row.spotter,
+            spotter_grid: row.spotter_grid,
+            location_desc: row.location_desc,
+            country_code: row.country_code,
+            state_abbr: row.state_abbr,
+            comments: row.comments,
+            snr: row.snr,
+            wpm: row.wpm,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            spotted_at: row.spotted_at,
+            expires_at: row.expires_at,
+        }
+    }
+}
+
+/// Older spots listed with each callsign group, beyond its newest spot.
+pub const MAX_GROUPED_OTHER_SPOTS: i64 = 10;
+
+/// A spot from the grouped spot list query, with the number of spots its
+/// callsign has in the window. Rows arrive group by group, newest first.
+#[derive(Debug, Clone, FromRow)]
+pub struct GroupedSpotRow {
+    #[sqlx(flatten)]
+    pub spot: SpotRow,
+    pub spot_count: i64,
+}
+
+/// One callsign's spots in the window: the newest, how many there are, and up
+/// to [`MAX_GROUPED_OTHER_SPOTS`] of the rest, newest first.
+#[derive(Debug)]
+pub struct SpotGroup {
+    pub newest: SpotRow,
+    pub spot_count: i64,
+    pub others: Vec<SpotRow>,
+}
+
+/// Fold grouped rows into one [`SpotGroup`] per run of the same callsign,
+/// keeping the order the query returned them in.
+pub fn group_spot_rows(rows: Vec<GroupedSpotRow>) -> Vec<SpotGroup> {
+    let mut groups: Vec<SpotGroup> = Vec::new();
+    for row in rows {
+        match groups.last_mut() {
+            Some(group) if group.newest.callsign == row.spot.callsign => {
+                group.others.push(row.spot);
+            }
+            _ => groups.push(SpotGroup {
+                newest: row.spot,
+                spot_count: row.spot_count,
+                others: Vec::new(),
+            }),
+        }
+    }
+    groups
+}
+
+/// One of a group's older spots, reduced to where and how it was heard.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupedOtherSpot {
+    pub frequency_khz: f64,
+    pub mode: String,
+    pub source: SpotSource,
+    pub spotted_at: DateTime<Utc>,
+}
+
+impl From<SpotRow> for GroupedOtherSpot {
+    fn from(row: SpotRow) -> Self {
+        Self {
+            frequency_khz: row.frequency_khz,
+            mode: row.mode,
+            source: row.source,
+            spotted_at: row.spotted_at,
+        }
+    }
+}
+
+/// A callsign group in `GET /v1/spots?groupBy=callsign`: the newest spot,
+/// shaped like an ungrouped list entry, plus the group's count and older spots.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotGroupResponse {
+    #[serde(flatten)]
+    pub spot: SparseSpotResponse,
+    pub spot_count: i64,
+    pub other_spots: Vec<GroupedOtherSpot>,
+}
+
+/// Program summaries keyed by slug, for `include=program`.
+pub type SpotPrograms = BTreeMap<String, ProgramSummary>;
+
+/// API response for GET /v1/spots. With `groupBy=callsign` the entries are
+/// [`SpotGroupResponse`]s.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotsListResponse<T = SparseSpotResponse> {
+    pub spots: Vec<T>,
+    pub pagination: SpotsPagination,
+    /// Programs of the spots on this page; only with `include=program`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub programs: Option<SpotPrograms>,
+    /// Upstream sources more than three intervals behind; omitted when none.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stale_sources: Vec<&'static str>,
+}
+
+/// API response for GET /v1/spots/:id: the spot, plus its program with
+/// `include=program`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotDetailResponse {
+    #[serde(flatten)]
+    pub spot: SpotResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub programs: Option<SpotPrograms>,
+}
+
+/// API response for POST /v1/spots: the stored spot, plus the frequency as
+/// submitted and after snapping to a standard channel.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfSpotResponse {
+    #[serde(flatten)]
+    pub spot: SpotResponse,
+    pub submitted_frequency_khz: f64,
+    pub normalized_frequency_khz: f64,
+}
+
+/// API response for DELETE /v1/spots/mine.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedSpotsResponse {
+    pub deleted: u64,
+}
+
+/// Pagination metadata for spots list.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotsPagination {
+    pub has_more: bool,
+    pub next_cursor: Option<String>,
+}
+
+/// API response for GET /v1/spots/delta.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotsDeltaResponse {
+    /// Active spots added or updated since the cursor.
+    pub spots: Vec<SpotResponse>,
+    /// IDs of spots deleted or expired since the cursor.
+    pub deleted: Vec<Uuid>,
+    pub next_cursor: String,
+}
+
+/// Request body for POST /v1/spots/lookup.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotLookupRequest {
+    pub ids: Vec<Uuid>,
+}
+
+/// A spot found by POST /v1/spots/lookup. Spots past `expiresAt` that TTL
+/// cleanup hasn't removed yet are returned with `expired: true`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotLookupEntry {
+    #[serde(flatten)]
+    pub spot: SpotResponse,
+    pub expired: bool,
+}
+
+/// API response for POST /v1/spots/lookup.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotLookupResponse {
+    /// Found spots, in the order their IDs were requested.
+    pub spots: Vec<SpotLookupEntry>,
+    /// Requested IDs with no spot, e.g. removed by cleanup or by their owner.
+    pub missing_ids: Vec<Uuid>,
+}
+
+impl SpotLookupResponse {
+    /// Match fetched rows to the requested `ids`, keeping request order and
+    /// listing the IDs nothing was found for. Duplicate IDs appear once.
+    pub fn build(ids: &[Uuid], rows: Vec<SpotRow>, now: DateTime<Utc>) -> Self {
+        let mut by_id: BTreeMap<Uuid, SpotRow> = rows.into_iter().map(|r| (r.id, r)).collect();
+        let mut seen = BTreeSet::new();
+        let mut spots = Vec::new();
+        let mut missing_ids = Vec::new();
+        for &id in ids {
+            if !seen.insert(id) {
+                continue;
+            }
+            match by_id.remove(&id) {
+                Some(row) => spots.push(SpotLookupEntry {
+                    expired: row.expires_at <= now,
+                    spot: SpotResponse::from_row(row, now),
+                }),
+                None => missing_ids.push(id),
+            }
+        }
+        Self { spots, missing_ids }
+    }
+}
+
+/// Archived spots of one source and program, from `get_spots_archive_counts`.
+#[derive(Debug, Clone, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotsArchiveCountRow {
+    pub source: SpotSource,
+    pub program_slug: Option<String>,
+    pub count: i64,
+}
+
+/// API response for GET /v1/admin/spots/archive/stats.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotsArchiveStatsResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub total: i64,
+    /// Largest first.
+    pub counts: Vec<SpotsArchiveCountRow>,
+}
+
+impl SpotsArchiveStatsResponse {
+    pub fn from_counts(
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        counts: Vec<SpotsArchiveCountRow>,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            total: counts.iter().map(|row| row.count).sum(),
+            counts,
+        }
+    }
+}
+
+/// Spots in one band and UTC hour of day, from `get_spot_heatmap`.
+#[derive(Debug, Clone, FromRow)]
+pub struct SpotHeatmapCellRow {
+    pub band: String,
+    pub hour: i32,
+    pub count: i64,
+}
+
+/// One heatmap row: spot counts for a band by UTC hour of day (index 0 is
+/// 00:00-00:59 UTC).
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandHourCounts {
+    pub band: String,
+    pub hourly: [i64; 24],
+    pub total: i64,
+}
+
+/// API response for GET /v1/spots/heatmap.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotHeatmapResponse {
+    pub program: Option<String>,
+    pub days: i64,
+    pub since: DateTime<Utc>,
+    /// Bands with at least one spot, lowest frequency first.
+    pub bands: Vec<BandHourCounts>,
+    pub total: i64,
+}
+
+impl SpotHeatmapResponse {
+    /// Fold grouped `(band, hour, count)` cells into one row per band.
+    pub fn from_cells(
+        program: Option<String>,
+        days: i64,
+        since: DateTime<Utc>,
+        cells: Vec<SpotHeatmapCellRow>,
+    ) -> Self {
+        let mut bands: Vec<BandHourCounts> = Vec::new();
+        for cell in cells {
+            let Ok(hour) = usize::try_from(cell.hour) else {
+                continue;
+            };
+            if hour >= 24 {
+                continue;
+            }
+            let row = match bands.iter().position(|row| row.band == cell.band) {
+                Some(i) => &mut bands[i],
+                None => {
+                    bands.push(BandHourCounts {
+                        band: cell.band,
+                        hourly: [0; 24],
+                        total: 0,
+                    });
+                    bands.last_mut().unwrap()
+                }
+            };
+            row.hourly[hour] += cell.count;
+            row.total += cell.count;
+        }
+        bands.sort_by_key(|row| {
+            BANDS
+                .iter()
+                .position(|(name, _, _)| *name == row.band)
+                .unwrap_or(BANDS.len())
+        });
+
+        Self {
+            program,
+            days,
+            since,
+            total: bands.iter().map(|row| row.total).sum(),
+            bands,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::models::spot::tests::{sample_row, strings};
+
+    #[test]
+    fn grouped_rows_fold_into_one_group_per_callsign() {
+        let now = Utc::now();
+        let row = |callsign: &str, minutes_ago: i64, spot_count: i64| GroupedSpotRow {
+            spot: SpotRow {
+                callsign: callsign.to_string(),
+                ..sample_row(now - Duration::minutes(minutes_ago))
+            },
+            spot_count,
+        };
+        let groups = group_spot_rows(vec![
+            row("K1ABC", 1, 14),
+            row("K1ABC", 4, 14),
+            row("K1ABC", 9, 14),
+            row("W2XYZ", 2, 1),
+        ]);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].newest.callsign, "K1ABC");
+        assert_eq!(groups[0].newest.spotted_at, now - Duration::minutes(1));
+        // The count covers the whole window, not just the spots returned
+        assert_eq!(groups[0].spot_count, 14);
+        assert_eq!(groups[0].others.len(), 2);
+        assert_eq!(groups[1].newest.callsign, "W2XYZ");
+        assert!(groups[1].others.is_empty());
+
+        let json =
+            serde_json::to_value(GroupedOtherSpot::from(groups[0].others[0].clone())).unwrap();
+        let keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        assert_eq!(keys, ["frequencyKhz", "mode", "source", "spottedAt"]);
+    }
+    #[test]
+    fn spot_age_is_measured_from_now() {
+        let spotted_at = DateTime::parse_from_rfc3339("2025-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let row = sample_row(spotted_at);
+
+        let now = spotted_at + Duration::seconds(325);
+        let spot = SpotResponse::from_row(row.clone(), now);
+        assert_eq!(spot.age_seconds, 325);
+        assert_eq!(serde_json::to_value(&spot).unwrap()["ageSeconds"], 325);
+
+        // A spot stamped ahead of the server clock isn't given a negative age
+        let ahead = SpotResponse::from_row(row, spotted_at - Duration::seconds(20));
+        assert_eq!(ahead.age_seconds, 0);
+    }
+
+    #[test]
+    fn lookup_keeps_request_order_and_lists_missing_ids() {
+        let now = Utc::now();
+        let active = sample_row(now - Duration::minutes(5));
+        let expired = sample_row(now - Duration::minutes(45));
+        let unknown = Uuid::new_v4();
+        let ids = [expired.id, unknown, active.id, expired.id];
+
+        let lookup = SpotLookupResponse::build(&ids, vec![active.clone(), expired.clone()], now);
+
+        let found: Vec<(Uuid, bool)> = lookup
+            .spots
+            .iter()
+            .map(|entry| (entry.spot.id, entry.expired))
+            .collect();
+        assert_eq!(found, vec![(expired.id, true), (active.id, false)]);
+        assert_eq!(lookup.missing_ids, vec![unknown]);
+
+        let json = serde_json::to_value(&lookup).unwrap();
+        assert_eq!(json["spots"][0]["expired"], true);
+        assert_eq!(json["spots"][0]["callsign"], "K1ABC");
+        assert_eq!(json["missingIds"][0], unknown.to_string());
+    }
+
+    #[test]
+    fn every_optional_field_is_redactable() {
+        let mut spot = SpotResponse::populated_sample();
+        for name in SpotResponse::field_names() {
+            let required = matches!(
+                name.as_str(),
+                "id" | "callsign"
+                    | "source"
+                    | "frequencyKhz"
+                    | "frequencyMhz"
+                    | "mode"
+                    | "spottedAt"
+                    | "ageSeconds"
+                    | "expiresAt"
+            );
+            assert_eq!(spot.redact_field(&name), !required, "{name}");
+        }
+    }
+    #[test]
+    fn unauthenticated_response_is_redacted() {
+        let mut spot = SpotResponse::populated_sample();
+        spot.redact_unless_authenticated(false, &strings(&["spotterGrid", "comments"]));
+        assert_eq!(spot.spotter_grid, None);
+        assert_eq!(spot.comments, None);
+        assert!(spot.spotter.is_some());
+    }
+
+    #[test]
+    fn authenticated_response_is_complete() {
+        let mut spot = SpotResponse::populated_sample();
+        spot.redact_unless_authenticated(true, &strings(&["spotterGrid", "comments"]));
+        assert!(spot.spotter_grid.is_some());
+        assert!(spot.comments.is_some());
+    }
+    #[test]
+    fn heatmap_folds_cells_into_band_rows() {
+        let cell = |band: &str, hour, count| SpotHeatmapCellRow {
+            band: band.to_string(),
+            hour,
+            count,
+        };
+        let heatmap = SpotHeatmapResponse::from_cells(
+            Some("pota".to_string()),
+            7,
+            Utc::now(),
+            vec![
+                cell("20m", 14, 30),
+                cell("40m", 2, 5),
+                cell("20m", 15, 12),
+                cell("40m", 23, 1),
+            ],
+        );
+
+        let bands: Vec<&str> = heatmap.bands.iter().map(|row| row.band.as_str()).collect();
+        assert_eq!(bands, ["40m", "20m"]);
+        let twenty = &heatmap.bands[1];
+        assert_eq!(twenty.hourly[14], 30);
+        assert_eq!(twenty.hourly[15], 12);
+        assert_eq!(twenty.hourly[0], 0);
+        assert_eq!(twenty.total, 42);
+        assert_eq!(heatmap.total, 48);
+
+        let empty = SpotHeatmapResponse::from_cells(None, 30, Utc::now(), vec![]);
+        assert!(empty.bands.is_empty());
+        assert_eq!(empty.total, 0);
+    }
+}
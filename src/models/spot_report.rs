@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::spot::{IngestChannel, SpotResponse, SpotRow};
+
+/// Request body for POST /v1/spots/:id/report.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSpotRequest {
+    pub reason: String,
+}
+
+/// Database row for the spot_reports table.
+#[derive(Debug, Clone, FromRow)]
+pub struct SpotReportRow {
+    pub spot_id: Uuid,
+    pub reporter_callsign: String,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A reported spot with its aggregate report count.
+#[derive(Debug, Clone, FromRow)]
+pub struct ReportedSpotRow {
+    #[sqlx(flatten)]
+    pub spot: SpotRow,
+    pub ingest_channel: Option<IngestChannel>,
+    pub report_count: i64,
+}
+
+/// A single report as shown in the moderation queue.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotReportResponse {
+    pub reporter_callsign: String,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Entry in GET /v1/admin/spots/reports.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedSpotResponse {
+    pub spot: SpotResponse,
+    pub expired: bool,
+    /// How a submitted spot arrived (`app`, `bulk`, `integration`); null for
+    /// aggregated spots.
+    pub ingest_channel: Option<IngestChannel>,
+    pub report_count: i64,
+    pub reports: Vec<SpotReportResponse>,
+}
+
+/// Attach each spot's reports, preserving the order of `spots`.
+pub fn build_report_queue(
+    spots: Vec<ReportedSpotRow>,
+    reports: Vec<SpotReportRow>,
+    now: DateTime<Utc>,
+) -> Vec<ReportedSpotResponse> {
+    let mut by_spot: HashMap<Uuid, Vec<SpotReportResponse>> = HashMap::new();
+    for r in reports {
+        by_spot
+            .entry(r.spot_id)
+            .or_default()
+            .push(SpotReportResponse {
+                reporter_callsign: r.reporter_callsign,
+                reason: r.reason,
+                created_at: r.created_at,
+            });
+    }
+
+    spots
+        .into_iter()
+        .map(|row| ReportedSpotResponse {
+            expired: row.spot.expires_at <= now,
+            ingest_channel: row.ingest_channel,
+            report_count: row.report_count,
+            reports: by_spot.remove(&row.spot.id).unwrap_or_default(),
+            spot: row.spot.into(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::spot::SpotSource;
+    use chrono::Duration;
+
+    fn spot(callsign: &str, expires_at: DateTime<Utc>) -> SpotRow {
+        let now = Utc::now();
+        SpotRow {
+            id: Uuid::new_v4(),
+            callsign: callsign.to_string(),
+            program_slug: Some("pota".to_string()),
+            source: SpotSource::SelfSpot,
+            external_id: None,
+            frequency_khz: 14062.0,
+            mode: "CW".to_string(),
+            reference: None,
+            reference_name: None,
+            spotter: None,
+            spotter_grid: None,
+            location_desc: None,
+            country_code: None,
+            state_abbr: None,
+            comments: None,
+            snr: None,
+            wpm: None,
+            submitted_by: None,
+            latitude: None,
+            longitude: None,
+            spotted_at: now,
+            expires_at,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn report(spot_id: Uuid, reporter: &str, reason: &str) -> SpotReportRow {
+        SpotReportRow {
+            spot_id,
+            reporter_callsign: reporter.to_string(),
+            reason: reason.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn groups_reports_under_their_spots_in_queue_order() {
+        let now = Utc::now();
+        let busy = spot("W1AW", now + Duration::minutes(10));
+        let quiet = spot("K1ABC", now - Duration::minutes(1));
+        let (busy_id, quiet_id) = (busy.id, quiet.id);
+
+        let spots = vec![
+            ReportedSpotRow {
+                spot: busy,
+                ingest_channel: Some(IngestChannel::App),
+                report_count: 3,
+            },
+            ReportedSpotRow {
+                spot: quiet,
+                ingest_channel: None,
+                report_count: 1,
+            },
+        ];
+        let reports = vec![
+            report(busy_id, "N1A", "fake spot"),
+            report(quiet_id, "N1B", "phone number in comment"),
+            report(busy_id, "N1C", "spam"),
+            report(busy_id, "N1D", "spam"),
+        ];
+
+        let queue = build_report_queue(spots, reports, now);
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].spot.callsign, "W1AW");
+        assert_eq!(queue[0].report_count, 3);
+        assert_eq!(queue[0].reports.len(), 3);
+        assert!(!queue[0].expired);
+        assert_eq!(queue[0].ingest_channel, Some(IngestChannel::App));
+
+        assert_eq!(queue[1].spot.callsign, "K1ABC");
+        assert_eq!(queue[1].reports.len(), 1);
+        assert_eq!(queue[1].reports[0].reason, "phone number in comment");
+        assert!(queue[1].expired);
+    }
+
+    #[test]
+    fn spot_without_loaded_reports_gets_empty_list() {
+        let now = Utc::now();
+        let spots = vec![ReportedSpotRow {
+            spot: spot("W1AW", now + Duration::minutes(5)),
+            ingest_channel: None,
+            report_count: 2,
+        }];
+
+        let queue = build_report_queue(spots, Vec::new(), now);
+
+        assert_eq!(queue[0].report_count, 2);
+        assert!(queue[0].reports.is_empty());
+    }
+}
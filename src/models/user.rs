@@ -1,5 +1,7 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -68,9 +70,174 @@ pub struct AdminStatsResponse {
     pub users_last_30_days: i64,
 }
 
+#[derive(Debug, Clone, FromRow)]
+pub struct AdminUserRow {
+    pub id: Uuid,
+    pub callsign: String,
+    pub created_at: DateTime<Utc>,
+    pub suspended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUserResponse {
+    pub id: Uuid,
+    pub callsign: String,
+    pub created_at: DateTime<Utc>,
+    pub suspended: bool,
+    pub suspended_at: Option<DateTime<Utc>>,
+}
+
+impl From<AdminUserRow> for AdminUserResponse {
+    fn from(row: AdminUserRow) -> Self {
+        Self {
+            id: row.id,
+            callsign: row.callsign,
+            created_at: row.created_at,
+            suspended: row.suspended_at.is_some(),
+            suspended_at: row.suspended_at,
+        }
+    }
+}
+
+/// Suspension filter for the admin user listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserStatusFilter {
+    All,
+    Active,
+    Suspended,
+}
+
+impl UserStatusFilter {
+    /// Map to the `suspended` argument of `db::list_users_admin`.
+    pub fn as_suspended(self) -> Option<bool> {
+        match self {
+            Self::All => None,
+            Self::Active => Some(false),
+            Self::Suspended => Some(true),
+        }
+    }
+}
+
+/// Request body for POST /v1/admin/users/merge.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeUsersRequest {
+    /// Account whose data moves; suspended afterwards.
+    pub from: Uuid,
+    pub to: Uuid,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeUsersQuery {
+    /// Report what would change without committing.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Rows reassigned to the target account, and duplicates dropped instead, for one table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MergeTableCount {
+    pub moved: u64,
+    pub dropped: u64,
+}
+
+/// Per-table merge counts, keyed by table name.
+pub type MergeCounts = BTreeMap<&'static str, MergeTableCount>;
+
+/// API response for POST /v1/admin/users/merge.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeUsersResponse {
+    pub from: AdminUserResponse,
+    pub to: AdminUserResponse,
+    pub dry_run: bool,
+    pub tables: MergeCounts,
+}
+
+/// Reject merges that can't be undone sensibly: an account into itself, or
+/// into a suspended account.
+pub fn check_merge(from: &AdminUserRow, to: &AdminUserRow) -> Result<(), String> {
+    if from.id == to.id {
+        return Err("cannot merge a user into itself".to_string());
+    }
+    if to.suspended_at.is_some() {
+        return Err(format!("target account {} is suspended", to.callsign));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Serialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct UserCountByHour {
     pub hour: DateTime<Utc>,
     pub count: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_filter_maps_to_suspended_flag() {
+        assert_eq!(UserStatusFilter::All.as_suspended(), None);
+        assert_eq!(UserStatusFilter::Active.as_suspended(), Some(false));
+        assert_eq!(UserStatusFilter::Suspended.as_suspended(), Some(true));
+    }
+
+    #[test]
+    fn unsuspend_clears_response_flag() {
+        let now = Utc::now();
+        let mut row = AdminUserRow {
+            id: Uuid::new_v4(),
+            callsign: "W1AW".to_string(),
+            created_at: now,
+            suspended_at: Some(now),
+        };
+        assert!(AdminUserResponse::from(row.clone()).suspended);
+
+        row.suspended_at = None;
+        let resp = AdminUserResponse::from(row);
+        assert!(!resp.suspended);
+        assert_eq!(resp.callsign, "W1AW");
+    }
+
+    fn admin_row(callsign: &str, suspended: bool) -> AdminUserRow {
+        AdminUserRow {
+            id: Uuid::new_v4(),
+            callsign: callsign.to_string(),
+            created_at: Utc::now(),
+            suspended_at: suspended.then(Utc::now),
+        }
+    }
+
+    #[test]
+    fn merge_into_self_or_suspended_target_is_rejected() {
+        let from = admin_row("KD9ABC", false);
+        let to = admin_row("K9XYZ", false);
+        assert!(check_merge(&from, &to).is_ok());
+        assert!(check_merge(&from, &from).unwrap_err().contains("itself"));
+
+        let suspended = admin_row("K9XYZ", true);
+        assert!(check_merge(&from, &suspended)
+            .unwrap_err()
+            .contains("suspended"));
+    }
+
+    #[test]
+    fn merge_source_may_already_be_suspended() {
+        let from = admin_row("KD9ABC", true);
+        let to = admin_row("K9XYZ", false);
+        assert!(check_merge(&from, &to).is_ok());
+    }
+
+    #[test]
+    fn merge_query_defaults_to_commit() {
+        let query: MergeUsersQuery = serde_json::from_str("{}").unwrap();
+        assert!(!query.dry_run);
+        let query: MergeUsersQuery = serde_json::from_str(r#"{"dryRun":true}"#).unwrap();
+        assert!(query.dry_run);
+    }
+}
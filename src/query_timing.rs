@@ -0,0 +1,288 @@
+//! Per-request database timing and slow query/request logging.
+//!
+//! sqlx logs every statement under the `sqlx::query` target with its elapsed
+//! time (at TRACE, or WARN past `DB_SLOW_QUERY_MS`). `layer()` picks those
+//! events up and adds them to the totals of the request whose task ran the
+//! query; `track_request` records the totals on the request span, so they
+//! appear on tower-http's response log line, and warns about requests slower
+//! than `SLOW_REQUEST_MS`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use sqlx::postgres::PgConnectOptions;
+use sqlx::ConnectOptions;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Span, Subscriber};
+use tracing_subscriber::filter::{Filtered, Targets};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Target sqlx logs statements under.
+const QUERY_TARGET: &str = "sqlx::query";
+
+/// Query parameters whose values are never logged.
+const SENSITIVE_PARAMS: &[&str] = &["token", "key", "secret", "password", "auth", "signature"];
+
+/// Connect options for `database_url` that log statements for `layer()` and
+/// flag those slower than `slow_query_ms` (0 disables the slow log).
+pub fn connect_options(
+    database_url: &str,
+    slow_query_ms: u64,
+) -> Result<PgConnectOptions, sqlx::Error> {
+    let options: PgConnectOptions = database_url.parse()?;
+    let options = options.log_statements(log::LevelFilter::Trace);
+    Ok(if slow_query_ms == 0 {
+        // A zero duration would make every statement "slow" and, at Off, unlogged
+        options.log_slow_statements(log::LevelFilter::Off, Duration::MAX)
+    } else {
+        options.log_slow_statements(log::LevelFilter::Warn, Duration::from_millis(slow_query_ms))
+    })
+}
+
+/// Database totals for one request.
+#[derive(Debug, Default)]
+struct DbStats {
+    queries: AtomicU64,
+    slow_queries: AtomicU64,
+    micros: AtomicU64,
+}
+
+tokio::task_local! {
+    static REQUEST_DB_STATS: Arc<DbStats>;
+}
+
+/// Snapshot of the queries run while measuring a future.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DbTotals {
+    pub queries: u64,
+    pub slow_queries: u64,
+    pub time: Duration,
+}
+
+/// Run `future`, counting the queries it runs in its own task.
+pub async fn measure<F: std::future::Future>(future: F) -> (F::Output, DbTotals) {
+    let stats = Arc::new(DbStats::default());
+    let output = REQUEST_DB_STATS.scope(stats.clone(), future).await;
+    let totals = DbTotals {
+        queries: stats.queries.load(Ordering::Relaxed),
+        slow_queries: stats.slow_queries.load(Ordering::Relaxed),
+        time: Duration::from_micros(stats.micros.load(Ordering::Relaxed)),
+    };
+    (output, totals)
+}
+
+/// Tracing layer adding sqlx statement timings to the running request's totals.
+pub struct QueryTimingLayer;
+
+/// `QueryTimingLayer` with its own filter, so statement events are enabled for
+/// it even when the log output filter hides them.
+pub fn layer<S>() -> Filtered<QueryTimingLayer, Targets, S>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    QueryTimingLayer.with_filter(Targets::new().with_target(QUERY_TARGET, Level::TRACE))
+}
+
+impl<S: Subscriber> Layer<S> for QueryTimingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != QUERY_TARGET {
+            return;
+        }
+        let mut visitor = QueryEventVisitor::default();
+        event.record(&mut visitor);
+        let Some(elapsed_secs) = visitor.elapsed_secs else {
+            return;
+        };
+        // Outside a measured request (background tasks) there is nothing to add to
+        let _ = REQUEST_DB_STATS.try_with(|stats| {
+            stats.queries.fetch_add(1, Ordering::Relaxed);
+            stats
+                .micros
+                .fetch_add((elapsed_secs * 1_000_000.0) as u64, Ordering::Relaxed);
+            if visitor.slow {
+                stats.slow_queries.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+struct QueryEventVisitor {
+    elapsed_secs: Option<f64>,
+    slow: bool,
+}
+
+impl Visit for QueryEventVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed_secs = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, _value: &dyn std::fmt::Debug) {
+        // Only slow statements carry their threshold
+        if field.name() == "slow_threshold" {
+            self.slow = true;
+        }
+    }
+}
+
+/// Request span for `TraceLayer`, with empty DB fields `track_request` fills.
+/// Uses tower-http's default span target so log filters for it still apply.
+pub fn make_request_span(req: &Request<Body>) -> Span {
+    tracing::debug_span!(
+        target: "tower_http::trace::make_span",
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        version = ?req.version(),
+        db_ms = tracing::field::Empty,
+        db_queries = tracing::field::Empty,
+    )
+}
+
+/// Requests slower than this are logged as slow; zero disables the warning.
+#[derive(Debug, Clone, Copy)]
+pub struct SlowRequestThreshold(pub Duration);
+
+/// Middleware measuring a request's database time, recording it on the
+/// request span, and warning when the request as a whole was slow.
+pub async fn track_request(
+    State(threshold): State<SlowRequestThreshold>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let method = req.method().clone();
+    let params = req.uri().query().map(sanitize_query);
+
+    let start = Instant::now();
+    let (response, db) = measure(next.run(req)).await;
+    let elapsed = start.elapsed();
+
+    let span = Span::current();
+    span.record("db_ms", db.time.as_millis() as u64);
+    span.record("db_queries", db.queries);
+
+    if !threshold.0.is_zero() && elapsed >= threshold.0 {
+        tracing::warn!(
+            %method,
+            route,
+            params = params.as_deref().unwrap_or(""),
+            status = response.status().as_u16(),
+            elapsed_ms = elapsed.as_millis() as u64,
+            db_ms = db.time.as_millis() as u64,
+            db_queries = db.queries,
+            db_slow_queries = db.slow_queries,
+            "slow request"
+        );
+    }
+
+    response
+}
+
+/// A query string with the values of sensitive parameters replaced.
+fn sanitize_query(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, _) = pair.split_once('=').unwrap_or((pair, ""));
+            let lower = name.to_ascii_lowercase();
+            if SENSITIVE_PARAMS.iter().any(|s| lower.contains(s)) {
+                format!("{name}=[redacted]")
+            } else {
+                pair.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn sanitize_query_redacts_sensitive_values() {
+        assert_eq!(
+            sanitize_query("program=pota&token=abc123&apiKey=xyz&limit=5"),
+            "program=pota&token=[redacted]&apiKey=[redacted]&limit=5"
+        );
+        assert_eq!(sanitize_query("flag&&q=park"), "flag&q=park");
+        assert_eq!(sanitize_query(""), "");
+    }
+
+    #[tokio::test]
+    async fn layer_adds_query_events_to_measured_totals() {
+        let subscriber = tracing_subscriber::registry().with(layer());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (_, totals) = measure(async {
+            tracing::trace!(target: "sqlx::query", elapsed_secs = 0.002, "SELECT 1");
+            tracing::warn!(
+                target: "sqlx::query",
+                elapsed_secs = 0.5,
+                slow_threshold = ?Duration::from_millis(250),
+                "slow statement"
+            );
+            tracing::info!(elapsed_secs = 9.0, "not a query");
+        })
+        .await;
+        assert_eq!(totals.queries, 2);
+        assert_eq!(totals.slow_queries, 1);
+        assert_eq!(totals.time, Duration::from_micros(502_000));
+
+        // Queries outside a measured future are ignored
+        tracing::trace!(target: "sqlx::query", elapsed_secs = 0.002, "SELECT 1");
+    }
+
+    /// Run one `pg_sleep(0.2)` and one fast query with the given slow-query
+    /// threshold.
+    async fn sleep_with_threshold(slow_query_ms: u64) -> DbTotals {
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect_with(connect_options(&url, slow_query_ms).unwrap())
+            .await
+            .unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (_, totals) = measure(async {
+            sqlx::query("SELECT pg_sleep(0.2)")
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+        })
+        .await;
+        totals
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn slow_query_threshold_is_honored() {
+        let totals = sleep_with_threshold(100).await;
+        assert_eq!(totals.queries, 2);
+        assert_eq!(totals.slow_queries, 1);
+        assert!(totals.time >= Duration::from_millis(200), "{totals:?}");
+
+        let totals = sleep_with_threshold(1000).await;
+        assert_eq!(totals.queries, 2);
+        assert_eq!(totals.slow_queries, 0);
+    }
+}
@@ -4,6 +4,8 @@ use tokio::net::TcpStream;
 
 use super::store::{freq_to_band, RbnSpot, SpotStore};
 use crate::metrics as app_metrics;
+use crate::models::spot::SpotSource;
+use crate::server::ServerState;
 
 const RBN_HOST: &str = "telnet.reversebeacon.net";
 const RBN_PORT: u16 = 7000;
@@ -13,20 +15,26 @@ const BATCH_FLUSH_MS: u64 = 500;
 const INITIAL_BACKOFF_SECS: u64 = 5;
 const MAX_BACKOFF_SECS: u64 = 300;
 
+/// RBN streams spots rather than being polled; a minute without a batch
+/// counts as one missed interval for freshness reporting.
+pub const FRESHNESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// Spawn the RBN telnet ingester as a background tokio task.
-pub fn spawn_rbn_ingester(store: SpotStore, callsign: String) {
+pub fn spawn_rbn_ingester(store: SpotStore, callsign: String, state: ServerState) {
     tokio::spawn(async move {
-        ingester_loop(store, callsign).await;
+        ingester_loop(store, callsign, state).await;
     });
 }
 
-async fn ingester_loop(store: SpotStore, callsign: String) {
+async fn ingester_loop(store: SpotStore, callsign: String, state: ServerState) {
     let mut backoff_secs = INITIAL_BACKOFF_SECS;
 
     loop {
         tracing::info!("RBN ingester: connecting to {}:{}", RBN_HOST, RBN_PORT);
 
-        match run_connection(&store, &callsign).await {
+        // Failures only start the staleness clock; batches record successes
+        state.record_fetch(SpotSource::Rbn.as_str(), false);
+        match run_connection(&store, &callsign, &state).await {
             Ok(true) => {
                 // Was connected and received data — reset backoff
                 tracing::info!("RBN ingester: connection closed cleanly");
@@ -55,6 +63,7 @@ async fn ingester_loop(store: SpotStore, callsign: String) {
 async fn run_connection(
     store: &SpotStore,
     callsign: &str,
+    state: &ServerState,
 ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
     let stream = tokio::time::timeout(
         std::time::Duration::from_secs(LOGIN_TIMEOUT_SECS),
@@ -135,7 +144,7 @@ async fn run_connection(
                 }
 
                 if batch.len() >= BATCH_SIZE {
-                    store.push_batch(std::mem::take(&mut batch));
+                    push_batch(store, state, std::mem::take(&mut batch));
                     flush_deadline = tokio::time::Instant::now()
                         + std::time::Duration::from_millis(BATCH_FLUSH_MS);
                 }
@@ -143,20 +152,20 @@ async fn run_connection(
             Ok(Ok(None)) => {
                 // Connection closed
                 if !batch.is_empty() {
-                    store.push_batch(batch);
+                    push_batch(store, state, batch);
                 }
                 return Ok(true);
             }
             Ok(Err(e)) => {
                 if !batch.is_empty() {
-                    store.push_batch(batch);
+                    push_batch(store, state, batch);
                 }
                 return Err(e.into());
             }
             Err(_) => {
                 // Flush timeout — push whatever we have
                 if !batch.is_empty() {
-                    store.push_batch(std::mem::take(&mut batch));
+                    push_batch(store, state, std::mem::take(&mut batch));
                 }
                 flush_deadline =
                     tokio::time::Instant::now() + std::time::Duration::from_millis(BATCH_FLUSH_MS);
@@ -165,6 +174,12 @@ async fn run_connection(
     }
 }
 
+/// Store a batch and record it as a successful fetch.
+fn push_batch(store: &SpotStore, state: &ServerState, batch: Vec<RbnSpot>) {
+    store.push_batch(batch);
+    state.record_fetch(SpotSource::Rbn.as_str(), true);
+}
+
 /// Parse an RBN telnet spot line.
 ///
 /// Format: `DX de KM3T-#:     14039.8  W1AW           CW    18 dB  25 WPM  CQ      1832Z`
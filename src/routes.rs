@@ -0,0 +1,566 @@
+//! The API router: every route, its auth and feature gates, and the shared
+//! layers and extensions handlers rely on.
+
+use axum::{
+    extract::DefaultBodyLimit,
+    http::HeaderName,
+    middleware,
+    routing::{any, delete, get, post, put},
+    Extension, Router,
+};
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::services::{ServeDir, ServeFile};
+use tower_http::trace::TraceLayer;
+
+use crate::auth;
+use crate::blocks;
+use crate::config::Config;
+use crate::error;
+use crate::feature_flags::{self, Feature, FeatureFlags};
+use crate::handlers;
+use crate::http_client;
+use crate::leaderboard_live;
+use crate::metrics;
+use crate::query_timing;
+use crate::rbn;
+use crate::server;
+
+pub fn create_router(
+    pool: sqlx::PgPool,
+    config: Config,
+    rbn_store: rbn::SpotStore,
+    feature_flags: FeatureFlags,
+    server_state: server::ServerState,
+    metrics_handle: metrics_exporter_prometheus::PrometheusHandle,
+) -> Router {
+    let require_feature = |feature: Feature| {
+        middleware::from_fn_with_state(
+            (feature_flags.clone(), feature),
+            feature_flags::require_feature,
+        )
+    };
+
+    let cors = cors_layer(&config);
+
+    // Public routes
+    let public_routes = Router::new()
+        .route("/challenges", get(handlers::list_challenges))
+        .route("/challenges/validate", post(handlers::validate_challenge))
+        .route("/challenges/:id", get(handlers::get_challenge))
+        .route("/challenges/:id/join", post(handlers::join_challenge))
+        .route(
+            "/challenges/:id/hamalert/preview",
+            get(handlers::preview_hamalert_triggers),
+        )
+        .route(
+            "/challenges/:id/leaderboard",
+            get(handlers::get_leaderboard),
+        )
+        .route(
+            "/challenges/:id/leaderboard/ws",
+            get(handlers::leaderboard_ws),
+        )
+        .route("/contests", get(handlers::list_contests))
+        .route("/contests/:id", get(handlers::get_contest))
+        .route("/badges/:id/image", get(handlers::get_badge_image))
+        .route("/programs", get(handlers::list_programs))
+        .route("/programs/:slug", get(handlers::get_program))
+        .route("/programs/:slug/icon", get(handlers::get_program_icon))
+        .route(
+            "/programs/:slug/references",
+            get(handlers::search_references),
+        )
+        .route("/categories", get(handlers::list_categories))
+        .route("/challenge-categories", get(handlers::list_categories))
+        .route("/health", get(handlers::health_check))
+        .route("/capabilities", get(handlers::get_capabilities))
+        .route("/status", get(handlers::status))
+        .route("/users/search", get(handlers::search_users))
+        .route("/search", get(handlers::search))
+        .route("/register", post(handlers::register))
+        .route("/pota/stats/activator", get(handlers::get_activator_stats))
+        .route("/pota/stats/hunter", get(handlers::get_hunter_stats))
+        .route("/pota/stats/state/:state", get(handlers::get_state_stats))
+        .route("/pota/stats/park/:reference", get(handlers::get_park_stats))
+        .route(
+            "/pota/stats/rankings/activators",
+            get(handlers::get_activator_rankings),
+        )
+        .route("/pota/stats/status", get(handlers::get_sync_status))
+        .route("/parks/boundaries", get(handlers::get_boundaries))
+        .route(
+            "/parks/boundaries/status",
+            get(handlers::get_boundary_status),
+        )
+        .route("/parks/boundaries/:reference", get(handlers::get_boundary))
+        .route("/trails", get(handlers::get_trails))
+        .route("/trails/status", get(handlers::get_trail_status))
+        .route("/trails/:reference", get(handlers::get_trail))
+        .route(
+            "/clubs/:id/logo",
+            get(handlers::get_club_logo).route_layer(require_feature(Feature::Clubs)),
+        )
+        .route("/equipment/catalog", get(handlers::get_catalog))
+        .route("/equipment/search", get(handlers::search_equipment))
+        .route("/equipment/submissions", post(handlers::submit_equipment))
+        .route("/events", get(handlers::list_events))
+        .route("/events/:id", get(handlers::get_event))
+        .route("/rbn/spots", get(handlers::rbn_spots))
+        .route("/rbn/stats", get(handlers::rbn_stats))
+        .route("/rbn/skimmers", get(handlers::rbn_skimmers))
+        .route("/twilio/sms", post(handlers::twilio_sms_webhook))
+        .route("/metrics", post(handlers::ingest_metrics))
+        .route("/diagnostics", post(handlers::ingest_diagnostics))
+        .route(
+            "/telemetry/equipment-usage",
+            post(handlers::report_equipment_usage),
+        );
+
+    // Spot routes are only mounted when the spots system is enabled
+    let public_routes = if config.spots_enabled {
+        public_routes
+            .route("/spots", get(handlers::list_spots))
+            .route("/spots/delta", get(handlers::spots_delta))
+            .route("/spots/heatmap", get(handlers::spot_heatmap))
+            .route("/spots/modes", get(handlers::spot_modes))
+            .route("/spots/lookup", post(handlers::lookup_spots))
+            .route("/spots/:id", get(handlers::get_spot))
+            .route("/activators", get(handlers::list_activators))
+            .route(
+                "/callsigns/:callsign/spots",
+                get(handlers::list_callsign_spots),
+            )
+            .route(
+                "/callsigns/:callsign/spot-summary",
+                get(handlers::get_callsign_spot_summary),
+            )
+            .route("/callsigns/status", post(handlers::callsign_statuses))
+    } else {
+        public_routes
+    };
+
+    let public_routes = public_routes
+        .layer(Extension(rbn_store))
+        .layer(Extension(feature_flags.clone()))
+        .layer(Extension(config.clone()))
+        .layer(middleware::from_fn_with_state(
+            pool.clone(),
+            auth::optional_auth,
+        ));
+
+    // Authenticated routes
+    let auth_routes = Router::new()
+        .route(
+            "/challenges/preview-score",
+            post(handlers::preview_challenge_score),
+        )
+        .route("/challenges/:id/progress", post(handlers::report_progress))
+        .route("/challenges/:id/progress", get(handlers::get_progress))
+        .route(
+            "/challenges/:id/data-entries",
+            post(handlers::submit_data_entry),
+        )
+        .route("/challenges/:id/leave", delete(handlers::leave_challenge))
+        .route("/challenges/:id/division", put(handlers::change_division))
+        .route(
+            "/challenges/:id/participants/:callsign",
+            get(handlers::get_participation_status),
+        )
+        .route(
+            "/challenges/:id/certificates/:callsign",
+            get(handlers::get_certificate),
+        )
+        .route(
+            "/participants/:callsign/challenges",
+            get(handlers::list_challenges_for_callsign),
+        )
+        .route("/friends/invite-link", get(handlers::get_invite_link))
+        .route("/invites", post(handlers::create_friend_invite))
+        .route(
+            "/friends/requests",
+            get(handlers::list_pending_requests).post(handlers::create_friend_request),
+        )
+        .route(
+            "/friends/suggestions",
+            post(handlers::get_friend_suggestions),
+        )
+        .route("/friends", get(handlers::list_friends))
+        .route(
+            "/friends/requests/pending",
+            get(handlers::list_pending_requests),
+        )
+        .route(
+            "/friends/requests/:id/accept",
+            post(handlers::accept_friend_request),
+        )
+        .route(
+            "/friends/requests/:id/decline",
+            post(handlers::decline_friend_request),
+        )
+        .route("/friends/:id", delete(handlers::remove_friend))
+        .route("/activities", post(handlers::report_activity))
+        .route("/activities/:id", delete(handlers::delete_activity))
+        .route("/feed", get(handlers::get_feed))
+        .route("/users/:id/activities", get(handlers::get_user_activities))
+        .route(
+            "/users/:id/block",
+            put(handlers::block_user).delete(handlers::unblock_user),
+        )
+        .route("/events", post(handlers::create_event))
+        .route("/events/mine", get(handlers::list_my_events))
+        .route(
+            "/events/:id",
+            put(handlers::update_event).delete(handlers::delete_event),
+        )
+        .route(
+            "/telemetry/upload-errors",
+            post(handlers::report_upload_errors),
+        )
+        .route("/spot-markers", post(handlers::create_spot_marker))
+        .route("/account", delete(handlers::delete_account))
+        .route("/me/stats", get(handlers::get_my_stats))
+        .route("/me/challenges", get(handlers::get_my_challenges))
+        .route("/me/digest", get(handlers::get_my_digest))
+        .route("/account/callsign", put(handlers::change_callsign))
+        .route(
+            "/account/claim-previous",
+            post(handlers::claim_previous_account),
+        );
+
+    // Club routes, switchable at runtime via the `clubs` feature flag
+    let club_routes = Router::new()
+        .route("/clubs", get(handlers::get_clubs))
+        .route("/clubs/sync", get(handlers::sync_clubs))
+        .route("/clubs/membership", get(handlers::get_club_membership))
+        .route("/clubs/:id", get(handlers::get_club_details))
+        .route("/clubs/:id/activity", get(handlers::get_club_activity))
+        .route("/clubs/:id/status", get(handlers::get_club_status))
+        .route("/clubs/:id/notes", put(handlers::update_club_notes))
+        .route_layer(require_feature(Feature::Clubs));
+    let auth_routes = auth_routes.merge(club_routes);
+
+    let auth_routes = if config.spots_enabled {
+        auth_routes
+            .route(
+                "/spots",
+                post(handlers::create_self_spot).route_layer(require_feature(Feature::SpotIngest)),
+            )
+            .route("/spots/mine", delete(handlers::delete_all_own_spots))
+            .route("/spots/:id", delete(handlers::delete_own_spot))
+            .route("/spots/:id/report", post(handlers::report_spot))
+    } else {
+        auth_routes
+    };
+
+    let auth_routes =
+        auth_routes
+            .layer(Extension(config.clone()))
+            .layer(middleware::from_fn_with_state(
+                pool.clone(),
+                auth::require_auth,
+            ));
+
+    // Spots pushed by integration partners, authenticated by partner API key
+    let integration_routes = if config.spots_enabled {
+        Router::new()
+            .route(
+                "/integrations/spots",
+                post(handlers::create_integration_spot)
+                    .route_layer(require_feature(Feature::SpotIngest)),
+            )
+            .layer(Extension(config.clone()))
+            .layer(middleware::from_fn_with_state(
+                pool.clone(),
+                auth::require_integration_key,
+            ))
+    } else {
+        Router::new()
+    };
+
+    // Admin routes
+    let admin_routes = Router::new()
+        .route("/admin/challenges", post(handlers::create_challenge))
+        .route("/admin/challenges/:id", put(handlers::update_challenge))
+        .route("/admin/challenges/:id", delete(handlers::delete_challenge))
+        // For organizers, beside the participant's own lookup rather than under /admin
+        .route(
+            "/challenges/:id/certificates",
+            get(handlers::list_certificates),
+        )
+        .route("/admin/contests", post(handlers::upsert_contests))
+        .route("/admin/contests/:id", delete(handlers::delete_contest))
+        .route(
+            "/admin/contests/validate",
+            post(handlers::validate_contests),
+        )
+        .route(
+            "/admin/challenges/:id/badges",
+            post(handlers::upload_badge).get(handlers::list_badges),
+        )
+        .route("/admin/badges/:id", delete(handlers::delete_badge))
+        .route(
+            "/admin/challenges/:id/invites",
+            post(handlers::generate_invite).get(handlers::list_invites),
+        )
+        .route("/admin/invites/:token", delete(handlers::revoke_invite))
+        .route(
+            "/admin/programs",
+            post(handlers::create_program).get(handlers::admin_list_programs),
+        )
+        .route(
+            "/admin/programs/:slug",
+            put(handlers::update_program)
+                .get(handlers::admin_get_program)
+                .delete(handlers::delete_program),
+        )
+        .route(
+            "/admin/programs/:slug/icon",
+            put(handlers::upload_program_icon)
+                .layer(DefaultBodyLimit::max(handlers::ICON_MAX_BYTES)),
+        )
+        .route(
+            "/admin/programs/:slug/capabilities",
+            post(handlers::add_program_capability),
+        )
+        .route(
+            "/admin/programs/:slug/capabilities/:cap",
+            delete(handlers::remove_program_capability),
+        )
+        .route(
+            "/admin/programs/:slug/references",
+            post(handlers::import_references)
+                .layer(DefaultBodyLimit::max(handlers::REFERENCE_IMPORT_MAX_BYTES)),
+        )
+        .route(
+            "/admin/categories",
+            post(handlers::create_category).get(handlers::admin_list_categories),
+        )
+        .route("/admin/categories/merge", post(handlers::merge_categories))
+        .route(
+            "/admin/categories/:name",
+            put(handlers::update_category).delete(handlers::delete_category),
+        )
+        .route(
+            "/admin/clubs",
+            post(handlers::create_club).get(handlers::list_clubs_admin),
+        )
+        .route(
+            "/admin/clubs/:id",
+            put(handlers::update_club).delete(handlers::delete_club),
+        )
+        .route(
+            "/admin/clubs/:id/members",
+            post(handlers::add_club_members).get(handlers::list_club_members_admin),
+        )
+        .route(
+            "/admin/clubs/:id/import-notes",
+            post(handlers::import_notes_members),
+        )
+        .route(
+            "/admin/clubs/:id/logo",
+            put(handlers::upload_club_logo).delete(handlers::delete_club_logo),
+        )
+        .route(
+            "/admin/clubs/:id/members/:callsign",
+            delete(handlers::remove_club_member).put(handlers::update_club_member_role),
+        )
+        .route("/admin/equipment", post(handlers::create_equipment))
+        .route(
+            "/admin/equipment/:id",
+            put(handlers::update_equipment).delete(handlers::delete_equipment),
+        )
+        .route(
+            "/admin/equipment/submissions",
+            get(handlers::list_equipment_submissions),
+        )
+        .route(
+            "/admin/equipment/submissions/:id/review",
+            put(handlers::review_equipment_submission),
+        )
+        .route("/admin/events", get(handlers::list_events_admin))
+        .route(
+            "/admin/events/:id",
+            get(handlers::admin_get_event)
+                .put(handlers::admin_update_event)
+                .delete(handlers::admin_delete_event),
+        )
+        .route("/admin/events/:id/review", put(handlers::review_event))
+        .route(
+            "/admin/events/submitter/:callsign",
+            get(handlers::get_submitter_history),
+        )
+        .route(
+            "/admin/telemetry/upload-errors",
+            get(handlers::get_telemetry_summary),
+        )
+        .route("/admin/metrickit", get(handlers::get_metrickit_summary))
+        .route(
+            "/admin/spots/reports",
+            get(handlers::admin_list_spot_reports),
+        )
+        .route(
+            "/admin/spots/archive/stats",
+            get(handlers::admin_spots_archive_stats),
+        )
+        .route("/admin/spots/:id", delete(handlers::admin_delete_spot))
+        .route(
+            "/admin/integration-keys",
+            post(handlers::create_integration_key),
+        )
+        .route("/admin/trails/status", get(handlers::get_trail_status))
+        .route("/admin/stats", get(handlers::admin_stats))
+        .route("/admin/users", get(handlers::admin_list_users))
+        .route("/admin/users/merge", post(handlers::merge_users))
+        .route("/admin/users/:id/suspend", post(handlers::suspend_user))
+        .route("/admin/users/:id/unsuspend", post(handlers::unsuspend_user))
+        .route(
+            "/admin/users/:id/activities",
+            delete(handlers::admin_delete_user_activities),
+        )
+        .route(
+            "/admin/stats/users-by-hour",
+            get(handlers::admin_users_by_hour),
+        )
+        .route("/admin/config", get(handlers::get_admin_config))
+        .route("/admin/maintenance", get(handlers::get_maintenance_status))
+        .route(
+            "/admin/aggregators/:source/poll",
+            post(handlers::poll_aggregator),
+        )
+        .route(
+            "/admin/aggregators/:source/raw",
+            get(handlers::fetch_aggregator_raw),
+        )
+        .route(
+            "/admin/aggregators/pota/backfill",
+            post(handlers::backfill_pota),
+        )
+        .route("/admin/jobs/:id", get(handlers::get_job))
+        .route(
+            "/admin/digests/precompute",
+            post(handlers::precompute_digests),
+        )
+        .route("/admin/drain", post(handlers::drain))
+        .route("/admin/undrain", post(handlers::undrain))
+        .route(
+            "/admin/features",
+            get(handlers::list_feature_flags).put(handlers::update_feature_flags),
+        )
+        .layer(Extension(feature_flags.clone()))
+        .layer(Extension(config.clone()))
+        .layer(middleware::from_fn_with_state(
+            config.admin_token,
+            auth::require_admin,
+        ));
+
+    // Merge all v1 routes with a JSON 404 fallback for unmatched API paths
+    let mut v1_routes = public_routes
+        .merge(auth_routes)
+        .merge(integration_routes)
+        .merge(admin_routes);
+    if !config.spots_enabled {
+        v1_routes = v1_routes
+            .route("/spots", any(spots_disabled))
+            .route("/spots/*rest", any(spots_disabled))
+            .route("/integrations/spots", any(spots_disabled))
+            .route("/activators", any(spots_disabled))
+            .route("/callsigns/:callsign/spots", any(spots_disabled))
+            .route("/callsigns/:callsign/spot-summary", any(spots_disabled))
+            .route("/callsigns/status", any(spots_disabled));
+    }
+    let v1_routes = v1_routes
+        .fallback(api_not_found)
+        .layer(middleware::from_fn_with_state(
+            query_timing::SlowRequestThreshold(std::time::Duration::from_millis(
+                config.slow_request_ms,
+            )),
+            query_timing::track_request,
+        ))
+        .layer(axum::middleware::from_fn(metrics::http_metrics));
+
+    // Friend and challenge invite pages (server-rendered HTML for links opened in browsers)
+    let invite_route = Router::new()
+        .route("/invite/:token", get(handlers::invite_page))
+        .route("/join/:token", get(handlers::challenge_invite_page));
+
+    // Static file serving for SPA (fallback to index.html for client-side routing)
+    let serve_dir = ServeDir::new("web/dist").fallback(ServeFile::new("web/dist/index.html"));
+
+    let mut app = Router::new()
+        .nest("/v1", v1_routes)
+        .route("/readyz", get(handlers::readiness_check));
+    if config.metrics_port.is_none() {
+        app = app.route("/metrics", get(handlers::get_metrics));
+    }
+
+    if config.error_doc_links_enabled {
+        app = app.layer(middleware::from_fn_with_state(
+            error::ErrorDocs::new(&config.error_doc_base_url),
+            error::add_doc_links,
+        ));
+    }
+
+    // Registered last so it covers every route above; unknown non-API paths
+    // fall through to the SPA
+    let app = app
+        .merge(invite_route)
+        .method_not_allowed_fallback(method_not_allowed)
+        .fallback_service(serve_dir)
+        .layer(Extension(metrics_handle))
+        .layer(Extension(leaderboard_live::LeaderboardHub::new(
+            leaderboard_live::PUSH_INTERVAL,
+        )))
+        .layer(Extension(blocks::BlockCache::new(blocks::CACHE_TTL)))
+        .layer(Extension(http_client::HttpClient::new()))
+        .layer(Extension(server_state.clone()))
+        .layer(middleware::from_fn_with_state(
+            server_state,
+            server::track_in_flight,
+        ))
+        .layer(TraceLayer::new_for_http().make_span_with(query_timing::make_request_span))
+        .layer(cors)
+        .with_state(pool);
+
+    // Behind a shared gateway everything, health and invite pages included,
+    // lives under ROUTE_PREFIX
+    if config.route_prefix.is_empty() {
+        app
+    } else {
+        Router::new().nest(&config.route_prefix, app)
+    }
+}
+
+/// Any origin may call the API; exposed headers and preflight caching come
+/// from `CORS_EXPOSE_HEADERS` and `CORS_MAX_AGE_SECS`.
+fn cors_layer(config: &Config) -> CorsLayer {
+    let expose_headers: Vec<HeaderName> = config
+        .cors_expose_headers
+        .iter()
+        .filter_map(|name| name.parse().ok())
+        .collect();
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers(expose_headers);
+    if config.cors_max_age_secs > 0 {
+        cors.max_age(std::time::Duration::from_secs(config.cors_max_age_secs))
+    } else {
+        cors
+    }
+}
+
+async fn spots_disabled() -> error::AppError {
+    error::AppError::FeatureDisabled { feature: "spots" }
+}
+
+async fn api_not_found() -> error::AppError {
+    error::AppError::NotFound
+}
+
+async fn method_not_allowed() -> error::AppError {
+    error::AppError::MethodNotAllowed
+}
+
+#[cfg(test)]
+mod tests;
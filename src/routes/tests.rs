@@ -0,0 +1,920 @@
+use super::*;
+use crate::aggregators;
+use crate::cursor;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use http_body_util::BodyExt;
+use sqlx::postgres::PgPoolOptions;
+use tower::ServiceExt;
+
+fn router(spots_enabled: &str) -> Router {
+    router_with_flags(spots_enabled).0
+}
+
+fn router_with_flags(spots_enabled: &str) -> (Router, FeatureFlags) {
+    router_with(
+        &[("SPOTS_ENABLED", spots_enabled)],
+        server::ServerState::default(),
+    )
+}
+
+/// A router over a lazy pool with extra config vars and the given state.
+fn router_with(vars: &[(&str, &str)], state: server::ServerState) -> (Router, FeatureFlags) {
+    let config = Config::from_lookup(|var| match var {
+        "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+        "ADMIN_TOKEN" => Some("admin".to_string()),
+        _ => vars
+            .iter()
+            .find(|(name, _)| *name == var)
+            .map(|(_, value)| value.to_string()),
+    })
+    .unwrap();
+    let pool = PgPoolOptions::new()
+        .connect_lazy(&config.database_url)
+        .unwrap();
+    let metrics_handle = metrics_exporter_prometheus::PrometheusBuilder::new()
+        .build_recorder()
+        .handle();
+    let flags = FeatureFlags::from_config(&config);
+    let app = create_router(
+        pool,
+        config,
+        rbn::SpotStore::new(),
+        flags.clone(),
+        state,
+        metrics_handle,
+    );
+    (app, flags)
+}
+
+async fn send(app: Router, method: Method, uri: &str) -> (StatusCode, serde_json::Value) {
+    let req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    let status = res.status();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn send_json(
+    app: Router,
+    method: Method,
+    uri: &str,
+    body: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    let status = res.status();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn spot_routes_return_feature_disabled() {
+    for (method, uri) in [
+        (Method::GET, "/v1/spots"),
+        (
+            Method::GET,
+            "/v1/spots/00000000-0000-0000-0000-000000000000",
+        ),
+        (Method::GET, "/v1/spots/delta"),
+        (Method::GET, "/v1/spots/heatmap"),
+        (Method::GET, "/v1/spots/modes"),
+        (Method::POST, "/v1/spots/lookup"),
+        (Method::GET, "/v1/activators"),
+        (Method::GET, "/v1/callsigns/W1AW/spots"),
+        (Method::GET, "/v1/callsigns/W1AW/spot-summary"),
+        (Method::POST, "/v1/callsigns/status"),
+        (Method::POST, "/v1/spots"),
+        (Method::DELETE, "/v1/spots/mine"),
+        (
+            Method::POST,
+            "/v1/spots/00000000-0000-0000-0000-000000000000/report",
+        ),
+        (Method::POST, "/v1/integrations/spots"),
+    ] {
+        let (status, body) = send(router("false"), method, uri).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{uri}");
+        assert_eq!(body["error"]["code"], "FEATURE_DISABLED");
+        assert_eq!(body["error"]["details"]["feature"], "spots");
+    }
+}
+
+#[tokio::test]
+async fn integration_spots_reject_missing_and_malformed_keys() {
+    for authorization in [None, Some("Bearer fd_notapartnerkey"), Some("ik_nobearer")] {
+        let mut req = Request::builder()
+            .method(Method::POST)
+            .uri("/v1/integrations/spots")
+            .header("content-type", "application/json");
+        if let Some(value) = authorization {
+            req = req.header("authorization", value);
+        }
+        let req = req.body(Body::from("{}")).unwrap();
+        let res = router("true").oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{authorization:?}");
+    }
+}
+
+#[tokio::test]
+async fn malformed_spots_cursor_is_rejected() {
+    let (status, body) = send(router("true"), Method::GET, "/v1/spots?cursor=garbage").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_CURSOR");
+}
+
+#[tokio::test]
+async fn grouped_spots_reject_unknown_grouping_and_ungrouped_cursors() {
+    let (status, body) = send(router("true"), Method::GET, "/v1/spots?groupBy=reference").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+
+    let ungrouped = cursor::Cursor(chrono::Utc::now()).encode();
+    let uri = format!("/v1/spots?groupBy=callsign&cursor={ungrouped}");
+    let (status, body) = send(router("true"), Method::GET, &uri).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_CURSOR");
+}
+
+#[tokio::test]
+async fn spot_windows_are_validated() {
+    for (query, message) in [
+        ("to=2025-06-01T12:00:00Z", "to requires from"),
+        (
+            "from=2025-06-01T12:00:00Z&maxAgeMinutes=30",
+            "cannot be combined with maxAgeMinutes",
+        ),
+        (
+            "from=2025-06-01T12:00:00Z&to=2025-06-01T11:00:00Z",
+            "from must be before to",
+        ),
+        (
+            "from=2025-06-01T12:00:00Z&to=2025-06-01T18:00:01Z",
+            "at most 6 hours",
+        ),
+        (
+            "from=2025-06-01T12:00:00Z&to=2025-06-01T13:00:00Z&groupBy=callsign",
+            "groupBy cannot be combined",
+        ),
+    ] {
+        let uri = format!("/v1/spots?{query}");
+        let (status, body) = send(router("true"), Method::GET, &uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+        let actual = body["error"]["message"].as_str().unwrap();
+        assert!(actual.contains(message), "{uri}: {actual}");
+    }
+}
+
+#[tokio::test]
+async fn malformed_query_parameters_are_json_errors() {
+    for (uri, param) in [
+        ("/v1/spots?limit=abc", "limit"),
+        ("/v1/spots?source=bogus", "source"),
+        ("/v1/challenges?active=maybe", "active"),
+    ] {
+        let (status, body) = send(router("true"), Method::GET, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+        let message = body["error"]["message"].as_str().unwrap();
+        assert!(
+            message.contains(&format!("Invalid query parameter '{param}'")),
+            "{message}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn unknown_spot_include_is_rejected() {
+    for uri in [
+        "/v1/spots?include=program,spotter",
+        "/v1/spots/00000000-0000-0000-0000-000000000000?include=spotter",
+    ] {
+        let (status, body) = send(router("true"), Method::GET, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    }
+}
+
+#[tokio::test]
+async fn reference_search_requires_query_or_valid_grid() {
+    for uri in [
+        "/v1/programs/pota/references",
+        "/v1/programs/pota/references?q=%20",
+        "/v1/programs/pota/references?nearGrid=ZZ99",
+    ] {
+        let (status, body) = send(router("true"), Method::GET, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    }
+}
+
+#[tokio::test]
+async fn spot_modes_lists_canonical_set() {
+    let (status, body) = send(router("true"), Method::GET, "/v1/spots/modes").await;
+    assert_eq!(status, StatusCode::OK);
+    let modes = body["data"]["modes"].as_array().unwrap();
+    for mode in ["CW", "SSB", "FT8", "UNKNOWN"] {
+        assert!(modes.contains(&serde_json::json!(mode)), "{mode}");
+    }
+}
+
+#[tokio::test]
+async fn spot_heatmap_rejects_out_of_range_days() {
+    for uri in ["/v1/spots/heatmap?days=0", "/v1/spots/heatmap?days=31"] {
+        let (status, body) = send(router("true"), Method::GET, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    }
+}
+
+#[tokio::test]
+async fn error_bodies_link_to_docs_when_enabled() {
+    let (app, _) = router_with(
+        &[
+            ("ERROR_DOC_LINKS_ENABLED", "true"),
+            ("ERROR_DOC_BASE_URL", "https://docs.example/api/"),
+        ],
+        server::ServerState::default(),
+    );
+    let (status, body) = send(app, Method::GET, "/v1/spots?fields=bogus").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["error"]["docUrl"],
+        "https://docs.example/api/errors#validation-error"
+    );
+
+    let (_, body) = send(router("true"), Method::GET, "/v1/spots?fields=bogus").await;
+    assert!(body["error"].get("docUrl").is_none(), "{body}");
+}
+
+#[tokio::test]
+async fn spot_list_rejects_unknown_fields() {
+    let (status, body) = send(
+        router("true"),
+        Method::GET,
+        "/v1/spots?fields=callsign,freq",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(message.contains("'freq'"), "{message}");
+    assert!(message.contains("frequencyKhz"), "{message}");
+}
+
+#[tokio::test]
+async fn challenge_validation_reports_without_saving() {
+    let valid = serde_json::json!({
+        "type": "cumulative",
+        "configuration": { "goals": { "type": "cumulative", "targetValue": 100 } }
+    });
+    let (status, body) = send_json(
+        router("true"),
+        Method::POST,
+        "/v1/challenges/validate",
+        valid,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["valid"], true);
+    assert_eq!(body["data"]["errors"], serde_json::json!([]));
+
+    let invalid = serde_json::json!({
+        "type": "collection",
+        "configuration": { "goals": { "type": "collection", "items": [] } }
+    });
+    let (status, body) = send_json(
+        router("true"),
+        Method::POST,
+        "/v1/challenges/validate",
+        invalid,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["valid"], false);
+    assert_eq!(
+        body["data"]["errors"][0]["path"],
+        "configuration.goals.items"
+    );
+}
+
+#[tokio::test]
+async fn spots_delta_rejects_bad_and_expired_cursors() {
+    let (status, body) = send(router("true"), Method::GET, "/v1/spots/delta?since=garbage").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_CURSOR");
+
+    let (status, body) = send(
+        router("true"),
+        Method::GET,
+        "/v1/spots/delta?since=2020-01-01T00:00:00Z",
+    )
+    .await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(body["error"]["code"], "CURSOR_EXPIRED");
+}
+
+#[tokio::test]
+async fn cors_exposes_configured_headers_and_caches_preflight() {
+    let config = Config::from_lookup(|var| match var {
+        "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+        "ADMIN_TOKEN" => Some("admin".to_string()),
+        "CORS_EXPOSE_HEADERS" => Some("ETag,X-Request-Id,X-Total-Count".to_string()),
+        "CORS_MAX_AGE_SECS" => Some("3600".to_string()),
+        _ => None,
+    })
+    .unwrap();
+    let app = Router::new()
+        .route("/ping", get(|| async { "pong" }))
+        .layer(cors_layer(&config));
+
+    let req = Request::get("/ping")
+        .header("origin", "https://example.com")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    let exposed = res.headers()["access-control-expose-headers"]
+        .to_str()
+        .unwrap();
+    assert_eq!(exposed, "etag,x-request-id,x-total-count");
+
+    let preflight = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/ping")
+        .header("origin", "https://example.com")
+        .header("access-control-request-method", "GET")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(preflight).await.unwrap();
+    assert_eq!(res.headers()["access-control-max-age"], "3600");
+}
+
+#[tokio::test]
+async fn unknown_activator_band_is_rejected() {
+    let (status, body) = send(router("true"), Method::GET, "/v1/activators?band=11m").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+}
+
+#[tokio::test]
+async fn spot_routes_mounted_when_enabled() {
+    // Reaches the auth middleware rather than the disabled stub
+    let (status, body) = send(router("true"), Method::POST, "/v1/spots").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn user_activities_require_auth() {
+    let uri = format!("/v1/users/{}/activities", uuid::Uuid::new_v4());
+    let (status, body) = send(router("true"), Method::GET, &uri).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn certificates_require_admin_or_auth() {
+    let uri = format!("/v1/challenges/{}/certificates", uuid::Uuid::new_v4());
+    let (status, _) = send(router("true"), Method::GET, &uri).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = send(router("true"), Method::GET, &format!("{uri}/K1ABC")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn challenge_saves_reject_invalid_hamalert_config() {
+    let body = serde_json::json!({
+        "name": "Parks on Air",
+        "description": "",
+        "category": "award",
+        "type": "collection",
+        "configuration": {},
+        "hamalertConfig": { "alertType": "app", "bands": ["11m"] }
+    });
+    for (method, uri) in [
+        (Method::POST, "/v1/admin/challenges"),
+        (
+            Method::PUT,
+            "/v1/admin/challenges/00000000-0000-0000-0000-000000000000",
+        ),
+    ] {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", "Bearer admin")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let res = router("true").oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{uri}");
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "VALIDATION_ERROR");
+        let message = json["error"]["message"].as_str().unwrap();
+        assert!(message.contains("unknown band '11m'"), "{message}");
+    }
+}
+
+#[tokio::test]
+async fn admin_config_redacts_secrets() {
+    let req = Request::builder()
+        .uri("/v1/admin/config")
+        .header("authorization", "Bearer admin")
+        .body(Body::empty())
+        .unwrap();
+    let res = router("true").oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["adminToken"], "****");
+    assert_eq!(json["data"]["spotsEnabled"], true);
+    assert_eq!(json["data"]["port"], 8080);
+
+    let (status, _) = send(router("true"), Method::GET, "/v1/admin/config").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn bulk_activity_delete_requires_admin_and_a_cutoff() {
+    let uri = "/v1/admin/users/00000000-0000-0000-0000-000000000000/activities";
+    let (status, _) = send(router("true"), Method::DELETE, uri).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    for query in ["", "?after=yesterday"] {
+        let req = Request::delete(format!("{uri}{query}"))
+            .header("authorization", "Bearer admin")
+            .body(Body::empty())
+            .unwrap();
+        let res = router("true").oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{query}");
+    }
+}
+
+#[tokio::test]
+async fn program_icon_upload_rejects_oversized_and_unrecognized_bodies() {
+    let upload = |body: Vec<u8>| {
+        Request::put("/v1/admin/programs/pota/icon")
+            .header("authorization", "Bearer admin")
+            // Claimed types aren't trusted either way
+            .header("content-type", "image/png")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let cases = [
+        (
+            vec![0; handlers::ICON_MAX_BYTES + 1],
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "ICON_TOO_LARGE",
+        ),
+        (
+            b"\xff\xd8\xff\xe0JFIF".to_vec(),
+            StatusCode::BAD_REQUEST,
+            "INVALID_ICON",
+        ),
+    ];
+    for (body, expected_status, expected_code) in cases {
+        let res = router("true").oneshot(upload(body)).await.unwrap();
+        assert_eq!(res.status(), expected_status);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], expected_code);
+    }
+}
+
+#[tokio::test]
+async fn manual_poll_within_cooldown_is_rate_limited() {
+    let state = server::ServerState::default();
+    // As if a poll had just run
+    state
+        .claim_manual_poll("pota", std::time::Duration::from_secs(30))
+        .unwrap();
+    let (app, _) = router_with(&[("POTA_AGGREGATOR_ENABLED", "true")], state);
+
+    let req = Request::post("/v1/admin/aggregators/pota/poll")
+        .header("authorization", "Bearer admin")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.headers()["retry-after"], "30");
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"]["code"], "RATE_LIMITED");
+}
+
+#[tokio::test]
+async fn pota_backfill_validates_window_and_reports_its_job() {
+    let state = server::ServerState::default();
+    let to = chrono::Utc::now() - chrono::Duration::hours(1);
+    let running = state
+        .jobs()
+        .start("potaBackfill", to - chrono::Duration::hours(1), to)
+        .unwrap();
+    let (app, _) = router_with(
+        &[
+            ("POTA_AGGREGATOR_ENABLED", "true"),
+            ("POTA_BACKFILL_URL", "https://pota-history.example/spots"),
+        ],
+        state,
+    );
+    let backfill = |hours: i64| {
+        let from = (to - chrono::Duration::hours(hours)).format("%Y-%m-%dT%H:%M:%SZ");
+        let to = to.format("%Y-%m-%dT%H:%M:%SZ");
+        Request::post(format!(
+            "/v1/admin/aggregators/pota/backfill?from={from}&to={to}"
+        ))
+        .header("authorization", "Bearer admin")
+        .body(Body::empty())
+        .unwrap()
+    };
+
+    for (hours, expected_status, expected_code) in [
+        (25, StatusCode::BAD_REQUEST, "VALIDATION_ERROR"),
+        (0, StatusCode::BAD_REQUEST, "VALIDATION_ERROR"),
+        (2, StatusCode::CONFLICT, "BACKFILL_RUNNING"),
+    ] {
+        let res = app.clone().oneshot(backfill(hours)).await.unwrap();
+        assert_eq!(res.status(), expected_status);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], expected_code);
+    }
+
+    let job = |id: uuid::Uuid| {
+        Request::get(format!("/v1/admin/jobs/{id}"))
+            .header("authorization", "Bearer admin")
+            .body(Body::empty())
+            .unwrap()
+    };
+    let res = app.clone().oneshot(job(running)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["data"]["status"], "running");
+    assert_eq!(body["data"]["kind"], "potaBackfill");
+
+    let res = app.oneshot(job(uuid::Uuid::new_v4())).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn raw_aggregator_fetch_shares_the_poll_cooldown() {
+    let state = server::ServerState::default();
+    state
+        .claim_manual_poll("sota", std::time::Duration::from_secs(30))
+        .unwrap();
+    let (app, _) = router_with(&[("SOTA_AGGREGATOR_ENABLED", "true")], state);
+
+    let req = Request::get("/v1/admin/aggregators/sota/raw")
+        .header("authorization", "Bearer admin")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.headers()["retry-after"], "30");
+
+    // Unknown and disabled sources are rejected before the cooldown
+    for source in ["dxcc", "pota"] {
+        let req = Request::get(format!("/v1/admin/aggregators/{source}/raw"))
+            .header("authorization", "Bearer admin")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{source}");
+    }
+
+    let (status, _) = send(
+        router("true"),
+        Method::GET,
+        "/v1/admin/aggregators/sota/raw",
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn spots_archive_stats_requires_from_before_to() {
+    let req = Request::get(
+        "/v1/admin/spots/archive/stats?from=2025-06-01T00:00:00Z&to=2025-05-01T00:00:00Z",
+    )
+    .header("authorization", "Bearer admin")
+    .body(Body::empty())
+    .unwrap();
+    let res = router("true").oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(router("true"), Method::GET, "/v1/admin/spots/archive/stats").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn digest_precompute_rejects_invalid_and_future_weeks() {
+    for week in ["2024-W53", "2024-32", "2999-W01"] {
+        let req = Request::post(format!("/v1/admin/digests/precompute?week={week}"))
+            .header("authorization", "Bearer admin")
+            .body(Body::empty())
+            .unwrap();
+        let res = router("true").oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{week}");
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    }
+}
+
+#[tokio::test]
+async fn unknown_api_path_returns_json_not_found() {
+    let (status, body) = send(router("true"), Method::GET, "/v1/does-not-exist").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["code"], "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn wrong_method_returns_json_method_not_allowed() {
+    for uri in ["/v1/health", "/v1/programs", "/readyz"] {
+        let (status, body) = send(router("true"), Method::DELETE, uri).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED, "{uri}");
+        assert_eq!(body["error"]["code"], "METHOD_NOT_ALLOWED");
+    }
+}
+
+#[tokio::test]
+async fn routes_are_nested_under_route_prefix() {
+    let (app, _) = router_with(
+        &[("ROUTE_PREFIX", "/challenges/")],
+        server::ServerState::default(),
+    );
+
+    let (status, body) = send(app.clone(), Method::GET, "/challenges/v1/me/challenges").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+
+    let (status, body) = send(app.clone(), Method::GET, "/challenges/v1/does-not-exist").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["code"], "NOT_FOUND");
+
+    let (status, body) = send(app.clone(), Method::DELETE, "/challenges/readyz").await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(body["error"]["code"], "METHOD_NOT_ALLOWED");
+
+    let req = Request::builder()
+        .uri("/v1/me/challenges")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn my_challenges_requires_auth() {
+    let (status, body) = send(router("true"), Method::GET, "/v1/me/challenges").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn invite_creation_requires_auth() {
+    let (status, body) = send_json(
+        router("true"),
+        Method::POST,
+        "/v1/invites",
+        serde_json::json!({ "note": "see you on 20m" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn friend_request_routes_require_auth() {
+    for (method, uri) in [
+        (Method::GET, "/v1/friends/requests"),
+        (Method::POST, "/v1/friends/requests"),
+    ] {
+        let (status, _) = send(router("true"), method, uri).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn block_routes_require_auth() {
+    let uri = "/v1/users/00000000-0000-0000-0000-000000000001/block";
+    for method in [Method::PUT, Method::DELETE] {
+        let (status, _) = send(router("true"), method.clone(), uri).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{method}");
+    }
+}
+
+#[tokio::test]
+async fn score_preview_requires_auth() {
+    let body = serde_json::json!({
+        "type": "collection",
+        "configuration": { "goals": { "type": "collection", "items": [{ "id": "AL" }] } },
+        "entries": [{ "goalId": "AL" }]
+    });
+    let (status, body) = send_json(
+        router("true"),
+        Method::POST,
+        "/v1/challenges/preview-score",
+        body,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["error"]["code"].is_string());
+}
+
+#[tokio::test]
+async fn data_entries_require_auth() {
+    let (status, body) = send_json(
+        router("true"),
+        Method::POST,
+        "/v1/challenges/00000000-0000-0000-0000-000000000000/data-entries",
+        serde_json::json!({ "value": "123456" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["error"]["code"].is_string());
+}
+
+#[tokio::test]
+async fn category_admin_routes_require_admin_token() {
+    for (method, uri) in [
+        (Method::GET, "/v1/admin/categories"),
+        (Method::POST, "/v1/admin/categories"),
+        (Method::POST, "/v1/admin/categories/merge"),
+        (Method::PUT, "/v1/admin/categories/award"),
+        (Method::DELETE, "/v1/admin/categories/award"),
+    ] {
+        let (status, _) = send(router("true"), method, uri).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn program_capability_routes_require_admin_token() {
+    for (method, uri) in [
+        (Method::POST, "/v1/admin/programs/pota/capabilities"),
+        (
+            Method::DELETE,
+            "/v1/admin/programs/pota/capabilities/selfSpot",
+        ),
+    ] {
+        let (status, _) = send(router("true"), method, uri).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn user_merge_requires_admin_token() {
+    let (status, _) = send(router("true"), Method::POST, "/v1/admin/users/merge").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn disabled_feature_flags_guard_routes() {
+    let (app, flags) = router_with_flags("true");
+    flags.apply([("clubs", false), ("spot_ingest", false)]);
+
+    let (status, body) = send(app.clone(), Method::GET, "/v1/clubs/x/logo").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["error"]["details"]["feature"], "clubs");
+
+    // Authenticated routes check auth before the flag
+    let (status, _) = send(app, Method::POST, "/v1/spots").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn drain_fails_readiness_until_undrained() {
+    let app = router("true");
+    let admin = |uri: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("authorization", "Bearer admin")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, body) = send(app.clone(), Method::GET, "/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+
+    let res = app
+        .clone()
+        .oneshot(admin("/v1/admin/drain?timeoutSecs=1"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["data"]["draining"], true);
+    assert_eq!(body["data"]["idle"], true);
+    assert_eq!(body["data"]["inFlight"], 0);
+
+    let (status, body) = send(app.clone(), Method::GET, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "draining");
+    let (status, _) = send(app.clone(), Method::GET, "/v1/health").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let res = app
+        .clone()
+        .oneshot(admin("/v1/admin/undrain"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let (status, _) = send(app, Method::GET, "/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn status_reports_last_cleanup_run() {
+    let state = server::ServerState::default();
+    let (app, _) = router_with(&[], state.clone());
+    let (status, body) = send(app.clone(), Method::GET, "/v1/status").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["cleanup"].is_null());
+
+    state.record_cleanup(4);
+    let (_, body) = send(app, Method::GET, "/v1/status").await;
+    assert_eq!(body["cleanup"]["lastDeleted"], 4);
+    assert!(body["cleanup"]["lastRunAt"].is_string());
+}
+
+#[tokio::test]
+async fn status_lists_aggregator_starts() {
+    let state = server::ServerState::default();
+    let (app, _) = router_with(&[], state.clone());
+    let (_, body) = send(app.clone(), Method::GET, "/v1/status").await;
+    assert_eq!(body["aggregators"], serde_json::json!([]));
+
+    state.record_aggregator_start(aggregators::start_reported("sota", || Ok(())));
+    state.record_aggregator_start(aggregators::start_reported("pota", || {
+        Err("no POTA spot URLs configured".to_string())
+    }));
+    let (_, body) = send(app, Method::GET, "/v1/status").await;
+    assert_eq!(
+        body["aggregators"],
+        serde_json::json!([
+            {"name": "pota", "status": "failed", "reason": "no POTA spot URLs configured"},
+            {"name": "sota", "status": "started"},
+        ])
+    );
+}
+
+#[tokio::test]
+async fn capabilities_reflect_enabled_aggregators() {
+    let (app, flags) = router_with(
+        &[
+            ("POTA_AGGREGATOR_ENABLED", "true"),
+            ("WSPR_AGGREGATOR_ENABLED", "true"),
+        ],
+        server::ServerState::default(),
+    );
+    let (status, body) = send(app.clone(), Method::GET, "/v1/capabilities").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"]["spots"]["sources"],
+        serde_json::json!(["pota", "wspr", "self", "other"])
+    );
+    assert_eq!(body["data"]["spots"]["limits"]["maxPageSize"], 250);
+    assert!(body["data"]["spots"]["filters"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("maxAgeMinutes")));
+    assert_eq!(
+        body["data"]["activities"]["serverGeneratedTypes"],
+        serde_json::json!(["overtaken"])
+    );
+
+    // Switching spot ingest off at runtime stops the aggregators
+    flags.apply([("spot_ingest", false)]);
+    let (_, body) = send(app, Method::GET, "/v1/capabilities").await;
+    assert_eq!(body["data"]["spots"]["sources"], serde_json::json!([]));
+
+    let (_, body) = send(router("false"), Method::GET, "/v1/capabilities").await;
+    assert_eq!(body["data"]["spots"]["enabled"], false);
+    assert_eq!(body["data"]["spots"]["sources"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn health_reports_spots_feature_state() {
+    let (status, body) = send(router("false"), Method::GET, "/v1/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["features"]["spots"], "disabled");
+
+    let (_, body) = send(router("true"), Method::GET, "/v1/health").await;
+    assert_eq!(body["features"]["spots"], "enabled");
+}
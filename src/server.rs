@@ -0,0 +1,566 @@
+//! HTTP server loop with protocol and keep-alive tuning.
+//!
+//! `axum::serve` doesn't expose the hyper connection builder, so connections
+//! are accepted here and served with hyper-util's auto builder, which speaks
+//! HTTP/1.1 and (when enabled) HTTP/2 with prior knowledge on the same port.
+//! TLS and ALPN are expected to be terminated upstream.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tower::ServiceExt;
+
+use crate::aggregators::AggregatorStartReport;
+use crate::config::Config;
+use crate::jobs::JobRegistry;
+
+/// Connection-level settings taken from [`Config`].
+#[derive(Debug, Clone, Copy)]
+pub struct ServerOptions {
+    pub http2_enabled: bool,
+    pub keep_alive: bool,
+    /// Closes HTTP/1 connections that don't send the next request's headers in time.
+    pub idle_timeout: Option<Duration>,
+    pub http2_keep_alive_interval: Option<Duration>,
+    pub http2_keep_alive_timeout: Duration,
+}
+
+impl ServerOptions {
+    pub fn from_config(config: &Config) -> Self {
+        let secs = |s: u64| (s > 0).then(|| Duration::from_secs(s));
+        Self {
+            http2_enabled: config.http2_enabled,
+            keep_alive: config.http_keep_alive_enabled,
+            idle_timeout: secs(config.http_idle_timeout_secs),
+            http2_keep_alive_interval: secs(config.http2_keep_alive_interval_secs),
+            http2_keep_alive_timeout: Duration::from_secs(config.http2_keep_alive_timeout_secs),
+        }
+    }
+
+    fn builder(&self) -> Builder<TokioExecutor> {
+        let mut builder = Builder::new(TokioExecutor::new());
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .keep_alive(self.keep_alive)
+            .header_read_timeout(self.idle_timeout);
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .keep_alive_interval(self.http2_keep_alive_interval)
+            .keep_alive_timeout(self.http2_keep_alive_timeout);
+        if self.http2_enabled {
+            builder
+        } else {
+            // Connections served with upgrades sniff the protocol regardless of
+            // http1_only, so also refuse every HTTP/2 stream
+            builder.http2().max_concurrent_streams(0);
+            builder.http1_only()
+        }
+    }
+}
+
+/// Process-wide serving state shared by the router, readiness probe, and
+/// background aggregators. Draining flips readiness to 503 and pauses
+/// aggregators after their current cycle until undrained.
+#[derive(Clone, Default)]
+pub struct ServerState {
+    inner: Arc<ServerStateInner>,
+}
+
+#[derive(Default)]
+struct ServerStateInner {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    active_cycles: AtomicUsize,
+    undrained: Notify,
+    /// When each aggregator source was last polled from the admin API.
+    manual_polls: Mutex<HashMap<&'static str, Instant>>,
+    /// Fetch outcomes per upstream spot source, keyed like `SpotSource`.
+    fetches: Mutex<HashMap<&'static str, SourceFreshness>>,
+    /// The last successful TTL cleanup run.
+    last_cleanup: Mutex<Option<CleanupRun>>,
+    /// The latest start attempt of each spot aggregator.
+    aggregator_starts: Mutex<HashMap<&'static str, AggregatorStartReport>>,
+    /// Admin-started background jobs.
+    jobs: JobRegistry,
+}
+
+/// When the TTL cleanup last ran and how many expired spots it deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanupRun {
+    pub at: DateTime<Utc>,
+    pub deleted: u64,
+}
+
+/// How recently an upstream source delivered, as recorded by its aggregator.
+#[derive(Debug, Clone, Copy)]
+pub struct SourceFreshness {
+    /// The first recorded fetch attempt.
+    pub tracked_since: Instant,
+    pub last_success: Option<Instant>,
+}
+
+impl SourceFreshness {
+    /// Time since the last successful fetch, if there was one.
+    pub fn age(&self, now: Instant) -> Option<Duration> {
+        self.last_success
+            .map(|success| now.saturating_duration_since(success))
+    }
+
+    /// Whether nothing succeeded within `max_age`. A source that has never
+    /// succeeded counts from its first attempt.
+    pub fn is_stale(&self, now: Instant, max_age: Duration) -> bool {
+        let since = self.last_success.unwrap_or(self.tracked_since);
+        now.saturating_duration_since(since) > max_age
+    }
+}
+
+impl ServerState {
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn set_draining(&self, draining: bool) {
+        self.inner.draining.store(draining, Ordering::SeqCst);
+        if !draining {
+            self.inner.undrained.notify_waiters();
+        }
+    }
+
+    /// HTTP requests currently being handled, including the caller's own.
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Aggregator cycles currently running.
+    pub fn active_cycles(&self) -> usize {
+        self.inner.active_cycles.load(Ordering::SeqCst)
+    }
+
+    /// Wait until not draining, then mark an aggregator cycle as running
+    /// until the returned guard is dropped.
+    pub async fn begin_cycle(&self) -> CycleGuard {
+        loop {
+            // Register for the wakeup before checking, so an undrain between
+            // the check and the await isn't missed
+            let undrained = self.inner.undrained.notified();
+            tokio::pin!(undrained);
+            undrained.as_mut().enable();
+            self.inner.active_cycles.fetch_add(1, Ordering::SeqCst);
+            if !self.is_draining() {
+                return CycleGuard {
+                    state: self.clone(),
+                };
+            }
+            self.inner.active_cycles.fetch_sub(1, Ordering::SeqCst);
+            undrained.await;
+        }
+    }
+
+    /// Record a manual poll of `source` unless the previous one was less than
+    /// `cooldown` ago, in which case returns the time left to wait.
+    pub fn claim_manual_poll(
+        &self,
+        source: &'static str,
+        cooldown: Duration,
+    ) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut polls = self.inner.manual_polls.lock().unwrap();
+        if let Some(remaining) = polls
+            .get(source)
+            .and_then(|last| cooldown.checked_sub(now.duration_since(*last)))
+            .filter(|remaining| !remaining.is_zero())
+        {
+            return Err(remaining);
+        }
+        polls.insert(source, now);
+        Ok(())
+    }
+
+    /// Record a fetch from an upstream spot source, successful or not.
+    pub fn record_fetch(&self, source: &'static str, succeeded: bool) {
+        let now = Instant::now();
+        let mut fetches = self.inner.fetches.lock().unwrap();
+        let freshness = fetches.entry(source).or_insert(SourceFreshness {
+            tracked_since: now,
+            last_success: None,
+        });
+        if succeeded {
+            freshness.last_success = Some(now);
+        }
+    }
+
+    /// Fetch freshness of `source`; `None` until its first attempt.
+    pub fn freshness(&self, source: &str) -> Option<SourceFreshness> {
+        self.inner.fetches.lock().unwrap().get(source).copied()
+    }
+
+    /// Record a completed TTL cleanup run that deleted `deleted` spots.
+    pub fn record_cleanup(&self, deleted: u64) {
+        *self.inner.last_cleanup.lock().unwrap() = Some(CleanupRun {
+            at: Utc::now(),
+            deleted,
+        });
+    }
+
+    /// The last completed TTL cleanup run; `None` until the first one.
+    pub fn last_cleanup(&self) -> Option<CleanupRun> {
+        *self.inner.last_cleanup.lock().unwrap()
+    }
+
+    /// Record a spot aggregator start attempt, replacing its previous one.
+    pub fn record_aggregator_start(&self, report: AggregatorStartReport) {
+        self.inner
+            .aggregator_starts
+            .lock()
+            .unwrap()
+            .insert(report.name, report);
+    }
+
+    /// The latest start attempt of `name`; `None` if it was never started.
+    pub fn aggregator_start(&self, name: &str) -> Option<AggregatorStartReport> {
+        self.inner
+            .aggregator_starts
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+    }
+
+    /// The latest start attempt of every spot aggregator, by name.
+    pub fn aggregator_starts(&self) -> Vec<AggregatorStartReport> {
+        let mut reports: Vec<_> = self
+            .inner
+            .aggregator_starts
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        reports.sort_by_key(|report| report.name);
+        reports
+    }
+
+    /// Admin-started background jobs on this instance.
+    pub fn jobs(&self) -> &JobRegistry {
+        &self.inner.jobs
+    }
+
+    /// Wait until at most `max_in_flight` requests and no aggregator cycles
+    /// are running, or `timeout` passes. Returns whether it drained in time.
+    pub async fn wait_until_idle(&self, max_in_flight: usize, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.in_flight() <= max_in_flight && self.active_cycles() == 0 {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+/// Marks an aggregator cycle as running; see [`ServerState::begin_cycle`].
+pub struct CycleGuard {
+    state: ServerState,
+}
+
+impl Drop for CycleGuard {
+    fn drop(&mut self) {
+        self.state
+            .inner
+            .active_cycles
+            .fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct InFlightGuard(ServerState);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.inner.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Middleware counting in-flight requests for [`ServerState::wait_until_idle`].
+pub async fn track_in_flight(
+    State(state): State<ServerState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    state.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+    let _guard = InFlightGuard(state);
+    next.run(req).await
+}
+
+/// Accept connections until `shutdown` resolves, then wait for in-flight
+/// connections to finish.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    options: ServerOptions,
+    shutdown: impl Future<Output = ()>,
+) {
+    let builder = options.builder();
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, remote) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::warn!("Failed to accept connection: {e}");
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let app = app.clone();
+        let service = hyper::service::service_fn(move |req: hyper::Request<Incoming>| {
+            app.clone().oneshot(req)
+        });
+        // Upgrades carry the leaderboard WebSockets
+        let conn = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+        let conn = graceful.watch(conn.into_owned());
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                tracing::debug!("Connection from {remote} closed with error: {e}");
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use http_body_util::BodyExt;
+    use hyper::Version;
+
+    fn options(http2_enabled: bool) -> ServerOptions {
+        ServerOptions {
+            http2_enabled,
+            keep_alive: true,
+            idle_timeout: Some(Duration::from_secs(5)),
+            http2_keep_alive_interval: Some(Duration::from_secs(5)),
+            http2_keep_alive_timeout: Duration::from_secs(5),
+        }
+    }
+
+    async fn spawn_server(options: ServerOptions) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/ping", get(|| async { "pong" }));
+        tokio::spawn(serve(listener, app, options, std::future::pending()));
+        addr
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn draining_pauses_cycles_until_undrained() {
+        let state = ServerState::default();
+        let running = state.begin_cycle().await;
+        state.set_draining(true);
+        assert_eq!(state.active_cycles(), 1);
+
+        // The running cycle finishes; the next one waits for undrain
+        drop(running);
+        let waiter = tokio::spawn({
+            let state = state.clone();
+            async move { state.begin_cycle().await }
+        });
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(!waiter.is_finished());
+        assert_eq!(state.active_cycles(), 0);
+
+        state.set_draining(false);
+        let _guard = waiter.await.unwrap();
+        assert_eq!(state.active_cycles(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wait_until_idle_times_out_with_busy_cycle() {
+        let state = ServerState::default();
+        let guard = state.begin_cycle().await;
+        assert!(!state.wait_until_idle(0, Duration::from_secs(1)).await);
+
+        drop(guard);
+        assert!(state.wait_until_idle(0, Duration::from_secs(1)).await);
+    }
+
+    #[test]
+    fn manual_poll_within_cooldown_is_rejected() {
+        let state = ServerState::default();
+        let cooldown = Duration::from_secs(30);
+        assert!(state.claim_manual_poll("pota", cooldown).is_ok());
+
+        let remaining = state.claim_manual_poll("pota", cooldown).unwrap_err();
+        assert!(remaining > Duration::from_secs(29) && remaining <= cooldown);
+
+        // Cooldowns are per source
+        assert!(state.claim_manual_poll("sota", cooldown).is_ok());
+        assert!(state.claim_manual_poll("pota", Duration::ZERO).is_ok());
+    }
+
+    #[test]
+    fn aggregator_start_retry_replaces_failed_report() {
+        use crate::aggregators::{start_reported, StartStatus};
+
+        let state = ServerState::default();
+        assert!(state.aggregator_start("pota").is_none());
+
+        state.record_aggregator_start(start_reported("pota", || Err("down".to_string())));
+        assert!(state.aggregator_start("pota").unwrap().is_failed());
+
+        state.record_aggregator_start(start_reported("pota", || Ok(())));
+        let starts = state.aggregator_starts();
+        assert_eq!(starts.len(), 1);
+        assert_eq!(starts[0].status, StartStatus::Started);
+    }
+
+    #[test]
+    fn freshness_tracks_last_success_per_source() {
+        let state = ServerState::default();
+        assert!(state.freshness("pota").is_none());
+
+        state.record_fetch("pota", false);
+        let failing = state.freshness("pota").unwrap();
+        let later = failing.tracked_since + Duration::from_secs(200);
+        assert_eq!(failing.age(later), None);
+        assert!(failing.is_stale(later, Duration::from_secs(180)));
+        assert!(!failing.is_stale(later, Duration::from_secs(300)));
+
+        state.record_fetch("pota", true);
+        let fresh = state.freshness("pota").unwrap();
+        let success = fresh.last_success.unwrap();
+        assert_eq!(fresh.tracked_since, failing.tracked_since);
+        assert_eq!(
+            fresh.age(success + Duration::from_secs(5)),
+            Some(Duration::from_secs(5))
+        );
+        assert!(!fresh.is_stale(success + Duration::from_secs(5), Duration::from_secs(180)));
+
+        // A later failure keeps the last success
+        state.record_fetch("pota", false);
+        assert_eq!(state.freshness("pota").unwrap().last_success, Some(success));
+        assert!(state.freshness("sota").is_none());
+    }
+
+    #[test]
+    fn cleanup_run_updates_recorded_timestamp() {
+        let state = ServerState::default();
+        assert!(state.last_cleanup().is_none());
+
+        state.record_cleanup(3);
+        let first = state.last_cleanup().unwrap();
+        assert_eq!(first.deleted, 3);
+
+        state.record_cleanup(0);
+        let second = state.last_cleanup().unwrap();
+        assert!(second.at >= first.at);
+        assert_eq!(second.deleted, 0);
+    }
+
+    #[tokio::test]
+    async fn in_flight_counts_active_requests() {
+        let state = ServerState::default();
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    "done"
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                track_in_flight,
+            ));
+
+        let req = hyper::Request::get("/slow").body(Body::empty()).unwrap();
+        let pending = tokio::spawn(app.oneshot(req));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(state.in_flight(), 1);
+
+        pending.await.unwrap().unwrap();
+        assert_eq!(state.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn http2_prior_knowledge_request_succeeds() {
+        let addr = spawn_server(options(true)).await;
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (mut sender, conn) =
+            hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                .await
+                .unwrap();
+        tokio::spawn(conn);
+
+        let req = hyper::Request::get(format!("http://{addr}/ping"))
+            .body(Body::empty())
+            .unwrap();
+        let res = sender.send_request(req).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.version(), Version::HTTP_2);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"pong");
+    }
+
+    #[tokio::test]
+    async fn http1_still_served_when_http2_enabled() {
+        let addr = spawn_server(options(true)).await;
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+
+        let req = hyper::Request::get("/ping")
+            .header("host", addr.to_string())
+            .body(Body::empty())
+            .unwrap();
+        let res = sender.send_request(req).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.version(), Version::HTTP_11);
+    }
+
+    #[tokio::test]
+    async fn http2_rejected_when_disabled() {
+        let addr = spawn_server(options(false)).await;
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let handshake =
+            hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream)).await;
+        let Ok((mut sender, conn)) = handshake else {
+            return;
+        };
+        tokio::spawn(conn);
+
+        let req = hyper::Request::get(format!("http://{addr}/ping"))
+            .body(Body::empty())
+            .unwrap();
+        assert!(sender.send_request(req).await.is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Spot notifications shared across server instances.
+//!
+//! Spot writes announce the stored spot on this process's broadcast channel
+//! and with `pg_notify('spots', ...)`. Every instance runs a listener on that
+//! channel that forwards other instances' spots into its own broadcast
+//! channel. A notification carries its sender's [`instance_id`], so the
+//! listener skips the ones it already delivered locally and subscribers see
+//! each spot once, whichever replica stored it.
+//!
+//! Spots are written from aggregator tasks and handlers alike, so the local
+//! channel is process-wide rather than handed to every writer.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::spot::SpotRow;
+
+/// Postgres notification channel carrying spot writes.
+pub const SPOTS_CHANNEL: &str = "spots";
+
+/// Spots a slow subscriber may fall behind by before it skips ahead.
+const STREAM_BUFFER: usize = 1024;
+
+/// Wait before reconnecting a failed listener, doubled per consecutive
+/// failure up to [`MAX_RECONNECT_DELAY`].
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// A stored spot as streamed: enough to place it on a list or map, and well
+/// under the 8000-byte `pg_notify` payload limit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotNotification {
+    /// Instance that stored the spot.
+    pub origin: Uuid,
+    pub id: Uuid,
+    pub source: String,
+    pub callsign: String,
+    pub program_slug: Option<String>,
+    pub reference: Option<String>,
+    pub frequency_khz: f64,
+    pub mode: String,
+    pub spotted_at: DateTime<Utc>,
+}
+
+impl SpotNotification {
+    fn from_row(row: &SpotRow) -> Self {
+        Self {
+            origin: instance_id(),
+            id: row.id,
+            source: row.source.as_str().to_string(),
+            callsign: row.callsign.clone(),
+            program_slug: row.program_slug.clone(),
+            reference: row.reference.clone(),
+            frequency_khz: row.frequency_khz,
+            mode: row.mode.clone(),
+            spotted_at: row.spotted_at,
+        }
+    }
+}
+
+struct LocalStream {
+    instance_id: Uuid,
+    sender: broadcast::Sender<SpotNotification>,
+}
+
+fn local() -> &'static LocalStream {
+    static LOCAL: OnceLock<LocalStream> = OnceLock::new();
+    LOCAL.get_or_init(|| LocalStream {
+        instance_id: Uuid::new_v4(),
+        sender: broadcast::channel(STREAM_BUFFER).0,
+    })
+}
+
+/// This process's id, stamped on the notifications it sends.
+pub fn instance_id() -> Uuid {
+    local().instance_id
+}
+
+/// Receive every spot stored from now on, by this or any other instance.
+#[allow(dead_code)] // For the spot streams; nothing subscribes in-process yet
+pub fn subscribe() -> broadcast::Receiver<SpotNotification> {
+    local().sender.subscribe()
+}
+
+/// Announce a stored spot to local subscribers and, through `pg_notify`, to
+/// other instances. A failed notify is logged rather than failing the write.
+pub async fn announce(pool: &PgPool, row: &SpotRow) {
+    let notification = SpotNotification::from_row(row);
+    // Having no subscribers yet is fine
+    let _ = local().sender.send(notification.clone());
+    if let Err(e) = notify(pool, &notification).await {
+        tracing::warn!(spot_id = %row.id, "Failed to notify other instances of a spot: {e}");
+    }
+}
+
+async fn notify(pool: &PgPool, notification: &SpotNotification) -> Result<(), sqlx::Error> {
+    let payload = serde_json::to_string(notification).expect("spot notification serializes");
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(SPOTS_CHANNEL)
+        .bind(payload)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The spot in a notification from another instance, or `None` for this
+/// instance's own (already delivered locally) and unreadable payloads.
+fn remote_notification(payload: &str, own: Uuid) -> Option<SpotNotification> {
+    match serde_json::from_str::<SpotNotification>(payload) {
+        Ok(notification) if notification.origin == own => None,
+        Ok(notification) => Some(notification),
+        Err(e) => {
+            tracing::warn!("Ignoring malformed spot notification: {e}");
+            None
+        }
+    }
+}
+
+/// Feed other instances' spots to local subscribers, reconnecting with
+/// backoff whenever the listener's connection fails.
+pub fn spawn_listener(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut delay = RECONNECT_DELAY;
+        loop {
+            if let Err(e) = listen(&pool, &local().sender, instance_id(), &mut delay).await {
+                tracing::warn!(
+                    "Spot listener failed, reconnecting in {}s: {e}",
+                    delay.as_secs()
+                );
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    });
+}
+
+/// Forward notifications until the connection can't be re-established.
+/// `delay` is reset once the channel is being listened on.
+async fn listen(
+    pool: &PgPool,
+    sender: &broadcast::Sender<SpotNotification>,
+    own: Uuid,
+    delay: &mut Duration,
+) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(SPOTS_CHANNEL).await?;
+    *delay = RECONNECT_DELAY;
+    loop {
+        // Unlike recv(), try_recv() reports a dropped connection, so the gap
+        // (whose notifications are lost) shows up in the logs; the next call
+        // reconnects and listens again
+        match listener.try_recv().await? {
+            Some(notification) => {
+                if let Some(spot) = remote_notification(notification.payload(), own) {
+                    let _ = sender.send(spot);
+                }
+            }
+            None => tracing::warn!("Spot listener connection lost, reconnecting"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use chrono::SubsecRound;
+
+    fn notification(origin: Uuid) -> SpotNotification {
+        SpotNotification {
+            origin,
+            id: Uuid::new_v4(),
+            source: "pota".to_string(),
+            callsign: "K1ABC".to_string(),
+            program_slug: Some("pota".to_string()),
+            reference: Some("US-0001".to_string()),
+            frequency_khz: 14062.0,
+            mode: "CW".to_string(),
+            spotted_at: Utc::now().trunc_subsecs(0),
+        }
+    }
+
+    #[test]
+    fn own_and_malformed_notifications_are_skipped() {
+        let own = Uuid::new_v4();
+        let mine = serde_json::to_string(&notification(own)).unwrap();
+        let theirs = notification(Uuid::new_v4());
+
+        assert_eq!(remote_notification(&mine, own), None);
+        assert_eq!(
+            remote_notification(&serde_json::to_string(&theirs).unwrap(), own),
+            Some(theirs)
+        );
+        assert_eq!(remote_notification("{\"id\":", own), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn notifications_round_trip_between_connections() {
+        let pool = test_support::pool().await;
+        let own = Uuid::new_v4();
+        let mut listener = PgListener::connect_with(&pool).await.unwrap();
+        listener.listen(SPOTS_CHANNEL).await.unwrap();
+
+        // Sent on pool connections, received on the listener's own
+        let mine = notification(own);
+        let theirs = notification(Uuid::new_v4());
+        notify(&pool, &mine).await.unwrap();
+        notify(&pool, &theirs).await.unwrap();
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            let n = tokio::time::timeout(Duration::from_secs(5), listener.recv())
+                .await
+                .expect("notification within 5s")
+                .unwrap();
+            assert_eq!(n.channel(), SPOTS_CHANNEL);
+            let spot: SpotNotification = serde_json::from_str(n.payload()).unwrap();
+            // Other tests' spots share the channel
+            if spot.id == mine.id || spot.id == theirs.id {
+                received.push(n.payload().to_string());
+            }
+        }
+        assert_eq!(remote_notification(&received[0], own), None);
+        assert_eq!(remote_notification(&received[1], own), Some(theirs));
+    }
+}
@@ -0,0 +1,209 @@
+//! Startup checks that catch misconfiguration before background tasks run.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use sqlx::PgPool;
+
+use crate::config::Config;
+
+/// A program that an enabled aggregator writes into `program_slug`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredProgram {
+    pub aggregator: &'static str,
+    /// `POTA_PROGRAM_SLUG` / `SOTA_PROGRAM_SLUG`.
+    pub slug: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramProblem {
+    Missing(RequiredProgram),
+    Inactive(RequiredProgram),
+}
+
+impl fmt::Display for ProgramProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(p) => write!(
+                f,
+                "{} aggregator is enabled but program '{}' does not exist",
+                p.aggregator, p.slug
+            ),
+            Self::Inactive(p) => write!(
+                f,
+                "{} aggregator is enabled but program '{}' is inactive",
+                p.aggregator, p.slug
+            ),
+        }
+    }
+}
+
+/// Programs needed by the aggregators enabled in `config`.
+pub fn required_programs(config: &Config) -> Vec<RequiredProgram> {
+    let mut required = Vec::new();
+    if config.spots_enabled && config.pota_aggregator_enabled {
+        required.push(RequiredProgram {
+            aggregator: "POTA",
+            slug: config.pota_program_slug.clone(),
+        });
+    }
+    if config.spots_enabled && config.sota_aggregator_enabled {
+        required.push(RequiredProgram {
+            aggregator: "SOTA",
+            slug: config.sota_program_slug.clone(),
+        });
+    }
+    required
+}
+
+/// Compare required programs against `slug -> is_active` from the database.
+pub fn find_program_problems(
+    required: &[RequiredProgram],
+    programs: &HashMap<String, bool>,
+) -> Vec<ProgramProblem> {
+    required
+        .iter()
+        .filter_map(|req| match programs.get(&req.slug) {
+            None => Some(ProgramProblem::Missing(req.clone())),
+            Some(false) => Some(ProgramProblem::Inactive(req.clone())),
+            Some(true) => None,
+        })
+        .collect()
+}
+
+/// Log each problem as a warning, or as an error and fail when `strict`.
+pub fn report_program_problems(problems: &[ProgramProblem], strict: bool) -> Result<(), String> {
+    for problem in problems {
+        if strict {
+            tracing::error!("Startup check failed: {problem}");
+        } else {
+            tracing::warn!(
+                "Startup check: {problem}; its spots will reference a missing program \
+                 (set STRICT_PROGRAM_CHECK=true to fail startup instead)"
+            );
+        }
+    }
+
+    if strict && !problems.is_empty() {
+        return Err(format!(
+            "{} required program(s) missing or inactive",
+            problems.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Check that every enabled aggregator's program exists and is active.
+pub async fn check_required_programs(pool: &PgPool, config: &Config) -> Result<(), String> {
+    let required = required_programs(config);
+    if required.is_empty() {
+        return Ok(());
+    }
+
+    let programs: HashMap<String, bool> = match crate::db::list_all_programs(pool).await {
+        Ok(rows) => rows.into_iter().map(|p| (p.slug, p.is_active)).collect(),
+        Err(e) if config.strict_program_check => {
+            return Err(format!("could not load programs: {e}"));
+        }
+        Err(e) => {
+            tracing::warn!("Startup check skipped, could not load programs: {e}");
+            return Ok(());
+        }
+    };
+
+    report_program_problems(
+        &find_program_problems(&required, &programs),
+        config.strict_program_check,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(pairs: &[(&str, &str)]) -> Config {
+        Config::from_lookup(|var| match var {
+            "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+            "ADMIN_TOKEN" => Some("admin".to_string()),
+            _ => pairs
+                .iter()
+                .find(|(k, _)| *k == var)
+                .map(|(_, v)| v.to_string()),
+        })
+        .unwrap()
+    }
+
+    fn programs(entries: &[(&str, bool)]) -> HashMap<String, bool> {
+        entries.iter().map(|(s, a)| (s.to_string(), *a)).collect()
+    }
+
+    #[test]
+    fn only_enabled_aggregators_require_programs() {
+        assert!(required_programs(&config(&[])).is_empty());
+
+        let required = required_programs(&config(&[("SOTA_AGGREGATOR_ENABLED", "true")]));
+        assert_eq!(
+            required,
+            [RequiredProgram {
+                aggregator: "SOTA",
+                slug: "sota".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn detects_missing_and_inactive_programs() {
+        let required = required_programs(&config(&[
+            ("POTA_AGGREGATOR_ENABLED", "true"),
+            ("SOTA_AGGREGATOR_ENABLED", "true"),
+        ]));
+        let problems = find_program_problems(&required, &programs(&[("sota", false)]));
+        assert_eq!(
+            problems,
+            [
+                ProgramProblem::Missing(required[0].clone()),
+                ProgramProblem::Inactive(required[1].clone()),
+            ]
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            "POTA aggregator is enabled but program 'pota' does not exist"
+        );
+    }
+
+    #[test]
+    fn configured_program_slugs_are_required() {
+        let required = required_programs(&config(&[
+            ("POTA_AGGREGATOR_ENABLED", "true"),
+            ("POTA_PROGRAM_SLUG", "parks"),
+        ]));
+        assert_eq!(required[0].slug, "parks");
+        let problems = find_program_problems(&required, &programs(&[("pota", true)]));
+        assert_eq!(
+            problems[0].to_string(),
+            "POTA aggregator is enabled but program 'parks' does not exist"
+        );
+    }
+
+    #[test]
+    fn active_programs_pass() {
+        let required = required_programs(&config(&[("POTA_AGGREGATOR_ENABLED", "true")]));
+        assert!(find_program_problems(&required, &programs(&[("pota", true)])).is_empty());
+    }
+
+    #[test]
+    fn problems_only_warn_by_default() {
+        let required = required_programs(&config(&[("POTA_AGGREGATOR_ENABLED", "true")]));
+        let problems = find_program_problems(&required, &programs(&[]));
+        assert_eq!(problems.len(), 1);
+        assert!(report_program_problems(&problems, false).is_ok());
+    }
+
+    #[test]
+    fn problems_fail_when_strict() {
+        let required = required_programs(&config(&[("POTA_AGGREGATOR_ENABLED", "true")]));
+        let problems = find_program_problems(&required, &programs(&[("pota", false)]));
+        assert!(report_program_problems(&problems, true).is_err());
+        assert!(report_program_problems(&[], true).is_ok());
+    }
+}